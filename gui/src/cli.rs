@@ -0,0 +1,197 @@
+//! Command-line argument handling.
+//!
+//! Supports opening a page directly (`--page`) and running a named action,
+//! either in the GUI or headlessly with `--no-gui`, optionally printing a
+//! JSON report of the run with `--json`.
+
+use crate::config;
+use crate::core;
+use crate::core::actions;
+use crate::i18n::gettext;
+use crate::ui::navigation;
+use crate::ui::task_runner;
+
+/// Help text printed for `--help` and usage errors.
+pub const USAGE: &str = "\
+Usage: xero-toolkit [OPTIONS]
+
+Options:
+  --page <ID>   Open the given page
+  --run <ID>    Run the given action
+  --no-gui      Run the action in this terminal without opening a window
+  --json        With --no-gui, print a JSON report of the run to stdout
+  --list        List available page and action ids
+  -h, --help    Show this help";
+
+/// Parsed command-line options.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// Page slug to open on startup.
+    pub page: Option<String>,
+    /// Action id to run on startup.
+    pub action: Option<String>,
+    pub no_gui: bool,
+    /// Print the run report as JSON, progress goes to stderr.
+    pub json: bool,
+    pub list: bool,
+    pub help: bool,
+}
+
+/// Parse arguments, excluding the program name.
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+
+        let mut value = |name: &str| {
+            inline_value
+                .map(str::to_string)
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} requires a value", name))
+        };
+
+        match flag.as_str() {
+            "--page" => options.page = Some(value("--page")?),
+            "--run" => options.action = Some(value("--run")?),
+            "--no-gui" => options.no_gui = true,
+            "--json" => options.json = true,
+            "--list" => options.list = true,
+            "-h" | "--help" => options.help = true,
+            _ => return Err(format!("Unknown argument '{}'", arg)),
+        }
+    }
+
+    if options.no_gui && options.action.is_none() {
+        return Err("--no-gui requires --run <ID>".to_string());
+    }
+    if options.json && !options.no_gui {
+        return Err("--json requires --no-gui".to_string());
+    }
+
+    Ok(options)
+}
+
+/// Check that the requested page and action exist, listing the available
+/// ids otherwise.
+pub fn validate(options: &Options) -> Result<(), String> {
+    if let Some(slug) = &options.page {
+        let page = navigation::find_page(slug).filter(|page| page.is_available());
+        if page.is_none() {
+            return Err(format!(
+                "Unknown page '{}'. Available pages: {}",
+                slug,
+                page_slugs().join(", ")
+            ));
+        }
+    }
+
+    if let Some(id) = &options.action {
+        if actions::find(id).is_none() {
+            return Err(format!(
+                "Unknown action '{}'. Available actions: {}",
+                id,
+                action_ids().join(", ")
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Print all page and action ids with a short description.
+pub fn print_ids() {
+    println!("Pages:");
+    for page in navigation::available_pages() {
+        println!("  {:<20} {}", page.slug, gettext(page.title));
+    }
+    println!("\nActions:");
+    for action in actions::ACTIONS {
+        println!("  {:<20} {}", action.id, gettext(action.description));
+    }
+}
+
+/// Run an action without a GUI and return the process exit code.
+///
+/// With `json`, the report of the run is printed to stdout, also when the
+/// action failed.
+pub fn run_headless(action_id: &str, json: bool) -> i32 {
+    let Some(action) = actions::find(action_id) else {
+        eprintln!("Unknown action '{}'", action_id);
+        return 2;
+    };
+
+    if let Err(e) = config::env::init() {
+        eprintln!("Failed to initialize environment variables: {}", e);
+        return 1;
+    }
+    core::aur::init();
+
+    let report = task_runner::run_headless(action.commands(), &gettext(action.title), json);
+    if json {
+        println!("{}", report.to_json());
+    }
+    report.exit_code()
+}
+
+fn page_slugs() -> Vec<&'static str> {
+    navigation::available_pages()
+        .map(|page| page.slug)
+        .collect()
+}
+
+fn action_ids() -> Vec<&'static str> {
+    actions::ACTIONS.iter().map(|action| action.id).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Options, String> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_page_and_run() {
+        let options = parse_args(&["--page", "kernel-manager"]).unwrap();
+        assert_eq!(options.page.as_deref(), Some("kernel-manager"));
+
+        let options = parse_args(&["--run=clear-pacman-cache", "--no-gui"]).unwrap();
+        assert_eq!(options.action.as_deref(), Some("clear-pacman-cache"));
+        assert!(options.no_gui);
+        assert!(!options.json);
+
+        let options = parse_args(&["--run", "update-system", "--no-gui", "--json"]).unwrap();
+        assert!(options.json);
+
+        assert_eq!(parse_args(&[]).unwrap(), Options::default());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_args(&["--page"]).is_err());
+        assert!(parse_args(&["--no-gui"]).is_err());
+        assert!(parse_args(&["--run", "update-system", "--json"]).is_err());
+        assert!(parse_args(&["--bogus"]).is_err());
+        assert!(parse_args(&["stray"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_lists_available_ids() {
+        let options = parse_args(&["--run", "nope"]).unwrap();
+        let error = validate(&options).unwrap_err();
+        assert!(error.contains("clear-pacman-cache"));
+
+        let options = parse_args(&["--page", "nope"]).unwrap();
+        let error = validate(&options).unwrap_err();
+        assert!(error.contains("kernel-manager"));
+
+        let options = parse_args(&["--page", "drivers", "--run", "unlock-pacman"]).unwrap();
+        assert!(validate(&options).is_ok());
+    }
+}
@@ -18,6 +18,8 @@ pub mod links {
     pub const YOUTUBE: &str = "https://www.youtube.com/@XeroLinux";
     pub const WEBSITE: &str = "https://xerolinux.xyz/";
     pub const DONATE: &str = "https://ko-fi.com/xerolinux";
+    pub const DISCORD: &str = "https://discord.xerolinux.xyz/";
+    pub const GUIDES: &str = "https://xerolinux.xyz/posts/";
 }
 
 /// Binary paths for system executables.
@@ -34,6 +36,7 @@ pub mod paths {
     pub const SYSTEMD: &str = "/opt/xero-toolkit/sources/systemd";
     pub const DESKTOP_FILE: &str = "/usr/share/applications/xero-toolkit.desktop";
     pub const SYSTEM_AUTOSTART: &str = "/etc/xdg/autostart/xero-toolkit.desktop";
+    pub const LOCALE_DIR: &str = "/usr/share/locale";
 
     pub fn daemon() -> PathBuf {
         PathBuf::from(DAEMON)
@@ -69,6 +72,7 @@ pub mod paths {
 pub mod seasonal_debug {
     pub const ENABLE_SNOW: &str = "XERO_TOOLKIT_ENABLE_SNOW";
     pub const ENABLE_HALLOWEEN: &str = "XERO_TOOLKIT_ENABLE_HALLOWEEN";
+    pub const ENABLE_FIREWORKS: &str = "XERO_TOOLKIT_ENABLE_FIREWORKS";
 
     pub fn check_effect_env(var_name: &str) -> Option<bool> {
         std::env::var(var_name).ok().and_then(|value| {
@@ -94,23 +98,31 @@ pub mod resources {
 
     pub mod dialogs {
         pub const ABOUT: &str = "/xyz/xerolinux/xero-toolkit/ui/dialogs/about_dialog.ui";
+        pub const COMMAND_PALETTE: &str =
+            "/xyz/xerolinux/xero-toolkit/ui/dialogs/command_palette_dialog.ui";
         pub const DEPENDENCY_ERROR: &str =
             "/xyz/xerolinux/xero-toolkit/ui/dialogs/dependency_error_dialog.ui";
         pub const DOWNLOAD: &str = "/xyz/xerolinux/xero-toolkit/ui/dialogs/download_dialog.ui";
         pub const DOWNLOAD_SETUP: &str =
             "/xyz/xerolinux/xero-toolkit/ui/dialogs/download_setup_dialog.ui";
+        pub const LOG_VIEWER: &str = "/xyz/xerolinux/xero-toolkit/ui/dialogs/log_viewer_dialog.ui";
+        pub const PREFERENCES: &str =
+            "/xyz/xerolinux/xero-toolkit/ui/dialogs/preferences_dialog.ui";
         pub const SCHEDULER_SELECTION: &str =
             "/xyz/xerolinux/xero-toolkit/ui/dialogs/scheduler_selection_dialog.ui";
         pub const SELECTION: &str = "/xyz/xerolinux/xero-toolkit/ui/dialogs/selection_dialog.ui";
         pub const TASK_LIST: &str = "/xyz/xerolinux/xero-toolkit/ui/dialogs/task_list_dialog.ui";
         pub const TERMINAL: &str = "/xyz/xerolinux/xero-toolkit/ui/dialogs/terminal_dialog.ui";
+        pub const WALLPAPERS: &str = "/xyz/xerolinux/xero-toolkit/ui/dialogs/wallpapers_dialog.ui";
         pub const WARNING: &str = "/xyz/xerolinux/xero-toolkit/ui/dialogs/warning_dialog.ui";
+        pub const WELCOME: &str = "/xyz/xerolinux/xero-toolkit/ui/dialogs/welcome_dialog.ui";
         pub const XEROLINUX_CHECK: &str =
             "/xyz/xerolinux/xero-toolkit/ui/dialogs/xerolinux_check_dialog.ui";
     }
 
     pub mod tabs {
         pub const BIOMETRICS: &str = "/xyz/xerolinux/xero-toolkit/ui/tabs/biometrics.ui";
+        pub const BTRFS: &str = "/xyz/xerolinux/xero-toolkit/ui/tabs/btrfs.ui";
         pub const CONTAINERS_VMS: &str = "/xyz/xerolinux/xero-toolkit/ui/tabs/containers_vms.ui";
         pub const CUSTOMIZATION: &str = "/xyz/xerolinux/xero-toolkit/ui/tabs/customization.ui";
         pub const DRIVERS: &str = "/xyz/xerolinux/xero-toolkit/ui/tabs/drivers.ui";
@@ -119,7 +131,9 @@ pub mod resources {
         pub const KERNEL_SCHEDULERS: &str =
             "/xyz/xerolinux/xero-toolkit/ui/tabs/kernel_schedulers.ui";
         pub const MAIN_PAGE: &str = "/xyz/xerolinux/xero-toolkit/ui/tabs/main_page.ui";
+        pub const SERVICES: &str = "/xyz/xerolinux/xero-toolkit/ui/tabs/services.ui";
         pub const SERVICING_SYSTEM_TWEAKS: &str =
             "/xyz/xerolinux/xero-toolkit/ui/tabs/servicing_system_tweaks.ui";
+        pub const SYSTEM_INFO: &str = "/xyz/xerolinux/xero-toolkit/ui/tabs/system_info.ui";
     }
 }
@@ -1,20 +1,342 @@
 //! User-configurable settings stored in ~/.config/xero-toolkit/config.toml
+//!
+//! The file carries a layout version; older files are migrated on load and
+//! unreadable ones are moved aside so the app starts with defaults.
 
+use crate::core::events::{self, Event};
+use gtk4::glib;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::cell::{Ref, RefCell};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+/// Delay before a changed config is written, so bursts of changes
+/// (e.g. resizing the window) result in a single write.
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// Layout version written by this build.
+pub const CONFIG_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Upgrades a parsed config by one version; entry `n` turns version `n`
+/// into `n + 1`.
+type Migration = fn(&mut toml::Table);
+
+const MIGRATIONS: &[Migration] = &[
+    // Files from before versioning only lack the version key
+    |_| {},
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Config {
+    /// Layout version the file was written with
+    pub version: u32,
     pub general: GeneralConfig,
+    pub window: WindowConfig,
+    pub tasks: TasksConfig,
+    pub downloads: DownloadsConfig,
+    pub appearance: AppearanceConfig,
+    pub seasonal: SeasonalConfig,
+    pub updates: UpdatesConfig,
+    pub auth: AuthConfig,
     pub warnings: WarningsConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            general: GeneralConfig::default(),
+            window: WindowConfig::default(),
+            tasks: TasksConfig::default(),
+            downloads: DownloadsConfig::default(),
+            appearance: AppearanceConfig::default(),
+            seasonal: SeasonalConfig::default(),
+            updates: UpdatesConfig::default(),
+            auth: AuthConfig::default(),
+            warnings: WarningsConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct GeneralConfig {
     /// Whether to launch xero-toolkit on login
     pub autostart: bool,
+    /// Page id opened on startup; the last visited page when unset
+    pub start_page: Option<String>,
+    /// Whether the window size is restored on startup
+    pub remember_window_size: bool,
+    /// Log level, overridden by the XERO_TOOLKIT_LOG environment variable
+    pub log_level: String,
+    /// Whether the first-run welcome wizard was shown
+    pub welcomed: bool,
+}
+
+impl Default for GeneralConfig {
+    fn default() -> Self {
+        Self {
+            autostart: false,
+            start_page: None,
+            remember_window_size: true,
+            log_level: "info".to_string(),
+            welcomed: false,
+        }
+    }
+}
+
+/// Main window state restored on the next launch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    /// Unmaximized window size
+    pub width: i32,
+    pub height: i32,
+    pub maximized: bool,
+    pub sidebar_visible: bool,
+    /// Page id selected when the app was last used
+    pub last_page: Option<String>,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            width: 1100,
+            height: 750,
+            maximized: false,
+            sidebar_visible: true,
+            last_page: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TasksConfig {
+    /// Send a desktop notification when a task finishes in the background
+    pub notifications: bool,
+    /// Close the task dialog after a successful run
+    pub auto_close: bool,
+    /// Ask for confirmation before risky actions
+    pub confirm_destructive: bool,
+    /// Build AUR packages with lowered CPU and I/O priority
+    pub low_priority_aur: bool,
+    /// How much AUR helpers ask before building packages
+    pub aur_confirmation: AurConfirmation,
+    /// Battery percentage below which long operations ask before running on
+    /// battery, 0 to never ask
+    pub battery_threshold: u32,
+    /// Keep the working folder of a run whose step failed, to look into it
+    pub keep_failed_workspace: bool,
+}
+
+impl Default for TasksConfig {
+    fn default() -> Self {
+        Self {
+            notifications: true,
+            auto_close: false,
+            confirm_destructive: true,
+            low_priority_aur: true,
+            aur_confirmation: AurConfirmation::default(),
+            battery_threshold: 30,
+            keep_failed_workspace: false,
+        }
+    }
+}
+
+/// How much AUR helpers ask before building packages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AurConfirmation {
+    /// Run with `--noconfirm`, without showing PKGBUILDs
+    #[default]
+    Silent,
+    /// Run in a terminal, where the helper shows PKGBUILDs and diffs and
+    /// asks before building
+    Review,
+    /// Ask before each run which of the two to do
+    Ask,
+}
+
+impl AurConfirmation {
+    /// Every level, in the order the preferences list them.
+    pub const ALL: [Self; 3] = [Self::Silent, Self::Review, Self::Ask];
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadsConfig {
+    /// Arch Linux mirror used for ISO downloads
+    pub mirror: String,
+    /// Download speed limit in KiB/s, 0 for unlimited
+    pub speed_limit_kib: u32,
+    /// Default download folder; the desktop's download folder when unset
+    pub folder: Option<String>,
+    /// Folder ISO images are saved to; the download folder when unset
+    pub iso_folder: Option<String>,
+    /// Offer the folder of the last download instead of the defaults
+    pub remember_last_folder: bool,
+    /// Folder the last download was saved to
+    pub last_folder: Option<String>,
+    /// Country code system mirrors are ranked around; global when unset
+    pub mirror_region: Option<String>,
+}
+
+impl Default for DownloadsConfig {
+    fn default() -> Self {
+        Self {
+            mirror: "https://fastly.mirror.pkgbuild.com".to_string(),
+            speed_limit_kib: 0,
+            folder: None,
+            iso_folder: None,
+            remember_last_folder: false,
+            last_folder: None,
+            mirror_region: None,
+        }
+    }
+}
+
+impl DownloadsConfig {
+    /// Folder new downloads are saved to, `default` when none is set.
+    pub fn folder_or_default(&self, default: &Path) -> PathBuf {
+        match &self.folder {
+            Some(folder) => PathBuf::from(folder),
+            None => default.to_path_buf(),
+        }
+    }
+
+    /// Folder new ISO images are saved to: the last one used if it should
+    /// be remembered, else the ISO folder or the download folder.
+    pub fn iso_folder_or_default(&self, default: &Path) -> PathBuf {
+        let last = self
+            .last_folder
+            .as_ref()
+            .filter(|_| self.remember_last_folder);
+        match last.or(self.iso_folder.as_ref()) {
+            Some(folder) => PathBuf::from(folder),
+            None => self.folder_or_default(default),
+        }
+    }
+
+    /// Speed limit in bytes per second, if any.
+    pub fn speed_limit(&self) -> Option<u64> {
+        (self.speed_limit_kib > 0).then(|| u64::from(self.speed_limit_kib) * 1024)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AppearanceConfig {
+    /// Disable interface animations
+    pub reduce_motion: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SeasonalConfig {
+    /// Whether seasonal effects are shown when one is active; unset follows
+    /// the reduced-motion preference
+    pub enabled: Option<bool>,
+    /// Snow in December
+    pub snow: bool,
+    /// Bats and fog in October
+    pub halloween: bool,
+    /// Fireworks from December 31 to January 2
+    pub fireworks: bool,
+    /// Multiplier for the number of particles each effect draws
+    pub intensity: f64,
+}
+
+impl Default for SeasonalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: None,
+            snow: true,
+            halloween: true,
+            fireworks: true,
+            intensity: 1.0,
+        }
+    }
+}
+
+impl SeasonalConfig {
+    pub const MIN_INTENSITY: f64 = 0.25;
+    pub const MAX_INTENSITY: f64 = 2.0;
+
+    /// Seasonal effects are off by default when motion should be reduced.
+    pub fn is_enabled(&self, reduced_motion: bool) -> bool {
+        self.enabled.unwrap_or(!reduced_motion)
+    }
+
+    /// Whether the effect with the given id may be shown.
+    pub fn effect_enabled(&self, id: &str) -> bool {
+        match id {
+            "snow" => self.snow,
+            "halloween" => self.halloween,
+            "fireworks" => self.fireworks,
+            _ => true,
+        }
+    }
+
+    /// Intensity limited to the range offered in the preferences.
+    pub fn intensity(&self) -> f64 {
+        if self.intensity.is_finite() {
+            self.intensity
+                .clamp(Self::MIN_INTENSITY, Self::MAX_INTENSITY)
+        } else {
+            1.0
+        }
+    }
+
+    /// Particle count for an effect whose default count is `base`.
+    pub fn scaled_count(&self, base: usize) -> usize {
+        ((base as f64 * self.intensity()).round() as usize).max(1)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// Minutes the authentication daemon stays up after a task finishes,
+    /// 0 to end the session right away
+    pub idle_timeout_minutes: u32,
+    /// List the privileged steps of a task before the password prompt
+    pub show_summary: bool,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            idle_timeout_minutes: 0,
+            show_summary: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdatesConfig {
+    /// Look for a newer toolkit version once a day
+    pub check: bool,
+    /// Unix time of the last online check
+    pub last_check: Option<u64>,
+    /// Newest version seen by the last check
+    pub latest_version: Option<String>,
+    /// Version the user chose not to be reminded about
+    pub skipped_version: Option<String>,
+}
+
+impl Default for UpdatesConfig {
+    fn default() -> Self {
+        Self {
+            check: true,
+            last_check: None,
+            latest_version: None,
+            skipped_version: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -22,23 +344,54 @@ pub struct GeneralConfig {
 pub struct WarningsConfig {
     /// User dismissed the "limited support on non-XeroLinux" notice
     pub dismissed_generic_distro_notice: bool,
+    /// User opted out of the crash report dialog shown after a crash
+    pub dismissed_crash_reports: bool,
     // Add future "don't show again" flags here, not as loose keys
 }
 
 pub fn config_path() -> PathBuf {
-    dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("~/.config"))
-        .join("xero-toolkit")
-        .join("config.toml")
+    crate::core::paths::get().config.join("config.toml")
+}
+
+/// Run the migrations from the table's version up to the last one and
+/// stamp the resulting version. Files missing the key predate versioning.
+fn migrate(table: &mut toml::Table, migrations: &[Migration]) {
+    let from = table
+        .get("version")
+        .and_then(toml::Value::as_integer)
+        .and_then(|version| usize::try_from(version).ok())
+        .unwrap_or(0);
+    if from > migrations.len() {
+        // Written by a newer build; keep what this one understands
+        return;
+    }
+    for migration in &migrations[from..] {
+        migration(table);
+    }
+    table.insert(
+        "version".to_string(),
+        toml::Value::Integer(migrations.len() as i64),
+    );
 }
 
 impl Config {
+    /// Parse config file content, migrating older layouts.
+    pub fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        let mut table: toml::Table = content.parse()?;
+        migrate(&mut table, MIGRATIONS);
+        table.try_into()
+    }
+
     /// Load config from disk, returning defaults for any missing keys or
     /// if the file does not exist yet.
+    ///
+    /// Runs before logging is set up, so problems go to stderr.
     pub fn load() -> Self {
-        let path = config_path();
+        Self::load_from(&config_path())
+    }
 
-        let content = match std::fs::read_to_string(&path) {
+    fn load_from(path: &Path) -> Self {
+        let content = match std::fs::read_to_string(path) {
             Ok(s) => s,
             Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
                 return Self::default();
@@ -49,20 +402,55 @@ impl Config {
             }
         };
 
-        match toml::from_str(&content) {
+        match Self::parse(&content) {
             Ok(cfg) => cfg,
             Err(e) => {
-                eprintln!("Warning: config parse error ({e}), using defaults");
+                // Keep the broken file for inspection instead of
+                // overwriting it with the next save
+                let aside = path.with_extension("toml.corrupt");
+                match std::fs::rename(path, &aside) {
+                    Ok(()) => eprintln!(
+                        "Warning: config parse error ({e}), moved it to {} and using defaults",
+                        aside.display()
+                    ),
+                    Err(rename_error) => eprintln!(
+                        "Warning: config parse error ({e}), using defaults; \
+                         could not move it aside: {rename_error}"
+                    ),
+                }
                 Self::default()
             }
         }
     }
 
+    /// Restore default preferences, keeping window state, autostart and
+    /// whether the welcome wizard ran, which are state rather than choices.
+    pub fn reset_preferences(&mut self) {
+        let autostart = self.general.autostart;
+        let welcomed = self.general.welcomed;
+        let last_folder = self.downloads.last_folder.take();
+        let updates = std::mem::take(&mut self.updates);
+        *self = Self {
+            window: std::mem::take(&mut self.window),
+            ..Self::default()
+        };
+        self.general.autostart = autostart;
+        self.general.welcomed = welcomed;
+        self.downloads.last_folder = last_folder;
+        // Only the switch is a preference, the rest is check state
+        self.updates = UpdatesConfig {
+            check: true,
+            ..updates
+        };
+    }
+
     /// Atomically write config to disk.
     /// Writes to a temp file first, then renames — avoids corruption on crash.
     pub fn save(&self) -> Result<(), ConfigError> {
-        let path = config_path();
+        self.save_to(&config_path())
+    }
 
+    fn save_to(&self, path: &Path) -> Result<(), ConfigError> {
         // Ensure parent directory exists
         if let Some(dir) = path.parent() {
             std::fs::create_dir_all(dir).map_err(ConfigError::Io)?;
@@ -75,12 +463,132 @@ impl Config {
         std::fs::write(&tmp_path, &content).map_err(ConfigError::Io)?;
 
         // Atomic rename
-        std::fs::rename(&tmp_path, &path).map_err(ConfigError::Io)?;
+        std::fs::rename(&tmp_path, path).map_err(ConfigError::Io)?;
 
         Ok(())
     }
 }
 
+thread_local! {
+    static SHARED: SharedConfig = SharedConfig::new(Config::load());
+}
+
+/// The application-wide config, loaded on first use.
+pub fn shared() -> SharedConfig {
+    SHARED.with(SharedConfig::clone)
+}
+
+type ChangeHandler = Rc<dyn Fn(&Config)>;
+
+/// Config shared across the UI that saves itself shortly after each change,
+/// so a crash loses at most the last fraction of a second.
+///
+/// Lives on the main thread; background work reads the values it needs
+/// before it starts.
+#[derive(Clone)]
+pub struct SharedConfig {
+    config: Rc<RefCell<Config>>,
+    pending_save: Rc<RefCell<Option<glib::SourceId>>>,
+    handlers: Rc<RefCell<Vec<ChangeHandler>>>,
+}
+
+impl SharedConfig {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config: Rc::new(RefCell::new(config)),
+            pending_save: Rc::new(RefCell::new(None)),
+            handlers: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    /// Call `handler` with the new settings after every change, so widgets
+    /// showing a setting stay in sync wherever it was changed.
+    pub fn connect_changed<F>(&self, handler: F)
+    where
+        F: Fn(&Config) + 'static,
+    {
+        self.handlers.borrow_mut().push(Rc::new(handler));
+    }
+
+    /// Read the current settings.
+    pub fn get(&self) -> Ref<'_, Config> {
+        self.config.borrow()
+    }
+
+    /// Change settings, schedule a save and notify the change handlers.
+    ///
+    /// Each changed setting is also sent as [`Event::SettingsChanged`].
+    pub fn update(&self, change: impl FnOnce(&mut Config)) {
+        let before = self.config.borrow().clone();
+        change(&mut self.config.borrow_mut());
+
+        if let Some(source) = self.pending_save.borrow_mut().take() {
+            source.remove();
+        }
+
+        let this = self.clone();
+        let source = glib::timeout_add_local_once(SAVE_DELAY, move || {
+            this.pending_save.borrow_mut().take();
+            this.write();
+        });
+        *self.pending_save.borrow_mut() = Some(source);
+
+        // Handlers get a copy, so they may update the config themselves
+        let config = self.config.borrow().clone();
+        let handlers = self.handlers.borrow().clone();
+        for handler in handlers {
+            handler(&config);
+        }
+
+        for key in changed_keys(&before, &config) {
+            events::emit(Event::SettingsChanged { key });
+        }
+    }
+
+    /// Write pending changes immediately.
+    pub fn flush(&self) {
+        if let Some(source) = self.pending_save.borrow_mut().take() {
+            source.remove();
+        }
+        self.write();
+    }
+
+    fn write(&self) {
+        if let Err(e) = self.config.borrow().save() {
+            log::warn!("Failed to save config: {e}");
+        }
+    }
+}
+
+/// Keys of the settings that differ between `before` and `after`, like
+/// `seasonal.intensity`, as written to the config file.
+fn changed_keys(before: &Config, after: &Config) -> Vec<String> {
+    let table = |config: &Config| match toml::Value::try_from(config) {
+        Ok(toml::Value::Table(table)) => table,
+        _ => toml::Table::new(),
+    };
+    let mut keys = Vec::new();
+    diff_tables("", &table(before), &table(after), &mut keys);
+    keys
+}
+
+fn diff_tables(prefix: &str, before: &toml::Table, after: &toml::Table, keys: &mut Vec<String>) {
+    // Unset options are left out of the file, so a key may be on one side only
+    let mut names: Vec<&String> = before.keys().chain(after.keys()).collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        let key = format!("{}{}", prefix, name);
+        match (before.get(name), after.get(name)) {
+            (Some(toml::Value::Table(old)), Some(toml::Value::Table(new))) => {
+                diff_tables(&format!("{}.", key), old, new, keys)
+            }
+            (old, new) if old != new => keys.push(key),
+            _ => {}
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ConfigError {
     Io(std::io::Error),
@@ -95,3 +603,230 @@ impl std::fmt::Display for ConfigError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_keys() {
+        let before = Config::default();
+        assert!(changed_keys(&before, &before.clone()).is_empty());
+
+        let mut after = before.clone();
+        after.seasonal.intensity = 2.0;
+        after.seasonal.enabled = Some(false);
+        after.auth.show_summary = !before.auth.show_summary;
+        assert_eq!(
+            changed_keys(&before, &after),
+            [
+                "auth.show_summary",
+                "seasonal.enabled",
+                "seasonal.intensity"
+            ]
+        );
+        // Unsetting an option counts as well
+        assert_eq!(changed_keys(&after, &before).len(), 3);
+    }
+
+    #[test]
+    fn test_missing_keys_use_defaults() {
+        let config: Config = toml::from_str("[general]\nautostart = true\n").unwrap();
+        assert!(config.general.autostart);
+        assert_eq!(config.seasonal.enabled, None);
+        assert_eq!(config.window.width, 1100);
+        assert!(config.window.sidebar_visible);
+        assert_eq!(config.window.last_page, None);
+        assert_eq!(config.tasks.aur_confirmation, AurConfirmation::Silent);
+
+        let config: Config = toml::from_str("[tasks]\naur_confirmation = \"review\"\n").unwrap();
+        assert_eq!(config.tasks.aur_confirmation, AurConfirmation::Review);
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("xero-config-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("config.toml")
+    }
+
+    #[test]
+    fn test_save_load_round_trip() {
+        let path = temp_path("round-trip");
+        let mut config = Config::default();
+        config.tasks.battery_threshold = 15;
+        config.seasonal.enabled = Some(false);
+        config.save_to(&path).unwrap();
+
+        let loaded = Config::load_from(&path);
+        assert_eq!(loaded.version, CONFIG_VERSION);
+        assert_eq!(loaded.tasks.battery_threshold, 15);
+        assert_eq!(loaded.seasonal.enabled, Some(false));
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_unversioned_file_is_migrated() {
+        let config = Config::parse("[tasks]\nauto_close = true\n").unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert!(config.tasks.auto_close);
+    }
+
+    #[test]
+    fn test_migrations_run_from_file_version() {
+        let migrations: &[Migration] = &[
+            |table| {
+                table.insert("first".to_string(), toml::Value::Boolean(true));
+            },
+            |table| {
+                // Rename a key
+                if let Some(value) = table.remove("old") {
+                    table.insert("new".to_string(), value);
+                }
+            },
+        ];
+
+        let mut table: toml::Table = "old = 1\n".parse().unwrap();
+        migrate(&mut table, migrations);
+        assert_eq!(table.get("version"), Some(&toml::Value::Integer(2)));
+        assert_eq!(table.get("first"), Some(&toml::Value::Boolean(true)));
+        assert_eq!(table.get("new"), Some(&toml::Value::Integer(1)));
+
+        // Already at version 1: only the second migration runs
+        let mut table: toml::Table = "version = 1\nold = 1\n".parse().unwrap();
+        migrate(&mut table, migrations);
+        assert_eq!(table.get("first"), None);
+        assert_eq!(table.get("new"), Some(&toml::Value::Integer(1)));
+
+        // Written by a newer build
+        let mut table: toml::Table = "version = 5\nold = 1\n".parse().unwrap();
+        migrate(&mut table, migrations);
+        assert_eq!(table.get("version"), Some(&toml::Value::Integer(5)));
+        assert_eq!(table.get("old"), Some(&toml::Value::Integer(1)));
+    }
+
+    #[test]
+    fn test_corrupt_file_is_moved_aside() {
+        let path = temp_path("corrupt");
+        std::fs::write(&path, "[general\nautostart = ").unwrap();
+
+        let config = Config::load_from(&path);
+        assert!(!config.general.autostart);
+        assert!(!path.exists());
+        assert_eq!(
+            std::fs::read_to_string(path.with_extension("toml.corrupt")).unwrap(),
+            "[general\nautostart = "
+        );
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_window_state_round_trip() {
+        let mut config = Config::default();
+        config.window.maximized = true;
+        config.window.last_page = Some("drivers".to_string());
+
+        let parsed: Config = toml::from_str(&toml::to_string_pretty(&config).unwrap()).unwrap();
+        assert!(parsed.window.maximized);
+        assert_eq!(parsed.window.last_page.as_deref(), Some("drivers"));
+    }
+
+    #[test]
+    fn test_reset_preferences_keeps_state() {
+        let mut config = Config::default();
+        config.general.autostart = true;
+        config.general.welcomed = true;
+        config.window.width = 800;
+        config.tasks.auto_close = true;
+        config.downloads.speed_limit_kib = 512;
+        config.downloads.last_folder = Some("/mnt/isos".to_string());
+        config.updates.check = false;
+        config.updates.skipped_version = Some("0.3.0".to_string());
+
+        config.reset_preferences();
+        assert!(config.general.autostart);
+        assert!(config.general.welcomed);
+        assert_eq!(config.window.width, 800);
+        assert!(config.updates.check);
+        assert_eq!(config.updates.skipped_version.as_deref(), Some("0.3.0"));
+        assert!(!config.tasks.auto_close);
+        assert_eq!(config.downloads.speed_limit(), None);
+        assert_eq!(config.downloads.last_folder.as_deref(), Some("/mnt/isos"));
+    }
+
+    #[test]
+    fn test_seasonal_effects_follow_reduced_motion() {
+        let mut seasonal = SeasonalConfig::default();
+        assert!(seasonal.is_enabled(false));
+        assert!(!seasonal.is_enabled(true));
+
+        let parsed: Config = toml::from_str("[seasonal]\nenabled = true\n").unwrap();
+        assert!(parsed.seasonal.is_enabled(true));
+
+        seasonal.enabled = Some(false);
+        assert!(!seasonal.is_enabled(false));
+    }
+
+    #[test]
+    fn test_seasonal_intensity_scales_counts() {
+        let mut seasonal = SeasonalConfig::default();
+        assert_eq!(seasonal.scaled_count(80), 80);
+
+        seasonal.intensity = 0.25;
+        assert_eq!(seasonal.scaled_count(15), 4);
+
+        seasonal.intensity = 10.0;
+        assert_eq!(seasonal.scaled_count(15), 30);
+
+        seasonal.intensity = f64::NAN;
+        assert_eq!(seasonal.scaled_count(15), 15);
+
+        seasonal.halloween = false;
+        assert!(!seasonal.effect_enabled("halloween"));
+        assert!(seasonal.effect_enabled("snow"));
+    }
+
+    #[test]
+    fn test_download_folder_defaults_to_home() {
+        let default = Path::new("/home/xero/Downloads");
+        let mut downloads = DownloadsConfig::default();
+        assert_eq!(
+            downloads.folder_or_default(default),
+            PathBuf::from("/home/xero/Downloads")
+        );
+
+        downloads.folder = Some("/mnt/isos".to_string());
+        downloads.speed_limit_kib = 2;
+        assert_eq!(
+            downloads.folder_or_default(default),
+            PathBuf::from("/mnt/isos")
+        );
+        assert_eq!(downloads.speed_limit(), Some(2048));
+    }
+
+    #[test]
+    fn test_iso_folder() {
+        let default = Path::new("/home/xero/Downloads");
+        let mut downloads = DownloadsConfig {
+            last_folder: Some("/tmp/last".to_string()),
+            ..DownloadsConfig::default()
+        };
+        // The last folder only counts when it should be remembered
+        assert_eq!(downloads.iso_folder_or_default(default), default);
+
+        downloads.folder = Some("/mnt/downloads".to_string());
+        assert_eq!(
+            downloads.iso_folder_or_default(default),
+            Path::new("/mnt/downloads")
+        );
+        downloads.iso_folder = Some("/mnt/isos".to_string());
+        assert_eq!(
+            downloads.iso_folder_or_default(default),
+            Path::new("/mnt/isos")
+        );
+        downloads.remember_last_folder = true;
+        assert_eq!(
+            downloads.iso_folder_or_default(default),
+            Path::new("/tmp/last")
+        );
+    }
+}
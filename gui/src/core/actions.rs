@@ -0,0 +1,645 @@
+//! Named maintenance actions shared by page buttons and the command line.
+//!
+//! Each action builds its command sequence on demand, so buttons and
+//! `xero-toolkit --run <id>` execute exactly the same steps.
+
+use crate::core;
+use crate::core::mirrorlist::{Backend, Protocol};
+use crate::core::package::UpdateComponent;
+use crate::i18n::{self, gettext, gettext_noop};
+use crate::ui::task_runner::{Command, CommandSequence};
+use std::path::Path;
+
+/// An optional part of an action, picked in its selection dialog.
+#[derive(Debug, Clone, Copy)]
+pub struct ActionChoice {
+    /// Identifier stored in setup profiles.
+    pub id: &'static str,
+    pub label: &'static str,
+}
+
+/// A maintenance action runnable from a button or the command line.
+#[derive(Debug, Clone, Copy)]
+pub struct Action {
+    /// Identifier used with `--run`.
+    pub id: &'static str,
+    /// Title of the task runner dialog.
+    pub title: &'static str,
+    pub description: &'static str,
+    /// Extra search terms for the command palette.
+    pub keywords: &'static [&'static str],
+    /// Page id and builder id of the button running this action.
+    pub location: (&'static str, &'static str),
+    /// Choices offered by the action's selection dialog, if it has one.
+    pub choices: &'static [ActionChoice],
+    build: fn(&[String]) -> CommandSequence,
+}
+
+impl Action {
+    /// Build the command sequence for this action with its default choices.
+    pub fn commands(&self) -> CommandSequence {
+        (self.build)(&[])
+    }
+
+    /// Build the command sequence for the given choice ids.
+    pub fn commands_with(&self, choices: &[String]) -> CommandSequence {
+        (self.build)(choices)
+    }
+
+    /// Look up one of this action's choices by id.
+    pub fn find_choice(&self, id: &str) -> Option<&'static ActionChoice> {
+        self.choices.iter().find(|choice| choice.id == id)
+    }
+}
+
+pub const CLEAR_PACMAN_CACHE: Action = Action {
+    id: "clear-pacman-cache",
+    title: gettext_noop("Clear Pacman Cache"),
+    description: gettext_noop("Remove all but the newest cached version of each package"),
+    keywords: &["clean", "disk space", "packages", "free"],
+    location: ("servicing_system_tweaks", "btn_clr_pacman"),
+    choices: &[],
+    build: |_| clear_pacman_cache(),
+};
+
+pub const UNLOCK_PACMAN: Action = Action {
+    id: "unlock-pacman",
+    title: gettext_noop("Unlock Pacman Database"),
+    description: gettext_noop("Remove a stale pacman database lock"),
+    keywords: &["lock", "db.lck", "database", "stuck"],
+    location: ("servicing_system_tweaks", "btn_unlock_pacman"),
+    choices: &[],
+    build: |_| unlock_pacman(),
+};
+
+pub const FIX_PACMAN_DB: Action = Action {
+    id: "fix-pacman-db",
+    title: gettext_noop("Pacman DB Fix"),
+    description: gettext_noop("Strip stray %INSTALLED_DB% entries from the local database"),
+    keywords: &["database", "corrupt", "installed_db", "repair"],
+    location: ("servicing_system_tweaks", "btn_pacman_db_fix"),
+    choices: &[],
+    build: |_| fix_pacman_db(),
+};
+
+pub const FIX_ARCH_KEYRING: Action = Action {
+    id: "fix-arch-keyring",
+    title: gettext_noop("Fix GnuPG Keyring"),
+    description: gettext_noop("Recreate the pacman keyring and reinstall archlinux-keyring"),
+    keywords: &["gpg", "keys", "signature", "pgp", "trust"],
+    location: ("servicing_system_tweaks", "btn_fix_arch_keyring"),
+    choices: &[],
+    build: |_| fix_arch_keyring(),
+};
+
+pub const UPDATE_MIRRORLIST: Action = Action {
+    id: "update-mirrorlist",
+    title: gettext_noop("Update System Mirrorlist"),
+    description: gettext_noop("Rank Arch mirrors with rate-mirrors"),
+    keywords: &[
+        "mirrors",
+        "rate-mirrors",
+        "reflector",
+        "slow downloads",
+        "chaotic",
+    ],
+    location: ("servicing_system_tweaks", "btn_update_mirrorlist"),
+    choices: &[ActionChoice {
+        id: "chaotic",
+        label: gettext_noop("Chaotic-AUR Mirrorlist"),
+    }],
+    build: |choices| {
+        mirrorlist_commands(
+            choices.iter().any(|c| c == "chaotic"),
+            None,
+            Backend::default(),
+            Protocol::default(),
+        )
+    },
+};
+
+pub const PLASMA_X11_SESSION: Action = Action {
+    id: "plasma-x11-session",
+    title: gettext_noop("Install KDE X11 Session"),
+    description: gettext_noop("Install the KDE Plasma X11 session"),
+    keywords: &["kde", "xorg", "x11", "wayland", "login"],
+    location: ("servicing_system_tweaks", "btn_plasma_x11"),
+    choices: &[],
+    build: |_| plasma_x11_session(),
+};
+
+pub const UPDATE_SYSTEM: Action = Action {
+    id: "update-system",
+    title: gettext_noop("System Update"),
+    description: gettext_noop(
+        "Update repo packages, AUR packages, Flatpaks and firmware where available",
+    ),
+    keywords: &["upgrade", "pacman", "aur", "flatpak", "firmware", "fwupd"],
+    location: ("main_page", "btn_update_system"),
+    choices: &[
+        ActionChoice {
+            id: "repo",
+            label: gettext_noop("Repo packages"),
+        },
+        ActionChoice {
+            id: "aur",
+            label: gettext_noop("AUR packages"),
+        },
+        ActionChoice {
+            id: "flatpak",
+            label: gettext_noop("Flatpaks"),
+        },
+        ActionChoice {
+            id: "firmware",
+            label: gettext_noop("Firmware"),
+        },
+    ],
+    // Without choices everything available is updated
+    build: |choices| {
+        let available: Vec<UpdateComponent> = UpdateComponent::ALL
+            .into_iter()
+            .filter(UpdateComponent::is_available)
+            .filter(|component| choices.is_empty() || choices.iter().any(|c| c == component.id()))
+            .collect();
+        update_commands(&available)
+    },
+};
+
+/// All registered actions.
+pub const ACTIONS: &[Action] = &[
+    UPDATE_SYSTEM,
+    CLEAR_PACMAN_CACHE,
+    UNLOCK_PACMAN,
+    FIX_PACMAN_DB,
+    FIX_ARCH_KEYRING,
+    UPDATE_MIRRORLIST,
+    PLASMA_X11_SESSION,
+];
+
+/// Look up an action by id.
+pub fn find(id: &str) -> Option<&'static Action> {
+    ACTIONS.iter().find(|action| action.id == id)
+}
+
+fn clear_pacman_cache() -> CommandSequence {
+    // paccache needs no answers, unlike pacman -Scc
+    CommandSequence::new()
+        .then(
+            Command::builder()
+                .privileged()
+                .program("paccache")
+                .args(&["-rk1"])
+                .description(&gettext("Removing older cached package versions..."))
+                .build(),
+        )
+        .then(
+            Command::builder()
+                .privileged()
+                .program("paccache")
+                .args(&["-ruk0"])
+                .description(&gettext(
+                    "Removing cached versions of uninstalled packages...",
+                ))
+                .build(),
+        )
+}
+
+fn unlock_pacman() -> CommandSequence {
+    CommandSequence::new().then(
+        Command::builder()
+            .privileged()
+            .program("rm")
+            .args(&["-f", "/var/lib/pacman/db.lck"])
+            .description(&gettext("Removing Pacman lock file..."))
+            .build(),
+    )
+}
+
+fn fix_pacman_db() -> CommandSequence {
+    CommandSequence::new().then(
+        Command::builder()
+            .privileged()
+            .program("sh")
+            .args(&[
+                "-c",
+                "find /var/lib/pacman/local/ -type f -name 'desc' -exec sed -i '/^%INSTALLED_DB%$/,+2d' {} \\;",
+            ])
+            .description(&gettext("Fixing Pacman local database..."))
+            .build(),
+    )
+}
+
+fn fix_arch_keyring() -> CommandSequence {
+    CommandSequence::new()
+        .then(
+            Command::builder()
+                .privileged()
+                .program("rm")
+                .args(&["-rf", "/etc/pacman.d/gnupg"])
+                .description(&gettext("Removing existing GnuPG keyring..."))
+                .build(),
+        )
+        .then(
+            Command::builder()
+                .privileged()
+                .program("pacman-key")
+                .args(&["--init"])
+                .description(&gettext("Initializing new keyring..."))
+                .build(),
+        )
+        .then(
+            Command::builder()
+                .privileged()
+                .program("pacman-key")
+                .args(&["--populate"])
+                .description(&gettext("Populating keyring..."))
+                .build(),
+        )
+        .then(
+            core::privileged_fs::append_line_if_missing(
+                "/etc/pacman.d/gnupg/gpg.conf",
+                "keyserver hkp://keyserver.ubuntu.com:80",
+            )
+            .command(&gettext("Setting keyserver...")),
+        )
+        .then(
+            Command::builder()
+                .privileged()
+                .program("pacman")
+                .args(&["-Syy", "--noconfirm", "archlinux-keyring"])
+                .description(&gettext("Reinstalling Arch Linux keyring..."))
+                .build(),
+        )
+}
+
+/// Rank Arch mirrors with `backend`, and Chaotic-AUR mirrors if
+/// `include_chaotic` is set. rate-mirrors ranks the Chaotic-AUR ones with
+/// either backend, reflector only knows the Arch mirrors.
+///
+/// `entry_country` is the country code mirrors are ranked around, or
+/// `None` to rank them globally. The old Arch mirrorlist is backed up first.
+pub fn mirrorlist_commands(
+    include_chaotic: bool,
+    entry_country: Option<&str>,
+    backend: Backend,
+    protocol: Protocol,
+) -> CommandSequence {
+    let mut commands = CommandSequence::new();
+    let entry = core::mirrorlist::region_args(entry_country);
+    let protocol_args = core::mirrorlist::protocol_args(protocol);
+
+    let mut packages = vec![backend.package()];
+    if include_chaotic && backend != Backend::RateMirrors {
+        packages.push(Backend::RateMirrors.package());
+    }
+    for package in packages {
+        if !core::is_package_installed(package) {
+            commands = commands.then(
+                Command::builder()
+                    .aur()
+                    .args(&["-S", "--needed", package])
+                    .description(&i18n::fill(
+                        &gettext("Installing {} utility..."),
+                        &[package],
+                    ))
+                    .build(),
+            );
+        }
+    }
+
+    commands = commands.extend(backup_mirrorlist_commands());
+
+    let update = match backend {
+        Backend::RateMirrors => Command::builder().privileged().program("sh").args(&[
+            "-c",
+            &format!(
+                "rate-mirrors --allow-root{protocol_args}{entry} arch | tee {}",
+                core::mirrorlist::ARCH_MIRRORLIST
+            ),
+        ]),
+        Backend::Reflector => {
+            let args = core::mirrorlist::reflector_args(entry_country, protocol);
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            Command::builder()
+                .privileged()
+                .program("reflector")
+                .args(&args)
+        }
+    };
+    commands = commands.then(
+        update
+            .description(&gettext("Updating Arch mirrorlist..."))
+            .build(),
+    );
+
+    if include_chaotic {
+        commands = commands.then(
+            Command::builder()
+                .privileged()
+                .program("sh")
+                .args(&[
+                    "-c",
+                    &format!(
+                        "rate-mirrors --allow-root{protocol_args}{entry} chaotic-aur | tee /etc/pacman.d/chaotic-mirrorlist"
+                    ),
+                ])
+                .description(&gettext("Updating Chaotic-AUR mirrorlist..."))
+                .build(),
+        );
+    }
+
+    commands
+}
+
+/// Copy the Arch mirrorlist to a timestamped backup, keeping the newest
+/// [`core::mirrorlist::BACKUPS_KEPT`].
+fn backup_mirrorlist_commands() -> CommandSequence {
+    let exists = || Path::new(core::mirrorlist::ARCH_MIRRORLIST).exists();
+    let mut commands = CommandSequence::new().then(
+        Command::script(
+            &gettext("Backing up the mirrorlist..."),
+            r#"cp -p "$MIRRORLIST" "$MIRRORLIST.bak.$(date +%Y%m%d-%H%M%S)""#,
+        )
+        .privileged()
+        .var("MIRRORLIST", core::mirrorlist::ARCH_MIRRORLIST)
+        .run_if(exists)
+        .build(),
+    );
+
+    // Counting the backup made above
+    let stale = core::mirrorlist::stale_backups(
+        Path::new(core::mirrorlist::ARCH_MIRRORLIST),
+        core::mirrorlist::BACKUPS_KEPT - 1,
+    );
+    if !stale.is_empty() {
+        let paths: Vec<String> = stale
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        let mut args = vec!["-f", "--"];
+        args.extend(paths.iter().map(String::as_str));
+        commands = commands.then(
+            Command::builder()
+                .privileged()
+                .program("rm")
+                .args(&args)
+                .description(&gettext("Removing old mirrorlist backups..."))
+                .run_if(exists)
+                .build(),
+        );
+    }
+    commands
+}
+
+/// Replace the Arch mirrorlist with the backup at `backup`.
+pub fn restore_mirrorlist_commands(backup: &Path) -> CommandSequence {
+    CommandSequence::new().then(
+        Command::builder()
+            .privileged()
+            .program("cp")
+            .args(&[
+                "--",
+                &backup.to_string_lossy(),
+                core::mirrorlist::ARCH_MIRRORLIST,
+            ])
+            .description(&gettext("Restoring the mirrorlist..."))
+            .build(),
+    )
+}
+
+/// Starts a scrub in the background, prints its status until it finishes
+/// and fails unless it finished without errors.
+const SCRUB_SCRIPT: &str = "btrfs scrub start / || exit; \
+    while btrfs scrub status / | grep -q 'running'; do \
+        btrfs scrub status / | grep -E 'Bytes scrubbed|Rate|Error summary'; sleep 5; \
+    done; \
+    status=$(btrfs scrub status -R /) || exit; \
+    echo \"$status\"; \
+    echo \"$status\" | awk '/^[[:space:]]*Status:/ && $2 != \"finished\" { bad = 1 } \
+        /_errors:/ && $2 > 0 { bad = 1 } END { exit bad }'";
+
+/// Scrub the root btrfs filesystem.
+pub fn btrfs_scrub() -> CommandSequence {
+    CommandSequence::new().then(
+        Command::builder()
+            .privileged()
+            .program("sh")
+            .args(&["-c", SCRUB_SCRIPT])
+            .description(&gettext("Scrubbing root filesystem..."))
+            .build(),
+    )
+}
+
+/// Keep the two most recent versions of each cached package.
+pub fn trim_package_cache() -> CommandSequence {
+    CommandSequence::new()
+        .then(
+            Command::builder()
+                .privileged()
+                .program("paccache")
+                .args(&["-rk2"])
+                .description(&gettext("Trimming package cache..."))
+                .build(),
+        )
+        .then(
+            Command::builder()
+                .privileged()
+                .program("paccache")
+                .args(&["-ruk0"])
+                .description(&gettext(
+                    "Removing cached versions of uninstalled packages...",
+                ))
+                .build(),
+        )
+}
+
+/// Remove Flatpak runtimes no installed app uses.
+pub fn remove_unused_flatpaks() -> CommandSequence {
+    CommandSequence::new().then(
+        Command::builder()
+            .privileged()
+            .program("flatpak")
+            .args(&["uninstall", "--system", "--unused", "--noninteractive"])
+            .description(&gettext("Removing unused Flatpak runtimes..."))
+            .build(),
+    )
+}
+
+/// Drop journal entries older than four weeks.
+pub fn vacuum_journal() -> CommandSequence {
+    CommandSequence::new().then(
+        Command::builder()
+            .privileged()
+            .program("journalctl")
+            .args(&["--vacuum-time=4weeks"])
+            .description(&gettext("Shrinking the system journal..."))
+            .build(),
+    )
+}
+
+fn plasma_x11_session() -> CommandSequence {
+    CommandSequence::new().then(
+        Command::builder()
+            .aur()
+            .args(&["-S", "--needed", "kwin-x11", "plasma-x11-session"])
+            .description(&gettext("Installing KDE Plasma X11 session components..."))
+            .build(),
+    )
+}
+
+/// SDDM drop-in choosing the session preselected at login.
+pub const SDDM_DEFAULT_SESSION: &str = "/etc/sddm.conf.d/default-session.conf";
+
+/// Session file plasma-x11-session installs in /usr/share/xsessions.
+const PLASMA_X11_DESKTOP: &str = "plasmax11.desktop";
+
+/// Make SDDM preselect the Plasma X11 session instead of the last used one.
+pub fn default_x11_session_commands() -> CommandSequence {
+    CommandSequence::new()
+        .then(
+            Command::builder()
+                .privileged()
+                .program("mkdir")
+                .args(&["-p", "/etc/sddm.conf.d"])
+                .description(&gettext("Creating the SDDM configuration directory..."))
+                .build(),
+        )
+        .then(
+            core::privileged_fs::write_file(
+                SDDM_DEFAULT_SESSION,
+                &format!("[General]\nDefaultSession={}\n", PLASMA_X11_DESKTOP),
+            )
+            .command(&gettext("Setting the default login session...")),
+        )
+}
+
+/// Remove the SDDM drop-in, so the last used session is preselected again.
+pub fn revert_default_session_commands() -> CommandSequence {
+    CommandSequence::new().then(
+        Command::builder()
+            .privileged()
+            .program("rm")
+            .args(&["-f", "--", SDDM_DEFAULT_SESSION])
+            .description(&gettext("Resetting the default login session..."))
+            .build(),
+    )
+}
+
+/// Update the given components.
+pub fn update_commands(components: &[UpdateComponent]) -> CommandSequence {
+    let mut commands = CommandSequence::new().long_running();
+
+    if components.contains(&UpdateComponent::Repo) {
+        commands = commands.then(
+            Command::builder()
+                .privileged()
+                .program("pacman")
+                .args(&["-Syu", "--noconfirm"])
+                .description(&gettext("Updating repo packages..."))
+                .build(),
+        );
+    }
+
+    if components.contains(&UpdateComponent::Aur) {
+        commands = commands.then(
+            Command::builder()
+                .aur()
+                .args(&["-Sua"])
+                .description(&gettext("Updating AUR packages..."))
+                .build(),
+        );
+    }
+
+    if components.contains(&UpdateComponent::Flatpak) {
+        commands = commands.then(
+            Command::builder()
+                .normal()
+                .program("flatpak")
+                .args(&["update", "-y"])
+                .description(&gettext("Updating Flatpaks..."))
+                .build(),
+        );
+    }
+
+    if components.contains(&UpdateComponent::Firmware) {
+        // fwupdmgr exits 2 when there is nothing to do, which is not a failure here
+        commands = commands
+            .then(
+                Command::builder()
+                    .privileged()
+                    .program("sh")
+                    .args(&[
+                        "-c",
+                        "fwupdmgr refresh --force; rc=$?; [ $rc -eq 0 ] || [ $rc -eq 2 ]",
+                    ])
+                    .description(&gettext("Refreshing firmware metadata..."))
+                    .build(),
+            )
+            .then(
+                Command::builder()
+                    .privileged()
+                    .program("sh")
+                    .args(&[
+                        "-c",
+                        "fwupdmgr update -y --no-reboot-check; rc=$?; [ $rc -eq 0 ] || [ $rc -eq 2 ]",
+                    ])
+                    .description(&gettext("Updating firmware..."))
+                    .build(),
+            );
+    }
+
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_ids_are_unique() {
+        for (index, action) in ACTIONS.iter().enumerate() {
+            assert!(
+                ACTIONS[index + 1..]
+                    .iter()
+                    .all(|other| other.id != action.id),
+                "duplicate action id {}",
+                action.id
+            );
+        }
+        assert_eq!(
+            find("unlock-pacman").map(|a| a.title),
+            Some(UNLOCK_PACMAN.title)
+        );
+        assert!(find("nope").is_none());
+    }
+
+    #[test]
+    fn test_actions_live_on_known_pages() {
+        for action in ACTIONS {
+            let (page, button) = action.location;
+            assert!(
+                crate::ui::navigation::PAGES.iter().any(|p| p.id == page),
+                "action {} points at unknown page {}",
+                action.id,
+                page
+            );
+            assert!(button.starts_with("btn_"));
+        }
+    }
+
+    #[test]
+    fn test_update_choices_match_components() {
+        for component in UpdateComponent::ALL {
+            assert!(UPDATE_SYSTEM.find_choice(component.id()).is_some());
+        }
+        assert_eq!(UPDATE_SYSTEM.choices.len(), UpdateComponent::ALL.len());
+        assert!(UPDATE_MIRRORLIST.find_choice("chaotic").is_some());
+        assert!(UNLOCK_PACMAN.find_choice("chaotic").is_none());
+    }
+
+    #[test]
+    fn test_update_commands_follow_selection() {
+        assert!(update_commands(&[]).is_empty());
+        assert!(!update_commands(&[UpdateComponent::Flatpak]).is_empty());
+    }
+}
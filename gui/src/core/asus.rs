@@ -0,0 +1,253 @@
+//! ASUS laptop controls through asusctl and supergfxctl.
+//!
+//! Both tools talk to their daemons (asusd, supergfxd) over D-Bus, so
+//! queries and switches run as the user.
+
+use super::aur::is_executable_in_path;
+use anyhow::{bail, Context, Result};
+use log::warn;
+use std::path::Path;
+use std::process::Command;
+
+/// Files naming the machine's manufacturer.
+const DMI_VENDOR_FILES: &[&str] = &[
+    "/sys/class/dmi/id/sys_vendor",
+    "/sys/class/dmi/id/board_vendor",
+];
+
+/// Keyboard backlight exposed by the asus-nb-wmi driver.
+const KBD_BACKLIGHT: &str = "/sys/class/leds/asus::kbd_backlight/brightness";
+
+/// Lowest charge limit asusctl accepts.
+pub const MIN_CHARGE_LIMIT: u8 = 20;
+
+/// Keyboard backlight levels, as `asusctl -k` arguments with their labels.
+pub const KBD_LEVELS: &[(&str, &str)] = &[
+    ("off", crate::i18n::gettext_noop("Off")),
+    ("low", crate::i18n::gettext_noop("Low")),
+    ("med", crate::i18n::gettext_noop("Medium")),
+    ("high", crate::i18n::gettext_noop("High")),
+];
+
+/// State of the ROG controls; `None` and empty lists for whatever could not
+/// be queried.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RogStatus {
+    pub has_asusctl: bool,
+    pub has_supergfxctl: bool,
+    /// Active platform profile, like `Balanced`
+    pub profile: Option<String>,
+    pub profiles: Vec<String>,
+    /// Current GPU mode, like `Hybrid`
+    pub gpu_mode: Option<String>,
+    pub gpu_modes: Vec<String>,
+    /// Index into [`KBD_LEVELS`]
+    pub kbd_level: Option<usize>,
+    /// Battery charge limit in percent
+    pub charge_limit: Option<u8>,
+}
+
+/// Whether a DMI vendor string names ASUS.
+fn is_asus_vendor(vendor: &str) -> bool {
+    let vendor = vendor.trim().to_lowercase();
+    vendor.starts_with("asustek") || vendor == "asus"
+}
+
+/// Whether this machine is made by ASUS.
+pub fn is_asus_hardware() -> bool {
+    DMI_VENDOR_FILES
+        .iter()
+        .any(|file| std::fs::read_to_string(file).is_ok_and(|vendor| is_asus_vendor(&vendor)))
+}
+
+/// Active profile from `asusctl profile -p` output.
+pub fn parse_active_profile(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Active profile is "))
+        .map(|profile| profile.trim().to_string())
+        .filter(|profile| !profile.is_empty())
+}
+
+/// Profiles from `asusctl profile -l` output, skipping the version banner.
+pub fn parse_profile_list(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.contains(' '))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Current mode from `supergfxctl -g` output.
+pub fn parse_gpu_mode(output: &str) -> Option<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.contains(' '))
+        .map(str::to_string)
+}
+
+/// Supported modes from `supergfxctl -s` output, like
+/// `[Integrated, Hybrid, AsusMuxDgpu]`.
+pub fn parse_gpu_modes(output: &str) -> Vec<String> {
+    let Some(list) = output
+        .trim()
+        .strip_prefix('[')
+        .and_then(|list| list.strip_suffix(']'))
+    else {
+        return Vec::new();
+    };
+    list.split(',')
+        .map(str::trim)
+        .filter(|mode| !mode.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Index into [`KBD_LEVELS`] of a sysfs brightness value.
+fn parse_kbd_level(brightness: &str) -> Option<usize> {
+    brightness
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .filter(|level| *level < KBD_LEVELS.len())
+}
+
+/// Whether switching from `from` to `to` needs a reboot rather than a
+/// logout. The MUX switch rewires the display outputs.
+pub fn gpu_switch_needs_reboot(from: &str, to: &str) -> bool {
+    from == "AsusMuxDgpu" || to == "AsusMuxDgpu"
+}
+
+/// Check a charge limit before passing it to `asusctl -c`.
+pub fn validate_charge_limit(limit: u8) -> Result<()> {
+    if !(MIN_CHARGE_LIMIT..=100).contains(&limit) {
+        bail!(
+            "Charge limit must be between {} and 100 percent",
+            MIN_CHARGE_LIMIT
+        );
+    }
+    Ok(())
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        bail!("{} exited with {}", program, output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Log why a value is missing and drop the error.
+fn tolerate<T>(what: &str, result: Result<T>) -> Option<T> {
+    result
+        .map_err(|e| warn!("Failed to query {}: {:#}", what, e))
+        .ok()
+}
+
+/// Charge limit of the first battery that has one.
+fn charge_limit() -> Option<u8> {
+    std::fs::read_dir("/sys/class/power_supply")
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("BAT"))
+        .find_map(|entry| {
+            std::fs::read_to_string(entry.path().join("charge_control_end_threshold")).ok()
+        })
+        .and_then(|limit| limit.trim().parse().ok())
+}
+
+/// Query asusctl, supergfxctl and sysfs. Blocks on the tools, so run it off
+/// the main thread.
+pub fn status() -> RogStatus {
+    let mut status = RogStatus {
+        has_asusctl: is_executable_in_path("asusctl"),
+        has_supergfxctl: is_executable_in_path("supergfxctl"),
+        ..RogStatus::default()
+    };
+
+    if status.has_asusctl {
+        status.profile = tolerate("profile", run("asusctl", &["profile", "-p"]))
+            .and_then(|output| parse_active_profile(&output));
+        status.profiles = tolerate("profiles", run("asusctl", &["profile", "-l"]))
+            .map(|output| parse_profile_list(&output))
+            .unwrap_or_default();
+        status.kbd_level = std::fs::read_to_string(Path::new(KBD_BACKLIGHT))
+            .ok()
+            .and_then(|brightness| parse_kbd_level(&brightness));
+        status.charge_limit = charge_limit();
+    }
+
+    if status.has_supergfxctl {
+        status.gpu_mode = tolerate("GPU mode", run("supergfxctl", &["-g"]))
+            .and_then(|output| parse_gpu_mode(&output));
+        status.gpu_modes = tolerate("GPU modes", run("supergfxctl", &["-s"]))
+            .map(|output| parse_gpu_modes(&output))
+            .unwrap_or_default();
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_asus_vendor() {
+        assert!(is_asus_vendor("ASUSTeK COMPUTER INC.\n"));
+        assert!(is_asus_vendor("ASUS"));
+        assert!(!is_asus_vendor("LENOVO\n"));
+        assert!(!is_asus_vendor("Dell Inc."));
+        assert!(!is_asus_vendor(""));
+    }
+
+    #[test]
+    fn test_parse_profiles() {
+        assert_eq!(
+            parse_active_profile(include_str!("../../tests/fixtures/asus/profile-p.txt"))
+                .as_deref(),
+            Some("Balanced")
+        );
+        assert_eq!(parse_active_profile("Error: asusd is not running\n"), None);
+        assert_eq!(
+            parse_profile_list(include_str!("../../tests/fixtures/asus/profile-l.txt")),
+            ["Quiet", "Balanced", "Performance"]
+        );
+    }
+
+    #[test]
+    fn test_parse_gpu_modes() {
+        assert_eq!(
+            parse_gpu_mode(include_str!("../../tests/fixtures/asus/supergfxctl-g.txt")).as_deref(),
+            Some("Hybrid")
+        );
+        assert_eq!(parse_gpu_mode(""), None);
+        assert_eq!(
+            parse_gpu_modes(include_str!("../../tests/fixtures/asus/supergfxctl-s.txt")),
+            ["Integrated", "Hybrid", "AsusMuxDgpu"]
+        );
+        assert!(parse_gpu_modes("Zbus error\n").is_empty());
+
+        assert!(!gpu_switch_needs_reboot("Hybrid", "Integrated"));
+        assert!(gpu_switch_needs_reboot("Hybrid", "AsusMuxDgpu"));
+        assert!(gpu_switch_needs_reboot("AsusMuxDgpu", "Hybrid"));
+    }
+
+    #[test]
+    fn test_kbd_level_and_charge_limit() {
+        assert_eq!(parse_kbd_level("2\n"), Some(2));
+        assert_eq!(parse_kbd_level("0"), Some(0));
+        assert_eq!(parse_kbd_level("4"), None);
+        assert_eq!(parse_kbd_level("bright"), None);
+
+        assert!(validate_charge_limit(80).is_ok());
+        assert!(validate_charge_limit(100).is_ok());
+        assert!(validate_charge_limit(MIN_CHARGE_LIMIT).is_ok());
+        assert!(validate_charge_limit(10).is_err());
+        assert!(validate_charge_limit(101).is_err());
+    }
+}
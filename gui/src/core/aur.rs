@@ -58,7 +58,7 @@ pub fn get() -> Option<&'static str> {
 }
 
 /// Check if a command is executable in PATH.
-fn is_executable_in_path(cmd: &str) -> bool {
+pub(crate) fn is_executable_in_path(cmd: &str) -> bool {
     if cmd.contains(std::path::MAIN_SEPARATOR) {
         return PathBuf::from(cmd).is_file();
     }
@@ -11,15 +11,17 @@ use std::process::Command;
 
 /// Get the autostart desktop file path
 pub fn get_autostart_path() -> PathBuf {
-    let config_dir = dirs::config_dir().unwrap_or_else(|| PathBuf::from("~/.config"));
-    config_dir.join("autostart").join("xero-toolkit.desktop")
+    autostart_dir().join("xero-toolkit.desktop")
+}
+
+/// The host's autostart directory, also from inside a sandbox.
+fn autostart_dir() -> PathBuf {
+    crate::core::paths::get().host_config.join("autostart")
 }
 
 /// Enable autostart by creating a symlink to the desktop file in autostart directory
 pub fn enable() -> Result<(), std::io::Error> {
-    let autostart_dir = dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("~/.config"))
-        .join("autostart");
+    let autostart_dir = autostart_dir();
 
     // Create autostart directory if it doesn't exist
     fs::create_dir_all(&autostart_dir)?;
@@ -0,0 +1,979 @@
+//! Bootloader settings: menu timeout, default entry and kernel parameters.
+//!
+//! GRUB reads them from `/etc/default/grub` and needs `grub-mkconfig`
+//! afterwards; systemd-boot reads `loader.conf` and the `options` line of
+//! each entry directly. Edits rewrite only the lines they change, so
+//! comments and settings the toolkit does not know about are kept.
+//!
+//! Recovery after an interrupted update also lives here: finding the tool
+//! that builds the initramfs and the kernels it builds images for.
+
+use crate::i18n::gettext_noop;
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+/// GRUB settings file.
+pub const GRUB_DEFAULTS: &str = "/etc/default/grub";
+
+/// Generated GRUB menu.
+pub const GRUB_CONFIG: &str = "/boot/grub/grub.cfg";
+
+/// Where the EFI system partition holding systemd-boot is mounted.
+const ESP_MOUNTS: &[&str] = &["/boot", "/efi", "/boot/efi"];
+
+/// Longest menu timeout offered, in seconds.
+pub const MAX_TIMEOUT: u32 = 60;
+
+/// Kernel parameters offered as toggles, with their labels.
+pub const COMMON_PARAMS: &[(&str, &str)] = &[
+    (
+        "nvidia-drm.modeset=1",
+        gettext_noop("NVIDIA kernel modesetting"),
+    ),
+    ("amd_pstate=active", gettext_noop("AMD P-State active mode")),
+    ("quiet", gettext_noop("Quiet boot")),
+    ("splash", gettext_noop("Boot splash screen")),
+    ("nowatchdog", gettext_noop("Disable watchdog timers")),
+];
+
+/// The installed bootloader.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Bootloader {
+    Grub,
+    /// systemd-boot with its EFI system partition mounted at `esp`
+    SystemdBoot {
+        esp: PathBuf,
+    },
+}
+
+impl Bootloader {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Grub => "GRUB",
+            Self::SystemdBoot { .. } => "systemd-boot",
+        }
+    }
+}
+
+/// Find the bootloader this system uses.
+pub fn detect() -> Option<Bootloader> {
+    if Path::new(GRUB_DEFAULTS).is_file() && Path::new(GRUB_CONFIG).is_file() {
+        return Some(Bootloader::Grub);
+    }
+    ESP_MOUNTS
+        .iter()
+        .map(PathBuf::from)
+        .find(|esp| esp.join("loader/loader.conf").is_file())
+        .map(|esp| Bootloader::SystemdBoot { esp })
+}
+
+/// mkinitcpio settings, relative to the root.
+const MKINITCPIO_CONFIG: &str = "etc/mkinitcpio.conf";
+
+/// mkinitcpio presets, one per kernel, relative to the root.
+const MKINITCPIO_PRESETS: &str = "etc/mkinitcpio.d";
+
+/// dracut settings, relative to the root.
+const DRACUT_CONFIG: &str = "etc/dracut.conf";
+
+/// dracut drop-in settings, relative to the root.
+const DRACUT_CONFIG_DIR: &str = "etc/dracut.conf.d";
+
+/// Module directories of the kernels, relative to the root.
+const MODULES_DIR: &str = "usr/lib/modules";
+
+/// The tool building the initramfs images.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InitramfsGenerator {
+    Mkinitcpio,
+    Dracut,
+}
+
+impl InitramfsGenerator {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Mkinitcpio => "mkinitcpio",
+            Self::Dracut => "dracut",
+        }
+    }
+}
+
+/// Find the tool building the initramfs images from the configuration
+/// under `root`.
+///
+/// mkinitcpio wins as long as it has presets, as the kernel hooks build
+/// from them; a system moved to dracut often keeps `mkinitcpio.conf`.
+pub fn detect_initramfs_in(root: &Path) -> Option<InitramfsGenerator> {
+    let has_presets = std::fs::read_dir(root.join(MKINITCPIO_PRESETS))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .any(|entry| entry.path().extension().is_some_and(|ext| ext == "preset"));
+    if has_presets {
+        Some(InitramfsGenerator::Mkinitcpio)
+    } else if root.join(DRACUT_CONFIG).is_file() || root.join(DRACUT_CONFIG_DIR).is_dir() {
+        Some(InitramfsGenerator::Dracut)
+    } else if root.join(MKINITCPIO_CONFIG).is_file() {
+        Some(InitramfsGenerator::Mkinitcpio)
+    } else {
+        None
+    }
+}
+
+/// Find the tool building the initramfs images of this system.
+pub fn detect_initramfs() -> Option<InitramfsGenerator> {
+    detect_initramfs_in(Path::new("/"))
+}
+
+/// A kernel with its modules installed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Kernel {
+    /// Package name, like `linux-lts`
+    pub package: String,
+    /// Release, like `6.6.30-1-lts`
+    pub version: String,
+}
+
+impl Kernel {
+    /// Kernel image shipped by the package.
+    pub fn image(&self) -> PathBuf {
+        Path::new("/")
+            .join(MODULES_DIR)
+            .join(&self.version)
+            .join("vmlinuz")
+    }
+
+    /// Where the install hook copies [`Kernel::image`] for the bootloader.
+    pub fn boot_image(&self) -> PathBuf {
+        PathBuf::from(format!("/boot/vmlinuz-{}", self.package))
+    }
+
+    /// Initramfs image the bootloader loads with the kernel.
+    pub fn initramfs(&self) -> PathBuf {
+        PathBuf::from(format!("/boot/initramfs-{}.img", self.package))
+    }
+}
+
+/// Kernels under `root` with an image and the package name in their module
+/// directory, sorted by package. Directories left behind by removed
+/// kernels hold neither.
+pub fn installed_kernels_in(root: &Path) -> Vec<Kernel> {
+    let mut kernels: Vec<Kernel> = std::fs::read_dir(root.join(MODULES_DIR))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().join("vmlinuz").is_file())
+        .filter_map(|entry| {
+            let package = std::fs::read_to_string(entry.path().join("pkgbase")).ok()?;
+            Some(Kernel {
+                package: package.trim().to_string(),
+                version: entry.file_name().to_str()?.to_string(),
+            })
+        })
+        .filter(|kernel| !kernel.package.is_empty())
+        .collect();
+    kernels.sort_by(|a, b| a.package.cmp(&b.package));
+    kernels
+}
+
+/// Kernels installed on this system.
+pub fn installed_kernels() -> Vec<Kernel> {
+    installed_kernels_in(Path::new("/"))
+}
+
+/// Programs and arguments rebuilding the initramfs images of `kernels`.
+///
+/// `mkinitcpio -P` builds every preset at once; dracut has no presets, so
+/// it builds one image per kernel where the Arch hooks put it.
+pub fn rebuild_initramfs(
+    generator: InitramfsGenerator,
+    kernels: &[Kernel],
+) -> Vec<(&'static str, Vec<String>)> {
+    match generator {
+        InitramfsGenerator::Mkinitcpio => vec![("mkinitcpio", vec!["-P".to_string()])],
+        InitramfsGenerator::Dracut => kernels
+            .iter()
+            .map(|kernel| {
+                (
+                    "dracut",
+                    vec![
+                        "--force".to_string(),
+                        "--kver".to_string(),
+                        kernel.version.clone(),
+                        kernel.initramfs().to_string_lossy().into_owned(),
+                    ],
+                )
+            })
+            .collect(),
+    }
+}
+
+/// Program and arguments regenerating the boot menu of `loader`, `None`
+/// when it reads its entries directly, like systemd-boot.
+pub fn regenerate_menu(loader: &Bootloader) -> Option<(&'static str, Vec<String>)> {
+    match loader {
+        Bootloader::Grub => Some((
+            "grub-mkconfig",
+            vec!["-o".to_string(), GRUB_CONFIG.to_string()],
+        )),
+        Bootloader::SystemdBoot { .. } => None,
+    }
+}
+
+/// How a config file separates keys from values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Syntax {
+    /// `KEY="value"`, sourced by a shell
+    Shell,
+    /// `key value`, as in systemd-boot files
+    Spaced,
+}
+
+/// A config file edited line by line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigFile {
+    lines: Vec<String>,
+    syntax: Syntax,
+}
+
+impl ConfigFile {
+    /// Parse `/etc/default/grub`.
+    pub fn grub(content: &str) -> Self {
+        Self::parse(content, Syntax::Shell)
+    }
+
+    /// Parse `loader.conf` or a boot entry.
+    pub fn systemd_boot(content: &str) -> Self {
+        Self::parse(content, Syntax::Spaced)
+    }
+
+    fn parse(content: &str, syntax: Syntax) -> Self {
+        Self {
+            lines: content.split('\n').map(str::to_string).collect(),
+            syntax,
+        }
+    }
+
+    /// The file contents; unchanged files render exactly as parsed.
+    pub fn render(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Index of the last line setting `key`; later lines win for both
+    /// GRUB's shell and systemd-boot.
+    fn find(&self, key: &str) -> Option<usize> {
+        self.lines
+            .iter()
+            .rposition(|line| self.value_of(line, key).is_some())
+    }
+
+    /// The raw value text if `line` sets `key`.
+    fn value_of<'a>(&self, line: &'a str, key: &str) -> Option<&'a str> {
+        let line = line.trim_start();
+        match self.syntax {
+            Syntax::Shell => line.strip_prefix(key)?.strip_prefix('='),
+            Syntax::Spaced => {
+                let rest = line.strip_prefix(key)?;
+                if rest.is_empty() {
+                    Some(rest)
+                } else if rest.starts_with(char::is_whitespace) {
+                    Some(rest.trim())
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Value of `key`, unquoted.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let raw = self.value_of(&self.lines[self.find(key)?], key)?;
+        Some(match self.syntax {
+            Syntax::Shell => unquote(raw),
+            Syntax::Spaced => raw.to_string(),
+        })
+    }
+
+    /// Set `key` to `value`, replacing its last definition or appending one.
+    pub fn set(&mut self, key: &str, value: &str) {
+        let Some(index) = self.find(key) else {
+            let line = self.format(key, value, " ");
+            // Before the final newline, if the file ends with one
+            match self.lines.last() {
+                Some(last) if last.is_empty() && self.lines.len() > 1 => {
+                    self.lines.insert(self.lines.len() - 1, line)
+                }
+                _ => self.lines.push(line),
+            }
+            return;
+        };
+
+        let line = &self.lines[index];
+        let indent = &line[..line.len() - line.trim_start().len()];
+        // Keep the column alignment of systemd-boot files
+        let gap = line
+            .trim_start()
+            .strip_prefix(key)
+            .map(|rest| &rest[..rest.len() - rest.trim_start().len()])
+            .filter(|gap| !gap.is_empty())
+            .unwrap_or(" ");
+        self.lines[index] = format!("{}{}", indent, self.format(key, value, gap));
+    }
+
+    fn format(&self, key: &str, value: &str, gap: &str) -> String {
+        match self.syntax {
+            Syntax::Shell => format!("{}={}", key, shell_quote(value)),
+            Syntax::Spaced => format!("{}{}{}", key, gap, value),
+        }
+    }
+}
+
+/// Value of a shell assignment: quotes removed, escapes resolved and a
+/// trailing comment dropped.
+fn unquote(raw: &str) -> String {
+    let mut value = String::new();
+    let mut chars = raw.chars();
+    let mut quote = None;
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, c) if c.is_whitespace() => break,
+            (Some('"') | None, '\\') => {
+                if let Some(next) = chars.next() {
+                    value.push(next);
+                }
+            }
+            _ => value.push(c),
+        }
+    }
+    value
+}
+
+/// `value` ready for a shell assignment, quoted unless plain.
+fn shell_quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-/:=,+@".contains(c));
+    if plain {
+        return value.to_string();
+    }
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '$' | '`') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Split a kernel command line into parameters, keeping quoted values whole.
+pub fn split_params(cmdline: &str) -> Vec<String> {
+    let mut params = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in cmdline.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    params.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        params.push(current);
+    }
+    params
+}
+
+/// Name of a parameter, without its value.
+fn param_name(param: &str) -> &str {
+    param.split_once('=').map_or(param, |(name, _)| name)
+}
+
+/// Turn `param` on or off in `params`.
+///
+/// Turning it on replaces other values of the same parameter, so toggling
+/// `amd_pstate=active` drops `amd_pstate=passive`.
+pub fn set_param(params: &mut Vec<String>, param: &str, enabled: bool) {
+    let name = param_name(param);
+    if enabled {
+        if params.iter().any(|p| p == param) {
+            return;
+        }
+        params.retain(|p| param_name(p) != name);
+        params.push(param.to_string());
+    } else {
+        params.retain(|p| p != param);
+    }
+}
+
+/// Settings shown in the boot options editor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BootSettings {
+    /// Seconds the menu is shown
+    pub timeout: u32,
+    /// GRUB menu entry or systemd-boot entry id
+    pub default_entry: String,
+    pub params: Vec<String>,
+}
+
+/// Check a menu timeout.
+pub fn validate_timeout(timeout: u32) -> Result<()> {
+    if timeout > MAX_TIMEOUT {
+        bail!("Timeout must be at most {} seconds", MAX_TIMEOUT);
+    }
+    Ok(())
+}
+
+/// Check a default entry or kernel parameters for characters the files
+/// cannot hold safely. Double quotes must be balanced, as in
+/// `acpi_osi="!Windows 2020"`.
+pub fn validate_text(value: &str) -> Result<()> {
+    if let Some(c) = value
+        .chars()
+        .find(|c| c.is_control() || matches!(c, '\'' | '\\' | '$' | '`'))
+    {
+        bail!("{:?} is not allowed", c);
+    }
+    if !value.matches('"').count().is_multiple_of(2) {
+        bail!("Unbalanced quote");
+    }
+    Ok(())
+}
+
+/// A file to rewrite with new contents.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+/// The bootloader files behind [`BootSettings`].
+#[derive(Clone, Debug)]
+pub struct BootConfig {
+    pub loader: Bootloader,
+    /// GRUB defaults, or `loader.conf` followed by the entries
+    files: Vec<(PathBuf, ConfigFile)>,
+}
+
+impl BootConfig {
+    /// Read the files of `loader`.
+    pub fn load(loader: Bootloader) -> Result<Self> {
+        let read = |path: &Path| {
+            std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))
+        };
+        let mut files = Vec::new();
+        match &loader {
+            Bootloader::Grub => {
+                let path = PathBuf::from(GRUB_DEFAULTS);
+                files.push((path.clone(), read(&path)?));
+            }
+            Bootloader::SystemdBoot { esp } => {
+                let path = esp.join("loader/loader.conf");
+                files.push((path.clone(), read(&path)?));
+                let dir = esp.join("loader/entries");
+                let mut entries: Vec<PathBuf> = std::fs::read_dir(&dir)
+                    .with_context(|| format!("Failed to read {}", dir.display()))?
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "conf"))
+                    .collect();
+                entries.sort();
+                for path in entries {
+                    files.push((path.clone(), read(&path)?));
+                }
+            }
+        }
+        Ok(Self::from_files(loader, files))
+    }
+
+    /// Config from already read `(path, contents)` pairs, in the order
+    /// [`BootConfig::load`] reads them.
+    pub fn from_files(loader: Bootloader, files: Vec<(PathBuf, String)>) -> Self {
+        let parse = match loader {
+            Bootloader::Grub => ConfigFile::grub,
+            Bootloader::SystemdBoot { .. } => ConfigFile::systemd_boot,
+        };
+        Self {
+            files: files
+                .into_iter()
+                .map(|(path, content)| (path, parse(&content)))
+                .collect(),
+            loader,
+        }
+    }
+
+    fn main_file(&self) -> Option<&ConfigFile> {
+        self.files.first().map(|(_, file)| file)
+    }
+
+    /// The entry whose parameters are shown: the default one if it names a
+    /// file, otherwise the first.
+    fn shown_entry(&self) -> Option<&ConfigFile> {
+        let default = self.main_file()?.get("default");
+        let entries = self.files.get(1..)?;
+        entries
+            .iter()
+            .find(|(path, _)| {
+                default
+                    .as_deref()
+                    .is_some_and(|default| path.file_name().is_some_and(|name| name == default))
+            })
+            .or_else(|| entries.first())
+            .map(|(_, file)| file)
+    }
+
+    /// Current settings.
+    pub fn settings(&self) -> BootSettings {
+        let main = self.main_file();
+        let get = |key| main.and_then(|file| file.get(key));
+        match self.loader {
+            Bootloader::Grub => BootSettings {
+                timeout: get("GRUB_TIMEOUT")
+                    .and_then(|t| t.parse().ok())
+                    .unwrap_or(5),
+                default_entry: get("GRUB_DEFAULT").unwrap_or_else(|| "0".to_string()),
+                params: split_params(&get("GRUB_CMDLINE_LINUX_DEFAULT").unwrap_or_default()),
+            },
+            Bootloader::SystemdBoot { .. } => BootSettings {
+                timeout: get("timeout").and_then(|t| t.parse().ok()).unwrap_or(0),
+                default_entry: get("default").unwrap_or_default(),
+                params: split_params(
+                    &self
+                        .shown_entry()
+                        .and_then(|entry| entry.get("options"))
+                        .unwrap_or_default(),
+                ),
+            },
+        }
+    }
+
+    /// The files to rewrite for `settings`; empty when nothing changed.
+    ///
+    /// Parameters added or removed relative to the current settings are
+    /// applied to every systemd-boot entry, leaving parameters that differ
+    /// between entries, like those of a fallback entry, alone.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a setting fails validation.
+    pub fn changes(&self, settings: &BootSettings) -> Result<Vec<FileChange>> {
+        validate_timeout(settings.timeout)?;
+        validate_text(&settings.default_entry)?;
+        for param in &settings.params {
+            validate_text(param).with_context(|| format!("Invalid parameter {}", param))?;
+        }
+
+        let current = self.settings();
+        let mut files = self.files.clone();
+        match self.loader {
+            Bootloader::Grub => {
+                let Some((_, grub)) = files.first_mut() else {
+                    return Ok(Vec::new());
+                };
+                if settings.timeout != current.timeout {
+                    grub.set("GRUB_TIMEOUT", &settings.timeout.to_string());
+                }
+                if settings.default_entry != current.default_entry {
+                    grub.set("GRUB_DEFAULT", &settings.default_entry);
+                }
+                if settings.params != current.params {
+                    grub.set("GRUB_CMDLINE_LINUX_DEFAULT", &settings.params.join(" "));
+                }
+            }
+            Bootloader::SystemdBoot { .. } => {
+                let Some(((_, loader_conf), entries)) = files.split_first_mut() else {
+                    return Ok(Vec::new());
+                };
+                if settings.timeout != current.timeout {
+                    loader_conf.set("timeout", &settings.timeout.to_string());
+                }
+                if settings.default_entry != current.default_entry {
+                    loader_conf.set("default", &settings.default_entry);
+                }
+                let removed: Vec<&String> = current
+                    .params
+                    .iter()
+                    .filter(|p| !settings.params.contains(p))
+                    .collect();
+                let added: Vec<&String> = settings
+                    .params
+                    .iter()
+                    .filter(|p| !current.params.contains(p))
+                    .collect();
+                for (_, entry) in entries {
+                    let Some(options) = entry.get("options") else {
+                        continue;
+                    };
+                    let mut params = split_params(&options);
+                    params.retain(|p| !removed.contains(&p));
+                    for param in &added {
+                        set_param(&mut params, param, true);
+                    }
+                    if params != split_params(&options) {
+                        entry.set("options", &params.join(" "));
+                    }
+                }
+            }
+        }
+
+        Ok(files
+            .into_iter()
+            .zip(&self.files)
+            .filter(|((_, new), (_, old))| new != old)
+            .map(|((path, file), _)| FileChange {
+                path,
+                content: file.render(),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRUB: &str = include_str!("../../tests/fixtures/boot/grub");
+    const LOADER: &str = include_str!("../../tests/fixtures/boot/loader.conf");
+    const ENTRY: &str = include_str!("../../tests/fixtures/boot/arch.conf");
+    const FALLBACK: &str = include_str!("../../tests/fixtures/boot/arch-fallback.conf");
+
+    fn systemd_boot() -> BootConfig {
+        BootConfig::from_files(
+            Bootloader::SystemdBoot {
+                esp: PathBuf::from("/boot"),
+            },
+            vec![
+                (
+                    PathBuf::from("/boot/loader/loader.conf"),
+                    LOADER.to_string(),
+                ),
+                (
+                    PathBuf::from("/boot/loader/entries/arch-fallback.conf"),
+                    FALLBACK.to_string(),
+                ),
+                (
+                    PathBuf::from("/boot/loader/entries/arch.conf"),
+                    ENTRY.to_string(),
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for content in [GRUB, LOADER, ENTRY, FALLBACK] {
+            assert_eq!(ConfigFile::grub(content).render(), content);
+            assert_eq!(ConfigFile::systemd_boot(content).render(), content);
+        }
+    }
+
+    #[test]
+    fn test_grub_get_and_set() {
+        let mut grub = ConfigFile::grub(GRUB);
+        assert_eq!(grub.get("GRUB_TIMEOUT").as_deref(), Some("5"));
+        assert_eq!(grub.get("GRUB_DISTRIBUTOR").as_deref(), Some("Arch"));
+        assert_eq!(
+            grub.get("GRUB_CMDLINE_LINUX_DEFAULT").as_deref(),
+            Some("loglevel=3 quiet")
+        );
+        // Commented out settings are not set
+        assert_eq!(grub.get("GRUB_SAVEDEFAULT"), None);
+        // Not to be confused with GRUB_TIMEOUT
+        assert_eq!(grub.get("GRUB_TIMEOUT_STYLE").as_deref(), Some("menu"));
+
+        grub.set("GRUB_TIMEOUT", "2");
+        grub.set("GRUB_CMDLINE_LINUX_DEFAULT", "loglevel=3 quiet splash");
+        grub.set("GRUB_SAVEDEFAULT", "true");
+        let rendered = grub.render();
+        assert!(rendered.contains("\nGRUB_TIMEOUT=2\n"));
+        assert!(rendered.contains("\nGRUB_CMDLINE_LINUX_DEFAULT=\"loglevel=3 quiet splash\"\n"));
+        assert!(rendered.contains("\n#GRUB_SAVEDEFAULT=true\n"));
+        assert!(rendered.ends_with("GRUB_DISABLE_OS_PROBER=false\nGRUB_SAVEDEFAULT=true\n"));
+        // Everything else is untouched
+        assert_eq!(rendered.lines().count(), GRUB.lines().count() + 1);
+
+        let reparsed = ConfigFile::grub(&rendered);
+        assert_eq!(reparsed.get("GRUB_TIMEOUT").as_deref(), Some("2"));
+        assert_eq!(
+            reparsed.get("GRUB_CMDLINE_LINUX_DEFAULT").as_deref(),
+            Some("loglevel=3 quiet splash")
+        );
+    }
+
+    #[test]
+    fn test_shell_quoting() {
+        assert_eq!(unquote("\"a b\" # comment"), "a b");
+        assert_eq!(unquote("'a \"b\"'"), "a \"b\"");
+        assert_eq!(unquote("saved"), "saved");
+        assert_eq!(unquote("\"\""), "");
+        assert_eq!(shell_quote("0"), "0");
+        assert_eq!(shell_quote(""), "\"\"");
+        assert_eq!(
+            shell_quote("Advanced options>Arch $x"),
+            "\"Advanced options>Arch \\$x\""
+        );
+        assert_eq!(unquote(&shell_quote("a \"b\" $c")), "a \"b\" $c");
+    }
+
+    #[test]
+    fn test_systemd_boot_files() {
+        let mut loader = ConfigFile::systemd_boot(LOADER);
+        assert_eq!(loader.get("default").as_deref(), Some("arch.conf"));
+        assert_eq!(loader.get("timeout").as_deref(), Some("4"));
+        assert_eq!(loader.get("console-mode").as_deref(), Some("max"));
+        loader.set("timeout", "0");
+        assert_eq!(
+            loader.render(),
+            "default  arch.conf\ntimeout  0\nconsole-mode max\neditor   no\n"
+        );
+    }
+
+    #[test]
+    fn test_params() {
+        let mut params = split_params("root=UUID=x rw quiet acpi_osi=\"!Windows 2020\"");
+        assert_eq!(
+            params,
+            ["root=UUID=x", "rw", "quiet", "acpi_osi=\"!Windows 2020\""]
+        );
+
+        set_param(&mut params, "quiet", true);
+        assert_eq!(params.len(), 4);
+        set_param(&mut params, "amd_pstate=passive", true);
+        set_param(&mut params, "amd_pstate=active", true);
+        assert_eq!(params.last().map(String::as_str), Some("amd_pstate=active"));
+        assert!(!params.iter().any(|p| p == "amd_pstate=passive"));
+        set_param(&mut params, "quiet", false);
+        assert!(!params.iter().any(|p| p == "quiet"));
+    }
+
+    #[test]
+    fn test_grub_changes() {
+        let config = BootConfig::from_files(
+            Bootloader::Grub,
+            vec![(PathBuf::from(GRUB_DEFAULTS), GRUB.to_string())],
+        );
+        let mut settings = config.settings();
+        assert_eq!(
+            settings,
+            BootSettings {
+                timeout: 5,
+                default_entry: "0".to_string(),
+                params: vec!["loglevel=3".to_string(), "quiet".to_string()],
+            }
+        );
+        assert!(config.changes(&settings).unwrap().is_empty());
+
+        settings.timeout = 1;
+        set_param(&mut settings.params, "nvidia-drm.modeset=1", true);
+        let changes = config.changes(&settings).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, PathBuf::from(GRUB_DEFAULTS));
+        let expected = GRUB
+            .replace("GRUB_TIMEOUT=5\n", "GRUB_TIMEOUT=1\n")
+            .replace(
+                "GRUB_CMDLINE_LINUX_DEFAULT=\"loglevel=3 quiet\"",
+                "GRUB_CMDLINE_LINUX_DEFAULT=\"loglevel=3 quiet nvidia-drm.modeset=1\"",
+            );
+        assert_eq!(changes[0].content, expected);
+
+        settings.timeout = MAX_TIMEOUT + 1;
+        assert!(config.changes(&settings).is_err());
+        settings.timeout = 1;
+        settings.params.push("$(reboot)".to_string());
+        assert!(config.changes(&settings).is_err());
+        settings.params.pop();
+        settings.params.push("acpi_osi=\"!Windows".to_string());
+        assert!(config.changes(&settings).is_err());
+        settings.params.pop();
+        settings
+            .params
+            .push("acpi_osi=\"!Windows 2020\"".to_string());
+        let changes = config.changes(&settings).unwrap();
+        let grub = ConfigFile::grub(&changes[0].content);
+        assert_eq!(
+            grub.get("GRUB_CMDLINE_LINUX_DEFAULT").as_deref(),
+            Some("loglevel=3 quiet nvidia-drm.modeset=1 acpi_osi=\"!Windows 2020\"")
+        );
+    }
+
+    #[test]
+    fn test_systemd_boot_changes() {
+        let config = systemd_boot();
+        let mut settings = config.settings();
+        // Shows the default entry, not the first one
+        assert_eq!(settings.timeout, 4);
+        assert_eq!(settings.default_entry, "arch.conf");
+        assert!(settings.params.iter().any(|p| p == "nvidia-drm.modeset=1"));
+        assert!(config.changes(&settings).unwrap().is_empty());
+
+        set_param(&mut settings.params, "splash", false);
+        set_param(&mut settings.params, "nowatchdog", true);
+        let changes = config.changes(&settings).unwrap();
+        let paths: Vec<_> = changes.iter().map(|c| c.path.clone()).collect();
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("/boot/loader/entries/arch-fallback.conf"),
+                PathBuf::from("/boot/loader/entries/arch.conf"),
+            ]
+        );
+        // The fallback keeps its own parameters and gains the new one
+        assert!(changes[0].content.ends_with(
+            "options root=UUID=0a3407de-014b-458b-b5c1-848e92a327a3 rw rootflags=subvol=@ nowatchdog\n"
+        ));
+        assert!(changes[1].content.ends_with(
+            "options root=UUID=0a3407de-014b-458b-b5c1-848e92a327a3 rw rootflags=subvol=@ nvidia-drm.modeset=1 quiet nowatchdog\n"
+        ));
+        assert!(changes[1].content.starts_with("title   Arch Linux\n"));
+
+        settings.timeout = 0;
+        let changes = config.changes(&settings).unwrap();
+        assert_eq!(changes[0].path, PathBuf::from("/boot/loader/loader.conf"));
+        assert!(changes[0].content.contains("\ntimeout  0\n"));
+    }
+
+    /// Create `files` under a fresh directory named after `name`.
+    fn root_with(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("xero-boot-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for (path, content) in files {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_detect_initramfs() {
+        // Name, files and the generator expected from them
+        type Case = (
+            &'static str,
+            &'static [(&'static str, &'static str)],
+            Option<InitramfsGenerator>,
+        );
+        let cases: &[Case] = &[
+            ("empty", &[], None),
+            (
+                "mkinitcpio",
+                &[
+                    (MKINITCPIO_CONFIG, "HOOKS=(base udev)\n"),
+                    ("etc/mkinitcpio.d/linux.preset", "PRESETS=('default')\n"),
+                ],
+                Some(InitramfsGenerator::Mkinitcpio),
+            ),
+            (
+                "mkinitcpio-unused",
+                &[
+                    (MKINITCPIO_CONFIG, "HOOKS=(base udev)\n"),
+                    ("etc/dracut.conf.d/eos-defaults.conf", "hostonly=\"yes\"\n"),
+                ],
+                Some(InitramfsGenerator::Dracut),
+            ),
+            (
+                "dracut",
+                &[(DRACUT_CONFIG, "")],
+                Some(InitramfsGenerator::Dracut),
+            ),
+            (
+                "both",
+                &[
+                    ("etc/mkinitcpio.d/linux-lts.preset", ""),
+                    ("etc/dracut.conf.d/extra.conf", ""),
+                ],
+                Some(InitramfsGenerator::Mkinitcpio),
+            ),
+            (
+                "config-only",
+                &[(MKINITCPIO_CONFIG, ""), ("etc/mkinitcpio.d/README", "")],
+                Some(InitramfsGenerator::Mkinitcpio),
+            ),
+        ];
+        for (name, files, expected) in cases {
+            let root = root_with(name, files);
+            assert_eq!(detect_initramfs_in(&root), *expected, "{}", name);
+            std::fs::remove_dir_all(root).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_installed_kernels() {
+        let root = root_with(
+            "kernels",
+            &[
+                ("usr/lib/modules/6.9.1-arch1-1/vmlinuz", ""),
+                ("usr/lib/modules/6.9.1-arch1-1/pkgbase", "linux\n"),
+                ("usr/lib/modules/6.6.30-1-lts/vmlinuz", ""),
+                ("usr/lib/modules/6.6.30-1-lts/pkgbase", "linux-lts\n"),
+                // Left behind by a removed kernel
+                ("usr/lib/modules/6.8.9-arch1-2/extramodules/nvidia.ko", ""),
+                ("usr/lib/modules/6.8.9-arch1-2/pkgbase", "linux\n"),
+            ],
+        );
+        let kernels = installed_kernels_in(&root);
+        std::fs::remove_dir_all(root).unwrap();
+
+        assert_eq!(
+            kernels,
+            [
+                Kernel {
+                    package: "linux".to_string(),
+                    version: "6.9.1-arch1-1".to_string(),
+                },
+                Kernel {
+                    package: "linux-lts".to_string(),
+                    version: "6.6.30-1-lts".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            kernels[1].image(),
+            PathBuf::from("/usr/lib/modules/6.6.30-1-lts/vmlinuz")
+        );
+        assert_eq!(
+            kernels[1].boot_image(),
+            PathBuf::from("/boot/vmlinuz-linux-lts")
+        );
+
+        assert_eq!(
+            rebuild_initramfs(InitramfsGenerator::Mkinitcpio, &kernels),
+            [("mkinitcpio", vec!["-P".to_string()])]
+        );
+        let dracut = rebuild_initramfs(InitramfsGenerator::Dracut, &kernels);
+        assert_eq!(dracut.len(), 2);
+        assert_eq!(
+            dracut[0].1,
+            [
+                "--force",
+                "--kver",
+                "6.9.1-arch1-1",
+                "/boot/initramfs-linux.img"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_regenerate_menu() {
+        assert_eq!(
+            regenerate_menu(&Bootloader::Grub),
+            Some((
+                "grub-mkconfig",
+                vec!["-o".to_string(), GRUB_CONFIG.to_string()]
+            ))
+        );
+        let esp = PathBuf::from("/efi");
+        assert_eq!(regenerate_menu(&Bootloader::SystemdBoot { esp }), None);
+    }
+}
@@ -0,0 +1,197 @@
+//! Crash reports written by the panic hook.
+//!
+//! The hook only writes a file: GTK may be unusable at panic time, so the
+//! report is shown to the user on the next start instead.
+
+use crate::config;
+use crate::core::logs;
+use crate::core::sysinfo;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Number of log lines included in a report.
+const LOG_TAIL_LINES: usize = 100;
+
+const REPORT_PREFIX: &str = "crash-";
+const REPORT_EXTENSION: &str = "txt";
+/// Extension a report is renamed to once it was shown.
+const REPORTED_EXTENSION: &str = "reported.txt";
+
+const NEW_ISSUE_URL: &str = "https://github.com/synsejse/xero-toolkit/issues/new";
+
+/// Install a panic hook that writes a crash report before the default hook runs.
+pub fn install_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info.payload_as_str().unwrap_or("Box<dyn Any>");
+        let location = info
+            .location()
+            .map(|location| location.to_string())
+            .unwrap_or_default();
+        let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+        let log_tail = logs::read_log()
+            .map(|log| tail_lines(&log, LOG_TAIL_LINES).to_string())
+            .unwrap_or_default();
+
+        let report = format_report(message, &location, &backtrace, &log_tail);
+        match write_report(&report) {
+            Some(path) => eprintln!("Crash report written to {}", path.display()),
+            None => eprintln!("Failed to write crash report"),
+        }
+
+        default_hook(info);
+    }));
+}
+
+/// Directory crash reports are written to, next to the log file.
+fn report_dir() -> Option<PathBuf> {
+    logs::log_path().parent().map(Path::to_path_buf)
+}
+
+fn write_report(report: &str) -> Option<PathBuf> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let dir = report_dir()?;
+    std::fs::create_dir_all(&dir).ok()?;
+
+    let path = dir.join(format!("{REPORT_PREFIX}{timestamp}.{REPORT_EXTENSION}"));
+    std::fs::write(&path, report).ok()?;
+    Some(path)
+}
+
+/// Assemble the report text.
+pub fn format_report(message: &str, location: &str, backtrace: &str, log_tail: &str) -> String {
+    let mut report = String::new();
+    let _ = writeln!(report, "{}", sysinfo::heading("crash report"));
+    let _ = writeln!(report, "Message: {message}");
+    let _ = writeln!(report, "Location: {location}");
+    let _ = writeln!(report, "\n--- Backtrace ---\n{}", backtrace.trim_end());
+    let _ = writeln!(report, "\n--- Last log lines ---\n{}", log_tail.trim_end());
+    report
+}
+
+/// The last `count` lines of `content`.
+pub fn tail_lines(content: &str, count: usize) -> &str {
+    let content = content.trim_end_matches('\n');
+    match content.rmatch_indices('\n').nth(count.saturating_sub(1)) {
+        Some((index, _)) if count > 0 => &content[index + 1..],
+        _ if count > 0 => content,
+        _ => "",
+    }
+}
+
+/// The panic message recorded in a report.
+pub fn report_message(report: &str) -> Option<&str> {
+    report
+        .lines()
+        .find_map(|line| line.strip_prefix("Message: "))
+}
+
+/// The newest report that has not been shown yet.
+pub fn latest_unreported() -> Option<PathBuf> {
+    let entries = std::fs::read_dir(report_dir()?).ok()?;
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| is_unreported(path))
+        .max()
+}
+
+fn is_unreported(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let Some(stem) = name
+        .strip_prefix(REPORT_PREFIX)
+        .and_then(|rest| rest.strip_suffix(&format!(".{REPORT_EXTENSION}")))
+    else {
+        return false;
+    };
+    !stem.is_empty() && stem.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Mark every pending report as shown so it is not offered again.
+pub fn mark_all_reported() {
+    let Some(entries) = report_dir().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return;
+    };
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        if is_unreported(&path) {
+            let reported = path.with_extension(REPORTED_EXTENSION);
+            if let Err(e) = std::fs::rename(&path, &reported) {
+                log::warn!("Failed to mark {} as reported: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// URL opening a prefilled GitHub issue for a crash.
+pub fn issue_url(message: &str) -> String {
+    let title = format!("Crash: {message}");
+    let body = format!(
+        "Version: {}\n\nPanic message:\n```\n{}\n```\n\n\
+         Please attach or paste the crash report from ~/.cache/{}/ \
+         and describe what you were doing.",
+        config::app_info::VERSION,
+        message,
+        config::app_info::NAME
+    );
+    format!(
+        "{NEW_ISSUE_URL}?title={}&body={}",
+        percent_encode(&title),
+        percent_encode(&body)
+    )
+}
+
+/// Percent-encode everything except RFC 3986 unreserved characters.
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => {
+                let _ = write!(encoded, "%{byte:02X}");
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tail_lines() {
+        let log = "one\ntwo\nthree\nfour\n";
+        assert_eq!(tail_lines(log, 2), "three\nfour");
+        assert_eq!(tail_lines(log, 10), "one\ntwo\nthree\nfour");
+        assert_eq!(tail_lines(log, 0), "");
+    }
+
+    #[test]
+    fn test_report_round_trip() {
+        let report = format_report("index out of bounds", "src/main.rs:1:1", "bt", "log");
+        assert_eq!(report_message(&report), Some("index out of bounds"));
+        assert!(report.contains(config::app_info::VERSION));
+        assert!(report.contains("--- Last log lines ---\nlog"));
+    }
+
+    #[test]
+    fn test_unreported_file_names() {
+        assert!(is_unreported(Path::new("/tmp/crash-1760000000.txt")));
+        assert!(!is_unreported(Path::new(
+            "/tmp/crash-1760000000.reported.txt"
+        )));
+        assert!(!is_unreported(Path::new("/tmp/app.log")));
+    }
+
+    #[test]
+    fn test_issue_url_is_encoded() {
+        let url = issue_url("a & b");
+        assert!(url.starts_with(NEW_ISSUE_URL));
+        assert!(url.contains("title=Crash%3A%20a%20%26%20b"));
+        assert!(!url.contains(' '));
+    }
+}
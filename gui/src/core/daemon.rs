@@ -1,12 +1,26 @@
 //! Daemon management for xero-auth.
 
 use crate::config;
-use anyhow::{Context, Result};
+use crate::i18n::{fill, gettext};
+use anyhow::Result;
 use log::{info, warn};
+use std::fmt;
+use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
-use std::process::{Command, Stdio};
-use std::time::Duration;
-use xero_auth::shared::is_daemon_running;
+use std::process::{ChildStderr, Command, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use xero_auth::shared::{is_daemon_running, is_daemon_running_async, is_systemd_socket_active};
+use xero_auth::WaitError;
+
+/// How long to wait for the password prompt and the daemon to start.
+const START_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// pkexec exit code when the password prompt was dismissed.
+const PKEXEC_DISMISSED: i32 = 126;
+
+/// pkexec exit code when the user is not authorized.
+const PKEXEC_NOT_AUTHORIZED: i32 = 127;
 
 /// Get the path to the xero-authd daemon binary.
 fn get_daemon_path() -> PathBuf {
@@ -18,9 +32,63 @@ pub fn get_xero_auth_path() -> PathBuf {
     config::paths::client()
 }
 
+/// Lines of pkexec's stderr kept to explain a failed start.
+const STDERR_LINES: usize = 20;
+
+/// Why the daemon did not start.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartError {
+    /// The password prompt was dismissed or timed out
+    Cancelled,
+    /// polkit did not authorize the user
+    NotAuthorized,
+    /// No polkit agent is running to show the password prompt
+    NoAgent,
+    /// The daemon did not listen within [`START_TIMEOUT`]
+    TimedOut,
+    /// Anything else, with its user-facing explanation
+    Failed(String),
+}
+
+impl StartError {
+    /// Whether authenticating again may get the daemon started. Not after
+    /// a timeout, where the first prompt may still be open.
+    pub fn retryable(&self) -> bool {
+        matches!(self, Self::Cancelled)
+    }
+}
+
+impl fmt::Display for StartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cancelled => f.write_str(&gettext("Authentication was cancelled.")),
+            Self::NotAuthorized => f.write_str(&gettext(
+                "You are not authorized to run privileged operations.",
+            )),
+            Self::NoAgent => f.write_str(&gettext(
+                "No authentication agent is running to ask for your password.",
+            )),
+            Self::TimedOut => f.write_str(&fill(
+                &gettext("The authentication daemon did not start within {} seconds."),
+                &[&START_TIMEOUT.as_secs().to_string()],
+            )),
+            Self::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for StartError {}
+
 /// Start the daemon.
 /// Returns Ok(()) if daemon is already running or started successfully.
-pub fn start_daemon() -> Result<()> {
+///
+/// A daemon systemd runs for the system is used when the user may connect
+/// to its socket, so no password is asked for.
+pub fn start_daemon() -> Result<(), StartError> {
+    if is_systemd_socket_active() {
+        info!("Using the daemon started by systemd");
+        return Ok(());
+    }
     if is_daemon_running() {
         info!("Daemon is already running");
         return Ok(());
@@ -38,42 +106,137 @@ pub fn start_daemon() -> Result<()> {
         .arg("--parent-pid")
         .arg(current_pid.to_string())
         .stdout(Stdio::null())
-        .stderr(Stdio::null())
+        .stderr(Stdio::piped())
         .spawn()
-        .context("Failed to spawn pkexec")?;
+        .map_err(|e| {
+            StartError::Failed(fill(
+                &gettext("Failed to run pkexec: {}"),
+                &[&e.to_string()],
+            ))
+        })?;
+    let stderr = child.stderr.take().map(drain_stderr);
 
-    let socket_path = xero_auth::shared::get_socket_path(None)?;
-    let start = std::time::Instant::now();
-    let timeout = Duration::from_secs(60);
-    let poll_interval = Duration::from_millis(50);
-
-    loop {
-        if socket_path.exists() {
+    // Answered once the new daemon listens, not by a stale socket file
+    match xero_auth::wait_for_socket(Instant::now() + START_TIMEOUT, Some(&mut child)) {
+        Ok(()) => {
             info!("Daemon started successfully");
-            return Ok(());
+            Ok(())
+        }
+        Err(WaitError::DaemonExited(status)) => {
+            // pkexec is gone, so its stderr is complete
+            let stderr = stderr
+                .and_then(|reader| reader.join().ok())
+                .unwrap_or_default();
+            warn!("pkexec exited with {}: {}", status, stderr.trim());
+            Err(pkexec_failure(status.code(), &stderr))
+        }
+        Err(e) => {
+            warn!("Daemon did not start: {}", e);
+            Err(wait_failure(&e))
         }
+    }
+}
 
-        // Check if pkexec has exited (including zombie state)
-        if let Ok(Some(_status)) = child.try_wait() {
-            anyhow::bail!("pkexec process has exited (may have been cancelled)");
+/// Log the daemon's stderr on a thread, so it never blocks on a full pipe,
+/// returning the first lines once it closes.
+fn drain_stderr(stderr: ChildStderr) -> JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut kept = String::new();
+        for (index, line) in BufReader::new(stderr)
+            .lines()
+            .map_while(|line| line.ok())
+            .enumerate()
+        {
+            warn!("xero-authd: {}", line);
+            if index < STDERR_LINES {
+                kept.push_str(&line);
+                kept.push('\n');
+            }
         }
+        kept
+    })
+}
 
-        if start.elapsed() >= timeout {
-            anyhow::bail!(
-                "Daemon socket not found after starting within {:?} at {:?}",
-                timeout,
-                socket_path
-            );
+/// Why pkexec, exiting with `code` and `stderr`, did not start the daemon.
+pub fn pkexec_failure(code: Option<i32>, stderr: &str) -> StartError {
+    match code {
+        Some(PKEXEC_DISMISSED) => StartError::Cancelled,
+        // pkexec reports a missing agent with the same code as a refusal
+        Some(PKEXEC_NOT_AUTHORIZED) if stderr.contains("No authentication agent") => {
+            StartError::NoAgent
+        }
+        Some(PKEXEC_NOT_AUTHORIZED) => StartError::NotAuthorized,
+        Some(code) => {
+            let code = code.to_string();
+            let reason = stderr.lines().map(str::trim).rfind(|line| !line.is_empty());
+            StartError::Failed(match reason {
+                Some(reason) => fill(
+                    &gettext("The authentication daemon exited while starting (exit code {}): {}"),
+                    &[&code, reason],
+                ),
+                None => fill(
+                    &gettext("The authentication daemon exited while starting (exit code {})."),
+                    &[&code],
+                ),
+            })
         }
+        None => StartError::Failed(gettext(
+            "The authentication daemon was stopped while starting.",
+        )),
+    }
+}
 
-        std::thread::sleep(poll_interval);
+/// Why waiting for the daemon failed, other than it exiting.
+fn wait_failure(error: &WaitError) -> StartError {
+    match error {
+        WaitError::Timeout(_) => StartError::TimedOut,
+        WaitError::PermissionDenied(path) => StartError::Failed(fill(
+            &gettext("Permission denied on {}. Check the permissions of your runtime directory."),
+            &[&path.display().to_string()],
+        )),
+        WaitError::DaemonExited(status) => pkexec_failure(status.code(), ""),
+        WaitError::Io(e) => StartError::Failed(fill(
+            &gettext("The authentication daemon socket could not be checked: {}"),
+            &[&e.to_string()],
+        )),
     }
 }
 
+/// Terminate every command the daemon is running.
+///
+/// Returns how many commands were signalled, 0 if no daemon is running.
+pub async fn cancel_commands() -> Result<u32> {
+    use xero_auth::Client;
+
+    if !is_daemon_running_async().await {
+        return Ok(0);
+    }
+    let mut client = Client::new().await?;
+    client.cancel().await
+}
+
+/// Counters of the commands the daemon ran this session, `None` if no
+/// daemon is running.
+pub async fn session_stats() -> Result<Option<xero_auth::protocol::SessionStats>> {
+    use xero_auth::Client;
+
+    if !is_daemon_running_async().await {
+        return Ok(None);
+    }
+    let mut client = Client::new().await?;
+    Ok(Some(client.status().await?))
+}
+
 pub async fn stop_daemon() -> Result<()> {
     use xero_auth::Client;
 
-    if is_daemon_running() {
+    // systemd owns that daemon and would start it again on the next
+    // connection anyway
+    if is_systemd_socket_active() {
+        return Ok(());
+    }
+
+    if is_daemon_running_async().await {
         if let Ok(mut client) = Client::new().await {
             if let Err(e) = client.shutdown().await {
                 warn!("Failed to shutdown daemon: {}", e);
@@ -85,3 +248,45 @@ pub async fn stop_daemon() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pkexec_failure() {
+        let dismissed = "Error executing command as another user: Request dismissed\n";
+        assert_eq!(pkexec_failure(Some(126), dismissed), StartError::Cancelled);
+        assert!(StartError::Cancelled.retryable());
+
+        let refused = "Error executing command as another user: Not authorized\n\n\
+                       This incident has been reported.\n";
+        assert_eq!(
+            pkexec_failure(Some(127), refused),
+            StartError::NotAuthorized
+        );
+        assert!(!StartError::NotAuthorized.retryable());
+
+        let no_agent = "Error executing command as another user: No authentication agent found.\n";
+        assert_eq!(pkexec_failure(Some(127), no_agent), StartError::NoAgent);
+    }
+
+    #[test]
+    fn test_other_failures_keep_the_reason() {
+        let StartError::Failed(message) = pkexec_failure(Some(1), "Daemon error: bad uid\n\n")
+        else {
+            panic!("expected a generic failure");
+        };
+        assert!(message.contains("exit code 1"));
+        assert!(message.ends_with("Daemon error: bad uid"));
+
+        let StartError::Failed(message) = pkexec_failure(Some(1), "") else {
+            panic!("expected a generic failure");
+        };
+        assert!(message.ends_with("(exit code 1)."));
+
+        let stopped = pkexec_failure(None, "");
+        assert!(matches!(stopped, StartError::Failed(_)));
+        assert!(!stopped.retryable());
+    }
+}
@@ -0,0 +1,546 @@
+//! Theme and dotfile deployment with backup and restore.
+//!
+//! A [`Manifest`] describes what a theme installs. Before deploying, every
+//! destination is snapshotted into
+//! `~/.local/share/xero-toolkit/backups/<name>/<timestamp>` together with a
+//! `manifest.toml` record, so the previous look can be restored later.
+
+use anyhow::{Context, Result};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+/// File name of the record stored in each backup directory.
+const RECORD_FILE: &str = "manifest.toml";
+
+/// Directory inside a backup holding the snapshotted files.
+const FILES_DIR: &str = "files";
+
+/// A file or directory copied from the source repository.
+#[derive(Debug, Clone, Copy)]
+pub struct FileCopy {
+    /// Path relative to the repository root.
+    pub source: &'static str,
+    /// Absolute destination path; `~/` is expanded to the user's home.
+    pub destination: &'static str,
+}
+
+/// A command run as part of deploying or restoring.
+#[derive(Debug, Clone, Copy)]
+pub struct StepCommand {
+    pub privileged: bool,
+    pub program: &'static str,
+    pub args: &'static [&'static str],
+    pub description: &'static str,
+}
+
+/// Describes what a theme deployment writes.
+#[derive(Debug, Clone, Copy)]
+pub struct Manifest {
+    /// Backup directory name.
+    pub name: &'static str,
+    /// Repository cloned before copying `files`.
+    pub repo: Option<&'static str>,
+    pub files: &'static [FileCopy],
+    /// Additional paths modified by `post_commands`.
+    pub tracked: &'static [&'static str],
+    /// Commands run after copying.
+    pub post_commands: &'static [StepCommand],
+    /// Commands run after a restore, e.g. to regenerate config.
+    pub restore_commands: &'static [StepCommand],
+}
+
+impl Manifest {
+    /// All destinations that are snapshotted before deploying.
+    pub fn destinations(&self, home: &Path) -> Vec<PathBuf> {
+        self.files
+            .iter()
+            .map(|file| file.destination)
+            .chain(self.tracked.iter().copied())
+            .map(|path| expand_home(path, home))
+            .collect()
+    }
+}
+
+/// One step of a deploy or restore plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeployStep {
+    pub privileged: bool,
+    pub program: String,
+    pub args: Vec<String>,
+    pub description: String,
+}
+
+impl From<&StepCommand> for DeployStep {
+    fn from(command: &StepCommand) -> Self {
+        Self {
+            privileged: command.privileged,
+            program: command.program.to_string(),
+            args: command.args.iter().map(|arg| arg.to_string()).collect(),
+            description: command.description.to_string(),
+        }
+    }
+}
+
+/// Owner and permission bits given to what a step writes as root.
+///
+/// Copies made as root keep the owner of their source, which is the user
+/// for cloned repositories and backups, so privileged destinations are
+/// handed back to this owner afterwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ownership {
+    pub uid: u32,
+    pub gid: u32,
+    /// Permission bits; unset for symlinks and for new files.
+    #[serde(default)]
+    pub mode: Option<u32>,
+}
+
+impl Ownership {
+    /// Owned by root, keeping the permissions of the copied files.
+    pub const ROOT: Self = Self {
+        uid: 0,
+        gid: 0,
+        mode: None,
+    };
+
+    fn of(metadata: &fs::Metadata) -> Self {
+        Self {
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            mode: (!metadata.file_type().is_symlink()).then_some(metadata.mode() & 0o7777),
+        }
+    }
+}
+
+impl Default for Ownership {
+    fn default() -> Self {
+        Self::ROOT
+    }
+}
+
+/// A destination captured in a backup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupEntry {
+    pub destination: PathBuf,
+    /// Whether the destination existed; missing ones are removed on restore.
+    pub existed: bool,
+    /// Owner and mode the destination had; records written before these
+    /// were kept read as owned by root.
+    #[serde(default)]
+    pub ownership: Ownership,
+}
+
+/// Record written alongside each backup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupRecord {
+    pub name: String,
+    /// Unix time the backup was taken.
+    pub created: u64,
+    pub entries: Vec<BackupEntry>,
+}
+
+/// A backup found on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub dir: PathBuf,
+    pub record: BackupRecord,
+}
+
+/// Root directory for all deployment backups.
+pub fn backup_root() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from(&crate::config::env::get().home).join(".local/share"))
+        .join("xero-toolkit/backups")
+}
+
+/// Expand a leading `~/` to `home`.
+fn expand_home(path: &str, home: &Path) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home.join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Location of `destination` inside a backup's file tree.
+fn backup_file_path(backup_dir: &Path, destination: &Path) -> PathBuf {
+    let relative = destination.strip_prefix("/").unwrap_or(destination);
+    backup_dir.join(FILES_DIR).join(relative)
+}
+
+/// Copy the current state of `destinations` into `root/<name>/<created>`.
+pub fn snapshot(root: &Path, name: &str, destinations: &[PathBuf], created: u64) -> Result<Backup> {
+    let dir = root.join(name).join(created.to_string());
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let mut entries = Vec::new();
+    for destination in destinations {
+        let metadata = fs::symlink_metadata(destination).ok();
+        if metadata.is_some() {
+            copy_recursive(destination, &backup_file_path(&dir, destination))
+                .with_context(|| format!("Failed to back up {}", destination.display()))?;
+        }
+        entries.push(BackupEntry {
+            destination: destination.clone(),
+            existed: metadata.is_some(),
+            ownership: metadata.as_ref().map(Ownership::of).unwrap_or_default(),
+        });
+    }
+
+    let record = BackupRecord {
+        name: name.to_string(),
+        created,
+        entries,
+    };
+    let toml = toml::to_string_pretty(&record).context("Failed to serialize backup record")?;
+    fs::write(dir.join(RECORD_FILE), toml).context("Failed to write backup record")?;
+
+    info!("Snapshotted {} into {}", name, dir.display());
+    Ok(Backup { dir, record })
+}
+
+/// Newest backup recorded for `name`.
+pub fn latest_backup(root: &Path, name: &str) -> Option<Backup> {
+    fs::read_dir(root.join(name))
+        .ok()?
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let content = fs::read_to_string(dir.join(RECORD_FILE)).ok()?;
+            let record: BackupRecord = toml::from_str(&content).ok()?;
+            Some(Backup { dir, record })
+        })
+        .max_by_key(|backup| backup.record.created)
+}
+
+/// Steps that deploy `manifest` using `staging` as the clone directory.
+pub fn deploy_plan(manifest: &Manifest, staging: &Path, home: &Path) -> Vec<DeployStep> {
+    let mut steps = Vec::new();
+
+    if let (Some(repo), false) = (manifest.repo, manifest.files.is_empty()) {
+        steps.push(DeployStep {
+            privileged: false,
+            program: "sh".into(),
+            args: vec![
+                "-c".into(),
+                "rm -rf -- \"$2\" && git clone --depth 1 -- \"$1\" \"$2\"".into(),
+                "sh".into(),
+                repo.into(),
+                staging.display().to_string(),
+            ],
+            description: "Downloading theme files...".into(),
+        });
+
+        for file in manifest.files {
+            let destination = expand_home(file.destination, home);
+            steps.push(copy_step(
+                &staging.join(file.source),
+                &destination,
+                home,
+                Ownership::ROOT,
+                format!("Installing {}...", destination.display()),
+            ));
+        }
+    }
+
+    steps.extend(manifest.post_commands.iter().map(DeployStep::from));
+
+    if manifest.repo.is_some() && !manifest.files.is_empty() {
+        steps.push(DeployStep {
+            privileged: false,
+            program: "rm".into(),
+            args: vec!["-rf".into(), staging.display().to_string()],
+            description: "Cleaning up downloaded files...".into(),
+        });
+    }
+
+    steps
+}
+
+/// Steps that put every destination in `backup` back the way it was.
+pub fn restore_plan(backup: &Backup, manifest: &Manifest, home: &Path) -> Vec<DeployStep> {
+    let mut steps: Vec<DeployStep> = backup
+        .record
+        .entries
+        .iter()
+        .map(|entry| {
+            if entry.existed {
+                copy_step(
+                    &backup_file_path(&backup.dir, &entry.destination),
+                    &entry.destination,
+                    home,
+                    entry.ownership,
+                    format!("Restoring {}...", entry.destination.display()),
+                )
+            } else {
+                DeployStep {
+                    privileged: !entry.destination.starts_with(home),
+                    program: "rm".into(),
+                    args: vec!["-rf".into(), entry.destination.display().to_string()],
+                    description: format!("Removing {}...", entry.destination.display()),
+                }
+            }
+        })
+        .collect();
+
+    steps.extend(manifest.restore_commands.iter().map(DeployStep::from));
+    steps
+}
+
+/// Replace `destination` with a copy of `source`, escalating outside home.
+///
+/// Outside home the copy is given `ownership` instead of the owner of
+/// `source`.
+fn copy_step(
+    source: &Path,
+    destination: &Path,
+    home: &Path,
+    ownership: Ownership,
+    description: String,
+) -> DeployStep {
+    let privileged = !destination.starts_with(home);
+    let mut script = String::from("rm -rf -- \"$2\" && mkdir -p -- \"$(dirname -- \"$2\")\"");
+    let mut owner_args = Vec::new();
+    if privileged {
+        script.push_str(
+            " && cp -a --no-preserve=ownership -- \"$1\" \"$2\" && chown -hR -- \"$3\" \"$2\"",
+        );
+        owner_args.push(format!("{}:{}", ownership.uid, ownership.gid));
+        if let Some(mode) = ownership.mode {
+            script.push_str(" && chmod -- \"$4\" \"$2\"");
+            owner_args.push(format!("{:o}", mode));
+        }
+    } else {
+        script.push_str(" && cp -a -- \"$1\" \"$2\"");
+    }
+
+    let mut args = vec![
+        "-c".into(),
+        script,
+        "sh".into(),
+        source.display().to_string(),
+        destination.display().to_string(),
+    ];
+    args.extend(owner_args);
+
+    DeployStep {
+        privileged,
+        program: "sh".into(),
+        args,
+        description,
+    }
+}
+
+/// Copy a file, symlink or directory tree.
+fn copy_recursive(source: &Path, destination: &Path) -> io::Result<()> {
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let metadata = fs::symlink_metadata(source)?;
+    if metadata.file_type().is_symlink() {
+        std::os::unix::fs::symlink(fs::read_link(source)?, destination)
+    } else if metadata.is_dir() {
+        fs::create_dir_all(destination)?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &destination.join(entry.file_name()))?;
+        }
+        // Restores copy modes from the backup
+        fs::set_permissions(
+            destination,
+            fs::Permissions::from_mode(metadata.mode() & 0o7777),
+        )
+    } else {
+        fs::copy(source, destination).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("xero-deploy-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    const MANIFEST: Manifest = Manifest {
+        name: "test-theme",
+        repo: Some("https://example.invalid/theme.git"),
+        files: &[FileCopy {
+            source: "theme",
+            destination: "~/.local/share/themes/Test",
+        }],
+        tracked: &["/etc/test-theme.conf"],
+        post_commands: &[],
+        restore_commands: &[StepCommand {
+            privileged: true,
+            program: "true",
+            args: &[],
+            description: "Reloading...",
+        }],
+    };
+
+    #[test]
+    fn test_snapshot_and_latest_backup() {
+        let dir = scratch_dir("snapshot");
+        let root = dir.join("backups");
+        let config = dir.join("theme.conf");
+        let theme = dir.join("themes/Test");
+        let missing = dir.join("missing");
+        fs::create_dir_all(theme.join("sub")).unwrap();
+        fs::write(&config, "old").unwrap();
+        fs::write(theme.join("sub/colors"), "blue").unwrap();
+
+        let destinations = vec![config.clone(), theme.clone(), missing.clone()];
+        let first = snapshot(&root, "test-theme", &destinations, 100).unwrap();
+        fs::write(&config, "new").unwrap();
+        let second = snapshot(&root, "test-theme", &destinations, 200).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(backup_file_path(&first.dir, &config)).unwrap(),
+            "old"
+        );
+        assert_eq!(
+            fs::read_to_string(backup_file_path(&second.dir, &theme).join("sub/colors")).unwrap(),
+            "blue"
+        );
+        assert!(!second.record.entries[2].existed);
+
+        let latest = latest_backup(&root, "test-theme").unwrap();
+        assert_eq!(latest, second);
+        assert_eq!(latest_backup(&root, "other"), None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_restore_plan() {
+        let home = Path::new("/home/xero");
+        let backup = Backup {
+            dir: PathBuf::from("/home/xero/.local/share/xero-toolkit/backups/test-theme/100"),
+            record: BackupRecord {
+                name: "test-theme".into(),
+                created: 100,
+                entries: vec![
+                    BackupEntry {
+                        destination: PathBuf::from("/etc/test-theme.conf"),
+                        existed: true,
+                        ownership: Ownership {
+                            uid: 0,
+                            gid: 0,
+                            mode: Some(0o644),
+                        },
+                    },
+                    BackupEntry {
+                        destination: PathBuf::from("/home/xero/.local/share/themes/Test"),
+                        existed: false,
+                        ownership: Ownership::ROOT,
+                    },
+                ],
+            },
+        };
+
+        let steps = restore_plan(&backup, &MANIFEST, home);
+        assert_eq!(steps.len(), 3);
+        assert!(steps[0].privileged);
+        assert_eq!(
+            steps[0].args[3],
+            "/home/xero/.local/share/xero-toolkit/backups/test-theme/100/files/etc/test-theme.conf"
+        );
+        assert_eq!(steps[0].args[4], "/etc/test-theme.conf");
+        assert!(steps[0].args[1].contains("--no-preserve=ownership"));
+        assert_eq!(steps[0].args[5..], ["0:0", "644"]);
+        assert!(!steps[1].privileged);
+        assert_eq!(steps[1].program, "rm");
+        assert_eq!(steps[2].description, "Reloading...");
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let dir = scratch_dir("restore");
+        let root = dir.join("backups");
+        let config = dir.join("etc/default/grub");
+        let themes = dir.join("boot/grub/themes");
+        let added = dir.join("etc/added.conf");
+        fs::create_dir_all(config.parent().unwrap()).unwrap();
+        fs::create_dir_all(themes.join("Xero")).unwrap();
+        fs::write(&config, "GRUB_TIMEOUT=5\n").unwrap();
+        fs::set_permissions(&config, fs::Permissions::from_mode(0o640)).unwrap();
+        fs::write(themes.join("Xero/theme.txt"), "old").unwrap();
+        fs::set_permissions(themes.join("Xero"), fs::Permissions::from_mode(0o750)).unwrap();
+        let original = fs::metadata(&config).unwrap();
+
+        let destinations = vec![config.clone(), themes.clone(), added.clone()];
+        let backup = snapshot(&root, "test-theme", &destinations, 100).unwrap();
+
+        // What a theme installer leaves behind
+        fs::write(&config, "GRUB_THEME=/boot/grub/themes/Other/theme.txt\n").unwrap();
+        fs::set_permissions(&config, fs::Permissions::from_mode(0o666)).unwrap();
+        fs::remove_dir_all(themes.join("Xero")).unwrap();
+        fs::create_dir_all(themes.join("Other")).unwrap();
+        fs::write(&added, "new").unwrap();
+
+        // Everything here is outside home, so it is restored like root-owned
+        // files are
+        let home = dir.join("home");
+        let manifest = Manifest {
+            restore_commands: &[],
+            ..MANIFEST
+        };
+        for step in restore_plan(&backup, &manifest, &home) {
+            assert!(step.privileged);
+            let status = std::process::Command::new(&step.program)
+                .args(&step.args)
+                .status()
+                .unwrap();
+            assert!(status.success(), "{} failed", step.description);
+        }
+
+        assert_eq!(fs::read_to_string(&config).unwrap(), "GRUB_TIMEOUT=5\n");
+        let restored = fs::metadata(&config).unwrap();
+        assert_eq!(
+            (restored.uid(), restored.gid(), restored.mode() & 0o7777),
+            (original.uid(), original.gid(), 0o640)
+        );
+        assert_eq!(
+            fs::read_to_string(themes.join("Xero/theme.txt")).unwrap(),
+            "old"
+        );
+        let theme_dir = fs::metadata(themes.join("Xero")).unwrap();
+        assert_eq!(theme_dir.mode() & 0o7777, 0o750);
+        assert_eq!(theme_dir.uid(), original.uid());
+        assert!(!themes.join("Other").exists());
+        assert!(!added.exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_deploy_plan() {
+        let home = Path::new("/home/xero");
+        let staging = Path::new("/tmp/stage");
+        let steps = deploy_plan(&MANIFEST, staging, home);
+
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[0].args[3], "https://example.invalid/theme.git");
+        assert_eq!(steps[1].args[3], "/tmp/stage/theme");
+        assert_eq!(steps[1].args[4], "/home/xero/.local/share/themes/Test");
+        assert!(!steps[1].privileged);
+        assert_eq!(steps[2].program, "rm");
+
+        assert_eq!(
+            MANIFEST.destinations(home),
+            vec![
+                PathBuf::from("/home/xero/.local/share/themes/Test"),
+                PathBuf::from("/etc/test-theme.conf"),
+            ]
+        );
+    }
+}
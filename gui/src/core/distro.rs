@@ -0,0 +1,444 @@
+//! The distribution and the desktop session the toolkit runs on.
+//!
+//! Both are read once and cached, as neither changes while the toolkit
+//! runs. Parsing is kept apart from reading so fixtures can be tested.
+
+use crate::i18n::gettext;
+use log::info;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// os-release of the running system, read on first use.
+static OS_RELEASE: OnceLock<Option<OsRelease>> = OnceLock::new();
+
+/// Session the toolkit runs in, read on first use.
+static SESSION: OnceLock<SessionInfo> = OnceLock::new();
+
+/// Distributions the toolkit tells apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distro {
+    XeroLinux,
+    Arch,
+    CachyOs,
+    EndeavourOs,
+    /// Another distribution naming Arch in `ID_LIKE`
+    ArchBased,
+    /// Not based on Arch, so pacman actions don't apply
+    Unsupported,
+}
+
+/// The fields of os-release(5) the toolkit uses.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OsRelease {
+    /// Like `arch` or `xerolinux`
+    pub id: String,
+    /// Distributions this one derives from, closest first
+    pub id_like: Vec<String>,
+    pub name: Option<String>,
+    pub pretty_name: Option<String>,
+    /// Like `40`, unset on rolling releases
+    pub version_id: Option<String>,
+}
+
+/// Value of an os-release assignment, with shell quoting removed.
+///
+/// Double quotes allow backslash escapes, single quotes are literal, and an
+/// unquoted value ends at the first space.
+fn unquote(value: &str) -> String {
+    let mut chars = value.trim().chars();
+    let mut result = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => result.extend(chars.next()),
+                        _ => result.push(c),
+                    }
+                }
+            }
+            '\'' => result.extend(chars.by_ref().take_while(|c| *c != '\'')),
+            '\\' => result.extend(chars.next()),
+            c if c.is_whitespace() => break,
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+impl OsRelease {
+    /// Parse the contents of an os-release file; missing fields stay empty.
+    pub fn parse(content: &str) -> Self {
+        let fields: HashMap<&str, String> = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim(), unquote(value)))
+            .collect();
+        let field = |key| fields.get(key).filter(|value| !value.is_empty()).cloned();
+
+        Self {
+            // "linux" when unset, per os-release(5)
+            id: field("ID").unwrap_or_else(|| "linux".to_string()),
+            id_like: field("ID_LIKE")
+                .map(|ids| ids.split_whitespace().map(str::to_string).collect())
+                .unwrap_or_default(),
+            name: field("NAME"),
+            pretty_name: field("PRETTY_NAME"),
+            version_id: field("VERSION_ID"),
+        }
+    }
+
+    /// Read /etc/os-release, or /usr/lib/os-release where it is missing.
+    pub fn read() -> Option<Self> {
+        std::fs::read_to_string("/etc/os-release")
+            .or_else(|_| std::fs::read_to_string("/usr/lib/os-release"))
+            .ok()
+            .map(|content| Self::parse(&content))
+    }
+
+    pub fn distro(&self) -> Distro {
+        match self.id.as_str() {
+            "xerolinux" => Distro::XeroLinux,
+            "arch" => Distro::Arch,
+            "cachyos" => Distro::CachyOs,
+            "endeavouros" => Distro::EndeavourOs,
+            _ if self.id_like.iter().any(|id| id == "arch") => Distro::ArchBased,
+            _ => Distro::Unsupported,
+        }
+    }
+
+    /// Whether this is Arch Linux or a derivative.
+    pub fn is_arch_based(&self) -> bool {
+        self.distro() != Distro::Unsupported
+    }
+
+    /// Name to show, from `NAME`, `PRETTY_NAME` or `ID`.
+    pub fn display_name(&self) -> &str {
+        self.name
+            .as_deref()
+            .or(self.pretty_name.as_deref())
+            .unwrap_or(&self.id)
+    }
+}
+
+/// os-release of the running system, `None` if neither file is readable.
+pub fn os_release() -> Option<&'static OsRelease> {
+    OS_RELEASE
+        .get_or_init(|| {
+            let os_release = OsRelease::read();
+            info!("Detected distribution: {:?}", os_release);
+            os_release
+        })
+        .as_ref()
+}
+
+/// Desktop environment of the running session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Desktop {
+    Plasma,
+    Gnome,
+    /// Named by the session, like `XFCE` or `Hyprland`
+    Other(String),
+    /// Nothing names one, like on a TTY or over SSH
+    Unknown,
+}
+
+impl Desktop {
+    /// Name to show for the desktop.
+    pub fn name(&self) -> String {
+        match self {
+            Self::Plasma => "KDE Plasma".to_string(),
+            Self::Gnome => "GNOME".to_string(),
+            Self::Other(name) => name.clone(),
+            Self::Unknown => gettext("an unknown desktop"),
+        }
+    }
+}
+
+/// Display protocol of the session, from `XDG_SESSION_TYPE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionType {
+    Wayland,
+    X11,
+    Tty,
+    Unknown,
+}
+
+impl SessionType {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Wayland => "Wayland",
+            Self::X11 => "X11",
+            Self::Tty => "TTY",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// The session the toolkit runs in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionInfo {
+    pub desktop: Desktop,
+    pub session_type: SessionType,
+    /// Like `KWin` or `Hyprland`, `None` when the desktop doesn't tell
+    pub compositor: Option<String>,
+}
+
+/// Desktop from the values of `XDG_CURRENT_DESKTOP`, `XDG_SESSION_DESKTOP`
+/// and `KDE_FULL_SESSION`.
+///
+/// `XDG_CURRENT_DESKTOP` is a colon-separated list, like `ubuntu:GNOME`,
+/// where a known desktop wins over the others.
+fn desktop_from_env(
+    current_desktop: Option<&str>,
+    session_desktop: Option<&str>,
+    kde_full_session: Option<&str>,
+) -> Desktop {
+    let names = current_desktop
+        .into_iter()
+        .flat_map(|value| value.split(':'))
+        .chain(session_desktop)
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+
+    let mut other = None;
+    for name in names {
+        match name.to_ascii_lowercase().as_str() {
+            "kde" | "plasma" | "plasmawayland" | "plasmax11" => return Desktop::Plasma,
+            "gnome" | "gnome-classic" | "gnome-flashback" => return Desktop::Gnome,
+            _ => {
+                other.get_or_insert(name);
+            }
+        }
+    }
+    match other {
+        Some(name) => Desktop::Other(name.to_string()),
+        // Only consulted when nothing names the desktop
+        None if kde_full_session == Some("true") => Desktop::Plasma,
+        None => Desktop::Unknown,
+    }
+}
+
+impl SessionInfo {
+    /// Describe the session from its environment, reading it with `var`.
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        let set = |name: &str| var(name).is_some_and(|value| !value.is_empty());
+        let desktop = desktop_from_env(
+            var("XDG_CURRENT_DESKTOP").as_deref(),
+            var("XDG_SESSION_DESKTOP").as_deref(),
+            var("KDE_FULL_SESSION").as_deref(),
+        );
+
+        let session_type = match var("XDG_SESSION_TYPE").as_deref() {
+            Some("wayland") => SessionType::Wayland,
+            Some("x11") => SessionType::X11,
+            Some("tty") => SessionType::Tty,
+            _ if set("WAYLAND_DISPLAY") => SessionType::Wayland,
+            _ if set("DISPLAY") => SessionType::X11,
+            _ => SessionType::Unknown,
+        };
+
+        // Compositors without a desktop of their own announce a socket
+        let compositor = if set("HYPRLAND_INSTANCE_SIGNATURE") {
+            Some("Hyprland")
+        } else if set("SWAYSOCK") {
+            Some("Sway")
+        } else if set("NIRI_SOCKET") {
+            Some("niri")
+        } else {
+            match desktop {
+                Desktop::Plasma => Some("KWin"),
+                Desktop::Gnome => Some("Mutter"),
+                _ => None,
+            }
+        };
+
+        Self {
+            desktop,
+            session_type,
+            compositor: compositor.map(str::to_string),
+        }
+    }
+}
+
+/// The session the toolkit runs in.
+pub fn session() -> &'static SessionInfo {
+    SESSION.get_or_init(|| {
+        let session = SessionInfo::from_env(|name| std::env::var(name).ok());
+        info!("Detected session: {:?}", session);
+        session
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARCH: &str = r#"NAME="Arch Linux"
+PRETTY_NAME="Arch Linux"
+ID=arch
+BUILD_ID=rolling
+ANSI_COLOR="38;2;23;147;209"
+HOME_URL="https://archlinux.org/"
+LOGO=archlinux-logo
+"#;
+
+    const XEROLINUX: &str = r#"NAME="XeroLinux"
+PRETTY_NAME="XeroLinux"
+ID=xerolinux
+ID_LIKE=arch
+BUILD_ID=rolling
+HOME_URL="https://xerolinux.xyz/"
+LOGO=xerolinux
+"#;
+
+    const CACHYOS: &str = r#"NAME="CachyOS Linux"
+PRETTY_NAME="CachyOS"
+ID=cachyos
+BUILD_ID=rolling
+HOME_URL="https://cachyos.org/"
+LOGO=cachyos
+"#;
+
+    const FEDORA: &str = r#"NAME="Fedora Linux"
+VERSION="40 (Workstation Edition)"
+ID=fedora
+VERSION_ID=40
+PRETTY_NAME="Fedora Linux 40 (Workstation Edition)"
+# Comments are ignored
+VARIANT_ID=workstation
+"#;
+
+    #[test]
+    fn test_parse_fixtures() {
+        let arch = OsRelease::parse(ARCH);
+        assert_eq!(arch.id, "arch");
+        assert!(arch.id_like.is_empty());
+        assert_eq!(arch.display_name(), "Arch Linux");
+        assert_eq!(arch.version_id, None);
+        assert_eq!(arch.distro(), Distro::Arch);
+
+        let xero = OsRelease::parse(XEROLINUX);
+        assert_eq!(xero.distro(), Distro::XeroLinux);
+        assert_eq!(xero.id_like, ["arch"]);
+        assert_eq!(xero.display_name(), "XeroLinux");
+
+        // No ID_LIKE, known by its ID
+        let cachyos = OsRelease::parse(CACHYOS);
+        assert_eq!(cachyos.distro(), Distro::CachyOs);
+        assert!(cachyos.is_arch_based());
+        assert_eq!(cachyos.display_name(), "CachyOS Linux");
+
+        let fedora = OsRelease::parse(FEDORA);
+        assert_eq!(fedora.distro(), Distro::Unsupported);
+        assert!(!fedora.is_arch_based());
+        assert_eq!(fedora.version_id.as_deref(), Some("40"));
+        assert_eq!(
+            fedora.pretty_name.as_deref(),
+            Some("Fedora Linux 40 (Workstation Edition)")
+        );
+    }
+
+    #[test]
+    fn test_parse_quoting() {
+        let endeavour = OsRelease::parse("NAME='EndeavourOS'\nID='endeavouros'\nID_LIKE='arch'\n");
+        assert_eq!(endeavour.distro(), Distro::EndeavourOs);
+        assert_eq!(endeavour.display_name(), "EndeavourOS");
+
+        let escaped = OsRelease::parse(r#"NAME="Say \"hi\" \\ \$HOME""#);
+        assert_eq!(escaped.name.as_deref(), Some(r#"Say "hi" \ $HOME"#));
+
+        let derived = OsRelease::parse("ID=garuda\nID_LIKE=\"arch archlinux\"\n");
+        assert_eq!(derived.id_like, ["arch", "archlinux"]);
+        assert_eq!(derived.distro(), Distro::ArchBased);
+        assert_eq!(
+            OsRelease::parse("ID=ubuntu\nID_LIKE=debian\n").distro(),
+            Distro::Unsupported
+        );
+        assert!(!OsRelease::parse("ID=archcraftish\n").is_arch_based());
+    }
+
+    #[test]
+    fn test_parse_missing_fields() {
+        let empty = OsRelease::parse("");
+        assert_eq!(empty.id, "linux");
+        assert_eq!(empty.display_name(), "linux");
+        assert!(!empty.is_arch_based());
+
+        let pretty = OsRelease::parse("PRETTY_NAME=\"Some OS\"\nNAME=\"\"\ngarbage\n");
+        assert_eq!(pretty.name, None);
+        assert_eq!(pretty.display_name(), "Some OS");
+    }
+
+    #[test]
+    fn test_desktop_from_env() {
+        assert_eq!(desktop_from_env(Some("KDE"), None, None), Desktop::Plasma);
+        assert_eq!(
+            desktop_from_env(None, Some("plasmawayland"), None),
+            Desktop::Plasma
+        );
+        assert_eq!(
+            desktop_from_env(Some("GNOME"), Some("gnome"), None),
+            Desktop::Gnome
+        );
+        // Distributions prefix their own name
+        assert_eq!(
+            desktop_from_env(Some("ubuntu:GNOME"), None, None),
+            Desktop::Gnome
+        );
+        assert_eq!(
+            desktop_from_env(Some("XFCE"), Some("xfce"), None),
+            Desktop::Other("XFCE".to_string())
+        );
+        // A variable left over from the Plasma session Hyprland was started from
+        assert_eq!(
+            desktop_from_env(Some("Hyprland"), None, Some("true")),
+            Desktop::Other("Hyprland".to_string())
+        );
+        assert_eq!(desktop_from_env(None, None, Some("true")), Desktop::Plasma);
+        assert_eq!(
+            desktop_from_env(Some(" : "), Some(""), None),
+            Desktop::Unknown
+        );
+        assert_eq!(desktop_from_env(None, None, None), Desktop::Unknown);
+    }
+
+    fn session(vars: &[(&str, &str)]) -> SessionInfo {
+        let vars: HashMap<&str, &str> = vars.iter().copied().collect();
+        SessionInfo::from_env(|name| vars.get(name).map(|value| value.to_string()))
+    }
+
+    #[test]
+    fn test_session_from_env() {
+        let plasma = session(&[
+            ("XDG_CURRENT_DESKTOP", "KDE"),
+            ("XDG_SESSION_TYPE", "wayland"),
+        ]);
+        assert_eq!(plasma.desktop, Desktop::Plasma);
+        assert_eq!(plasma.session_type, SessionType::Wayland);
+        assert_eq!(plasma.compositor.as_deref(), Some("KWin"));
+
+        let hyprland = session(&[
+            ("XDG_CURRENT_DESKTOP", "Hyprland"),
+            ("HYPRLAND_INSTANCE_SIGNATURE", "abc_123"),
+            ("WAYLAND_DISPLAY", "wayland-1"),
+        ]);
+        assert_eq!(hyprland.session_type, SessionType::Wayland);
+        assert_eq!(hyprland.compositor.as_deref(), Some("Hyprland"));
+
+        let xfce = session(&[("XDG_CURRENT_DESKTOP", "XFCE"), ("DISPLAY", ":0")]);
+        assert_eq!(xfce.session_type, SessionType::X11);
+        assert_eq!(xfce.compositor, None);
+
+        let ssh = session(&[]);
+        assert_eq!(ssh.desktop, Desktop::Unknown);
+        assert_eq!(ssh.session_type, SessionType::Unknown);
+        assert_eq!(
+            session(&[("XDG_SESSION_TYPE", "tty")]).session_type,
+            SessionType::Tty
+        );
+    }
+}
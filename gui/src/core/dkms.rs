@@ -0,0 +1,241 @@
+//! DKMS module status.
+//!
+//! Out-of-tree drivers like NVIDIA's or xpadneo are rebuilt by DKMS for
+//! every installed kernel; a missing headers package or a failed build
+//! leaves the running kernel without them.
+
+use crate::ui::task_runner::Command;
+use std::path::Path;
+
+/// One line of `dkms status`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DkmsEntry {
+    pub module: String,
+    pub version: String,
+    /// Kernel release the module was built for, `None` if only added
+    pub kernel: Option<String>,
+    /// Like `installed`, `built` or `added`
+    pub state: String,
+}
+
+impl DkmsEntry {
+    /// Whether the module is installed for its kernel, also when dkms warns
+    /// about it.
+    pub fn is_installed(&self) -> bool {
+        self.state.starts_with("installed")
+    }
+}
+
+/// Entries of `dkms status` output, in both the current
+/// `module/version, kernel, arch: state` and the older
+/// `module, version, kernel, arch: state` format.
+///
+/// Warnings and errors some versions print between the entries are
+/// skipped.
+pub fn parse_status(output: &str) -> Vec<DkmsEntry> {
+    let is_name = |field: &str| !field.is_empty() && !field.contains(char::is_whitespace);
+    output
+        .lines()
+        .filter_map(|line| {
+            let (fields, state) = line.trim().split_once(": ")?;
+            let mut fields = fields.split(", ").map(str::trim);
+            let first = fields.next()?;
+            let (module, version) = match first.split_once('/') {
+                Some((module, version)) => (module, version),
+                None => (first, fields.next()?),
+            };
+            if !is_name(module) || !is_name(version) {
+                return None;
+            }
+            Some(DkmsEntry {
+                module: module.to_string(),
+                version: version.to_string(),
+                kernel: fields.next().map(str::to_string),
+                state: state.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Whether one of `entries` is installed for `kernel`.
+pub fn built_for(entries: &[DkmsEntry], kernel: &str) -> bool {
+    entries
+        .iter()
+        .any(|entry| entry.kernel.as_deref() == Some(kernel) && entry.is_installed())
+}
+
+/// A module version with its builds, as listed on the drivers page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DkmsModule {
+    pub module: String,
+    pub version: String,
+    /// Entries of the version, with a kernel unless only added
+    pub entries: Vec<DkmsEntry>,
+}
+
+impl DkmsModule {
+    /// Argument naming the version for dkms, like `nvidia/560.35.03`.
+    pub fn spec(&self) -> String {
+        format!("{}/{}", self.module, self.version)
+    }
+
+    /// Whether the version is installed for `kernel`.
+    pub fn installed_for(&self, kernel: &str) -> bool {
+        built_for(&self.entries, kernel)
+    }
+}
+
+/// `entries` grouped by module version, in the order dkms lists them.
+pub fn modules(entries: &[DkmsEntry]) -> Vec<DkmsModule> {
+    let mut modules: Vec<DkmsModule> = Vec::new();
+    for entry in entries {
+        let existing = modules
+            .iter_mut()
+            .find(|module| module.module == entry.module && module.version == entry.version);
+        match existing {
+            Some(module) => module.entries.push(entry.clone()),
+            None => modules.push(DkmsModule {
+                module: entry.module.clone(),
+                version: entry.version.clone(),
+                entries: vec![entry.clone()],
+            }),
+        }
+    }
+    modules
+}
+
+/// Whether the dkms command is available.
+pub fn is_available() -> bool {
+    crate::core::aur::is_executable_in_path("dkms")
+}
+
+/// Entries of `dkms status`; empty if dkms is not installed.
+pub fn status() -> Vec<DkmsEntry> {
+    std::process::Command::new("dkms")
+        .arg("status")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| parse_status(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+/// Release of the running kernel, like `6.10.10-arch1-1`.
+pub fn running_kernel() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .map(|release| release.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Headers package of `kernel`, like `linux-zen-headers`.
+pub fn headers_package(kernel: &str) -> Option<String> {
+    // Kernel packages record their name next to the modules
+    let pkgbase = Path::new("/usr/lib/modules").join(kernel).join("pkgbase");
+    std::fs::read_to_string(pkgbase)
+        .ok()
+        .map(|pkgbase| pkgbase.trim().to_string())
+        .filter(|pkgbase| !pkgbase.is_empty())
+        .map(|pkgbase| format!("{}-headers", pkgbase))
+}
+
+/// A step failing unless `module` is installed for the running kernel,
+/// to end sequences that build one.
+pub fn verify_command(module: &str, description: &str) -> Command {
+    Command::script(
+        description,
+        r#"kernel=$(uname -r)
+if dkms status -m "$MODULE" -k "$kernel" | grep -q ': installed'; then
+    echo "$MODULE is built for $kernel"
+else
+    echo "$MODULE is not built for $kernel, check that its headers are installed" >&2
+    exit 1
+fi"#,
+    )
+    .var("MODULE", module)
+    .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KERNEL: &str = "6.10.10-arch1-1";
+
+    #[test]
+    fn test_parse_status() {
+        let entries = parse_status(include_str!("../../tests/fixtures/nvidia/dkms-status.txt"));
+        assert_eq!(entries.len(), 4);
+        assert_eq!(
+            entries[0],
+            DkmsEntry {
+                module: "nvidia".to_string(),
+                version: "560.35.03".to_string(),
+                kernel: Some(KERNEL.to_string()),
+                state: "installed".to_string(),
+            }
+        );
+        assert!(entries[1].state.starts_with("installed (WARNING!"));
+        assert_eq!(entries[2].kernel, None);
+        assert_eq!(entries[2].state, "added");
+        // Older format
+        assert_eq!(entries[3].module, "v4l2loopback");
+        assert_eq!(entries[3].version, "0.13.2");
+        assert_eq!(entries[3].kernel.as_deref(), Some(KERNEL));
+    }
+
+    #[test]
+    fn test_parse_status_skips_messages() {
+        let entries = parse_status(include_str!("../../tests/fixtures/dkms/status-mixed.txt"));
+        let names: Vec<String> = entries
+            .iter()
+            .map(|entry| format!("{}/{}", entry.module, entry.version))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "nvidia/560.35.03",
+                "nvidia/560.35.03",
+                "xpadneo/0.9.6",
+                "xpadneo/0.9.6",
+                "vboxhost/7.0.20_OSE",
+                "v4l2loopback/0.13.2",
+                "zenergy/r21.4f6bd4f",
+            ]
+        );
+        assert_eq!(entries[3].state, "built");
+        assert!(!entries[3].is_installed());
+        assert!(entries[1].is_installed());
+        assert_eq!(entries[4].kernel, None);
+        // Indented line
+        assert_eq!(entries[6].kernel.as_deref(), Some(KERNEL));
+        assert!(parse_status("").is_empty());
+    }
+
+    #[test]
+    fn test_modules() {
+        let entries = parse_status(include_str!("../../tests/fixtures/dkms/status-mixed.txt"));
+        let modules = modules(&entries);
+        assert_eq!(modules.len(), 5);
+
+        assert_eq!(modules[0].spec(), "nvidia/560.35.03");
+        assert_eq!(modules[0].entries.len(), 2);
+        assert!(modules[0].installed_for(KERNEL));
+        // Built but not installed
+        assert_eq!(modules[1].spec(), "xpadneo/0.9.6");
+        assert!(!modules[1].installed_for(KERNEL));
+        assert!(modules[1].installed_for("6.6.52-1-lts"));
+        // Only added
+        assert!(!modules[2].installed_for(KERNEL));
+        assert_eq!(modules[3].spec(), "v4l2loopback/0.13.2");
+        assert!(modules[3].installed_for(KERNEL));
+    }
+
+    #[test]
+    fn test_built_for() {
+        let entries = parse_status(include_str!("../../tests/fixtures/nvidia/dkms-status.txt"));
+        assert!(built_for(&entries, KERNEL));
+        assert!(built_for(&entries, "6.6.52-1-lts"));
+        assert!(!built_for(&entries, "6.11.1-arch1-1"));
+        assert!(!built_for(&[], KERNEL));
+    }
+}
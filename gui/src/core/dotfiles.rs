@@ -0,0 +1,160 @@
+//! Per-user configuration file deployment.
+//!
+//! Writes default dotfiles into the user's home, backing up any existing
+//! file that differs so user customizations are never silently lost.
+
+use log::info;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Outcome of deploying a configuration file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeployOutcome {
+    /// The file did not exist and was created.
+    Created,
+    /// The file already had the requested contents.
+    Unchanged,
+    /// The existing file was moved to the given backup path and replaced.
+    Replaced { backup: PathBuf },
+}
+
+/// Deploy `contents` to `path`, creating parent directories as needed.
+///
+/// An existing file with different contents is renamed to `<name>.bak`
+/// (or `<name>.bak.N` if that is taken) before the new file is written.
+pub fn deploy(path: &Path, contents: &str) -> io::Result<DeployOutcome> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let outcome = match fs::read_to_string(path) {
+        Ok(existing) if existing == contents => return Ok(DeployOutcome::Unchanged),
+        Ok(_) => {
+            let backup = backup_path(path);
+            fs::rename(path, &backup)?;
+            info!("Backed up {} to {}", path.display(), backup.display());
+            DeployOutcome::Replaced { backup }
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => DeployOutcome::Created,
+        Err(e) => return Err(e),
+    };
+
+    fs::write(path, contents)?;
+    info!("Deployed {}", path.display());
+    Ok(outcome)
+}
+
+/// Most recently written backup of `path`, i.e. a sibling named
+/// `<name>.bak` or `<name>.bak.<suffix>`.
+pub fn latest_backup(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty())?;
+    let prefix = format!("{}.bak", name);
+
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|file| file == prefix || file.starts_with(&format!("{}.", prefix)))
+        })
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+/// First unused backup path for `path`.
+fn backup_path(path: &Path) -> PathBuf {
+    let base = format!("{}.bak", path.display());
+    let mut candidate = PathBuf::from(&base);
+    let mut index = 1;
+    while candidate.exists() {
+        candidate = PathBuf::from(format!("{}.{}", base, index));
+        index += 1;
+    }
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("xero-dotfiles-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_deploy_creates_and_skips_identical() {
+        let dir = scratch_dir("create");
+        let path = dir.join("nested/app.conf");
+
+        assert_eq!(deploy(&path, "a=1\n").unwrap(), DeployOutcome::Created);
+        assert_eq!(deploy(&path, "a=1\n").unwrap(), DeployOutcome::Unchanged);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a=1\n");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_deploy_backs_up_existing() {
+        let dir = scratch_dir("backup");
+        let path = dir.join("app.conf");
+
+        deploy(&path, "old\n").unwrap();
+        let first = deploy(&path, "new\n").unwrap();
+        assert_eq!(
+            first,
+            DeployOutcome::Replaced {
+                backup: dir.join("app.conf.bak")
+            }
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("app.conf.bak")).unwrap(),
+            "old\n"
+        );
+
+        let second = deploy(&path, "newer\n").unwrap();
+        assert_eq!(
+            second,
+            DeployOutcome::Replaced {
+                backup: dir.join("app.conf.bak.1")
+            }
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "newer\n");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_latest_backup_prefers_newest() {
+        let dir = scratch_dir("latest");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".zshrc");
+        assert_eq!(latest_backup(&path), None);
+
+        let now = std::time::SystemTime::now();
+        for (name, age) in [
+            (".zshrc.bak.20250101-120000", 300),
+            (".zshrc.bak.20250301-120000", 100),
+            (".zshrc.bak", 200),
+            (".zshrc.user", 0),
+        ] {
+            let file = fs::File::create(dir.join(name)).unwrap();
+            file.set_modified(now - std::time::Duration::from_secs(age))
+                .unwrap();
+        }
+
+        assert_eq!(
+            latest_backup(&path),
+            Some(dir.join(".zshrc.bak.20250301-120000"))
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,12 +1,27 @@
 //! Download manager with progress tracking
+//!
+//! Downloads are written to a `.part` file next to the destination, with a
+//! JSON sidecar recording enough to continue them after the app restarts.
 
 use anyhow::{Context, Result};
-use log::info;
+use log::{info, warn};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+/// Suffix of the file a download is written to until it completes.
+const PART_SUFFIX: &str = ".part";
+
+/// Suffix of the resume state saved next to the partial file.
+const STATE_SUFFIX: &str = ".part.json";
+
+/// How often the resume state is saved while data arrives.
+const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(2);
+
 /// Represents the state of a download
 #[derive(Clone, Debug)]
 pub struct DownloadState {
@@ -15,8 +30,207 @@ pub struct DownloadState {
     pub speed: f64, // bytes per second
 }
 
-/// Fetch the latest Arch Linux ISO information
-pub async fn fetch_arch_iso_info() -> Result<(String, String)> {
+/// What the server reports about a file, used to tell whether a partial
+/// download still matches it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RemoteInfo {
+    /// Size in bytes, 0 when unknown
+    pub total: u64,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl RemoteInfo {
+    fn from_headers(headers: &reqwest::header::HeaderMap, total: u64) -> Self {
+        use reqwest::header::{ETAG, LAST_MODIFIED};
+
+        let header = |name: reqwest::header::HeaderName| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            total,
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+}
+
+/// Progress of an interrupted download, saved next to its partial file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumeState {
+    pub url: String,
+    /// Expected size in bytes, 0 when unknown
+    pub total: u64,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Bytes written to the partial file when the state was last saved
+    pub downloaded: u64,
+}
+
+impl ResumeState {
+    fn new(url: &str, remote: &RemoteInfo, downloaded: u64) -> Self {
+        Self {
+            url: url.to_string(),
+            total: remote.total,
+            etag: remote.etag.clone(),
+            last_modified: remote.last_modified.clone(),
+            downloaded,
+        }
+    }
+
+    /// Read the state saved for the download to `dest`.
+    pub fn load(dest: &Path) -> Result<Self> {
+        let content =
+            std::fs::read_to_string(state_path(dest)).context("Failed to read resume state")?;
+        serde_json::from_str(&content).context("Failed to parse resume state")
+    }
+
+    /// Save the state for the download to `dest`.
+    fn save(&self, dest: &Path) -> Result<()> {
+        let content = serde_json::to_string(self).context("Failed to serialize resume state")?;
+        std::fs::write(state_path(dest), content).context("Failed to write resume state")
+    }
+
+    /// Offset to continue from when the partial file is `part_len` bytes,
+    /// or `None` when the file cannot be the one this state was saved for.
+    ///
+    /// The file may be ahead of the state, which is only saved now and then.
+    pub fn resume_offset(&self, part_len: u64) -> Option<u64> {
+        if part_len == 0 || part_len < self.downloaded {
+            return None;
+        }
+        if self.total > 0 && part_len > self.total {
+            return None;
+        }
+        Some(part_len)
+    }
+
+    /// Whether the file on the server is still the one the partial file
+    /// holds the start of.
+    ///
+    /// Without an ETag or Last-Modified to compare, a changed file cannot
+    /// be told apart, so the download starts over.
+    pub fn matches_remote(&self, remote: &RemoteInfo) -> bool {
+        if self.total > 0 && remote.total > 0 && self.total != remote.total {
+            return false;
+        }
+        match (&self.etag, &self.last_modified) {
+            (Some(etag), _) => remote.etag.as_ref() == Some(etag),
+            (None, Some(modified)) => remote.last_modified.as_ref() == Some(modified),
+            (None, None) => false,
+        }
+    }
+
+    /// Value for the `If-Range` header, so the server sends the whole file
+    /// instead of a range when it changed.
+    fn validator(&self) -> Option<&str> {
+        self.etag
+            .as_deref()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified.as_deref())
+    }
+}
+
+/// A partial download that can be continued.
+#[derive(Clone, Debug)]
+pub struct ResumableDownload {
+    /// Where the finished file goes
+    pub path: PathBuf,
+    pub state: ResumeState,
+    /// Bytes already in the partial file
+    pub downloaded: u64,
+}
+
+impl ResumableDownload {
+    /// File name of the download.
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Partial file of the download to `dest`.
+pub fn part_path(dest: &Path) -> PathBuf {
+    with_suffix(dest, PART_SUFFIX)
+}
+
+/// Resume state of the download to `dest`.
+pub fn state_path(dest: &Path) -> PathBuf {
+    with_suffix(dest, STATE_SUFFIX)
+}
+
+/// Remove the partial file and resume state of the download to `dest`.
+pub fn discard_partial(dest: &Path) {
+    let _ = std::fs::remove_file(part_path(dest));
+    let _ = std::fs::remove_file(state_path(dest));
+}
+
+/// Interrupted downloads in `folder` that can be continued.
+///
+/// Resume states that cannot be read or whose partial file is gone or does
+/// not match them are removed along with the partial file.
+pub fn find_resumable(folder: &Path) -> Vec<ResumableDownload> {
+    let Ok(entries) = std::fs::read_dir(folder) else {
+        return Vec::new();
+    };
+
+    let mut downloads = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(dest_name) = name.strip_suffix(STATE_SUFFIX) else {
+            continue;
+        };
+        let path = folder.join(dest_name);
+
+        let resumable = ResumeState::load(&path).ok().and_then(|state| {
+            let part_len = std::fs::metadata(part_path(&path)).ok()?.len();
+            let downloaded = state.resume_offset(part_len)?;
+            Some(ResumableDownload {
+                path: path.clone(),
+                state,
+                downloaded,
+            })
+        });
+        match resumable {
+            Some(download) => downloads.push(download),
+            None => {
+                info!("Removing stale partial download {}", path.display());
+                discard_partial(&path);
+            }
+        }
+    }
+    downloads.sort_by(|a, b| a.path.cmp(&b.path));
+    downloads
+}
+
+/// The partial file to continue the download of `url` to `dest` from, and
+/// its length, if it is still valid for `remote`.
+fn resume_point(dest: &Path, url: &str, remote: &RemoteInfo) -> Option<(ResumeState, u64)> {
+    let state = ResumeState::load(dest)
+        .ok()
+        .filter(|state| state.url == url)?;
+    let part_len = std::fs::metadata(part_path(dest)).ok()?.len();
+    let offset = state.resume_offset(part_len)?;
+    if !state.matches_remote(remote) {
+        info!("{} changed on the server, starting over", url);
+        return None;
+    }
+    Some((state, offset))
+}
+
+/// Fetch the latest Arch Linux ISO information from `mirror`
+pub async fn fetch_arch_iso_info(mirror: &str) -> Result<(String, String)> {
     info!("Fetching Arch Linux ISO information...");
 
     let client = reqwest::Client::builder()
@@ -24,10 +238,9 @@ pub async fn fetch_arch_iso_info() -> Result<(String, String)> {
         .build()
         .context("Failed to build HTTP client")?;
 
-    // Use LeaseWeb mirror (same as bash script)
-    let base_url = "https://fastly.mirror.pkgbuild.com/iso/latest/";
+    let base_url = format!("{}/iso/latest/", mirror.trim_end_matches('/'));
     let html = client
-        .get(base_url)
+        .get(&base_url)
         .send()
         .await
         .context("Failed to fetch ISO listing")?
@@ -51,19 +264,40 @@ pub async fn fetch_arch_iso_info() -> Result<(String, String)> {
     Ok((iso_name, download_url))
 }
 
-/// Download a file with progress tracking
+/// Size of the file at `url` as the server reports it, `None` when unknown.
+pub async fn fetch_remote_size(url: &str) -> Option<u64> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .ok()?;
+    match client.head(url).send().await {
+        Ok(resp) => resp.content_length().filter(|size| *size > 0),
+        Err(e) => {
+            warn!("Failed to query the size of {}: {}", url, e);
+            None
+        }
+    }
+}
+
+/// Download a file with progress tracking, optionally limited to
+/// `speed_limit` bytes per second
+///
+/// Data goes to a `.part` file renamed to `dest_path` once complete. A
+/// partial file left by an earlier run is continued when the server still
+/// has the same file; pausing keeps it for later.
 pub async fn download_file<F>(
     url: String,
     dest_path: String,
     progress_callback: F,
     pause_flag: Arc<AtomicBool>,
     cancel_flag: Arc<AtomicBool>,
+    speed_limit: Option<u64>,
 ) -> Result<()>
 where
     F: Fn(DownloadState) + Send + 'static,
 {
     use futures_util::StreamExt;
-    use reqwest::header::RANGE;
+    use reqwest::header::{IF_RANGE, RANGE};
     use tokio::io::AsyncWriteExt;
 
     info!("Starting download from {} to {}", url, dest_path);
@@ -73,34 +307,62 @@ where
         .build()
         .context("Failed to build HTTP client")?;
 
-    // Create file (truncate if exists)
-    let mut file = tokio::fs::File::create(&dest_path)
-        .await
-        .context("Failed to create destination file")?;
+    let dest = PathBuf::from(&dest_path);
+    let part = part_path(&dest);
+
+    // Try to get total size and validators first
+    let remote = match client.head(&url).send().await {
+        Ok(resp) => RemoteInfo::from_headers(resp.headers(), resp.content_length().unwrap_or(0)),
+        Err(_) => RemoteInfo::default(),
+    };
+    if remote.total > 0 {
+        info!("Total size determined via HEAD: {}", remote.total);
+    }
+
+    let (mut state, mut file) = match resume_point(&dest, &url, &remote) {
+        Some((state, offset)) => {
+            info!("Continuing partial download from byte {}", offset);
+            let file = tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(&part)
+                .await
+                .context("Failed to open partial file")?;
+            (
+                ResumeState {
+                    downloaded: offset,
+                    ..state
+                },
+                file,
+            )
+        }
+        None => {
+            // Create file (truncate if exists)
+            let file = tokio::fs::File::create(&part)
+                .await
+                .context("Failed to create destination file")?;
+            (ResumeState::new(&url, &remote, 0), file)
+        }
+    };
+    if let Err(e) = state.save(&dest) {
+        warn!("Failed to save resume state: {:#}", e);
+    }
 
-    let mut downloaded: u64 = 0;
-    let mut total_size: u64 = 0;
+    let mut downloaded: u64 = state.downloaded;
+    let mut total_size: u64 = state.total;
+    let mut last_saved = Instant::now();
 
     // Speed calculation variables
     let mut last_update = Instant::now();
-    let mut last_downloaded = 0u64;
+    let mut last_downloaded = downloaded;
     let mut speed_samples: Vec<f64> = Vec::with_capacity(20);
     let max_samples = 20;
 
-    // Try to get total size first
-    if let Ok(resp) = client.head(&url).send().await {
-        if let Some(len) = resp.content_length() {
-            total_size = len;
-            info!("Total size determined via HEAD: {}", total_size);
-        }
-    }
-
     loop {
         // Check cancellation
         if cancel_flag.load(Ordering::Relaxed) {
             info!("Download cancelled");
             drop(file);
-            let _ = tokio::fs::remove_file(&dest_path).await;
+            discard_partial(&dest);
             anyhow::bail!("Download cancelled");
         }
 
@@ -120,16 +382,34 @@ where
         if downloaded > 0 {
             info!("Resuming download from byte {}", downloaded);
             request = request.header(RANGE, format!("bytes={}-", downloaded));
+            if let Some(validator) = state.validator() {
+                request = request.header(IF_RANGE, validator);
+            }
         }
 
         let response_result = request.send().await;
 
         match response_result {
             Ok(response) => {
+                // The whole file came back, because the server ignores
+                // ranges or the file changed since the partial download
+                if downloaded > 0 && response.status() == reqwest::StatusCode::OK {
+                    info!("Server sent the whole file, starting over");
+                    file = tokio::fs::File::create(&part)
+                        .await
+                        .context("Failed to create destination file")?;
+                    downloaded = 0;
+                    last_downloaded = 0;
+                    total_size = 0;
+                    state =
+                        ResumeState::new(&url, &RemoteInfo::from_headers(response.headers(), 0), 0);
+                }
+
                 // Update total_size if we didn't have it
                 if total_size == 0 {
                     if let Some(len) = response.content_length() {
                         total_size = downloaded + len;
+                        state.total = total_size;
                         info!("Total size determined via GET: {}", total_size);
                     }
                 }
@@ -149,17 +429,24 @@ where
 
                 let mut stream = response.bytes_stream();
                 let mut error_occurred = false;
+                let stream_start = Instant::now();
+                let stream_offset = downloaded;
 
                 while let Some(chunk_result) = stream.next().await {
                     if cancel_flag.load(Ordering::Relaxed) {
                         info!("Download cancelled");
                         drop(file);
-                        let _ = tokio::fs::remove_file(&dest_path).await;
+                        discard_partial(&dest);
                         anyhow::bail!("Download cancelled");
                     }
 
                     if pause_flag.load(Ordering::Relaxed) {
                         info!("Download paused. Dropping connection.");
+                        file.flush().await?;
+                        state.downloaded = downloaded;
+                        if let Err(e) = state.save(&dest) {
+                            warn!("Failed to save resume state: {:#}", e);
+                        }
                         break;
                     }
 
@@ -168,6 +455,16 @@ where
                             file.write_all(&chunk).await?;
                             downloaded += chunk.len() as u64;
 
+                            if let Some(delay) = speed_limit.and_then(|limit| {
+                                throttle_delay(
+                                    downloaded - stream_offset,
+                                    stream_start.elapsed(),
+                                    limit,
+                                )
+                            }) {
+                                tokio::time::sleep(delay).await;
+                            }
+
                             // Update progress
                             let now = Instant::now();
                             if now.duration_since(last_update) >= Duration::from_millis(100) {
@@ -186,17 +483,25 @@ where
                                     instant_speed
                                 };
 
-                                let state = DownloadState {
+                                let progress = DownloadState {
                                     downloaded,
                                     total: total_size,
                                     speed: avg_speed,
                                 };
 
-                                progress_callback(state);
+                                progress_callback(progress);
 
                                 last_update = now;
                                 last_downloaded = downloaded;
                             }
+
+                            if last_saved.elapsed() >= STATE_SAVE_INTERVAL {
+                                state.downloaded = downloaded;
+                                if let Err(e) = state.save(&dest) {
+                                    warn!("Failed to save resume state: {:#}", e);
+                                }
+                                last_saved = Instant::now();
+                            }
                         }
                         Err(e) => {
                             info!("Error reading chunk: {}", e);
@@ -227,19 +532,81 @@ where
 
     file.flush().await?;
     drop(file);
+    tokio::fs::rename(&part, &dest)
+        .await
+        .context("Failed to move finished download into place")?;
+    let _ = std::fs::remove_file(state_path(&dest));
 
     // Final update
-    let state = DownloadState {
+    let progress = DownloadState {
         downloaded,
         total: total_size,
         speed: 0.0,
     };
-    progress_callback(state);
+    progress_callback(progress);
 
     info!("Download completed: {}", dest_path);
     Ok(())
 }
 
+/// How long to wait so that `bytes` received in `elapsed` stay within
+/// `limit` bytes per second.
+fn throttle_delay(bytes: u64, elapsed: Duration, limit: u64) -> Option<Duration> {
+    let expected = Duration::from_secs_f64(bytes as f64 / limit as f64);
+    expected
+        .checked_sub(elapsed)
+        .filter(|delay| !delay.is_zero())
+}
+
+/// SHA-256 of the file at `path` as lowercase hex. Reads the whole file, so
+/// call it off the main thread.
+pub fn sha256(path: &Path) -> Result<String> {
+    let path = path.to_string_lossy();
+    let captured = super::exec::capture(
+        "sha256sum",
+        &["--", &path],
+        super::exec::Options {
+            timeout: Duration::from_secs(600),
+            max_bytes: 4096,
+            merge_stderr: false,
+        },
+    )?;
+    if !captured.success() {
+        anyhow::bail!("sha256sum failed: {}", captured.stderr.trim());
+    }
+    parse_sha256sum(&captured.stdout).context("Unexpected sha256sum output")
+}
+
+/// The checksum of a `sha256sum` output line.
+fn parse_sha256sum(output: &str) -> Option<String> {
+    // Escaped file names get a leading backslash
+    let hash = output.trim_start_matches('\\').split_whitespace().next()?;
+    (hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| hash.to_ascii_lowercase())
+}
+
+/// Flatpak of Impression, a writer of disk images to USB drives.
+const IMPRESSION_FLATPAK: &str = "io.gitlab.adhami3310.Impression";
+
+/// Command line opening an installed USB image writer on `image`, if any.
+pub fn usb_writer_command(image: &Path) -> Option<Vec<String>> {
+    let image = image.to_string_lossy().to_string();
+    if super::aur::is_executable_in_path("gnome-disks") {
+        return Some(vec![
+            "gnome-disks".to_string(),
+            format!("--restore-disk-image={}", image),
+        ]);
+    }
+    super::package::is_flatpak_installed(IMPRESSION_FLATPAK).then(|| {
+        vec![
+            "flatpak".to_string(),
+            "run".to_string(),
+            IMPRESSION_FLATPAK.to_string(),
+            image,
+        ]
+    })
+}
+
 /// Format bytes to human-readable string
 pub fn format_bytes(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
@@ -282,3 +649,133 @@ pub fn format_time_remaining(seconds: u64) -> String {
         format!("{}s", secs)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sha256sum() {
+        let hash = "5D2BAC3D95E6D7B5D7E19B8A47A2C9E1D9A4B1E2F3C4D5E6F708192A3B4C5D6E";
+        assert_eq!(
+            parse_sha256sum(&format!("{}  /home/user/archlinux.iso\n", hash)),
+            Some(hash.to_ascii_lowercase())
+        );
+        // File names with a backslash or newline are escaped
+        assert_eq!(
+            parse_sha256sum(&format!("\\{}  /tmp/a\\nb.iso\n", hash)),
+            Some(hash.to_ascii_lowercase())
+        );
+        assert_eq!(parse_sha256sum("abc  file\n"), None);
+        assert_eq!(parse_sha256sum(""), None);
+    }
+
+    #[test]
+    fn test_throttle_delay() {
+        // 2 KiB at 1 KiB/s should take two seconds
+        assert_eq!(
+            throttle_delay(2048, Duration::from_millis(500), 1024),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(throttle_delay(1024, Duration::from_secs(2), 1024), None);
+    }
+
+    fn saved_state() -> ResumeState {
+        ResumeState {
+            url: "https://example.org/file.iso".to_string(),
+            total: 1000,
+            etag: Some("\"abc\"".to_string()),
+            last_modified: Some("Wed, 01 Jul 2026 00:00:00 GMT".to_string()),
+            downloaded: 400,
+        }
+    }
+
+    #[test]
+    fn test_resume_offset() {
+        let state = saved_state();
+        // The partial file may be ahead of the last save
+        assert_eq!(state.resume_offset(400), Some(400));
+        assert_eq!(state.resume_offset(600), Some(600));
+        // Truncated or longer than the file itself
+        assert_eq!(state.resume_offset(300), None);
+        assert_eq!(state.resume_offset(1001), None);
+        assert_eq!(state.resume_offset(0), None);
+    }
+
+    #[test]
+    fn test_matches_remote() {
+        let state = saved_state();
+        let remote = RemoteInfo {
+            total: 1000,
+            etag: state.etag.clone(),
+            last_modified: None,
+        };
+        assert!(state.matches_remote(&remote));
+
+        let changed = RemoteInfo {
+            etag: Some("\"def\"".to_string()),
+            ..remote.clone()
+        };
+        assert!(!state.matches_remote(&changed));
+        let resized = RemoteInfo {
+            total: 2000,
+            ..remote
+        };
+        assert!(!state.matches_remote(&resized));
+
+        // Falls back to Last-Modified without an ETag
+        let state = ResumeState {
+            etag: None,
+            ..saved_state()
+        };
+        assert!(state.matches_remote(&RemoteInfo {
+            total: 0,
+            etag: None,
+            last_modified: state.last_modified.clone(),
+        }));
+
+        // Nothing to validate against
+        let state = ResumeState {
+            etag: None,
+            last_modified: None,
+            ..saved_state()
+        };
+        assert!(!state.matches_remote(&RemoteInfo::default()));
+    }
+
+    #[test]
+    fn test_find_resumable_removes_stale_states() {
+        let dir = std::env::temp_dir().join(format!("xero-download-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let valid = dir.join("valid.iso");
+        saved_state().save(&valid).unwrap();
+        std::fs::write(part_path(&valid), vec![0u8; 500]).unwrap();
+
+        // Partial file is gone
+        let orphan = dir.join("orphan.iso");
+        saved_state().save(&orphan).unwrap();
+
+        // Unreadable state
+        let broken = dir.join("broken.iso");
+        std::fs::write(state_path(&broken), "{").unwrap();
+        std::fs::write(part_path(&broken), "data").unwrap();
+
+        let found = find_resumable(&dir);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, valid);
+        assert_eq!(found[0].downloaded, 500);
+        assert_eq!(found[0].file_name(), "valid.iso");
+
+        assert!(!state_path(&orphan).exists());
+        assert!(!state_path(&broken).exists());
+        assert!(!part_path(&broken).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.50 KB");
+    }
+}
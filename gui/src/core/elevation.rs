@@ -0,0 +1,268 @@
+//! Fallbacks for root commands of the terminal dialog.
+//!
+//! The terminal dialog runs root commands through pkexec, which needs the
+//! polkit agent of the graphical session. When pkexec cannot reach one,
+//! like on some Wayland setups, it exits with 127 before running anything;
+//! run0 or sudo can still ask for the password. The xero-auth daemon
+//! cannot stand in, as the commands it runs get no input.
+
+use super::aur::is_executable_in_path;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+
+/// Program the terminal dialog is given for root commands.
+pub const PKEXEC: &str = "pkexec";
+
+/// Exit code of pkexec when it could not authorize the user, other than
+/// by the user dismissing the dialog, which gives 126.
+const PKEXEC_AUTH_FAILED: i32 = 127;
+
+/// Graphical askpass helpers for `sudo -A`, in order of preference.
+const ASKPASS_HELPERS: &[&str] = &[
+    "/usr/bin/ksshaskpass",
+    "/usr/lib/ssh/ssh-askpass",
+    "/usr/bin/lxqt-openssh-askpass",
+    "/usr/bin/ssh-askpass",
+];
+
+/// Why the session may not show a password dialog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionIssue {
+    /// Neither `DISPLAY` nor `WAYLAND_DISPLAY` is set
+    NoDisplay,
+    /// `XDG_SESSION_TYPE` names a session whose display variable is unset
+    MissingDisplay {
+        session_type: String,
+        variable: &'static str,
+    },
+}
+
+/// Check the display variables, reading them with `var`.
+pub fn session_issue(var: impl Fn(&str) -> Option<String>) -> Option<SessionIssue> {
+    let set = |name: &str| var(name).is_some_and(|value| !value.is_empty());
+    let session_type = var("XDG_SESSION_TYPE").unwrap_or_default();
+
+    let variable = match session_type.as_str() {
+        "wayland" => "WAYLAND_DISPLAY",
+        "x11" => "DISPLAY",
+        _ if set("DISPLAY") || set("WAYLAND_DISPLAY") => return None,
+        _ => return Some(SessionIssue::NoDisplay),
+    };
+    (!set(variable)).then_some(SessionIssue::MissingDisplay {
+        session_type,
+        variable,
+    })
+}
+
+/// Programs a failed pkexec command can be retried with.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Tools {
+    pub run0: bool,
+    pub sudo: bool,
+    /// Askpass helper for `sudo -A`, from `SUDO_ASKPASS` or a known one
+    pub askpass: Option<PathBuf>,
+}
+
+impl Tools {
+    pub fn detect() -> Self {
+        let askpass = std::env::var_os("SUDO_ASKPASS")
+            .map(PathBuf::from)
+            .into_iter()
+            .chain(ASKPASS_HELPERS.iter().map(PathBuf::from))
+            .find(|path| path.is_absolute() && path.is_file());
+        Self {
+            run0: is_executable_in_path("run0"),
+            sudo: is_executable_in_path("sudo"),
+            askpass,
+        }
+    }
+}
+
+/// A way to run a root command without pkexec.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fallback {
+    /// systemd's run0, asking through polkit or in the terminal
+    Run0,
+    /// sudo with a graphical askpass helper
+    SudoAskpass { askpass: PathBuf },
+    /// sudo asking in the terminal
+    Sudo,
+}
+
+impl Fallback {
+    /// Command line running `args` as root.
+    pub fn argv(&self, args: &[String]) -> Vec<String> {
+        let mut argv: Vec<String> = match self {
+            Self::Run0 => vec!["run0".to_string()],
+            Self::SudoAskpass { askpass } => vec![
+                "env".to_string(),
+                format!("SUDO_ASKPASS={}", askpass.display()),
+                "sudo".to_string(),
+                "-A".to_string(),
+            ],
+            Self::Sudo => vec!["sudo".to_string()],
+        };
+        argv.extend(args.iter().cloned());
+        argv
+    }
+}
+
+/// What to retry a pkexec command with, given its `exit_code`.
+///
+/// Only an authorization failure is retried: the user dismissing the
+/// dialog or the command itself failing is final. A graphical askpass
+/// helper needs a working display, so without one sudo asks in the
+/// terminal instead.
+pub fn fallback(
+    exit_code: Option<i32>,
+    session: Option<&SessionIssue>,
+    tools: &Tools,
+) -> Option<Fallback> {
+    if exit_code != Some(PKEXEC_AUTH_FAILED) {
+        return None;
+    }
+    if tools.run0 {
+        return Some(Fallback::Run0);
+    }
+    if !tools.sudo {
+        return None;
+    }
+    match (&tools.askpass, session) {
+        (Some(askpass), None) => Some(Fallback::SudoAskpass {
+            askpass: askpass.clone(),
+        }),
+        _ => Some(Fallback::Sudo),
+    }
+}
+
+/// Whether `program` is pkexec, by name or path.
+pub fn is_pkexec(program: &str) -> bool {
+    Path::new(program).file_name() == Some(PKEXEC.as_ref())
+}
+
+/// Exit code of a child from its raw wait status, `None` if it was killed.
+pub fn exit_code(wait_status: i32) -> Option<i32> {
+    ExitStatus::from_raw(wait_status).code()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn issue(vars: &[(&str, &str)]) -> Option<SessionIssue> {
+        let vars: HashMap<&str, &str> = vars.iter().copied().collect();
+        session_issue(|name| vars.get(name).map(|value| value.to_string()))
+    }
+
+    #[test]
+    fn test_session_issue() {
+        assert_eq!(
+            issue(&[
+                ("XDG_SESSION_TYPE", "wayland"),
+                ("WAYLAND_DISPLAY", "wayland-0")
+            ]),
+            None
+        );
+        assert_eq!(
+            issue(&[("XDG_SESSION_TYPE", "x11"), ("DISPLAY", ":0")]),
+            None
+        );
+        // Unknown session types only need some display
+        assert_eq!(
+            issue(&[("XDG_SESSION_TYPE", "tty"), ("DISPLAY", ":1")]),
+            None
+        );
+        assert_eq!(
+            issue(&[("XDG_SESSION_TYPE", "wayland"), ("DISPLAY", ":0")]),
+            Some(SessionIssue::MissingDisplay {
+                session_type: "wayland".to_string(),
+                variable: "WAYLAND_DISPLAY"
+            })
+        );
+        assert_eq!(
+            issue(&[("XDG_SESSION_TYPE", "x11"), ("DISPLAY", "")]),
+            Some(SessionIssue::MissingDisplay {
+                session_type: "x11".to_string(),
+                variable: "DISPLAY"
+            })
+        );
+        assert_eq!(issue(&[]), Some(SessionIssue::NoDisplay));
+    }
+
+    #[test]
+    fn test_fallback() {
+        let askpass = PathBuf::from("/usr/bin/ksshaskpass");
+        let all = Tools {
+            run0: true,
+            sudo: true,
+            askpass: Some(askpass.clone()),
+        };
+        let sudo_only = Tools {
+            run0: false,
+            ..all.clone()
+        };
+
+        assert_eq!(fallback(Some(127), None, &all), Some(Fallback::Run0));
+        assert_eq!(
+            fallback(Some(127), None, &sudo_only),
+            Some(Fallback::SudoAskpass { askpass })
+        );
+        // No display for the askpass helper
+        assert_eq!(
+            fallback(Some(127), Some(&SessionIssue::NoDisplay), &sudo_only),
+            Some(Fallback::Sudo)
+        );
+        assert_eq!(
+            fallback(
+                Some(127),
+                None,
+                &Tools {
+                    askpass: None,
+                    ..sudo_only.clone()
+                }
+            ),
+            Some(Fallback::Sudo)
+        );
+        assert_eq!(fallback(Some(127), None, &Tools::default()), None);
+
+        // Dismissed, succeeded, failed by itself or killed
+        for exit_code in [Some(126), Some(0), Some(1), None] {
+            assert_eq!(fallback(exit_code, None, &all), None);
+        }
+    }
+
+    #[test]
+    fn test_fallback_argv() {
+        let args = ["tailscale".to_string(), "up".to_string()];
+        assert_eq!(Fallback::Run0.argv(&args), ["run0", "tailscale", "up"]);
+        assert_eq!(Fallback::Sudo.argv(&args), ["sudo", "tailscale", "up"]);
+        assert_eq!(
+            Fallback::SudoAskpass {
+                askpass: PathBuf::from("/usr/lib/ssh/ssh-askpass")
+            }
+            .argv(&args),
+            [
+                "env",
+                "SUDO_ASKPASS=/usr/lib/ssh/ssh-askpass",
+                "sudo",
+                "-A",
+                "tailscale",
+                "up"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_pkexec_and_exit_code() {
+        assert!(is_pkexec("pkexec"));
+        assert!(is_pkexec("/usr/bin/pkexec"));
+        assert!(!is_pkexec("/usr/local/bin/upd"));
+
+        assert_eq!(exit_code(0), Some(0));
+        assert_eq!(exit_code(127 << 8), Some(127));
+        // Killed by SIGKILL
+        assert_eq!(exit_code(9), None);
+    }
+}
@@ -0,0 +1,234 @@
+//! Events other parts of the app can observe.
+//!
+//! Task runs, downloads and settings changes are announced here, so
+//! features reacting to them, like badges or buttons showing an installed
+//! state, subscribe instead of hooking into the code that runs them.
+//! Events are delivered on the main thread, in the order they were sent;
+//! one sent by a subscriber is delivered once the current one reached
+//! everyone.
+
+use log::debug;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Something that happened in the app.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// A task run opened its dialog.
+    TaskStarted { title: String },
+    /// A task run ended, `success` only if every step succeeded.
+    TaskFinished {
+        title: String,
+        success: bool,
+        duration: Duration,
+    },
+    /// A download was saved to `path`.
+    DownloadFinished { path: PathBuf },
+    /// A setting changed, with `key` like `seasonal.intensity`.
+    SettingsChanged { key: String },
+}
+
+type Handler = Rc<dyn Fn(&Event)>;
+
+#[derive(Default)]
+struct Dispatcher {
+    next_id: Cell<u64>,
+    handlers: RefCell<Vec<(u64, Handler)>>,
+    /// Events sent while others were being delivered
+    queue: RefCell<VecDeque<Event>>,
+    dispatching: Cell<bool>,
+}
+
+impl Dispatcher {
+    fn is_subscribed(&self, id: u64) -> bool {
+        self.handlers.borrow().iter().any(|(other, _)| *other == id)
+    }
+}
+
+thread_local! {
+    static DISPATCHER: Dispatcher = Dispatcher::default();
+}
+
+/// A subscription; dropping it unsubscribes.
+#[must_use = "dropping a subscription ends it right away"]
+#[derive(Debug)]
+pub struct Subscription {
+    id: u64,
+}
+
+impl Subscription {
+    /// Keep the subscription for the rest of the session.
+    pub fn detach(self) {
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        // The dispatcher may already be gone when the thread exits
+        let _ = DISPATCHER.try_with(|dispatcher| {
+            dispatcher
+                .handlers
+                .borrow_mut()
+                .retain(|(id, _)| *id != self.id);
+        });
+    }
+}
+
+/// Call `handler` with every event sent until the returned subscription
+/// is dropped.
+pub fn subscribe<F>(handler: F) -> Subscription
+where
+    F: Fn(&Event) + 'static,
+{
+    DISPATCHER.with(|dispatcher| {
+        let id = dispatcher.next_id.get();
+        dispatcher.next_id.set(id + 1);
+        dispatcher
+            .handlers
+            .borrow_mut()
+            .push((id, Rc::new(handler)));
+        Subscription { id }
+    })
+}
+
+/// Send `event` to the subscribers. Call on the main thread.
+pub fn emit(event: Event) {
+    DISPATCHER.with(|dispatcher| {
+        dispatcher.queue.borrow_mut().push_back(event);
+        // A subscriber sending an event; the loop below delivers it next
+        if dispatcher.dispatching.replace(true) {
+            return;
+        }
+
+        loop {
+            let Some(event) = dispatcher.queue.borrow_mut().pop_front() else {
+                break;
+            };
+            debug!("Event: {:?}", event);
+            // Cloned first, so handlers may subscribe and unsubscribe
+            let handlers = dispatcher.handlers.borrow().clone();
+            for (id, handler) in handlers {
+                // Skip handlers ended by an earlier one
+                if dispatcher.is_subscribed(id) {
+                    handler(&event);
+                }
+            }
+        }
+        dispatcher.dispatching.set(false);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Events seen by a subscriber, as short labels.
+    fn recorder() -> (Rc<RefCell<Vec<String>>>, impl Fn(&Event) + 'static) {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let seen_clone = seen.clone();
+        (seen, move |event: &Event| {
+            seen_clone.borrow_mut().push(label(event))
+        })
+    }
+
+    fn label(event: &Event) -> String {
+        match event {
+            Event::TaskStarted { title } => format!("started {}", title),
+            Event::TaskFinished { title, success, .. } => format!("finished {} {}", title, success),
+            Event::DownloadFinished { path } => format!("downloaded {}", path.display()),
+            Event::SettingsChanged { key } => format!("changed {}", key),
+        }
+    }
+
+    fn changed(key: &str) -> Event {
+        Event::SettingsChanged {
+            key: key.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_subscription_lifetime() {
+        let (seen, handler) = recorder();
+        let subscription = subscribe(handler);
+        emit(changed("a"));
+        drop(subscription);
+        emit(changed("b"));
+
+        let (detached_seen, handler) = recorder();
+        subscribe(handler).detach();
+        emit(changed("c"));
+
+        let (unsubscribed_seen, handler) = recorder();
+        drop(subscribe(handler));
+        emit(changed("d"));
+
+        assert_eq!(*seen.borrow(), ["changed a"]);
+        assert_eq!(*detached_seen.borrow(), ["changed c", "changed d"]);
+        assert!(unsubscribed_seen.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_reentrant_emit_is_delivered_after_current_event() {
+        let (seen, handler) = recorder();
+        let first = subscribe(move |event| {
+            handler(event);
+            if let Event::TaskStarted { .. } = event {
+                emit(Event::DownloadFinished {
+                    path: PathBuf::from("/tmp/arch.iso"),
+                });
+            }
+        });
+        let seen_clone = seen.clone();
+        let second =
+            subscribe(move |event| seen_clone.borrow_mut().push(format!("2: {}", label(event))));
+
+        emit(Event::TaskStarted {
+            title: "Install".to_string(),
+        });
+        assert_eq!(
+            *seen.borrow(),
+            [
+                "started Install",
+                "2: started Install",
+                "downloaded /tmp/arch.iso",
+                "2: downloaded /tmp/arch.iso"
+            ]
+        );
+        drop((first, second));
+    }
+
+    #[test]
+    fn test_subscribing_and_unsubscribing_during_dispatch() {
+        let (later_seen, later_handler) = recorder();
+        let later_handler = Rc::new(later_handler);
+        let late = Rc::new(RefCell::new(None::<Subscription>));
+        let victim = Rc::new(RefCell::new(None::<Subscription>));
+
+        // Subscribes another handler and ends the one after it
+        let late_clone = late.clone();
+        let victim_clone = victim.clone();
+        let first = subscribe(move |_| {
+            victim_clone.borrow_mut().take();
+            if late_clone.borrow().is_none() {
+                let handler = later_handler.clone();
+                late_clone.replace(Some(subscribe(move |event| handler(event))));
+            }
+        });
+        let (victim_seen, handler) = recorder();
+        victim.replace(Some(subscribe(handler)));
+
+        emit(changed("a"));
+        // A new subscriber only gets the events after it joined
+        assert!(later_seen.borrow().is_empty());
+        emit(changed("b"));
+
+        assert!(victim_seen.borrow().is_empty());
+        assert_eq!(*later_seen.borrow(), ["changed b"]);
+        drop(first);
+        late.borrow_mut().take();
+    }
+}
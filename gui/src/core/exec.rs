@@ -0,0 +1,300 @@
+//! Output of quick queries run by the pages.
+//!
+//! Pages ask tools like scxctl, pacman or systemctl for their state while
+//! they are set up. A tool that hangs or prints without end must not freeze
+//! the page, so every query runs with a timeout after which the child and
+//! its own children are killed, and keeps only the start of its output.
+
+use anyhow::{Context, Result};
+use gtk4::gio;
+use std::io::Read;
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How often a running query is checked on.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Limits of a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// Time after which the query is killed
+    pub timeout: Duration,
+    /// Bytes kept of stdout and of stderr each, the rest is dropped
+    pub max_bytes: usize,
+    /// Read stderr into stdout, in the order it was written
+    pub merge_stderr: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            max_bytes: 1024 * 1024,
+            merge_stderr: false,
+        }
+    }
+}
+
+/// Output of a query that ran to its end.
+#[derive(Debug, Clone)]
+pub struct Captured {
+    pub status: ExitStatus,
+    pub stdout: String,
+    /// Empty when merged into stdout
+    pub stderr: String,
+    /// Whether output beyond `max_bytes` was dropped
+    pub truncated: bool,
+}
+
+impl Captured {
+    pub fn success(&self) -> bool {
+        self.status.success()
+    }
+
+    /// Trimmed stdout if the query succeeded.
+    pub fn stdout_if_success(self) -> Option<String> {
+        self.success().then(|| self.stdout.trim().to_string())
+    }
+}
+
+/// Error of a query killed for running past its timeout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimedOut {
+    pub program: String,
+    pub timeout: Duration,
+}
+
+impl std::fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} did not finish within {:.1}s",
+            self.program,
+            self.timeout.as_secs_f64()
+        )
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Read `source` to its end on a thread, keeping the first `max_bytes`.
+///
+/// The rest is still read, so the child never blocks on a full pipe.
+fn read_capped(
+    mut source: impl Read + Send + 'static,
+    max_bytes: usize,
+) -> JoinHandle<(Vec<u8>, bool)> {
+    std::thread::spawn(move || {
+        let mut kept = Vec::new();
+        let mut truncated = false;
+        let mut buffer = [0u8; 8192];
+        loop {
+            let read = match source.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            };
+            let room = max_bytes.saturating_sub(kept.len());
+            kept.extend_from_slice(&buffer[..read.min(room)]);
+            truncated |= read > room;
+        }
+        (kept, truncated)
+    })
+}
+
+fn join(reader: Option<JoinHandle<(Vec<u8>, bool)>>) -> (String, bool) {
+    let (bytes, truncated) = reader
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+    (String::from_utf8_lossy(&bytes).into_owned(), truncated)
+}
+
+/// Wait for `child` until `timeout` has passed, then kill its process
+/// group.
+fn wait(child: &mut Child, program: &str, timeout: Duration) -> Result<ExitStatus> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if Instant::now() >= deadline {
+            // The child leads its own group, so helpers it started go too
+            unsafe {
+                libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+            }
+            let _ = child.wait();
+            return Err(TimedOut {
+                program: program.to_string(),
+                timeout,
+            }
+            .into());
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Run `program` with `args` and capture its output within `options`.
+///
+/// Fails if the program cannot be started or is killed for running past
+/// the timeout, in which case the error downcasts to [`TimedOut`]. A
+/// program exiting with an error is not a failure; check
+/// [`Captured::success`].
+pub fn capture(program: &str, args: &[&str], options: Options) -> Result<Captured> {
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .process_group(0);
+    if options.merge_stderr {
+        let (reader, writer) = std::io::pipe().context("Failed to create output pipe")?;
+        command
+            .stdout(writer.try_clone().context("Failed to create output pipe")?)
+            .stderr(writer);
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("Failed to run {}", program))?;
+        // Close the write ends held by the command, so reading ends with
+        // the child
+        drop(command);
+        let stdout = Some(read_capped(reader, options.max_bytes));
+        let status = wait(&mut child, program, options.timeout)?;
+        let (stdout, truncated) = join(stdout);
+        return Ok(Captured {
+            status,
+            stdout,
+            stderr: String::new(),
+            truncated,
+        });
+    }
+
+    command.stderr(Stdio::piped());
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+    let stdout = child
+        .stdout
+        .take()
+        .map(|source| read_capped(source, options.max_bytes));
+    let stderr = child
+        .stderr
+        .take()
+        .map(|source| read_capped(source, options.max_bytes));
+    let status = wait(&mut child, program, options.timeout)?;
+    let (stdout, stdout_truncated) = join(stdout);
+    let (stderr, stderr_truncated) = join(stderr);
+    Ok(Captured {
+        status,
+        stdout,
+        stderr,
+        truncated: stdout_truncated || stderr_truncated,
+    })
+}
+
+/// [`capture`] on a worker thread, to await inside glib futures.
+pub async fn capture_async(program: &str, args: &[&str], options: Options) -> Result<Captured> {
+    let program = program.to_string();
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    gio::spawn_blocking(move || {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        capture(&program, &args, options)
+    })
+    .await
+    .unwrap_or_else(|_| Err(anyhow::anyhow!("Query thread panicked")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture() {
+        let captured =
+            capture("sh", &["-c", "echo out; echo err >&2"], Options::default()).unwrap();
+        assert!(captured.success());
+        assert_eq!(captured.stdout, "out\n");
+        assert_eq!(captured.stderr, "err\n");
+        assert!(!captured.truncated);
+
+        let captured = capture("sh", &["-c", "echo failed; exit 3"], Options::default()).unwrap();
+        assert_eq!(captured.status.code(), Some(3));
+        assert_eq!(captured.stdout_if_success(), None);
+
+        assert!(capture("xero-no-such-program", &[], Options::default()).is_err());
+    }
+
+    #[test]
+    fn test_merge_stderr() {
+        let captured = capture(
+            "sh",
+            &["-c", "echo one; echo two >&2; echo three"],
+            Options {
+                merge_stderr: true,
+                ..Options::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(captured.stdout, "one\ntwo\nthree\n");
+        assert_eq!(captured.stderr, "");
+    }
+
+    #[test]
+    fn test_timeout_kills() {
+        let started = Instant::now();
+        // The sleep is a child of the shell and holds the pipe open
+        let error = capture(
+            "sh",
+            &["-c", "sleep 30; echo done"],
+            Options {
+                timeout: Duration::from_millis(200),
+                ..Options::default()
+            },
+        )
+        .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(
+            error.downcast_ref::<TimedOut>(),
+            Some(&TimedOut {
+                program: "sh".to_string(),
+                timeout: Duration::from_millis(200)
+            })
+        );
+    }
+
+    #[test]
+    fn test_truncation() {
+        // Far more than a pipe holds, so the child only ends if it is
+        // drained
+        let captured = capture(
+            "sh",
+            &[
+                "-c",
+                "head -c 1000000 /dev/zero | tr '\\0' x; echo tail >&2",
+            ],
+            Options {
+                max_bytes: 100,
+                ..Options::default()
+            },
+        )
+        .unwrap();
+        assert!(captured.success());
+        assert_eq!(captured.stdout, "x".repeat(100));
+        assert_eq!(captured.stderr, "tail\n");
+        assert!(captured.truncated);
+
+        let captured = capture(
+            "echo",
+            &["12345"],
+            Options {
+                max_bytes: 6,
+                ..Options::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(captured.stdout, "12345\n");
+        assert!(!captured.truncated);
+    }
+}
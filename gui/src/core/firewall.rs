@@ -0,0 +1,328 @@
+//! Firewall backend detection and port rule helpers.
+//!
+//! Supports ufw and firewalld, the two frontends shipped in the Arch repos.
+
+use log::{debug, info};
+
+/// Firewall frontends the toolkit knows how to configure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FirewallBackend {
+    Ufw,
+    Firewalld,
+}
+
+/// A port or inclusive port range for one protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortRule {
+    pub first: u16,
+    pub last: u16,
+    pub protocol: &'static str,
+}
+
+impl PortRule {
+    pub const fn single(port: u16, protocol: &'static str) -> Self {
+        Self::range(port, port, protocol)
+    }
+
+    pub const fn range(first: u16, last: u16, protocol: &'static str) -> Self {
+        Self {
+            first,
+            last,
+            protocol,
+        }
+    }
+}
+
+/// A named set of ports users commonly need reachable.
+#[derive(Debug, Clone, Copy)]
+pub struct Allowance {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub description: &'static str,
+    pub rules: &'static [PortRule],
+}
+
+/// Allowances offered during firewall setup.
+pub const ALLOWANCES: &[Allowance] = &[
+    Allowance {
+        id: "ssh",
+        label: "SSH",
+        description: "Remote shell access on port 22",
+        rules: &[PortRule::single(22, "tcp")],
+    },
+    Allowance {
+        id: "kdeconnect",
+        label: "KDE Connect",
+        description: "Phone integration on ports 1714-1764 (TCP and UDP)",
+        rules: &[
+            PortRule::range(1714, 1764, "tcp"),
+            PortRule::range(1714, 1764, "udp"),
+        ],
+    },
+    Allowance {
+        id: "jellyfin",
+        label: "Jellyfin",
+        description: "Media server web interface on port 8096",
+        rules: &[PortRule::single(8096, "tcp")],
+    },
+];
+
+/// One privileged command in a firewall setup plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FirewallStep {
+    pub program: &'static str,
+    pub args: Vec<String>,
+    pub description: String,
+}
+
+impl FirewallStep {
+    fn new(program: &'static str, args: &[&str], description: impl Into<String>) -> Self {
+        Self {
+            program,
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            description: description.into(),
+        }
+    }
+}
+
+impl FirewallBackend {
+    /// Package providing this firewall.
+    pub fn package(&self) -> &'static str {
+        match self {
+            Self::Ufw => "ufw",
+            Self::Firewalld => "firewalld",
+        }
+    }
+
+    /// Systemd unit backing this firewall.
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Self::Ufw => "ufw.service",
+            Self::Firewalld => "firewalld.service",
+        }
+    }
+
+    /// Program and arguments that permanently allow a port rule.
+    pub fn allow_command(&self, rule: &PortRule) -> (&'static str, Vec<String>) {
+        match self {
+            Self::Ufw => {
+                let ports = if rule.first == rule.last {
+                    rule.first.to_string()
+                } else {
+                    format!("{}:{}", rule.first, rule.last)
+                };
+                (
+                    "ufw",
+                    vec!["allow".into(), format!("{}/{}", ports, rule.protocol)],
+                )
+            }
+            Self::Firewalld => {
+                let ports = if rule.first == rule.last {
+                    rule.first.to_string()
+                } else {
+                    format!("{}-{}", rule.first, rule.last)
+                };
+                (
+                    "firewall-cmd",
+                    vec![
+                        "--permanent".into(),
+                        format!("--add-port={}/{}", ports, rule.protocol),
+                    ],
+                )
+            }
+        }
+    }
+
+    /// Program and arguments that print the active configuration.
+    pub fn status_command(&self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            Self::Ufw => ("ufw", &["status", "verbose"]),
+            Self::Firewalld => ("firewall-cmd", &["--list-all"]),
+        }
+    }
+
+    /// Privileged steps that set defaults, apply `allowances` and activate
+    /// the firewall. Package installation is left to the caller.
+    pub fn setup_plan(&self, allowances: &[&Allowance]) -> Vec<FirewallStep> {
+        let mut steps = Vec::new();
+
+        // firewalld's default zone already rejects unsolicited incoming traffic
+        if *self == Self::Ufw {
+            steps.push(FirewallStep::new(
+                "ufw",
+                &["default", "deny", "incoming"],
+                "Denying incoming connections by default...",
+            ));
+            steps.push(FirewallStep::new(
+                "ufw",
+                &["default", "allow", "outgoing"],
+                "Allowing outgoing connections by default...",
+            ));
+        }
+
+        steps.push(FirewallStep::new(
+            "systemctl",
+            &["enable", "--now", self.unit()],
+            format!("Enabling {}...", self.unit()),
+        ));
+
+        for allowance in allowances {
+            for rule in allowance.rules {
+                let (program, args) = self.allow_command(rule);
+                steps.push(FirewallStep {
+                    program,
+                    args,
+                    description: format!("Allowing {} ({})...", allowance.label, rule.protocol),
+                });
+            }
+        }
+
+        steps.push(match self {
+            Self::Ufw => FirewallStep::new("ufw", &["--force", "enable"], "Activating ufw..."),
+            Self::Firewalld => FirewallStep::new(
+                "firewall-cmd",
+                &["--reload"],
+                "Reloading firewalld rules...",
+            ),
+        });
+
+        steps
+    }
+}
+
+/// Detect the active firewall, if any.
+///
+/// Uses `systemctl is-active` since `ufw status` needs root.
+pub fn detect_active() -> Option<FirewallBackend> {
+    let output = std::process::Command::new("systemctl")
+        .args([
+            "is-active",
+            FirewallBackend::Ufw.unit(),
+            FirewallBackend::Firewalld.unit(),
+        ])
+        .output()
+        .ok()?;
+
+    let backend = parse_is_active(&String::from_utf8_lossy(&output.stdout));
+    info!("Active firewall backend: {:?}", backend);
+    backend
+}
+
+/// Pick the backend to configure.
+///
+/// An active firewall is kept; otherwise an installed firewalld is reused,
+/// and ufw is the default for systems without either.
+pub fn choose_backend(
+    active: Option<FirewallBackend>,
+    ufw_installed: bool,
+    firewalld_installed: bool,
+) -> FirewallBackend {
+    match active {
+        Some(backend) => backend,
+        None if firewalld_installed && !ufw_installed => FirewallBackend::Firewalld,
+        None => FirewallBackend::Ufw,
+    }
+}
+
+/// Parse `systemctl is-active ufw.service firewalld.service` output.
+///
+/// systemctl prints one state per unit, in argument order.
+fn parse_is_active(output: &str) -> Option<FirewallBackend> {
+    let mut states = output.lines().map(str::trim);
+    let ufw = states.next();
+    let firewalld = states.next();
+    debug!(
+        "Firewall unit states: ufw={:?} firewalld={:?}",
+        ufw, firewalld
+    );
+
+    if ufw == Some("active") {
+        Some(FirewallBackend::Ufw)
+    } else if firewalld == Some("active") {
+        Some(FirewallBackend::Firewalld)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_is_active() {
+        assert_eq!(
+            parse_is_active("active\ninactive\n"),
+            Some(FirewallBackend::Ufw)
+        );
+        assert_eq!(
+            parse_is_active("inactive\nactive\n"),
+            Some(FirewallBackend::Firewalld)
+        );
+        assert_eq!(parse_is_active("inactive\ninactive\n"), None);
+        assert_eq!(parse_is_active("unknown\nfailed\n"), None);
+        assert_eq!(parse_is_active(""), None);
+    }
+
+    #[test]
+    fn test_allow_single_port() {
+        let rule = PortRule::single(8096, "tcp");
+        let (program, args) = FirewallBackend::Ufw.allow_command(&rule);
+        assert_eq!(program, "ufw");
+        assert_eq!(args, vec!["allow", "8096/tcp"]);
+
+        let (program, args) = FirewallBackend::Firewalld.allow_command(&rule);
+        assert_eq!(program, "firewall-cmd");
+        assert_eq!(args, vec!["--permanent", "--add-port=8096/tcp"]);
+    }
+
+    #[test]
+    fn test_allow_port_range() {
+        let rule = PortRule::range(1714, 1764, "udp");
+        assert_eq!(
+            FirewallBackend::Ufw.allow_command(&rule).1,
+            vec!["allow", "1714:1764/udp"]
+        );
+        assert_eq!(
+            FirewallBackend::Firewalld.allow_command(&rule).1,
+            vec!["--permanent", "--add-port=1714-1764/udp"]
+        );
+    }
+
+    #[test]
+    fn test_choose_backend() {
+        use FirewallBackend::*;
+        assert_eq!(choose_backend(Some(Firewalld), true, true), Firewalld);
+        assert_eq!(choose_backend(None, false, true), Firewalld);
+        assert_eq!(choose_backend(None, true, true), Ufw);
+        assert_eq!(choose_backend(None, false, false), Ufw);
+    }
+
+    #[test]
+    fn test_ufw_setup_plan() {
+        let ssh = &ALLOWANCES[0];
+        let steps = FirewallBackend::Ufw.setup_plan(&[ssh]);
+        let commands: Vec<String> = steps
+            .iter()
+            .map(|step| format!("{} {}", step.program, step.args.join(" ")))
+            .collect();
+        assert_eq!(
+            commands,
+            vec![
+                "ufw default deny incoming",
+                "ufw default allow outgoing",
+                "systemctl enable --now ufw.service",
+                "ufw allow 22/tcp",
+                "ufw --force enable",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_firewalld_setup_plan() {
+        let steps = FirewallBackend::Firewalld.setup_plan(&[]);
+        assert_eq!(steps.first().unwrap().program, "systemctl");
+        assert_eq!(steps.last().unwrap().args, vec!["--reload"]);
+        assert!(steps.iter().all(|step| step.program != "ufw"));
+    }
+}
@@ -0,0 +1,151 @@
+//! Curated font packages and the fixed-width font setting of desktops.
+
+use crate::core::distro::Desktop;
+use crate::i18n::gettext_noop;
+
+/// A font package offered by the font installer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FontPackage {
+    pub package: &'static str,
+    pub label: &'static str,
+    pub description: &'static str,
+    /// Family the preview is rendered in
+    pub family: &'static str,
+    /// Text of the preview
+    pub sample: &'static str,
+    /// Whether the family suits as the fixed-width font of the system
+    pub monospace: bool,
+}
+
+const CODE_SAMPLE: &str = "fn main() { 0O 1lI => != }";
+const TEXT_SAMPLE: &str = "The quick brown fox jumps over the lazy dog";
+
+pub const FONTS: &[FontPackage] = &[
+    FontPackage {
+        package: "ttf-meslo-nerd",
+        label: "MesloLGS Nerd Font",
+        description: gettext_noop("The font Powerlevel10k prompts are designed for"),
+        family: "MesloLGS Nerd Font",
+        sample: CODE_SAMPLE,
+        monospace: true,
+    },
+    FontPackage {
+        package: "ttf-jetbrains-mono-nerd",
+        label: "JetBrains Mono Nerd Font",
+        description: gettext_noop("Coding font with ligatures and terminal icons"),
+        family: "JetBrainsMono Nerd Font",
+        sample: CODE_SAMPLE,
+        monospace: true,
+    },
+    FontPackage {
+        package: "ttf-firacode-nerd",
+        label: "Fira Code Nerd Font",
+        description: gettext_noop("Coding font with ligatures and terminal icons"),
+        family: "FiraCode Nerd Font",
+        sample: CODE_SAMPLE,
+        monospace: true,
+    },
+    FontPackage {
+        package: "ttf-liberation",
+        label: "Liberation",
+        description: gettext_noop(
+            "Same metrics as Arial, Times New Roman and Courier New, so documents keep their layout",
+        ),
+        family: "Liberation Sans",
+        sample: TEXT_SAMPLE,
+        monospace: false,
+    },
+    FontPackage {
+        package: "ttf-carlito",
+        label: "Carlito",
+        description: gettext_noop("Same metrics as Calibri, the default of Microsoft Office"),
+        family: "Carlito",
+        sample: TEXT_SAMPLE,
+        monospace: false,
+    },
+    FontPackage {
+        package: "noto-fonts-emoji",
+        label: "Noto Color Emoji",
+        description: gettext_noop("Color emoji for browsers, chats and terminals"),
+        family: "Noto Color Emoji",
+        sample: "😀 🎉 🚀 🐧 ❤️",
+        monospace: false,
+    },
+];
+
+/// Look up a curated font by package name.
+pub fn find(package: &str) -> Option<&'static FontPackage> {
+    FONTS.iter().find(|font| font.package == package)
+}
+
+/// Program and arguments making `family` the fixed-width font of `desktop`,
+/// `None` for desktops without a known setting.
+pub fn fixed_width_setting(desktop: &Desktop, family: &str) -> Option<(&'static str, Vec<String>)> {
+    match desktop {
+        // QFont::toString() of a regular 10pt font
+        Desktop::Plasma => Some((
+            "kwriteconfig6",
+            vec![
+                "--file".to_string(),
+                "kdeglobals".to_string(),
+                "--group".to_string(),
+                "General".to_string(),
+                "--key".to_string(),
+                "fixed".to_string(),
+                format!("{},10,-1,5,50,0,0,0,0,0", family),
+            ],
+        )),
+        Desktop::Gnome => Some((
+            "gsettings",
+            vec![
+                "set".to_string(),
+                "org.gnome.desktop.interface".to_string(),
+                "monospace-font-name".to_string(),
+                format!("{} 11", family),
+            ],
+        )),
+        Desktop::Other(_) | Desktop::Unknown => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fonts() {
+        for font in FONTS {
+            assert_eq!(find(font.package), Some(font));
+        }
+        assert_eq!(find("ttf-ms-win11-auto"), None);
+        assert!(FONTS.iter().any(|font| font.monospace));
+    }
+
+    #[test]
+    fn test_fixed_width_setting() {
+        let (program, args) = fixed_width_setting(&Desktop::Plasma, "MesloLGS Nerd Font").unwrap();
+        assert_eq!(program, "kwriteconfig6");
+        assert_eq!(
+            args.last().unwrap(),
+            "MesloLGS Nerd Font,10,-1,5,50,0,0,0,0,0"
+        );
+
+        let (program, args) = fixed_width_setting(&Desktop::Gnome, "FiraCode Nerd Font").unwrap();
+        assert_eq!(program, "gsettings");
+        assert_eq!(
+            args,
+            [
+                "set",
+                "org.gnome.desktop.interface",
+                "monospace-font-name",
+                "FiraCode Nerd Font 11"
+            ]
+        );
+
+        assert_eq!(
+            fixed_width_setting(&Desktop::Other("XFCE".to_string()), "Carlito"),
+            None
+        );
+        assert_eq!(fixed_width_setting(&Desktop::Unknown, "Carlito"), None);
+    }
+}
@@ -0,0 +1,438 @@
+//! Filesystem detection, free space and btrfs usage parsing.
+
+use log::warn;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+/// Space accounting for one btrfs block group type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockGroupUsage {
+    pub size: u64,
+    pub used: u64,
+}
+
+/// Parsed `btrfs filesystem usage -b` output.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BtrfsUsage {
+    pub device_size: u64,
+    pub unallocated: u64,
+    pub data: BlockGroupUsage,
+    pub metadata: BlockGroupUsage,
+    pub system: BlockGroupUsage,
+}
+
+/// Bytes available to unprivileged users on the filesystem holding `path`.
+pub fn free_space(path: &str) -> std::io::Result<u64> {
+    let path = CString::new(path)?;
+    // SAFETY: statvfs only writes to the struct it is given
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// Filesystem type as reported by `statfs`, for the types a download
+/// target is likely to be on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsType {
+    /// FAT12/16/32, as most USB sticks come formatted
+    Fat,
+    ExFat,
+    /// The ntfs3 driver
+    Ntfs,
+    /// FUSE mounts, like ntfs-3g
+    Fuse,
+    Ext4,
+    Btrfs,
+    Xfs,
+    Tmpfs,
+    Other(i64),
+}
+
+/// `f_type` magic numbers from linux/magic.h, with the largest file each
+/// type holds. Types without a practical limit have none.
+const FS_TYPES: &[(i64, FsType, Option<u64>)] = &[
+    (0x4d44, FsType::Fat, Some(FAT_MAX_FILE_SIZE)),
+    (0x2011_bab0, FsType::ExFat, None),
+    (0x7366_746e, FsType::Ntfs, None),
+    (0x6573_5546, FsType::Fuse, None),
+    (0xef53, FsType::Ext4, None),
+    (0x9123_683e, FsType::Btrfs, None),
+    (0x5846_5342, FsType::Xfs, None),
+    (0x0102_1994, FsType::Tmpfs, None),
+];
+
+/// Largest file FAT32 holds, one byte short of 4 GiB.
+pub const FAT_MAX_FILE_SIZE: u64 = 4 * 1024 * 1024 * 1024 - 1;
+
+impl FsType {
+    pub fn from_magic(magic: i64) -> Self {
+        FS_TYPES
+            .iter()
+            .find(|(known, _, _)| *known == magic)
+            .map_or(Self::Other(magic), |(_, fs_type, _)| *fs_type)
+    }
+
+    /// Largest file the filesystem holds, `None` without a practical limit.
+    pub fn max_file_size(self) -> Option<u64> {
+        FS_TYPES
+            .iter()
+            .find(|(_, fs_type, _)| *fs_type == self)
+            .and_then(|(_, _, limit)| *limit)
+    }
+}
+
+/// What `statfs` reports about a filesystem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FsStat {
+    pub fs_type: FsType,
+    /// Bytes available to unprivileged users
+    pub available: u64,
+}
+
+/// `statfs` of the filesystem holding `path`.
+pub fn statfs(path: &Path) -> std::io::Result<FsStat> {
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    // SAFETY: statfs only writes to the struct it is given
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    #[allow(clippy::unnecessary_cast)] // f_type is 32 bits wide on some targets
+    let magic = stat.f_type as i64;
+    Ok(FsStat {
+        fs_type: FsType::from_magic(magic),
+        available: stat.f_bavail as u64 * stat.f_bsize as u64,
+    })
+}
+
+/// Why a file cannot be saved to a folder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetProblem {
+    /// The filesystem does not hold files of that size
+    FileTooLarge {
+        fs_type: FsType,
+        limit: u64,
+    },
+    NotEnoughSpace {
+        available: u64,
+    },
+}
+
+/// Check whether a file of `size` bytes fits into `folder`.
+///
+/// A folder that does not exist yet is checked on the filesystem of its
+/// nearest existing parent, where it would be created.
+pub fn check_target(folder: &Path, size: u64) -> std::io::Result<Option<TargetProblem>> {
+    check_target_with(folder, size, statfs)
+}
+
+fn check_target_with(
+    folder: &Path,
+    size: u64,
+    statfs: impl Fn(&Path) -> std::io::Result<FsStat>,
+) -> std::io::Result<Option<TargetProblem>> {
+    let mut result = Err(std::io::ErrorKind::NotFound.into());
+    for dir in folder.ancestors().filter(|dir| !dir.as_os_str().is_empty()) {
+        result = statfs(dir);
+        if !matches!(&result, Err(e) if e.kind() == std::io::ErrorKind::NotFound) {
+            break;
+        }
+    }
+    let stat = result?;
+
+    if let Some(limit) = stat.fs_type.max_file_size().filter(|limit| size > *limit) {
+        return Ok(Some(TargetProblem::FileTooLarge {
+            fs_type: stat.fs_type,
+            limit,
+        }));
+    }
+    if size > stat.available {
+        return Ok(Some(TargetProblem::NotEnoughSpace {
+            available: stat.available,
+        }));
+    }
+    Ok(None)
+}
+
+/// Filesystem type mounted at `/`, as listed in /proc/mounts.
+pub fn root_fs_type() -> Option<String> {
+    match std::fs::read_to_string("/proc/mounts") {
+        Ok(content) => parse_mount_fs_type(&content, "/"),
+        Err(e) => {
+            warn!("Failed to read /proc/mounts: {}", e);
+            None
+        }
+    }
+}
+
+/// Whether the root filesystem is btrfs.
+pub fn is_root_btrfs() -> bool {
+    root_fs_type().as_deref() == Some("btrfs")
+}
+
+/// Find the filesystem type for a mount point in /proc/mounts content.
+///
+/// The last matching entry wins, since later mounts shadow earlier ones.
+fn parse_mount_fs_type(content: &str, mount_point: &str) -> Option<String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let _device = fields.next()?;
+            let target = fields.next()?;
+            let fs_type = fields.next()?;
+            (target == mount_point).then(|| fs_type.to_string())
+        })
+        .next_back()
+}
+
+/// Query usage of the btrfs filesystem at `path`.
+pub fn btrfs_usage(path: &str) -> anyhow::Result<BtrfsUsage> {
+    let output = std::process::Command::new("btrfs")
+        .args(["filesystem", "usage", "-b", path])
+        .output()?;
+
+    // Without root, btrfs warns about missing chunk info but still prints
+    // the overall and per-type summary, so only fail on empty output.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.trim().is_empty() {
+        anyhow::bail!(
+            "btrfs filesystem usage failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(parse_btrfs_usage(&stdout))
+}
+
+/// Parse `btrfs filesystem usage -b` output.
+///
+/// Block group lines look like `Data,single: Size:1073741824, Used:52428800 (4.88%)`.
+/// Filesystems with mixed profiles list a type several times; those are summed.
+pub fn parse_btrfs_usage(output: &str) -> BtrfsUsage {
+    let mut usage = BtrfsUsage::default();
+
+    for line in output.lines() {
+        let trimmed = line.trim();
+
+        if let Some(value) = trimmed.strip_prefix("Device size:") {
+            usage.device_size = parse_bytes(value).unwrap_or(0);
+        } else if let Some(value) = trimmed.strip_prefix("Device unallocated:") {
+            usage.unallocated = parse_bytes(value).unwrap_or(0);
+        } else if let Some((kind, rest)) = trimmed.split_once(',') {
+            let group = match kind {
+                "Data" => &mut usage.data,
+                "Metadata" => &mut usage.metadata,
+                "System" => &mut usage.system,
+                _ => continue,
+            };
+            group.size += field_bytes(rest, "Size:").unwrap_or(0);
+            group.used += field_bytes(rest, "Used:").unwrap_or(0);
+        }
+    }
+
+    usage
+}
+
+/// Parse the first whitespace-delimited number in `value`.
+fn parse_bytes(value: &str) -> Option<u64> {
+    value.split_whitespace().next()?.parse().ok()
+}
+
+/// Extract the number following `key` in a block group line.
+fn field_bytes(line: &str, key: &str) -> Option<u64> {
+    let start = line.find(key)? + key.len();
+    line[start..]
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|s| !s.is_empty())?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOUNTS: &str = "\
+proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
+/dev/nvme0n1p2 / btrfs rw,noatime,compress=zstd:3,ssd,subvol=/@ 0 0
+/dev/nvme0n1p1 /boot/efi vfat rw,relatime 0 0
+/dev/nvme0n1p2 /home btrfs rw,noatime,subvol=/@home 0 0
+";
+
+    const USAGE: &str = "\
+Overall:
+    Device size:                    1000203091968
+    Device allocated:                214752559104
+    Device unallocated:              785450532864
+    Device missing:                             0
+    Device slack:                               0
+    Used:                            187404959744
+    Free (estimated):                808613470208      (min: 415888203776)
+    Free (statfs, df):               808612421632
+    Data ratio:                              1.00
+    Metadata ratio:                          2.00
+    Global reserve:                     536870912      (used: 0)
+    Multiple profiles:                         no
+
+Data,single: Size:206158430208, Used:182995492864 (88.76%)
+   /dev/nvme0n1p2  206158430208
+
+Metadata,DUP: Size:4294967296, Used:2204729344 (51.33%)
+   /dev/nvme0n1p2  8589934592
+
+System,DUP: Size:8388608, Used:49152 (0.59%)
+   /dev/nvme0n1p2    16777216
+
+Unallocated:
+   /dev/nvme0n1p2  785450532864
+";
+
+    #[test]
+    fn test_parse_mount_fs_type() {
+        assert_eq!(parse_mount_fs_type(MOUNTS, "/").as_deref(), Some("btrfs"));
+        assert_eq!(
+            parse_mount_fs_type(MOUNTS, "/boot/efi").as_deref(),
+            Some("vfat")
+        );
+        assert_eq!(parse_mount_fs_type(MOUNTS, "/srv"), None);
+    }
+
+    #[test]
+    fn test_free_space() {
+        let temp = std::env::temp_dir();
+        assert!(free_space(temp.to_str().unwrap()).unwrap() > 0);
+        assert!(free_space("/nonexistent/xero-toolkit").is_err());
+        assert!(free_space("bad\0path").is_err());
+    }
+
+    #[test]
+    fn test_fs_type() {
+        assert_eq!(FsType::from_magic(0x4d44), FsType::Fat);
+        assert_eq!(FsType::from_magic(0x9123683e), FsType::Btrfs);
+        assert_eq!(FsType::from_magic(0x1234), FsType::Other(0x1234));
+        assert_eq!(FsType::Fat.max_file_size(), Some(FAT_MAX_FILE_SIZE));
+        assert_eq!(FsType::ExFat.max_file_size(), None);
+        assert_eq!(FsType::Other(0x1234).max_file_size(), None);
+    }
+
+    #[test]
+    fn test_statfs() {
+        let stat = statfs(&std::env::temp_dir()).unwrap();
+        assert!(stat.available > 0);
+        assert!(statfs(Path::new("/nonexistent/xero-toolkit")).is_err());
+    }
+
+    /// `statfs` of a USB stick mounted at /run/media/stick and a root
+    /// filesystem with `root_free` bytes free.
+    fn mock_statfs(root_free: u64) -> impl Fn(&Path) -> std::io::Result<FsStat> {
+        move |path| {
+            if path.starts_with("/run/media/stick/missing") {
+                Err(std::io::ErrorKind::NotFound.into())
+            } else if path.starts_with("/run/media/stick") {
+                Ok(FsStat {
+                    fs_type: FsType::Fat,
+                    available: 30 * 1024 * 1024 * 1024,
+                })
+            } else if path.starts_with("/forbidden") {
+                Err(std::io::ErrorKind::PermissionDenied.into())
+            } else {
+                Ok(FsStat {
+                    fs_type: FsType::Ext4,
+                    available: root_free,
+                })
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_target() {
+        let iso = 1_300_000_000;
+        let stick = Path::new("/run/media/stick");
+        assert_eq!(check_target_with(stick, iso, mock_statfs(0)).unwrap(), None);
+
+        let large = 5 * 1024 * 1024 * 1024;
+        assert_eq!(
+            check_target_with(stick, large, mock_statfs(0)).unwrap(),
+            Some(TargetProblem::FileTooLarge {
+                fs_type: FsType::Fat,
+                limit: FAT_MAX_FILE_SIZE
+            })
+        );
+        // Not yet created, so checked on the stick it would be created on
+        assert_eq!(
+            check_target_with(
+                Path::new("/run/media/stick/missing/isos"),
+                large,
+                mock_statfs(0)
+            )
+            .unwrap(),
+            Some(TargetProblem::FileTooLarge {
+                fs_type: FsType::Fat,
+                limit: FAT_MAX_FILE_SIZE
+            })
+        );
+
+        let home = Path::new("/home/user/Downloads");
+        assert_eq!(
+            check_target_with(home, large, mock_statfs(large)).unwrap(),
+            None
+        );
+        assert_eq!(
+            check_target_with(home, iso, mock_statfs(1000)).unwrap(),
+            Some(TargetProblem::NotEnoughSpace { available: 1000 })
+        );
+        assert_eq!(
+            check_target_with(Path::new("/forbidden/isos"), iso, mock_statfs(0))
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[test]
+    fn test_parse_mount_last_entry_wins() {
+        let mounts = "a / ext4 rw 0 0\nb / btrfs rw 0 0\n";
+        assert_eq!(parse_mount_fs_type(mounts, "/").as_deref(), Some("btrfs"));
+    }
+
+    #[test]
+    fn test_parse_btrfs_usage() {
+        let usage = parse_btrfs_usage(USAGE);
+        assert_eq!(usage.device_size, 1000203091968);
+        assert_eq!(usage.unallocated, 785450532864);
+        assert_eq!(usage.data.size, 206158430208);
+        assert_eq!(usage.data.used, 182995492864);
+        assert_eq!(usage.metadata.size, 4294967296);
+        assert_eq!(usage.metadata.used, 2204729344);
+        assert_eq!(usage.system.used, 49152);
+    }
+
+    #[test]
+    fn test_parse_btrfs_usage_mixed_profiles() {
+        let output = "\
+Data,single: Size:100, Used:40 (40.00%)
+Data,RAID1: Size:200, Used:60 (30.00%)
+";
+        let usage = parse_btrfs_usage(output);
+        assert_eq!(
+            usage.data,
+            BlockGroupUsage {
+                size: 300,
+                used: 100
+            }
+        );
+        assert_eq!(usage.metadata, BlockGroupUsage::default());
+    }
+
+    #[test]
+    fn test_parse_btrfs_usage_garbage() {
+        assert_eq!(
+            parse_btrfs_usage("ERROR: not a btrfs filesystem"),
+            BtrfsUsage::default()
+        );
+    }
+}
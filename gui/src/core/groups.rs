@@ -0,0 +1,148 @@
+//! Group memberships that commonly gate hardware and services.
+
+use crate::i18n::gettext_noop;
+use anyhow::{bail, Context, Result};
+use std::process::Command;
+
+/// Group that grants administrator rights through sudo.
+pub const ADMIN_GROUP: &str = "wheel";
+
+/// Groups offered in the account helper, with what membership allows.
+pub const GROUPS: &[(&str, &str)] = &[
+    (
+        ADMIN_GROUP,
+        gettext_noop("Administrator rights through sudo"),
+    ),
+    (
+        "video",
+        gettext_noop("Backlight control and some capture devices"),
+    ),
+    (
+        "input",
+        gettext_noop("Raw input devices, needed by some controllers and remappers"),
+    ),
+    ("lp", gettext_noop("Printers and Bluetooth printing")),
+    (
+        "docker",
+        gettext_noop("Docker without sudo; as powerful as root"),
+    ),
+    ("libvirt", gettext_noop("Virtual machines through libvirt")),
+    (
+        "gamemode",
+        gettext_noop("GameMode CPU governor and priority tweaks"),
+    ),
+];
+
+/// Membership changes turning one set of groups into another.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct GroupChanges {
+    pub add: Vec<String>,
+    pub remove: Vec<String>,
+}
+
+impl GroupChanges {
+    pub fn is_empty(&self) -> bool {
+        self.add.is_empty() && self.remove.is_empty()
+    }
+}
+
+/// Group names from `id -Gn` output.
+pub fn parse_id_groups(output: &str) -> Vec<String> {
+    output.split_whitespace().map(str::to_string).collect()
+}
+
+/// Names of the groups defined in group(5) content.
+pub fn parse_group_names(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split(':').next())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Changes from the `current` groups to the `wanted` ones, limited to
+/// the groups in `offered`.
+pub fn changes(offered: &[&str], current: &[String], wanted: &[&str]) -> GroupChanges {
+    let is_member = |group: &str| current.iter().any(|g| g == group);
+    GroupChanges {
+        add: offered
+            .iter()
+            .filter(|group| wanted.contains(group) && !is_member(group))
+            .map(|group| group.to_string())
+            .collect(),
+        remove: offered
+            .iter()
+            .filter(|group| !wanted.contains(group) && is_member(group))
+            .map(|group| group.to_string())
+            .collect(),
+    }
+}
+
+/// Groups `user` belongs to according to the group database. Unlike the
+/// running session, this includes changes made since login.
+pub fn user_groups(user: &str) -> Result<Vec<String>> {
+    let output = Command::new("id")
+        .args(["-Gn", user])
+        .output()
+        .context("Failed to run id")?;
+    if !output.status.success() {
+        bail!(
+            "id failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(parse_id_groups(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Groups of [`GROUPS`] that exist on this system.
+pub fn available_groups() -> Vec<(&'static str, &'static str)> {
+    let defined = std::fs::read_to_string("/etc/group")
+        .map(|content| parse_group_names(&content))
+        .unwrap_or_default();
+    GROUPS
+        .iter()
+        .copied()
+        .filter(|(group, _)| defined.iter().any(|name| name == group))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_id_groups() {
+        assert_eq!(
+            parse_id_groups("xero wheel video input docker\n"),
+            ["xero", "wheel", "video", "input", "docker"]
+        );
+        assert!(parse_id_groups("\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_group_names() {
+        let content = "root:x:0:root\nwheel:x:998:xero\n\nvideo:x:985:\n";
+        assert_eq!(parse_group_names(content), ["root", "wheel", "video"]);
+    }
+
+    #[test]
+    fn test_changes() {
+        let offered = ["wheel", "video", "input", "docker"];
+        let current = parse_id_groups("xero wheel video");
+
+        assert!(changes(&offered, &current, &["wheel", "video"]).is_empty());
+        assert_eq!(
+            changes(&offered, &current, &["video", "docker"]),
+            GroupChanges {
+                add: vec!["docker".to_string()],
+                remove: vec!["wheel".to_string()],
+            }
+        );
+        // Groups outside the offer are never removed
+        assert!(changes(&offered, &current, &["wheel", "video"])
+            .remove
+            .iter()
+            .all(|group| group != "xero"));
+    }
+}
@@ -0,0 +1,1034 @@
+//! Hardware detection helpers.
+//!
+//! Reads CPU information from /proc/cpuinfo to determine the vendor and
+//! whether hardware virtualization (VT-x/AMD-V) is available, and the power
+//! supplies in /sys/class/power_supply to tell whether a laptop runs on
+//! battery. Also parses the output of `lspci -k`, `lsblk` and `smartctl` and
+//! /proc/meminfo for the system information page, and `vainfo` to tell
+//! whether video decoding is hardware accelerated, and picks the GPU of a
+//! hybrid graphics laptop that programs can be offloaded to.
+
+use anyhow::{bail, Context, Result};
+use log::{info, warn};
+use std::process::Command;
+
+/// Where the kernel lists power supplies.
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+/// Scaling governor of the first CPU; the others normally match.
+const CPU_GOVERNOR: &str = "/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor";
+
+/// CPU vendor as reported by the `vendor_id` field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CpuVendor {
+    Intel,
+    Amd,
+    Other(String),
+}
+
+/// CPU details from /proc/cpuinfo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuInfo {
+    pub vendor: CpuVendor,
+    pub model_name: Option<String>,
+    /// `cpu family` and `model` numbers, telling generations apart
+    pub family: Option<u32>,
+    pub model: Option<u32>,
+    pub flags: Vec<String>,
+    /// Physical cores per package
+    pub cores: Option<u32>,
+    /// Logical processors
+    pub threads: usize,
+}
+
+impl CpuInfo {
+    /// Check if the CPU advertises a given flag.
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    /// Whether VT-x (vmx) or AMD-V (svm) is available.
+    ///
+    /// The kernel hides these flags when virtualization is disabled in firmware.
+    pub fn virtualization_supported(&self) -> bool {
+        self.has_flag("vmx") || self.has_flag("svm")
+    }
+
+    /// KVM kernel module matching this CPU, if any.
+    pub fn kvm_module(&self) -> Option<&'static str> {
+        if self.has_flag("vmx") {
+            Some("kvm-intel")
+        } else if self.has_flag("svm") {
+            Some("kvm-amd")
+        } else {
+            match self.vendor {
+                CpuVendor::Intel => Some("kvm-intel"),
+                CpuVendor::Amd => Some("kvm-amd"),
+                CpuVendor::Other(_) => None,
+            }
+        }
+    }
+}
+
+/// Read and parse /proc/cpuinfo.
+pub fn detect_cpu() -> Option<CpuInfo> {
+    match std::fs::read_to_string("/proc/cpuinfo") {
+        Ok(content) => {
+            let cpu = parse_cpuinfo(&content);
+            if let Some(ref cpu) = cpu {
+                info!(
+                    "Detected CPU vendor {:?}, virtualization supported: {}",
+                    cpu.vendor,
+                    cpu.virtualization_supported()
+                );
+            }
+            cpu
+        }
+        Err(e) => {
+            warn!("Failed to read /proc/cpuinfo: {}", e);
+            None
+        }
+    }
+}
+
+/// Parse /proc/cpuinfo content: details from the first processor block and
+/// the number of blocks.
+pub fn parse_cpuinfo(content: &str) -> Option<CpuInfo> {
+    let mut vendor = None;
+    let mut model_name = None;
+    let mut family = None;
+    let mut model = None;
+    let mut flags = Vec::new();
+    let mut cores = None;
+    let mut threads = 0;
+    let mut first_block = true;
+
+    for line in content.lines() {
+        // Processor blocks are separated by blank lines; the first is enough
+        if line.trim().is_empty() && vendor.is_some() {
+            first_block = false;
+        }
+
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        let key = key.trim();
+
+        if key == "processor" {
+            threads += 1;
+        }
+        if !first_block {
+            continue;
+        }
+        match key {
+            "vendor_id" => {
+                vendor = Some(match value {
+                    "GenuineIntel" => CpuVendor::Intel,
+                    "AuthenticAMD" => CpuVendor::Amd,
+                    other => CpuVendor::Other(other.to_string()),
+                });
+            }
+            "model name" => model_name = Some(value.to_string()),
+            "cpu family" => family = value.parse().ok(),
+            "model" => model = value.parse().ok(),
+            "flags" => flags = value.split_whitespace().map(str::to_string).collect(),
+            "cpu cores" => cores = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    vendor.map(|vendor| CpuInfo {
+        vendor,
+        model_name,
+        family,
+        model,
+        flags,
+        cores,
+        threads,
+    })
+}
+
+/// Current CPU frequency scaling governor.
+pub fn cpu_governor() -> Option<String> {
+    std::fs::read_to_string(CPU_GOVERNOR)
+        .map(|governor| governor.trim().to_string())
+        .ok()
+        .filter(|governor| !governor.is_empty())
+}
+
+/// A graphics device found by `lspci -k`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gpu {
+    pub name: String,
+    /// Kernel driver bound to the device, `None` if unbound
+    pub driver: Option<String>,
+}
+
+/// PCI device classes that are graphics adapters.
+const GPU_CLASSES: &[&str] = &[
+    "VGA compatible controller",
+    "3D controller",
+    "Display controller",
+];
+
+/// Parse graphics devices from `lspci -k` output.
+pub fn parse_lspci(output: &str) -> Vec<Gpu> {
+    let mut gpus = Vec::new();
+    let mut current: Option<Gpu> = None;
+
+    for line in output.lines() {
+        if !line.starts_with(char::is_whitespace) {
+            gpus.extend(current.take());
+            // `00:02.0 VGA compatible controller: Intel Corporation ...`
+            let Some((_, rest)) = line.split_once(' ') else {
+                continue;
+            };
+            let Some((class, name)) = rest.split_once(": ") else {
+                continue;
+            };
+            if GPU_CLASSES.contains(&class) {
+                current = Some(Gpu {
+                    name: name.trim().to_string(),
+                    driver: None,
+                });
+            }
+        } else if let Some(gpu) = current.as_mut() {
+            if let Some(driver) = line.trim().strip_prefix("Kernel driver in use:") {
+                gpu.driver = Some(driver.trim().to_string());
+            }
+        }
+    }
+    gpus.extend(current);
+    gpus
+}
+
+/// Graphics devices with their drivers.
+pub fn detect_gpus() -> Result<Vec<Gpu>> {
+    Ok(parse_lspci(&run("lspci", &["-k"])?))
+}
+
+/// Vulkan driver packages matching `gpus`, 32-bit ones included for Steam
+/// and Wine.
+///
+/// Goes by the bound kernel driver, or by the vendor in the name for
+/// devices without one. The native NVIDIA package comes with the driver.
+pub fn vulkan_packages(gpus: &[Gpu]) -> Vec<&'static str> {
+    let mut packages = Vec::new();
+    for gpu in gpus {
+        let found: &[&str] = match gpu.driver.as_deref() {
+            Some("amdgpu" | "radeon") => &["vulkan-radeon", "lib32-vulkan-radeon"],
+            Some("nvidia") => &["lib32-nvidia-utils"],
+            Some("nouveau") => &["vulkan-nouveau", "lib32-vulkan-nouveau"],
+            Some("i915" | "xe") => &["vulkan-intel", "lib32-vulkan-intel"],
+            _ if gpu.name.contains("AMD") => &["vulkan-radeon", "lib32-vulkan-radeon"],
+            _ if gpu.name.contains("NVIDIA") => &["lib32-nvidia-utils"],
+            _ if gpu.name.contains("Intel") => &["vulkan-intel", "lib32-vulkan-intel"],
+            _ => &[],
+        };
+        for package in found {
+            if !packages.contains(package) {
+                packages.push(*package);
+            }
+        }
+    }
+    packages
+}
+
+/// VA-API driver of Intel GPUs before Broadwell.
+const INTEL_LEGACY_VAAPI: &str = "libva-intel-driver";
+
+/// VA-API driver of Broadwell and newer Intel GPUs.
+const INTEL_MEDIA_VAAPI: &str = "intel-media-driver";
+
+/// First Broadwell model of CPU family 6; models from here on have GPUs
+/// the media driver supports.
+const INTEL_BROADWELL_MODEL: u32 = 0x3d;
+
+/// VA-API driver packages matching `gpus`.
+///
+/// Intel GPUs get the media driver or the legacy one depending on the CPU
+/// generation, defaulting to the media driver when it is unknown.
+pub fn vaapi_packages(gpus: &[Gpu], cpu: Option<&CpuInfo>) -> Vec<&'static str> {
+    let intel = match cpu.and_then(|cpu| Some((cpu.family?, cpu.model?))) {
+        Some((6, model)) if model < INTEL_BROADWELL_MODEL => INTEL_LEGACY_VAAPI,
+        _ => INTEL_MEDIA_VAAPI,
+    };
+
+    let mut packages = Vec::new();
+    for gpu in gpus {
+        let found = match gpu.driver.as_deref() {
+            Some("amdgpu" | "radeon" | "nouveau") => Some("libva-mesa-driver"),
+            Some("nvidia") => Some("nvidia-vaapi-driver"),
+            Some("i915" | "xe") => Some(intel),
+            _ if gpu.name.contains("AMD") => Some("libva-mesa-driver"),
+            _ if gpu.name.contains("NVIDIA") => Some("nvidia-vaapi-driver"),
+            _ if gpu.name.contains("Intel") => Some(intel),
+            _ => None,
+        };
+        if let Some(package) = found.filter(|package| !packages.contains(package)) {
+            packages.push(package);
+        }
+    }
+    packages
+}
+
+/// How programs are sent to the second GPU of a hybrid graphics system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffloadDriver {
+    /// NVIDIA's driver, through its PRIME render offload variables
+    Nvidia,
+    /// Mesa drivers (AMD, Intel and nouveau), through `DRI_PRIME`
+    Mesa,
+}
+
+impl OffloadDriver {
+    /// Environment variables sending OpenGL and Vulkan to the offload GPU.
+    pub fn env(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Nvidia => &[
+                ("__NV_PRIME_RENDER_OFFLOAD", "1"),
+                ("__VK_LAYER_NV_optimus", "NVIDIA_only"),
+                ("__GLX_VENDOR_LIBRARY_NAME", "nvidia"),
+            ],
+            Self::Mesa => &[("DRI_PRIME", "1")],
+        }
+    }
+}
+
+/// The GPU of a hybrid graphics system that programs can be offloaded to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Offload {
+    pub gpu: Gpu,
+    pub driver: OffloadDriver,
+}
+
+/// The offload GPU among `gpus`, `None` without a second one.
+///
+/// `lspci` lists the integrated GPU first, on the root bus, so the next one
+/// is taken. A device without a bound driver goes by the vendor in its name.
+pub fn hybrid_offload(gpus: &[Gpu]) -> Option<Offload> {
+    let gpu = gpus.get(1)?;
+    let driver = match gpu.driver.as_deref() {
+        Some("nvidia") => OffloadDriver::Nvidia,
+        Some(_) => OffloadDriver::Mesa,
+        None if gpu.name.contains("NVIDIA") => OffloadDriver::Nvidia,
+        None => OffloadDriver::Mesa,
+    };
+    Some(Offload {
+        gpu: gpu.clone(),
+        driver,
+    })
+}
+
+/// `prime-run` wrapper running its arguments on the offload GPU.
+pub fn prime_run_script(offload: &Offload) -> String {
+    let mut script = format!(
+        "#!/bin/sh\n# Written by Xero Toolkit: runs a program on the {}\n",
+        offload.gpu.name
+    );
+    for (name, value) in offload.driver.env() {
+        script.push_str(&format!("export {}={}\n", name, value));
+    }
+    script.push_str("exec \"$@\"\n");
+    script
+}
+
+/// What `vainfo` reports about a working VA-API driver.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VaapiInfo {
+    /// Like `Intel iHD driver for Intel(R) Gen Graphics - 24.3.4 ()`
+    pub driver: Option<String>,
+    /// Profiles that can be decoded, like `VAProfileH264High`
+    pub decode_profiles: Vec<String>,
+}
+
+/// Parse `vainfo` output.
+pub fn parse_vainfo(output: &str) -> VaapiInfo {
+    let mut info = VaapiInfo::default();
+    for line in output.lines() {
+        if let Some((_, driver)) = line.split_once("Driver version:") {
+            info.driver = Some(driver.trim().to_string());
+            continue;
+        }
+        // Profile lines look like `VAProfileH264Main : VAEntrypointVLD`
+        let Some((profile, entrypoint)) = line.split_once(':') else {
+            continue;
+        };
+        let profile = profile.trim();
+        if profile.starts_with("VAProfile")
+            && profile != "VAProfileNone"
+            && entrypoint.trim() == "VAEntrypointVLD"
+            && !info.decode_profiles.iter().any(|p| p == profile)
+        {
+            info.decode_profiles.push(profile.to_string());
+        }
+    }
+    info
+}
+
+/// Query VA-API; fails if vainfo is missing or no driver initializes.
+pub fn vaapi_info() -> Result<VaapiInfo> {
+    Ok(parse_vainfo(&run("vainfo", &[])?))
+}
+
+/// Memory totals from /proc/meminfo, in KiB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryInfo {
+    pub total: u64,
+    pub available: u64,
+}
+
+impl MemoryInfo {
+    /// Memory in use, not counting caches the kernel can drop.
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.available)
+    }
+}
+
+/// Parse /proc/meminfo content.
+pub fn parse_meminfo(content: &str) -> Option<MemoryInfo> {
+    let field = |name: &str| {
+        content.lines().find_map(|line| {
+            let value = line.strip_prefix(name)?.strip_prefix(':')?;
+            value.trim().trim_end_matches("kB").trim().parse().ok()
+        })
+    };
+    Some(MemoryInfo {
+        total: field("MemTotal")?,
+        available: field("MemAvailable")?,
+    })
+}
+
+/// Read /proc/meminfo.
+pub fn memory_info() -> Result<MemoryInfo> {
+    let content =
+        std::fs::read_to_string("/proc/meminfo").context("Failed to read /proc/meminfo")?;
+    parse_meminfo(&content).context("Unexpected /proc/meminfo format")
+}
+
+/// A whole disk reported by `lsblk`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disk {
+    /// Kernel name, like `nvme0n1`
+    pub name: String,
+    pub model: Option<String>,
+    /// Size in bytes
+    pub size: u64,
+    pub rotational: bool,
+}
+
+impl Disk {
+    pub fn path(&self) -> String {
+        format!("/dev/{}", self.name)
+    }
+}
+
+/// Parse `lsblk -d -b -n -P -o NAME,MODEL,SIZE,TYPE,ROTA` output.
+///
+/// Optical drives, zram devices and empty card readers are skipped.
+pub fn parse_lsblk(output: &str) -> Vec<Disk> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields = parse_pairs(line);
+            let field = |key: &str| {
+                fields
+                    .iter()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, value)| value.as_str())
+            };
+            let name = field("NAME")?;
+            let size = field("SIZE")?.parse().ok()?;
+            if field("TYPE")? != "disk" || name.starts_with("zram") || size == 0 {
+                return None;
+            }
+            Some(Disk {
+                name: name.to_string(),
+                model: field("MODEL")
+                    .map(str::trim)
+                    .filter(|model| !model.is_empty())
+                    .map(str::to_string),
+                size,
+                rotational: field("ROTA") == Some("1"),
+            })
+        })
+        .collect()
+}
+
+/// `KEY="value"` pairs of one `lsblk -P` line.
+fn parse_pairs(line: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut rest = line.trim();
+    while let Some((key, after)) = rest.split_once("=\"") {
+        let Some((value, tail)) = after.split_once('"') else {
+            break;
+        };
+        pairs.push((key.trim().to_string(), value.to_string()));
+        rest = tail;
+    }
+    pairs
+}
+
+/// Whole disks of this system.
+pub fn detect_disks() -> Result<Vec<Disk>> {
+    Ok(parse_lsblk(&run(
+        "lsblk",
+        &["-d", "-b", "-n", "-P", "-o", "NAME,MODEL,SIZE,TYPE,ROTA"],
+    )?))
+}
+
+/// Overall SMART health verdict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmartHealth {
+    Passed,
+    Failed,
+}
+
+/// Parse the verdict from `smartctl -H` output; `None` if the drive could
+/// not be queried.
+pub fn parse_smart_health(output: &str) -> Option<SmartHealth> {
+    output.lines().find_map(|line| {
+        // ATA and NVMe drives, then SCSI drives
+        let verdict = line
+            .strip_prefix("SMART overall-health self-assessment test result:")
+            .or_else(|| line.strip_prefix("SMART Health Status:"))?
+            .trim();
+        Some(if verdict.starts_with("PASSED") || verdict == "OK" {
+            SmartHealth::Passed
+        } else {
+            SmartHealth::Failed
+        })
+    })
+}
+
+/// SMART health of `disk`, `None` without smartctl or permission to query it.
+pub fn smart_health(disk: &Disk) -> Option<SmartHealth> {
+    // smartctl exits non-zero for failing drives, so only the output counts
+    let output = Command::new("smartctl")
+        .args(["-H", &disk.path()])
+        .output()
+        .ok()?;
+    parse_smart_health(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Stdout of a successful command.
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        bail!("{} exited with {}", program, output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// One power supply as described by its `uevent` file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PowerSupply {
+    /// `Battery`, `Mains`, `USB`, ...
+    pub kind: String,
+    /// Whether an external supply is plugged in
+    pub online: bool,
+    /// `Charging`, `Discharging`, `Full`, ...
+    pub status: Option<String>,
+    /// Charge in percent
+    pub capacity: Option<u8>,
+    /// Batteries of peripherals like mice report the `Device` scope
+    pub scope: Option<String>,
+    /// Kernel name, like `BAT0`
+    pub name: String,
+    pub cycle_count: Option<u32>,
+    /// Current full charge, in µWh or µAh
+    pub full: Option<u64>,
+    /// Full charge when new, in the unit of `full`
+    pub full_design: Option<u64>,
+}
+
+impl PowerSupply {
+    pub fn is_system_battery(&self) -> bool {
+        self.kind == "Battery" && self.scope.as_deref() != Some("Device")
+    }
+
+    /// Remaining capacity compared to new, in percent.
+    pub fn health_percent(&self) -> Option<u32> {
+        let full = self.full?;
+        let design = self.full_design.filter(|design| *design > 0)?;
+        Some((full * 100 / design) as u32)
+    }
+}
+
+/// Whether the machine runs on battery, and how full it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerStatus {
+    pub on_battery: bool,
+    /// Average charge of the system batteries in percent
+    pub battery_percent: Option<u8>,
+}
+
+impl PowerStatus {
+    /// Whether the machine runs on a battery charged below `threshold` percent.
+    pub fn is_low(&self, threshold: u32) -> bool {
+        self.on_battery
+            && self
+                .battery_percent
+                .is_some_and(|percent| u32::from(percent) < threshold)
+    }
+}
+
+/// Parse the `KEY=value` lines of a power supply's `uevent` file.
+pub fn parse_power_supply(uevent: &str) -> PowerSupply {
+    let mut supply = PowerSupply::default();
+    for line in uevent.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "POWER_SUPPLY_TYPE" => supply.kind = value.to_string(),
+            "POWER_SUPPLY_ONLINE" => supply.online = value == "1",
+            "POWER_SUPPLY_STATUS" => supply.status = Some(value.to_string()),
+            "POWER_SUPPLY_CAPACITY" => supply.capacity = value.parse().ok(),
+            "POWER_SUPPLY_SCOPE" => supply.scope = Some(value.to_string()),
+            "POWER_SUPPLY_NAME" => supply.name = value.to_string(),
+            "POWER_SUPPLY_CYCLE_COUNT" => supply.cycle_count = value.parse().ok(),
+            // Batteries report either energy or charge
+            "POWER_SUPPLY_ENERGY_FULL" | "POWER_SUPPLY_CHARGE_FULL" => {
+                supply.full = value.parse().ok()
+            }
+            "POWER_SUPPLY_ENERGY_FULL_DESIGN" | "POWER_SUPPLY_CHARGE_FULL_DESIGN" => {
+                supply.full_design = value.parse().ok()
+            }
+            _ => {}
+        }
+    }
+    supply
+}
+
+/// Combine power supplies into a status; `None` without a system battery.
+pub fn power_status_from(supplies: &[PowerSupply]) -> Option<PowerStatus> {
+    let batteries: Vec<&PowerSupply> = supplies
+        .iter()
+        .filter(|supply| supply.is_system_battery())
+        .collect();
+    if batteries.is_empty() {
+        return None;
+    }
+
+    let plugged_in = supplies
+        .iter()
+        .any(|supply| supply.kind != "Battery" && supply.online);
+    let discharging = batteries
+        .iter()
+        .any(|battery| battery.status.as_deref() == Some("Discharging"));
+
+    let capacities: Vec<u32> = batteries
+        .iter()
+        .filter_map(|battery| battery.capacity.map(u32::from))
+        .collect();
+    let battery_percent = (!capacities.is_empty())
+        .then(|| (capacities.iter().sum::<u32>() / capacities.len() as u32) as u8);
+
+    Some(PowerStatus {
+        on_battery: discharging && !plugged_in,
+        battery_percent,
+    })
+}
+
+/// Read the power supplies the kernel reports.
+pub fn power_supplies() -> Result<Vec<PowerSupply>> {
+    let entries = std::fs::read_dir(POWER_SUPPLY_DIR)
+        .with_context(|| format!("Failed to read {}", POWER_SUPPLY_DIR))?;
+    let mut supplies: Vec<PowerSupply> = entries
+        .flatten()
+        .filter_map(|entry| std::fs::read_to_string(entry.path().join("uevent")).ok())
+        .map(|uevent| parse_power_supply(&uevent))
+        .collect();
+    supplies.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(supplies)
+}
+
+/// Whether the machine runs on battery.
+///
+/// Returns `None` on machines without a battery.
+pub fn power_status() -> Option<PowerStatus> {
+    let supplies = power_supplies().map_err(|e| warn!("{:#}", e)).ok()?;
+    let status = power_status_from(&supplies);
+    info!("Power status: {:?}", status);
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTEL_CPUINFO: &str = "\
+processor\t: 0
+vendor_id\t: GenuineIntel
+cpu family\t: 6
+model\t\t: 158
+model name\t: Intel(R) Core(TM) i7-9700K CPU @ 3.60GHz
+flags\t\t: fpu vme de pse tsc msr vmx ssse3 sse4_1
+
+processor\t: 1
+vendor_id\t: GenuineIntel
+flags\t\t: fpu
+";
+
+    const AMD_CPUINFO: &str = "\
+processor\t: 0
+vendor_id\t: AuthenticAMD
+model name\t: AMD Ryzen 7 5800X 8-Core Processor
+flags\t\t: fpu vme de pse svm sse4a
+";
+
+    const AMD_DISABLED_CPUINFO: &str = "\
+processor\t: 0
+vendor_id\t: AuthenticAMD
+flags\t\t: fpu vme de pse sse4a
+";
+
+    #[test]
+    fn test_parse_intel() {
+        let cpu = parse_cpuinfo(INTEL_CPUINFO).unwrap();
+        assert_eq!(cpu.vendor, CpuVendor::Intel);
+        assert!(cpu.model_name.as_deref().unwrap().contains("i7-9700K"));
+        assert!(cpu.has_flag("vmx"));
+        assert!(!cpu.has_flag("svm"));
+    }
+
+    #[test]
+    fn test_parse_amd() {
+        let cpu = parse_cpuinfo(AMD_CPUINFO).unwrap();
+        assert_eq!(cpu.vendor, CpuVendor::Amd);
+        assert!(cpu.virtualization_supported());
+        assert_eq!(cpu.kvm_module(), Some("kvm-amd"));
+    }
+
+    #[test]
+    fn test_virtualization_disabled() {
+        let cpu = parse_cpuinfo(AMD_DISABLED_CPUINFO).unwrap();
+        assert!(!cpu.virtualization_supported());
+        assert_eq!(cpu.kvm_module(), Some("kvm-amd"));
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        assert_eq!(parse_cpuinfo(""), None);
+    }
+
+    #[test]
+    fn test_flags_are_exact_matches() {
+        let cpu = parse_cpuinfo("vendor_id : Foo\nflags : vmxfoo svmbar\n").unwrap();
+        assert_eq!(cpu.vendor, CpuVendor::Other("Foo".to_string()));
+        assert!(!cpu.virtualization_supported());
+        assert_eq!(cpu.kvm_module(), None);
+    }
+
+    const BATTERY_UEVENT: &str = "\
+POWER_SUPPLY_NAME=BAT0
+POWER_SUPPLY_TYPE=Battery
+POWER_SUPPLY_STATUS=Discharging
+POWER_SUPPLY_PRESENT=1
+POWER_SUPPLY_CAPACITY=8
+";
+
+    const AC_UEVENT: &str = "\
+POWER_SUPPLY_NAME=AC
+POWER_SUPPLY_TYPE=Mains
+POWER_SUPPLY_ONLINE=0
+";
+
+    const MOUSE_UEVENT: &str = "\
+POWER_SUPPLY_NAME=hidpp_battery_0
+POWER_SUPPLY_TYPE=Battery
+POWER_SUPPLY_SCOPE=Device
+POWER_SUPPLY_STATUS=Discharging
+POWER_SUPPLY_CAPACITY=90
+";
+
+    #[test]
+    fn test_parse_power_supply() {
+        let battery = parse_power_supply(BATTERY_UEVENT);
+        assert_eq!(battery.kind, "Battery");
+        assert_eq!(battery.status.as_deref(), Some("Discharging"));
+        assert_eq!(battery.capacity, Some(8));
+
+        let ac = parse_power_supply(AC_UEVENT);
+        assert_eq!(ac.kind, "Mains");
+        assert!(!ac.online);
+    }
+
+    #[test]
+    fn test_low_battery_on_laptop() {
+        let supplies = [
+            parse_power_supply(BATTERY_UEVENT),
+            parse_power_supply(AC_UEVENT),
+            parse_power_supply(MOUSE_UEVENT),
+        ];
+        let status = power_status_from(&supplies).unwrap();
+        assert!(status.on_battery);
+        // The mouse battery does not count
+        assert_eq!(status.battery_percent, Some(8));
+        assert!(status.is_low(30));
+        assert!(!status.is_low(0));
+    }
+
+    #[test]
+    fn test_plugged_in_is_not_low() {
+        let mut ac = parse_power_supply(AC_UEVENT);
+        ac.online = true;
+        let status = power_status_from(&[parse_power_supply(BATTERY_UEVENT), ac]).unwrap();
+        assert!(!status.on_battery);
+        assert!(!status.is_low(30));
+    }
+
+    #[test]
+    fn test_no_system_battery() {
+        assert_eq!(power_status_from(&[parse_power_supply(AC_UEVENT)]), None);
+        assert_eq!(power_status_from(&[parse_power_supply(MOUSE_UEVENT)]), None);
+    }
+
+    #[test]
+    fn test_cpu_cores_and_threads() {
+        let cpu = parse_cpuinfo(INTEL_CPUINFO).unwrap();
+        assert_eq!(cpu.threads, 2);
+        assert_eq!(cpu.cores, None);
+
+        let cpu = parse_cpuinfo(
+            "processor : 0\nvendor_id : AuthenticAMD\ncpu cores : 8\n\n\
+             processor : 1\nvendor_id : AuthenticAMD\ncpu cores : 99\n",
+        )
+        .unwrap();
+        assert_eq!(cpu.cores, Some(8));
+        assert_eq!(cpu.threads, 2);
+    }
+
+    #[test]
+    fn test_parse_lspci() {
+        let gpus = parse_lspci(include_str!("../../tests/fixtures/hardware/lspci-k.txt"));
+        assert_eq!(
+            gpus,
+            [
+                Gpu {
+                    name: "Intel Corporation Raptor Lake-S GT1 [UHD Graphics 770] (rev 04)"
+                        .to_string(),
+                    driver: Some("i915".to_string()),
+                },
+                Gpu {
+                    name: "NVIDIA Corporation AD104 [GeForce RTX 4070] (rev a1)".to_string(),
+                    driver: Some("nvidia".to_string()),
+                },
+                // Listed, but no driver bound
+                Gpu {
+                    name: "Advanced Micro Devices, Inc. [AMD/ATI] Navi 24 \
+                           [Radeon RX 6400/6500 XT/6500M] (rev c1)"
+                        .to_string(),
+                    driver: None,
+                },
+            ]
+        );
+        assert!(parse_lspci("").is_empty());
+    }
+
+    #[test]
+    fn test_vulkan_packages() {
+        let gpus = parse_lspci(include_str!("../../tests/fixtures/hardware/lspci-k.txt"));
+        assert_eq!(
+            vulkan_packages(&gpus),
+            [
+                "vulkan-intel",
+                "lib32-vulkan-intel",
+                "lib32-nvidia-utils",
+                "vulkan-radeon",
+                "lib32-vulkan-radeon",
+            ]
+        );
+
+        // The open driver needs Mesa's Vulkan driver instead
+        let nouveau = Gpu {
+            name: "NVIDIA Corporation GP107 [GeForce GTX 1050 Ti]".to_string(),
+            driver: Some("nouveau".to_string()),
+        };
+        assert_eq!(
+            vulkan_packages(&[nouveau.clone(), nouveau]),
+            ["vulkan-nouveau", "lib32-vulkan-nouveau"]
+        );
+        assert!(vulkan_packages(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_hybrid_offload() {
+        let gpus = parse_lspci(include_str!("../../tests/fixtures/hardware/lspci-k.txt"));
+        let offload = hybrid_offload(&gpus).unwrap();
+        assert_eq!(offload.gpu, gpus[1]);
+        assert_eq!(offload.driver, OffloadDriver::Nvidia);
+
+        let gpu = |name: &str, driver: Option<&str>| Gpu {
+            name: name.to_string(),
+            driver: driver.map(str::to_string),
+        };
+        let intel = gpu(
+            "Intel Corporation Alder Lake-P GT2 [Iris Xe Graphics]",
+            Some("i915"),
+        );
+        let amd = gpu(
+            "Advanced Micro Devices, Inc. [AMD/ATI] Navi 33",
+            Some("amdgpu"),
+        );
+        let nouveau = gpu(
+            "NVIDIA Corporation GA107M [GeForce RTX 3050]",
+            Some("nouveau"),
+        );
+        let unbound = gpu("NVIDIA Corporation GA107M [GeForce RTX 3050]", None);
+
+        let driver = |gpus: &[Gpu]| hybrid_offload(gpus).map(|offload| offload.driver);
+        assert_eq!(
+            driver(&[intel.clone(), amd.clone()]),
+            Some(OffloadDriver::Mesa)
+        );
+        // The open NVIDIA driver is a Mesa one
+        assert_eq!(driver(&[intel.clone(), nouveau]), Some(OffloadDriver::Mesa));
+        assert_eq!(driver(&[amd.clone(), unbound]), Some(OffloadDriver::Nvidia));
+        // A single GPU has nothing to offload to
+        assert_eq!(driver(&[intel]), None);
+        assert_eq!(driver(&[]), None);
+    }
+
+    #[test]
+    fn test_prime_run_script() {
+        let gpus = parse_lspci(include_str!("../../tests/fixtures/hardware/lspci-k.txt"));
+        let script = prime_run_script(&hybrid_offload(&gpus).unwrap());
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains("export __NV_PRIME_RENDER_OFFLOAD=1\n"));
+        assert!(script.contains("export __GLX_VENDOR_LIBRARY_NAME=nvidia\n"));
+        assert!(!script.contains("DRI_PRIME"));
+        assert!(script.ends_with("exec \"$@\"\n"));
+
+        let amd = Offload {
+            gpu: gpus[2].clone(),
+            driver: OffloadDriver::Mesa,
+        };
+        let script = prime_run_script(&amd);
+        assert!(script.contains("export DRI_PRIME=1\n"));
+        assert!(!script.contains("__NV_"));
+    }
+
+    #[test]
+    fn test_vaapi_packages() {
+        let gpus = parse_lspci(include_str!("../../tests/fixtures/hardware/lspci-k.txt"));
+        let cpu = parse_cpuinfo(INTEL_CPUINFO).unwrap();
+        assert_eq!(cpu.family, Some(6));
+        assert_eq!(cpu.model, Some(158));
+        assert_eq!(
+            vaapi_packages(&gpus, Some(&cpu)),
+            [
+                "intel-media-driver",
+                "nvidia-vaapi-driver",
+                "libva-mesa-driver"
+            ]
+        );
+
+        // Haswell predates the media driver
+        let haswell = CpuInfo {
+            model: Some(60),
+            ..cpu
+        };
+        assert_eq!(
+            vaapi_packages(&gpus[..1], Some(&haswell)),
+            ["libva-intel-driver"]
+        );
+        assert_eq!(vaapi_packages(&gpus[..1], None), ["intel-media-driver"]);
+        assert!(vaapi_packages(&[], Some(&haswell)).is_empty());
+    }
+
+    #[test]
+    fn test_parse_vainfo() {
+        let info = parse_vainfo(include_str!("../../tests/fixtures/hardware/vainfo.txt"));
+        assert_eq!(
+            info.driver.as_deref(),
+            Some("Intel iHD driver for Intel(R) Gen Graphics - 24.3.4 ()")
+        );
+        assert_eq!(
+            info.decode_profiles,
+            [
+                "VAProfileMPEG2Simple",
+                "VAProfileH264Main",
+                "VAProfileH264High",
+                "VAProfileHEVCMain",
+                "VAProfileVP9Profile0",
+                "VAProfileAV1Profile0",
+            ]
+        );
+        assert_eq!(parse_vainfo(""), VaapiInfo::default());
+    }
+
+    #[test]
+    fn test_parse_meminfo() {
+        let memory = parse_meminfo(include_str!("../../tests/fixtures/hardware/meminfo")).unwrap();
+        assert_eq!(memory.total, 32598716);
+        assert_eq!(memory.available, 21378204);
+        assert_eq!(memory.used(), 32598716 - 21378204);
+        assert_eq!(parse_meminfo("MemTotal: 100 kB\n"), None);
+    }
+
+    #[test]
+    fn test_parse_lsblk() {
+        let disks = parse_lsblk(include_str!("../../tests/fixtures/hardware/lsblk.txt"));
+        let names: Vec<&str> = disks.iter().map(|disk| disk.name.as_str()).collect();
+        // No optical drive, zram or empty card reader
+        assert_eq!(names, ["sda", "nvme0n1"]);
+        assert_eq!(disks[0].model.as_deref(), Some("WDC WD40EFRX-68N32N0"));
+        assert!(disks[0].rotational);
+        assert_eq!(disks[1].size, 1000204886016);
+        assert!(!disks[1].rotational);
+        assert_eq!(disks[1].path(), "/dev/nvme0n1");
+
+        let disks = parse_lsblk(r#"NAME="vda" MODEL="" SIZE="1024" TYPE="disk" ROTA="1""#);
+        assert_eq!(disks[0].model, None);
+    }
+
+    #[test]
+    fn test_parse_smart_health() {
+        assert_eq!(
+            parse_smart_health(include_str!(
+                "../../tests/fixtures/hardware/smartctl-ata.txt"
+            )),
+            Some(SmartHealth::Passed)
+        );
+        assert_eq!(
+            parse_smart_health(include_str!(
+                "../../tests/fixtures/hardware/smartctl-failed.txt"
+            )),
+            Some(SmartHealth::Failed)
+        );
+        assert_eq!(
+            parse_smart_health(include_str!(
+                "../../tests/fixtures/hardware/smartctl-denied.txt"
+            )),
+            None
+        );
+        assert_eq!(
+            parse_smart_health("SMART Health Status: OK\n"),
+            Some(SmartHealth::Passed)
+        );
+    }
+
+    #[test]
+    fn test_battery_health() {
+        let battery =
+            parse_power_supply(include_str!("../../tests/fixtures/hardware/battery.uevent"));
+        assert_eq!(battery.name, "BAT0");
+        assert_eq!(battery.cycle_count, Some(312));
+        assert_eq!(battery.health_percent(), Some(87));
+
+        let battery = parse_power_supply(include_str!(
+            "../../tests/fixtures/hardware/battery-charge.uevent"
+        ));
+        assert_eq!(battery.health_percent(), Some(95));
+        assert_eq!(battery.cycle_count, Some(0));
+
+        assert_eq!(parse_power_supply(AC_UEVENT).health_percent(), None);
+    }
+}
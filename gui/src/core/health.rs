@@ -0,0 +1,183 @@
+//! Health figures shown at a glance on the main page.
+//!
+//! Each figure is probed on its own thread and [`gather`] waits for all of
+//! them up to a timeout, so a slow or failing probe only leaves its own
+//! figure without a value.
+
+use super::{fs, package, systemd};
+use anyhow::Result;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// How long [`gather_all`] waits for the slowest probe.
+pub const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Free space on `/` below which the figure asks for attention.
+pub const LOW_SPACE: u64 = 10 * 1024 * 1024 * 1024;
+
+/// A figure of the health strip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stat {
+    /// Pending repo and AUR updates
+    Updates,
+    /// systemd units in the failed state
+    FailedUnits,
+    /// Dependencies nothing requires anymore
+    Orphans,
+    /// Bytes free on `/`
+    FreeSpace,
+}
+
+impl Stat {
+    pub const ALL: [Self; 4] = [
+        Self::Updates,
+        Self::FailedUnits,
+        Self::Orphans,
+        Self::FreeSpace,
+    ];
+
+    /// Measure the figure. Blocks, some probes query the network.
+    pub fn probe(self) -> Result<u64> {
+        Ok(match self {
+            Self::Updates => package::pending_update_count()? as u64,
+            Self::FailedUnits => systemd::list_failed_units()?.len() as u64,
+            Self::Orphans => package::orphans()?.len() as u64,
+            Self::FreeSpace => fs::free_space("/")?,
+        })
+    }
+
+    /// Whether `value` is worth acting on.
+    pub fn needs_attention(self, value: u64) -> bool {
+        match self {
+            Self::FreeSpace => value < LOW_SPACE,
+            _ => value > 0,
+        }
+    }
+}
+
+/// Outcome of one probe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reading {
+    Value(u64),
+    Failed(String),
+    /// Still running when [`gather`] stopped waiting
+    TimedOut,
+}
+
+/// Run every probe on its own thread and wait up to `timeout` for them,
+/// returning the readings in the order of `probes`.
+///
+/// Probes still running are left to finish on their own.
+pub fn gather<F>(
+    probes: impl IntoIterator<Item = (Stat, F)>,
+    timeout: Duration,
+) -> Vec<(Stat, Reading)>
+where
+    F: FnOnce() -> Result<u64> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    let mut readings: Vec<(Stat, Reading)> = probes
+        .into_iter()
+        .enumerate()
+        .map(|(index, (stat, probe))| {
+            let sender = sender.clone();
+            std::thread::spawn(move || {
+                let _ = sender.send((index, probe()));
+            });
+            (stat, Reading::TimedOut)
+        })
+        .collect();
+    drop(sender);
+
+    let deadline = Instant::now() + timeout;
+    let mut pending = readings.len();
+    while pending > 0 {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let Ok((index, result)) = receiver.recv_timeout(remaining) else {
+            break;
+        };
+        readings[index].1 = match result {
+            Ok(value) => Reading::Value(value),
+            Err(e) => Reading::Failed(format!("{:#}", e)),
+        };
+        pending -= 1;
+    }
+    readings
+}
+
+/// Every figure of the health strip. Blocks up to [`TIMEOUT`].
+pub fn gather_all() -> Vec<(Stat, Reading)> {
+    gather(
+        Stat::ALL
+            .into_iter()
+            .map(|stat| (stat, move || stat.probe())),
+        TIMEOUT,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Probe = Box<dyn FnOnce() -> Result<u64> + Send>;
+
+    #[test]
+    fn test_gather() {
+        let probes: Vec<(Stat, Probe)> = vec![
+            (
+                Stat::Updates,
+                Box::new(|| {
+                    std::thread::sleep(Duration::from_millis(100));
+                    Ok(3)
+                }),
+            ),
+            (
+                Stat::FailedUnits,
+                Box::new(|| anyhow::bail!("systemctl not found")),
+            ),
+            (
+                Stat::Orphans,
+                Box::new(|| {
+                    std::thread::sleep(Duration::from_secs(5));
+                    Ok(1)
+                }),
+            ),
+            (Stat::FreeSpace, Box::new(|| Ok(42))),
+        ];
+
+        let started = Instant::now();
+        let readings = gather(probes, Duration::from_millis(500));
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(
+            readings,
+            [
+                (Stat::Updates, Reading::Value(3)),
+                (
+                    Stat::FailedUnits,
+                    Reading::Failed("systemctl not found".to_string())
+                ),
+                (Stat::Orphans, Reading::TimedOut),
+                (Stat::FreeSpace, Reading::Value(42)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gather_returns_early() {
+        let probes = Stat::ALL.into_iter().map(|stat| (stat, || Ok(0)));
+        let started = Instant::now();
+        let readings = gather(probes, Duration::from_secs(30));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(readings
+            .iter()
+            .all(|(_, reading)| *reading == Reading::Value(0)));
+    }
+
+    #[test]
+    fn test_needs_attention() {
+        assert!(Stat::Updates.needs_attention(1));
+        assert!(!Stat::FailedUnits.needs_attention(0));
+        assert!(Stat::FreeSpace.needs_attention(LOW_SPACE - 1));
+        assert!(!Stat::FreeSpace.needs_attention(LOW_SPACE));
+    }
+}
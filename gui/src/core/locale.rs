@@ -0,0 +1,308 @@
+//! Locale generation and timezone settings.
+//!
+//! Locales are generated from the uncommented entries of /etc/locale.gen;
+//! the entries on offer come from glibc's list of supported locales.
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
+use std::process::Command;
+
+/// Locales generated by `locale-gen`.
+pub const LOCALE_GEN: &str = "/etc/locale.gen";
+
+/// Locales glibc can generate.
+const SUPPORTED: &str = "/usr/share/i18n/SUPPORTED";
+
+/// System locale set by `localectl set-locale`.
+const LOCALE_CONF: &str = "/etc/locale.conf";
+
+/// A locale with its character set, like `de_DE.UTF-8 UTF-8`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocaleEntry {
+    pub name: String,
+    pub charset: String,
+}
+
+impl LocaleEntry {
+    /// The entry as a locale.gen line.
+    pub fn line(&self) -> String {
+        format!("{} {}", self.name, self.charset)
+    }
+}
+
+/// Parse the supported locales, either glibc's `name/charset \` list or
+/// the `name charset` lines of locale.gen.
+pub fn parse_supported(content: &str) -> Vec<LocaleEntry> {
+    content
+        .lines()
+        .map(|line| line.trim().trim_end_matches('\\').trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.contains('='))
+        .filter_map(|line| {
+            let (name, charset) = line
+                .split_once('/')
+                .or_else(|| line.split_once(char::is_whitespace))?;
+            let name = name.trim();
+            is_valid_locale_name(name).then(|| LocaleEntry {
+                name: name.to_string(),
+                charset: charset.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parse a locale.gen line into the entry and whether it is enabled.
+/// Prose comments start with `#` and a space, entries right after it.
+fn parse_gen_line(line: &str) -> Option<(LocaleEntry, bool)> {
+    let line = line.trim_end();
+    let (entry, enabled) = match line.strip_prefix('#') {
+        Some(entry) => (entry, false),
+        None => (line, true),
+    };
+    if entry.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let mut fields = entry.split_whitespace();
+    let (name, charset) = (fields.next()?, fields.next()?);
+    if fields.next().is_some() || !is_valid_locale_name(name) {
+        return None;
+    }
+    Some((
+        LocaleEntry {
+            name: name.to_string(),
+            charset: charset.to_string(),
+        },
+        enabled,
+    ))
+}
+
+/// Names of the locales enabled in locale.gen.
+pub fn enabled_locales(locale_gen: &str) -> Vec<String> {
+    locale_gen
+        .lines()
+        .filter_map(parse_gen_line)
+        .filter(|(_, enabled)| *enabled)
+        .map(|(entry, _)| entry.name)
+        .collect()
+}
+
+/// Rewrite locale.gen so exactly the `enabled` locales are uncommented.
+///
+/// Lines whose state does not change are kept as they are. Enabled locales
+/// without a line are appended.
+pub fn edit_locale_gen(locale_gen: &str, enabled: &[LocaleEntry]) -> String {
+    let wanted: HashSet<&str> = enabled.iter().map(|entry| entry.name.as_str()).collect();
+    let mut written: HashSet<String> = HashSet::new();
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in locale_gen.lines() {
+        let Some((entry, is_enabled)) = parse_gen_line(line) else {
+            lines.push(line.to_string());
+            continue;
+        };
+        // Enable only the first line of a locale listed twice
+        let enable = wanted.contains(entry.name.as_str()) && !written.contains(&entry.name);
+        if enable {
+            written.insert(entry.name.clone());
+        }
+        if enable == is_enabled {
+            lines.push(line.to_string());
+        } else if enable {
+            lines.push(entry.line());
+        } else {
+            lines.push(format!("#{}", entry.line()));
+        }
+    }
+
+    for entry in enabled {
+        if written.insert(entry.name.clone()) {
+            lines.push(entry.line());
+        }
+    }
+
+    let mut content = lines.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    content
+}
+
+/// The `LANG` value of locale.conf.
+pub fn parse_locale_conf(content: &str) -> Option<String> {
+    content
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("LANG="))
+        .map(|lang| lang.trim().trim_matches('"').to_string())
+        .filter(|lang| !lang.is_empty())
+}
+
+/// Whether `name` looks like a locale name, like `sr_RS@latin` or
+/// `en_US.UTF-8`.
+pub fn is_valid_locale_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '@' | '-'))
+}
+
+/// Supported locales, sorted by name.
+pub fn supported() -> Result<Vec<LocaleEntry>> {
+    let content = std::fs::read_to_string(SUPPORTED)
+        .with_context(|| format!("Failed to read {}", SUPPORTED))?;
+    let mut entries = parse_supported(&content);
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+/// Current locale.gen contents, empty if the file is missing.
+pub fn read_locale_gen() -> Result<String> {
+    match std::fs::read_to_string(LOCALE_GEN) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", LOCALE_GEN)),
+    }
+}
+
+/// The system's default locale.
+pub fn current_lang() -> Option<String> {
+    std::fs::read_to_string(LOCALE_CONF)
+        .ok()
+        .and_then(|content| parse_locale_conf(&content))
+}
+
+fn timedatectl(args: &[&str]) -> Result<String> {
+    let output = Command::new("timedatectl")
+        .args(args)
+        .output()
+        .context("Failed to run timedatectl")?;
+    if !output.status.success() {
+        bail!(
+            "timedatectl {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Timezones known to the system, like `Europe/Berlin`.
+pub fn timezones() -> Result<Vec<String>> {
+    Ok(timedatectl(&["list-timezones", "--no-pager"])?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// The system's timezone.
+pub fn current_timezone() -> Option<String> {
+    timedatectl(&["show", "-p", "Timezone", "--value"])
+        .ok()
+        .map(|timezone| timezone.trim().to_string())
+        .filter(|timezone| !timezone.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCALE_GEN_FIXTURE: &str = include_str!("../../tests/fixtures/locale/locale.gen");
+
+    fn entry(name: &str, charset: &str) -> LocaleEntry {
+        LocaleEntry {
+            name: name.to_string(),
+            charset: charset.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_supported() {
+        let glibc = parse_supported(include_str!("../../tests/fixtures/locale/SUPPORTED"));
+        assert_eq!(glibc.len(), 10);
+        assert_eq!(glibc[0], entry("aa_DJ.UTF-8", "UTF-8"));
+        assert_eq!(glibc[4], entry("de_DE@euro", "ISO-8859-15"));
+        assert_eq!(glibc[9], entry("sr_RS@latin", "UTF-8"));
+
+        let plain = parse_supported("aa_DJ.UTF-8 UTF-8\nde_DE ISO-8859-1\n");
+        assert_eq!(
+            plain,
+            [entry("aa_DJ.UTF-8", "UTF-8"), entry("de_DE", "ISO-8859-1")]
+        );
+    }
+
+    #[test]
+    fn test_enabled_locales() {
+        assert_eq!(enabled_locales(LOCALE_GEN_FIXTURE), ["en_US.UTF-8"]);
+        // Header examples are prose, not entries
+        assert!(!enabled_locales(LOCALE_GEN_FIXTURE).contains(&"en_US".to_string()));
+        assert!(enabled_locales("").is_empty());
+    }
+
+    #[test]
+    fn test_edit_locale_gen_round_trip() {
+        let current = [entry("en_US.UTF-8", "UTF-8")];
+        assert_eq!(
+            edit_locale_gen(LOCALE_GEN_FIXTURE, &current),
+            LOCALE_GEN_FIXTURE
+        );
+
+        let wanted = [entry("de_DE.UTF-8", "UTF-8"), entry("en_US.UTF-8", "UTF-8")];
+        let edited = edit_locale_gen(LOCALE_GEN_FIXTURE, &wanted);
+        assert_eq!(enabled_locales(&edited), ["de_DE.UTF-8", "en_US.UTF-8"]);
+        // Only the one line changed, the header is untouched
+        let changed: Vec<(&str, &str)> = LOCALE_GEN_FIXTURE
+            .lines()
+            .zip(edited.lines())
+            .filter(|(old, new)| old != new)
+            .collect();
+        assert_eq!(changed, [("#de_DE.UTF-8 UTF-8", "de_DE.UTF-8 UTF-8")]);
+        // And back
+        assert_eq!(edit_locale_gen(&edited, &current), LOCALE_GEN_FIXTURE);
+    }
+
+    #[test]
+    fn test_edit_locale_gen_disable_and_append() {
+        let edited = edit_locale_gen(
+            LOCALE_GEN_FIXTURE,
+            &[entry("sr_RS@latin", "UTF-8"), entry("nl_NL.UTF-8", "UTF-8")],
+        );
+        assert!(edited.contains("\n#en_US.UTF-8 UTF-8\n"));
+        assert!(edited.contains("\nsr_RS@latin UTF-8\n"));
+        assert!(edited.ends_with("\nnl_NL.UTF-8 UTF-8\n"));
+        assert_eq!(enabled_locales(&edited), ["sr_RS@latin", "nl_NL.UTF-8"]);
+
+        assert_eq!(
+            edit_locale_gen("", &[entry("en_US.UTF-8", "UTF-8")]),
+            "en_US.UTF-8 UTF-8\n"
+        );
+        // A locale listed twice is generated once
+        assert_eq!(
+            edit_locale_gen(
+                "#de_DE.UTF-8 UTF-8\n#de_DE.UTF-8 UTF-8\n",
+                &[entry("de_DE.UTF-8", "UTF-8")]
+            ),
+            "de_DE.UTF-8 UTF-8\n#de_DE.UTF-8 UTF-8\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_locale_conf() {
+        assert_eq!(
+            parse_locale_conf(include_str!("../../tests/fixtures/locale/locale.conf")).as_deref(),
+            Some("en_US.UTF-8")
+        );
+        assert_eq!(
+            parse_locale_conf("LANG=\"de_DE.UTF-8\"\n").as_deref(),
+            Some("de_DE.UTF-8")
+        );
+        assert_eq!(parse_locale_conf("LC_TIME=C\n"), None);
+    }
+
+    #[test]
+    fn test_is_valid_locale_name() {
+        assert!(is_valid_locale_name("sr_RS@latin"));
+        assert!(is_valid_locale_name("en_US.UTF-8"));
+        assert!(!is_valid_locale_name("en_US; rm"));
+        assert!(!is_valid_locale_name(""));
+    }
+}
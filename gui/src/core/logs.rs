@@ -0,0 +1,93 @@
+//! Application log file access and level handling.
+
+use log::LevelFilter;
+use std::path::PathBuf;
+
+/// Log file name in the app's cache directory.
+pub const LOG_FILE: &str = "app.log";
+
+/// Selectable log levels, most severe first.
+pub const LEVELS: [LevelFilter; 5] = [
+    LevelFilter::Error,
+    LevelFilter::Warn,
+    LevelFilter::Info,
+    LevelFilter::Debug,
+    LevelFilter::Trace,
+];
+
+/// Path of the current log file.
+pub fn log_path() -> PathBuf {
+    crate::core::paths::get().cache.join(LOG_FILE)
+}
+
+/// Read the current log file.
+pub fn read_log() -> std::io::Result<String> {
+    std::fs::read_to_string(log_path())
+}
+
+/// Parse a level name as stored in the config, falling back to `Info`.
+pub fn parse_level(name: &str) -> LevelFilter {
+    name.parse().unwrap_or(LevelFilter::Info)
+}
+
+/// Keep log lines at or above `max_level`.
+///
+/// Lines without a level, such as continuations of multi-line messages,
+/// follow the line before them.
+pub fn filter_lines(content: &str, max_level: LevelFilter) -> String {
+    let mut keep = true;
+    let mut filtered = String::new();
+
+    for line in content.lines() {
+        if let Some(level) = line_level(line) {
+            keep = level <= max_level;
+        }
+        if keep {
+            filtered.push_str(line);
+            filtered.push('\n');
+        }
+    }
+
+    filtered
+}
+
+/// Level of a `<timestamp> <LEVEL> [target] message` line.
+fn line_level(line: &str) -> Option<log::Level> {
+    let mut parts = line.split_whitespace();
+    let timestamp = parts.next()?;
+    if !timestamp.ends_with('Z') {
+        return None;
+    }
+    parts.next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "\
+2026-10-16T08:00:00Z INFO  [xero_toolkit] Starting
+2026-10-16T08:00:01Z DEBUG [xero_toolkit::ui] Loading page
+with a second line
+2026-10-16T08:00:02Z ERROR [xero_toolkit::core] Failed
+";
+
+    #[test]
+    fn test_filter_lines_by_level() {
+        assert_eq!(filter_lines(LOG, LevelFilter::Trace), LOG);
+
+        let filtered = filter_lines(LOG, LevelFilter::Info);
+        assert!(filtered.contains("Starting"));
+        assert!(!filtered.contains("second line"));
+        assert!(filtered.contains("Failed"));
+
+        assert_eq!(filter_lines(LOG, LevelFilter::Error).lines().count(), 1);
+    }
+
+    #[test]
+    fn test_parse_level() {
+        assert_eq!(parse_level("debug"), LevelFilter::Debug);
+        assert_eq!(parse_level("WARN"), LevelFilter::Warn);
+        assert_eq!(parse_level("nonsense"), LevelFilter::Info);
+    }
+}
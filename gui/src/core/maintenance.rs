@@ -0,0 +1,391 @@
+//! Scheduled maintenance through a systemd timer.
+//!
+//! The chosen tasks are rendered into a root-owned shell script from the
+//! same command sequences the servicing buttons run, and a oneshot service
+//! started by a timer runs it.
+
+use crate::core::actions;
+use crate::core::aur::is_executable_in_path;
+use crate::core::mirrorlist::{Backend, Protocol};
+use crate::core::privileged_fs;
+use crate::i18n::{gettext, gettext_noop};
+use crate::ui::task_runner::{Command, CommandSequence, CommandType};
+use std::path::Path;
+
+/// Script run by the service.
+pub const SCRIPT_PATH: &str = "/usr/local/lib/xero-toolkit/maintenance.sh";
+
+/// Timer unit starting the maintenance run.
+pub const TIMER_UNIT: &str = "xero-maintenance.timer";
+
+const SERVICE_UNIT: &str = "xero-maintenance.service";
+const UNIT_DIR: &str = "/etc/systemd/system";
+
+/// Header line listing the task ids in the script.
+const TASKS_HEADER: &str = "# Tasks: ";
+
+/// How often the timer fires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cadence {
+    Weekly,
+    Monthly,
+}
+
+impl Cadence {
+    pub const ALL: [Cadence; 2] = [Cadence::Weekly, Cadence::Monthly];
+
+    /// `OnCalendar=` value.
+    pub fn calendar(self) -> &'static str {
+        match self {
+            Cadence::Weekly => "weekly",
+            Cadence::Monthly => "monthly",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Cadence::Weekly => gettext_noop("Weekly"),
+            Cadence::Monthly => gettext_noop("Monthly"),
+        }
+    }
+}
+
+/// A cleanup task that can be scheduled.
+#[derive(Debug, Clone, Copy)]
+pub struct MaintenanceTask {
+    /// Identifier recorded in the script.
+    pub id: &'static str,
+    pub label: &'static str,
+    commands: fn() -> CommandSequence,
+    available: fn() -> bool,
+}
+
+impl MaintenanceTask {
+    /// Commands the task runs, the same ones its button runs.
+    pub fn commands(&self) -> CommandSequence {
+        (self.commands)()
+    }
+
+    /// Whether the task's tools are installed.
+    pub fn is_available(&self) -> bool {
+        (self.available)()
+    }
+}
+
+/// All schedulable tasks.
+pub const TASKS: &[MaintenanceTask] = &[
+    MaintenanceTask {
+        id: "trim-package-cache",
+        label: gettext_noop("Trim the package cache"),
+        commands: actions::trim_package_cache,
+        available: || is_executable_in_path("paccache"),
+    },
+    MaintenanceTask {
+        id: "remove-unused-flatpaks",
+        label: gettext_noop("Remove unused Flatpak runtimes"),
+        commands: actions::remove_unused_flatpaks,
+        available: || is_executable_in_path("flatpak"),
+    },
+    MaintenanceTask {
+        id: "vacuum-journal",
+        label: gettext_noop("Shrink the system journal"),
+        commands: actions::vacuum_journal,
+        available: || true,
+    },
+    MaintenanceTask {
+        id: "update-mirrorlist",
+        label: gettext_noop("Refresh the mirrorlist"),
+        commands: || {
+            actions::mirrorlist_commands(false, None, Backend::default(), Protocol::default())
+        },
+        available: || is_executable_in_path("rate-mirrors"),
+    },
+    MaintenanceTask {
+        id: "btrfs-scrub",
+        label: gettext_noop("Scrub the btrfs root filesystem"),
+        commands: actions::btrfs_scrub,
+        available: crate::core::fs::is_root_btrfs,
+    },
+];
+
+/// Quote `arg` for the shell.
+fn quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-/:=,+@".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Render the maintenance script for tasks given as ids with their
+/// commands. A failing step does not stop the others, but fails the run.
+///
+/// AUR, script and file write steps are left out: the first cannot run as
+/// root and the others only exist for the task runner.
+pub fn render_script(tasks: &[(&str, CommandSequence)]) -> String {
+    let ids: Vec<&str> = tasks.iter().map(|(id, _)| *id).collect();
+    let mut script = format!(
+        "#!/bin/bash\n\
+         # Written by Xero Toolkit; saving the schedule again replaces it.\n\
+         {}{}\n\
+         status=0\n\
+         run() {{\n    \"$@\" || status=1\n}}\n",
+        TASKS_HEADER,
+        ids.join(",")
+    );
+    for (id, commands) in tasks {
+        script.push_str(&format!("\n# {}\n", id));
+        for command in commands.commands() {
+            if command.command_type == CommandType::Aur
+                || command.script.is_some()
+                || command.write.is_some()
+            {
+                continue;
+            }
+            let mut line = vec!["run".to_string(), quote(&command.program)];
+            line.extend(command.args.iter().map(|arg| quote(arg)));
+            script.push_str(&format!(
+                "echo {}\n{}\n",
+                quote(&format!("==> {}", command.description)),
+                line.join(" ")
+            ));
+        }
+    }
+    script.push_str("\nexit \"$status\"\n");
+    script
+}
+
+/// Task ids recorded in a maintenance script.
+pub fn parse_script_tasks(script: &str) -> Vec<String> {
+    script
+        .lines()
+        .find_map(|line| line.strip_prefix(TASKS_HEADER))
+        .map(|ids| {
+            ids.split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The oneshot service running the script at low priority.
+pub fn render_service() -> String {
+    format!(
+        "[Unit]\n\
+         Description=Xero Toolkit scheduled maintenance\n\
+         Wants=network-online.target\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=oneshot\n\
+         ExecStart={}\n\
+         Nice=19\n\
+         IOSchedulingClass=idle\n",
+        SCRIPT_PATH
+    )
+}
+
+/// The timer starting the service at `cadence`, catching up on runs
+/// missed while the machine was off.
+pub fn render_timer(cadence: Cadence) -> String {
+    format!(
+        "[Unit]\n\
+         Description=Xero Toolkit scheduled maintenance\n\
+         \n\
+         [Timer]\n\
+         OnCalendar={}\n\
+         Persistent=true\n\
+         RandomizedDelaySec=1h\n\
+         \n\
+         [Install]\n\
+         WantedBy=timers.target\n",
+        cadence.calendar()
+    )
+}
+
+/// Cadence of a rendered timer unit.
+pub fn parse_timer_cadence(timer: &str) -> Option<Cadence> {
+    let calendar = timer
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("OnCalendar="))?;
+    Cadence::ALL
+        .into_iter()
+        .find(|cadence| cadence.calendar() == calendar.trim())
+}
+
+fn unit_path(unit: &str) -> String {
+    format!("{}/{}", UNIT_DIR, unit)
+}
+
+/// The installed schedule: tasks and cadence, `None` if none is installed.
+pub fn installed() -> Option<(Vec<String>, Cadence)> {
+    let script = std::fs::read_to_string(SCRIPT_PATH).ok()?;
+    let timer = std::fs::read_to_string(unit_path(TIMER_UNIT)).ok()?;
+    Some((parse_script_tasks(&script), parse_timer_cadence(&timer)?))
+}
+
+/// Write the script and units for `tasks` and enable the timer.
+pub fn install_commands(tasks: &[&MaintenanceTask], cadence: Cadence) -> CommandSequence {
+    let rendered: Vec<(&str, CommandSequence)> = tasks
+        .iter()
+        .map(|task| (task.id, task.commands()))
+        .collect();
+    let script_dir = Path::new(SCRIPT_PATH)
+        .parent()
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    CommandSequence::new()
+        .then(
+            Command::builder()
+                .privileged()
+                .program("mkdir")
+                .args(&["-p", &script_dir])
+                .description(&gettext("Creating script directory..."))
+                .build(),
+        )
+        .then(
+            privileged_fs::write_file(SCRIPT_PATH, &render_script(&rendered))
+                .mode(0o755)
+                .command(&gettext("Writing maintenance script...")),
+        )
+        .then(
+            privileged_fs::write_file(unit_path(SERVICE_UNIT), &render_service())
+                .command(&gettext("Writing maintenance service...")),
+        )
+        .then(
+            privileged_fs::write_file(unit_path(TIMER_UNIT), &render_timer(cadence))
+                .command(&gettext("Writing maintenance timer...")),
+        )
+        .then(
+            Command::builder()
+                .privileged()
+                .program("systemctl")
+                .args(&["daemon-reload"])
+                .description(&gettext("Reloading systemd units..."))
+                .build(),
+        )
+        .then(
+            Command::builder()
+                .privileged()
+                .program("systemctl")
+                .args(&["enable", "--now", TIMER_UNIT])
+                .description(&gettext("Enabling maintenance timer..."))
+                .build(),
+        )
+}
+
+/// Disable the timer and remove the script, the units and their backups.
+pub fn remove_commands() -> CommandSequence {
+    CommandSequence::new()
+        .then(
+            Command::builder()
+                .privileged()
+                .program("systemctl")
+                .args(&["disable", "--now", TIMER_UNIT])
+                .description(&gettext("Disabling maintenance timer..."))
+                .build(),
+        )
+        .then(
+            Command::script(
+                &gettext("Removing maintenance units..."),
+                r#"rm -f -- "$SCRIPT" "$SCRIPT".bak.* "$SERVICE" "$SERVICE".bak.* "$TIMER" "$TIMER".bak.*
+rmdir --ignore-fail-on-non-empty -- "$(dirname -- "$SCRIPT")""#,
+            )
+            .privileged()
+            .var("SCRIPT", SCRIPT_PATH)
+            .var("SERVICE", &unit_path(SERVICE_UNIT))
+            .var("TIMER", &unit_path(TIMER_UNIT))
+            .build(),
+        )
+        .then(
+            Command::builder()
+                .privileged()
+                .program("systemctl")
+                .args(&["daemon-reload"])
+                .description(&gettext("Reloading systemd units..."))
+                .build(),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(program: &str, args: &[&str]) -> Command {
+        Command::builder()
+            .privileged()
+            .program(program)
+            .args(args)
+            .description("Step")
+            .build()
+    }
+
+    #[test]
+    fn test_render_script() {
+        let script = render_script(&[
+            (
+                "vacuum-journal",
+                CommandSequence::new().then(step("journalctl", &["--vacuum-time=4weeks"])),
+            ),
+            (
+                "update-mirrorlist",
+                CommandSequence::new()
+                    .then(
+                        Command::builder()
+                            .aur()
+                            .args(&["-S", "rate-mirrors"])
+                            .description("Installing")
+                            .build(),
+                    )
+                    .then(step("sh", &["-c", "rate-mirrors arch | tee 'list'"]))
+                    .then(Command::write_file("Writing", "/etc/pacman.d/mirrorlist").build()),
+            ),
+        ]);
+
+        assert!(script.starts_with("#!/bin/bash\n"));
+        assert!(script.contains("\nrun journalctl --vacuum-time=4weeks\n"));
+        assert!(script.contains("\nrun sh -c 'rate-mirrors arch | tee '\\''list'\\'''\n"));
+        assert!(script.contains("\necho '==> Step'\n"));
+        // AUR steps cannot run as root
+        assert!(!script.contains("rate-mirrors\n"));
+        // Neither can file writes
+        assert!(!script.contains("Writing"));
+        assert!(script.ends_with("exit \"$status\"\n"));
+
+        assert_eq!(
+            parse_script_tasks(&script),
+            ["vacuum-journal", "update-mirrorlist"]
+        );
+        assert!(parse_script_tasks("#!/bin/bash\n").is_empty());
+    }
+
+    #[test]
+    fn test_render_units() {
+        let service = render_service();
+        assert!(service.contains(&format!("ExecStart={}\n", SCRIPT_PATH)));
+        assert!(service.contains("Type=oneshot\n"));
+
+        for cadence in Cadence::ALL {
+            let timer = render_timer(cadence);
+            assert!(timer.contains("Persistent=true\n"));
+            assert!(timer.contains("WantedBy=timers.target\n"));
+            assert_eq!(parse_timer_cadence(&timer), Some(cadence));
+        }
+        assert_eq!(parse_timer_cadence("[Timer]\nOnCalendar=daily\n"), None);
+    }
+
+    #[test]
+    fn test_task_ids() {
+        for (index, task) in TASKS.iter().enumerate() {
+            assert!(TASKS[index + 1..].iter().all(|other| other.id != task.id));
+            assert!(!task.id.contains(','));
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Swap detection and zram-generator configuration.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// Config file read by zram-generator.
+pub const ZRAM_CONFIG_PATH: &str = "/etc/systemd/zram-generator.conf";
+
+/// Unit generated for the first zram device.
+pub const ZRAM_UNIT: &str = "systemd-zram-setup@zram0.service";
+
+/// Compression algorithms offered for zram, best ratio first.
+pub const ZRAM_ALGORITHMS: &[&str] = &["zstd", "lz4", "lzo-rle"];
+
+/// Share of RAM offered as zram size, in percent.
+pub const ZRAM_SIZE_PERCENTS: &[u8] = &[25, 50, 75, 100];
+
+/// Entry from /proc/swaps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapDevice {
+    pub name: String,
+    pub kind: String,
+    /// Size in KiB.
+    pub size: u64,
+    /// Used space in KiB.
+    pub used: u64,
+    pub priority: i32,
+}
+
+impl SwapDevice {
+    /// Whether this swap lives on a zram device.
+    pub fn is_zram(&self) -> bool {
+        self.name.starts_with("/dev/zram")
+    }
+}
+
+/// Entry from `zramctl --json`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ZramDevice {
+    pub name: String,
+    #[serde(default)]
+    pub algorithm: String,
+    #[serde(default)]
+    pub mountpoint: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ZramctlOutput {
+    #[serde(default)]
+    zramctl: Vec<ZramDevice>,
+}
+
+/// Values used to generate zram-generator.conf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZramConfig {
+    /// zram device size as a percentage of RAM.
+    pub size_percent: u8,
+    pub algorithm: String,
+}
+
+impl ZramConfig {
+    /// Render the config for a single zram0 swap device.
+    pub fn render(&self) -> String {
+        format!(
+            "# Generated by XeroLinux Toolkit\n\
+             [zram0]\n\
+             zram-size = ram * {} / 100\n\
+             compression-algorithm = {}\n\
+             swap-priority = 100\n\
+             fs-type = swap\n",
+            self.size_percent, self.algorithm
+        )
+    }
+}
+
+/// Parse /proc/swaps content, skipping the header line.
+pub fn parse_proc_swaps(content: &str) -> Vec<SwapDevice> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some(SwapDevice {
+                name: fields.next()?.to_string(),
+                kind: fields.next()?.to_string(),
+                size: fields.next()?.parse().ok()?,
+                used: fields.next()?.parse().ok()?,
+                priority: fields.next()?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Parse `zramctl --json` output.
+pub fn parse_zramctl(json: &str) -> Result<Vec<ZramDevice>> {
+    if json.trim().is_empty() {
+        // zramctl prints nothing when no devices exist
+        return Ok(Vec::new());
+    }
+    let output: ZramctlOutput =
+        serde_json::from_str(json).context("Failed to parse zramctl output")?;
+    Ok(output.zramctl)
+}
+
+/// Currently active swap devices.
+pub fn active_swaps() -> Result<Vec<SwapDevice>> {
+    let content = std::fs::read_to_string("/proc/swaps").context("Failed to read /proc/swaps")?;
+    Ok(parse_proc_swaps(&content))
+}
+
+/// Configured zram devices, empty if zramctl is unavailable.
+pub fn zram_devices() -> Vec<ZramDevice> {
+    let Ok(output) = std::process::Command::new("zramctl")
+        .args(["--json", "--output", "NAME,ALGORITHM,MOUNTPOINT"])
+        .output()
+    else {
+        return Vec::new();
+    };
+
+    parse_zramctl(&String::from_utf8_lossy(&output.stdout)).unwrap_or_else(|e| {
+        log::warn!("{}", e);
+        Vec::new()
+    })
+}
+
+/// Whether a zram-generator config is present.
+pub fn zram_configured() -> bool {
+    std::path::Path::new(ZRAM_CONFIG_PATH).exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_swaps() {
+        let content = "\
+Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority
+/swapfile                               file\t\t8388604\t\t0\t\t-2
+/dev/zram0                              partition\t16281596\t\t1024\t\t100
+";
+        let swaps = parse_proc_swaps(content);
+        assert_eq!(swaps.len(), 2);
+        assert_eq!(swaps[0].name, "/swapfile");
+        assert_eq!(swaps[0].kind, "file");
+        assert_eq!(swaps[0].size, 8388604);
+        assert_eq!(swaps[0].priority, -2);
+        assert!(!swaps[0].is_zram());
+        assert!(swaps[1].is_zram());
+        assert_eq!(swaps[1].used, 1024);
+    }
+
+    #[test]
+    fn test_parse_proc_swaps_empty() {
+        let content = "Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority\n";
+        assert!(parse_proc_swaps(content).is_empty());
+    }
+
+    #[test]
+    fn test_parse_zramctl() {
+        let json = r#"{
+   "zramctl": [
+      {"name":"/dev/zram0", "algorithm":"zstd", "mountpoint":"[SWAP]"}
+   ]
+}"#;
+        let devices = parse_zramctl(json).unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].name, "/dev/zram0");
+        assert_eq!(devices[0].algorithm, "zstd");
+        assert_eq!(devices[0].mountpoint.as_deref(), Some("[SWAP]"));
+        assert!(parse_zramctl("").unwrap().is_empty());
+        assert!(parse_zramctl("not json").is_err());
+    }
+
+    #[test]
+    fn test_render_config() {
+        let config = ZramConfig {
+            size_percent: 50,
+            algorithm: "zstd".into(),
+        };
+        let rendered = config.render();
+        assert!(rendered.contains("[zram0]\n"));
+        assert!(rendered.contains("zram-size = ram * 50 / 100\n"));
+        assert!(rendered.contains("compression-algorithm = zstd\n"));
+        assert!(rendered.contains("fs-type = swap\n"));
+    }
+}
@@ -0,0 +1,302 @@
+//! Ranked pacman mirrorlists written by rate-mirrors or reflector.
+//!
+//! rate-mirrors probes mirrors country by country, starting from an entry
+//! country and jumping to its neighbours. Without one it starts in the US,
+//! which can leave users elsewhere with mirrors on another continent.
+//! reflector instead ranks the mirrors of the countries it is given.
+//!
+//! Each update first copies the old mirrorlist to
+//! `mirrorlist.bak.<YYYYmmdd-HHMMSS>`, the naming the daemon uses for its
+//! backups, and the oldest copies beyond [`BACKUPS_KEPT`] are removed.
+
+use std::path::{Path, PathBuf};
+
+/// Mirrorlist of the Arch repositories.
+pub const ARCH_MIRRORLIST: &str = "/etc/pacman.d/mirrorlist";
+
+/// Number of mirrors shown after an update.
+pub const TOP_MIRRORS: usize = 5;
+
+/// Number of mirrorlist backups kept.
+pub const BACKUPS_KEPT: usize = 5;
+
+/// Mirrors reflector ranks by download rate, after taking the most
+/// recently synchronized ones.
+const REFLECTOR_LATEST: &str = "20";
+
+/// Tool ranking the mirrors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    #[default]
+    RateMirrors,
+    Reflector,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 2] = [Backend::RateMirrors, Backend::Reflector];
+
+    /// Package, and program, of the tool.
+    pub fn package(self) -> &'static str {
+        match self {
+            Self::RateMirrors => "rate-mirrors",
+            Self::Reflector => "reflector",
+        }
+    }
+}
+
+/// Protocols of the mirrors to use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Protocol {
+    #[default]
+    Https,
+    /// HTTPS and plain HTTP, for more mirrors nearby
+    Any,
+}
+
+/// rate-mirrors options keeping the search around `country`, or none to
+/// let it rank mirrors globally.
+pub fn region_args(country: Option<&str>) -> String {
+    match country {
+        // Fewer neighbours and jumps keep it from wandering off the region
+        Some(country) => {
+            format!(" --entry-country {country} --country-neighbors-per-country 2 --max-jumps 2")
+        }
+        None => String::new(),
+    }
+}
+
+/// rate-mirrors options limiting it to `protocol`; it tries both without.
+pub fn protocol_args(protocol: Protocol) -> &'static str {
+    match protocol {
+        Protocol::Https => " --protocol https",
+        Protocol::Any => "",
+    }
+}
+
+/// reflector arguments writing the Arch mirrorlist ranked around
+/// `country`, or from every country without one.
+pub fn reflector_args(country: Option<&str>, protocol: Protocol) -> Vec<String> {
+    let protocols = match protocol {
+        Protocol::Https => "https",
+        Protocol::Any => "https,http",
+    };
+    let mut args: Vec<String> = [
+        "--save",
+        ARCH_MIRRORLIST,
+        "--protocol",
+        protocols,
+        "--latest",
+        REFLECTOR_LATEST,
+        "--sort",
+        "rate",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    if let Some(country) = country {
+        args.extend(["--country".to_string(), country.to_string()]);
+    }
+    args
+}
+
+/// A copy of a mirrorlist saved before it was replaced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// `YYYYmmdd-HHMMSS`, with a counter for copies made within a second
+    pub stamp: String,
+}
+
+impl Backup {
+    /// When the copy was made, as `YYYY-mm-dd HH:MM:SS`.
+    pub fn date(&self) -> String {
+        let s = &self.stamp;
+        format!(
+            "{}-{}-{} {}:{}:{}",
+            &s[0..4],
+            &s[4..6],
+            &s[6..8],
+            &s[9..11],
+            &s[11..13],
+            &s[13..15]
+        )
+    }
+}
+
+/// Whether `stamp` starts with `YYYYmmdd-HHMMSS`.
+fn is_stamp(stamp: &str) -> bool {
+    let bytes = stamp.as_bytes();
+    bytes.len() >= 15
+        && bytes[8] == b'-'
+        && bytes[..15]
+            .iter()
+            .enumerate()
+            .all(|(i, byte)| i == 8 || byte.is_ascii_digit())
+}
+
+/// Backups of the mirrorlist at `path`, newest first.
+pub fn backups(path: &Path) -> Vec<Backup> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.bak.", name.to_string_lossy());
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut backups: Vec<Backup> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let stamp = name.strip_prefix(&prefix)?;
+            is_stamp(stamp).then(|| Backup {
+                path: entry.path(),
+                stamp: stamp.to_string(),
+            })
+        })
+        .collect();
+    // Stamps sort by time
+    backups.sort_by(|a, b| b.stamp.cmp(&a.stamp));
+    backups
+}
+
+/// Backups of the mirrorlist at `path` beyond the newest `keep`, to remove.
+pub fn stale_backups(path: &Path, keep: usize) -> Vec<PathBuf> {
+    backups(path)
+        .into_iter()
+        .skip(keep)
+        .map(|backup| backup.path)
+        .collect()
+}
+
+/// The first `limit` active servers of a mirrorlist, fastest first, without
+/// the `$repo/os/$arch` suffix.
+pub fn ranked_mirrors(content: &str, limit: usize) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == "Server").then(|| value.trim())
+        })
+        .map(|server| server.trim_end_matches("$repo/os/$arch").to_string())
+        .take(limit)
+        .collect()
+}
+
+/// The fastest mirrors of the Arch mirrorlist; empty if it can't be read.
+pub fn top_mirrors() -> Vec<String> {
+    std::fs::read_to_string(ARCH_MIRRORLIST)
+        .map(|content| ranked_mirrors(&content, TOP_MIRRORS))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranked_mirrors() {
+        let content = include_str!("../../tests/fixtures/mirrorlist/rate-mirrors.txt");
+        assert_eq!(
+            ranked_mirrors(content, TOP_MIRRORS),
+            [
+                "https://mirror.netcologne.de/archlinux/",
+                "https://ftp.halifax.rwth-aachen.de/archlinux/",
+                "https://mirror.nl.leaseweb.net/archlinux/",
+                "https://mirror.cyberbits.eu/archlinux/",
+                "https://mirror.moson.org/arch/",
+            ]
+        );
+        assert_eq!(ranked_mirrors(content, 1).len(), 1);
+        assert!(ranked_mirrors("# FETCHED MIRRORS: 0\n", TOP_MIRRORS).is_empty());
+    }
+
+    #[test]
+    fn test_reflector_args() {
+        let args = reflector_args(Some("DE"), Protocol::Https).join(" ");
+        assert_eq!(
+            args,
+            "--save /etc/pacman.d/mirrorlist --protocol https --latest 20 --sort rate --country DE"
+        );
+        let args = reflector_args(None, Protocol::Any);
+        assert!(args.contains(&"https,http".to_string()));
+        assert!(!args.contains(&"--country".to_string()));
+        assert_eq!(protocol_args(Protocol::Https), " --protocol https");
+        assert_eq!(protocol_args(Protocol::Any), "");
+    }
+
+    /// An empty mirrorlist in a new temporary directory.
+    fn mirrorlist(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("xero-mirrorlist-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mirrorlist");
+        std::fs::write(&path, "").unwrap();
+        path
+    }
+
+    fn backup(path: &Path, stamp: &str) {
+        std::fs::write(format!("{}.bak.{}", path.display(), stamp), "").unwrap();
+    }
+
+    #[test]
+    fn test_backups() {
+        let path = mirrorlist("list");
+        for stamp in ["20260101-120000", "20261016-080500", "20261016-080500-1"] {
+            backup(&path, stamp);
+        }
+        // Not backups of this mirrorlist
+        backup(&path, "old");
+        std::fs::write(path.with_file_name("mirrorlist.pacnew"), "").unwrap();
+        std::fs::write(
+            path.with_file_name("chaotic-mirrorlist.bak.20261016-090000"),
+            "",
+        )
+        .unwrap();
+
+        let found = backups(&path);
+        let stamps: Vec<&str> = found.iter().map(|b| b.stamp.as_str()).collect();
+        assert_eq!(
+            stamps,
+            ["20261016-080500-1", "20261016-080500", "20260101-120000"]
+        );
+        assert_eq!(found[1].date(), "2026-10-16 08:05:00");
+        assert_eq!(
+            found[2].path,
+            path.with_file_name("mirrorlist.bak.20260101-120000")
+        );
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert!(backups(&path).is_empty());
+    }
+
+    #[test]
+    fn test_stale_backups() {
+        let path = mirrorlist("stale");
+        for day in 10..17 {
+            backup(&path, &format!("202610{}-120000", day));
+        }
+
+        let stale = stale_backups(&path, BACKUPS_KEPT);
+        assert_eq!(
+            stale,
+            [
+                path.with_file_name("mirrorlist.bak.20261011-120000"),
+                path.with_file_name("mirrorlist.bak.20261010-120000"),
+            ]
+        );
+        assert_eq!(stale_backups(&path, 7), Vec::<PathBuf>::new());
+        assert_eq!(stale_backups(&path, 0).len(), 7);
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_region_args() {
+        assert_eq!(region_args(None), "");
+        let args = region_args(Some("DE"));
+        assert!(args.starts_with(" --entry-country DE "));
+        assert!(args.contains("--country-neighbors-per-country"));
+    }
+}
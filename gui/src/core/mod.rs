@@ -1,20 +1,92 @@
 //! Core functionality and business logic.
 //!
 //! This module contains:
+//! - `actions`: Named maintenance actions shared by buttons and the CLI
+//! - `asus`: ASUS laptop profiles, GPU modes and charge limit
 //! - `aur`: AUR helper detection and management
+//! - `boot`: GRUB and systemd-boot settings
+//! - `crash`: Panic hook writing crash reports
 //! - `daemon`: Daemon management for xero-auth
+//! - `deploy`: Theme deployment with backup and restore
+//! - `distro`: Distribution and desktop session detection
+//! - `dkms`: DKMS module status and build checks
+//! - `dotfiles`: Per-user configuration file deployment
 //! - `download`: File download functionality
+//! - `elevation`: Fallbacks when pkexec cannot authorize in the terminal
+//! - `events`: Task, download and settings events to subscribe to
+//! - `exec`: Command output capture with timeouts and size caps
+//! - `firewall`: Firewall backend detection and port rules
+//! - `fonts`: Curated font packages and the fixed-width font setting
+//! - `fs`: Filesystem detection and btrfs usage parsing
+//! - `groups`: Group memberships for hardware and services
+//! - `hardware`: CPU and hardware capability detection
+//! - `health`: Health figures shown on the main page
+//! - `locale`: Locale generation and timezone settings
+//! - `logs`: Log file access and level filtering
+//! - `maintenance`: Scheduled maintenance through a systemd timer
+//! - `memory`: Swap detection and zram configuration
+//! - `mirrorlist`: Mirror region options and ranked mirrorlists
+//! - `nvidia`: NVIDIA driver health checks
 //! - `package`: Package and flatpak checking utilities
+//! - `pacman_conf`: Repository sections of the pacman configuration
+//! - `paths`: Host and app directories, inside a sandbox or not
+//! - `podman`: Rootless Podman readiness checks
+//! - `privileged_fs`: Edits of root-owned files with diff previews
+//! - `profile`: Setup profiles of actions to replay
+//! - `proton`: Proton-GE release lookup and installation helpers
+//! - `search`: Fuzzy search over pages and actions
+//! - `setup`: Quick setup choices of the welcome wizard
 //! - `system_check`: System dependency and distribution validation
+//! - `sysinfo`: System report of the system information page
+//! - `systemd`: Systemd unit listing and state queries
+//! - `tailscale`: Tailscale status parsing
+//! - `update_check`: Toolkit update detection
+//! - `wallpapers`: Wallpaper index, thumbnail cache and downloads
 
+pub mod actions;
+pub mod asus;
 pub mod aur;
 pub mod autostart;
+pub mod boot;
+pub mod crash;
 pub mod daemon;
+pub mod deploy;
+pub mod distro;
+pub mod dkms;
+pub mod dotfiles;
 pub mod download;
+pub mod elevation;
+pub mod events;
+pub mod exec;
+pub mod firewall;
+pub mod fonts;
+pub mod fs;
+pub mod groups;
+pub mod hardware;
+pub mod health;
+pub mod locale;
+pub mod logs;
+pub mod maintenance;
+pub mod memory;
+pub mod mirrorlist;
+pub mod nvidia;
 pub mod package;
+pub mod pacman_conf;
+pub mod paths;
+pub mod podman;
+pub mod privileged_fs;
+pub mod profile;
+pub mod proton;
+pub mod search;
+pub mod setup;
+pub mod sysinfo;
 pub mod system_check;
+pub mod systemd;
+pub mod tailscale;
+pub mod update_check;
+pub mod wallpapers;
 
 // Re-export commonly used items
 pub use aur::get as aur_helper;
 pub use package::{is_flatpak_installed, is_package_installed};
-pub use system_check::{check_dependencies, get_distribution_name, show_dependency_error_dialog};
+pub use system_check::{check_dependencies, show_dependency_error_dialog};
@@ -0,0 +1,227 @@
+//! NVIDIA driver health checks.
+//!
+//! Covers the usual causes of a black screen after an update: modules that
+//! did not load, a DKMS module not built for the running kernel, missing
+//! kernel modesetting and a loaded module older than the installed driver.
+
+use crate::core::dkms::{self, DkmsEntry};
+use anyhow::{bail, Context, Result};
+use log::warn;
+use std::path::Path;
+use std::process::Command;
+
+/// Modules the driver needs for a graphical session.
+pub const REQUIRED_MODULES: &[&str] = &["nvidia", "nvidia_modeset", "nvidia_drm"];
+
+/// Kernel parameter enabling modesetting, as set in the boot options.
+pub const MODESET_PARAM: &str = "nvidia-drm.modeset=1";
+
+const MODESET_FILE: &str = "/sys/module/nvidia_drm/parameters/modeset";
+const MODULE_VERSION_FILE: &str = "/sys/module/nvidia/version";
+
+/// Driver state of the running system.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Health {
+    /// Running kernel release
+    pub kernel: String,
+    /// Loaded modules of [`REQUIRED_MODULES`]
+    pub loaded: Vec<String>,
+    /// `None` if nvidia_drm is not loaded
+    pub modeset: Option<bool>,
+    /// NVIDIA entries of `dkms status`; empty for prebuilt drivers
+    pub dkms: Vec<DkmsEntry>,
+    /// Version of the loaded kernel module
+    pub module_version: Option<String>,
+    /// Version of the installed nvidia-utils package
+    pub driver_version: Option<String>,
+    /// Headers package of the running kernel, like `linux-zen-headers`
+    pub headers: Option<String>,
+}
+
+impl Health {
+    /// Required modules that are not loaded.
+    pub fn missing_modules(&self) -> Vec<&'static str> {
+        REQUIRED_MODULES
+            .iter()
+            .copied()
+            .filter(|module| !self.loaded.iter().any(|loaded| loaded == module))
+            .collect()
+    }
+
+    /// Whether the DKMS module is built for the running kernel; `None` if
+    /// the driver does not use DKMS.
+    pub fn dkms_built(&self) -> Option<bool> {
+        if self.dkms.is_empty() {
+            return None;
+        }
+        Some(dkms::built_for(&self.dkms, &self.kernel))
+    }
+
+    /// Whether the loaded module differs from the installed driver, which
+    /// a reboot after a driver update resolves.
+    pub fn version_mismatch(&self) -> bool {
+        matches!(
+            (&self.module_version, &self.driver_version),
+            (Some(module), Some(driver)) if module != driver
+        )
+    }
+}
+
+/// Module names from `lsmod` output.
+pub fn parse_lsmod(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .skip_while(|line| line.starts_with("Module"))
+        .filter_map(|line| line.split_whitespace().next())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Value of a boolean module parameter, `Y` or `N`.
+pub fn parse_bool_param(value: &str) -> Option<bool> {
+    match value.trim() {
+        "Y" | "y" | "1" => Some(true),
+        "N" | "n" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Upstream version from `pacman -Q` output, like `560.35.03` from
+/// `nvidia-utils 1:560.35.03-1`.
+pub fn parse_package_version(output: &str) -> Option<String> {
+    let version = output.split_whitespace().nth(1)?;
+    let version = version.split_once(':').map_or(version, |(_, v)| v);
+    let version = version.rsplit_once('-').map_or(version, |(v, _)| v);
+    Some(version.to_string())
+}
+
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        bail!("{} exited with {}", program, output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Log why a value is missing and drop the error.
+fn tolerate<T>(what: &str, result: Result<T>) -> Option<T> {
+    result
+        .map_err(|e| warn!("Failed to check {}: {:#}", what, e))
+        .ok()
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|content| content.trim().to_string())
+        .filter(|content| !content.is_empty())
+}
+
+/// Check the driver. Blocks on external commands, so run it off the main
+/// thread.
+pub fn check() -> Health {
+    let kernel = dkms::running_kernel();
+    let loaded = tolerate("loaded modules", run("lsmod", &[]))
+        .map(|output| parse_lsmod(&output))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|module| REQUIRED_MODULES.contains(&module.as_str()))
+        .collect();
+    // dkms is not installed with prebuilt drivers
+    let dkms = dkms::status()
+        .into_iter()
+        .filter(|entry| entry.module.starts_with("nvidia"))
+        .collect();
+    let headers = dkms::headers_package(&kernel);
+
+    Health {
+        loaded,
+        modeset: read_trimmed(Path::new(MODESET_FILE)).and_then(|v| parse_bool_param(&v)),
+        dkms,
+        module_version: read_trimmed(Path::new(MODULE_VERSION_FILE)),
+        driver_version: tolerate("driver version", run("pacman", &["-Q", "nvidia-utils"]))
+            .and_then(|output| parse_package_version(&output)),
+        headers,
+        kernel,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KERNEL: &str = "6.10.10-arch1-1";
+
+    #[test]
+    fn test_parse_lsmod() {
+        let modules = parse_lsmod(include_str!("../../tests/fixtures/nvidia/lsmod.txt"));
+        assert_eq!(modules.len(), 7);
+        assert!(modules.iter().any(|m| m == "nvidia_drm"));
+        assert!(!modules.iter().any(|m| m == "Module"));
+
+        let health = Health {
+            loaded: modules,
+            ..Health::default()
+        };
+        assert!(health.missing_modules().is_empty());
+        let health = Health {
+            loaded: vec!["nvidia".to_string()],
+            ..Health::default()
+        };
+        assert_eq!(health.missing_modules(), ["nvidia_modeset", "nvidia_drm"]);
+    }
+
+    #[test]
+    fn test_parse_bool_param() {
+        assert_eq!(parse_bool_param("Y\n"), Some(true));
+        assert_eq!(parse_bool_param("N\n"), Some(false));
+        assert_eq!(parse_bool_param(""), None);
+    }
+
+    #[test]
+    fn test_dkms_built() {
+        let dkms: Vec<DkmsEntry> =
+            dkms::parse_status(include_str!("../../tests/fixtures/nvidia/dkms-status.txt"))
+                .into_iter()
+                .filter(|entry| entry.module == "nvidia")
+                .collect();
+        let health = |kernel: &str, dkms: Vec<DkmsEntry>| Health {
+            kernel: kernel.to_string(),
+            dkms,
+            ..Health::default()
+        };
+        assert_eq!(health(KERNEL, dkms.clone()).dkms_built(), Some(true));
+        assert_eq!(
+            health("6.6.52-1-lts", dkms.clone()).dkms_built(),
+            Some(true)
+        );
+        // Kernel updated, module not rebuilt
+        assert_eq!(health("6.11.1-arch1-1", dkms).dkms_built(), Some(false));
+        assert_eq!(health(KERNEL, Vec::new()).dkms_built(), None);
+    }
+
+    #[test]
+    fn test_version_mismatch() {
+        assert_eq!(
+            parse_package_version("nvidia-utils 560.35.03-2\n").as_deref(),
+            Some("560.35.03")
+        );
+        assert_eq!(
+            parse_package_version("nvidia-utils 1:560.35.03-1").as_deref(),
+            Some("560.35.03")
+        );
+        assert_eq!(parse_package_version("error"), None);
+
+        let health = |module: Option<&str>, driver: Option<&str>| Health {
+            module_version: module.map(str::to_string),
+            driver_version: driver.map(str::to_string),
+            ..Health::default()
+        };
+        assert!(health(Some("555.58.02"), Some("560.35.03")).version_mismatch());
+        assert!(!health(Some("560.35.03"), Some("560.35.03")).version_mismatch());
+        assert!(!health(None, Some("560.35.03")).version_mismatch());
+    }
+}
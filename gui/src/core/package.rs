@@ -2,15 +2,80 @@
 //!
 //! This module provides utilities for checking installed packages,
 //! flatpaks, and system operations.
+//!
+//! Installed packages are read from pacman's local database directly, one
+//! directory per package holding a `desc` file, which is much faster than
+//! spawning pacman and works while it holds its lock. When the database
+//! does not look as expected, pacman is asked instead.
 
 use super::aur;
-use anyhow::Result;
+use crate::i18n::gettext;
+use crate::ui::task_runner::{Command, CommandSequence};
+use anyhow::{Context, Result};
 use log::debug;
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Repository file of the Flathub remote.
+pub const FLATHUB_REPO: &str = "https://dl.flathub.org/repo/flathub.flatpakrepo";
+
+/// pacman's database of installed packages.
+const LOCAL_DB: &str = "/var/lib/pacman/local";
+
+/// Whether the Flathub remote is configured, once checked this session.
+static FLATHUB_ENABLED: Mutex<Option<bool>> = Mutex::new(None);
+
+/// Repository details of packages, once asked this session.
+static DETAILS: Mutex<BTreeMap<String, PkgDetails>> = Mutex::new(BTreeMap::new());
+
+/// An installed package, as recorded in the local database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PkgInfo {
+    pub name: String,
+    /// Full version, like `1:1.2.3-1`
+    pub version: String,
+    /// Unix time of the install, `None` when pacman was asked instead
+    pub install_date: Option<u64>,
+    /// Installed size in bytes, `None` when pacman was asked instead
+    pub size: Option<u64>,
+    /// Names the package also satisfies, without their versions
+    pub provides: Vec<String>,
+}
+
+/// A package in the sync repositories, as shown by `pacman -Si`.
+///
+/// Dates and sizes are kept as pacman formats them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PkgDetails {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    /// Upstream website, `None` when pacman shows none
+    pub url: Option<String>,
+    pub packager: String,
+    pub build_date: String,
+    pub download_size: String,
+    pub installed_size: String,
+}
 
 /// Check if a package is installed using AUR helper or pacman.
+///
+/// Like `pacman -Q`, a package providing `package` counts as well.
 pub fn is_package_installed(package: &str) -> bool {
     debug!("Checking if package '{}' is installed", package);
 
+    if let Some(installed) = installed_in_db(Path::new(LOCAL_DB), package) {
+        debug!(
+            "Package '{}' {} in the local database",
+            package,
+            if installed { "found" } else { "not found" }
+        );
+        return installed;
+    }
+
     // Try AUR helper first (prefer initialized, fall back to detection)
     if let Some(helper) = aur::get().or_else(aur::detect) {
         if check_with_helper(helper, package) {
@@ -63,6 +128,225 @@ fn check_with_pacman(package: &str) -> bool {
     installed
 }
 
+/// All installed packages.
+pub fn local_packages() -> Vec<PkgInfo> {
+    read_local_db(Path::new(LOCAL_DB)).unwrap_or_else(|| {
+        debug!("Unexpected local database layout, asking pacman");
+        query_pacman()
+    })
+}
+
+/// Names of all installed packages.
+pub fn installed_set() -> HashSet<String> {
+    local_packages()
+        .into_iter()
+        .map(|package| package.name)
+        .collect()
+}
+
+/// Packages in the local database at `db`, `None` if it is not laid out
+/// like one.
+fn read_local_db(db: &Path) -> Option<Vec<PkgInfo>> {
+    local_db_entries(db)?
+        .iter()
+        .map(|entry| parse_desc(&std::fs::read_to_string(entry.join("desc")).ok()?))
+        .collect()
+}
+
+/// Whether `package` is installed according to the database at `db`,
+/// `None` if it is not laid out like one.
+fn installed_in_db(db: &Path, package: &str) -> Option<bool> {
+    let entries = local_db_entries(db)?;
+    // Entries are named `name-pkgver-pkgrel`, so names need no desc reads
+    let by_name = entries
+        .iter()
+        .any(|entry| entry_name(entry) == Some(package) && entry.join("desc").is_file());
+    if by_name {
+        return Some(true);
+    }
+    let packages = read_local_db(db)?;
+    Some(
+        packages
+            .iter()
+            .any(|info| info.provides.iter().any(|provided| provided == package)),
+    )
+}
+
+/// Package directories of the database at `db`, `None` if it has no
+/// version file or an entry is not named like a package.
+fn local_db_entries(db: &Path) -> Option<Vec<PathBuf>> {
+    if !db.join("ALPM_DB_VERSION").is_file() {
+        return None;
+    }
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(db).ok()? {
+        let entry = entry.ok()?;
+        if !entry.file_type().ok()?.is_dir() {
+            continue;
+        }
+        let path = entry.path();
+        entry_name(&path)?;
+        entries.push(path);
+    }
+    Some(entries)
+}
+
+/// Package name of a database entry, its directory name without the
+/// version and release.
+fn entry_name(entry: &Path) -> Option<&str> {
+    let dir = entry.file_name()?.to_str()?;
+    let (rest, _pkgrel) = dir.rsplit_once('-')?;
+    let (name, _pkgver) = rest.rsplit_once('-')?;
+    Some(name).filter(|name| !name.is_empty())
+}
+
+/// A package `desc` file: `%FIELD%` headers, each followed by its values
+/// up to the next blank line.
+fn parse_desc(desc: &str) -> Option<PkgInfo> {
+    let mut name = None;
+    let mut version = None;
+    let mut install_date = None;
+    let mut size = None;
+    let mut provides = Vec::new();
+
+    let mut field = "";
+    for line in desc.lines() {
+        if line.is_empty() {
+            field = "";
+        } else if line.starts_with('%') && line.ends_with('%') && field.is_empty() {
+            field = line;
+        } else {
+            match field {
+                "%NAME%" => name = Some(line.to_string()),
+                "%VERSION%" => version = Some(line.to_string()),
+                "%INSTALLDATE%" => install_date = line.parse().ok(),
+                "%SIZE%" => size = line.parse().ok(),
+                "%PROVIDES%" => {
+                    let provided = line.split(['=', '<', '>']).next().unwrap_or(line);
+                    provides.push(provided.to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Some(PkgInfo {
+        name: name?,
+        version: version?,
+        install_date,
+        size,
+        provides,
+    })
+}
+
+/// Installed packages from `pacman -Q`, which has no dates or sizes.
+fn query_pacman() -> Vec<PkgInfo> {
+    match std::process::Command::new("pacman").arg("-Q").output() {
+        Ok(output) if output.status.success() => {
+            parse_pacman_query(&String::from_utf8_lossy(&output.stdout))
+        }
+        Ok(output) => {
+            debug!("pacman -Q failed with {:?}", output.status.code());
+            Vec::new()
+        }
+        Err(e) => {
+            debug!("Failed to execute pacman: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// `name version` lines of `pacman -Q`.
+fn parse_pacman_query(output: &str) -> Vec<PkgInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, version) = line.trim().split_once(' ')?;
+            Some(PkgInfo {
+                name: name.to_string(),
+                version: version.trim().to_string(),
+                install_date: None,
+                size: None,
+                provides: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+/// Details of `package` in the sync repositories, `None` if it is in none.
+///
+/// Runs `pacman -Si` the first time a package is asked for this session.
+pub fn package_details(package: &str) -> Option<PkgDetails> {
+    if let Some(details) = cached_package_details(package) {
+        return Some(details);
+    }
+
+    let output = std::process::Command::new("pacman")
+        .args(["-Si", package])
+        .env("LC_ALL", "C")
+        .output();
+    let details = match output {
+        Ok(output) if output.status.success() => {
+            parse_pacman_info(&String::from_utf8_lossy(&output.stdout))
+        }
+        Ok(output) => {
+            debug!(
+                "pacman -Si {} failed with {:?}",
+                package,
+                output.status.code()
+            );
+            None
+        }
+        Err(e) => {
+            debug!("Failed to execute pacman: {}", e);
+            None
+        }
+    }?;
+
+    if let Ok(mut cache) = DETAILS.lock() {
+        cache.insert(package.to_string(), details.clone());
+    }
+    Some(details)
+}
+
+/// Details of `package` if they were already asked this session.
+pub fn cached_package_details(package: &str) -> Option<PkgDetails> {
+    DETAILS.lock().ok()?.get(package).cloned()
+}
+
+/// The first package of `pacman -Si` output, which lists one per
+/// repository holding it, separated by blank lines.
+fn parse_pacman_info(info: &str) -> Option<PkgDetails> {
+    let mut fields = BTreeMap::new();
+    for line in info.lines() {
+        if line.trim().is_empty() {
+            if fields.is_empty() {
+                continue;
+            }
+            break;
+        }
+        // Lists like dependencies go on over indented lines
+        if line.starts_with(char::is_whitespace) {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            fields.insert(key.trim(), value.trim());
+        }
+    }
+
+    let field = |key: &str| fields.get(key).map(|value| value.to_string());
+    Some(PkgDetails {
+        name: field("Name")?,
+        version: field("Version")?,
+        description: field("Description").unwrap_or_default(),
+        url: field("URL").filter(|url| !url.is_empty() && url != "None"),
+        packager: field("Packager").unwrap_or_default(),
+        build_date: field("Build Date").unwrap_or_default(),
+        download_size: field("Download Size").unwrap_or_default(),
+        installed_size: field("Installed Size").unwrap_or_default(),
+    })
+}
+
 /// Check if a flatpak package is installed.
 pub fn is_flatpak_installed(package: &str) -> bool {
     debug!("Checking if Flatpak '{}' is installed", package);
@@ -86,6 +370,240 @@ pub fn is_flatpak_installed(package: &str) -> bool {
     installed
 }
 
+/// Check if the Flathub remote is configured.
+///
+/// The answer is cached for the session until [`ensure_flathub_sequence`]
+/// adds the remote.
+pub fn is_flathub_enabled() -> bool {
+    let mut cached = FLATHUB_ENABLED.lock().unwrap_or_else(|e| e.into_inner());
+    *cached.get_or_insert_with(|| {
+        let enabled = check_flathub();
+        debug!(
+            "Flathub remote {}",
+            if enabled { "found" } else { "missing" }
+        );
+        enabled
+    })
+}
+
+fn check_flathub() -> bool {
+    std::process::Command::new("flatpak")
+        .args(["remotes", "--columns=name"])
+        .output()
+        .is_ok_and(|output| {
+            output.status.success()
+                && String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .any(|line| line.trim() == "flathub")
+        })
+}
+
+/// A sequence adding the Flathub remote system-wide unless it exists.
+///
+/// Sequences installing flatpaks get it prepended automatically when the
+/// remote is missing.
+pub fn ensure_flathub_sequence() -> CommandSequence {
+    // Checked again once the step ran
+    *FLATHUB_ENABLED.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    CommandSequence::new().then(
+        Command::builder()
+            .privileged()
+            .program("flatpak")
+            .args(&["remote-add", "--if-not-exists", "flathub", FLATHUB_REPO])
+            .description(&gettext("Enabling Flathub repository..."))
+            .build(),
+    )
+}
+
+/// Check if fwupd is available for firmware updates.
+pub fn is_fwupd_installed() -> bool {
+    is_package_installed("fwupd")
+}
+
+/// Parts of the system that can be updated independently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateComponent {
+    Repo,
+    Aur,
+    Flatpak,
+    Firmware,
+}
+
+impl UpdateComponent {
+    pub const ALL: [Self; 4] = [Self::Repo, Self::Aur, Self::Flatpak, Self::Firmware];
+
+    /// Stable identifier used in selection dialogs.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Repo => "repo",
+            Self::Aur => "aur",
+            Self::Flatpak => "flatpak",
+            Self::Firmware => "firmware",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Repo => "Repo packages (pacman -Syu)",
+            Self::Aur => "AUR packages",
+            Self::Flatpak => "Flatpaks (flatpak update -y)",
+            Self::Firmware => "Firmware (fwupdmgr refresh && update)",
+        }
+    }
+
+    /// Whether the tooling for this component is installed.
+    pub fn is_available(&self) -> bool {
+        match self {
+            Self::Repo => true,
+            Self::Aur => aur::get().or_else(aur::detect).is_some(),
+            Self::Flatpak => is_package_installed("flatpak"),
+            Self::Firmware => is_fwupd_installed(),
+        }
+    }
+
+    /// Number of pending updates, or `None` if the check failed.
+    ///
+    /// Queries the network, so call this off the main thread.
+    pub fn pending_updates(&self) -> Option<usize> {
+        match self {
+            // checkupdates exits 2 when there is nothing to update
+            Self::Repo => run_count("checkupdates", &[], count_lines),
+            Self::Aur => run_count(aur::get().or_else(aur::detect)?, &["-Qua"], count_lines),
+            Self::Flatpak => run_count(
+                "flatpak",
+                &["remote-ls", "--updates", "--columns=application"],
+                count_lines,
+            ),
+            // fwupdmgr exits 2 when no updates are available
+            Self::Firmware => run_count("fwupdmgr", &["get-updates", "--json"], |output| {
+                parse_fwupd_updates(output).unwrap_or(0)
+            }),
+        }
+    }
+}
+
+/// Number of pending repo and AUR updates, failing only if no check worked.
+///
+/// Queries the network, so call this off the main thread.
+pub fn pending_update_count() -> Result<usize> {
+    let mut total = 0;
+    let mut checked = false;
+    for component in [UpdateComponent::Repo, UpdateComponent::Aur] {
+        if !component.is_available() {
+            continue;
+        }
+        if let Some(count) = component.pending_updates() {
+            total += count;
+            checked = true;
+        }
+    }
+    if !checked {
+        anyhow::bail!("No update check succeeded");
+    }
+    Ok(total)
+}
+
+/// Packages installed as dependencies that nothing requires anymore.
+pub fn orphans() -> Result<Vec<String>> {
+    let output = std::process::Command::new("pacman")
+        .arg("-Qtdq")
+        .output()
+        .context("Failed to execute pacman")?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match output.status.code() {
+        Some(0) => Ok(stdout.split_whitespace().map(str::to_string).collect()),
+        // Exits 1 without output when there are none
+        Some(1) if stdout.trim().is_empty() => Ok(Vec::new()),
+        code => anyhow::bail!("pacman -Qtdq failed with {:?}", code),
+    }
+}
+
+/// Run a query and count its results; exit code 2 means nothing pending.
+fn run_count(program: &str, args: &[&str], count: fn(&str) -> usize) -> Option<usize> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+
+    match output.status.code() {
+        Some(0) => Some(count(&String::from_utf8_lossy(&output.stdout))),
+        Some(2) => Some(0),
+        code => {
+            debug!("'{}' update check failed with {:?}", program, code);
+            None
+        }
+    }
+}
+
+/// Count non-empty output lines.
+fn count_lines(output: &str) -> usize {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .count()
+}
+
+/// `fwupdmgr get-updates --json` output.
+#[derive(Deserialize)]
+struct FwupdUpdates {
+    #[serde(default, rename = "Devices")]
+    devices: Vec<IgnoredAny>,
+}
+
+/// Count devices with firmware updates.
+fn parse_fwupd_updates(json: &str) -> Option<usize> {
+    serde_json::from_str::<FwupdUpdates>(json)
+        .ok()
+        .map(|updates| updates.devices.len())
+}
+
+/// Where pacman leaves `.pacnew` files next to changed configuration.
+pub const PACNEW_ROOT: &str = "/etc";
+
+/// Kernel module directories, one per installed kernel release.
+const MODULES_DIR: &str = "/usr/lib/modules";
+
+/// `.pacnew` files below `dir`, skipping directories that cannot be read.
+///
+/// Symlinks are not followed, so links out of `dir` are not searched.
+pub fn find_pacnew_files(dir: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let path = entry.path();
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "pacnew") {
+                found.push(path);
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+/// Whether the modules of the running kernel are gone, as happens when an
+/// update replaced it; loading modules fails until the next reboot.
+pub fn running_kernel_removed() -> Result<bool> {
+    let release = std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .context("Failed to read the running kernel release")?;
+    Ok(!kernel_modules_present(
+        Path::new(MODULES_DIR),
+        release.trim(),
+    ))
+}
+
+fn kernel_modules_present(modules_dir: &Path, release: &str) -> bool {
+    !release.is_empty() && modules_dir.join(release).is_dir()
+}
+
 /// Open a URL in the default browser.
 pub fn open_url(url: &str) -> Result<()> {
     debug!("Opening URL: {}", url);
@@ -104,4 +622,193 @@ mod tests {
             "this-package-definitely-does-not-exist-12345"
         ));
     }
+
+    const FIXTURE_DB: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/pacman/local");
+
+    #[test]
+    fn test_read_local_db() {
+        let mut packages = read_local_db(Path::new(FIXTURE_DB)).unwrap();
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        let names: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "linux",
+                "linux-headers",
+                "pipewire-jack",
+                "tailscale",
+                "xero-toolkit-git"
+            ]
+        );
+        assert_eq!(
+            packages[2],
+            PkgInfo {
+                name: "pipewire-jack".to_string(),
+                version: "1:1.2.3-1".to_string(),
+                install_date: Some(1725000000),
+                size: Some(402432),
+                provides: vec![
+                    "jack".to_string(),
+                    "libjack.so".to_string(),
+                    "libjackserver.so".to_string()
+                ],
+            }
+        );
+        assert_eq!(packages[0].size, Some(142635008));
+    }
+
+    #[test]
+    fn test_installed_in_db() {
+        let db = Path::new(FIXTURE_DB);
+        assert_eq!(installed_in_db(db, "tailscale"), Some(true));
+        assert_eq!(installed_in_db(db, "xero-toolkit-git"), Some(true));
+        // Found through what other packages provide
+        assert_eq!(installed_in_db(db, "xero-toolkit"), Some(true));
+        assert_eq!(installed_in_db(db, "jack"), Some(true));
+        // A prefix of an installed name is not installed
+        assert_eq!(
+            installed_in_db(db, "linux-headers-6.10.10.arch1"),
+            Some(false)
+        );
+        assert_eq!(installed_in_db(db, "linux-zen"), Some(false));
+    }
+
+    #[test]
+    fn test_unexpected_db_layout() {
+        // No version file
+        let dir = test_dir("db-version");
+        assert_eq!(read_local_db(&dir), None);
+        assert_eq!(installed_in_db(&dir, "linux"), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // An entry not named like a package
+        let dir = test_dir("db-entry");
+        std::fs::write(dir.join("ALPM_DB_VERSION"), "9\n").unwrap();
+        std::fs::create_dir(dir.join("linux")).unwrap();
+        assert_eq!(read_local_db(&dir), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // A package without its name
+        let dir = test_dir("db-desc");
+        std::fs::write(dir.join("ALPM_DB_VERSION"), "9\n").unwrap();
+        std::fs::create_dir(dir.join("linux-6.10-1")).unwrap();
+        std::fs::write(dir.join("linux-6.10-1/desc"), "%VERSION%\n6.10-1\n\n").unwrap();
+        assert_eq!(read_local_db(&dir), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_entry_name() {
+        assert_eq!(
+            entry_name(Path::new("/db/linux-6.10.10.arch1-1")),
+            Some("linux")
+        );
+        assert_eq!(
+            entry_name(Path::new("/db/xero-toolkit-git-r312.4f2c1ab-1")),
+            Some("xero-toolkit-git")
+        );
+        assert_eq!(entry_name(Path::new("/db/linux")), None);
+        assert_eq!(entry_name(Path::new("/db/-1-1")), None);
+    }
+
+    #[test]
+    fn test_parse_pacman_query() {
+        let packages = parse_pacman_query("linux 6.10.10.arch1-1\ntailscale 1.74.1-1\n\n");
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[1].name, "tailscale");
+        assert_eq!(packages[1].version, "1.74.1-1");
+        assert_eq!(packages[1].size, None);
+    }
+
+    #[test]
+    fn test_parse_pacman_info() {
+        let info = std::fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/pacman/si-linux-cachyos.txt"
+        ))
+        .unwrap();
+        let details = parse_pacman_info(&info).unwrap();
+        assert_eq!(
+            details,
+            PkgDetails {
+                name: "linux-cachyos".to_string(),
+                version: "6.11.3-2".to_string(),
+                description: "Linux BORE + LTO + AutoFDO + Propeller Cachyos by CachyOS with other patches and improvements - Kernel".to_string(),
+                url: Some("https://github.com/CachyOS/linux-cachyos".to_string()),
+                packager: "CachyOS <admin@cachyos.org>".to_string(),
+                build_date: "Sat Oct 12 14:02:51 2024".to_string(),
+                download_size: "140.31 MiB".to_string(),
+                installed_size: "153.96 MiB".to_string(),
+            }
+        );
+
+        // Only the first repository's package, and no placeholder URL
+        let info = "Repository : core\nName : linux\nVersion : 6.11.3.arch1-1\nURL : None\n\n\
+                    Repository : testing\nName : linux\nVersion : 6.12.0.arch1-1\n";
+        let details = parse_pacman_info(info).unwrap();
+        assert_eq!(details.version, "6.11.3.arch1-1");
+        assert_eq!(details.url, None);
+        assert_eq!(details.packager, "");
+
+        assert_eq!(
+            parse_pacman_info("error: package 'linux-foo' was not found\n"),
+            None
+        );
+        assert_eq!(parse_pacman_info(""), None);
+    }
+
+    #[test]
+    fn test_count_lines() {
+        assert_eq!(count_lines("linux 6.1-1 -> 6.2-1\nmesa 1-1 -> 2-1\n\n"), 2);
+        assert_eq!(count_lines(""), 0);
+    }
+
+    #[test]
+    fn test_parse_fwupd_updates() {
+        let json = r#"{"Devices":[{"Name":"System Firmware","Releases":[{"Version":"1.2"}]},{"Name":"SSD","Releases":[]}]}"#;
+        assert_eq!(parse_fwupd_updates(json), Some(2));
+        assert_eq!(parse_fwupd_updates(r#"{"Devices":[]}"#), Some(0));
+        assert_eq!(parse_fwupd_updates("No updates available"), None);
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "xero-toolkit-package-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_find_pacnew_files() {
+        let dir = test_dir("pacnew");
+        std::fs::create_dir_all(dir.join("pacman.d")).unwrap();
+        std::fs::write(dir.join("pacman.conf"), "").unwrap();
+        std::fs::write(dir.join("pacman.conf.pacnew"), "").unwrap();
+        std::fs::write(dir.join("pacman.d/mirrorlist.pacnew"), "").unwrap();
+        std::fs::write(dir.join("pacman.d/mirrorlist.pacsave"), "").unwrap();
+
+        assert_eq!(
+            find_pacnew_files(&dir),
+            vec![
+                dir.join("pacman.conf.pacnew"),
+                dir.join("pacman.d/mirrorlist.pacnew")
+            ]
+        );
+        assert!(find_pacnew_files(&dir.join("missing")).is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_kernel_modules_present() {
+        let dir = test_dir("modules");
+        std::fs::create_dir_all(dir.join("6.9.1-arch1-1")).unwrap();
+        assert!(kernel_modules_present(&dir, "6.9.1-arch1-1"));
+        assert!(!kernel_modules_present(&dir, "6.8.9-arch1-1"));
+        assert!(!kernel_modules_present(&dir, ""));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
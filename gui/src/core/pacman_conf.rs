@@ -0,0 +1,198 @@
+//! Repository sections and options of /etc/pacman.conf.
+//!
+//! Arch ships optional repositories like multilib as commented-out
+//! sections. Enabling one uncomments its header and the directives below
+//! it, keeping the rest of the file as it is.
+
+use crate::core::privileged_fs::{self, FileEdit};
+use anyhow::{Context, Result};
+
+/// Path of the pacman configuration.
+pub const PACMAN_CONF: &str = "/etc/pacman.conf";
+
+/// Repository with the 32-bit libraries Steam and Wine need.
+pub const MULTILIB: &str = "multilib";
+
+/// Mirror list used for repositories added without a commented section.
+const DEFAULT_INCLUDE: &str = "Include = /etc/pacman.d/mirrorlist";
+
+/// Matches the `ParallelDownloads` directive, commented out or not.
+const PARALLEL_DOWNLOADS_PATTERN: &str = r"(?m)^#?\s*ParallelDownloads\s*=.*$";
+
+/// Most parallel downloads offered.
+pub const MAX_PARALLEL_DOWNLOADS: u32 = 20;
+
+/// Whether `content` has an active `[repo]` section.
+pub fn repo_enabled(content: &str, repo: &str) -> bool {
+    let header = format!("[{}]", repo);
+    content.lines().any(|line| line.trim() == header)
+}
+
+/// `content` with the `[repo]` section enabled.
+///
+/// Uncomments a commented-out section with its directives, or appends a
+/// new section using the default mirror list when there is none.
+pub fn enable_repo(content: &str, repo: &str) -> String {
+    if repo_enabled(content, repo) {
+        return content.to_string();
+    }
+
+    let header = format!("[{}]", repo);
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let commented = lines
+        .iter()
+        .position(|line| line.trim().trim_start_matches('#').trim() == header);
+
+    match commented {
+        Some(start) => {
+            lines[start] = header;
+            // Directives of the section, up to a blank line or the next one
+            for line in lines.iter_mut().skip(start + 1) {
+                let directive = line.trim().trim_start_matches('#').trim();
+                if directive.is_empty() || directive.starts_with('[') || !directive.contains('=') {
+                    break;
+                }
+                *line = directive.to_string();
+            }
+        }
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(header);
+            lines.push(DEFAULT_INCLUDE.to_string());
+        }
+    }
+
+    let mut new = lines.join("\n");
+    new.push('\n');
+    new
+}
+
+/// Whether the `[repo]` section is enabled on this system.
+pub fn is_repo_enabled(repo: &str) -> Result<bool> {
+    let content = std::fs::read_to_string(PACMAN_CONF)
+        .with_context(|| format!("Failed to read {}", PACMAN_CONF))?;
+    Ok(repo_enabled(&content, repo))
+}
+
+/// Edit enabling the `[repo]` section, leaving an empty configuration
+/// alone rather than filling it with just that section.
+pub fn enable_repo_edit(repo: &str) -> FileEdit {
+    let repo = repo.to_string();
+    privileged_fs::transform(PACMAN_CONF, move |current| {
+        if current.trim().is_empty() {
+            return current.to_string();
+        }
+        enable_repo(current, &repo)
+    })
+}
+
+/// Value of the active `ParallelDownloads` directive in `content`.
+pub fn parallel_downloads(content: &str) -> Option<u32> {
+    content.lines().find_map(|line| {
+        let (key, value) = line.trim().split_once('=')?;
+        if key.trim() != "ParallelDownloads" {
+            return None;
+        }
+        value.trim().parse().ok()
+    })
+}
+
+/// How many packages pacman downloads at once on this system, `None` when
+/// the directive is not set.
+pub fn read_parallel_downloads() -> Result<Option<u32>> {
+    let content = std::fs::read_to_string(PACMAN_CONF)
+        .with_context(|| format!("Failed to read {}", PACMAN_CONF))?;
+    Ok(parallel_downloads(&content))
+}
+
+/// Edit setting `ParallelDownloads` to `count`, uncommenting the directive
+/// if needed.
+pub fn parallel_downloads_edit(count: u32) -> Result<FileEdit> {
+    privileged_fs::replace_regex(
+        PACMAN_CONF,
+        PARALLEL_DOWNLOADS_PATTERN,
+        &format!("ParallelDownloads = {}", count),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STOCK: &str = "\
+[options]
+Architecture = auto
+
+[core]
+Include = /etc/pacman.d/mirrorlist
+
+#[multilib-testing]
+#Include = /etc/pacman.d/mirrorlist
+
+#[multilib]
+#Include = /etc/pacman.d/mirrorlist
+
+# An example of a custom package repository.
+#[custom]
+#SigLevel = Optional TrustAll
+#Server = file:///home/custompkgs
+";
+
+    #[test]
+    fn test_enable_commented_repo() {
+        assert!(!repo_enabled(STOCK, MULTILIB));
+
+        let enabled = enable_repo(STOCK, MULTILIB);
+        assert!(repo_enabled(&enabled, MULTILIB));
+        assert!(enabled.contains("\n[multilib]\nInclude = /etc/pacman.d/mirrorlist\n\n"));
+        // Neighbouring sections stay commented
+        assert!(enabled.contains("#[multilib-testing]\n#Include"));
+        assert!(enabled.contains("#[custom]\n#SigLevel"));
+
+        // Already enabled
+        assert_eq!(enable_repo(&enabled, MULTILIB), enabled);
+    }
+
+    #[test]
+    fn test_enable_repo_stops_at_comments() {
+        let enabled = enable_repo(STOCK, "custom");
+        assert!(enabled.ends_with(
+            "[custom]\nSigLevel = Optional TrustAll\nServer = file:///home/custompkgs\n"
+        ));
+        assert!(enabled.contains("# An example of a custom package repository."));
+    }
+
+    #[test]
+    fn test_enable_missing_repo() {
+        let enabled = enable_repo("[options]\nColor\n", MULTILIB);
+        assert_eq!(
+            enabled,
+            "[options]\nColor\n\n[multilib]\nInclude = /etc/pacman.d/mirrorlist\n"
+        );
+    }
+
+    #[test]
+    fn test_enable_repo_edit_keeps_empty_file() {
+        let edit = enable_repo_edit(MULTILIB);
+        assert_eq!(edit.apply(Some("")), None);
+        assert!(edit.apply(Some(STOCK)).is_some());
+    }
+
+    #[test]
+    fn test_parallel_downloads() {
+        assert_eq!(parallel_downloads(STOCK), None);
+        assert_eq!(parallel_downloads("#ParallelDownloads = 5\n"), None);
+        assert_eq!(
+            parallel_downloads("[options]\nParallelDownloads = 5\n"),
+            Some(5)
+        );
+
+        let edit = parallel_downloads_edit(10).unwrap();
+        let conf = "[options]\n#ParallelDownloads = 5\nColor\n";
+        let new = edit.apply(Some(conf)).unwrap();
+        assert_eq!(parallel_downloads(&new), Some(10));
+        assert_eq!(edit.apply(Some(&new)), None);
+    }
+}
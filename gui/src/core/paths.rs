@@ -0,0 +1,388 @@
+//! Host and app directories, whether or not the app is sandboxed.
+//!
+//! Under Flatpak, `XDG_CONFIG_HOME` and friends point into the app's
+//! `~/.var/app` directory and `/tmp` is private to the sandbox; firejail
+//! can give the app a private `/tmp` as well. Files meant for the desktop,
+//! like autostart entries and dotfiles, go to the host directories, and
+//! files handed to privileged commands go to the runtime directory, which
+//! the host sees at the same path.
+//!
+//! Downloads default to the folder named in xdg-user-dirs' `user-dirs.dirs`,
+//! which the desktop may have localized, like `~/Téléchargements`.
+
+use std::ffi::OsString;
+use std::fs::{self, DirBuilder, Permissions};
+use std::io;
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Name of the app's own config, cache and runtime directories.
+const APP_DIR: &str = "xero-toolkit";
+
+/// Keyfile Flatpak mounts at the root of every sandbox.
+const FLATPAK_INFO: &str = ".flatpak-info";
+
+/// File of xdg-user-dirs naming the special folders, in the config dir.
+const USER_DIRS: &str = "user-dirs.dirs";
+
+static PATHS: OnceLock<Paths> = OnceLock::new();
+
+/// Sandbox the app runs in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sandbox {
+    None,
+    /// Flatpak, with the app id from its info file
+    Flatpak {
+        app_id: String,
+    },
+    /// firejail, which may give the app a private `/tmp`
+    Firejail,
+}
+
+/// Directories the app reads and writes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paths {
+    pub sandbox: Sandbox,
+    /// The user's home on the host
+    pub home: PathBuf,
+    /// Host `~/.config`, where desktop settings like autostart entries live
+    pub host_config: PathBuf,
+    /// Host `~/.local/share`
+    pub host_data: PathBuf,
+    /// The user's download folder
+    pub downloads: PathBuf,
+    /// The app's own config directory
+    pub config: PathBuf,
+    /// The app's own cache directory, holding the log and downloads
+    pub cache: PathBuf,
+    /// The app's private runtime directory, also seen by the host
+    pub runtime: PathBuf,
+}
+
+impl Paths {
+    /// Resolve the directories, looking for sandbox markers under `root`
+    /// and reading environment variables with `var`.
+    pub fn resolve(root: &Path, var: impl Fn(&str) -> Option<OsString>) -> Self {
+        let path_var = |name: &str| {
+            var(name)
+                .map(PathBuf::from)
+                .filter(|path| path.is_absolute())
+        };
+
+        let sandbox = detect_sandbox(root, &var);
+        let home = path_var("HOME").or_else(dirs::home_dir).unwrap_or_default();
+
+        // Flatpak passes the host's own values on under `HOST_` names
+        let host_dir = |name: &str, default: &str| {
+            let host = match sandbox {
+                Sandbox::Flatpak { .. } => path_var(&format!("HOST_{}", name)),
+                _ => path_var(name),
+            };
+            host.unwrap_or_else(|| home.join(default))
+        };
+        let app_dir = |name: &str, default: &str| {
+            path_var(name)
+                .unwrap_or_else(|| home.join(default))
+                .join(APP_DIR)
+        };
+
+        let host_config = host_dir("XDG_CONFIG_HOME", ".config");
+        let downloads = path_var("XDG_DOWNLOAD_DIR")
+            .or_else(|| {
+                let dirs = fs::read_to_string(host_config.join(USER_DIRS)).ok()?;
+                user_dir(&dirs, "XDG_DOWNLOAD_DIR", &home)
+            })
+            .unwrap_or_else(|| home.join("Downloads"));
+
+        let cache = app_dir("XDG_CACHE_HOME", ".cache");
+        let runtime = match (path_var("XDG_RUNTIME_DIR"), &sandbox) {
+            // The only part of the runtime directory shared with the host
+            (Some(dir), Sandbox::Flatpak { app_id }) => dir.join("app").join(app_id),
+            (Some(dir), _) => dir.join(APP_DIR),
+            (None, _) => cache.join("runtime"),
+        };
+
+        Self {
+            host_config,
+            host_data: host_dir("XDG_DATA_HOME", ".local/share"),
+            downloads,
+            config: app_dir("XDG_CONFIG_HOME", ".config"),
+            cache,
+            runtime,
+            home,
+            sandbox,
+        }
+    }
+
+    /// Whether the app runs in a sandbox.
+    pub fn is_sandboxed(&self) -> bool {
+        self.sandbox != Sandbox::None
+    }
+}
+
+fn detect_sandbox(root: &Path, var: &impl Fn(&str) -> Option<OsString>) -> Sandbox {
+    if let Ok(info) = fs::read_to_string(root.join(FLATPAK_INFO)) {
+        let app_id = flatpak_app_id(&info).unwrap_or(APP_DIR);
+        return Sandbox::Flatpak {
+            app_id: app_id.to_string(),
+        };
+    }
+    if var("container").is_some_and(|container| container == "firejail") {
+        return Sandbox::Firejail;
+    }
+    Sandbox::None
+}
+
+/// `name` in the `[Application]` group of a `.flatpak-info` keyfile.
+fn flatpak_app_id(info: &str) -> Option<&str> {
+    let mut in_application = false;
+    for line in info.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_application = line == "[Application]";
+        } else if let Some((key, value)) = line.split_once('=') {
+            if in_application && key.trim() == "name" {
+                return Some(value.trim()).filter(|id| !id.is_empty());
+            }
+        }
+    }
+    None
+}
+
+/// Folder `name` of a `user-dirs.dirs` file, whose lines look like
+/// `XDG_DOWNLOAD_DIR="$HOME/Downloads"`.
+///
+/// Values are quoted and either absolute or relative to `$HOME`.
+fn user_dir(dirs: &str, name: &str, home: &Path) -> Option<PathBuf> {
+    dirs.lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == name)
+        .and_then(|(_, value)| {
+            let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+            match value.strip_prefix("$HOME") {
+                Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+                    Some(home.join(rest.trim_start_matches('/')))
+                }
+                Some(_) => None,
+                None => Some(PathBuf::from(value)).filter(|path| path.is_absolute()),
+            }
+        })
+}
+
+/// The directories of this process, resolved on first use.
+pub fn get() -> &'static Paths {
+    PATHS.get_or_init(|| Paths::resolve(Path::new("/"), |name| std::env::var_os(name)))
+}
+
+/// The runtime directory, created and made private to the user.
+///
+/// For files handed to privileged commands, which `/tmp` would leave
+/// readable by everyone, or hide from the host in a sandbox.
+pub fn runtime_dir() -> io::Result<&'static Path> {
+    let dir = &get().runtime;
+    DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    fs::set_permissions(dir, Permissions::from_mode(0o700))?;
+    Ok(dir)
+}
+
+/// Create `dir` if missing and check that files can be written to it.
+pub fn ensure_writable_dir(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let probe = dir.join(format!(".{}-write-test-{}", APP_DIR, std::process::id()));
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)?;
+    fs::remove_file(&probe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn scratch_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("xero-paths-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn resolve(root: &Path, vars: &[(&str, &str)]) -> Paths {
+        let vars: HashMap<String, OsString> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), OsString::from(value)))
+            .collect();
+        Paths::resolve(root, |name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_unsandboxed_paths() {
+        let root = scratch_root("host");
+        let paths = resolve(
+            &root,
+            &[
+                ("HOME", "/home/xero"),
+                ("XDG_CACHE_HOME", "/home/xero/.cache-alt"),
+                ("XDG_RUNTIME_DIR", "/run/user/1000"),
+            ],
+        );
+        assert_eq!(paths.sandbox, Sandbox::None);
+        assert!(!paths.is_sandboxed());
+        assert_eq!(paths.home, Path::new("/home/xero"));
+        assert_eq!(paths.host_config, Path::new("/home/xero/.config"));
+        assert_eq!(paths.host_data, Path::new("/home/xero/.local/share"));
+        assert_eq!(paths.config, Path::new("/home/xero/.config/xero-toolkit"));
+        assert_eq!(paths.cache, Path::new("/home/xero/.cache-alt/xero-toolkit"));
+        assert_eq!(paths.runtime, Path::new("/run/user/1000/xero-toolkit"));
+        // No user-dirs.dirs under the config directory
+        assert_eq!(paths.downloads, Path::new("/home/xero/Downloads"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_flatpak_paths() {
+        let root = scratch_root("flatpak");
+        fs::write(
+            root.join(FLATPAK_INFO),
+            "[Application]\nname=org.xerolinux.Toolkit\nruntime=runtime/org.gnome.Platform\n\n\
+             [Instance]\nname=other\n",
+        )
+        .unwrap();
+        let paths = resolve(
+            &root,
+            &[
+                ("HOME", "/home/xero"),
+                (
+                    "XDG_CONFIG_HOME",
+                    "/home/xero/.var/app/org.xerolinux.Toolkit/config",
+                ),
+                (
+                    "XDG_CACHE_HOME",
+                    "/home/xero/.var/app/org.xerolinux.Toolkit/cache",
+                ),
+                ("HOST_XDG_DATA_HOME", "/data/xero"),
+                ("XDG_RUNTIME_DIR", "/run/user/1000"),
+            ],
+        );
+        assert_eq!(
+            paths.sandbox,
+            Sandbox::Flatpak {
+                app_id: "org.xerolinux.Toolkit".to_string()
+            }
+        );
+        // Desktop files go to the host, the app's own into the sandbox
+        assert_eq!(paths.host_config, Path::new("/home/xero/.config"));
+        assert_eq!(paths.host_data, Path::new("/data/xero"));
+        assert_eq!(
+            paths.config,
+            Path::new("/home/xero/.var/app/org.xerolinux.Toolkit/config/xero-toolkit")
+        );
+        assert_eq!(
+            paths.runtime,
+            Path::new("/run/user/1000/app/org.xerolinux.Toolkit")
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_firejail_and_fallbacks() {
+        let root = scratch_root("firejail");
+        let paths = resolve(
+            &root,
+            &[
+                ("HOME", "/home/xero"),
+                ("container", "firejail"),
+                // Relative values are ignored, as the XDG spec asks
+                ("XDG_CONFIG_HOME", "relative/config"),
+            ],
+        );
+        assert_eq!(paths.sandbox, Sandbox::Firejail);
+        assert!(paths.is_sandboxed());
+        assert_eq!(paths.host_config, Path::new("/home/xero/.config"));
+        // Without a runtime directory, a private one in the cache
+        assert_eq!(
+            paths.runtime,
+            Path::new("/home/xero/.cache/xero-toolkit/runtime")
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_download_folder() {
+        let root = scratch_root("downloads");
+        let config = root.join("config");
+        fs::create_dir_all(&config).unwrap();
+        fs::write(
+            config.join(USER_DIRS),
+            "# Written by xdg-user-dirs-update\nXDG_DESKTOP_DIR=\"$HOME/Desktop\"\n\
+             XDG_DOWNLOAD_DIR=\"$HOME/Téléchargements\"\n",
+        )
+        .unwrap();
+        let config_home = config.to_string_lossy().to_string();
+        let paths = resolve(
+            &root,
+            &[("HOME", "/home/xero"), ("XDG_CONFIG_HOME", &config_home)],
+        );
+        assert_eq!(paths.downloads, Path::new("/home/xero/Téléchargements"));
+
+        // The environment wins over the file
+        let paths = resolve(
+            &root,
+            &[
+                ("HOME", "/home/xero"),
+                ("XDG_CONFIG_HOME", &config_home),
+                ("XDG_DOWNLOAD_DIR", "/data/downloads"),
+            ],
+        );
+        assert_eq!(paths.downloads, Path::new("/data/downloads"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_user_dir() {
+        let home = Path::new("/home/xero");
+        let dirs = "XDG_DOWNLOAD_DIR=\"/mnt/downloads\"\nXDG_MUSIC_DIR=\"$HOME/\"\n\
+                    XDG_VIDEOS_DIR=\"$HOMEVideos\"\nXDG_PICTURES_DIR=Pictures\n\
+                    #XDG_TEMPLATES_DIR=\"$HOME/Templates\"\n";
+        assert_eq!(
+            user_dir(dirs, "XDG_DOWNLOAD_DIR", home),
+            Some(PathBuf::from("/mnt/downloads"))
+        );
+        assert_eq!(
+            user_dir(dirs, "XDG_MUSIC_DIR", home),
+            Some(PathBuf::from("/home/xero"))
+        );
+        // Malformed, unquoted, commented out or missing
+        assert_eq!(user_dir(dirs, "XDG_VIDEOS_DIR", home), None);
+        assert_eq!(user_dir(dirs, "XDG_PICTURES_DIR", home), None);
+        assert_eq!(user_dir(dirs, "XDG_TEMPLATES_DIR", home), None);
+        assert_eq!(user_dir(dirs, "XDG_DESKTOP_DIR", home), None);
+    }
+
+    #[test]
+    fn test_ensure_writable_dir() {
+        let root = scratch_root("writable");
+        let dir = root.join("isos/arch");
+        ensure_writable_dir(&dir).unwrap();
+        assert!(dir.is_dir());
+        // Nothing is left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        fs::write(root.join("file"), "").unwrap();
+        assert!(ensure_writable_dir(&root.join("file/isos")).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_flatpak_app_id() {
+        assert_eq!(
+            flatpak_app_id("[Application]\nname = org.example.App\n"),
+            Some("org.example.App")
+        );
+        assert_eq!(flatpak_app_id("[Instance]\nname=foo\n"), None);
+        assert_eq!(flatpak_app_id("[Application]\nname=\n"), None);
+    }
+}
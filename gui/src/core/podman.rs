@@ -0,0 +1,297 @@
+//! Rootless Podman readiness checks.
+//!
+//! Podman runs rootless containers in a user namespace mapping the
+//! subordinate IDs of `/etc/subuid` and `/etc/subgid`, set up by the
+//! privileged newuidmap and newgidmap helpers. Without them, installing
+//! podman still leaves `podman run` failing with namespace errors.
+
+use super::exec;
+use log::warn;
+use std::os::unix::fs::MetadataExt;
+use std::time::Duration;
+
+pub const SUBUID_PATH: &str = "/etc/subuid";
+pub const SUBGID_PATH: &str = "/etc/subgid";
+
+const NEWUIDMAP: &str = "/usr/bin/newuidmap";
+const NEWGIDMAP: &str = "/usr/bin/newgidmap";
+
+/// First ID handed out when no range is taken yet, as useradd does.
+const SUBID_FIRST: u64 = 100_000;
+
+/// Size of an added range, enough for the IDs of a full distribution.
+const SUBID_COUNT: u64 = 65_536;
+
+/// A range of subordinate IDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubIdRange {
+    pub start: u64,
+    pub count: u64,
+}
+
+/// Owner and ranges of each valid line of a subuid or subgid file.
+fn subid_entries(contents: &str) -> impl Iterator<Item = (&str, SubIdRange)> {
+    contents.lines().filter_map(|line| {
+        let line = line.trim();
+        if line.starts_with('#') {
+            return None;
+        }
+        let mut fields = line.split(':');
+        let owner = fields.next().filter(|owner| !owner.is_empty())?;
+        let start = fields.next()?.trim().parse().ok()?;
+        let count = fields.next()?.trim().parse().ok()?;
+        (fields.next().is_none() && count > 0).then_some((owner, SubIdRange { start, count }))
+    })
+}
+
+/// Ranges of `user` in a subuid or subgid file, which may name the user
+/// or give its `uid`.
+pub fn subid_ranges(contents: &str, user: &str, uid: u32) -> Vec<SubIdRange> {
+    let uid = uid.to_string();
+    subid_entries(contents)
+        .filter(|(owner, _)| *owner == user || *owner == uid)
+        .map(|(_, range)| range)
+        .collect()
+}
+
+/// `contents` with a range for `user` appended after every range taken,
+/// unchanged if the user already has one.
+pub fn add_subid_range(contents: &str, user: &str, uid: u32) -> String {
+    if !subid_ranges(contents, user, uid).is_empty() {
+        return contents.to_string();
+    }
+    let start = subid_entries(contents)
+        .map(|(_, range)| range.start + range.count)
+        .fold(SUBID_FIRST, u64::max);
+
+    let mut new = contents.to_string();
+    if !new.is_empty() && !new.ends_with('\n') {
+        new.push('\n');
+    }
+    new.push_str(&format!("{}:{}:{}\n", user, start, SUBID_COUNT));
+    new
+}
+
+/// Whether `getcap` output grants `capability` as effective and permitted,
+/// in both the `path cap_setuid=ep` and the older `path = cap_setuid+ep`
+/// format.
+pub fn grants_capability(getcap_output: &str, capability: &str) -> bool {
+    getcap_output.lines().any(|line| {
+        // The path comes first and has no spaces in the helpers' case
+        let Some((_, caps)) = line.trim().split_once(char::is_whitespace) else {
+            return false;
+        };
+        let caps = caps.trim_start().trim_start_matches("= ");
+        caps.split_whitespace().any(|clause| {
+            let Some(op) = clause.find(['=', '+']) else {
+                return false;
+            };
+            let (names, flags) = clause.split_at(op);
+            // `=ep` alone grants every capability
+            let named = names.is_empty()
+                || names
+                    .split(',')
+                    .any(|name| name == capability || name == "all");
+            named && flags.contains('e') && flags.contains('p')
+        })
+    })
+}
+
+/// Whether an ID map helper may write the maps, by being setuid root or
+/// by holding `capability`.
+pub fn helper_privileged(mode: u32, owner: u32, getcap_output: &str, capability: &str) -> bool {
+    (owner == 0 && mode & 0o4000 != 0) || grants_capability(getcap_output, capability)
+}
+
+/// Outcome of `podman info --format {{.Host.Security.Rootless}}`: `Ok` if
+/// podman ran rootless, else the error it gave.
+pub fn rootless_info(success: bool, stdout: &str, stderr: &str) -> Result<(), String> {
+    if success {
+        return match stdout.trim() {
+            "true" => Ok(()),
+            _ => Err("podman did not run rootless".to_string()),
+        };
+    }
+    let lines: Vec<&str> = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let error = lines
+        .iter()
+        .rev()
+        .find_map(|line| line.strip_prefix("Error: "))
+        .or(lines.last().copied())
+        .unwrap_or("podman info failed");
+    Err(error.to_string())
+}
+
+/// Rootless readiness of a user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Readiness {
+    pub subuid: bool,
+    pub subgid: bool,
+    pub newuidmap: bool,
+    pub newgidmap: bool,
+    /// Result of running `podman info` as the user
+    pub info: Result<(), String>,
+}
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.subuid && self.subgid && self.newuidmap && self.newgidmap && self.info.is_ok()
+    }
+}
+
+fn has_subids(path: &str, user: &str, uid: u32) -> bool {
+    std::fs::read_to_string(path)
+        .map(|contents| !subid_ranges(&contents, user, uid).is_empty())
+        .unwrap_or(false)
+}
+
+fn check_helper(path: &str, capability: &str) -> bool {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return false;
+    };
+    // getcap comes with libcap and may be missing on setuid systems
+    let getcap = exec::capture("getcap", &[path], exec::Options::default())
+        .map(|captured| captured.stdout)
+        .unwrap_or_default();
+    helper_privileged(metadata.mode(), metadata.uid(), &getcap, capability)
+}
+
+fn check_info() -> Result<(), String> {
+    let output = exec::capture(
+        "podman",
+        &["info", "--format", "{{.Host.Security.Rootless}}"],
+        exec::Options {
+            // The first run sets up the user's storage
+            timeout: Duration::from_secs(30),
+            ..Default::default()
+        },
+    )
+    .map_err(|e| format!("{:#}", e))?;
+    rootless_info(output.success(), &output.stdout, &output.stderr)
+        .inspect_err(|e| warn!("podman info failed: {}", e))
+}
+
+/// Check `user` with `uid`. Runs podman, so call it off the main thread.
+pub fn check(user: &str, uid: u32) -> Readiness {
+    Readiness {
+        subuid: has_subids(SUBUID_PATH, user, uid),
+        subgid: has_subids(SUBGID_PATH, user, uid),
+        newuidmap: check_helper(NEWUIDMAP, "cap_setuid"),
+        newgidmap: check_helper(NEWGIDMAP, "cap_setgid"),
+        info: check_info(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUBUID: &str = "\
+# Subordinate user IDs
+alice:100000:65536
+1001:165536:65536
+broken:line
+carol:231072:0
+";
+
+    #[test]
+    fn test_subid_ranges() {
+        assert_eq!(
+            subid_ranges(SUBUID, "alice", 1000),
+            [SubIdRange {
+                start: 100000,
+                count: 65536
+            }]
+        );
+        // Listed by UID
+        assert_eq!(subid_ranges(SUBUID, "bob", 1001).len(), 1);
+        // Empty range
+        assert!(subid_ranges(SUBUID, "carol", 1002).is_empty());
+        assert!(subid_ranges("", "alice", 1000).is_empty());
+    }
+
+    #[test]
+    fn test_add_subid_range() {
+        assert_eq!(add_subid_range(SUBUID, "alice", 1000), SUBUID);
+        assert_eq!(
+            add_subid_range(SUBUID, "dave", 1003),
+            format!("{}dave:231072:65536\n", SUBUID)
+        );
+        assert_eq!(add_subid_range("", "dave", 1003), "dave:100000:65536\n");
+        // Low ranges still start the new one at the usual first ID
+        assert_eq!(
+            add_subid_range("root:1000:10", "dave", 1003),
+            "root:1000:10\ndave:100000:65536\n"
+        );
+    }
+
+    #[test]
+    fn test_grants_capability() {
+        assert!(grants_capability(
+            "/usr/bin/newuidmap cap_setuid=ep\n",
+            "cap_setuid"
+        ));
+        // libcap before 2.40
+        assert!(grants_capability(
+            "/usr/bin/newgidmap = cap_setgid+ep",
+            "cap_setgid"
+        ));
+        assert!(grants_capability(
+            "/usr/bin/newuidmap cap_setgid,cap_setuid=ep",
+            "cap_setuid"
+        ));
+        assert!(grants_capability("/usr/bin/newuidmap =ep", "cap_setuid"));
+        // Only permitted
+        assert!(!grants_capability(
+            "/usr/bin/newuidmap cap_setuid=p",
+            "cap_setuid"
+        ));
+        assert!(!grants_capability(
+            "/usr/bin/newuidmap cap_setgid=ep",
+            "cap_setuid"
+        ));
+        // No capabilities set
+        assert!(!grants_capability("", "cap_setuid"));
+    }
+
+    #[test]
+    fn test_helper_privileged() {
+        assert!(helper_privileged(0o104755, 0, "", "cap_setuid"));
+        // Setuid, but not to root
+        assert!(!helper_privileged(0o104755, 1000, "", "cap_setuid"));
+        assert!(!helper_privileged(0o100755, 0, "", "cap_setuid"));
+        assert!(helper_privileged(
+            0o100755,
+            0,
+            "/usr/bin/newuidmap cap_setuid=ep",
+            "cap_setuid"
+        ));
+    }
+
+    #[test]
+    fn test_rootless_info() {
+        assert_eq!(rootless_info(true, "true\n", ""), Ok(()));
+        assert!(rootless_info(true, "false\n", "").is_err());
+        assert_eq!(
+            rootless_info(
+                false,
+                "",
+                "time=\"2024-09-01T10:00:00Z\" level=warning msg=\"missing subuid\"\n\
+                 Error: cannot set up namespace using \"/usr/bin/newuidmap\": exit status 1\n"
+            ),
+            Err("cannot set up namespace using \"/usr/bin/newuidmap\": exit status 1".to_string())
+        );
+        assert_eq!(
+            rootless_info(false, "", "permission denied\n"),
+            Err("permission denied".to_string())
+        );
+        assert_eq!(
+            rootless_info(false, "", ""),
+            Err("podman info failed".to_string())
+        );
+    }
+}
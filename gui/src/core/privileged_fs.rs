@@ -0,0 +1,464 @@
+//! Edits of root-owned files, computed in Rust and written with privileges.
+//!
+//! An edit reads the file when its step runs, so it sees what earlier steps
+//! left behind, and is skipped when the file already has the wanted
+//! contents. The daemon writes it through a temporary file in the same
+//! directory and a rename, after copying the old file to a timestamped
+//! backup.
+
+use crate::ui::task_runner::Command;
+use anyhow::{bail, Context, Result};
+use log::{info, warn};
+use regex::Regex;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Lines of unchanged context around each change in a diff.
+const DIFF_CONTEXT: usize = 3;
+
+/// Above this many line pairs, diffs show the whole file as replaced.
+const DIFF_MAX_CELLS: usize = 4_000_000;
+
+#[derive(Clone, Debug)]
+enum Edit {
+    AppendLine(String),
+    Write(String),
+    ReplaceRegex { regex: Regex, replacement: String },
+    Transform(Transform),
+}
+
+/// Function computing new contents from the current ones.
+#[derive(Clone)]
+struct Transform(Rc<dyn Fn(&str) -> String>);
+
+impl std::fmt::Debug for Transform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Transform")
+    }
+}
+
+/// A change to one file.
+#[derive(Clone, Debug)]
+pub struct FileEdit {
+    pub path: PathBuf,
+    edit: Edit,
+    mode: Option<u32>,
+}
+
+/// Append `line` unless the file already has it, ignoring surrounding
+/// whitespace. Creates the file if missing.
+pub fn append_line_if_missing(path: impl Into<PathBuf>, line: &str) -> FileEdit {
+    FileEdit {
+        path: path.into(),
+        edit: Edit::AppendLine(line.to_string()),
+        mode: None,
+    }
+}
+
+/// Replace the whole file with `contents`.
+pub fn write_file(path: impl Into<PathBuf>, contents: &str) -> FileEdit {
+    FileEdit {
+        path: path.into(),
+        edit: Edit::Write(contents.to_string()),
+        mode: None,
+    }
+}
+
+/// Replace every match of `pattern` with `replacement`, which may refer to
+/// groups as `$1`. Missing files are left alone.
+///
+/// # Errors
+///
+/// Returns an error if `pattern` is not a valid regular expression.
+pub fn replace_regex(
+    path: impl Into<PathBuf>,
+    pattern: &str,
+    replacement: &str,
+) -> Result<FileEdit> {
+    let regex = Regex::new(pattern).with_context(|| format!("Invalid pattern {:?}", pattern))?;
+    Ok(FileEdit {
+        path: path.into(),
+        edit: Edit::ReplaceRegex {
+            regex,
+            replacement: replacement.to_string(),
+        },
+        mode: None,
+    })
+}
+
+/// Replace the contents with what `transform` makes of them. A missing
+/// file is passed as empty.
+pub fn transform<F>(path: impl Into<PathBuf>, transform: F) -> FileEdit
+where
+    F: Fn(&str) -> String + 'static,
+{
+    FileEdit {
+        path: path.into(),
+        edit: Edit::Transform(Transform(Rc::new(transform))),
+        mode: None,
+    }
+}
+
+impl FileEdit {
+    /// Give the written file `mode` instead of keeping the old one.
+    pub fn mode(mut self, mode: u32) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// New contents given the `current` ones (`None` if the file is
+    /// missing), or `None` if nothing would change.
+    pub fn apply(&self, current: Option<&str>) -> Option<String> {
+        let new = match &self.edit {
+            Edit::AppendLine(line) => {
+                let current = current.unwrap_or_default();
+                if current
+                    .lines()
+                    .any(|existing| existing.trim() == line.trim())
+                {
+                    return None;
+                }
+                let mut new = current.to_string();
+                if !new.is_empty() && !new.ends_with('\n') {
+                    new.push('\n');
+                }
+                new.push_str(line);
+                new.push('\n');
+                new
+            }
+            Edit::Write(contents) => contents.clone(),
+            Edit::ReplaceRegex { regex, replacement } => regex
+                .replace_all(current?, replacement.as_str())
+                .into_owned(),
+            Edit::Transform(transform) => (transform.0)(current.unwrap_or_default()),
+        };
+        (current != Some(new.as_str())).then_some(new)
+    }
+
+    /// New contents given what is on disk now, `None` if unchanged.
+    pub fn pending(&self) -> Result<Option<String>> {
+        Ok(self.apply(read(&self.path)?.as_deref()))
+    }
+
+    /// Unified diff of the edit against what is on disk now, `None` if
+    /// unchanged.
+    pub fn preview(&self) -> Result<Option<String>> {
+        let current = read(&self.path)?;
+        Ok(self
+            .apply(current.as_deref())
+            .map(|new| unified_diff(&self.path, current.as_deref().unwrap_or_default(), &new)))
+    }
+
+    /// A privileged step performing the edit.
+    ///
+    /// The file is read and the new contents computed when the step's turn
+    /// comes; the step is skipped if the file needs no change.
+    pub fn command(&self, description: &str) -> Command {
+        let pending: Rc<RefCell<Option<Result<String, String>>>> = Rc::default();
+        let path = self.path.to_string_lossy().into_owned();
+
+        let edit = self.clone();
+        let computed = pending.clone();
+        let check = move || match edit.pending() {
+            Ok(Some(contents)) => {
+                *computed.borrow_mut() = Some(Ok(contents));
+                true
+            }
+            Ok(None) => {
+                info!("{} is already up to date", edit.path.display());
+                false
+            }
+            // Fails the step with the error
+            Err(e) => {
+                warn!("Failed to prepare edit of {}: {:#}", edit.path.display(), e);
+                *computed.borrow_mut() = Some(Err(format!("{:#}", e)));
+                true
+            }
+        };
+
+        let mut command = Command::write_file(description, &path)
+            .contents_with(move || {
+                pending
+                    .borrow_mut()
+                    .take()
+                    .unwrap_or_else(|| Err("File contents were not computed".to_string()))
+            })
+            .backup()
+            .run_if(check);
+        if let Some(mode) = self.mode {
+            command = command.mode(mode);
+        }
+        command.build()
+    }
+}
+
+/// Contents of `path`, `None` if it does not exist.
+///
+/// Files the user cannot read are read through the daemon, if it runs.
+pub fn read(path: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => read_privileged(path),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Read `path` as root through the daemon.
+fn read_privileged(path: &Path) -> Result<Option<String>> {
+    if !xero_auth::is_daemon_running() {
+        bail!(
+            "{} is only readable with administrator rights",
+            path.display()
+        );
+    }
+    info!("Reading {} through the daemon", path.display());
+
+    let contents = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("Failed to create runtime")?
+        .block_on(async {
+            let mut client = xero_auth::Client::new().await?;
+            client.read_file(&path.to_string_lossy()).await
+        })
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    contents
+        .map(String::from_utf8)
+        .transpose()
+        .with_context(|| format!("{} is not a text file", path.display()))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Line operations turning `old` into `new`, from their longest common
+/// subsequence.
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Op, &'a str)> {
+    if old.len().saturating_mul(new.len()) > DIFF_MAX_CELLS {
+        return old
+            .iter()
+            .map(|line| (Op::Delete, *line))
+            .chain(new.iter().map(|line| (Op::Insert, *line)))
+            .collect();
+    }
+
+    // lcs[i][j]: common lines of old[i..] and new[j..]
+    let width = new.len() + 1;
+    let mut lcs = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i * width + j] = if old[i] == new[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push((Op::Equal, old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len()
+            && (j == new.len() || lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+        {
+            ops.push((Op::Delete, old[i]));
+            i += 1;
+        } else {
+            ops.push((Op::Insert, new[j]));
+            j += 1;
+        }
+    }
+    ops
+}
+
+/// Unified diff from `old` to `new`, empty if they have the same lines.
+pub fn unified_diff(path: &Path, old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+
+    // Ranges of ops to show: changes plus context, merged when close
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (index, (op, _)) in ops.iter().enumerate() {
+        if *op == Op::Equal {
+            continue;
+        }
+        let start = index.saturating_sub(DIFF_CONTEXT);
+        let end = (index + 1 + DIFF_CONTEXT).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    let display = path.display();
+    let mut diff = format!("--- {display}\n+++ {display}\n");
+    for (start, end) in hunks {
+        let before = &ops[..start];
+        let old_start = before.iter().filter(|(op, _)| *op != Op::Insert).count();
+        let new_start = before.iter().filter(|(op, _)| *op != Op::Delete).count();
+        let hunk = &ops[start..end];
+        let old_count = hunk.iter().filter(|(op, _)| *op != Op::Insert).count();
+        let new_count = hunk.iter().filter(|(op, _)| *op != Op::Delete).count();
+
+        // Empty ranges name the line before them
+        let first = |start: usize, count: usize| if count > 0 { start + 1 } else { start };
+        diff.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            first(old_start, old_count),
+            old_count,
+            first(new_start, new_count),
+            new_count
+        ));
+        for (op, line) in hunk {
+            let prefix = match op {
+                Op::Equal => ' ',
+                Op::Delete => '-',
+                Op::Insert => '+',
+            };
+            diff.push(prefix);
+            diff.push_str(line);
+            diff.push('\n');
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GPG_CONF: &str = "no-greeting\nno-permission-warning\nlock-never\n";
+    const KEYSERVER: &str = "keyserver hkp://keyserver.ubuntu.com:80";
+
+    #[test]
+    fn test_append_line_if_missing() {
+        let edit = append_line_if_missing("/etc/pacman.d/gnupg/gpg.conf", KEYSERVER);
+        let new = edit.apply(Some(GPG_CONF)).unwrap();
+        assert_eq!(new, format!("{}{}\n", GPG_CONF, KEYSERVER));
+        // Applying again changes nothing
+        assert_eq!(edit.apply(Some(&new)), None);
+        assert_eq!(
+            edit.apply(Some("  keyserver hkp://keyserver.ubuntu.com:80  \n")),
+            None
+        );
+
+        // Missing newline at the end, missing and empty files
+        assert_eq!(
+            edit.apply(Some("lock-never")).unwrap(),
+            format!("lock-never\n{}\n", KEYSERVER)
+        );
+        assert_eq!(edit.apply(None).unwrap(), format!("{}\n", KEYSERVER));
+        assert_eq!(edit.apply(Some("")).unwrap(), format!("{}\n", KEYSERVER));
+        // A commented out line does not count
+        assert!(edit.apply(Some(&format!("# {}\n", KEYSERVER))).is_some());
+    }
+
+    #[test]
+    fn test_write_file() {
+        let edit = write_file("/etc/systemd/zram-generator.conf", "[zram0]\n");
+        assert_eq!(edit.apply(None).as_deref(), Some("[zram0]\n"));
+        assert_eq!(edit.apply(Some("[zram1]\n")).as_deref(), Some("[zram0]\n"));
+        assert_eq!(edit.apply(Some("[zram0]\n")), None);
+    }
+
+    #[test]
+    fn test_transform() {
+        let edit = transform("/etc/locale.gen", |current| current.replace("#de", "de"));
+        assert_eq!(
+            edit.apply(Some("#de_DE.UTF-8 UTF-8\n")).as_deref(),
+            Some("de_DE.UTF-8 UTF-8\n")
+        );
+        assert_eq!(edit.apply(Some("de_DE.UTF-8 UTF-8\n")), None);
+        assert_eq!(edit.apply(None).as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_replace_regex() {
+        let edit = replace_regex(
+            "/etc/pacman.conf",
+            r"(?m)^#?ParallelDownloads\s*=.*$",
+            "ParallelDownloads = 10",
+        )
+        .unwrap();
+        let conf = "[options]\n#ParallelDownloads = 5\nColor\n";
+        let new = edit.apply(Some(conf)).unwrap();
+        assert_eq!(new, "[options]\nParallelDownloads = 10\nColor\n");
+        assert_eq!(edit.apply(Some(&new)), None);
+        // Nothing to replace in a missing file
+        assert_eq!(edit.apply(None), None);
+        assert_eq!(edit.apply(Some("Color\n")), None);
+
+        assert!(replace_regex("/etc/x", "(", "").is_err());
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let path = Path::new("/etc/pacman.d/gnupg/gpg.conf");
+        let new = format!("{}{}\n", GPG_CONF, KEYSERVER);
+        assert_eq!(
+            unified_diff(path, GPG_CONF, &new),
+            format!(
+                "--- {0}\n+++ {0}\n@@ -1,3 +1,4 @@\n no-greeting\n no-permission-warning\n \
+                 lock-never\n+{1}\n",
+                path.display(),
+                KEYSERVER
+            )
+        );
+        assert_eq!(unified_diff(path, GPG_CONF, GPG_CONF), "");
+        // New file
+        assert_eq!(
+            unified_diff(path, "", "a\nb\n"),
+            format!(
+                "--- {0}\n+++ {0}\n@@ -0,0 +1,2 @@\n+a\n+b\n",
+                path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_hunks() {
+        let path = Path::new("/etc/f");
+        let lines = |edit: fn(u32) -> Option<String>| -> String {
+            (1..=20)
+                .filter_map(edit)
+                .map(|line| format!("{}\n", line))
+                .collect()
+        };
+        let old = lines(|n| Some(n.to_string()));
+        let new = lines(|n| match n {
+            2 => Some("two".to_string()),
+            18 => None,
+            n => Some(n.to_string()),
+        });
+        assert_eq!(
+            unified_diff(path, &old, &new),
+            "--- /etc/f\n+++ /etc/f\n\
+             @@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n 4\n 5\n\
+             @@ -15,6 +15,5 @@\n 15\n 16\n 17\n-18\n 19\n 20\n"
+        );
+
+        // Changes closer than twice the context share a hunk
+        let new = lines(|n| match n {
+            2 => Some("two".to_string()),
+            7 => Some("seven".to_string()),
+            n => Some(n.to_string()),
+        });
+        let diff = unified_diff(path, &old, &new);
+        assert_eq!(diff.matches("@@ -").count(), 1);
+        assert!(diff.contains("@@ -1,10 +1,10 @@\n"));
+    }
+}
@@ -0,0 +1,176 @@
+//! Setup profiles: a replayable list of actions and their choices.
+//!
+//! Profiles store action ids rather than commands, so importing one runs
+//! whatever the installed toolkit version does for those actions. Ids the
+//! current registry does not know are reported back instead of dropped.
+
+use crate::core::actions::{self, Action};
+use crate::ui::task_runner::CommandSequence;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Schema version written to new profiles.
+pub const PROFILE_VERSION: u32 = 1;
+
+/// File name suggested when exporting.
+pub const DEFAULT_FILE_NAME: &str = "xero-toolkit-profile.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    pub version: u32,
+    pub actions: Vec<ProfileEntry>,
+}
+
+/// One action to replay, with the choices made in its selection dialog.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileEntry {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<String>,
+}
+
+/// A profile matched against the action registry.
+#[derive(Debug, Default)]
+pub struct ResolvedProfile {
+    pub steps: Vec<(&'static Action, Vec<String>)>,
+    /// Action ids this version does not know
+    pub unknown_actions: Vec<String>,
+    /// Choices unknown to their action, as `(action id, choice id)`
+    pub unknown_choices: Vec<(String, String)>,
+}
+
+impl Profile {
+    pub fn new(actions: Vec<ProfileEntry>) -> Self {
+        Self {
+            version: PROFILE_VERSION,
+            actions,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("Failed to serialize profile")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let profile: Self = serde_json::from_str(json).context("Not a valid setup profile")?;
+        if profile.version > PROFILE_VERSION {
+            bail!(
+                "Profile version {} is newer than the supported version {}",
+                profile.version,
+                PROFILE_VERSION
+            );
+        }
+        Ok(profile)
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::from_json(&json)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_json()?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Match every entry against the action registry.
+    pub fn resolve(&self) -> ResolvedProfile {
+        let mut resolved = ResolvedProfile::default();
+        for entry in &self.actions {
+            let Some(action) = actions::find(&entry.id) else {
+                resolved.unknown_actions.push(entry.id.clone());
+                continue;
+            };
+
+            let mut choices = Vec::new();
+            for choice in &entry.choices {
+                if action.find_choice(choice).is_some() {
+                    choices.push(choice.clone());
+                } else {
+                    resolved
+                        .unknown_choices
+                        .push((entry.id.clone(), choice.clone()));
+                }
+            }
+            resolved.steps.push((action, choices));
+        }
+        resolved
+    }
+}
+
+impl ResolvedProfile {
+    /// Whether some entries could not be matched.
+    pub fn has_unknown(&self) -> bool {
+        !self.unknown_actions.is_empty() || !self.unknown_choices.is_empty()
+    }
+
+    /// The steps' commands, one action after another.
+    pub fn commands(&self) -> CommandSequence {
+        self.steps
+            .iter()
+            .fold(CommandSequence::new(), |commands, (action, choices)| {
+                commands.extend(action.commands_with(choices))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Profile {
+        Profile::new(vec![
+            ProfileEntry {
+                id: "unlock-pacman".to_string(),
+                choices: Vec::new(),
+            },
+            ProfileEntry {
+                id: "update-mirrorlist".to_string(),
+                choices: vec!["chaotic".to_string()],
+            },
+        ])
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let profile = sample();
+        let json = profile.to_json().unwrap();
+        assert_eq!(Profile::from_json(&json).unwrap(), profile);
+        // Empty choices are left out of the file
+        assert_eq!(json.matches("\"choices\"").count(), 1);
+    }
+
+    #[test]
+    fn test_schema() {
+        let json = r#"{"version": 1, "actions": [{"id": "fix-pacman-db"}]}"#;
+        let profile = Profile::from_json(json).unwrap();
+        assert_eq!(profile.actions[0].id, "fix-pacman-db");
+        assert!(profile.actions[0].choices.is_empty());
+
+        assert!(Profile::from_json(r#"{"version": 2, "actions": []}"#).is_err());
+        assert!(Profile::from_json(r#"{"actions": []}"#).is_err());
+        assert!(Profile::from_json("not json").is_err());
+    }
+
+    #[test]
+    fn test_unknown_ids_are_reported() {
+        let mut profile = sample();
+        profile.actions.push(ProfileEntry {
+            id: "install-docker".to_string(),
+            choices: Vec::new(),
+        });
+        profile.actions[1].choices.push("endeavouros".to_string());
+
+        let resolved = profile.resolve();
+        assert_eq!(resolved.steps.len(), 2);
+        assert_eq!(resolved.steps[1].1, vec!["chaotic".to_string()]);
+        assert_eq!(resolved.unknown_actions, vec!["install-docker".to_string()]);
+        assert_eq!(
+            resolved.unknown_choices,
+            vec![("update-mirrorlist".to_string(), "endeavouros".to_string())]
+        );
+        assert!(resolved.has_unknown());
+    }
+}
@@ -0,0 +1,289 @@
+//! Proton-GE release lookup and installation helpers.
+//!
+//! Queries the GloriousEggroll GitHub releases, resolves where Steam expects
+//! custom compatibility tools and verifies downloaded tarballs.
+
+use anyhow::{Context, Result};
+use log::info;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// GitHub API endpoint listing Proton-GE releases.
+const RELEASES_URL: &str = "https://api.github.com/repos/GloriousEggroll/proton-ge-custom/releases";
+
+/// Flatpak application ID of Steam.
+const STEAM_FLATPAK_ID: &str = "com.valvesoftware.Steam";
+
+/// A Proton-GE release with its downloadable tarball.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProtonRelease {
+    pub tag: String,
+    pub tarball_name: String,
+    pub tarball_url: String,
+    pub size: u64,
+    pub checksum_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+    #[serde(default)]
+    size: u64,
+}
+
+/// Parse the GitHub releases JSON into installable releases.
+///
+/// Drafts and releases without a `.tar.gz` asset are skipped.
+pub fn parse_releases(json: &str) -> Result<Vec<ProtonRelease>> {
+    let releases: Vec<GithubRelease> =
+        serde_json::from_str(json).context("Failed to parse releases JSON")?;
+
+    Ok(releases
+        .into_iter()
+        .filter(|release| !release.draft)
+        .filter_map(|release| {
+            let tarball = release
+                .assets
+                .iter()
+                .find(|asset| asset.name.ends_with(".tar.gz"))?;
+            let checksum_url = release
+                .assets
+                .iter()
+                .find(|asset| asset.name.ends_with(".sha512sum"))
+                .map(|asset| asset.browser_download_url.clone());
+
+            Some(ProtonRelease {
+                tag: release.tag_name.clone(),
+                tarball_name: tarball.name.clone(),
+                tarball_url: tarball.browser_download_url.clone(),
+                size: tarball.size,
+                checksum_url,
+            })
+        })
+        .collect())
+}
+
+/// Fetch the available Proton-GE releases from GitHub.
+pub async fn fetch_releases() -> Result<Vec<ProtonRelease>> {
+    info!("Fetching Proton-GE releases...");
+
+    let body = http_client()?
+        .get(RELEASES_URL)
+        .send()
+        .await
+        .context("Failed to fetch Proton-GE releases")?
+        .error_for_status()
+        .context("GitHub API returned an error")?
+        .text()
+        .await
+        .context("Failed to read response body")?;
+
+    let releases = parse_releases(&body)?;
+    info!("Found {} Proton-GE releases", releases.len());
+    Ok(releases)
+}
+
+/// Fetch the published sha512 checksum for a release tarball.
+pub async fn fetch_checksum(release: &ProtonRelease) -> Result<String> {
+    let url = release
+        .checksum_url
+        .as_deref()
+        .context("Release does not publish a sha512sum")?;
+
+    let body = http_client()?
+        .get(url)
+        .send()
+        .await
+        .context("Failed to fetch checksum")?
+        .error_for_status()
+        .context("Checksum download returned an error")?
+        .text()
+        .await
+        .context("Failed to read checksum body")?;
+
+    parse_checksum(&body, &release.tarball_name)
+        .context("Checksum file does not list the release tarball")
+}
+
+fn http_client() -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .user_agent("xero-toolkit")
+        .build()
+        .context("Failed to build HTTP client")
+}
+
+/// Extract the hash for `file_name` from `sha512sum` formatted output.
+pub fn parse_checksum(content: &str, file_name: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let hash = parts.next()?;
+        let name = parts.next()?.trim_start_matches('*');
+        let name = name.rsplit('/').next().unwrap_or(name);
+        (name == file_name).then(|| hash.to_lowercase())
+    })
+}
+
+/// Verify a file against an expected sha512 hash using `sha512sum`.
+pub fn verify_sha512(path: &Path, expected: &str) -> Result<()> {
+    let output = std::process::Command::new("sha512sum")
+        .arg(path)
+        .output()
+        .context("Failed to run sha512sum")?;
+
+    if !output.status.success() {
+        anyhow::bail!("sha512sum exited with {}", output.status);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let actual = stdout.split_whitespace().next().unwrap_or_default();
+
+    if !actual.eq_ignore_ascii_case(expected) {
+        anyhow::bail!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            path.display(),
+            expected,
+            actual
+        );
+    }
+
+    Ok(())
+}
+
+/// Resolve the compatibilitytools.d directory for the installed Steam.
+///
+/// Native Steam is preferred; the flatpak location is used only when
+/// native Steam is absent and the flatpak data directory exists.
+pub fn compat_tools_dir(home: &Path) -> PathBuf {
+    let native_root = home.join(".steam/root");
+    let flatpak_root = home.join(".var/app").join(STEAM_FLATPAK_ID);
+
+    if !native_root.exists() && flatpak_root.exists() {
+        flatpak_root.join("data/Steam/compatibilitytools.d")
+    } else {
+        native_root.join("compatibilitytools.d")
+    }
+}
+
+/// List installed GE builds in the given compatibility tools directory.
+pub fn list_installed(dir: &Path) -> Vec<String> {
+    let mut installed: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| is_ge_build(name))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    installed.sort();
+    installed
+}
+
+/// Whether a directory name looks like a Proton-GE build.
+fn is_ge_build(name: &str) -> bool {
+    name.starts_with("GE-Proton") || (name.starts_with("Proton-") && name.contains("GE"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RELEASES_JSON: &str = r#"[
+        {
+            "tag_name": "GE-Proton9-20",
+            "draft": false,
+            "assets": [
+                {
+                    "name": "GE-Proton9-20.sha512sum",
+                    "browser_download_url": "https://example.com/GE-Proton9-20.sha512sum",
+                    "size": 145
+                },
+                {
+                    "name": "GE-Proton9-20.tar.gz",
+                    "browser_download_url": "https://example.com/GE-Proton9-20.tar.gz",
+                    "size": 446000000
+                }
+            ]
+        },
+        { "tag_name": "GE-Proton9-21", "draft": true, "assets": [] },
+        { "tag_name": "notes-only", "assets": [] }
+    ]"#;
+
+    #[test]
+    fn test_parse_releases() {
+        let releases = parse_releases(RELEASES_JSON).unwrap();
+        assert_eq!(releases.len(), 1);
+        assert_eq!(releases[0].tag, "GE-Proton9-20");
+        assert_eq!(releases[0].tarball_name, "GE-Proton9-20.tar.gz");
+        assert_eq!(releases[0].size, 446000000);
+        assert_eq!(
+            releases[0].checksum_url.as_deref(),
+            Some("https://example.com/GE-Proton9-20.sha512sum")
+        );
+    }
+
+    #[test]
+    fn test_parse_releases_invalid_json() {
+        assert!(parse_releases("not json").is_err());
+    }
+
+    #[test]
+    fn test_parse_checksum() {
+        let content = "ABCDEF  GE-Proton9-20.tar.gz\n123456  other.tar.gz\n";
+        assert_eq!(
+            parse_checksum(content, "GE-Proton9-20.tar.gz").as_deref(),
+            Some("abcdef")
+        );
+        assert_eq!(parse_checksum(content, "missing.tar.gz"), None);
+    }
+
+    #[test]
+    fn test_compat_tools_dir() {
+        let home = std::env::temp_dir().join(format!("xero-proton-{}", std::process::id()));
+
+        // Neither install present: default to native
+        assert_eq!(
+            compat_tools_dir(&home),
+            home.join(".steam/root/compatibilitytools.d")
+        );
+
+        // Flatpak only
+        let flatpak = home.join(".var/app").join(STEAM_FLATPAK_ID);
+        std::fs::create_dir_all(&flatpak).unwrap();
+        assert_eq!(
+            compat_tools_dir(&home),
+            flatpak.join("data/Steam/compatibilitytools.d")
+        );
+
+        // Native wins when both exist
+        std::fs::create_dir_all(home.join(".steam/root")).unwrap();
+        assert_eq!(
+            compat_tools_dir(&home),
+            home.join(".steam/root/compatibilitytools.d")
+        );
+
+        let _ = std::fs::remove_dir_all(&home);
+    }
+
+    #[test]
+    fn test_is_ge_build() {
+        assert!(is_ge_build("GE-Proton9-20"));
+        assert!(is_ge_build("Proton-6.5-GE-2"));
+        assert!(!is_ge_build("Proton-Experimental"));
+        assert!(!is_ge_build("Luxtorpeda"));
+    }
+}
@@ -0,0 +1,130 @@
+//! Fuzzy search over pages and actions for the command palette.
+
+/// A searchable destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchEntry {
+    pub title: String,
+    pub subtitle: String,
+    pub keywords: &'static [&'static str],
+    /// Page to open when the entry is activated.
+    pub page: &'static str,
+    /// Builder id of the button to highlight on that page.
+    pub button: Option<&'static str>,
+}
+
+/// Score how well `query` matches `text` as a case-insensitive subsequence.
+///
+/// Consecutive characters and matches at word starts score higher.
+/// Returns `None` if not every query character is found in order.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let mut query = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut score = 0;
+    let mut previous_matched = false;
+    let mut previous_char: Option<char> = None;
+
+    for c in text.chars().flat_map(char::to_lowercase) {
+        let Some(&wanted) = query.peek() else { break };
+
+        if c == wanted {
+            query.next();
+            score += 1;
+            if previous_matched {
+                score += 4;
+            }
+            if previous_char.is_none_or(|p| !p.is_alphanumeric()) {
+                score += 6;
+            }
+            previous_matched = true;
+        } else {
+            previous_matched = false;
+        }
+        previous_char = Some(c);
+    }
+
+    query.peek().is_none().then_some(score)
+}
+
+/// Score an entry against every whitespace-separated term of `query`.
+///
+/// Each term has to match the title, a keyword or the subtitle; matches in
+/// the title and keywords weigh more than in the subtitle.
+pub fn score_entry(query: &str, entry: &SearchEntry) -> Option<u32> {
+    query.split_whitespace().try_fold(0, |total, term| {
+        let title = fuzzy_score(term, &entry.title).map(|s| s * 2);
+        let keyword = entry
+            .keywords
+            .iter()
+            .filter_map(|keyword| fuzzy_score(term, keyword))
+            .max()
+            .map(|s| s * 2);
+        let subtitle = fuzzy_score(term, &entry.subtitle);
+
+        [title, keyword, subtitle]
+            .into_iter()
+            .flatten()
+            .max()
+            .map(|best| total + best)
+    })
+}
+
+/// Entries matching `query`, best first. An empty query matches everything
+/// in the original order.
+pub fn rank<'a>(query: &str, entries: &'a [SearchEntry]) -> Vec<&'a SearchEntry> {
+    let mut matches: Vec<(u32, &SearchEntry)> = entries
+        .iter()
+        .filter_map(|entry| score_entry(query, entry).map(|score| (score, entry)))
+        .collect();
+    // Stable sort keeps registry order for equal scores
+    matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    matches.into_iter().map(|(_, entry)| entry).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyring() -> SearchEntry {
+        SearchEntry {
+            title: "Fix GnuPG Keyring".to_string(),
+            subtitle: "Recreate the pacman keyring and reinstall archlinux-keyring".to_string(),
+            keywords: &["gpg", "keys", "signature"],
+            page: "servicing_system_tweaks",
+            button: Some("btn_fix_arch_keyring"),
+        }
+    }
+
+    fn drivers() -> SearchEntry {
+        SearchEntry {
+            title: "Drivers".to_string(),
+            subtitle: "Page".to_string(),
+            keywords: &["gpu", "nvidia"],
+            page: "drivers",
+            button: None,
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("kr", "Keyring").is_some());
+        assert!(fuzzy_score("xyz", "Keyring").is_none());
+        assert!(fuzzy_score("", "Keyring").is_some());
+        // Consecutive and word-start matches beat scattered ones
+        assert!(fuzzy_score("key", "Fix Keyring") > fuzzy_score("key", "kernel entry"));
+    }
+
+    #[test]
+    fn test_keywords_match() {
+        let keyring = keyring();
+        assert!(score_entry("signature", &keyring).is_some());
+        assert!(score_entry("gpg fix", &keyring).is_some());
+        assert!(score_entry("gpg nvidia", &keyring).is_none());
+    }
+
+    #[test]
+    fn test_rank_orders_by_score() {
+        let entries = [drivers(), keyring()];
+        assert_eq!(rank("", &entries).len(), 2);
+        assert_eq!(rank("keyring", &entries), vec![&keyring()]);
+        assert_eq!(rank("gpu", &entries)[0].page, "drivers");
+    }
+}
@@ -0,0 +1,136 @@
+//! Quick setup offered by the first-run welcome wizard.
+//!
+//! The choices only describe what to do; nothing runs until the wizard's
+//! final page is confirmed, and then everything runs as one task.
+
+use crate::core::mirrorlist::{Backend, Protocol};
+use crate::core::{actions, package};
+use crate::i18n::{gettext, gettext_noop};
+use crate::ui::task_runner::{Command, CommandSequence};
+
+/// Countries rate-mirrors can start probing from, by ISO 3166 code.
+pub const MIRROR_COUNTRIES: &[(&str, &str)] = &[
+    ("US", gettext_noop("United States")),
+    ("CA", gettext_noop("Canada")),
+    ("BR", gettext_noop("Brazil")),
+    ("GB", gettext_noop("United Kingdom")),
+    ("DE", gettext_noop("Germany")),
+    ("FR", gettext_noop("France")),
+    ("IT", gettext_noop("Italy")),
+    ("ES", gettext_noop("Spain")),
+    ("SE", gettext_noop("Sweden")),
+    ("PL", gettext_noop("Poland")),
+    ("IN", gettext_noop("India")),
+    ("JP", gettext_noop("Japan")),
+    ("AU", gettext_noop("Australia")),
+    ("ZA", gettext_noop("South Africa")),
+];
+
+/// Choices made in the quick setup step.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuickSetup {
+    pub flathub: bool,
+    pub aur_helper: bool,
+    /// Country to rank mirrors from, `None` leaves the mirrorlist alone
+    pub mirror_country: Option<&'static str>,
+}
+
+impl QuickSetup {
+    /// Whether nothing was selected.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Translated one-line descriptions of the selected steps.
+    pub fn summary(&self) -> Vec<String> {
+        let mut steps = Vec::new();
+        if self.flathub {
+            steps.push(gettext("Enable the Flathub repository"));
+        }
+        if self.aur_helper {
+            steps.push(gettext("Install the paru AUR helper"));
+        }
+        if let Some(name) = self.mirror_country.and_then(country_name) {
+            steps.push(crate::i18n::fill(
+                &gettext("Rank mirrors starting from {}"),
+                &[&gettext(name)],
+            ));
+        }
+        steps
+    }
+
+    /// All selected steps as a single command sequence.
+    pub fn commands(&self) -> CommandSequence {
+        let mut commands = CommandSequence::new();
+
+        if self.flathub {
+            commands = commands.extend(package::ensure_flathub_sequence());
+        }
+
+        if self.aur_helper {
+            // paru is packaged in the XeroLinux and Chaotic-AUR repositories
+            commands = commands.then(
+                Command::builder()
+                    .privileged()
+                    .program("pacman")
+                    .args(&["-S", "--needed", "--noconfirm", "paru"])
+                    .description(&gettext("Installing paru..."))
+                    .build(),
+            );
+        }
+
+        if let Some(country) = self.mirror_country {
+            commands = commands.extend(actions::mirrorlist_commands(
+                false,
+                Some(country),
+                Backend::default(),
+                Protocol::default(),
+            ));
+        }
+
+        commands
+    }
+}
+
+/// Untranslated name of a country in [`MIRROR_COUNTRIES`].
+fn country_name(code: &str) -> Option<&'static str> {
+    MIRROR_COUNTRIES
+        .iter()
+        .find(|(country, _)| *country == code)
+        .map(|(_, name)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_setup_runs_nothing() {
+        let setup = QuickSetup::default();
+        assert!(setup.is_empty());
+        assert!(setup.summary().is_empty());
+        assert!(setup.commands().is_empty());
+    }
+
+    #[test]
+    fn test_choices_compose_into_one_sequence() {
+        let setup = QuickSetup {
+            flathub: true,
+            aur_helper: false,
+            mirror_country: Some("DE"),
+        };
+        assert!(!setup.is_empty());
+        assert_eq!(setup.summary().len(), 2);
+        assert!(!setup.commands().is_empty());
+    }
+
+    #[test]
+    fn test_mirror_country_codes() {
+        for (code, _) in MIRROR_COUNTRIES {
+            assert_eq!(code.len(), 2);
+            assert!(code.chars().all(|c| c.is_ascii_uppercase()));
+        }
+        assert_eq!(country_name("JP"), Some("Japan"));
+        assert_eq!(country_name("XX"), None);
+    }
+}
@@ -0,0 +1,273 @@
+//! System report shown on the system information page.
+//!
+//! Each item is probed on its own, so a missing tool or unreadable file only
+//! leaves that item empty. The plain text rendering is what users paste when
+//! asking for help, so it stays in English.
+
+use crate::config;
+use crate::core::distro::{self, Desktop, SessionType};
+use crate::core::download::format_bytes;
+use crate::core::hardware::{self, SmartHealth};
+use crate::core::system_check;
+use crate::i18n::gettext_noop;
+use log::warn;
+use std::fmt::Write as _;
+
+/// One labeled value; `None` if it could not be determined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub label: &'static str,
+    pub value: Option<String>,
+}
+
+/// A titled group of items.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub title: &'static str,
+    pub items: Vec<Item>,
+}
+
+/// Everything the system information page shows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemReport {
+    pub sections: Vec<Section>,
+}
+
+/// First line of a report, like `Xero Toolkit 0.2.0 crash report`.
+pub fn heading(kind: &str) -> String {
+    format!(
+        "{} {} {}",
+        config::app_info::NAME,
+        config::app_info::VERSION,
+        kind
+    )
+}
+
+impl SystemReport {
+    /// Plain text for pasting into a support request.
+    pub fn render(&self) -> String {
+        let mut report = heading("system report");
+        report.push('\n');
+        for section in &self.sections {
+            let _ = writeln!(report, "\n--- {} ---", section.title);
+            for item in &section.items {
+                let _ = writeln!(
+                    report,
+                    "{}: {}",
+                    item.label,
+                    item.value.as_deref().unwrap_or("unavailable")
+                );
+            }
+        }
+        report
+    }
+}
+
+impl Item {
+    fn new(label: &'static str, value: Option<String>) -> Self {
+        Self { label, value }
+    }
+}
+
+/// Log why an item is missing and drop the error.
+fn tolerate<T>(what: &str, result: anyhow::Result<T>) -> Option<T> {
+    result
+        .map_err(|e| warn!("Failed to detect {}: {:#}", what, e))
+        .ok()
+}
+
+/// Probe the system. Blocks on external commands, so run it off the main
+/// thread.
+pub fn probe() -> SystemReport {
+    let mut sections = vec![
+        system_section(),
+        cpu_section(),
+        gpu_section(),
+        memory_section(),
+        disk_section(),
+    ];
+    sections.extend(battery_section());
+    SystemReport { sections }
+}
+
+fn system_section() -> Section {
+    let kernel = std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .map(|release| release.trim().to_string())
+        .ok();
+    // The version tells releases apart, rolling ones have none
+    let distribution = match distro::os_release() {
+        Some(os_release) => Some(match &os_release.version_id {
+            Some(version) => format!("{} {}", os_release.display_name(), version),
+            None => os_release.display_name().to_string(),
+        }),
+        None => system_check::get_distribution_name(),
+    };
+    let session = distro::session();
+    let desktop = (session.desktop != Desktop::Unknown).then(|| {
+        let mut details = Vec::new();
+        if session.session_type != SessionType::Unknown {
+            details.push(session.session_type.name());
+        }
+        details.extend(session.compositor.as_deref());
+        if details.is_empty() {
+            session.desktop.name()
+        } else {
+            format!("{} ({})", session.desktop.name(), details.join(", "))
+        }
+    });
+
+    Section {
+        title: gettext_noop("System"),
+        items: vec![
+            Item::new(gettext_noop("Distribution"), distribution),
+            Item::new(gettext_noop("Kernel"), kernel),
+            Item::new(gettext_noop("Desktop"), desktop),
+        ],
+    }
+}
+
+fn cpu_section() -> Section {
+    let cpu = hardware::detect_cpu();
+    let threads = cpu.as_ref().map(|cpu| match cpu.cores {
+        Some(cores) => format!("{} cores, {} threads", cores, cpu.threads),
+        None => format!("{} threads", cpu.threads),
+    });
+
+    Section {
+        title: gettext_noop("Processor"),
+        items: vec![
+            Item::new(
+                gettext_noop("Model"),
+                cpu.as_ref().and_then(|cpu| cpu.model_name.clone()),
+            ),
+            Item::new(gettext_noop("Cores"), threads),
+            Item::new(gettext_noop("Governor"), hardware::cpu_governor()),
+        ],
+    }
+}
+
+fn gpu_section() -> Section {
+    let items = match tolerate("GPUs", hardware::detect_gpus()) {
+        Some(gpus) if !gpus.is_empty() => gpus
+            .into_iter()
+            .map(|gpu| {
+                let driver = gpu.driver.as_deref().unwrap_or("no driver");
+                Item::new(
+                    gettext_noop("GPU"),
+                    Some(format!("{} ({})", gpu.name, driver)),
+                )
+            })
+            .collect(),
+        _ => vec![Item::new(gettext_noop("GPU"), None)],
+    };
+    Section {
+        title: gettext_noop("Graphics"),
+        items,
+    }
+}
+
+fn memory_section() -> Section {
+    let memory = tolerate("memory", hardware::memory_info());
+    let used = memory.map(|memory| {
+        let percent = memory.used() * 100 / memory.total.max(1);
+        format!("{} ({}%)", format_bytes(memory.used() * 1024), percent)
+    });
+
+    Section {
+        title: gettext_noop("Memory"),
+        items: vec![
+            Item::new(
+                gettext_noop("Total"),
+                memory.map(|memory| format_bytes(memory.total * 1024)),
+            ),
+            Item::new(gettext_noop("Used"), used),
+        ],
+    }
+}
+
+fn disk_section() -> Section {
+    let items = match tolerate("disks", hardware::detect_disks()) {
+        Some(disks) if !disks.is_empty() => disks
+            .into_iter()
+            .map(|disk| {
+                let smart = match hardware::smart_health(&disk) {
+                    Some(SmartHealth::Passed) => "SMART passed",
+                    Some(SmartHealth::Failed) => "SMART FAILED",
+                    None => "SMART unavailable",
+                };
+                let mut value = disk.name.clone();
+                if let Some(model) = &disk.model {
+                    value.push_str(&format!(" · {}", model));
+                }
+                value.push_str(&format!(
+                    " · {} · {} · {}",
+                    format_bytes(disk.size),
+                    if disk.rotational { "HDD" } else { "SSD" },
+                    smart
+                ));
+                Item::new(gettext_noop("Disk"), Some(value))
+            })
+            .collect(),
+        _ => vec![Item::new(gettext_noop("Disk"), None)],
+    };
+    Section {
+        title: gettext_noop("Storage"),
+        items,
+    }
+}
+
+/// `None` on machines without a battery.
+fn battery_section() -> Option<Section> {
+    let supplies = tolerate("batteries", hardware::power_supplies())?;
+    let items: Vec<Item> = supplies
+        .iter()
+        .filter(|supply| supply.is_system_battery())
+        .map(|battery| {
+            let mut value = battery.name.clone();
+            if let Some(health) = battery.health_percent() {
+                value.push_str(&format!(" · {}% health", health));
+            }
+            if let Some(cycles) = battery.cycle_count {
+                value.push_str(&format!(" · {} cycles", cycles));
+            }
+            Item::new(gettext_noop("Battery"), Some(value))
+        })
+        .collect();
+
+    (!items.is_empty()).then_some(Section {
+        title: gettext_noop("Battery"),
+        items,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let report = SystemReport {
+            sections: vec![
+                Section {
+                    title: "System",
+                    items: vec![
+                        Item::new("Kernel", Some("6.10.10-arch1-1".to_string())),
+                        Item::new("Desktop", None),
+                    ],
+                },
+                Section {
+                    title: "Graphics",
+                    items: vec![Item::new("GPU", Some("Intel UHD (i915)".to_string()))],
+                },
+            ],
+        };
+        assert_eq!(
+            report.render(),
+            format!(
+                "{}\n\n--- System ---\nKernel: 6.10.10-arch1-1\nDesktop: unavailable\n\
+                 \n--- Graphics ---\nGPU: Intel UHD (i915)\n",
+                heading("system report")
+            )
+        );
+    }
+}
@@ -1,9 +1,143 @@
 //! System dependency checks and validation.
 
-use crate::ui::utils::extract_widget;
+use super::distro::{self, Desktop, OsRelease};
+use crate::i18n::{self, gettext};
+use crate::ui::utils::{builder_from_resource, expect_widget};
 use gtk4::prelude::*;
-use gtk4::{ApplicationWindow, Builder, Button, Label};
+use gtk4::{ApplicationWindow, Button, Label};
 use log::{error, info, warn};
+use std::sync::OnceLock;
+
+/// Environment detected at startup.
+static ENVIRONMENT: OnceLock<Environment> = OnceLock::new();
+
+/// Something actions need from the system they run on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// pacman manages the packages of this system
+    Pacman,
+    /// Flatpaks can be installed
+    Flatpak,
+    /// systemd units can be enabled and started
+    Services,
+    /// Commands can run as root through pkexec
+    Escalate,
+}
+
+/// The system the toolkit runs on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Environment {
+    /// Container engine, like `podman` or `docker`, when running inside one
+    pub container: Option<String>,
+    /// Whether os-release names Arch Linux or a derivative
+    pub arch_based: bool,
+    pub has_pacman: bool,
+    pub has_flatpak: bool,
+    pub has_systemd: bool,
+    pub has_pkexec: bool,
+}
+
+impl Environment {
+    /// Inspect the running system.
+    pub fn detect() -> Self {
+        // Exits with 1 and prints "none" outside of a container
+        let virt = super::exec::capture(
+            "systemd-detect-virt",
+            &["--container"],
+            super::exec::Options {
+                timeout: std::time::Duration::from_secs(2),
+                ..Default::default()
+            },
+        )
+        .ok()
+        .map(|captured| captured.stdout);
+        let containerenv = std::fs::read_to_string("/run/.containerenv").ok();
+
+        let environment = Self {
+            container: detect_container(
+                virt.as_deref(),
+                containerenv.as_deref(),
+                std::path::Path::new("/.dockerenv").exists(),
+            ),
+            arch_based: distro::os_release().is_some_and(OsRelease::is_arch_based),
+            has_pacman: super::aur::is_executable_in_path("pacman"),
+            has_flatpak: super::aur::is_executable_in_path("flatpak"),
+            // Created by systemd when it runs as init
+            has_systemd: std::path::Path::new("/run/systemd/system").is_dir(),
+            has_pkexec: super::aur::is_executable_in_path("pkexec"),
+        };
+        info!("Detected environment: {:?}", environment);
+        environment
+    }
+
+    /// Why `capability` is unavailable, or `None` if it is available.
+    pub fn missing_reason(&self, capability: Capability) -> Option<String> {
+        if let Some(engine) = &self.container {
+            return Some(i18n::fill(
+                &gettext("Not available inside a container ({})"),
+                &[engine],
+            ));
+        }
+        match capability {
+            Capability::Pacman if !self.arch_based => {
+                Some(gettext("Needs an Arch-based distribution"))
+            }
+            Capability::Pacman if !self.has_pacman => Some(gettext("pacman is not installed")),
+            Capability::Flatpak if !self.has_flatpak => Some(gettext("Flatpak is not installed")),
+            Capability::Services if !self.has_systemd => Some(gettext("systemd is not running")),
+            Capability::Escalate if !self.has_pkexec => Some(gettext("pkexec is not installed")),
+            _ => None,
+        }
+    }
+
+    /// Whether `capability` is available.
+    pub fn has(&self, capability: Capability) -> bool {
+        self.missing_reason(capability).is_none()
+    }
+
+    /// Whether every capability in `required` is available.
+    pub fn has_all(&self, required: &[Capability]) -> bool {
+        required.iter().all(|capability| self.has(*capability))
+    }
+}
+
+/// The environment, detected on first use.
+pub fn environment() -> &'static Environment {
+    ENVIRONMENT.get_or_init(Environment::detect)
+}
+
+/// Whether KDE Plasma runs, or is installed for a session started
+/// elsewhere, like from a TTY.
+pub fn has_plasma() -> bool {
+    distro::session().desktop == Desktop::Plasma || super::is_package_installed("plasma-workspace")
+}
+
+/// Container engine from `systemd-detect-virt --container` output, the
+/// contents of `/run/.containerenv` and whether `/.dockerenv` exists.
+fn detect_container(
+    virt: Option<&str>,
+    containerenv: Option<&str>,
+    dockerenv: bool,
+) -> Option<String> {
+    if let Some(virt) = virt
+        .map(str::trim)
+        .filter(|v| !v.is_empty() && *v != "none")
+    {
+        return Some(virt.to_string());
+    }
+    if let Some(content) = containerenv {
+        // Like `engine="podman-5.2.2"`, empty when the runtime hides it
+        let engine = content
+            .lines()
+            .find_map(|line| line.strip_prefix("engine="))
+            .map(|engine| engine.trim_matches('"'))
+            .and_then(|engine| engine.split('-').next())
+            .filter(|engine| !engine.is_empty())
+            .unwrap_or("podman");
+        return Some(engine.to_string());
+    }
+    dockerenv.then(|| "docker".to_string())
+}
 
 /// Result of dependency check containing missing dependencies.
 #[derive(Debug, Clone)]
@@ -44,10 +178,10 @@ impl DependencyCheckResult {
         let mut hints = Vec::new();
 
         if self.flatpak_missing {
-            hints.push("Install flatpak: <tt>sudo pacman -S flatpak</tt>");
+            hints.push(gettext("Install flatpak: <tt>sudo pacman -S flatpak</tt>"));
         }
         if self.aur_helper_missing {
-            hints.push("AUR Helper repositories:\n• Paru: <a href=\"https://github.com/Morganamilo/paru\">https://github.com/Morganamilo/paru</a>\n• Yay: <a href=\"https://github.com/Jguer/yay\">https://github.com/Jguer/yay</a>");
+            hints.push(gettext("AUR Helper repositories:\n• Paru: <a href=\"https://github.com/Morganamilo/paru\">https://github.com/Morganamilo/paru</a>\n• Yay: <a href=\"https://github.com/Jguer/yay\">https://github.com/Jguer/yay</a>"));
         }
 
         if hints.is_empty() {
@@ -105,81 +239,63 @@ fn check_aur_helper() -> bool {
     false
 }
 
-/// Get distribution name from os-release files.
+/// Get distribution name from os-release, or lsb-release without it.
 pub fn get_distribution_name() -> Option<String> {
-    use std::fs;
-
-    // Try /etc/os-release first (most common)
-    if let Ok(content) = fs::read_to_string("/etc/os-release") {
-        if let Some(name) = parse_os_release_name(&content) {
-            return Some(name);
-        }
-    }
-
-    // Fallback to /usr/lib/os-release
-    if let Ok(content) = fs::read_to_string("/usr/lib/os-release") {
-        if let Some(name) = parse_os_release_name(&content) {
-            return Some(name);
-        }
+    if let Some(os_release) = distro::os_release() {
+        return Some(os_release.display_name().to_string());
     }
 
-    // Fallback to /etc/lsb-release
-    if let Ok(content) = fs::read_to_string("/etc/lsb-release") {
-        for line in content.lines() {
-            if line.starts_with("DISTRIB_ID=") {
-                let name = line
-                    .trim_start_matches("DISTRIB_ID=")
-                    .trim_matches('"')
-                    .to_string();
-                if !name.is_empty() {
-                    return Some(name);
-                }
-            }
-        }
-    }
-
-    None
+    let content = std::fs::read_to_string("/etc/lsb-release").ok()?;
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("DISTRIB_ID="))
+        .map(|name| name.trim_matches('"').to_string())
+        .filter(|name| !name.is_empty())
 }
 
-/// Parse NAME field from os-release content.
-fn parse_os_release_name(content: &str) -> Option<String> {
-    // Try NAME first
-    for line in content.lines() {
-        if line.starts_with("NAME=") {
-            let name = line
-                .trim_start_matches("NAME=")
-                .trim_matches('"')
-                .to_string();
-            if !name.is_empty() {
-                return Some(name);
-            }
-        }
-    }
+/// Check if a user is listed as a member of a group in /etc/group.
+///
+/// Only supplementary membership is considered, which is what `usermod -aG`
+/// manages. The running session may not have picked up a new group yet.
+pub fn is_user_in_group(user: &str, group: &str) -> bool {
+    std::fs::read_to_string("/etc/group")
+        .map(|content| group_has_member(&content, group, user))
+        .unwrap_or(false)
+}
 
-    // Fallback to PRETTY_NAME
-    for line in content.lines() {
-        if line.starts_with("PRETTY_NAME=") {
-            let name = line
-                .trim_start_matches("PRETTY_NAME=")
-                .trim_matches('"')
-                .to_string();
-            if !name.is_empty() {
-                return Some(name);
-            }
-        }
-    }
+/// Check whether `group` lists `user` as a member in group(5) content.
+fn group_has_member(content: &str, group: &str, user: &str) -> bool {
+    content.lines().any(|line| {
+        let mut fields = line.split(':');
+        fields.next() == Some(group)
+            && fields
+                .nth(2)
+                .is_some_and(|members| members.split(',').any(|m| m.trim() == user))
+    })
+}
 
-    // Final fallback to ID
-    for line in content.lines() {
-        if line.starts_with("ID=") {
-            let name = line.trim_start_matches("ID=").trim_matches('"').to_string();
-            if !name.is_empty() {
-                return Some(name);
-            }
-        }
+/// Look up a user's login shell through NSS (`getent passwd`).
+pub fn login_shell(user: &str) -> Option<String> {
+    let output = std::process::Command::new("getent")
+        .args(["passwd", user])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
     }
+    parse_passwd_shell(&String::from_utf8_lossy(&output.stdout))
+}
 
-    None
+/// Extract the shell field from a passwd(5) entry.
+fn parse_passwd_shell(entry: &str) -> Option<String> {
+    entry
+        .lines()
+        .next()?
+        .split(':')
+        .nth(6)
+        .map(str::trim)
+        .filter(|shell| !shell.is_empty())
+        .map(str::to_string)
 }
 
 /// Perform all dependency checks and return results.
@@ -208,36 +324,33 @@ pub fn check_dependencies() -> DependencyCheckResult {
 /// This is a warning, not an error - the app continues after dismissal.
 pub fn show_generic_distro_notice(
     main_window: &ApplicationWindow,
-    config: std::rc::Rc<std::cell::RefCell<crate::config::user::Config>>,
+    config: crate::config::user::SharedConfig,
     distro_name: String,
 ) {
-    use std::rc::Rc;
-
     warn!("Showing generic distribution notice dialog");
 
-    let builder = Builder::from_resource(crate::config::resources::dialogs::XEROLINUX_CHECK);
+    let builder = builder_from_resource(crate::config::resources::dialogs::XEROLINUX_CHECK);
 
-    let notice_window: gtk4::Window = extract_widget(&builder, "xerolinux_error_window");
+    let notice_window: gtk4::Window = expect_widget(&builder, "xerolinux_error_window");
 
-    let distro_label: Label = extract_widget(&builder, "distro_label");
-    let dismiss_checkbox: gtk4::CheckButton = extract_widget(&builder, "dismiss_checkbox");
-    let ok_button: Button = extract_widget(&builder, "ok_button");
+    let distro_label: Label = expect_widget(&builder, "distro_label");
+    let dismiss_checkbox: gtk4::CheckButton = expect_widget(&builder, "dismiss_checkbox");
+    let ok_button: Button = expect_widget(&builder, "ok_button");
 
-    distro_label.set_label(&format!("Current distribution: <b>{}</b>", distro_name));
+    distro_label.set_label(&i18n::fill_markup(
+        &gettext("Current distribution: <b>{}</b>"),
+        &[&distro_name],
+    ));
 
     notice_window.set_transient_for(Some(main_window));
 
-    let config_clone = Rc::clone(&config);
+    let config_clone = config.clone();
     let dismiss_checkbox_clone = dismiss_checkbox.clone();
     let notice_window_clone = notice_window.clone();
     ok_button.connect_clicked(move |_| {
         if dismiss_checkbox_clone.is_active() {
-            // Update in-memory flag only; persistence happens on app shutdown.
-            config_clone
-                .borrow_mut()
-                .warnings
-                .dismissed_generic_distro_notice = true;
-            info!("User dismissed generic distro notice (in-memory); will be saved on shutdown");
+            config_clone.update(|config| config.warnings.dismissed_generic_distro_notice = true);
+            info!("User dismissed generic distro notice");
         }
         notice_window_clone.close();
     });
@@ -252,15 +365,15 @@ pub fn show_dependency_error_dialog(
 ) {
     error!("Showing dependency error dialog");
 
-    let builder = Builder::from_resource(crate::config::resources::dialogs::DEPENDENCY_ERROR);
+    let builder = builder_from_resource(crate::config::resources::dialogs::DEPENDENCY_ERROR);
 
-    let error_window: gtk4::Window = extract_widget(&builder, "dependency_error_window");
+    let error_window: gtk4::Window = expect_widget(&builder, "dependency_error_window");
 
-    let missing_deps_label: Label = extract_widget(&builder, "missing_deps_label");
+    let missing_deps_label: Label = expect_widget(&builder, "missing_deps_label");
 
-    let install_hint_label: Label = extract_widget(&builder, "install_hint_label");
+    let install_hint_label: Label = expect_widget(&builder, "install_hint_label");
 
-    let exit_button: Button = extract_widget(&builder, "exit_button");
+    let exit_button: Button = expect_widget(&builder, "exit_button");
 
     missing_deps_label.set_label(&check_result.format_missing_list());
 
@@ -277,3 +390,101 @@ pub fn show_dependency_error_dialog(
 
     error_window.present();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_container() {
+        assert_eq!(detect_container(Some("none\n"), None, false), None);
+        assert_eq!(detect_container(None, None, false), None);
+        assert_eq!(
+            detect_container(Some("podman\n"), None, false).as_deref(),
+            Some("podman")
+        );
+        // Distrobox on podman without systemd-detect-virt
+        let containerenv = "engine=\"podman-5.2.2\"\nname=\"arch-box\"\nrootless=1\n";
+        assert_eq!(
+            detect_container(None, Some(containerenv), false).as_deref(),
+            Some("podman")
+        );
+        assert_eq!(
+            detect_container(Some("none"), Some(""), false).as_deref(),
+            Some("podman")
+        );
+        assert_eq!(
+            detect_container(None, None, true).as_deref(),
+            Some("docker")
+        );
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let host = Environment {
+            container: None,
+            arch_based: true,
+            has_pacman: true,
+            has_flatpak: true,
+            has_systemd: true,
+            has_pkexec: true,
+        };
+        for capability in [
+            Capability::Pacman,
+            Capability::Flatpak,
+            Capability::Services,
+            Capability::Escalate,
+        ] {
+            assert!(host.has(capability));
+            let boxed = Environment {
+                container: Some("podman".to_string()),
+                ..host.clone()
+            };
+            assert!(!boxed.has(capability));
+        }
+
+        let fedora = Environment {
+            arch_based: OsRelease::parse("NAME=\"Fedora Linux\"\nID=fedora\n").is_arch_based(),
+            has_pacman: false,
+            ..host.clone()
+        };
+        assert!(!fedora.has(Capability::Pacman));
+        assert!(fedora.has(Capability::Flatpak));
+
+        let minimal = Environment {
+            has_flatpak: false,
+            has_pkexec: false,
+            ..host
+        };
+        assert!(minimal.has(Capability::Pacman));
+        assert!(!minimal.has(Capability::Flatpak));
+        assert!(!minimal.has(Capability::Escalate));
+    }
+
+    const GROUP_FIXTURE: &str = "\
+root:x:0:root
+wheel:x:998:alice,bob
+docker:x:969:
+libvirt:x:962:carol
+";
+
+    #[test]
+    fn test_group_has_member() {
+        assert!(group_has_member(GROUP_FIXTURE, "wheel", "alice"));
+        assert!(group_has_member(GROUP_FIXTURE, "wheel", "bob"));
+        assert!(!group_has_member(GROUP_FIXTURE, "wheel", "carol"));
+        assert!(!group_has_member(GROUP_FIXTURE, "docker", "alice"));
+        assert!(!group_has_member(GROUP_FIXTURE, "missing", "alice"));
+        assert!(!group_has_member(GROUP_FIXTURE, "libvirt", "caro"));
+    }
+
+    #[test]
+    fn test_parse_passwd_shell() {
+        assert_eq!(
+            parse_passwd_shell("alice:x:1000:1000:Alice:/home/alice:/bin/zsh\n").as_deref(),
+            Some("/bin/zsh")
+        );
+        assert_eq!(parse_passwd_shell("bob:x:1001:1001::/home/bob:"), None);
+        assert_eq!(parse_passwd_shell(""), None);
+    }
+}
@@ -0,0 +1,302 @@
+//! Systemd unit queries.
+//!
+//! Parses the JSON output of `systemctl list-unit-files` / `list-units` and
+//! the key=value output of `systemctl show` so pages can display unit state.
+//! User units of the session (like PipeWire) are queried with `--user`.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::process::Command;
+
+/// Entry from `systemctl list-unit-files --output=json`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct UnitFile {
+    pub unit_file: String,
+    pub state: String,
+}
+
+/// Entry from `systemctl list-units --output=json`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Unit {
+    pub unit: String,
+    pub load: String,
+    pub active: String,
+    pub sub: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Entry from `systemctl list-timers --output=json`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Timer {
+    pub unit: String,
+    /// Next elapse in microseconds since the epoch, `None` if not scheduled
+    pub next: Option<u64>,
+    /// Last run in microseconds since the epoch
+    pub last: Option<u64>,
+}
+
+/// Selected properties from `systemctl show`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnitDetails {
+    pub id: String,
+    pub description: String,
+    pub active_state: String,
+    pub unit_file_state: String,
+    pub state_change: Option<String>,
+}
+
+impl UnitDetails {
+    /// Whether the unit is currently running.
+    pub fn is_active(&self) -> bool {
+        is_active_state(&self.active_state)
+    }
+
+    /// Whether the unit starts automatically.
+    pub fn is_enabled(&self) -> bool {
+        matches!(
+            self.unit_file_state.as_str(),
+            "enabled" | "enabled-runtime" | "alias"
+        )
+    }
+
+    /// Whether the unit can be enabled/disabled at all.
+    pub fn is_toggleable(&self) -> bool {
+        !matches!(
+            self.unit_file_state.as_str(),
+            "static" | "masked" | "generated" | "transient" | ""
+        )
+    }
+}
+
+/// Whether an `ActiveState` value means the unit is running.
+fn is_active_state(state: &str) -> bool {
+    matches!(state, "active" | "reloading" | "activating")
+}
+
+/// Properties requested from `systemctl show`.
+const SHOW_PROPERTIES: &str = "Id,Description,ActiveState,UnitFileState,StateChangeTimestamp";
+
+/// Parse `systemctl list-unit-files --output=json` output.
+pub fn parse_unit_files(json: &str) -> Result<Vec<UnitFile>> {
+    serde_json::from_str(json).context("Failed to parse unit file list")
+}
+
+/// Parse `systemctl list-units --output=json` output.
+pub fn parse_units(json: &str) -> Result<Vec<Unit>> {
+    serde_json::from_str(json).context("Failed to parse unit list")
+}
+
+/// Parse `systemctl list-timers --output=json` output.
+pub fn parse_timers(json: &str) -> Result<Vec<Timer>> {
+    serde_json::from_str(json).context("Failed to parse timer list")
+}
+
+/// Parse `systemctl show` output for one or more units.
+///
+/// Multiple units are separated by blank lines; units that systemd does
+/// not know about (empty `Id`) are skipped.
+pub fn parse_show(output: &str) -> Vec<UnitDetails> {
+    output
+        .split("\n\n")
+        .filter_map(|block| {
+            let mut details = UnitDetails::default();
+            for line in block.lines() {
+                let Some((key, value)) = line.split_once('=') else {
+                    continue;
+                };
+                match key {
+                    "Id" => details.id = value.to_string(),
+                    "Description" => details.description = value.to_string(),
+                    "ActiveState" => details.active_state = value.to_string(),
+                    "UnitFileState" => details.unit_file_state = value.to_string(),
+                    "StateChangeTimestamp" if !value.is_empty() => {
+                        details.state_change = Some(value.to_string())
+                    }
+                    _ => {}
+                }
+            }
+            (!details.id.is_empty()).then_some(details)
+        })
+        .collect()
+}
+
+/// Parse `systemctl is-active` output, one state per line in the order the
+/// units were queried, into the units that are not running.
+pub fn parse_inactive<'a>(units: &[&'a str], output: &str) -> Vec<&'a str> {
+    let mut states = output.lines().map(str::trim);
+    units
+        .iter()
+        .copied()
+        .filter(|_| !states.next().is_some_and(is_active_state))
+        .collect()
+}
+
+/// List all installed service unit files.
+pub fn list_service_unit_files() -> Result<Vec<UnitFile>> {
+    let stdout = systemctl(&[
+        "list-unit-files",
+        "--type=service",
+        "--no-pager",
+        "--output=json",
+    ])?;
+    parse_unit_files(&stdout)
+}
+
+/// List units currently in the failed state.
+pub fn list_failed_units() -> Result<Vec<Unit>> {
+    let stdout = systemctl(&["list-units", "--failed", "--no-pager", "--output=json"])?;
+    parse_units(&stdout)
+}
+
+/// Schedule of a timer unit, `None` if it is not loaded.
+pub fn timer(unit: &str) -> Result<Option<Timer>> {
+    let stdout = systemctl(&["list-timers", "--all", "--no-pager", "--output=json", unit])?;
+    Ok(parse_timers(&stdout)?
+        .into_iter()
+        .find(|timer| timer.unit == unit))
+}
+
+/// Query details for the given units in a single `systemctl show` call.
+pub fn unit_details(units: &[&str]) -> Result<Vec<UnitDetails>> {
+    if units.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut args = vec!["show", "--no-pager", "-p", SHOW_PROPERTIES];
+    args.extend_from_slice(units);
+    Ok(parse_show(&systemctl(&args)?))
+}
+
+/// User units of the session that are not running.
+///
+/// Runs as the user; the user manager is not reachable through pkexec.
+pub fn inactive_user_units<'a>(units: &[&'a str]) -> Result<Vec<&'a str>> {
+    let output = Command::new("systemctl")
+        .args(["--user", "is-active"])
+        .args(units)
+        .output()
+        .context("Failed to run systemctl")?;
+
+    // is-active exits non-zero when any unit is inactive, so only a missing
+    // answer is an error
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.lines().count() < units.len() {
+        anyhow::bail!(
+            "systemctl --user is-active failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(parse_inactive(units, &stdout))
+}
+
+fn systemctl(args: &[&str]) -> Result<String> {
+    let output = Command::new("systemctl")
+        .args(args)
+        .output()
+        .context("Failed to run systemctl")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "systemctl {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_unit_files() {
+        let json = r#"[
+            {"unit_file":"docker.service","state":"enabled","preset":"disabled"},
+            {"unit_file":"cups.service","state":"disabled","preset":null}
+        ]"#;
+        let files = parse_unit_files(json).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].unit_file, "docker.service");
+        assert_eq!(files[1].state, "disabled");
+    }
+
+    #[test]
+    fn test_parse_units() {
+        let json = r#"[
+            {"unit":"foo.service","load":"loaded","active":"failed","sub":"failed","description":"Foo Daemon"}
+        ]"#;
+        let units = parse_units(json).unwrap();
+        assert_eq!(units[0].unit, "foo.service");
+        assert_eq!(units[0].active, "failed");
+        assert_eq!(units[0].description, "Foo Daemon");
+        assert!(parse_units("[]").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_timers() {
+        let json = r#"[
+            {"next":1760911200000000,"left":172800000000,"last":null,"passed":null,"unit":"xero-maintenance.timer","activates":"xero-maintenance.service"},
+            {"next":null,"left":null,"last":1760300000000000,"passed":1000,"unit":"old.timer","activates":"old.service"}
+        ]"#;
+        let timers = parse_timers(json).unwrap();
+        assert_eq!(timers[0].unit, "xero-maintenance.timer");
+        assert_eq!(timers[0].next, Some(1_760_911_200_000_000));
+        assert_eq!(timers[0].last, None);
+        assert_eq!(timers[1].next, None);
+        assert!(parse_timers("[]").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_show_multiple() {
+        let output = "\
+Id=docker.service
+Description=Docker Application Container Engine
+ActiveState=active
+UnitFileState=enabled
+StateChangeTimestamp=Mon 2025-01-06 10:00:00 CET
+
+Id=cups.service
+Description=CUPS Scheduler
+ActiveState=inactive
+UnitFileState=disabled
+StateChangeTimestamp=
+";
+        let details = parse_show(output);
+        assert_eq!(details.len(), 2);
+        assert!(details[0].is_active());
+        assert!(details[0].is_enabled());
+        assert_eq!(
+            details[0].state_change.as_deref(),
+            Some("Mon 2025-01-06 10:00:00 CET")
+        );
+        assert!(!details[1].is_active());
+        assert!(!details[1].is_enabled());
+        assert!(details[1].is_toggleable());
+        assert_eq!(details[1].state_change, None);
+    }
+
+    #[test]
+    fn test_parse_inactive() {
+        let units = [
+            "pipewire.service",
+            "pipewire-pulse.service",
+            "wireplumber.service",
+        ];
+        assert!(parse_inactive(&units, "active\nactive\nactivating\n").is_empty());
+        assert_eq!(
+            parse_inactive(&units, "active\nfailed\ninactive\n"),
+            ["pipewire-pulse.service", "wireplumber.service"]
+        );
+        // Units without an answer count as not running
+        assert_eq!(parse_inactive(&units, "active\n"), &units[1..]);
+    }
+
+    #[test]
+    fn test_static_units_not_toggleable() {
+        let details = parse_show("Id=foo.service\nUnitFileState=static\n");
+        assert!(!details[0].is_toggleable());
+    }
+}
@@ -0,0 +1,236 @@
+//! Tailscale status queries.
+//!
+//! Parses `tailscale status --json`, which is readable without root once
+//! tailscaled is running.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A node in the tailnet, either this machine or a peer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Node {
+    #[serde(default, rename = "ID")]
+    pub id: String,
+    #[serde(default)]
+    pub host_name: String,
+    #[serde(default, rename = "DNSName")]
+    pub dns_name: String,
+    #[serde(default, rename = "TailscaleIPs")]
+    pub tailscale_ips: Option<Vec<String>>,
+    #[serde(default)]
+    pub online: bool,
+    /// Whether this node is the exit node currently in use.
+    #[serde(default)]
+    pub exit_node: bool,
+    /// Whether this node offers itself as an exit node.
+    #[serde(default)]
+    pub exit_node_option: bool,
+    #[serde(default, rename = "UserID")]
+    pub user_id: u64,
+}
+
+impl Node {
+    /// Short machine name, preferring the MagicDNS label.
+    pub fn name(&self) -> &str {
+        self.dns_name
+            .split('.')
+            .next()
+            .filter(|label| !label.is_empty())
+            .unwrap_or(&self.host_name)
+    }
+
+    /// First IPv4 tailnet address, falling back to any address.
+    pub fn primary_ip(&self) -> Option<&str> {
+        let ips = self.tailscale_ips.as_deref()?;
+        ips.iter()
+            .find(|ip| ip.contains('.'))
+            .or_else(|| ips.first())
+            .map(String::as_str)
+    }
+}
+
+/// Login profile from the `User` map.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct UserProfile {
+    #[serde(default)]
+    pub login_name: String,
+    #[serde(default)]
+    pub display_name: String,
+}
+
+/// Parsed `tailscale status --json` output.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Status {
+    #[serde(default)]
+    pub backend_state: String,
+    #[serde(default, rename = "Self")]
+    pub self_node: Option<Node>,
+    #[serde(default)]
+    pub peer: Option<HashMap<String, Node>>,
+    #[serde(default)]
+    pub user: Option<HashMap<String, UserProfile>>,
+}
+
+impl Status {
+    /// Whether the node is connected to the tailnet.
+    pub fn is_running(&self) -> bool {
+        self.backend_state == "Running"
+    }
+
+    /// Whether the node must authenticate before it can connect.
+    pub fn needs_login(&self) -> bool {
+        matches!(self.backend_state.as_str(), "NeedsLogin" | "NoState")
+    }
+
+    /// Login name of the account this node belongs to.
+    pub fn account(&self) -> Option<&str> {
+        let user_id = self.self_node.as_ref()?.user_id;
+        self.user
+            .as_ref()?
+            .get(&user_id.to_string())
+            .map(|user| user.login_name.as_str())
+            .filter(|name| !name.is_empty())
+    }
+
+    /// This node's primary tailnet address.
+    pub fn own_ip(&self) -> Option<&str> {
+        self.self_node.as_ref()?.primary_ip()
+    }
+
+    /// Peers sorted by name.
+    pub fn peers(&self) -> Vec<&Node> {
+        let mut peers: Vec<&Node> = self.peer.iter().flat_map(|peers| peers.values()).collect();
+        peers.sort_by(|a, b| a.name().cmp(b.name()));
+        peers
+    }
+
+    /// Peers that advertise exit-node capability, sorted by name.
+    pub fn exit_node_options(&self) -> Vec<&Node> {
+        self.peers()
+            .into_iter()
+            .filter(|peer| peer.exit_node_option)
+            .collect()
+    }
+
+    /// Peer currently used as exit node, if any.
+    pub fn current_exit_node(&self) -> Option<&Node> {
+        self.peers().into_iter().find(|peer| peer.exit_node)
+    }
+}
+
+/// Parse `tailscale status --json` output.
+pub fn parse_status(json: &str) -> Result<Status> {
+    serde_json::from_str(json).context("Failed to parse tailscale status")
+}
+
+/// Query the local tailscaled.
+///
+/// `tailscale status` exits non-zero while logged out or stopped but still
+/// prints JSON, so only the output is checked.
+pub fn status() -> Result<Status> {
+    let output = std::process::Command::new("tailscale")
+        .args(["status", "--json"])
+        .output()
+        .context("Failed to run tailscale")?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.trim().is_empty() {
+        anyhow::bail!(
+            "tailscale status failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    parse_status(&stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUNNING: &str = r#"{
+  "Version": "1.78.1",
+  "BackendState": "Running",
+  "Self": {
+    "ID": "n1",
+    "HostName": "xero-desktop",
+    "DNSName": "xero-desktop.tail1234.ts.net.",
+    "TailscaleIPs": ["100.101.102.103", "fd7a:115c:a1e0::1"],
+    "UserID": 4242,
+    "Online": true,
+    "ExitNode": false,
+    "ExitNodeOption": false
+  },
+  "Peer": {
+    "nodekey:aaa": {
+      "ID": "n2",
+      "HostName": "vps",
+      "DNSName": "vps.tail1234.ts.net.",
+      "TailscaleIPs": ["100.64.0.2"],
+      "UserID": 4242,
+      "Online": true,
+      "ExitNode": true,
+      "ExitNodeOption": true
+    },
+    "nodekey:bbb": {
+      "ID": "n3",
+      "HostName": "phone",
+      "DNSName": "phone.tail1234.ts.net.",
+      "TailscaleIPs": ["100.64.0.3"],
+      "UserID": 4242,
+      "Online": false,
+      "ExitNode": false,
+      "ExitNodeOption": false
+    },
+    "nodekey:ccc": {
+      "ID": "n4",
+      "HostName": "home-router",
+      "DNSName": "",
+      "TailscaleIPs": ["100.64.0.4"],
+      "UserID": 4242,
+      "Online": true,
+      "ExitNode": false,
+      "ExitNodeOption": true
+    }
+  },
+  "User": {
+    "4242": {"ID": 4242, "LoginName": "xero@example.com", "DisplayName": "Xero"}
+  }
+}"#;
+
+    #[test]
+    fn test_parse_running_status() {
+        let status = parse_status(RUNNING).unwrap();
+        assert!(status.is_running());
+        assert!(!status.needs_login());
+        assert_eq!(status.account(), Some("xero@example.com"));
+        assert_eq!(status.own_ip(), Some("100.101.102.103"));
+        assert_eq!(status.peers().len(), 3);
+    }
+
+    #[test]
+    fn test_exit_node_options() {
+        let status = parse_status(RUNNING).unwrap();
+        let names: Vec<&str> = status
+            .exit_node_options()
+            .iter()
+            .map(|n| n.name())
+            .collect();
+        assert_eq!(names, vec!["home-router", "vps"]);
+        assert_eq!(status.current_exit_node().map(|n| n.name()), Some("vps"));
+    }
+
+    #[test]
+    fn test_parse_logged_out_status() {
+        let json = r#"{"BackendState":"NeedsLogin","Self":{"ID":"","TailscaleIPs":null},"Peer":null,"User":null}"#;
+        let status = parse_status(json).unwrap();
+        assert!(status.needs_login());
+        assert!(!status.is_running());
+        assert_eq!(status.account(), None);
+        assert_eq!(status.own_ip(), None);
+        assert!(status.peers().is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+//! Checks whether a newer version of the toolkit itself is available.
+//!
+//! The distro repository is asked first since that is where the package is
+//! installed from; the GitHub releases API is the fallback. Failures are
+//! only logged, an unreachable server never bothers the user.
+
+use crate::config;
+use anyhow::{Context, Result};
+use log::{debug, info};
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// GitHub API endpoint for the newest published release.
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/synsejse/xero-toolkit/releases/latest";
+
+/// Minimum time between two online checks.
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+}
+
+/// Seconds since the Unix epoch, as stored in the config.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Whether enough time has passed since `last_check` to ask again.
+pub fn is_due(last_check: Option<u64>, now: u64) -> bool {
+    match last_check {
+        // A clock set backwards also triggers a check
+        Some(last) => now < last || now - last >= CHECK_INTERVAL.as_secs(),
+        None => true,
+    }
+}
+
+/// Version of the installed package, including its pkgrel.
+///
+/// Falls back to the version the binary was built with when the package is
+/// not installed through pacman.
+pub fn installed_version() -> String {
+    pacman_version(&["-Q", config::app_info::NAME])
+        .and_then(|output| output.split_whitespace().nth(1).map(str::to_string))
+        .unwrap_or_else(|| config::app_info::VERSION.to_string())
+}
+
+/// Latest version from the repositories or GitHub, `None` when neither answers.
+pub fn fetch_latest_version() -> Option<String> {
+    if let Some(version) =
+        pacman_version(&["-Si", config::app_info::NAME]).and_then(|info| parse_pacman_info(&info))
+    {
+        debug!(
+            "Repository version of {}: {}",
+            config::app_info::NAME,
+            version
+        );
+        return Some(version);
+    }
+
+    let runtime = tokio::runtime::Runtime::new().ok()?;
+    match runtime.block_on(fetch_github_version()) {
+        Ok(version) => {
+            debug!("Latest GitHub release: {}", version);
+            Some(version)
+        }
+        Err(e) => {
+            info!("Update check failed: {:#}", e);
+            None
+        }
+    }
+}
+
+async fn fetch_github_version() -> Result<String> {
+    let body = reqwest::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .user_agent(config::app_info::NAME)
+        .build()
+        .context("Failed to build HTTP client")?
+        .get(LATEST_RELEASE_URL)
+        .send()
+        .await
+        .context("Failed to fetch latest release")?
+        .error_for_status()
+        .context("GitHub API returned an error")?
+        .text()
+        .await
+        .context("Failed to read response body")?;
+
+    let release: GithubRelease =
+        serde_json::from_str(&body).context("Failed to parse release JSON")?;
+    Ok(release.tag_name.trim_start_matches('v').to_string())
+}
+
+fn pacman_version(args: &[&str]) -> Option<String> {
+    let output = Command::new("pacman")
+        .args(args)
+        .env("LC_ALL", "C")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Extract the `Version` field from `pacman -Si` output.
+pub fn parse_pacman_info(info: &str) -> Option<String> {
+    info.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        (key.trim() == "Version").then(|| value.trim().to_string())
+    })
+}
+
+/// Whether `candidate` is a newer version than `current`.
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    compare_versions(candidate, current) == Ordering::Greater
+}
+
+/// Compare two versions of the form `[epoch:]major.minor.patch[-pre][-pkgrel]`.
+///
+/// A purely numeric suffix is a pacman pkgrel and only breaks ties; any other
+/// suffix is a pre-release, which sorts before the release itself.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    Version::parse(a).cmp(&Version::parse(b))
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Version {
+    epoch: u64,
+    release: Vec<u64>,
+    /// `None` sorts after `Some`, so releases beat their pre-releases
+    pre_release: PreRelease,
+    pkgrel: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct PreRelease(Option<Vec<String>>);
+
+impl Ord for PreRelease {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (&self.0, &other.0) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(a), Some(b)) => {
+                for (a, b) in a.iter().zip(b) {
+                    let order = match (a.parse::<u64>(), b.parse::<u64>()) {
+                        (Ok(a), Ok(b)) => a.cmp(&b),
+                        _ => a.cmp(b),
+                    };
+                    if order != Ordering::Equal {
+                        return order;
+                    }
+                }
+                a.len().cmp(&b.len())
+            }
+        }
+    }
+}
+
+impl PartialOrd for PreRelease {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Version {
+    fn parse(version: &str) -> Self {
+        let version = version.trim().trim_start_matches('v');
+        let (epoch, rest) = match version.split_once(':') {
+            Some((epoch, rest)) => (epoch.parse().unwrap_or(0), rest),
+            None => (0, version),
+        };
+
+        let mut parts: Vec<&str> = rest.split('-').collect();
+        let pkgrel = match parts.last() {
+            Some(last) if parts.len() > 1 && last.chars().all(|c| c.is_ascii_digit()) => {
+                let pkgrel = last.parse().unwrap_or(0);
+                parts.pop();
+                pkgrel
+            }
+            _ => 0,
+        };
+
+        let mut release: Vec<u64> = parts[0]
+            .split('.')
+            .map(|part| {
+                let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+                digits.parse().unwrap_or(0)
+            })
+            .collect();
+        // "1.2" and "1.2.0" are the same release
+        while release.len() > 1 && release.last() == Some(&0) {
+            release.pop();
+        }
+
+        let pre_release = (parts.len() > 1).then(|| {
+            parts[1..]
+                .join("-")
+                .split('.')
+                .map(str::to_string)
+                .collect()
+        });
+
+        Self {
+            epoch,
+            release,
+            pre_release: PreRelease(pre_release),
+            pkgrel,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_release_numbers() {
+        assert!(is_newer("0.2.1", "0.2.0"));
+        assert!(is_newer("0.10.0", "0.9.9"));
+        assert!(is_newer("v1.0.0", "0.9.0"));
+        assert!(!is_newer("0.2.0", "0.2.0"));
+        assert_eq!(compare_versions("1.2", "1.2.0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_compare_pkgrel_and_epoch() {
+        assert!(is_newer("0.2.0-2", "0.2.0-1"));
+        assert!(is_newer("0.2.0-1", "0.2.0"));
+        assert!(is_newer("0.2.1-1", "0.2.0-10"));
+        assert!(is_newer("1:0.1.0-1", "0.9.0-1"));
+    }
+
+    #[test]
+    fn test_pre_release_sorts_before_release() {
+        assert!(is_newer("0.3.0", "0.3.0-rc.1"));
+        assert!(is_newer("0.3.0-rc.2", "0.3.0-rc.1"));
+        assert!(is_newer("0.3.0-rc.10", "0.3.0-rc.2"));
+        assert!(is_newer("0.3.0-rc.1-2", "0.3.0-rc.1-1"));
+        assert!(is_newer("0.3.0-beta", "0.3.0-alpha"));
+    }
+
+    #[test]
+    fn test_parse_pacman_info() {
+        let info = "Repository      : xerolinux\n\
+                    Name            : xero-toolkit\n\
+                    Version         : 0.2.1-1\n\
+                    Description     : GTK4 GUI\n";
+        assert_eq!(parse_pacman_info(info).as_deref(), Some("0.2.1-1"));
+        assert_eq!(parse_pacman_info("error: package not found"), None);
+    }
+
+    #[test]
+    fn test_check_is_due_once_a_day() {
+        let day = CHECK_INTERVAL.as_secs();
+        assert!(is_due(None, 1000));
+        assert!(!is_due(Some(1000), 1000 + day - 1));
+        assert!(is_due(Some(1000), 1000 + day));
+        assert!(is_due(Some(1000), 500));
+    }
+}
@@ -0,0 +1,290 @@
+//! Wallpaper index, thumbnail cache and download locations.
+//!
+//! The index is a JSON list of wallpapers published in the XeroLinux
+//! wallpapers repository. The last fetched copy and every thumbnail are
+//! kept under `~/.cache/xero-toolkit/wallpapers`, so the browser opens
+//! instantly and still works offline.
+
+use crate::core::download::download_file;
+use anyhow::{Context, Result};
+use log::{info, warn};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Index of the wallpapers available for download.
+const INDEX_URL: &str =
+    "https://raw.githubusercontent.com/xerolinux/xero-wallpapers/main/index.json";
+
+/// Cached copy of the index, relative to the cache directory.
+const INDEX_CACHE: &str = "index.json";
+
+/// Directory of cached thumbnails, relative to the cache directory.
+const THUMBNAIL_DIR: &str = "thumbnails";
+
+/// Program that sets the Plasma wallpaper.
+pub const APPLY_PROGRAM: &str = "plasma-apply-wallpaperimage";
+
+/// Package providing [`APPLY_PROGRAM`].
+pub const APPLY_PACKAGE: &str = "plasma-workspace";
+
+/// A wallpaper listed in the index.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Wallpaper {
+    pub name: String,
+    /// Full-size image
+    pub url: String,
+    /// Smaller preview image; the full image is used when missing
+    #[serde(default)]
+    pub thumbnail: Option<String>,
+}
+
+impl Wallpaper {
+    /// URL of the preview image.
+    pub fn thumbnail_url(&self) -> &str {
+        self.thumbnail.as_deref().unwrap_or(&self.url)
+    }
+
+    /// File name to save the full image under.
+    pub fn file_name(&self) -> String {
+        let last = url_path(&self.url).rsplit('/').next().unwrap_or_default();
+        let name: String = last
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        if name.trim_matches(['.', '_']).is_empty() {
+            format!("{}.{}", cache_key(&self.url), extension(&self.url))
+        } else {
+            name
+        }
+    }
+}
+
+/// Parse the wallpaper index.
+///
+/// Entries without a name or with a non-HTTP(S) URL are skipped.
+pub fn parse_index(json: &str) -> Result<Vec<Wallpaper>> {
+    let wallpapers: Vec<Wallpaper> =
+        serde_json::from_str(json).context("Failed to parse wallpaper index")?;
+
+    Ok(wallpapers
+        .into_iter()
+        .filter(|wallpaper| {
+            !wallpaper.name.trim().is_empty()
+                && is_http(&wallpaper.url)
+                && wallpaper.thumbnail.as_deref().is_none_or(is_http)
+        })
+        .collect())
+}
+
+fn is_http(url: &str) -> bool {
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+/// URL without scheme, query or fragment.
+fn url_path(url: &str) -> &str {
+    let url = url.split(['?', '#']).next().unwrap_or(url);
+    url.split_once("://").map_or(url, |(_, rest)| rest)
+}
+
+/// Image extension of `url`, `img` when it has none.
+fn extension(url: &str) -> String {
+    let last = url_path(url).rsplit('/').next().unwrap_or_default();
+    match last.rsplit_once('.') {
+        Some((_, ext)) if !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric()) => {
+            ext.to_ascii_lowercase()
+        }
+        _ => "img".to_string(),
+    }
+}
+
+/// Stable file name stem for `url` (64-bit FNV-1a), so cache entries survive
+/// restarts and toolkit updates.
+fn cache_key(url: &str) -> String {
+    let hash = url.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// `wallpapers` in the app's cache directory.
+pub fn cache_dir() -> PathBuf {
+    crate::core::paths::get().cache.join("wallpapers")
+}
+
+fn thumbnail_path_in(cache: &Path, wallpaper: &Wallpaper) -> PathBuf {
+    let url = wallpaper.thumbnail_url();
+    cache
+        .join(THUMBNAIL_DIR)
+        .join(format!("{}.{}", cache_key(url), extension(url)))
+}
+
+/// Cached thumbnail of `wallpaper`, if it was fetched before.
+pub fn cached_thumbnail(wallpaper: &Wallpaper) -> Option<PathBuf> {
+    let path = thumbnail_path_in(&cache_dir(), wallpaper);
+    path.is_file().then_some(path)
+}
+
+/// Where downloaded wallpapers are saved: `~/Pictures/Wallpapers`.
+pub fn download_dir() -> PathBuf {
+    dirs::picture_dir()
+        .unwrap_or_else(|| crate::core::paths::get().home.join("Pictures"))
+        .join("Wallpapers")
+}
+
+/// Fetch the wallpaper index, falling back to the cached copy when offline.
+pub async fn fetch_index() -> Result<Vec<Wallpaper>> {
+    info!("Fetching wallpaper index...");
+    let cache = cache_dir();
+    let cached = cache.join(INDEX_CACHE);
+
+    match fetch_text(INDEX_URL).await.and_then(|body| {
+        let wallpapers = parse_index(&body)?;
+        Ok((body, wallpapers))
+    }) {
+        Ok((body, wallpapers)) => {
+            if let Err(e) =
+                std::fs::create_dir_all(&cache).and_then(|_| std::fs::write(&cached, body))
+            {
+                warn!("Failed to cache wallpaper index: {}", e);
+            }
+            info!("Found {} wallpapers", wallpapers.len());
+            Ok(wallpapers)
+        }
+        Err(e) => {
+            warn!(
+                "Failed to fetch wallpaper index, using cached copy: {:#}",
+                e
+            );
+            let body = std::fs::read_to_string(&cached)
+                .map_err(|_| e)
+                .context("No cached wallpaper index")?;
+            parse_index(&body)
+        }
+    }
+}
+
+/// Path of the thumbnail of `wallpaper`, downloading it into the cache first
+/// if needed.
+pub async fn fetch_thumbnail(wallpaper: &Wallpaper) -> Result<PathBuf> {
+    let cache = cache_dir();
+    let path = thumbnail_path_in(&cache, wallpaper);
+    if path.is_file() {
+        return Ok(path);
+    }
+
+    let dir = cache.join(THUMBNAIL_DIR);
+    std::fs::create_dir_all(&dir).context("Failed to create thumbnail cache")?;
+    // Written beside the final path until complete, so a partial file is
+    // never mistaken for a cached thumbnail
+    download_file(
+        wallpaper.thumbnail_url().to_string(),
+        path.to_string_lossy().to_string(),
+        |_| {},
+        Arc::new(AtomicBool::new(false)),
+        Arc::new(AtomicBool::new(false)),
+        None,
+    )
+    .await?;
+    Ok(path)
+}
+
+async fn fetch_text(url: &str) -> Result<String> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .user_agent("xero-toolkit")
+        .build()
+        .context("Failed to build HTTP client")?
+        .get(url)
+        .send()
+        .await
+        .context("Failed to fetch wallpaper index")?
+        .error_for_status()
+        .context("Wallpaper index returned an error")?
+        .text()
+        .await
+        .context("Failed to read response body")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wallpaper(url: &str, thumbnail: Option<&str>) -> Wallpaper {
+        Wallpaper {
+            name: "Test".to_string(),
+            url: url.to_string(),
+            thumbnail: thumbnail.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_parse_index() {
+        let json = r#"[
+            {"name": "Aurora", "url": "https://example.com/aurora.png",
+             "thumbnail": "https://example.com/thumbs/aurora.jpg"},
+            {"name": "Dunes", "url": "https://example.com/dunes.jpg"},
+            {"name": "", "url": "https://example.com/empty.jpg"},
+            {"name": "Local", "url": "file:///etc/passwd"},
+            {"name": "Sneaky", "url": "https://example.com/a.jpg", "thumbnail": "file:///x"}
+        ]"#;
+        let wallpapers = parse_index(json).unwrap();
+        assert_eq!(wallpapers.len(), 2);
+        assert_eq!(wallpapers[0].name, "Aurora");
+        assert_eq!(
+            wallpapers[0].thumbnail_url(),
+            "https://example.com/thumbs/aurora.jpg"
+        );
+        assert_eq!(
+            wallpapers[1].thumbnail_url(),
+            "https://example.com/dunes.jpg"
+        );
+        assert!(parse_index("{}").is_err());
+    }
+
+    #[test]
+    fn test_file_name() {
+        let name = |url| wallpaper(url, None).file_name();
+        assert_eq!(name("https://example.com/walls/aurora.png"), "aurora.png");
+        assert_eq!(
+            name("https://example.com/my wall (1).jpg?raw=true"),
+            "my_wall__1_.jpg"
+        );
+        // Nothing usable falls back to the hashed name
+        assert_eq!(
+            name("https://example.com/"),
+            format!("{}.img", cache_key("https://example.com/"))
+        );
+        assert_eq!(
+            name("https://example.com/../.."),
+            format!("{}.img", cache_key("https://example.com/../.."))
+        );
+    }
+
+    #[test]
+    fn test_thumbnail_cache_path() {
+        let cache = Path::new("/cache");
+        let a = wallpaper("https://example.com/a.PNG", None);
+        let b = wallpaper(
+            "https://example.com/b.png",
+            Some("https://example.com/b-small.webp"),
+        );
+
+        let path = thumbnail_path_in(cache, &a);
+        assert_eq!(path.parent().unwrap(), Path::new("/cache/thumbnails"));
+        assert_eq!(path.extension().unwrap(), "png");
+        // Stable across calls, distinct across URLs
+        assert_eq!(path, thumbnail_path_in(cache, &a));
+        assert_ne!(path, thumbnail_path_in(cache, &b));
+        assert_eq!(thumbnail_path_in(cache, &b).extension().unwrap(), "webp");
+        assert_eq!(cache_key("a"), "af63dc4c8601ec8c");
+    }
+}
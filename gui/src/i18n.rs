@@ -0,0 +1,121 @@
+//! Translation support through gettext.
+//!
+//! Strings are looked up in the `xero-toolkit` text domain, which also
+//! serves the `translatable` strings of the UI files. Catalogs are built
+//! from `po/` and installed to [`paths::LOCALE_DIR`](crate::config::paths::LOCALE_DIR).
+
+use crate::config;
+use gtk4::glib;
+use std::ffi::{c_char, CString};
+
+/// Text domain of the application's message catalogs.
+pub const GETTEXT_PACKAGE: &str = config::app_info::NAME;
+
+extern "C" {
+    // Provided by glibc's libintl
+    fn bindtextdomain(domainname: *const c_char, dirname: *const c_char) -> *mut c_char;
+    fn bind_textdomain_codeset(domainname: *const c_char, codeset: *const c_char) -> *mut c_char;
+    fn textdomain(domainname: *const c_char) -> *mut c_char;
+}
+
+/// Set up the locale and text domain. Call before any string is translated.
+pub fn init() {
+    let domain = CString::new(GETTEXT_PACKAGE).expect("text domain contains no NUL");
+    let dir = CString::new(config::paths::LOCALE_DIR).expect("locale dir contains no NUL");
+    let codeset = c"UTF-8";
+
+    // SAFETY: all arguments are valid NUL-terminated strings that outlive the calls.
+    unsafe {
+        libc::setlocale(libc::LC_ALL, c"".as_ptr());
+        bindtextdomain(domain.as_ptr(), dir.as_ptr());
+        bind_textdomain_codeset(domain.as_ptr(), codeset.as_ptr());
+        textdomain(domain.as_ptr());
+    }
+}
+
+/// Translate a message.
+pub fn gettext(msgid: &str) -> String {
+    glib::dgettext(Some(GETTEXT_PACKAGE), msgid).into()
+}
+
+/// Translate a message with a plural form chosen by `n`.
+pub fn ngettext(msgid: &str, msgid_plural: &str, n: u32) -> String {
+    glib::dngettext(Some(GETTEXT_PACKAGE), msgid, msgid_plural, n.into()).into()
+}
+
+/// Mark a message for extraction without translating it, for `const`
+/// tables whose strings are translated with [`gettext`] where shown.
+pub const fn gettext_noop(msgid: &'static str) -> &'static str {
+    msgid
+}
+
+/// Replace each `{}` in a translated template with the next argument, or
+/// `{N}` with the N-th one so translations can reorder them.
+///
+/// Translations cannot go through `format!`, which needs a literal.
+pub fn fill(template: &str, args: &[&str]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let placeholder = rest
+            .find('}')
+            .map(|end| (end, &rest[1..end]))
+            .filter(|(_, index)| index.chars().all(|c| c.is_ascii_digit()));
+        let Some((end, index)) = placeholder else {
+            filled.push('{');
+            rest = &rest[1..];
+            continue;
+        };
+
+        let index = if index.is_empty() {
+            next += 1;
+            next - 1
+        } else {
+            index.parse().unwrap_or(usize::MAX)
+        };
+        filled.push_str(args.get(index).copied().unwrap_or(&rest[..=end]));
+        rest = &rest[end + 1..];
+    }
+
+    filled.push_str(rest);
+    filled
+}
+
+/// Like [`fill`] for Pango markup templates, escaping the arguments so they
+/// cannot break or inject markup.
+pub fn fill_markup(template: &str, args: &[&str]) -> String {
+    let escaped: Vec<glib::GString> = args
+        .iter()
+        .map(|arg| glib::markup_escape_text(arg))
+        .collect();
+    let escaped: Vec<&str> = escaped.iter().map(|arg| arg.as_str()).collect();
+    fill(template, &escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill() {
+        assert_eq!(fill("{} installed", &["3"]), "3 installed");
+        assert_eq!(fill("{} von {}", &["1", "2"]), "1 von 2");
+        assert_eq!(fill("no args", &["x"]), "no args");
+        assert_eq!(fill("{} and {}", &["one"]), "one and {}");
+        assert_eq!(fill("{1} vor {0}", &["a", "b"]), "b vor a");
+        assert_eq!(fill("{x} {}", &["a"]), "{x} a");
+    }
+
+    #[test]
+    fn test_fill_markup_escapes_args() {
+        assert_eq!(
+            fill_markup("Shell: <b>{}</b>", &["a<b>&c"]),
+            "Shell: <b>a&lt;b&gt;&amp;c</b>"
+        );
+    }
+}
@@ -2,14 +2,52 @@
 
 use adw::prelude::*;
 use adw::Application;
-use log::info;
+use gtk4::{gio, glib};
+use log::{info, warn};
+use xero_auth::logging;
 
+mod cli;
 mod config;
 mod core;
+mod i18n;
 mod ui;
 
 fn main() {
-    simple_logger::SimpleLogger::new().init().unwrap();
+    i18n::init();
+
+    let options = match cli::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+
+    if options.list {
+        cli::print_ids();
+        return;
+    }
+
+    if let Err(message) = cli::validate(&options) {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    }
+
+    if options.no_gui {
+        // Keep stdout to plain progress lines; only surface problems
+        logging::init(log::LevelFilter::Warn, Some(core::logs::log_path())).unwrap();
+        let action = options.action.as_deref().unwrap_or_default();
+        std::process::exit(cli::run_headless(action, options.json));
+    }
+
+    let level = core::logs::parse_level(&config::user::shared().get().general.log_level);
+    logging::init(logging::level_from_env(level), Some(core::logs::log_path())).unwrap();
+    core::crash::install_hook();
 
     info!(
         "Starting {} v{}",
@@ -17,12 +55,38 @@ fn main() {
         config::app_info::VERSION
     );
     info!("Application ID: {}", config::app_info::ID);
+    let paths = core::paths::get();
+    if paths.is_sandboxed() {
+        info!("Running in a {:?} sandbox", paths.sandbox);
+    }
 
+    // The primary instance receives the command line of every later launch
     let app = Application::builder()
         .application_id(config::app_info::ID)
+        .flags(gio::ApplicationFlags::HANDLES_COMMAND_LINE)
         .build();
 
-    app.connect_activate(ui::setup_application_ui);
+    app.connect_command_line(|app, command_line| {
+        let args = command_line
+            .arguments()
+            .into_iter()
+            .skip(1)
+            .map(|arg| arg.to_string_lossy().into_owned());
+
+        match cli::parse(args) {
+            Ok(options) => {
+                ui::handle_launch(app, &options);
+                glib::ExitCode::SUCCESS
+            }
+            // Arguments were already validated by the launching process
+            Err(message) => {
+                warn!("Ignoring invalid command line: {}", message);
+                glib::ExitCode::from(2)
+            }
+        }
+    });
+
+    app.connect_activate(|app| ui::handle_launch(app, &cli::Options::default()));
 
     app.run();
 }
@@ -0,0 +1,38 @@
+//! Reduced-motion handling shared by animated widgets.
+//!
+//! The toolkit's own "Reduce Motion" preference is applied by turning off
+//! `gtk-enable-animations`, so that setting is the single source of truth
+//! for both the desktop-wide and the in-app preference.
+
+use std::cell::Cell;
+
+thread_local! {
+    /// `gtk-enable-animations` as set by the desktop, before we override it.
+    static SYSTEM_ANIMATIONS: Cell<Option<bool>> = const { Cell::new(None) };
+}
+
+fn system_animations(settings: &gtk4::Settings) -> bool {
+    SYSTEM_ANIMATIONS.with(|cell| {
+        let enabled = cell
+            .get()
+            .unwrap_or_else(|| settings.is_gtk_enable_animations());
+        cell.set(Some(enabled));
+        enabled
+    })
+}
+
+/// Enable or disable interface animations.
+///
+/// Turning reduce motion off restores the desktop setting rather than
+/// forcing animations on.
+pub fn apply_reduce_motion(enabled: bool) {
+    if let Some(settings) = gtk4::Settings::default() {
+        let animations = system_animations(&settings) && !enabled;
+        settings.set_gtk_enable_animations(animations);
+    }
+}
+
+/// Whether the desktop or the user asked for reduced motion.
+pub fn reduced_motion() -> bool {
+    gtk4::Settings::default().is_some_and(|settings| !settings.is_gtk_enable_animations())
+}
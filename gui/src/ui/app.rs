@@ -1,97 +1,185 @@
 //! Application setup and initialization.
 
+use crate::cli;
 use crate::config;
-use crate::config::user::Config;
+use crate::config::user::{self, SharedConfig};
 use crate::core;
-use crate::ui::context::AppContext;
-use crate::ui::context::UiComponents;
+use crate::core::distro::Distro;
+use crate::i18n::{self, gettext};
+use crate::ui::context::{self, AppContext, UiComponents};
 use crate::ui::navigation;
-use crate::ui::utils::extract_widget;
+use crate::ui::task_runner;
+use crate::ui::utils::{builder_from_resource, expect_widget};
 use adw::prelude::*;
 use adw::Application;
 use gtk4::glib;
 use gtk4::{gio, ApplicationWindow, Builder, CssProvider, Stack};
 use log::{error, info, warn};
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::time::Instant;
+
+/// Handle a launch of the application, local or forwarded from a second
+/// instance.
+///
+/// The first launch builds the main window. Later launches raise it, or the
+/// open task dialog, and switch to the requested page.
+pub fn handle_launch(app: &Application, options: &cli::Options) {
+    let Some(ctx) = context::current() else {
+        setup_application_ui(app, options);
+        return;
+    };
+
+    info!("Application already running - raising existing window");
+
+    if let Some(page) = options.page.as_deref().and_then(navigation::find_page) {
+        info!("Opening page '{}' requested by another instance", page.id);
+        navigation::open_page(&ctx.ui.tabs_container, page.id);
+    }
+
+    if task_runner::present_dialog() {
+        info!("Presented running task dialog");
+        return;
+    }
+
+    let Some(window) = main_window(app) else {
+        warn!("Main window not found");
+        return;
+    };
+    window.present();
+
+    if let Some(action) = options.action.as_deref().and_then(core::actions::find) {
+        info!(
+            "Running action '{}' requested by another instance",
+            action.id
+        );
+        task_runner::run(
+            window.upcast_ref(),
+            action.commands(),
+            &gettext(action.title),
+        );
+    }
+}
+
+/// The application's main window.
+fn main_window(app: &Application) -> Option<ApplicationWindow> {
+    app.windows()
+        .into_iter()
+        .find_map(|window| window.downcast::<ApplicationWindow>().ok())
+}
 
 /// Initialize and set up main application UI.
-pub fn setup_application_ui(app: &Application) {
+///
+/// `options` may request a page to open and an action to run once the
+/// window is ready.
+fn setup_application_ui(app: &Application, options: &cli::Options) {
     info!("Initializing application components");
+    let started = Instant::now();
 
     setup_resources_and_theme();
 
-    let config = Rc::new(RefCell::new(Config::load()));
+    let config = user::shared();
     info!("User configuration loaded");
+    crate::ui::accessibility::apply_reduce_motion(config.get().appearance.reduce_motion);
 
-    // Persist configuration once on application shutdown to avoid IO during interaction.
+    // Changes are saved shortly after they happen; write any pending save on shutdown.
     {
-        let config_for_shutdown = Rc::clone(&config);
+        let config_for_shutdown = config.clone();
         app.connect_shutdown(move |_| {
-            if let Err(e) = config_for_shutdown.borrow().save() {
-                eprintln!("Failed to save config on shutdown: {e}");
-            } else {
-                info!("Configuration saved on shutdown");
-            }
+            config_for_shutdown.flush();
+            info!("Configuration saved on shutdown");
         });
     }
 
-    let builder = Builder::from_resource(config::resources::MAIN_UI);
+    // Too late to ask here; quitting through any other path stops the task
+    app.connect_shutdown(|_| task_runner::stop_running());
+
+    let builder = builder_from_resource(config::resources::MAIN_UI);
     let window = create_main_window(app, &builder);
 
+    restore_window_state(&window, &config);
+
     window.present();
+    info!(
+        "Main window presented after {} ms",
+        started.elapsed().as_millis()
+    );
 
     info!("Initializing environment variables");
     if let Err(e) = config::env::init() {
         error!("Failed to initialize environment variables: {}", e);
         crate::ui::dialogs::error::show_error(
             &window,
-            &format!(
-                "Failed to initialize environment variables: {}\n\nRequired environment variables (USER, HOME) are not set.",
-                e
+            &i18n::fill(
+                &gettext(
+                    "Failed to initialize environment variables: {}\n\n\
+                     Required environment variables (USER, HOME) are not set.",
+                ),
+                &[&e.to_string()],
             ),
         );
         return;
     }
 
-    let distribution_name = core::get_distribution_name()
-        .unwrap_or_else(|| "Unknown".to_string())
-        .to_lowercase();
-    match distribution_name.as_str() {
-        "xerolinux" => info!("Running on XeroLinux"),
+    let os_release = core::distro::os_release();
+    match os_release.map(|os_release| os_release.distro()) {
+        Some(Distro::XeroLinux) => info!("Running on XeroLinux"),
         _ => {
+            let distribution_name = os_release
+                .map(|os_release| os_release.display_name().to_string())
+                .unwrap_or_else(|| "Unknown".to_string());
             warn!(
                 "Not running on XeroLinux - current distribution: {}",
                 distribution_name
             );
             warn!("Some features may not work correctly on non-XeroLinux systems");
 
-            if !config.borrow().warnings.dismissed_generic_distro_notice {
+            let dismissed = config.get().warnings.dismissed_generic_distro_notice;
+            if !dismissed {
                 core::system_check::show_generic_distro_notice(
                     &window,
                     config.clone(),
-                    distribution_name.clone(),
+                    distribution_name,
                 );
             }
         }
     }
 
-    let tabs_container = extract_widget(&builder, "tabs_container");
+    let tabs_container = expect_widget(&builder, "tabs_container");
 
     let stack = navigation::create_stack_and_tabs(&tabs_container, &builder);
 
     let ctx = setup_ui_components(&builder, stack, &window, config.clone());
-
-    info!("Setting initial view to first page");
-    if let Some(first_page) = navigation::PAGES.first() {
-        ctx.navigate_to_page(first_page.id);
+    context::install(ctx.clone());
+
+    // Only the page shown first is built now, the others on their first visit
+    let requested = options
+        .page
+        .as_deref()
+        .and_then(navigation::find_page)
+        .filter(|page| page.is_available());
+    let initial_page = if let Some(page) = requested {
+        info!("Opening page '{}' requested on the command line", page.id);
+        Some(page)
+    } else if let Some(page) = startup_page(&config) {
+        info!("Opening start page '{}'", page.id);
+        Some(page)
+    } else {
+        navigation::available_pages().next()
+    };
+    if let Some(page) = initial_page {
+        navigation::open_page(&ctx.ui.tabs_container, page.id);
     }
 
+    track_last_page(&ctx.ui.stack, &config);
+
     crate::ui::seasonal::apply_seasonal_effects(&window);
 
     info!("Running dependency checks");
     let dependency_result = core::check_dependencies();
-    if dependency_result.has_missing_dependencies() {
+    // Without pacman the pages explain what is unavailable instead
+    let environment = core::system_check::environment();
+    if dependency_result.has_missing_dependencies()
+        && environment.has(core::system_check::Capability::Pacman)
+    {
         core::show_dependency_error_dialog(&window, &dependency_result);
         return;
     }
@@ -102,7 +190,33 @@ pub fn setup_application_ui(app: &Application) {
         warn!("No AUR helper detected");
     }
 
-    info!("Xero Toolkit application startup complete");
+    crate::ui::update_banner::check_for_updates(&builder, &window);
+    crate::ui::badges::init();
+    crate::ui::session_indicator::init(&builder);
+
+    // A launch that runs an action right away is not the moment for a tour
+    let welcomed = config.get().general.welcomed;
+    if !welcomed && options.action.is_none() {
+        crate::ui::dialogs::welcome::show_welcome_wizard(&window, &dependency_result);
+    }
+    crate::ui::dialogs::crash_report::show_pending_report(&window);
+
+    if let Some(action) = options.action.as_deref().and_then(core::actions::find) {
+        info!(
+            "Running action '{}' requested on the command line",
+            action.id
+        );
+        task_runner::run(
+            window.upcast_ref(),
+            action.commands(),
+            &gettext(action.title),
+        );
+    }
+
+    info!(
+        "Xero Toolkit application startup complete in {} ms",
+        started.elapsed().as_millis()
+    );
 }
 
 fn setup_resources_and_theme() {
@@ -133,49 +247,150 @@ fn setup_resources_and_theme() {
 }
 
 fn create_main_window(app: &Application, builder: &Builder) -> ApplicationWindow {
-    let window: ApplicationWindow = extract_widget(builder, "app_window");
+    let window: ApplicationWindow = expect_widget(builder, "app_window");
 
     window.set_application(Some(app));
     info!("Setting window icon to xero-toolkit");
     window.set_icon_name(Some("xero-toolkit"));
+
+    // Closing the app would leave a running task's children behind
+    window.connect_close_request(|window| {
+        if !task_runner::is_running() {
+            return glib::Propagation::Proceed;
+        }
+        let window_clone = window.clone();
+        task_runner::confirm_stop(window, move || {
+            if let Some(app) = window_clone.application() {
+                app.quit();
+            }
+        });
+        glib::Propagation::Stop
+    });
     info!("Main application window created from UI resource");
 
     window
 }
 
+/// Apply the saved window size and maximized state, and keep them up to date.
+fn restore_window_state(window: &ApplicationWindow, config: &SharedConfig) {
+    let (remember, width, height, maximized) = {
+        let config = config.get();
+        let state = &config.window;
+        (
+            config.general.remember_window_size,
+            state.width,
+            state.height,
+            state.maximized,
+        )
+    };
+    if remember {
+        window.set_default_size(width, height);
+        if maximized {
+            window.maximize();
+        }
+    }
+
+    let config_clone = config.clone();
+    window.connect_default_width_notify(move |window| {
+        // A maximized window reports the screen size; keep the restored size instead
+        if !window.is_maximized() {
+            let width = window.default_width();
+            config_clone.update(|config| config.window.width = width);
+        }
+    });
+
+    let config_clone = config.clone();
+    window.connect_default_height_notify(move |window| {
+        if !window.is_maximized() {
+            let height = window.default_height();
+            config_clone.update(|config| config.window.height = height);
+        }
+    });
+
+    let config_clone = config.clone();
+    window.connect_maximized_notify(move |window| {
+        let maximized = window.is_maximized();
+        config_clone.update(|config| config.window.maximized = maximized);
+    });
+}
+
+/// The configured start page, or the last visited one, if it still exists
+/// and is available.
+fn startup_page(config: &SharedConfig) -> Option<&'static navigation::PageConfig> {
+    let page_id = {
+        let config = config.get();
+        config
+            .general
+            .start_page
+            .clone()
+            .or_else(|| config.window.last_page.clone())?
+    };
+    navigation::available_pages().find(|page| page.id == page_id)
+}
+
+/// Remember the visible page so it can be reopened on the next start.
+fn track_last_page(stack: &Stack, config: &SharedConfig) {
+    let config_clone = config.clone();
+    stack.connect_visible_child_name_notify(move |stack| {
+        if let Some(name) = stack.visible_child_name() {
+            config_clone.update(|config| config.window.last_page = Some(name.to_string()));
+        }
+    });
+}
+
 fn setup_ui_components(
     builder: &Builder,
     stack: Stack,
     window: &ApplicationWindow,
-    config: Rc<RefCell<Config>>,
+    config: SharedConfig,
 ) -> AppContext {
-    let tabs_container = extract_widget(builder, "tabs_container");
-    let main_split_view = extract_widget(builder, "main_split_view");
-    let sidebar_toggle = extract_widget(builder, "sidebar_toggle_button");
+    let tabs_container = expect_widget(builder, "tabs_container");
+    let main_split_view = expect_widget(builder, "main_split_view");
+    let sidebar_toggle = expect_widget(builder, "sidebar_toggle_button");
+    let toast_overlay = expect_widget(builder, "toast_overlay");
 
     setup_autostart_toggle(builder, config.clone());
     setup_about_button(builder, window);
-    setup_seasonal_effects_toggle(builder, window);
+    setup_seasonal_effects_toggle(builder);
+    setup_app_actions(builder, window, &stack);
 
     info!("All UI components successfully initialized from UI builder");
 
-    let ui = UiComponents::new(stack, tabs_container, main_split_view, sidebar_toggle);
+    let ui = UiComponents::new(
+        stack,
+        tabs_container,
+        main_split_view,
+        sidebar_toggle,
+        toast_overlay,
+    );
 
     ui.configure_sidebar(config::sidebar::MIN_WIDTH, config::sidebar::MAX_WIDTH);
+    restore_sidebar_state(&ui.main_split_view, &config);
 
     AppContext::new(ui, config)
 }
 
-fn setup_autostart_toggle(builder: &Builder, config: Rc<RefCell<Config>>) {
-    let switch = extract_widget::<gtk4::Switch>(builder, "switch_autostart");
-    switch.set_active(config.borrow().general.autostart);
+/// Apply the saved sidebar visibility and keep it up to date.
+fn restore_sidebar_state(split_view: &adw::OverlaySplitView, config: &SharedConfig) {
+    let visible = config.get().window.sidebar_visible;
+    split_view.set_show_sidebar(visible);
 
-    let config_clone = Rc::clone(&config);
+    let config_clone = config.clone();
+    split_view.connect_show_sidebar_notify(move |split_view| {
+        let visible = split_view.shows_sidebar();
+        config_clone.update(|config| config.window.sidebar_visible = visible);
+    });
+}
+
+fn setup_autostart_toggle(builder: &Builder, config: SharedConfig) {
+    let switch = expect_widget::<gtk4::Switch>(builder, "switch_autostart");
+    switch.set_active(config.get().general.autostart);
+
+    let config_clone = config.clone();
     switch.connect_state_set(move |_switch, state| {
         info!("Autostart toggle changed to: {}", state);
 
-        // Update in-memory config; actual persistence happens on app shutdown.
-        config_clone.borrow_mut().general.autostart = state;
+        config_clone.update(|config| config.general.autostart = state);
 
         let result = if state {
             core::autostart::enable()
@@ -200,7 +415,7 @@ fn setup_autostart_toggle(builder: &Builder, config: Rc<RefCell<Config>>) {
 fn setup_about_button(builder: &Builder, window: &ApplicationWindow) {
     use crate::ui::dialogs::about;
 
-    let button = extract_widget::<gtk4::Button>(builder, "about_button");
+    let button = expect_widget::<gtk4::Button>(builder, "about_button");
     let window_clone = window.clone();
     button.connect_clicked(move |_| {
         info!("About button clicked");
@@ -208,15 +423,94 @@ fn setup_about_button(builder: &Builder, window: &ApplicationWindow) {
     });
 }
 
-fn setup_seasonal_effects_toggle(builder: &Builder, _window: &ApplicationWindow) {
+/// Register the main menu actions.
+fn setup_app_actions(builder: &Builder, window: &ApplicationWindow, stack: &Stack) {
+    use crate::ui::dialogs::{about, command_palette, log_viewer, preferences, profile};
+
+    let Some(app) = window.application() else {
+        warn!("Main window has no application - menu actions unavailable");
+        return;
+    };
+
+    let preferences_action = gio::SimpleAction::new("preferences", None);
+    let window_clone = window.clone();
+    preferences_action.connect_activate(move |_, _| {
+        preferences::show_preferences_dialog(window_clone.upcast_ref());
+    });
+    app.add_action(&preferences_action);
+    app.set_accels_for_action("app.preferences", &["<Control>comma"]);
+
+    let search_action = gio::SimpleAction::new("search", None);
+    let window_clone = window.clone();
+    let tabs_container: gtk4::Box = expect_widget(builder, "tabs_container");
+    let stack = stack.clone();
+    search_action.connect_activate(move |_, _| {
+        command_palette::show_command_palette(window_clone.upcast_ref(), &tabs_container, &stack);
+    });
+    app.add_action(&search_action);
+    app.set_accels_for_action("app.search", &["<Control>k"]);
+
+    let logs_action = gio::SimpleAction::new("logs", None);
+    let window_clone = window.clone();
+    logs_action.connect_activate(move |_, _| {
+        log_viewer::show_log_viewer(window_clone.upcast_ref());
+    });
+    app.add_action(&logs_action);
+
+    let export_profile_action = gio::SimpleAction::new("export-profile", None);
+    let window_clone = window.clone();
+    export_profile_action.connect_activate(move |_, _| {
+        profile::show_export_dialog(&window_clone);
+    });
+    app.add_action(&export_profile_action);
+
+    let import_profile_action = gio::SimpleAction::new("import-profile", None);
+    let window_clone = window.clone();
+    import_profile_action.connect_activate(move |_, _| {
+        profile::show_import_dialog(&window_clone);
+    });
+    app.add_action(&import_profile_action);
+
+    let about_action = gio::SimpleAction::new("about", None);
+    let window_clone = window.clone();
+    about_action.connect_activate(move |_, _| {
+        about::show_about_dialog(window_clone.upcast_ref());
+    });
+    app.add_action(&about_action);
+
+    #[cfg(debug_assertions)]
+    setup_debug_crash_action(builder, &app);
+}
+
+/// Menu entry that panics on purpose, to exercise the crash report flow.
+#[cfg(debug_assertions)]
+fn setup_debug_crash_action(builder: &Builder, app: &gtk4::Application) {
+    let crash_action = gio::SimpleAction::new("debug-crash", None);
+    crash_action.connect_activate(|_, _| panic!("Crash triggered from the debug menu"));
+    app.add_action(&crash_action);
+
+    if let Some(menu) = crate::ui::utils::try_extract_widget::<gio::Menu>(builder, "main_menu") {
+        let section = gio::Menu::new();
+        section.append(Some("Crash (Debug)"), Some("app.debug-crash"));
+        menu.append_section(None, &section);
+    }
+}
+
+fn setup_seasonal_effects_toggle(builder: &Builder) {
     use crate::ui::seasonal;
 
-    let toggle = extract_widget::<gtk4::ToggleButton>(builder, "seasonal_effects_toggle");
+    let toggle = expect_widget::<gtk4::ToggleButton>(builder, "seasonal_effects_toggle");
 
     let has_active = seasonal::has_active_effect();
     toggle.set_visible(has_active);
     toggle.set_active(seasonal::are_effects_enabled());
 
+    // Follow changes made in the preferences
+    let toggle_clone = toggle.clone();
+    user::shared().connect_changed(move |_| {
+        toggle_clone.set_active(seasonal::are_effects_enabled());
+    });
+
     toggle.connect_toggled(move |btn| {
         let enabled = btn.is_active();
         seasonal::set_effects_enabled(enabled);
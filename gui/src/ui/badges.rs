@@ -0,0 +1,116 @@
+//! Sidebar badges for pages that need attention.
+//!
+//! Each badge is computed by its own background thread, so a slow or
+//! failing query only affects its own page. Badges refresh on startup and
+//! after every task run.
+
+use crate::core::events::{self, Event};
+use crate::core::package;
+use crate::core::systemd;
+use crate::ui::navigation;
+use anyhow::Result;
+use gtk4::glib;
+use log::{info, warn};
+use std::cell::Cell;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// A page badge and how to compute it.
+struct BadgeSource {
+    page_id: &'static str,
+    /// Badge text as for [`navigation::set_page_badge`]; runs off the main thread
+    compute: fn() -> Result<Option<String>>,
+}
+
+const SOURCES: &[BadgeSource] = &[
+    BadgeSource {
+        page_id: "main_page",
+        compute: pending_updates,
+    },
+    BadgeSource {
+        page_id: "servicing_system_tweaks",
+        compute: servicing_needed,
+    },
+    BadgeSource {
+        page_id: "kernel_schedulers",
+        compute: kernel_removed,
+    },
+];
+
+thread_local! {
+    /// Whether a refresh is running.
+    static REFRESHING: Cell<bool> = const { Cell::new(false) };
+    /// Whether another refresh was asked for while one was running.
+    static REFRESH_AGAIN: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Refresh the badges now and after every task run.
+pub fn init() {
+    events::subscribe(|event| {
+        if let Event::TaskFinished { .. } = event {
+            refresh();
+        }
+    })
+    .detach();
+    refresh();
+}
+
+/// Recompute all badges in the background.
+pub fn refresh() {
+    if REFRESHING.with(|refreshing| refreshing.replace(true)) {
+        REFRESH_AGAIN.with(|again| again.set(true));
+        return;
+    }
+    info!("Refreshing page badges");
+
+    let (sender, receiver) = mpsc::channel();
+    for source in SOURCES {
+        let sender = sender.clone();
+        let (page_id, compute) = (source.page_id, source.compute);
+        std::thread::spawn(move || {
+            let _ = sender.send((page_id, compute()));
+        });
+    }
+    drop(sender);
+
+    glib::timeout_add_local(Duration::from_millis(200), move || {
+        loop {
+            match receiver.try_recv() {
+                Ok((page_id, Ok(badge))) => navigation::set_page_badge(page_id, badge),
+                Ok((page_id, Err(e))) => {
+                    warn!("Failed to compute badge for {}: {:#}", page_id, e);
+                    navigation::set_page_badge(page_id, None);
+                }
+                Err(mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                Err(mpsc::TryRecvError::Disconnected) => break,
+            }
+        }
+
+        REFRESHING.with(|refreshing| refreshing.set(false));
+        if REFRESH_AGAIN.with(|again| again.replace(false)) {
+            refresh();
+        }
+        glib::ControlFlow::Break
+    });
+}
+
+/// Number of pending package updates.
+fn pending_updates() -> Result<Option<String>> {
+    let total = package::pending_update_count()?;
+    Ok((total > 0).then(|| total.to_string()))
+}
+
+/// A dot while `.pacnew` files wait for merging or units have failed.
+fn servicing_needed() -> Result<Option<String>> {
+    if !package::find_pacnew_files(Path::new(package::PACNEW_ROOT)).is_empty() {
+        return Ok(Some(String::new()));
+    }
+    let failed = systemd::list_failed_units()?;
+    Ok((!failed.is_empty()).then(String::new))
+}
+
+/// A dot when an update removed the running kernel.
+fn kernel_removed() -> Result<Option<String>> {
+    Ok(package::running_kernel_removed()?.then(String::new))
+}
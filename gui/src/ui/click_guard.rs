@@ -0,0 +1,106 @@
+//! Guard against a fast double-click running a button's handler twice.
+//!
+//! The task runner refuses a second task once one runs, but a dialog asking
+//! what to run opens before that, so two clicks open two dialogs. A guarded
+//! button ignores clicks while the [`ClickToken`] of the previous click is
+//! alive; the handler drops it when done, or hands it to the dialog it
+//! opened to keep the button busy until that dialog closes.
+
+use gtk4::prelude::*;
+use gtk4::{glib, Button};
+use log::debug;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+/// Whether a guarded button takes clicks.
+#[derive(Debug, Clone, Default)]
+pub struct ClickGuard {
+    busy: Rc<Cell<bool>>,
+}
+
+impl ClickGuard {
+    /// Start handling a click, or `None` while the previous one is handled.
+    pub fn begin(&self) -> Option<ClickToken> {
+        if self.busy.replace(true) {
+            return None;
+        }
+        Some(ClickToken {
+            busy: self.busy.clone(),
+        })
+    }
+}
+
+/// A click being handled; the button takes clicks again once it is dropped.
+#[derive(Debug)]
+#[must_use = "dropping the token takes clicks again right away"]
+pub struct ClickToken {
+    busy: Rc<Cell<bool>>,
+}
+
+impl ClickToken {
+    /// Keep the button busy until `window` closes.
+    pub fn release_on_close(self, window: &impl IsA<gtk4::Window>) {
+        let token = RefCell::new(Some(self));
+        window.connect_close_request(move |_| {
+            token.borrow_mut().take();
+            glib::Propagation::Proceed
+        });
+    }
+}
+
+impl Drop for ClickToken {
+    fn drop(&mut self) {
+        self.busy.set(false);
+    }
+}
+
+/// Connect `handler` to clicks of `button`, ignoring clicks until the
+/// previous one is done.
+///
+/// The handler takes the [`ClickToken`] of the click along with the button.
+pub fn connect_clicked_once_until_done<F>(button: &Button, handler: F) -> glib::SignalHandlerId
+where
+    F: Fn(&Button, ClickToken) + 'static,
+{
+    let guard = ClickGuard::default();
+    button.connect_clicked(move |button| match guard.begin() {
+        Some(token) => handler(button, token),
+        None => debug!("Ignoring click while the previous one is handled"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guard() {
+        let guard = ClickGuard::default();
+        let token = guard.begin().unwrap();
+        // The second click of a double-click
+        assert!(guard.begin().is_none());
+        assert!(guard.begin().is_none());
+
+        drop(token);
+        let token = guard.begin().unwrap();
+        drop(token);
+
+        // Handed on, like to a dialog, the token keeps the guard busy
+        let held = RefCell::new(guard.begin());
+        assert!(held.borrow().is_some());
+        assert!(guard.begin().is_none());
+        held.borrow_mut().take();
+        assert!(guard.begin().is_some());
+        // The token of that click was dropped right away
+        assert!(guard.begin().is_some());
+    }
+
+    #[test]
+    fn test_guards_are_independent() {
+        let first = ClickGuard::default();
+        let second = ClickGuard::default();
+        let _token = first.begin().unwrap();
+        assert!(second.begin().is_some());
+        assert!(first.clone().begin().is_none());
+    }
+}
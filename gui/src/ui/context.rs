@@ -3,41 +3,50 @@
 //! This module contains the application-wide context and UI component
 //! references used for navigation and state management.
 
-use crate::config::user::Config;
+use crate::config::user::SharedConfig;
+use crate::ui::toast::ToastService;
 use adw::prelude::*;
 use gtk4::{Box as GtkBox, Stack, ToggleButton};
 use std::cell::RefCell;
-use std::rc::Rc;
+
+thread_local! {
+    /// Context of the main window once it has been built.
+    static CONTEXT: RefCell<Option<AppContext>> = const { RefCell::new(None) };
+}
+
+/// Context of the main window, `None` until it has been built.
+pub fn current() -> Option<AppContext> {
+    CONTEXT.with(|ctx| ctx.borrow().clone())
+}
+
+/// Make `ctx` the context of the main window.
+pub fn install(ctx: AppContext) {
+    CONTEXT.with(|context| context.replace(Some(ctx)));
+}
 
 /// Main application context with UI elements.
 #[derive(Clone)]
 pub struct AppContext {
     pub ui: UiComponents,
     #[allow(dead_code)]
-    pub config: Rc<RefCell<Config>>,
+    pub config: SharedConfig,
 }
 
 impl AppContext {
     /// Create a new application context with UI components.
-    pub fn new(ui: UiComponents, config: Rc<RefCell<Config>>) -> Self {
+    pub fn new(ui: UiComponents, config: SharedConfig) -> Self {
         Self { ui, config }
     }
-
-    /// Navigate to a specific page in the stack.
-    pub fn navigate_to_page(&self, page_name: &str) {
-        self.ui.stack.set_visible_child_name(page_name);
-    }
 }
 
 /// UI components grouped by functionality.
 #[derive(Clone)]
 pub struct UiComponents {
     pub stack: Stack,
-    #[allow(dead_code)]
-    // Stored for potential future use, currently only used during initialization
     pub tabs_container: GtkBox,
     pub main_split_view: adw::OverlaySplitView,
     pub sidebar_toggle: ToggleButton,
+    pub toasts: ToastService,
 }
 
 impl UiComponents {
@@ -47,12 +56,14 @@ impl UiComponents {
         tabs_container: GtkBox,
         main_split_view: adw::OverlaySplitView,
         sidebar_toggle: ToggleButton,
+        toast_overlay: adw::ToastOverlay,
     ) -> Self {
         Self {
             stack,
             tabs_container,
             main_split_view,
             sidebar_toggle,
+            toasts: ToastService::new(toast_overlay),
         }
     }
 
@@ -1,21 +1,22 @@
 //! About dialog showing creator information and credits.
 
-use crate::core::package;
-use crate::ui::utils::extract_widget;
+use crate::core::{package, sysinfo};
+use crate::i18n::gettext;
+use crate::ui::utils::{builder_from_resource, expect_widget};
 use gtk4::glib;
 use gtk4::prelude::*;
-use gtk4::{Builder, Button, Label, Window};
+use gtk4::{Button, Label, Window};
 
 /// Show the about dialog.
 pub fn show_about_dialog(parent: &Window) {
     // Load the UI from resource
-    let builder = Builder::from_resource(crate::config::resources::dialogs::ABOUT);
+    let builder = builder_from_resource(crate::config::resources::dialogs::ABOUT);
 
     // Get the dialog window
-    let dialog: Window = extract_widget(&builder, "about_window");
+    let dialog: Window = expect_widget(&builder, "about_window");
 
     // Get the close button
-    let close_button: Button = extract_widget(&builder, "close_button");
+    let close_button: Button = expect_widget(&builder, "close_button");
 
     // Get labels with links and set up link activation
     let setup_link_handler = |label: &Label| {
@@ -27,18 +28,48 @@ pub fn show_about_dialog(parent: &Window) {
         });
     };
 
-    let darkxero_label = extract_widget::<Label>(&builder, "darkxero_donate_label");
+    let darkxero_label = expect_widget::<Label>(&builder, "darkxero_donate_label");
     setup_link_handler(&darkxero_label);
 
-    let synse_label = extract_widget::<Label>(&builder, "synse_donate_label");
+    let synse_label = expect_widget::<Label>(&builder, "synse_donate_label");
     setup_link_handler(&synse_label);
 
-    let version_label = extract_widget::<Label>(&builder, "version_label");
+    let version_label = expect_widget::<Label>(&builder, "version_label");
     version_label.set_label(&format!(
         "Version {}",
         crate::config::constants::app_info::VERSION
     ));
 
+    // Probe in the background, then copy the same report as the system
+    // information page
+    let copy_report_button: Button = expect_widget(&builder, "copy_report_button");
+    copy_report_button.connect_clicked(|button| {
+        button.set_sensitive(false);
+        button.set_label(&gettext("Collecting..."));
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(sysinfo::probe().render());
+        });
+
+        let button = button.clone();
+        glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
+            match receiver.try_recv() {
+                Ok(report) => {
+                    button.clipboard().set_text(&report);
+                    log::info!("Copied system report to clipboard");
+                    button.set_label(&gettext("Copied"));
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    button.set_label(&gettext("Copy System Report"));
+                }
+            }
+            button.set_sensitive(true);
+            glib::ControlFlow::Break
+        });
+    });
+
     // Set dialog as transient for parent
     dialog.set_transient_for(Some(parent));
 
@@ -0,0 +1,209 @@
+//! Command palette for jumping to pages and action buttons (Ctrl+K).
+
+use crate::core::actions;
+use crate::core::search::{self, SearchEntry};
+use crate::i18n::gettext;
+use crate::ui::navigation;
+use crate::ui::utils::{builder_from_resource, expect_widget};
+use adw::prelude::*;
+use gtk4::glib;
+use gtk4::{Box as GtkBox, ListBox, SearchEntry as GtkSearchEntry, Stack, Window};
+use log::{info, warn};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// How long the highlighted button stays outlined.
+const HIGHLIGHT_DURATION: Duration = Duration::from_millis(1500);
+
+/// How long to wait for a lazily loaded page to contain the button.
+const PAGE_LOAD_ATTEMPTS: u32 = 30;
+
+/// Searchable pages and actions, skipping pages unavailable on this system.
+fn search_entries() -> Vec<SearchEntry> {
+    let pages = navigation::available_pages().map(|page| SearchEntry {
+        title: gettext(page.title),
+        subtitle: gettext("Page"),
+        keywords: page.keywords,
+        page: page.id,
+        button: None,
+    });
+
+    let actions = actions::ACTIONS
+        .iter()
+        .filter(|action| navigation::available_pages().any(|page| page.id == action.location.0))
+        .map(|action| SearchEntry {
+            title: gettext(action.title),
+            subtitle: gettext(action.description),
+            keywords: action.keywords,
+            page: action.location.0,
+            button: Some(action.location.1),
+        });
+
+    pages.chain(actions).collect()
+}
+
+/// Show the command palette.
+///
+/// Typing filters the results, arrow keys move through them, Enter opens
+/// the selected one and Escape closes the palette.
+pub fn show_command_palette(parent: &Window, tabs_container: &GtkBox, stack: &Stack) {
+    info!("Opening command palette");
+
+    let builder = builder_from_resource(crate::config::resources::dialogs::COMMAND_PALETTE);
+    let dialog: adw::Dialog = expect_widget(&builder, "command_palette_dialog");
+    let search_entry: GtkSearchEntry = expect_widget(&builder, "search_entry");
+    let results_list: ListBox = expect_widget(&builder, "results_list");
+    let results_scroll: gtk4::ScrolledWindow = expect_widget(&builder, "results_scroll");
+    let empty_label: gtk4::Label = expect_widget(&builder, "empty_label");
+
+    let entries = search_entries();
+    let shown: Rc<RefCell<Vec<SearchEntry>>> = Rc::new(RefCell::new(Vec::new()));
+
+    let update_results = {
+        let shown = shown.clone();
+        let results_list = results_list.clone();
+        move |query: &str| {
+            let matches: Vec<SearchEntry> =
+                search::rank(query, &entries).into_iter().cloned().collect();
+
+            results_list.remove_all();
+            for entry in &matches {
+                let row = adw::ActionRow::builder()
+                    .title(glib::markup_escape_text(&entry.title))
+                    .subtitle(glib::markup_escape_text(&entry.subtitle))
+                    .activatable(true)
+                    .build();
+                results_list.append(&row);
+            }
+            results_list.select_row(results_list.row_at_index(0).as_ref());
+
+            results_scroll.set_visible(!matches.is_empty());
+            empty_label.set_visible(matches.is_empty());
+            *shown.borrow_mut() = matches;
+        }
+    };
+    update_results("");
+
+    let update_results = Rc::new(update_results);
+    search_entry.connect_search_changed(move |entry| {
+        update_results(&entry.text());
+    });
+
+    // Typing while a result has focus goes back to the search entry
+    search_entry.set_key_capture_widget(Some(&results_list));
+
+    // Down moves focus into the results; the list handles arrows from there
+    let key_controller = gtk4::EventControllerKey::new();
+    let results_list_clone = results_list.clone();
+    key_controller.connect_key_pressed(move |_, key, _, _| {
+        if key != gtk4::gdk::Key::Down {
+            return glib::Propagation::Proceed;
+        }
+        let row = results_list_clone
+            .selected_row()
+            .or_else(|| results_list_clone.row_at_index(0));
+        if let Some(row) = row {
+            let next = results_list_clone
+                .row_at_index(row.index() + 1)
+                .unwrap_or(row);
+            results_list_clone.select_row(Some(&next));
+            next.grab_focus();
+        }
+        glib::Propagation::Stop
+    });
+    search_entry.add_controller(key_controller);
+
+    let open = {
+        let dialog = dialog.clone();
+        let tabs_container = tabs_container.clone();
+        let stack = stack.clone();
+        let shown = shown.clone();
+        move |index: i32| {
+            let Some(entry) = usize::try_from(index)
+                .ok()
+                .and_then(|index| shown.borrow().get(index).cloned())
+            else {
+                return;
+            };
+            dialog.close();
+            open_entry(&tabs_container, &stack, &entry);
+        }
+    };
+    let open = Rc::new(open);
+
+    let open_clone = open.clone();
+    results_list.connect_row_activated(move |_, row| {
+        open_clone(row.index());
+    });
+
+    let results_list_clone = results_list.clone();
+    search_entry.connect_activate(move |_| {
+        if let Some(row) = results_list_clone.selected_row() {
+            open(row.index());
+        }
+    });
+
+    let dialog_clone = dialog.clone();
+    search_entry.connect_stop_search(move |_| {
+        dialog_clone.close();
+    });
+
+    dialog.present(Some(parent));
+    search_entry.grab_focus();
+}
+
+/// Navigate to the entry's page and highlight its button.
+fn open_entry(tabs_container: &GtkBox, stack: &Stack, entry: &SearchEntry) {
+    info!("Command palette: opening '{}'", entry.title);
+    navigation::open_page(tabs_container, entry.page);
+
+    if let Some(button_id) = entry.button {
+        highlight_button(stack, entry.page, button_id);
+    }
+}
+
+/// Focus and outline a button once its page has finished loading.
+fn highlight_button(stack: &Stack, page_id: &'static str, button_id: &'static str) {
+    let stack = stack.clone();
+    let mut attempts = 0;
+
+    glib::timeout_add_local(Duration::from_millis(100), move || {
+        attempts += 1;
+
+        let button = stack
+            .child_by_name(page_id)
+            .and_then(|page| find_by_buildable_id(&page, button_id));
+
+        let Some(button) = button else {
+            if attempts >= PAGE_LOAD_ATTEMPTS {
+                warn!("Button '{}' not found on page '{}'", button_id, page_id);
+                return glib::ControlFlow::Break;
+            }
+            return glib::ControlFlow::Continue;
+        };
+
+        button.grab_focus();
+        button.add_css_class("search-highlight");
+        glib::timeout_add_local_once(HIGHLIGHT_DURATION, move || {
+            button.remove_css_class("search-highlight");
+        });
+        glib::ControlFlow::Break
+    });
+}
+
+/// Find a descendant created by a `Builder` with the given object id.
+fn find_by_buildable_id(widget: &gtk4::Widget, id: &str) -> Option<gtk4::Widget> {
+    if widget.buildable_id().as_deref() == Some(id) {
+        return Some(widget.clone());
+    }
+
+    let mut child = widget.first_child();
+    while let Some(current) = child {
+        if let Some(found) = find_by_buildable_id(&current, id) {
+            return Some(found);
+        }
+        child = current.next_sibling();
+    }
+    None
+}
@@ -0,0 +1,77 @@
+//! Dialog offering the crash report left behind by the previous run.
+
+use crate::config::user;
+use crate::core::crash;
+use crate::core::package;
+use crate::i18n::{self, gettext};
+use adw::prelude::*;
+use gtk4::ApplicationWindow;
+use log::{info, warn};
+
+/// Show the newest unreported crash, if any, and mark all reports as shown.
+pub fn show_pending_report(window: &ApplicationWindow) {
+    let config = user::shared();
+    if config.get().warnings.dismissed_crash_reports {
+        return;
+    }
+
+    let Some(path) = crash::latest_unreported() else {
+        return;
+    };
+    let report = match std::fs::read_to_string(&path) {
+        Ok(report) => report,
+        Err(e) => {
+            warn!("Failed to read crash report {}: {}", path.display(), e);
+            return;
+        }
+    };
+    // Offered once, whatever the user picks
+    crash::mark_all_reported();
+    info!("Showing crash report {}", path.display());
+
+    let message = crash::report_message(&report)
+        .unwrap_or_default()
+        .to_string();
+    let dialog = adw::AlertDialog::new(
+        Some(&gettext("Xero Toolkit Crashed")),
+        Some(&i18n::fill(
+            &gettext(
+                "The last session ended unexpectedly:\n\n{0}\n\n\
+                 A report was saved to {1}. Opening an issue copies it to the \
+                 clipboard so it can be pasted into the description.",
+            ),
+            &[&message, &path.display().to_string()],
+        )),
+    );
+    dialog.add_response("close", &gettext("Close"));
+    dialog.add_response("copy", &gettext("Copy Report"));
+    dialog.add_response("issue", &gettext("Open GitHub Issue"));
+    dialog.set_response_appearance("issue", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("issue"));
+    dialog.set_close_response("close");
+
+    let dismiss_check = gtk4::CheckButton::with_label(&gettext("Don't show crash reports again"));
+    dismiss_check.set_halign(gtk4::Align::Center);
+    dialog.set_extra_child(Some(&dismiss_check));
+
+    let window_clone = window.clone();
+    dialog.connect_response(None, move |_, response| {
+        if dismiss_check.is_active() {
+            info!("User disabled crash report dialog");
+            user::shared().update(|config| config.warnings.dismissed_crash_reports = true);
+        }
+
+        match response {
+            "copy" => window_clone.clipboard().set_text(&report),
+            "issue" => {
+                window_clone.clipboard().set_text(&report);
+                if let Err(e) = package::open_url(&crash::issue_url(&message)) {
+                    warn!("Failed to open issue page: {}", e);
+                }
+            }
+            _ => {}
+        }
+    });
+
+    dialog.present(Some(window));
+}
@@ -0,0 +1,59 @@
+//! Confirmation dialog previewing file edits as a diff.
+
+use crate::i18n::gettext;
+use adw::prelude::*;
+use gtk4::Window;
+use log::info;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Show the unified `diff` of pending file changes. Calls `on_confirm` if
+/// the user applies them.
+pub fn show_diff_confirmation<F>(parent: &Window, heading: &str, diff: &str, on_confirm: F)
+where
+    F: FnOnce() + 'static,
+{
+    info!("Showing diff confirmation dialog: {}", heading);
+
+    let text_view = gtk4::TextView::builder()
+        .editable(false)
+        .cursor_visible(false)
+        .monospace(true)
+        .wrap_mode(gtk4::WrapMode::None)
+        .top_margin(6)
+        .bottom_margin(6)
+        .left_margin(6)
+        .right_margin(6)
+        .build();
+    text_view.buffer().set_text(diff);
+
+    let scrolled = gtk4::ScrolledWindow::builder()
+        .child(&text_view)
+        .min_content_height(260)
+        .min_content_width(480)
+        .css_classes(["card"])
+        .build();
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(heading)
+        .body(gettext(
+            "These changes will be written. Each changed file is backed up first.",
+        ))
+        .extra_child(&scrolled)
+        .build();
+    dialog.add_response("cancel", &gettext("Cancel"));
+    dialog.add_response("apply", &gettext("Apply"));
+    dialog.set_response_appearance("apply", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("apply"));
+    dialog.set_close_response("cancel");
+
+    let on_confirm = Rc::new(RefCell::new(Some(on_confirm)));
+    dialog.connect_response(Some("apply"), move |_, _| {
+        info!("Diff confirmation accepted");
+        if let Some(on_confirm) = on_confirm.borrow_mut().take() {
+            on_confirm();
+        }
+    });
+
+    dialog.present(Some(parent));
+}
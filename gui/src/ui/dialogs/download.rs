@@ -1,31 +1,122 @@
 //! Download dialog for showing download progress
 
 use crate::core::download::{
-    download_file, fetch_arch_iso_info, format_bytes, format_speed, format_time_remaining,
-    DownloadState,
+    discard_partial, download_file, fetch_arch_iso_info, fetch_remote_size, find_resumable,
+    format_bytes, format_speed, format_time_remaining, sha256, usb_writer_command, DownloadState,
+    ResumableDownload,
 };
-use crate::ui::utils::extract_widget;
-use gtk4::glib;
+use crate::core::events::{self, Event};
+use crate::core::fs::{self, TargetProblem};
+use crate::core::paths;
+use crate::i18n::{self, gettext};
+use crate::ui::dialogs::warning::show_warning_confirmation;
+use crate::ui::utils::{builder_from_resource, extract_widget, WidgetError};
 use gtk4::prelude::*;
-use gtk4::{Button, Entry, Image, Label, ProgressBar, Window};
-use log::{error, info};
+use gtk4::{gio, glib};
+use gtk4::{Box as GtkBox, Button, Entry, Image, Label, ProgressBar, Window};
+use log::{error, info, warn};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
 use std::sync::Arc;
 
 /// Show the download setup dialog for Arch ISO
+///
+/// Offers to continue an interrupted download in the download folder first.
 pub fn show_download_dialog(parent: &Window) {
+    if let Some(download) = find_resumable(&iso_folder()).into_iter().next() {
+        offer_resume(parent, download);
+        return;
+    }
+
     info!("Opening Arch ISO download setup dialog");
 
+    if let Err(e) = open_setup_dialog(parent) {
+        show_load_error(parent, &e);
+    }
+}
+
+/// Folder new ISO images are saved to, from the download preferences.
+fn iso_folder() -> PathBuf {
+    crate::config::user::shared()
+        .get()
+        .downloads
+        .iso_folder_or_default(&paths::get().downloads)
+}
+
+/// Ask whether to continue `download`, discard it or keep it and start a new
+/// download.
+fn offer_resume(parent: &Window, download: ResumableDownload) {
+    use adw::prelude::*;
+
+    let file_name = download.file_name();
+    let progress = if download.state.total > 0 {
+        i18n::fill(
+            &gettext("{} of {}"),
+            &[
+                &format_bytes(download.downloaded),
+                &format_bytes(download.state.total),
+            ],
+        )
+    } else {
+        format_bytes(download.downloaded)
+    };
+
+    let dialog = adw::AlertDialog::new(
+        Some(&gettext("Continue Download?")),
+        Some(&i18n::fill(
+            &gettext("The download of {} stopped after {}. Continue where it left off?"),
+            &[&file_name, &progress],
+        )),
+    );
+    dialog.add_response("new", &gettext("New Download"));
+    dialog.add_response("discard", &gettext("Discard"));
+    dialog.add_response("resume", &gettext("Continue"));
+    dialog.set_response_appearance("discard", adw::ResponseAppearance::Destructive);
+    dialog.set_response_appearance("resume", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("resume"));
+    dialog.set_close_response("cancel");
+
+    let parent = parent.clone();
+    glib::spawn_future_local(async move {
+        match dialog.choose_future(Some(&parent)).await.as_str() {
+            "resume" => {
+                info!("Continuing download of {}", file_name);
+                start_download(
+                    &parent,
+                    file_name,
+                    download.state.url,
+                    download.path.to_string_lossy().to_string(),
+                    None,
+                );
+            }
+            "discard" => {
+                info!("Discarding partial download of {}", file_name);
+                discard_partial(&download.path);
+                show_download_dialog(&parent);
+            }
+            "new" => {
+                if let Err(e) = open_setup_dialog(&parent) {
+                    show_load_error(&parent, &e);
+                }
+            }
+            _ => {}
+        }
+    });
+}
+
+fn open_setup_dialog(parent: &Window) -> Result<(), WidgetError> {
     // Load the setup UI
-    let builder = gtk4::Builder::from_resource(crate::config::resources::dialogs::DOWNLOAD_SETUP);
+    let builder = builder_from_resource(crate::config::resources::dialogs::DOWNLOAD_SETUP);
 
-    let window: adw::Window = extract_widget(&builder, "download_setup_window");
-    let version_label: Label = extract_widget(&builder, "version_label");
-    let download_path_entry: Entry = extract_widget(&builder, "download_path_entry");
-    let browse_button: Button = extract_widget(&builder, "browse_button");
-    let cancel_button: Button = extract_widget(&builder, "cancel_button");
-    let start_download_button: Button = extract_widget(&builder, "start_download_button");
-    let fetching_spinner: Image = extract_widget(&builder, "fetching_spinner");
+    let window: adw::Window = extract_widget(&builder, "download_setup_window")?;
+    let version_label: Label = extract_widget(&builder, "version_label")?;
+    let download_path_entry: Entry = extract_widget(&builder, "download_path_entry")?;
+    let download_path_error: Label = extract_widget(&builder, "download_path_error")?;
+    let browse_button: Button = extract_widget(&builder, "browse_button")?;
+    let cancel_button: Button = extract_widget(&builder, "cancel_button")?;
+    let start_download_button: Button = extract_widget(&builder, "start_download_button")?;
+    let fetching_spinner: Image = extract_widget(&builder, "fetching_spinner")?;
 
     window.set_transient_for(Some(parent));
 
@@ -34,6 +125,7 @@ pub fn show_download_dialog(parent: &Window) {
         Arc::new(std::sync::Mutex::new(None));
     let selected_path: Arc<std::sync::Mutex<Option<String>>> =
         Arc::new(std::sync::Mutex::new(None));
+    let iso_size: Arc<std::sync::Mutex<Option<u64>>> = Arc::new(std::sync::Mutex::new(None));
 
     // Setup cancel button
     let window_clone = window.clone();
@@ -41,15 +133,39 @@ pub fn show_download_dialog(parent: &Window) {
         window_clone.close();
     });
 
+    // Check every path set against the filesystem it would be saved to
+    let start_download_button_clone = start_download_button.clone();
+    let iso_size_clone = iso_size.clone();
+    download_path_entry.connect_changed(move |entry| {
+        let save_path = entry.text();
+        let problem = iso_size_clone
+            .lock()
+            .unwrap()
+            .and_then(|size| target_problem(Path::new(save_path.as_str()), size));
+        match problem {
+            Some(message) => {
+                download_path_error.set_text(&message);
+                download_path_error.set_visible(true);
+                entry.add_css_class("error");
+                start_download_button_clone.set_sensitive(false);
+            }
+            None => {
+                download_path_error.set_visible(false);
+                entry.remove_css_class("error");
+                start_download_button_clone.set_sensitive(!save_path.is_empty());
+            }
+        }
+    });
+
     // Create a channel for ISO info fetching
-    let (tx, rx) = std::sync::mpsc::channel::<Result<(String, String), String>>();
+    let (tx, rx) = std::sync::mpsc::channel::<Result<(String, String, Option<u64>), String>>();
 
     // Clone for the receiver
     let version_label_clone = version_label.clone();
     let browse_button_clone = browse_button.clone();
-    let start_download_button_clone = start_download_button.clone();
     let download_path_entry_clone = download_path_entry.clone();
     let iso_info_clone = iso_info.clone();
+    let iso_size_clone = iso_size.clone();
     let selected_path_clone = selected_path.clone();
     let fetching_spinner_clone = fetching_spinner.clone();
 
@@ -58,7 +174,7 @@ pub fn show_download_dialog(parent: &Window) {
         match rx.try_recv() {
             Ok(result) => {
                 match result {
-                    Ok((iso_name, download_url)) => {
+                    Ok((iso_name, download_url, size)) => {
                         info!("Fetched ISO info: {}", iso_name);
 
                         // Parse version from filename (archlinux-YYYY.MM.DD-x86_64.iso)
@@ -77,21 +193,17 @@ pub fn show_download_dialog(parent: &Window) {
 
                         // Store ISO info
                         *iso_info_clone.lock().unwrap() = Some((iso_name.clone(), download_url));
+                        *iso_size_clone.lock().unwrap() = size;
 
                         // Enable browse button
                         browse_button_clone.set_sensitive(true);
 
-                        // Set default download path
-                        let default_path = format!(
-                            "{}/Downloads/{}",
-                            crate::config::env::get().home.clone(),
-                            iso_name
-                        );
+                        // Set default download path, which enables the start
+                        // button once it passes the checks
+                        let default_path =
+                            iso_folder().join(&iso_name).to_string_lossy().to_string();
+                        *selected_path_clone.lock().unwrap() = Some(default_path.clone());
                         download_path_entry_clone.set_text(&default_path);
-                        *selected_path_clone.lock().unwrap() = Some(default_path);
-
-                        // Enable start button
-                        start_download_button_clone.set_sensitive(true);
                     }
                     Err(e) => {
                         error!("Failed to fetch ISO info: {}", e);
@@ -99,7 +211,7 @@ pub fn show_download_dialog(parent: &Window) {
                         // Show error state
                         fetching_spinner_clone.remove_css_class("spinning");
                         fetching_spinner_clone.set_icon_name(Some("circle-xmark"));
-                        version_label_clone.set_text("Failed to fetch version");
+                        version_label_clone.set_text(&gettext("Failed to fetch version"));
                         version_label_clone.remove_css_class("accent");
                         version_label_clone.add_css_class("error");
                     }
@@ -113,7 +225,7 @@ pub fn show_download_dialog(parent: &Window) {
                 // Show error state
                 fetching_spinner_clone.remove_css_class("spinning");
                 fetching_spinner_clone.set_icon_name(Some("circle-xmark"));
-                version_label_clone.set_text("Failed to fetch version");
+                version_label_clone.set_text(&gettext("Failed to fetch version"));
                 version_label_clone.remove_css_class("accent");
                 version_label_clone.add_css_class("error");
 
@@ -123,16 +235,20 @@ pub fn show_download_dialog(parent: &Window) {
     });
 
     // Spawn thread to fetch ISO info
+    let mirror = crate::config::user::shared().get().downloads.mirror.clone();
     std::thread::spawn(move || {
         let runtime = tokio::runtime::Runtime::new().unwrap();
-        let result = runtime.block_on(async { fetch_arch_iso_info().await });
-        let result: Result<(String, String), String> = result.map_err(|e| e.to_string());
+        let result = runtime.block_on(async {
+            let (iso_name, download_url) = fetch_arch_iso_info(&mirror).await?;
+            let size = fetch_remote_size(&download_url).await;
+            anyhow::Ok((iso_name, download_url, size))
+        });
+        let result = result.map_err(|e| e.to_string());
         let _ = tx.send(result);
     });
 
     // Setup browse button
     let download_path_entry_clone = download_path_entry.clone();
-    let start_download_button_clone = start_download_button.clone();
     let selected_path_clone = selected_path.clone();
     let window_clone = window.clone();
     let iso_info_clone = iso_info.clone();
@@ -142,9 +258,16 @@ pub fn show_download_dialog(parent: &Window) {
         if let Some((iso_name, _)) = iso_info_guard.as_ref() {
             let dialog = gtk4::FileDialog::new();
             dialog.set_initial_name(Some(iso_name));
+            // Start where the entry points, if that folder exists yet
+            let entry_text = download_path_entry_clone.text();
+            if let Some(folder) = Path::new(entry_text.as_str())
+                .parent()
+                .filter(|folder| folder.is_dir())
+            {
+                dialog.set_initial_folder(Some(&gio::File::for_path(folder)));
+            }
 
             let download_path_entry = download_path_entry_clone.clone();
-            let start_download_button = start_download_button_clone.clone();
             let selected_path = selected_path_clone.clone();
             let window = window_clone.clone();
 
@@ -153,9 +276,8 @@ pub fn show_download_dialog(parent: &Window) {
                     Ok(file) => {
                         if let Some(path) = file.path() {
                             let path_str = path.to_string_lossy().to_string();
+                            *selected_path.lock().unwrap() = Some(path_str.clone());
                             download_path_entry.set_text(&path_str);
-                            *selected_path.lock().unwrap() = Some(path_str);
-                            start_download_button.set_sensitive(true);
                         }
                     }
                     Err(_) => {
@@ -177,6 +299,25 @@ pub fn show_download_dialog(parent: &Window) {
         if let (Some((iso_name, download_url)), Some(save_path)) =
             (iso_info_guard.as_ref(), selected_path_guard.as_ref())
         {
+            // Created here, so a missing or read-only folder fails before
+            // the download starts
+            let folder = Path::new(save_path).parent().unwrap_or(Path::new("/"));
+            if let Err(e) = paths::ensure_writable_dir(folder) {
+                error!("Cannot save to {}: {}", folder.display(), e);
+                show_error_dialog(
+                    window_clone.upcast_ref(),
+                    &gettext("Folder Not Writable"),
+                    &i18n::fill(
+                        &gettext("The ISO image cannot be saved to {}.\n\n{}"),
+                        &[&folder.display().to_string(), &e.to_string()],
+                    ),
+                );
+                return;
+            }
+            let last_folder = folder.to_string_lossy().to_string();
+            crate::config::user::shared()
+                .update(|config| config.downloads.last_folder = Some(last_folder));
+
             info!("Starting download: {} -> {}", iso_name, save_path);
             window_clone.close();
             start_download(
@@ -184,31 +325,101 @@ pub fn show_download_dialog(parent: &Window) {
                 iso_name.clone(),
                 download_url.clone(),
                 save_path.clone(),
+                None,
             );
         }
     });
 
     window.present();
+    Ok(())
+}
+
+/// Why an ISO image of `size` bytes cannot be saved to `save_path`, with a
+/// folder to save it to instead, or `None` when it fits.
+fn target_problem(save_path: &Path, size: u64) -> Option<String> {
+    let folder = save_path.parent().unwrap_or(Path::new("/"));
+    let reason = match fs::check_target(folder, size) {
+        Ok(None) => return None,
+        Ok(Some(TargetProblem::FileTooLarge { fs_type, limit })) => {
+            info!(
+                "{} is on {:?}, which holds files up to {} bytes",
+                folder.display(),
+                fs_type,
+                limit
+            );
+            i18n::fill(
+                &gettext("The ISO image ({}) is larger than the {} a FAT32 drive holds per file."),
+                &[&format_bytes(size), &format_bytes(limit)],
+            )
+        }
+        Ok(Some(TargetProblem::NotEnoughSpace { available })) => i18n::fill(
+            &gettext("The ISO image needs {}, but only {} is free on this drive."),
+            &[&format_bytes(size), &format_bytes(available)],
+        ),
+        Err(e) => {
+            // Left to the start button, which reports an unusable folder
+            warn!("Failed to check {}: {}", folder.display(), e);
+            return None;
+        }
+    };
+
+    let alternative = [iso_folder(), paths::get().downloads.clone()]
+        .into_iter()
+        .find(|other| other != folder && matches!(fs::check_target(other, size), Ok(None)));
+    Some(match alternative {
+        Some(other) => i18n::fill(
+            &gettext("{} Save it to {} instead."),
+            &[&reason, &other.display().to_string()],
+        ),
+        None => i18n::fill(&gettext("{} Choose a folder on another drive."), &[&reason]),
+    })
 }
 
 /// Start the actual download with progress dialog
-fn start_download(parent: &Window, iso_name: String, download_url: String, save_path: String) {
-    // Load the UI
-    let builder = gtk4::Builder::from_resource(crate::config::resources::dialogs::DOWNLOAD);
+///
+/// When `on_complete` is given, the dialog closes itself after a successful
+/// download and hands control to the callback instead of waiting for the user.
+pub fn start_download(
+    parent: &Window,
+    file_name: String,
+    download_url: String,
+    save_path: String,
+    on_complete: Option<Box<dyn FnOnce()>>,
+) {
+    if let Err(e) = open_progress_dialog(parent, file_name, download_url, save_path, on_complete) {
+        show_load_error(parent, &e);
+    }
+}
 
-    let window: adw::Window = extract_widget(&builder, "download_window");
-    let filename_label: Label = extract_widget(&builder, "filename_label");
-    let progress_bar: ProgressBar = extract_widget(&builder, "progress_bar");
-    let speed_label: Label = extract_widget(&builder, "speed_label");
-    let downloaded_label: Label = extract_widget(&builder, "downloaded_label");
-    let time_remaining_label: Label = extract_widget(&builder, "time_remaining_label");
-    let pause_button: Button = extract_widget(&builder, "pause_button");
-    let cancel_button: Button = extract_widget(&builder, "cancel_button");
+fn open_progress_dialog(
+    parent: &Window,
+    file_name: String,
+    download_url: String,
+    save_path: String,
+    on_complete: Option<Box<dyn FnOnce()>>,
+) -> Result<(), WidgetError> {
+    // Load the UI
+    let builder = builder_from_resource(crate::config::resources::dialogs::DOWNLOAD);
+
+    let window: adw::Window = extract_widget(&builder, "download_window")?;
+    let filename_label: Label = extract_widget(&builder, "filename_label")?;
+    let progress_bar: ProgressBar = extract_widget(&builder, "progress_bar")?;
+    let speed_label: Label = extract_widget(&builder, "speed_label")?;
+    let downloaded_label: Label = extract_widget(&builder, "downloaded_label")?;
+    let time_remaining_label: Label = extract_widget(&builder, "time_remaining_label")?;
+    let pause_button: Button = extract_widget(&builder, "pause_button")?;
+    let cancel_button: Button = extract_widget(&builder, "cancel_button")?;
+    let toast_overlay: adw::ToastOverlay = extract_widget(&builder, "toast_overlay")?;
+    let completion_actions: GtkBox = extract_widget(&builder, "completion_actions")?;
+    let open_folder_button: Button = extract_widget(&builder, "open_folder_button")?;
+    let copy_checksum_button: Button = extract_widget(&builder, "copy_checksum_button")?;
+    let write_usb_button: Button = extract_widget(&builder, "write_usb_button")?;
+    let delete_file_button: Button = extract_widget(&builder, "delete_file_button")?;
 
     window.set_transient_for(Some(parent));
 
     // Set filename
-    filename_label.set_text(&iso_name);
+    filename_label.set_text(&file_name);
 
     // Create control flags
     let pause_flag = Arc::new(AtomicBool::new(false));
@@ -222,9 +433,9 @@ fn start_download(parent: &Window, iso_name: String, download_url: String, save_
         pause_flag_clone.store(!is_paused, Ordering::Relaxed);
 
         if is_paused {
-            pause_button_clone.set_label("Pause");
+            pause_button_clone.set_label(&gettext("Pause"));
         } else {
-            pause_button_clone.set_label("Resume");
+            pause_button_clone.set_label(&gettext("Resume"));
         }
     });
 
@@ -239,6 +450,20 @@ fn start_download(parent: &Window, iso_name: String, download_url: String, save_
     // Use a channel to send progress updates from download thread to UI thread
     let (tx, rx) = std::sync::mpsc::channel::<DownloadMessage>();
 
+    // The completion buttons only send their message, acted on once completed
+    for (button, message) in [
+        (&open_folder_button, DownloadMessage::OpenFolder),
+        (&copy_checksum_button, DownloadMessage::CopyChecksum),
+        (&write_usb_button, DownloadMessage::WriteToUsb),
+        (&delete_file_button, DownloadMessage::Delete),
+    ] {
+        let tx = tx.clone();
+        button.connect_clicked(move |_| {
+            let _ = tx.send(message.clone());
+        });
+    }
+    let tx_actions = tx.clone();
+
     // Clone for the result callback
     let window_clone = window.clone();
     let pause_button_clone = pause_button.clone();
@@ -247,9 +472,16 @@ fn start_download(parent: &Window, iso_name: String, download_url: String, save_
     let progress_bar_clone = progress_bar.clone();
     let speed_label_clone = speed_label.clone();
     let time_remaining_label_clone = time_remaining_label.clone();
+    let saved_path = PathBuf::from(&save_path);
+    let mut on_complete = on_complete;
+    let mut completed: Option<CompletedFile> = None;
 
     // Set up a timer to check for messages
     glib::timeout_add_local(std::time::Duration::from_millis(50), move || {
+        // Kept running after completion for the completion buttons
+        if completed.is_some() && !window_clone.is_visible() {
+            return glib::ControlFlow::Break;
+        }
         // Try to receive all pending messages
         while let Ok(msg) = rx.try_recv() {
             match msg {
@@ -276,7 +508,7 @@ fn start_download(parent: &Window, iso_name: String, download_url: String, save_
                     // Update time remaining - only show if download is not complete
                     if state.downloaded >= state.total && state.total > 0 {
                         // Download is complete, show completion status
-                        time_remaining_label.set_text("Completed");
+                        time_remaining_label.set_text(&gettext("Completed"));
                         time_remaining_label.add_css_class("success");
                     } else {
                         let time_remaining = if state.speed > 0.0 {
@@ -291,6 +523,15 @@ fn start_download(parent: &Window, iso_name: String, download_url: String, save_
                 }
                 DownloadMessage::Completed => {
                     info!("Download completed successfully");
+                    events::emit(Event::DownloadFinished {
+                        path: saved_path.clone(),
+                    });
+
+                    if let Some(callback) = on_complete.take() {
+                        window_clone.close();
+                        callback();
+                        return glib::ControlFlow::Break;
+                    }
 
                     // Update UI to show completion
                     progress_bar_clone.set_fraction(1.0);
@@ -299,14 +540,20 @@ fn start_download(parent: &Window, iso_name: String, download_url: String, save_
                     speed_label_clone.set_text("-");
                     speed_label_clone.remove_css_class("success");
 
-                    time_remaining_label_clone.set_text("Completed");
+                    time_remaining_label_clone.set_text(&gettext("Completed"));
                     time_remaining_label_clone.add_css_class("success");
 
                     pause_button_clone.set_sensitive(false);
-                    cancel_button_clone.set_label("Close");
+                    cancel_button_clone.set_label(&gettext("Close"));
                     cancel_button_clone.add_css_class("suggested-action");
 
-                    return glib::ControlFlow::Break;
+                    write_usb_button.set_visible(usb_writer_command(&saved_path).is_some());
+                    completion_actions.set_visible(true);
+                    completed = Some(CompletedFile {
+                        path: saved_path.clone(),
+                        checksum: None,
+                        hashing: false,
+                    });
                 }
                 DownloadMessage::Error(e) => {
                     error!("Download failed: {}", e);
@@ -316,12 +563,87 @@ fn start_download(parent: &Window, iso_name: String, download_url: String, save_
                     window_clone.close();
                     return glib::ControlFlow::Break;
                 }
+                // The buttons are hidden until the download completed
+                _ if completed.is_none() => {}
+                DownloadMessage::OpenFolder => {
+                    let Some(file) = &completed else { continue };
+                    open_folder(&file.path);
+                }
+                DownloadMessage::CopyChecksum => {
+                    let Some(file) = &mut completed else { continue };
+                    if let Some(checksum) = &file.checksum {
+                        copy_checksum_button.clipboard().set_text(checksum);
+                        toast_overlay.add_toast(adw::Toast::new(&gettext("SHA-256 copied")));
+                    } else if !file.hashing {
+                        file.hashing = true;
+                        copy_checksum_button.set_sensitive(false);
+                        copy_checksum_button.set_label(&gettext("Computing…"));
+                        compute_checksum(file.path.clone(), tx_actions.clone());
+                    }
+                }
+                DownloadMessage::Checksum(result) => {
+                    let Some(file) = &mut completed else { continue };
+                    file.hashing = false;
+                    copy_checksum_button.set_sensitive(true);
+                    copy_checksum_button.set_label(&gettext("Copy SHA-256"));
+                    match result {
+                        Ok(checksum) => {
+                            copy_checksum_button.clipboard().set_text(&checksum);
+                            toast_overlay.add_toast(adw::Toast::new(&gettext("SHA-256 copied")));
+                            file.checksum = Some(checksum);
+                        }
+                        Err(e) => {
+                            error!("Failed to compute checksum: {}", e);
+                            toast_overlay.add_toast(adw::Toast::new(&gettext(
+                                "The checksum could not be computed",
+                            )));
+                        }
+                    }
+                }
+                DownloadMessage::WriteToUsb => {
+                    let Some(file) = &completed else { continue };
+                    write_to_usb(&file.path);
+                }
+                DownloadMessage::Delete => {
+                    let Some(file) = &completed else { continue };
+                    let tx = tx_actions.clone();
+                    show_warning_confirmation(
+                        window_clone.upcast_ref(),
+                        &gettext("Delete File?"),
+                        i18n::fill(
+                            &gettext("{} will be deleted for good."),
+                            &[&file.path.display().to_string()],
+                        ),
+                        move || {
+                            let _ = tx.send(DownloadMessage::DeleteConfirmed);
+                        },
+                    );
+                }
+                DownloadMessage::DeleteConfirmed => {
+                    let Some(file) = &completed else { continue };
+                    match std::fs::remove_file(&file.path) {
+                        Ok(()) => {
+                            info!("Deleted {}", file.path.display());
+                            window_clone.close();
+                            return glib::ControlFlow::Break;
+                        }
+                        Err(e) => {
+                            error!("Failed to delete {}: {}", file.path.display(), e);
+                            show_error_dialog(
+                                window_clone.upcast_ref(),
+                                &gettext("Delete Failed"),
+                                &e.to_string(),
+                            );
+                        }
+                    }
+                }
             }
         }
         glib::ControlFlow::Continue
     });
 
     // Start download in background thread
+    let speed_limit = crate::config::user::shared().get().downloads.speed_limit();
     std::thread::spawn(move || {
         let runtime = tokio::runtime::Runtime::new().unwrap();
 
@@ -336,6 +658,7 @@ fn start_download(parent: &Window, iso_name: String, download_url: String, save_
                 },
                 pause_flag.clone(),
                 cancel_flag.clone(),
+                speed_limit,
             )
             .await;
 
@@ -352,13 +675,67 @@ fn start_download(parent: &Window, iso_name: String, download_url: String, save_
     });
 
     window.present();
+    Ok(())
 }
 
-/// Messages sent from download thread to UI thread
+/// Messages to the progress dialog, from the download thread and from the
+/// buttons offered once it completed
+#[derive(Clone)]
 enum DownloadMessage {
     Progress(DownloadState),
     Completed,
     Error(String),
+    OpenFolder,
+    /// Copy the checksum, computing it first if needed
+    CopyChecksum,
+    /// Result of computing the checksum
+    Checksum(Result<String, String>),
+    WriteToUsb,
+    /// Ask whether to delete the file
+    Delete,
+    DeleteConfirmed,
+}
+
+/// A downloaded file, with its checksum once computed.
+struct CompletedFile {
+    path: PathBuf,
+    checksum: Option<String>,
+    /// Whether the checksum is being computed
+    hashing: bool,
+}
+
+/// Open the folder holding `path` in the file manager.
+fn open_folder(path: &Path) {
+    let Some(folder) = path.parent() else {
+        return;
+    };
+    let uri = gio::File::for_path(folder).uri();
+    if let Err(e) = gio::AppInfo::launch_default_for_uri(&uri, None::<&gio::AppLaunchContext>) {
+        warn!("Failed to open {}: {}", folder.display(), e);
+    }
+}
+
+/// Compute the SHA-256 of `path` on a thread and send it back to the dialog.
+fn compute_checksum(path: PathBuf, tx: Sender<DownloadMessage>) {
+    std::thread::spawn(move || {
+        info!("Computing SHA-256 of {}", path.display());
+        let result = sha256(&path).map_err(|e| format!("{:#}", e));
+        let _ = tx.send(DownloadMessage::Checksum(result));
+    });
+}
+
+/// Open the installed USB image writer on `path`.
+fn write_to_usb(path: &Path) {
+    let Some(command) = usb_writer_command(path) else {
+        return;
+    };
+    info!("Opening USB writer: {:?}", command);
+    if let Err(e) = std::process::Command::new(&command[0])
+        .args(&command[1..])
+        .spawn()
+    {
+        warn!("Failed to start {}: {}", command[0], e);
+    }
 }
 
 /// Show an error dialog
@@ -366,7 +743,20 @@ fn show_error_dialog(parent: &Window, title: &str, message: &str) {
     use adw::prelude::*;
 
     let dialog = adw::AlertDialog::new(Some(title), Some(message));
-    dialog.add_response("ok", "OK");
+    dialog.add_response("ok", &gettext("OK"));
     dialog.set_default_response(Some("ok"));
     dialog.present(Some(parent));
 }
+
+/// Tell the user a download dialog could not be built.
+fn show_load_error(parent: &Window, e: &WidgetError) {
+    error!("Failed to build download dialog: {}", e);
+    show_error_dialog(
+        parent,
+        &gettext("Download Unavailable"),
+        &i18n::fill(
+            &gettext("The download window could not be opened.\n\n{}"),
+            &[&e.to_string()],
+        ),
+    );
+}
@@ -1,13 +1,13 @@
 //! Shared dialog helpers used across the UI pages.
 
+use crate::i18n::gettext;
 use adw::prelude::*;
 use adw::AlertDialog;
-use gtk4::ApplicationWindow;
 
 /// Show an error message dialog transient for the provided window.
-pub fn show_error(window: &ApplicationWindow, message: &str) {
+pub fn show_error(window: &impl IsA<gtk4::Widget>, message: &str) {
     let dialog = AlertDialog::builder()
-        .heading("Error")
+        .heading(gettext("Error"))
         .body(message)
         .build();
 
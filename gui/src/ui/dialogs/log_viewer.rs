@@ -0,0 +1,78 @@
+//! Log viewer showing the current application log.
+
+use crate::core::logs;
+use crate::i18n::{self, gettext};
+use crate::ui::utils::{builder_from_resource, expect_widget};
+use adw::prelude::*;
+use gtk4::{Button, DropDown, ScrolledWindow, TextView, Window};
+use log::{info, warn, LevelFilter};
+use std::rc::Rc;
+
+/// Minimum level for each entry of the level drop-down.
+const FILTER_LEVELS: [LevelFilter; 5] = [
+    LevelFilter::Trace,
+    LevelFilter::Error,
+    LevelFilter::Warn,
+    LevelFilter::Info,
+    LevelFilter::Debug,
+];
+
+/// Show the log viewer.
+pub fn show_log_viewer(parent: &Window) {
+    info!("Opening log viewer");
+
+    let builder = builder_from_resource(crate::config::resources::dialogs::LOG_VIEWER);
+    let dialog: adw::Dialog = expect_widget(&builder, "log_viewer_dialog");
+    let level_dropdown: DropDown = expect_widget(&builder, "level_dropdown");
+    let copy_button: Button = expect_widget(&builder, "copy_button");
+    let refresh_button: Button = expect_widget(&builder, "refresh_button");
+    let toast_overlay: adw::ToastOverlay = expect_widget(&builder, "toast_overlay");
+    let log_scroll: ScrolledWindow = expect_widget(&builder, "log_scroll");
+    let log_view: TextView = expect_widget(&builder, "log_view");
+
+    let reload = Rc::new({
+        let level_dropdown = level_dropdown.clone();
+        move || {
+            let content = match logs::read_log() {
+                Ok(content) => content,
+                Err(e) => {
+                    warn!("Failed to read log file: {}", e);
+                    i18n::fill(
+                        &gettext("Could not read the log file: {}"),
+                        &[&e.to_string()],
+                    )
+                }
+            };
+            let level = FILTER_LEVELS
+                .get(level_dropdown.selected() as usize)
+                .copied()
+                .unwrap_or(LevelFilter::Trace);
+
+            log_view
+                .buffer()
+                .set_text(&logs::filter_lines(&content, level));
+
+            // Newest entries are at the end
+            let adjustment = log_scroll.vadjustment();
+            gtk4::glib::idle_add_local_once(move || {
+                adjustment.set_value(adjustment.upper());
+            });
+        }
+    });
+    reload();
+
+    let reload_clone = reload.clone();
+    level_dropdown.connect_selected_notify(move |_| reload_clone());
+
+    refresh_button.connect_clicked(move |_| reload());
+
+    let log_view: TextView = expect_widget(&builder, "log_view");
+    copy_button.connect_clicked(move |button| {
+        let buffer = log_view.buffer();
+        let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+        button.clipboard().set_text(&text);
+        toast_overlay.add_toast(adw::Toast::new("Log copied to clipboard"));
+    });
+
+    dialog.present(Some(parent));
+}
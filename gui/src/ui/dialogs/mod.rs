@@ -2,14 +2,30 @@
 //!
 //! This module contains all dialog-related UI components:
 //! - `about`: About dialog with creator information
+//! - `command_palette`: Search across pages and actions
+//! - `crash_report`: Report of a crash from the previous run
+//! - `diff`: Confirmation of file edits with a diff preview
 //! - `error`: Simple error message dialogs
 //! - `selection`: Multi-choice selection dialogs
-//! - `download`: ISO download dialogs
+//! - `download`: ISO and file download dialogs
+//! - `log_viewer`: Viewer for the application log
+//! - `preferences`: Preferences backed by the user config
+//! - `profile`: Setup profile export and import
 //! - `terminal`: Interactive terminal dialogs
+//! - `wallpapers`: Wallpaper browser with previews
+//! - `welcome`: First-run welcome wizard
 
 pub mod about;
+pub mod command_palette;
+pub mod crash_report;
+pub mod diff;
 pub mod download;
 pub mod error;
+pub mod log_viewer;
+pub mod preferences;
+pub mod profile;
 pub mod selection;
 pub mod terminal;
+pub mod wallpapers;
 pub mod warning;
+pub mod welcome;
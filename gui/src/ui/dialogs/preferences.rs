@@ -0,0 +1,460 @@
+//! Preferences dialog backed by the user config.
+//!
+//! Every row writes straight to the shared config and applies its effect
+//! immediately where the setting allows it.
+
+use crate::config::user::{self, AurConfirmation, Config, SharedConfig};
+use crate::core::{logs, paths};
+use crate::i18n::gettext;
+use crate::ui::accessibility;
+use crate::ui::navigation;
+use crate::ui::seasonal;
+use crate::ui::task_runner;
+use crate::ui::utils::{builder_from_resource, expect_widget};
+use adw::prelude::*;
+use gtk4::glib;
+use gtk4::{Builder, Button, StringList};
+use log::info;
+use std::path::PathBuf;
+
+/// Rows whose values come from the config.
+#[derive(Clone)]
+struct PreferenceRows {
+    start_page: adw::ComboRow,
+    remember_window: adw::SwitchRow,
+    check_updates: adw::SwitchRow,
+    log_level: adw::ComboRow,
+    notifications: adw::SwitchRow,
+    auto_close: adw::SwitchRow,
+    confirm_destructive: adw::SwitchRow,
+    low_priority_aur: adw::SwitchRow,
+    aur_confirmation: adw::ComboRow,
+    battery_threshold: adw::SpinRow,
+    keep_failed_workspace: adw::SwitchRow,
+    mirror: adw::EntryRow,
+    speed_limit: adw::SpinRow,
+    download_folder: adw::ActionRow,
+    iso_folder: adw::ActionRow,
+    remember_last_folder: adw::SwitchRow,
+    seasonal_effects: adw::SwitchRow,
+    snow: adw::SwitchRow,
+    halloween: adw::SwitchRow,
+    fireworks: adw::SwitchRow,
+    intensity: gtk4::Scale,
+    reduce_motion: adw::SwitchRow,
+    idle_timeout: adw::SpinRow,
+    auth_summary: adw::SwitchRow,
+}
+
+impl PreferenceRows {
+    fn from_builder(builder: &Builder) -> Self {
+        Self {
+            start_page: expect_widget(builder, "start_page_row"),
+            remember_window: expect_widget(builder, "remember_window_row"),
+            check_updates: expect_widget(builder, "check_updates_row"),
+            log_level: expect_widget(builder, "log_level_row"),
+            notifications: expect_widget(builder, "notifications_row"),
+            auto_close: expect_widget(builder, "auto_close_row"),
+            confirm_destructive: expect_widget(builder, "confirm_destructive_row"),
+            low_priority_aur: expect_widget(builder, "low_priority_aur_row"),
+            aur_confirmation: expect_widget(builder, "aur_confirmation_row"),
+            battery_threshold: expect_widget(builder, "battery_threshold_row"),
+            keep_failed_workspace: expect_widget(builder, "keep_failed_workspace_row"),
+            mirror: expect_widget(builder, "mirror_row"),
+            speed_limit: expect_widget(builder, "speed_limit_row"),
+            download_folder: expect_widget(builder, "download_folder_row"),
+            iso_folder: expect_widget(builder, "iso_folder_row"),
+            remember_last_folder: expect_widget(builder, "remember_last_folder_row"),
+            seasonal_effects: expect_widget(builder, "seasonal_effects_row"),
+            snow: expect_widget(builder, "snow_row"),
+            halloween: expect_widget(builder, "halloween_row"),
+            fireworks: expect_widget(builder, "fireworks_row"),
+            intensity: expect_widget(builder, "intensity_scale"),
+            reduce_motion: expect_widget(builder, "reduce_motion_row"),
+            idle_timeout: expect_widget(builder, "idle_timeout_row"),
+            auth_summary: expect_widget(builder, "auth_summary_row"),
+        }
+    }
+
+    /// Show the current config values.
+    fn load(&self, config: &Config) {
+        let start_page = config
+            .general
+            .start_page
+            .as_deref()
+            .and_then(|id| navigation::available_pages().position(|page| page.id == id));
+        // Index 0 is "Last Visited"
+        self.start_page
+            .set_selected(start_page.map_or(0, |index| index as u32 + 1));
+        self.remember_window
+            .set_active(config.general.remember_window_size);
+        self.check_updates.set_active(config.updates.check);
+        let log_level = logs::parse_level(&config.general.log_level);
+        let log_level = logs::LEVELS.iter().position(|level| *level == log_level);
+        self.log_level
+            .set_selected(log_level.unwrap_or_default() as u32);
+
+        self.notifications.set_active(config.tasks.notifications);
+        self.auto_close.set_active(config.tasks.auto_close);
+        self.confirm_destructive
+            .set_active(config.tasks.confirm_destructive);
+        self.low_priority_aur
+            .set_active(config.tasks.low_priority_aur);
+        let aur_confirmation = AurConfirmation::ALL
+            .iter()
+            .position(|level| *level == config.tasks.aur_confirmation);
+        self.aur_confirmation
+            .set_selected(aur_confirmation.unwrap_or_default() as u32);
+        self.battery_threshold
+            .set_value(f64::from(config.tasks.battery_threshold));
+        self.keep_failed_workspace
+            .set_active(config.tasks.keep_failed_workspace);
+
+        self.mirror.set_text(&config.downloads.mirror);
+        self.speed_limit
+            .set_value(f64::from(config.downloads.speed_limit_kib));
+        let default_folder = &paths::get().downloads;
+        let folder = config.downloads.folder_or_default(default_folder);
+        self.download_folder.set_subtitle(&folder.to_string_lossy());
+        let iso_folder = config
+            .downloads
+            .iso_folder
+            .as_ref()
+            .map_or(folder, PathBuf::from);
+        self.iso_folder.set_subtitle(&iso_folder.to_string_lossy());
+        self.remember_last_folder
+            .set_active(config.downloads.remember_last_folder);
+
+        // Applied first: unset seasonal effects follow the motion setting
+        self.reduce_motion
+            .set_active(config.appearance.reduce_motion);
+        self.seasonal_effects
+            .set_active(config.seasonal.is_enabled(accessibility::reduced_motion()));
+        self.snow.set_active(config.seasonal.snow);
+        self.halloween.set_active(config.seasonal.halloween);
+        self.fireworks.set_active(config.seasonal.fireworks);
+        self.intensity.set_value(config.seasonal.intensity());
+
+        self.idle_timeout
+            .set_value(f64::from(config.auth.idle_timeout_minutes));
+        self.auth_summary.set_active(config.auth.show_summary);
+    }
+}
+
+/// Show the preferences dialog.
+pub fn show_preferences_dialog(parent: &gtk4::Window) {
+    info!("Opening preferences dialog");
+
+    let builder = builder_from_resource(crate::config::resources::dialogs::PREFERENCES);
+    let dialog: adw::PreferencesDialog = expect_widget(&builder, "preferences_dialog");
+    let folder_button: Button = expect_widget(&builder, "download_folder_button");
+    let iso_folder_button: Button = expect_widget(&builder, "iso_folder_button");
+    let lock_now_button: Button = expect_widget(&builder, "lock_now_button");
+    let reset_button: Button = expect_widget(&builder, "reset_button");
+
+    let config = user::shared();
+    let rows = PreferenceRows::from_builder(&builder);
+
+    let pages = StringList::new(&[&gettext("Last Visited")]);
+    for page in navigation::available_pages() {
+        pages.append(&gettext(page.title));
+    }
+    rows.start_page.set_model(Some(&pages));
+
+    let levels = StringList::new(&[]);
+    for level in logs::LEVELS {
+        levels.append(&capitalize(level.as_str()));
+    }
+    rows.log_level.set_model(Some(&levels));
+
+    let aur_levels = StringList::new(&[]);
+    for level in AurConfirmation::ALL {
+        aur_levels.append(&aur_confirmation_label(level));
+    }
+    rows.aur_confirmation.set_model(Some(&aur_levels));
+
+    rows.load(&config.get());
+    connect_rows(&rows, &config);
+
+    connect_folder_button(
+        &dialog,
+        &folder_button,
+        &rows.download_folder,
+        &config,
+        &gettext("Choose Download Folder"),
+        |config, folder| config.downloads.folder = Some(folder),
+    );
+    connect_folder_button(
+        &dialog,
+        &iso_folder_button,
+        &rows.iso_folder,
+        &config,
+        &gettext("Choose ISO Folder"),
+        |config, folder| config.downloads.iso_folder = Some(folder),
+    );
+
+    let dialog_clone = dialog.clone();
+    lock_now_button.connect_clicked(move |_| {
+        let message = if task_runner::end_auth_session() {
+            "Authentication session ended"
+        } else {
+            "A task is still running"
+        };
+        dialog_clone.add_toast(adw::Toast::new(message));
+    });
+
+    let dialog_clone = dialog.clone();
+    reset_button.connect_clicked(move |_| {
+        confirm_reset(&dialog_clone, &config, &rows);
+    });
+
+    dialog.present(Some(parent));
+}
+
+/// Let `button` pick a folder, shown in `row` and stored with `store`.
+fn connect_folder_button(
+    dialog: &adw::PreferencesDialog,
+    button: &Button,
+    row: &adw::ActionRow,
+    config: &SharedConfig,
+    title: &str,
+    store: fn(&mut Config, String),
+) {
+    let dialog = dialog.clone();
+    let row = row.clone();
+    let config = config.clone();
+    let title = title.to_string();
+    button.connect_clicked(move |_| {
+        let file_dialog = gtk4::FileDialog::new();
+        file_dialog.set_title(&title);
+
+        let config = config.clone();
+        let row = row.clone();
+        let parent = dialog.root().and_downcast::<gtk4::Window>();
+        glib::spawn_future_local(async move {
+            // Cancelling the file dialog keeps the current folder
+            let Ok(file) = file_dialog.select_folder_future(parent.as_ref()).await else {
+                return;
+            };
+            if let Some(path) = file.path() {
+                let folder = path.to_string_lossy().to_string();
+                row.set_subtitle(&folder);
+                config.update(|config| store(config, folder));
+            }
+        });
+    });
+}
+
+/// Write row changes to the config and apply them.
+fn connect_rows(rows: &PreferenceRows, config: &SharedConfig) {
+    let config_clone = config.clone();
+    rows.start_page.connect_selected_notify(move |row| {
+        let start_page = row
+            .selected()
+            .checked_sub(1)
+            .and_then(|index| navigation::available_pages().nth(index as usize))
+            .map(|page| page.id.to_string());
+        config_clone.update(|config| config.general.start_page = start_page);
+    });
+
+    let config_clone = config.clone();
+    rows.remember_window.connect_active_notify(move |row| {
+        let active = row.is_active();
+        config_clone.update(|config| config.general.remember_window_size = active);
+    });
+
+    let config_clone = config.clone();
+    rows.check_updates.connect_active_notify(move |row| {
+        let active = row.is_active();
+        config_clone.update(|config| config.updates.check = active);
+    });
+
+    let config_clone = config.clone();
+    rows.log_level.connect_selected_notify(move |row| {
+        let Some(level) = logs::LEVELS.get(row.selected() as usize).copied() else {
+            return;
+        };
+        log::set_max_level(level);
+        info!("Log level set to {}", level);
+        config_clone.update(|config| config.general.log_level = level.as_str().to_lowercase());
+    });
+
+    let config_clone = config.clone();
+    rows.notifications.connect_active_notify(move |row| {
+        let active = row.is_active();
+        config_clone.update(|config| config.tasks.notifications = active);
+    });
+
+    let config_clone = config.clone();
+    rows.auto_close.connect_active_notify(move |row| {
+        let active = row.is_active();
+        config_clone.update(|config| config.tasks.auto_close = active);
+    });
+
+    let config_clone = config.clone();
+    rows.confirm_destructive.connect_active_notify(move |row| {
+        let active = row.is_active();
+        config_clone.update(|config| config.tasks.confirm_destructive = active);
+    });
+
+    let config_clone = config.clone();
+    rows.low_priority_aur.connect_active_notify(move |row| {
+        let active = row.is_active();
+        config_clone.update(|config| config.tasks.low_priority_aur = active);
+    });
+
+    let config_clone = config.clone();
+    rows.aur_confirmation.connect_selected_notify(move |row| {
+        let Some(level) = AurConfirmation::ALL.get(row.selected() as usize).copied() else {
+            return;
+        };
+        config_clone.update(|config| config.tasks.aur_confirmation = level);
+    });
+
+    let config_clone = config.clone();
+    rows.battery_threshold.connect_value_notify(move |row| {
+        let percent = row.value() as u32;
+        config_clone.update(|config| config.tasks.battery_threshold = percent);
+    });
+
+    let config_clone = config.clone();
+    rows.keep_failed_workspace
+        .connect_active_notify(move |row| {
+            let active = row.is_active();
+            config_clone.update(|config| config.tasks.keep_failed_workspace = active);
+        });
+
+    let config_clone = config.clone();
+    rows.mirror.connect_apply(move |row| {
+        let text = row.text().trim().trim_end_matches('/').to_string();
+        let mirror = if text.is_empty() {
+            user::DownloadsConfig::default().mirror
+        } else {
+            text
+        };
+        row.set_text(&mirror);
+        config_clone.update(|config| config.downloads.mirror = mirror);
+    });
+
+    let config_clone = config.clone();
+    rows.speed_limit.connect_value_notify(move |row| {
+        let limit = row.value() as u32;
+        config_clone.update(|config| config.downloads.speed_limit_kib = limit);
+    });
+
+    let config_clone = config.clone();
+    rows.remember_last_folder.connect_active_notify(move |row| {
+        let active = row.is_active();
+        config_clone.update(|config| config.downloads.remember_last_folder = active);
+    });
+
+    rows.seasonal_effects.connect_active_notify(move |row| {
+        seasonal::set_effects_enabled(row.is_active());
+    });
+
+    let config_clone = config.clone();
+    rows.snow.connect_active_notify(move |row| {
+        let active = row.is_active();
+        config_clone.update(|config| config.seasonal.snow = active);
+        seasonal::restart_effects();
+    });
+
+    let config_clone = config.clone();
+    rows.halloween.connect_active_notify(move |row| {
+        let active = row.is_active();
+        config_clone.update(|config| config.seasonal.halloween = active);
+        seasonal::restart_effects();
+    });
+
+    let config_clone = config.clone();
+    rows.fireworks.connect_active_notify(move |row| {
+        let active = row.is_active();
+        config_clone.update(|config| config.seasonal.fireworks = active);
+        seasonal::restart_effects();
+    });
+
+    let config_clone = config.clone();
+    rows.intensity.connect_value_changed(move |scale| {
+        // Snap to the slider steps so dragging only restarts on real changes
+        let intensity = (scale.value() * 4.0).round() / 4.0;
+        if intensity == config_clone.get().seasonal.intensity {
+            return;
+        }
+        config_clone.update(|config| config.seasonal.intensity = intensity);
+        seasonal::restart_effects();
+    });
+
+    let config_clone = config.clone();
+    let seasonal_row = rows.seasonal_effects.clone();
+    rows.reduce_motion.connect_active_notify(move |row| {
+        let active = row.is_active();
+        accessibility::apply_reduce_motion(active);
+        config_clone.update(|config| config.appearance.reduce_motion = active);
+
+        // Unpinned seasonal effects follow the new motion setting
+        seasonal::sync_effects();
+        seasonal_row.set_active(seasonal::are_effects_enabled());
+    });
+
+    let config_clone = config.clone();
+    rows.idle_timeout.connect_value_notify(move |row| {
+        let minutes = row.value() as u32;
+        config_clone.update(|config| config.auth.idle_timeout_minutes = minutes);
+    });
+
+    let config_clone = config.clone();
+    rows.auth_summary.connect_active_notify(move |row| {
+        let active = row.is_active();
+        config_clone.update(|config| config.auth.show_summary = active);
+    });
+}
+
+fn confirm_reset(dialog: &adw::PreferencesDialog, config: &SharedConfig, rows: &PreferenceRows) {
+    let alert = adw::AlertDialog::new(
+        Some(&gettext("Reset All Settings?")),
+        Some(&gettext(
+            "All preferences return to their defaults. Window size and autostart are kept.",
+        )),
+    );
+    alert.add_response("cancel", &gettext("Cancel"));
+    alert.add_response("reset", &gettext("Reset"));
+    alert.set_response_appearance("reset", adw::ResponseAppearance::Destructive);
+    alert.set_default_response(Some("cancel"));
+    alert.set_close_response("cancel");
+
+    let config = config.clone();
+    let rows = rows.clone();
+    let dialog_clone = dialog.clone();
+    alert.connect_response(None, move |_, response| {
+        if response != "reset" {
+            return;
+        }
+
+        info!("Resetting all settings");
+        config.update(Config::reset_preferences);
+        // Reloading the rows also re-applies their effects
+        let defaults = config.get().clone();
+        rows.load(&defaults);
+        dialog_clone.add_toast(adw::Toast::new("Settings reset"));
+    });
+
+    alert.present(Some(dialog));
+}
+
+/// "WARN" -> "Warn"
+fn capitalize(name: &str) -> String {
+    let lower = name.to_lowercase();
+    let mut chars = lower.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+fn aur_confirmation_label(level: AurConfirmation) -> String {
+    match level {
+        AurConfirmation::Silent => gettext("Never"),
+        AurConfirmation::Review => gettext("Always"),
+        AurConfirmation::Ask => gettext("Ask Each Time"),
+    }
+}
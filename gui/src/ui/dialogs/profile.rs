@@ -0,0 +1,220 @@
+//! Export and import of setup profiles.
+
+use crate::core::actions;
+use crate::core::profile::{self, Profile, ProfileEntry, ResolvedProfile};
+use crate::i18n::{self, gettext};
+use crate::ui::dialogs::error::show_error;
+use crate::ui::dialogs::selection::{
+    show_selection_dialog, SelectionDialogConfig, SelectionOption, SelectionType,
+};
+use crate::ui::task_runner;
+use adw::prelude::*;
+use gtk4::{gio, glib, ApplicationWindow};
+use log::{info, warn};
+
+/// Separates action and choice ids in the export checklist.
+const CHOICE_SEPARATOR: char = ':';
+
+/// Let the user pick actions and choices, then save them as a profile.
+pub fn show_export_dialog(window: &ApplicationWindow) {
+    let mut config = SelectionDialogConfig::new(
+        &gettext("Export Setup Profile"),
+        gettext("Select the actions to replay after a reinstall."),
+    )
+    .selection_type(SelectionType::Multi)
+    .selection_required(true)
+    .confirm_label(&gettext("Export"));
+
+    for action in actions::ACTIONS {
+        let title = gettext(action.title);
+        config = config.add_option(SelectionOption::new(
+            action.id,
+            &title,
+            gettext(action.description),
+            false,
+        ));
+        for choice in action.choices {
+            config = config.add_option(SelectionOption::new(
+                &format!("{}{}{}", action.id, CHOICE_SEPARATOR, choice.id),
+                &gettext(choice.label),
+                i18n::fill(&gettext("Option for {}"), &[&title]),
+                false,
+            ));
+        }
+    }
+
+    let window_clone = window.clone();
+    show_selection_dialog(window.upcast_ref(), config, move |selected| {
+        let profile = Profile::new(entries_from_selection(&selected));
+        save_profile(&window_clone, profile);
+    });
+}
+
+/// Group checklist ids into profile entries, in registry order.
+///
+/// A selected choice implies its action.
+fn entries_from_selection(selected: &[String]) -> Vec<ProfileEntry> {
+    actions::ACTIONS
+        .iter()
+        .filter_map(|action| {
+            let choices: Vec<String> = selected
+                .iter()
+                .filter_map(|id| id.split_once(CHOICE_SEPARATOR))
+                .filter(|(action_id, _)| *action_id == action.id)
+                .map(|(_, choice)| choice.to_string())
+                .collect();
+            let selected_action = selected.iter().any(|id| id == action.id);
+            (selected_action || !choices.is_empty()).then(|| ProfileEntry {
+                id: action.id.to_string(),
+                choices,
+            })
+        })
+        .collect()
+}
+
+fn save_profile(window: &ApplicationWindow, profile: Profile) {
+    let file_dialog = gtk4::FileDialog::new();
+    file_dialog.set_title(&gettext("Export Setup Profile"));
+    file_dialog.set_initial_name(Some(profile::DEFAULT_FILE_NAME));
+
+    let window = window.clone();
+    glib::spawn_future_local(async move {
+        let Ok(file) = file_dialog.save_future(Some(&window)).await else {
+            return;
+        };
+        let Some(path) = file.path() else {
+            return;
+        };
+        match profile.write(&path) {
+            Ok(()) => info!(
+                "Exported setup profile with {} actions to {}",
+                profile.actions.len(),
+                path.display()
+            ),
+            Err(e) => show_error(
+                &window,
+                &i18n::fill(
+                    &gettext("Failed to export setup profile: {}"),
+                    &[&format!("{:#}", e)],
+                ),
+            ),
+        }
+    });
+}
+
+/// Pick a profile file, review its actions and run them.
+pub fn show_import_dialog(window: &ApplicationWindow) {
+    let filter = gtk4::FileFilter::new();
+    filter.set_name(Some(&gettext("Setup Profiles")));
+    filter.add_pattern("*.json");
+    let filters = gio::ListStore::new::<gtk4::FileFilter>();
+    filters.append(&filter);
+
+    let file_dialog = gtk4::FileDialog::new();
+    file_dialog.set_title(&gettext("Import Setup Profile"));
+    file_dialog.set_filters(Some(&filters));
+
+    let window = window.clone();
+    glib::spawn_future_local(async move {
+        let Ok(file) = file_dialog.open_future(Some(&window)).await else {
+            return;
+        };
+        let Some(path) = file.path() else {
+            return;
+        };
+        match Profile::read(&path) {
+            Ok(profile) => show_review_dialog(&window, profile.resolve()),
+            Err(e) => show_error(
+                &window,
+                &i18n::fill(
+                    &gettext("Failed to import setup profile: {}"),
+                    &[&format!("{:#}", e)],
+                ),
+            ),
+        }
+    });
+}
+
+fn show_review_dialog(window: &ApplicationWindow, resolved: ResolvedProfile) {
+    let mut body = String::new();
+    if resolved.steps.is_empty() {
+        body.push_str(&gettext(
+            "The profile contains no actions this version can run.",
+        ));
+    } else {
+        body.push_str(&gettext("These actions will run one after another:"));
+        body.push('\n');
+        for (action, choices) in &resolved.steps {
+            let labels: Vec<String> = choices
+                .iter()
+                .filter_map(|choice| action.find_choice(choice))
+                .map(|choice| gettext(choice.label))
+                .collect();
+            body.push_str(&format!("\n• {}", gettext(action.title)));
+            if !labels.is_empty() {
+                body.push_str(&format!(" ({})", labels.join(", ")));
+            }
+        }
+    }
+
+    if resolved.has_unknown() {
+        warn!(
+            "Setup profile has unknown actions {:?} and choices {:?}",
+            resolved.unknown_actions, resolved.unknown_choices
+        );
+        body.push_str("\n\n");
+        body.push_str(&gettext(
+            "Not available in this version and will be skipped:",
+        ));
+        body.push('\n');
+        for id in &resolved.unknown_actions {
+            body.push_str(&format!("\n• {}", id));
+        }
+        for (action, choice) in &resolved.unknown_choices {
+            body.push_str(&format!("\n• {}{}{}", action, CHOICE_SEPARATOR, choice));
+        }
+    }
+
+    let dialog = adw::AlertDialog::new(Some(&gettext("Import Setup Profile")), Some(&body));
+    dialog.add_response("cancel", &gettext("Cancel"));
+    dialog.add_response("run", &gettext("Run Actions"));
+    dialog.set_response_appearance("run", adw::ResponseAppearance::Suggested);
+    dialog.set_response_enabled("run", !resolved.steps.is_empty());
+    dialog.set_default_response(Some("cancel"));
+    dialog.set_close_response("cancel");
+
+    let window_clone = window.clone();
+    dialog.connect_response(Some("run"), move |_, _| {
+        info!(
+            "Running setup profile with {} actions",
+            resolved.steps.len()
+        );
+        task_runner::run(
+            window_clone.upcast_ref(),
+            resolved.commands(),
+            &gettext("Setup Profile"),
+        );
+    });
+
+    dialog.present(Some(window));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_from_selection() {
+        let selected = vec![
+            "update-mirrorlist:chaotic".to_string(),
+            "unlock-pacman".to_string(),
+        ];
+        let entries = entries_from_selection(&selected);
+        assert_eq!(entries.len(), 2);
+        // Registry order, not selection order
+        assert_eq!(entries[0].id, "unlock-pacman");
+        assert!(entries[0].choices.is_empty());
+        assert_eq!(entries[1].id, "update-mirrorlist");
+        assert_eq!(entries[1].choices, vec!["chaotic".to_string()]);
+    }
+}
@@ -3,9 +3,10 @@
 //! This module provides a reusable dialog window for presenting users with
 //! multiple options to select from, with customizable title, description, and actions.
 
-use crate::ui::utils::extract_widget;
+use crate::ui::markup::Markup;
+use crate::ui::utils::{builder_from_resource, expect_widget};
 use gtk4::prelude::*;
-use gtk4::{Box as GtkBox, Builder, Button, CheckButton, Label, Separator, Window};
+use gtk4::{Box as GtkBox, Button, CheckButton, Label, Separator, Window};
 use log::info;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -15,20 +16,38 @@ use std::rc::Rc;
 pub struct SelectionOption {
     pub id: String,
     pub label: String,
-    pub description: String,
+    pub description: Markup,
     pub installed: bool,
+    /// Checked initially but still toggleable, unlike `installed`.
+    pub preselected: bool,
+    /// Shown below the description, like a sample of a font
+    pub preview: Option<Markup>,
 }
 
 impl SelectionOption {
-    /// Create a new selection option
-    pub fn new(id: &str, label: &str, description: &str, installed: bool) -> Self {
+    /// Create a new selection option; a plain text description is escaped
+    pub fn new(id: &str, label: &str, description: impl Into<Markup>, installed: bool) -> Self {
         Self {
             id: id.to_string(),
             label: label.to_string(),
-            description: description.to_string(),
+            description: description.into(),
             installed,
+            preselected: false,
+            preview: None,
         }
     }
+
+    /// Check this option initially while leaving it toggleable
+    pub fn preselected(mut self, preselected: bool) -> Self {
+        self.preselected = preselected;
+        self
+    }
+
+    /// Show `preview` below the description
+    pub fn preview(mut self, preview: Markup) -> Self {
+        self.preview = Some(preview);
+        self
+    }
 }
 
 /// Selection type for the dialog
@@ -43,23 +62,27 @@ pub enum SelectionType {
 /// Configuration for the selection dialog
 pub struct SelectionDialogConfig {
     pub title: String,
-    pub description: String,
+    pub description: Markup,
     pub options: Vec<SelectionOption>,
     pub confirm_label: String,
     pub selection_type: SelectionType,
     pub selection_required: bool,
+    /// Widget shown above the options, for settings that aren't options
+    pub extra_widget: Option<gtk4::Widget>,
 }
 
 impl SelectionDialogConfig {
-    /// Create a new dialog configuration
-    pub fn new(title: &str, description: &str) -> Self {
+    /// Create a new dialog configuration; a plain text description is
+    /// escaped
+    pub fn new(title: &str, description: impl Into<Markup>) -> Self {
         Self {
             title: title.to_string(),
-            description: description.to_string(),
+            description: description.into(),
             options: Vec::new(),
             confirm_label: "Install".to_string(),
             selection_type: SelectionType::Multi,
             selection_required: true,
+            extra_widget: None,
         }
     }
 
@@ -86,34 +109,46 @@ impl SelectionDialogConfig {
         self.selection_required = required;
         self
     }
+
+    /// Show `widget` above the options; the caller reads its state when
+    /// the dialog is confirmed
+    pub fn extra_widget(mut self, widget: &impl IsA<gtk4::Widget>) -> Self {
+        self.extra_widget = Some(widget.clone().upcast());
+        self
+    }
 }
 
-/// Show a selection dialog and call the callback with selected option IDs
-pub fn show_selection_dialog<F>(parent: &Window, config: SelectionDialogConfig, on_confirm: F)
+/// Show a selection dialog and call the callback with selected option IDs,
+/// returning the dialog window
+pub fn show_selection_dialog<F>(
+    parent: &Window,
+    config: SelectionDialogConfig,
+    on_confirm: F,
+) -> Window
 where
     F: Fn(Vec<String>) + 'static,
 {
     info!("Opening selection dialog: {}", config.title);
 
     // Load the UI from resource
-    let builder = Builder::from_resource(crate::config::resources::dialogs::SELECTION);
+    let builder = builder_from_resource(crate::config::resources::dialogs::SELECTION);
 
     // Get the dialog window
-    let dialog: Window = extract_widget(&builder, "selection_dialog");
+    let dialog: Window = expect_widget(&builder, "selection_dialog");
 
     // Set transient parent
     dialog.set_transient_for(Some(parent));
 
     // Get UI elements
-    let title_label: Label = extract_widget(&builder, "dialog_title");
-    let description_label: Label = extract_widget(&builder, "dialog_description");
-    let options_container: GtkBox = extract_widget(&builder, "options_container");
-    let cancel_button: Button = extract_widget(&builder, "cancel_button");
-    let confirm_button: Button = extract_widget(&builder, "confirm_button");
+    let title_label: Label = expect_widget(&builder, "dialog_title");
+    let description_label: Label = expect_widget(&builder, "dialog_description");
+    let options_container: GtkBox = expect_widget(&builder, "options_container");
+    let cancel_button: Button = expect_widget(&builder, "cancel_button");
+    let confirm_button: Button = expect_widget(&builder, "confirm_button");
 
     // Set title and description
     title_label.set_label(&config.title);
-    description_label.set_label(&config.description);
+    description_label.set_markup(config.description.as_str());
     confirm_button.set_label(&config.confirm_label);
 
     let checkboxes: Rc<RefCell<Vec<(String, CheckButton)>>> = Rc::new(RefCell::new(Vec::new()));
@@ -121,6 +156,13 @@ where
     let selection_type = config.selection_type;
     let selection_required = config.selection_required;
 
+    if let Some(widget) = &config.extra_widget {
+        options_container.append(widget);
+        if !config.options.is_empty() {
+            options_container.append(&Separator::new(gtk4::Orientation::Horizontal));
+        }
+    }
+
     let mut first_radio: Option<CheckButton> = None;
 
     for (i, option) in config.options.iter().enumerate() {
@@ -135,7 +177,7 @@ where
         match selection_type {
             SelectionType::Multi => {
                 let checkbox = CheckButton::new();
-                checkbox.set_active(option.installed);
+                checkbox.set_active(option.installed || option.preselected);
                 checkbox.set_sensitive(!option.installed);
                 checkboxes
                     .borrow_mut()
@@ -152,13 +194,17 @@ where
                     title_label.set_css_classes(&["dim"]);
                 }
 
-                let desc_label = Label::new(Some(&option.description));
+                let desc_label = Label::new(None);
+                desc_label.set_markup(option.description.as_str());
                 desc_label.set_css_classes(&["dim", "caption"]);
                 desc_label.set_halign(gtk4::Align::Start);
                 desc_label.set_wrap(true);
 
                 text_box.append(&title_label);
                 text_box.append(&desc_label);
+                if let Some(preview) = preview_label(option) {
+                    text_box.append(&preview);
+                }
 
                 option_row.append(&checkbox);
                 option_row.append(&text_box);
@@ -173,7 +219,7 @@ where
                     first_radio = Some(radio.clone());
                     radio
                 };
-                radio.set_active(option.installed);
+                radio.set_active(option.installed || option.preselected);
                 radio.set_sensitive(!option.installed);
                 radio_buttons
                     .borrow_mut()
@@ -190,13 +236,17 @@ where
                     title_label.set_css_classes(&["dim"]);
                 }
 
-                let desc_label = Label::new(Some(&option.description));
+                let desc_label = Label::new(None);
+                desc_label.set_markup(option.description.as_str());
                 desc_label.set_css_classes(&["dim", "caption"]);
                 desc_label.set_halign(gtk4::Align::Start);
                 desc_label.set_wrap(true);
 
                 text_box.append(&title_label);
                 text_box.append(&desc_label);
+                if let Some(preview) = preview_label(option) {
+                    text_box.append(&preview);
+                }
 
                 option_row.append(&radio);
                 option_row.append(&text_box);
@@ -212,11 +262,6 @@ where
         }
     }
 
-    // Set initial state of confirm button based on selection_required
-    if selection_required {
-        confirm_button.set_sensitive(false);
-    }
-
     // Cancel button - just close the dialog
     let dialog_clone = dialog.clone();
     cancel_button.connect_clicked(move |_| {
@@ -235,11 +280,11 @@ where
                 SelectionType::Multi => checkboxes_clone
                     .borrow()
                     .iter()
-                    .any(|(_, checkbox)| checkbox.is_active()),
+                    .any(|(_, checkbox)| checkbox.is_active() && checkbox.is_sensitive()),
                 SelectionType::Single => radio_buttons_clone
                     .borrow()
                     .iter()
-                    .any(|(_, radio)| radio.is_active()),
+                    .any(|(_, radio)| radio.is_active() && radio.is_sensitive()),
             };
 
             if selection_required {
@@ -250,6 +295,9 @@ where
         }
     };
 
+    // Set initial state of confirm button based on selection_required
+    update_confirm_button();
+
     // Connect selection change handlers
     let connect_toggle_handler = |button: &CheckButton| {
         let update = update_confirm_button.clone();
@@ -300,4 +348,15 @@ where
 
     // Show the dialog
     dialog.present();
+    dialog
+}
+
+/// Label showing the preview of `option`, if it has one.
+fn preview_label(option: &SelectionOption) -> Option<Label> {
+    let preview = option.preview.as_ref()?;
+    let label = Label::new(None);
+    label.set_markup(preview.as_str());
+    label.set_halign(gtk4::Align::Start);
+    label.set_wrap(true);
+    Some(label)
 }
@@ -1,16 +1,28 @@
 //! Interactive terminal dialog for running shell commands.
+//!
+//! Root commands go through pkexec. The session is checked for a display
+//! first, since pkexec's password dialog needs one, and when pkexec could
+//! not authorize, the dialog offers to retry with run0 or sudo.
 
-use crate::ui::utils::extract_widget;
+use crate::core::elevation::{self, Fallback, SessionIssue};
+use crate::i18n::{self, gettext};
+use crate::ui::utils::{builder_from_resource, expect_widget};
 use gtk4::gdk::RGBA;
 use gtk4::prelude::*;
-use gtk4::{Builder, Button, Window};
-use log::{error, info};
-use std::cell::RefCell;
+use gtk4::{Button, Window};
+use log::{error, info, warn};
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use std::str::FromStr;
 use vte4::prelude::*;
 use vte4::Terminal;
 
+/// Pattern for URLs that can be opened with a click, such as login links.
+const URL_PATTERN: &str = r#"https?://[^\s<>"']+"#;
+
+/// PCRE2_MULTILINE, required by VTE for match regexes.
+const PCRE2_MULTILINE: u32 = 0x0000_0400;
+
 fn update_terminal_style(terminal: &Terminal) {
     let style_manager = adw::StyleManager::default();
     let is_dark = style_manager.is_dark();
@@ -44,6 +56,31 @@ fn update_terminal_style(terminal: &Terminal) {
     terminal.set_colors(Some(&fg_color), Some(&bg_color), &palette_refs);
 }
 
+/// Make URLs in the output clickable, opening them in the browser.
+fn setup_url_matching(terminal: &Terminal) {
+    match vte4::Regex::for_match(URL_PATTERN, PCRE2_MULTILINE) {
+        Ok(regex) => {
+            let tag = terminal.match_add_regex(&regex, 0);
+            terminal.match_set_cursor_name(tag, "pointer");
+        }
+        Err(e) => {
+            warn!("Failed to compile terminal URL pattern: {}", e);
+            return;
+        }
+    }
+
+    let click = gtk4::GestureClick::new();
+    let terminal_clone = terminal.clone();
+    click.connect_released(move |_, _, x, y| {
+        if let (Some(url), _) = terminal_clone.check_match_at(x, y) {
+            if let Err(e) = crate::core::package::open_url(&url) {
+                warn!("Failed to open {}: {}", url, e);
+            }
+        }
+    });
+    terminal.add_controller(click);
+}
+
 /// Shows an interactive terminal window for the given command.
 pub fn show_terminal_dialog(
     parent: &Window,
@@ -51,13 +88,66 @@ pub fn show_terminal_dialog(
     command: &str,
     args: &[&str],
     close_on_exit: bool,
-) {
+) -> adw::Window {
+    show_inner(parent, title, command, args, close_on_exit, None)
+}
+
+/// Shows an interactive terminal window and invokes `on_exit` when the
+/// command exits, with `true` on a zero exit code.
+pub fn show_terminal_dialog_with_completion<F>(
+    parent: &Window,
+    title: &str,
+    command: &str,
+    args: &[&str],
+    close_on_exit: bool,
+    on_exit: F,
+) -> adw::Window
+where
+    F: FnOnce(bool) + 'static,
+{
+    show_terminal_dialog_with_exit_code(parent, title, command, args, close_on_exit, move |code| {
+        on_exit(code == Some(0))
+    })
+}
+
+/// Shows an interactive terminal window and invokes `on_exit` with the exit
+/// code of the command, `None` if it was killed or the window was closed
+/// first.
+pub fn show_terminal_dialog_with_exit_code<F>(
+    parent: &Window,
+    title: &str,
+    command: &str,
+    args: &[&str],
+    close_on_exit: bool,
+    on_exit: F,
+) -> adw::Window
+where
+    F: FnOnce(Option<i32>) + 'static,
+{
+    show_inner(
+        parent,
+        title,
+        command,
+        args,
+        close_on_exit,
+        Some(Box::new(on_exit)),
+    )
+}
+
+fn show_inner(
+    parent: &Window,
+    title: &str,
+    command: &str,
+    args: &[&str],
+    close_on_exit: bool,
+    on_exit: Option<Box<dyn FnOnce(Option<i32>)>>,
+) -> adw::Window {
     // Load the UI
-    let builder = Builder::from_resource(crate::config::resources::dialogs::TERMINAL);
+    let builder = builder_from_resource(crate::config::resources::dialogs::TERMINAL);
 
-    let window: adw::Window = extract_widget(&builder, "terminal_window");
-    let terminal: Terminal = extract_widget(&builder, "terminal");
-    let close_button: Button = extract_widget(&builder, "close_button");
+    let window: adw::Window = expect_widget(&builder, "terminal_window");
+    let terminal: Terminal = expect_widget(&builder, "terminal");
+    let close_button: Button = expect_widget(&builder, "close_button");
 
     window.set_transient_for(Some(parent));
     window.set_title(Some(title));
@@ -68,6 +158,7 @@ pub fn show_terminal_dialog(
 
     // Setup theming
     update_terminal_style(&terminal);
+    setup_url_matching(&terminal);
 
     let terminal_weak = terminal.downgrade();
     let style_manager = adw::StyleManager::default();
@@ -92,14 +183,91 @@ pub fn show_terminal_dialog(
         window_clone.close();
     });
 
-    // Spawn the command
+    let banner: adw::Banner = expect_widget(&builder, "session_banner");
     let mut argv = vec![command.to_string()];
     argv.extend(args.iter().map(|s| s.to_string()));
+
+    // pkexec's password dialog needs the graphical session
+    let via_pkexec = Cell::new(elevation::is_pkexec(command));
+    let session_issue = via_pkexec
+        .get()
+        .then(|| elevation::session_issue(|name| std::env::var(name).ok()))
+        .flatten();
+    if let Some(issue) = &session_issue {
+        warn!("Terminal: {:?} before running pkexec", issue);
+        banner.set_title(&session_issue_text(issue));
+        banner.set_revealed(true);
+    }
+
+    spawn(&terminal, &argv, &close_button);
+
+    // Enable close button and show exit status when child exits
+    let window_for_exit = window.clone();
+    let terminal_exit = terminal.clone();
+    let on_exit = Rc::new(RefCell::new(on_exit));
+    let on_exit_exit = on_exit.clone();
+    terminal.connect_child_exited(move |_, status| {
+        // Print exit message to terminal with improved formatting
+        let exit_code = elevation::exit_code(status);
+        let status_text = if exit_code == Some(0) {
+            "success"
+        } else {
+            "error"
+        };
+        let message = match exit_code {
+            Some(code) => format!(
+                "\r\n[Process completed] Command exited with code {} ({})\r\n",
+                code, status_text
+            ),
+            None => "\r\n[Process completed] Command was killed (error)\r\n".to_string(),
+        };
+        terminal_exit.feed(message.as_bytes());
+
+        // Enable close button and ensure it's blue
+        enable_close_button(&close_button);
+
+        // Offered once; the retry does not run through pkexec
+        if via_pkexec.replace(false) {
+            let tools = elevation::Tools::detect();
+            if let Some(fallback) = elevation::fallback(exit_code, session_issue.as_ref(), &tools) {
+                offer_fallback(&banner, &terminal_exit, &close_button, &argv, fallback);
+                return;
+            }
+        }
+
+        if let Some(callback) = on_exit_exit.borrow_mut().take() {
+            callback(exit_code);
+        }
+
+        if close_on_exit && exit_code == Some(0) {
+            window_for_exit.close();
+        }
+    });
+
+    // Closed with a retry still offered
+    window.connect_close_request(move |_| {
+        if let Some(callback) = on_exit.borrow_mut().take() {
+            callback(None);
+        }
+        gtk4::glib::Propagation::Proceed
+    });
+
+    window.present();
+    window
+}
+
+/// Enable the close button and make it blue.
+fn enable_close_button(close_button: &Button) {
+    close_button.add_css_class("suggested-action");
+    close_button.set_sensitive(true);
+}
+
+/// Run `argv` in `terminal`.
+fn spawn(terminal: &Terminal, argv: &[String], close_button: &Button) {
     let argv_refs: Vec<&str> = argv.iter().map(|s| s.as_str()).collect();
 
     info!("Terminal: Spawning {:?} in interactive window", argv_refs);
 
-    let close_button_clone = close_button.clone();
     let close_button_error = close_button.clone();
     let terminal_error = terminal.clone();
     let env_vars: Vec<String> = std::env::vars().map(|(k, v)| format!("{k}={v}")).collect();
@@ -119,34 +287,70 @@ pub fn show_terminal_dialog(
                 // Print error message to terminal
                 let error_msg = format!("\r\n[ERROR] Failed to spawn command: {}\r\n", e);
                 terminal_error.feed(error_msg.as_bytes());
-                // Enable close button and make it blue on error
-                close_button_error.add_css_class("suggested-action");
-                close_button_error.set_sensitive(true);
+                enable_close_button(&close_button_error);
             }
         },
     );
+}
 
-    // Enable close button and show exit status when child exits
-    let window_for_exit = window.clone();
-    let terminal_exit = terminal.clone();
-    terminal.connect_child_exited(move |_, status| {
-        // Print exit message to terminal with improved formatting
-        let exit_code = status;
-        let status_text = if exit_code == 0 { "success" } else { "error" };
-        let message = format!(
-            "\r\n[Process completed] Command exited with code {} ({})\r\n",
-            exit_code, status_text
-        );
-        terminal_exit.feed(message.as_bytes());
+/// Explain in the banner why pkexec failed and offer to rerun the pkexec
+/// command `argv` with `fallback`.
+fn offer_fallback(
+    banner: &adw::Banner,
+    terminal: &Terminal,
+    close_button: &Button,
+    argv: &[String],
+    fallback: Fallback,
+) {
+    info!(
+        "Terminal: pkexec could not authorize, offering {:?}",
+        fallback
+    );
 
-        // Enable close button and ensure it's blue
-        close_button_clone.add_css_class("suggested-action");
-        close_button_clone.set_sensitive(true);
+    let (program, title) = match &fallback {
+        Fallback::Run0 => (
+            "run0",
+            gettext("pkexec could not ask for your password. run0 can ask for it in this terminal instead."),
+        ),
+        Fallback::SudoAskpass { askpass } => (
+            "sudo",
+            i18n::fill(
+                &gettext("pkexec could not ask for your password. sudo can ask for it with {} instead."),
+                &[&askpass.file_name().unwrap_or_default().to_string_lossy()],
+            ),
+        ),
+        Fallback::Sudo => (
+            "sudo",
+            gettext("pkexec could not ask for your password. sudo can ask for it in this terminal instead."),
+        ),
+    };
+    banner.set_title(&title);
+    banner.set_button_label(Some(&i18n::fill(&gettext("Retry with {}"), &[program])));
+    banner.set_revealed(true);
 
-        if close_on_exit && exit_code == 0 {
-            window_for_exit.close();
-        }
+    let terminal = terminal.clone();
+    let close_button = close_button.clone();
+    let fallback_argv = fallback.argv(&argv[1..]);
+    banner.connect_button_clicked(move |banner| {
+        banner.set_revealed(false);
+        banner.set_button_label(None);
+        close_button.set_sensitive(false);
+        terminal.feed(format!("\r\n[Retrying with {}]\r\n\r\n", program).as_bytes());
+        spawn(&terminal, &fallback_argv, &close_button);
     });
+}
 
-    window.present();
+fn session_issue_text(issue: &SessionIssue) -> String {
+    match issue {
+        SessionIssue::NoDisplay => {
+            gettext("No graphical session found, so the password dialog may not appear.")
+        }
+        SessionIssue::MissingDisplay {
+            session_type,
+            variable,
+        } => i18n::fill(
+            &gettext("This {} session has no {} set, so the password dialog may not appear."),
+            &[session_type, variable],
+        ),
+    }
 }
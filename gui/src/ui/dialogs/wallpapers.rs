@@ -0,0 +1,320 @@
+//! Wallpaper browser with thumbnail previews.
+//!
+//! Thumbnails load lazily as grid cells are bound, one at a time on a
+//! background thread, and come from the cache on later visits.
+
+use crate::core::package::is_package_installed;
+use crate::core::wallpapers::{self, Wallpaper};
+use crate::i18n::{self, gettext};
+use crate::ui::dialogs::download::start_download;
+use crate::ui::utils::{builder_from_resource, extract_widget, WidgetError};
+use adw::prelude::*;
+use gtk4::{gio, glib};
+use gtk4::{
+    Button, GridView, Label, Picture, SignalListItemFactory, SingleSelection, Stack, Window,
+};
+use log::{error, info, warn};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Thumbnail size in the grid.
+const THUMBNAIL_WIDTH: i32 = 240;
+const THUMBNAIL_HEIGHT: i32 = 135;
+
+/// Thumbnails requested by grid cells, fetched one by one in the background.
+struct ThumbnailLoader {
+    requests: mpsc::Sender<(u32, Wallpaper)>,
+    requested: RefCell<HashSet<u32>>,
+}
+
+impl ThumbnailLoader {
+    /// Start the worker; `model` is told to rebind an item once its
+    /// thumbnail is cached.
+    fn new(model: &gio::ListStore) -> Rc<Self> {
+        let (request_tx, request_rx) = mpsc::channel::<(u32, Wallpaper)>();
+        let (done_tx, done_rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            // Ends once the dialog, and with it the request sender, is gone
+            for (position, wallpaper) in request_rx {
+                let result = runtime.block_on(wallpapers::fetch_thumbnail(&wallpaper));
+                if done_tx.send((position, wallpaper.name, result)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let model = model.downgrade();
+        glib::timeout_add_local(Duration::from_millis(100), move || loop {
+            match done_rx.try_recv() {
+                Ok((position, _, Ok(_))) => {
+                    if let Some(model) = model.upgrade() {
+                        model.items_changed(position, 1, 1);
+                    }
+                }
+                Ok((_, name, Err(e))) => warn!("Failed to load thumbnail of {}: {:#}", name, e),
+                Err(mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                Err(mpsc::TryRecvError::Disconnected) => return glib::ControlFlow::Break,
+            }
+        });
+
+        Rc::new(Self {
+            requests: request_tx,
+            requested: RefCell::new(HashSet::new()),
+        })
+    }
+
+    /// Fetch the thumbnail at `position` unless already asked for.
+    fn request(&self, position: u32, wallpaper: &Wallpaper) {
+        if self.requested.borrow_mut().insert(position) {
+            let _ = self.requests.send((position, wallpaper.clone()));
+        }
+    }
+}
+
+/// Show the wallpaper browser.
+pub fn show_wallpaper_dialog(parent: &Window) {
+    info!("Opening wallpaper browser");
+
+    if let Err(e) = open_dialog(parent) {
+        error!("Failed to build wallpaper dialog: {}", e);
+        crate::ui::dialogs::error::show_error(parent, &e.to_string());
+    }
+}
+
+fn open_dialog(parent: &Window) -> Result<(), WidgetError> {
+    let builder = builder_from_resource(crate::config::resources::dialogs::WALLPAPERS);
+    let dialog: adw::Dialog = extract_widget(&builder, "wallpapers_dialog")?;
+    let toast_overlay: adw::ToastOverlay = extract_widget(&builder, "toast_overlay")?;
+    let stack: Stack = extract_widget(&builder, "wallpaper_stack")?;
+    let error_page: adw::StatusPage = extract_widget(&builder, "error_page")?;
+    let grid: GridView = extract_widget(&builder, "wallpaper_grid")?;
+    let location_label: Label = extract_widget(&builder, "location_label")?;
+    let download_button: Button = extract_widget(&builder, "btn_download_wallpaper")?;
+    let apply_button: Button = extract_widget(&builder, "btn_apply_wallpaper")?;
+
+    let download_dir = wallpapers::download_dir();
+    location_label.set_label(&i18n::fill(
+        &gettext("Saved to {}"),
+        &[&download_dir.display().to_string()],
+    ));
+    apply_button.set_visible(is_package_installed(wallpapers::APPLY_PACKAGE));
+
+    let model = gio::ListStore::new::<glib::BoxedAnyObject>();
+    let selection = SingleSelection::new(Some(model.clone()));
+    selection.set_autoselect(false);
+    grid.set_model(Some(&selection));
+    grid.set_factory(Some(&thumbnail_factory(ThumbnailLoader::new(&model))));
+
+    selection.connect_selected_item_notify({
+        let download_button = download_button.clone();
+        let apply_button = apply_button.clone();
+        move |selection| {
+            let selected = selection.selected_item().is_some();
+            download_button.set_sensitive(selected);
+            apply_button.set_sensitive(selected);
+        }
+    });
+
+    for (button, apply) in [(&download_button, false), (&apply_button, true)] {
+        let parent = parent.clone();
+        let selection = selection.clone();
+        let toast_overlay = toast_overlay.clone();
+        button.connect_clicked(move |_| {
+            if let Some(wallpaper) = selected_wallpaper(&selection) {
+                download_wallpaper(&parent, &toast_overlay, &wallpaper, apply);
+            }
+        });
+    }
+
+    // Fetch the index
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _ = tx.send(runtime.block_on(wallpapers::fetch_index()));
+    });
+
+    glib::timeout_add_local(Duration::from_millis(50), move || match rx.try_recv() {
+        Ok(Ok(list)) if !list.is_empty() => {
+            for wallpaper in list {
+                model.append(&glib::BoxedAnyObject::new(wallpaper));
+            }
+            stack.set_visible_child_name("grid");
+            glib::ControlFlow::Break
+        }
+        Ok(Ok(_)) => {
+            error_page.set_description(Some(&gettext("The wallpaper index is empty.")));
+            stack.set_visible_child_name("error");
+            glib::ControlFlow::Break
+        }
+        Ok(Err(e)) => {
+            error!("Failed to load wallpaper index: {:#}", e);
+            error_page.set_description(Some(&i18n::fill(
+                &gettext("Check your internet connection and try again.\n\n{}"),
+                &[&e.to_string()],
+            )));
+            stack.set_visible_child_name("error");
+            glib::ControlFlow::Break
+        }
+        Err(mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+        Err(mpsc::TryRecvError::Disconnected) => glib::ControlFlow::Break,
+    });
+
+    dialog.present(Some(parent));
+    Ok(())
+}
+
+/// Grid cells showing a thumbnail above the wallpaper name.
+fn thumbnail_factory(loader: Rc<ThumbnailLoader>) -> SignalListItemFactory {
+    let factory = SignalListItemFactory::new();
+
+    factory.connect_setup(|_, item| {
+        let Some(item) = item.downcast_ref::<gtk4::ListItem>() else {
+            return;
+        };
+        let cell = gtk4::Box::new(gtk4::Orientation::Vertical, 6);
+        cell.set_margin_top(6);
+        cell.set_margin_bottom(6);
+        cell.set_margin_start(6);
+        cell.set_margin_end(6);
+
+        let picture = Picture::new();
+        picture.set_content_fit(gtk4::ContentFit::Cover);
+        picture.set_size_request(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT);
+        picture.add_css_class("card");
+        cell.append(&picture);
+
+        let label = Label::new(None);
+        label.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+        cell.append(&label);
+
+        item.set_child(Some(&cell));
+    });
+
+    factory.connect_bind(move |_, item| {
+        let Some(item) = item.downcast_ref::<gtk4::ListItem>() else {
+            return;
+        };
+        let Some(cell) = item.child().and_downcast::<gtk4::Box>() else {
+            return;
+        };
+        let (Some(picture), Some(label)) = (
+            cell.first_child().and_downcast::<Picture>(),
+            cell.last_child().and_downcast::<Label>(),
+        ) else {
+            return;
+        };
+        let Some(object) = item.item().and_downcast::<glib::BoxedAnyObject>() else {
+            return;
+        };
+        let wallpaper = object.borrow::<Wallpaper>();
+
+        label.set_label(&wallpaper.name);
+        picture.set_alternative_text(Some(&wallpaper.name));
+        match wallpapers::cached_thumbnail(&wallpaper) {
+            Some(path) => picture.set_filename(Some(&path)),
+            None => {
+                picture.set_paintable(None::<&gtk4::gdk::Paintable>);
+                loader.request(item.position(), &wallpaper);
+            }
+        }
+    });
+
+    factory
+}
+
+fn selected_wallpaper(selection: &SingleSelection) -> Option<Wallpaper> {
+    let object = selection
+        .selected_item()
+        .and_downcast::<glib::BoxedAnyObject>()?;
+    let wallpaper = object.borrow::<Wallpaper>().clone();
+    Some(wallpaper)
+}
+
+/// Save `wallpaper` to the wallpapers folder with a progress dialog, then
+/// set it as the Plasma wallpaper if `apply` is set.
+fn download_wallpaper(
+    parent: &Window,
+    toast_overlay: &adw::ToastOverlay,
+    wallpaper: &Wallpaper,
+    apply: bool,
+) {
+    let dir = wallpapers::download_dir();
+    let path = dir.join(wallpaper.file_name());
+
+    let toast_overlay = toast_overlay.clone();
+    let done_path = path.clone();
+    let on_done = move || {
+        if apply {
+            apply_wallpaper(&toast_overlay, &done_path);
+        } else {
+            toast_overlay.add_toast(adw::Toast::new(&i18n::fill(
+                &gettext("Saved {}"),
+                &[&done_path.display().to_string()],
+            )));
+        }
+    };
+
+    // Already downloaded before
+    if path.is_file() {
+        info!("Wallpaper {} already downloaded", path.display());
+        on_done();
+        return;
+    }
+
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        error!("Failed to create {}: {}", dir.display(), e);
+        crate::ui::dialogs::error::show_error(
+            parent,
+            &i18n::fill(
+                &gettext("Could not create the wallpapers folder: {}"),
+                &[&e.to_string()],
+            ),
+        );
+        return;
+    }
+
+    info!(
+        "Downloading wallpaper {} to {}",
+        wallpaper.name,
+        path.display()
+    );
+    start_download(
+        parent,
+        wallpaper.file_name(),
+        wallpaper.url.clone(),
+        path.to_string_lossy().to_string(),
+        Some(Box::new(on_done)),
+    );
+}
+
+/// Set `path` as the Plasma wallpaper.
+fn apply_wallpaper(toast_overlay: &adw::ToastOverlay, path: &Path) {
+    let argv = [wallpapers::APPLY_PROGRAM.as_ref(), path.as_os_str()];
+    let process = match gio::Subprocess::newv(&argv, gio::SubprocessFlags::NONE) {
+        Ok(process) => process,
+        Err(e) => {
+            error!("Failed to start {}: {}", wallpapers::APPLY_PROGRAM, e);
+            toast_overlay.add_toast(adw::Toast::new(&gettext("Could not set the wallpaper")));
+            return;
+        }
+    };
+
+    let toast_overlay = toast_overlay.clone();
+    let path: PathBuf = path.to_path_buf();
+    process.wait_check_async(None::<&gio::Cancellable>, move |result| match result {
+        Ok(()) => {
+            info!("Set {} as the wallpaper", path.display());
+            toast_overlay.add_toast(adw::Toast::new(&gettext("Wallpaper set")));
+        }
+        Err(e) => {
+            error!("Failed to set {} as the wallpaper: {}", path.display(), e);
+            toast_overlay.add_toast(adw::Toast::new(&gettext("Could not set the wallpaper")));
+        }
+    });
+}
@@ -1,40 +1,62 @@
-//! Warning confirmation dialog for experimental features.
+//! Warning confirmation dialogs for experimental and destructive actions.
 
-use crate::ui::utils::extract_widget;
+use crate::i18n::{self, gettext};
+use crate::ui::markup::Markup;
+use crate::ui::utils::{builder_from_resource, expect_widget};
 use gtk4::prelude::*;
-use gtk4::{Builder, Button, Label, Window};
+use gtk4::{Button, Label, Window};
 use log::info;
 use std::cell::RefCell;
 use std::rc::Rc;
 
 /// Show a warning confirmation dialog with cancel and continue buttons.
 /// Calls on_confirm callback if user clicks continue.
-pub fn show_warning_confirmation<F>(parent: &Window, heading: &str, message: &str, on_confirm: F)
-where
+///
+/// Plain text messages are escaped; pass [`Markup::trusted`] for markup.
+pub fn show_warning_confirmation<F>(
+    parent: &Window,
+    heading: &str,
+    message: impl Into<Markup>,
+    on_confirm: F,
+) where
     F: FnOnce() + 'static,
 {
+    let message = message.into();
+    if !crate::config::user::shared()
+        .get()
+        .tasks
+        .confirm_destructive
+    {
+        info!(
+            "Skipping warning confirmation (disabled in preferences): {}",
+            heading
+        );
+        on_confirm();
+        return;
+    }
+
     info!("Showing warning confirmation dialog: {}", heading);
 
     // Load the UI from resource
-    let builder = Builder::from_resource(crate::config::resources::dialogs::WARNING);
+    let builder = builder_from_resource(crate::config::resources::dialogs::WARNING);
 
     // Get the dialog window
-    let dialog: Window = extract_widget(&builder, "warning_dialog");
+    let dialog: Window = expect_widget(&builder, "warning_dialog");
 
     // Set transient parent
     dialog.set_transient_for(Some(parent));
 
     // Get UI elements
-    let heading_label: Label = extract_widget(&builder, "dialog_heading");
-    let warning_message: Label = extract_widget(&builder, "warning_message");
-    let cancel_button: Button = extract_widget(&builder, "cancel_button");
-    let continue_button: Button = extract_widget(&builder, "continue_button");
+    let heading_label: Label = expect_widget(&builder, "dialog_heading");
+    let warning_message: Label = expect_widget(&builder, "warning_message");
+    let cancel_button: Button = expect_widget(&builder, "cancel_button");
+    let continue_button: Button = expect_widget(&builder, "continue_button");
 
     // Set heading (remove emoji from heading since we have an icon now)
     heading_label.set_label(heading);
 
     // Set message with Pango markup
-    warning_message.set_markup(message);
+    warning_message.set_markup(message.as_str());
 
     // Setup callbacks
     let dialog_clone = dialog.clone();
@@ -57,3 +79,72 @@ where
     // Show the dialog
     dialog.present();
 }
+
+/// Show a warning that only continues once the user types `phrase`, for
+/// changes that are hard to undo. Shown even when confirmations are turned
+/// off in the preferences.
+pub fn show_typed_confirmation<F>(
+    parent: &Window,
+    heading: &str,
+    message: impl Into<Markup>,
+    phrase: &str,
+    on_confirm: F,
+) where
+    F: FnOnce() + 'static,
+{
+    let message = message.into();
+    info!("Showing typed confirmation dialog: {}", heading);
+
+    let builder = builder_from_resource(crate::config::resources::dialogs::WARNING);
+    let dialog: Window = expect_widget(&builder, "warning_dialog");
+    dialog.set_transient_for(Some(parent));
+
+    let heading_label: Label = expect_widget(&builder, "dialog_heading");
+    let warning_message: Label = expect_widget(&builder, "warning_message");
+    let root_box: gtk4::Box = expect_widget(&builder, "warning_root_box");
+    let cancel_button: Button = expect_widget(&builder, "cancel_button");
+    let continue_button: Button = expect_widget(&builder, "continue_button");
+
+    heading_label.set_label(heading);
+    warning_message.set_markup(message.as_str());
+    continue_button.remove_css_class("suggested-action");
+    continue_button.add_css_class("destructive-action");
+    continue_button.set_sensitive(false);
+
+    let prompt = Label::builder()
+        .label(i18n::fill_markup(
+            &gettext("Type <b>{}</b> to confirm:"),
+            &[phrase],
+        ))
+        .use_markup(true)
+        .halign(gtk4::Align::Start)
+        .build();
+    let entry = gtk4::Entry::new();
+    root_box.insert_child_after(&prompt, Some(&warning_message));
+    root_box.insert_child_after(&entry, Some(&prompt));
+
+    let phrase = phrase.to_string();
+    let continue_clone = continue_button.clone();
+    entry.connect_changed(move |entry| {
+        continue_clone.set_sensitive(entry.text().trim() == phrase);
+    });
+
+    let dialog_clone = dialog.clone();
+    cancel_button.connect_clicked(move |_| {
+        info!("Typed confirmation cancelled");
+        dialog_clone.close();
+    });
+
+    let dialog_clone = dialog.clone();
+    let on_confirm = Rc::new(RefCell::new(Some(on_confirm)));
+    continue_button.connect_clicked(move |_| {
+        info!("Typed confirmation confirmed");
+        if let Some(on_confirm) = on_confirm.borrow_mut().take() {
+            on_confirm();
+        }
+        dialog_clone.close();
+    });
+
+    dialog.present();
+    entry.grab_focus();
+}
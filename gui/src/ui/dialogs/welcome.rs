@@ -0,0 +1,187 @@
+//! First-run welcome wizard.
+//!
+//! Four steps in a navigation view: an intro, what was detected on the
+//! system, optional quick setup choices and a final page that runs the
+//! chosen setup only when confirmed there.
+
+use crate::config;
+use crate::core::package;
+use crate::core::setup::{QuickSetup, MIRROR_COUNTRIES};
+use crate::core::system_check::{self, DependencyCheckResult};
+use crate::i18n::{self, gettext};
+use crate::ui::task_runner;
+use crate::ui::utils::{builder_from_resource, expect_widget};
+use adw::prelude::*;
+use gtk4::{ApplicationWindow, Builder, Button, Label, StringList};
+use log::{info, warn};
+use std::rc::Rc;
+
+/// Show the welcome wizard and remember that it was shown.
+pub fn show_welcome_wizard(window: &ApplicationWindow, dependencies: &DependencyCheckResult) {
+    info!("Showing first-run welcome wizard");
+    // Shown once, whether the user finishes, skips or closes it
+    config::user::shared().update(|config| config.general.welcomed = true);
+
+    let builder = builder_from_resource(config::resources::dialogs::WELCOME);
+    let dialog: adw::Dialog = expect_widget(&builder, "welcome_dialog");
+    let navigation_view: adw::NavigationView = expect_widget(&builder, "navigation_view");
+
+    for id in [
+        "skip_intro_button",
+        "skip_system_button",
+        "skip_setup_button",
+        "finish_button",
+    ] {
+        let dialog = dialog.clone();
+        expect_widget::<Button>(&builder, id).connect_clicked(move |_| {
+            dialog.close();
+        });
+    }
+
+    for (id, next) in [
+        ("intro_next_button", "system"),
+        ("system_next_button", "setup"),
+        ("setup_next_button", "finish"),
+    ] {
+        let navigation_view = navigation_view.clone();
+        expect_widget::<Button>(&builder, id).connect_clicked(move |_| {
+            navigation_view.push_by_tag(next);
+        });
+    }
+
+    let flathub_enabled = !dependencies.flatpak_missing && package::is_flathub_enabled();
+    fill_system_summary(&builder, dependencies, flathub_enabled);
+    let choices = setup_quick_setup(&builder, dependencies, flathub_enabled);
+    setup_finish_page(&builder, &dialog, &navigation_view, window, choices);
+    setup_links(&builder);
+
+    dialog.present(Some(window));
+}
+
+fn fill_system_summary(
+    builder: &Builder,
+    dependencies: &DependencyCheckResult,
+    flathub_enabled: bool,
+) {
+    let distro_row: adw::ActionRow = expect_widget(builder, "distro_row");
+    let aur_helper_row: adw::ActionRow = expect_widget(builder, "aur_helper_row");
+    let flatpak_row: adw::ActionRow = expect_widget(builder, "flatpak_row");
+    let flathub_row: adw::ActionRow = expect_widget(builder, "flathub_row");
+
+    distro_row
+        .set_subtitle(&system_check::get_distribution_name().unwrap_or_else(|| gettext("Unknown")));
+    aur_helper_row.set_subtitle(
+        &crate::core::aur_helper()
+            .map(str::to_string)
+            .unwrap_or_else(|| gettext("Not installed")),
+    );
+    flatpak_row.set_subtitle(&if dependencies.flatpak_missing {
+        gettext("Not installed")
+    } else {
+        gettext("Installed")
+    });
+    flathub_row.set_subtitle(&if flathub_enabled {
+        gettext("Enabled")
+    } else {
+        gettext("Not enabled")
+    });
+}
+
+/// Wire up the quick setup rows and return a reader for the current choices.
+fn setup_quick_setup(
+    builder: &Builder,
+    dependencies: &DependencyCheckResult,
+    flathub_enabled: bool,
+) -> Rc<dyn Fn() -> QuickSetup> {
+    let flathub_switch: adw::SwitchRow = expect_widget(builder, "flathub_switch");
+    let aur_helper_switch: adw::SwitchRow = expect_widget(builder, "aur_helper_switch");
+    let mirror_row: adw::ComboRow = expect_widget(builder, "mirror_row");
+
+    if dependencies.flatpak_missing {
+        flathub_switch.set_sensitive(false);
+        flathub_switch.set_subtitle(&gettext("Flatpak is not installed"));
+    } else if flathub_enabled {
+        flathub_switch.set_sensitive(false);
+        flathub_switch.set_subtitle(&gettext("Flathub is already enabled"));
+    }
+
+    if let Some(helper) = crate::core::aur_helper() {
+        aur_helper_switch.set_sensitive(false);
+        aur_helper_switch.set_subtitle(&i18n::fill(&gettext("{} is already installed"), &[helper]));
+    }
+
+    let regions = StringList::new(&[&gettext("Keep Current Mirrors")]);
+    for (_, name) in MIRROR_COUNTRIES {
+        regions.append(&gettext(name));
+    }
+    mirror_row.set_model(Some(&regions));
+    mirror_row.set_selected(0);
+
+    Rc::new(move || QuickSetup {
+        flathub: flathub_switch.is_sensitive() && flathub_switch.is_active(),
+        aur_helper: aur_helper_switch.is_sensitive() && aur_helper_switch.is_active(),
+        // Index 0 keeps the current mirrors
+        mirror_country: (mirror_row.selected() as usize)
+            .checked_sub(1)
+            .and_then(|index| MIRROR_COUNTRIES.get(index))
+            .map(|(code, _)| *code),
+    })
+}
+
+fn setup_finish_page(
+    builder: &Builder,
+    dialog: &adw::Dialog,
+    navigation_view: &adw::NavigationView,
+    window: &ApplicationWindow,
+    choices: Rc<dyn Fn() -> QuickSetup>,
+) {
+    let summary_label: Label = expect_widget(builder, "summary_label");
+    let run_button: Button = expect_widget(builder, "run_button");
+
+    let Some(finish_page) = navigation_view.find_page("finish") else {
+        warn!("Welcome wizard has no finish page");
+        return;
+    };
+
+    let choices_clone = choices.clone();
+    let run_button_clone = run_button.clone();
+    finish_page.connect_showing(move |_| {
+        let setup = choices_clone();
+        let steps = setup.summary();
+        if setup.is_empty() {
+            summary_label.set_label(&gettext(
+                "No setup tasks selected. Everything can also be done later from the toolkit's pages.",
+            ));
+        } else {
+            let list: Vec<String> = steps.iter().map(|step| format!("• {}", step)).collect();
+            summary_label.set_label(&list.join("\n"));
+        }
+        run_button_clone.set_visible(!setup.is_empty());
+    });
+
+    let dialog = dialog.clone();
+    let window = window.clone();
+    run_button.connect_clicked(move |_| {
+        let setup = choices();
+        info!("Running quick setup: {:?}", setup);
+        dialog.close();
+        task_runner::run(
+            window.upcast_ref(),
+            setup.commands(),
+            &gettext("Quick Setup"),
+        );
+    });
+}
+
+fn setup_links(builder: &Builder) {
+    for (id, url) in [
+        ("discord_row", config::links::DISCORD),
+        ("docs_row", config::links::GUIDES),
+    ] {
+        expect_widget::<adw::ActionRow>(builder, id).connect_activated(move |_| {
+            if let Err(e) = package::open_url(url) {
+                warn!("Failed to open {}: {}", url, e);
+            }
+        });
+    }
+}
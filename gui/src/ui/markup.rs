@@ -0,0 +1,84 @@
+//! Pango markup for dialog text.
+//!
+//! Dialogs take their text as [`Markup`]. Plain strings convert into it by
+//! escaping, so package and kernel names containing `&` or `<` show as
+//! written; markup we wrote ourselves is passed with [`Markup::trusted`].
+
+use gtk4::glib;
+
+/// Escape `text` for use inside Pango markup.
+pub fn escape(text: &str) -> String {
+    glib::markup_escape_text(text).into()
+}
+
+/// Text that is valid Pango markup.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Markup(String);
+
+impl Markup {
+    /// Use `markup` as is. Only for markup written in the code or its
+    /// translations, with interpolated values escaped, as
+    /// [`crate::i18n::fill_markup`] does.
+    pub fn trusted(markup: impl Into<String>) -> Self {
+        Self(markup.into())
+    }
+
+    /// Escape plain `text`.
+    pub fn plain(text: &str) -> Self {
+        Self(escape(text))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Markup {
+    fn from(text: &str) -> Self {
+        Self::plain(text)
+    }
+}
+
+impl From<&String> for Markup {
+    fn from(text: &String) -> Self {
+        Self::plain(text)
+    }
+}
+
+impl From<String> for Markup {
+    fn from(text: String) -> Self {
+        Self::plain(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gtk4::pango;
+
+    /// Whether Pango accepts `markup`; labels log a GTK-CRITICAL otherwise.
+    fn parses(markup: &str) -> bool {
+        pango::parse_markup(markup, '\0').is_ok()
+    }
+
+    #[test]
+    fn test_plain_text_is_escaped() {
+        let text = "linux-foo&bar <rc> 6.11 > 6.10";
+        assert!(!parses(text));
+
+        let markup = Markup::from(text);
+        assert_eq!(
+            markup.as_str(),
+            "linux-foo&amp;bar &lt;rc&gt; 6.11 &gt; 6.10"
+        );
+        assert!(parses(markup.as_str()));
+        assert_eq!(Markup::from(text.to_string()), markup);
+    }
+
+    #[test]
+    fn test_trusted_markup_is_kept() {
+        let markup = Markup::trusted(crate::i18n::fill_markup("Remove <b>{}</b>?", &["a&b<c>"]));
+        assert_eq!(markup.as_str(), "Remove <b>a&amp;b&lt;c&gt;</b>?");
+        assert!(parses(markup.as_str()));
+    }
+}
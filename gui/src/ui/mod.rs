@@ -1,21 +1,39 @@
 //! User interface components and functionality.
 //!
 //! This module contains all UI-related components organized by functionality:
+//! - `accessibility`: Reduced-motion handling
 //! - `app`: Application setup and initialization
+//! - `badges`: Sidebar badges for pages that need attention
+//! - `click_guard`: Ignoring clicks while the previous one is handled
 //! - `context`: Application state and UI components
+//! - `markup`: Escaped and trusted Pango markup for dialogs
 //! - `navigation`: Tab navigation and sidebar management
 //! - `dialogs`: Dialog windows (error, selection, download)
 //! - `task_runner`: Command execution with progress UI
+//! - `toast`: Transient messages over the main window
 //! - `pages`: Page-specific button handlers
+//! - `session`: Logging out of the desktop session
+//! - `session_indicator`: Header bar indicator of the administrator session
+//! - `stateful_button`: Install buttons that turn into manage buttons
+//! - `update_banner`: Toolkit update notice
 
+pub mod accessibility;
 pub mod app;
+pub mod badges;
+pub mod click_guard;
 pub mod context;
 pub mod dialogs;
+pub mod markup;
 pub mod navigation;
 pub mod pages;
 pub mod seasonal;
+pub mod session;
+pub mod session_indicator;
+pub mod stateful_button;
 pub mod task_runner;
+pub mod toast;
+pub mod update_banner;
 pub mod utils;
 
 // Re-export the main entry point
-pub use app::setup_application_ui;
+pub use app::handle_launch;
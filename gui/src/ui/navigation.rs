@@ -6,27 +6,98 @@
 //! Pages are lazy-loaded asynchronously on first access to reduce initial memory usage
 //! and avoid UI lag spikes.
 
+use crate::i18n::{self, gettext, gettext_noop};
 use crate::ui::pages;
+use crate::ui::utils::{builder_from_resource, extract_widget, WidgetError};
 use gtk4::glib;
 use gtk4::prelude::*;
 use gtk4::{ApplicationWindow, Box as GtkBox, Builder, Button, Image, Label, Orientation, Stack};
 use log::{info, warn};
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Load time after which a page is reported as slow; probes that take
+/// longer belong on a worker thread.
+const SLOW_PAGE_LOAD: Duration = Duration::from_millis(250);
+
+thread_local! {
+    /// Badge label of each sidebar tab, by page id.
+    static TAB_BADGES: RefCell<HashMap<String, Label>> = RefCell::new(HashMap::new());
+}
+
+/// What a page's setup function is given when the page is first shown.
+pub struct PageContext {
+    /// Builder of the page's UI file
+    pub builder: Builder,
+    /// Builder of the main window
+    pub main_builder: Builder,
+    pub window: ApplicationWindow,
+}
+
+/// Connects a page's handlers.
+///
+/// A missing widget fails the page load, which then shows the error in place
+/// of the page.
+pub type SetupHandler = fn(&PageContext) -> Result<(), WidgetError>;
+
+/// Setup function taking the page builder, the main builder and the window
+/// one by one.
+pub type LegacySetupHandler = fn(&Builder, &Builder, &ApplicationWindow) -> Result<(), WidgetError>;
+
+/// How a page connects its handlers.
+#[derive(Clone, Copy)]
+pub enum PageSetup {
+    Context(SetupHandler),
+    /// Pages not moved to [`PageContext`] yet
+    Legacy(LegacySetupHandler),
+}
+
+impl PageSetup {
+    fn run(self, context: &PageContext) -> Result<(), WidgetError> {
+        match self {
+            Self::Context(setup) => setup(context),
+            Self::Legacy(setup) => setup(&context.builder, &context.main_builder, &context.window),
+        }
+    }
+}
 
 /// Configuration for a single page in the application.
 pub struct PageConfig {
     /// Internal identifier for the page (used in navigation)
     pub id: &'static str,
+    /// Command-line identifier used with `--page`
+    pub slug: &'static str,
     /// Display title for the page
     pub title: &'static str,
+    /// Extra search terms for the command palette
+    pub keywords: &'static [&'static str],
     /// Icon name for the tab button
     pub icon: &'static str,
     /// Resource path to the UI file
     pub ui_resource: &'static str,
     /// Function to set up event handlers for the page
-    pub setup_handler: Option<fn(&Builder, &Builder, &ApplicationWindow)>,
+    pub setup_handler: Option<PageSetup>,
+    /// Predicate deciding whether the page applies to this system (always shown if `None`)
+    pub condition: Option<fn() -> bool>,
+}
+
+impl PageConfig {
+    /// Check whether this page should be shown.
+    pub fn is_available(&self) -> bool {
+        self.condition.is_none_or(|condition| condition())
+    }
+}
+
+/// Pages that apply to this system, in display order.
+pub fn available_pages() -> impl Iterator<Item = &'static PageConfig> {
+    PAGES.iter().filter(|page| page.is_available())
+}
+
+/// Find a page by its command-line identifier.
+pub fn find_page(slug: &str) -> Option<&'static PageConfig> {
+    PAGES.iter().find(|page| page.slug == slug)
 }
 
 /// Central list of all pages in the application.
@@ -34,66 +105,159 @@ pub struct PageConfig {
 pub const PAGES: &[PageConfig] = &[
     PageConfig {
         id: "main_page",
-        title: "Main Page",
+        slug: "main",
+        title: gettext_noop("Main Page"),
+        keywords: &["update", "upgrade", "packages", "iso", "nix"],
         icon: "house-symbolic",
         ui_resource: crate::config::resources::tabs::MAIN_PAGE,
-        setup_handler: Some(pages::main_page::setup_handlers),
+        setup_handler: Some(PageSetup::Context(pages::main_page::setup_handlers)),
+        condition: None,
     },
     PageConfig {
         id: "drivers",
-        title: "Drivers",
+        slug: "drivers",
+        title: gettext_noop("Drivers"),
+        keywords: &[
+            "gpu",
+            "nvidia",
+            "amd",
+            "tailscale",
+            "vpn",
+            "asus",
+            "rog",
+            "hardware",
+        ],
         icon: "gear-symbolic",
         ui_resource: crate::config::resources::tabs::DRIVERS,
-        setup_handler: Some(pages::drivers::setup_handlers),
+        setup_handler: Some(PageSetup::Legacy(pages::drivers::setup_handlers)),
+        condition: None,
     },
     PageConfig {
         id: "customization",
-        title: "Customization",
+        slug: "customization",
+        title: gettext_noop("Customization"),
+        keywords: &[
+            "theme",
+            "zsh",
+            "shell",
+            "grub",
+            "plymouth",
+            "look",
+            "appearance",
+        ],
         icon: "brush-symbolic",
         ui_resource: crate::config::resources::tabs::CUSTOMIZATION,
-        setup_handler: Some(pages::customization::setup_handlers),
+        setup_handler: Some(PageSetup::Legacy(pages::customization::setup_handlers)),
+        condition: None,
     },
     PageConfig {
         id: "gaming_tools",
-        title: "Gaming Tools",
+        slug: "gaming-tools",
+        title: gettext_noop("Gaming Tools"),
+        keywords: &["steam", "lutris", "heroic", "bottles", "proton", "games"],
         icon: "gamepad-symbolic",
         ui_resource: crate::config::resources::tabs::GAMING_TOOLS,
-        setup_handler: Some(pages::gaming_tools::setup_handlers),
+        setup_handler: Some(PageSetup::Context(pages::gaming_tools::setup_handlers)),
+        condition: None,
     },
     PageConfig {
         id: "gamescope",
-        title: "Gamescope",
+        slug: "gamescope",
+        title: gettext_noop("Gamescope"),
+        keywords: &["steam deck", "session", "compositor", "games"],
         icon: "steam-symbolic",
         ui_resource: crate::config::resources::tabs::GAMESCOPE,
-        setup_handler: Some(pages::gamescope::setup_handlers),
+        setup_handler: Some(PageSetup::Context(pages::gamescope::setup_handlers)),
+        condition: None,
     },
     PageConfig {
         id: "containers_vms",
-        title: "Containers/VMs",
+        slug: "containers-vms",
+        title: gettext_noop("Containers/VMs"),
+        keywords: &[
+            "docker",
+            "podman",
+            "virtualbox",
+            "distrobox",
+            "kvm",
+            "qemu",
+            "virtual machine",
+        ],
         icon: "box-symbolic",
         ui_resource: crate::config::resources::tabs::CONTAINERS_VMS,
-        setup_handler: Some(pages::containers_vms::setup_handlers),
+        setup_handler: Some(PageSetup::Legacy(pages::containers_vms::setup_handlers)),
+        condition: None,
     },
     PageConfig {
         id: "kernel_schedulers",
-        title: "Kernel & Schedulers",
+        slug: "kernel-manager",
+        title: gettext_noop("Kernel & Schedulers"),
+        keywords: &["kernel", "scx", "scheduler", "cpu", "linux"],
         icon: "hammer-symbolic",
         ui_resource: crate::config::resources::tabs::KERNEL_SCHEDULERS,
-        setup_handler: Some(pages::kernel_schedulers::setup_handlers),
+        setup_handler: Some(PageSetup::Legacy(pages::kernel_schedulers::setup_handlers)),
+        condition: None,
     },
     PageConfig {
         id: "biometrics",
-        title: "Biometrics",
+        slug: "biometrics",
+        title: gettext_noop("Biometrics"),
+        keywords: &["fingerprint", "fprintd", "face", "login"],
         icon: "fingerprint-symbolic",
         ui_resource: crate::config::resources::tabs::BIOMETRICS,
-        setup_handler: Some(pages::biometrics::setup_handlers),
+        setup_handler: Some(PageSetup::Context(pages::biometrics::setup_handlers)),
+        condition: None,
     },
     PageConfig {
         id: "servicing_system_tweaks",
-        title: "Servicing/System tweaks",
+        slug: "servicing",
+        title: gettext_noop("Servicing/System tweaks"),
+        keywords: &["fix", "repair", "pacman", "keyring", "mirrors", "cache"],
         icon: "toolbox-symbolic",
         ui_resource: crate::config::resources::tabs::SERVICING_SYSTEM_TWEAKS,
-        setup_handler: Some(pages::servicing::setup_handlers),
+        setup_handler: Some(PageSetup::Legacy(pages::servicing::setup_handlers)),
+        condition: None,
+    },
+    PageConfig {
+        id: "services",
+        slug: "services",
+        title: gettext_noop("Services"),
+        keywords: &["systemd", "daemon", "enable", "startup"],
+        icon: "gears-symbolic",
+        ui_resource: crate::config::resources::tabs::SERVICES,
+        setup_handler: Some(PageSetup::Legacy(pages::services::setup_handlers)),
+        condition: None,
+    },
+    PageConfig {
+        id: "btrfs",
+        slug: "btrfs",
+        title: gettext_noop("Btrfs"),
+        keywords: &["snapshots", "snapper", "filesystem", "rollback"],
+        icon: "drive-harddisk-symbolic",
+        ui_resource: crate::config::resources::tabs::BTRFS,
+        setup_handler: Some(PageSetup::Context(pages::btrfs::setup_handlers)),
+        condition: Some(crate::core::fs::is_root_btrfs),
+    },
+    PageConfig {
+        id: "system_info",
+        slug: "system-info",
+        title: gettext_noop("System Info"),
+        keywords: &[
+            "about",
+            "hardware",
+            "cpu",
+            "gpu",
+            "ram",
+            "disk",
+            "smart",
+            "battery",
+            "specs",
+            "fastfetch",
+        ],
+        icon: "info-circle-symbolic",
+        ui_resource: crate::config::resources::tabs::SYSTEM_INFO,
+        setup_handler: Some(PageSetup::Context(pages::system_info::setup_handlers)),
+        condition: None,
     },
 ];
 
@@ -167,7 +331,7 @@ impl LazyPageLoader {
         let page_id_str = page_id.to_string();
         let ui_resource = config.ui_resource;
         let setup_handler = config.setup_handler;
-        let title = config.title;
+        let title = gettext(config.title);
         let main_builder = self.main_builder.clone();
         let window = self.window.clone();
         let container = container.clone();
@@ -210,7 +374,10 @@ impl LazyPageLoader {
                         spinner.set_icon_name(Some("dialog-error-symbolic"));
                     }
                     if let Some(label) = find_child_by_name::<Label>(&container, "loading_label") {
-                        label.set_label(&format!("Failed to load {}: {}", title, e));
+                        label.set_label(&i18n::fill(
+                            &gettext("Failed to load {}: {}"),
+                            &[&title, &e.to_string()],
+                        ));
                     }
 
                     // Remove from loading set but don't add to loaded
@@ -264,9 +431,18 @@ impl Tab {
 
         let label_widget = Label::new(Some(label));
         label_widget.set_xalign(0.0);
+        label_widget.set_hexpand(true);
+
+        let badge = Label::builder()
+            .css_classes(vec!["tab-badge".to_string()])
+            .valign(gtk4::Align::Center)
+            .visible(false)
+            .build();
 
         content_box.append(&image);
         content_box.append(&label_widget);
+        content_box.append(&badge);
+        TAB_BADGES.with(|badges| badges.borrow_mut().insert(page_name.to_string(), badge));
 
         let button = Button::builder()
             .hexpand(true)
@@ -274,6 +450,7 @@ impl Tab {
             .build();
 
         button.set_child(Some(&content_box));
+        button.set_widget_name(page_name);
 
         Tab {
             page_name: page_name.to_string(),
@@ -303,12 +480,13 @@ impl Tab {
 
 /// Create dynamic stack with pages and set up navigation tabs.
 /// Returns the fully configured stack with all pages and tabs ready.
-/// Pages are lazy-loaded asynchronously on first access.
+/// Pages are lazy-loaded asynchronously on first access, including the
+/// first one shown, which the caller opens with [`open_page`].
 pub fn create_stack_and_tabs(tabs_container: &GtkBox, main_builder: &Builder) -> Stack {
     info!("Creating dynamic stack with async lazy loading");
 
     // Extract window for lazy loader
-    let window: ApplicationWindow = crate::ui::utils::extract_widget(main_builder, "app_window");
+    let window: ApplicationWindow = crate::ui::utils::expect_widget(main_builder, "app_window");
 
     // Create lazy loader
     let loader = Rc::new(LazyPageLoader::new(main_builder.clone(), window));
@@ -318,36 +496,62 @@ pub fn create_stack_and_tabs(tabs_container: &GtkBox, main_builder: &Builder) ->
 
     info!(
         "Dynamic stack created with {} page placeholders",
-        PAGES.len()
+        available_pages().count()
     );
 
     // Set up navigation tabs
     info!("Setting up navigation tabs");
-    let mut first_button: Option<Button> = None;
-
-    for page_config in PAGES {
-        let tab = Tab::new(page_config.title, page_config.id, page_config.icon);
+    for page_config in available_pages() {
+        let tab = Tab::new(
+            &gettext(page_config.title),
+            page_config.id,
+            page_config.icon,
+        );
         tab.connect(&stack, tabs_container, &loader);
 
-        if first_button.is_none() {
-            first_button = Some(tab.button.clone());
-        }
-
         tabs_container.append(&tab.button);
         info!("Added tab: {} -> '{}'", page_config.title, page_config.id);
     }
 
-    // Set first tab as active
-    if let Some(button) = first_button {
-        button.add_css_class("active");
-    }
+    stack
+}
 
-    // Eagerly load the first page (main_page) since it's shown immediately
-    if let Some(first_page) = PAGES.first() {
-        loader.ensure_page_loaded(&stack, first_page.id);
+/// Switch to a page as if its tab had been clicked, loading it if needed.
+pub fn open_page(tabs_container: &GtkBox, page_id: &str) {
+    let mut child = tabs_container.first_child();
+    while let Some(widget) = child {
+        if widget.widget_name() == page_id {
+            if let Some(button) = widget.downcast_ref::<Button>() {
+                button.emit_clicked();
+                return;
+            }
+        }
+        child = widget.next_sibling();
     }
+    warn!("No tab found for page: {}", page_id);
+}
 
-    stack
+/// Show a badge on a page's sidebar tab, or hide it with `None`.
+///
+/// An empty string shows a dot, anything else is shown as a counter.
+pub fn set_page_badge(page_id: &str, badge: Option<String>) {
+    let Some(label) = TAB_BADGES.with(|badges| badges.borrow().get(page_id).cloned()) else {
+        // Pages unavailable on this system have no tab
+        return;
+    };
+
+    match badge {
+        Some(text) => {
+            if text.is_empty() {
+                label.add_css_class("dot");
+            } else {
+                label.remove_css_class("dot");
+            }
+            label.set_text(&text);
+            label.set_visible(true);
+        }
+        None => label.set_visible(false),
+    }
 }
 
 /// Create a dynamic stack with placeholder containers for lazy loading.
@@ -358,15 +562,19 @@ fn create_lazy_stack(main_builder: &Builder) -> Stack {
     stack.set_transition_type(gtk4::StackTransitionType::Crossfade);
 
     // Create placeholder containers for each page
-    for page_config in PAGES {
+    for page_config in available_pages() {
         let container = create_placeholder_container(page_config);
-        stack.add_titled(&container, Some(page_config.id), page_config.title);
+        stack.add_titled(
+            &container,
+            Some(page_config.id),
+            &gettext(page_config.title),
+        );
         info!("Created placeholder for page: {}", page_config.id);
     }
 
     // Add the dynamic stack to the right container
     let right_container =
-        crate::ui::utils::extract_widget::<GtkBox>(main_builder, "right_container");
+        crate::ui::utils::expect_widget::<GtkBox>(main_builder, "right_container");
     right_container.append(&stack);
     info!("Dynamic stack added to right container");
 
@@ -398,7 +606,10 @@ fn create_placeholder_container(config: &PageConfig) -> GtkBox {
 
     // Loading label
     let loading_label = Label::builder()
-        .label(format!("Loading {}...", config.title))
+        .label(i18n::fill(
+            &gettext("Loading {}..."),
+            &[&gettext(config.title)],
+        ))
         .halign(gtk4::Align::Center)
         .build();
     loading_label.set_widget_name("loading_label");
@@ -415,21 +626,39 @@ fn create_placeholder_container(config: &PageConfig) -> GtkBox {
 fn load_page_content(
     page_id: &str,
     ui_resource: &str,
-    setup_handler: Option<fn(&Builder, &Builder, &ApplicationWindow)>,
+    setup_handler: Option<PageSetup>,
     main_builder: &Builder,
     window: &ApplicationWindow,
 ) -> anyhow::Result<gtk4::Widget> {
-    let page_builder = Builder::from_resource(ui_resource);
+    let started = Instant::now();
+    let page_builder = builder_from_resource(ui_resource);
 
-    let page_widget: gtk4::Widget = page_builder
-        .object(format!("page_{}", page_id))
-        .ok_or_else(|| {
-            anyhow::anyhow!("Could not find page_{} widget in {}", page_id, ui_resource)
-        })?;
+    let page_widget: gtk4::Widget = extract_widget(&page_builder, &format!("page_{}", page_id))?;
+    let built = started.elapsed();
 
     // Call setup handler if provided
-    if let Some(setup_fn) = setup_handler {
-        setup_fn(&page_builder, main_builder, window);
+    if let Some(setup) = setup_handler {
+        let context = PageContext {
+            builder: page_builder,
+            main_builder: main_builder.clone(),
+            window: window.clone(),
+        };
+        setup.run(&context)?;
+    }
+
+    let total = started.elapsed();
+    info!(
+        "Page '{}' built in {} ms, set up in {} ms",
+        page_id,
+        built.as_millis(),
+        (total - built).as_millis()
+    );
+    if total > SLOW_PAGE_LOAD {
+        warn!(
+            "Page '{}' blocked the main thread for {} ms while loading",
+            page_id,
+            total.as_millis()
+        );
     }
 
     Ok(page_widget)
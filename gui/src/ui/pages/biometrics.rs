@@ -5,17 +5,37 @@
 //! - Howdy facial recognition setup (xero-howdy-qt)
 
 use crate::core;
+use crate::core::system_check::{self, Capability};
+use crate::i18n::gettext;
+use crate::ui::navigation::PageContext;
 use crate::ui::task_runner::{self, Command, CommandSequence};
-use crate::ui::utils::extract_widget;
+use crate::ui::utils::{extract_widget, require_capabilities, WidgetError};
 use gtk4::prelude::*;
-use gtk4::{ApplicationWindow, Builder};
+use gtk4::{gio, glib, ApplicationWindow, Builder};
 use log::{error, info};
 use std::process::{Command as StdCommand, Stdio};
 
+/// What installing and removing the biometrics tools needs.
+const REQUIRED: &[Capability] = &[Capability::Pacman, Capability::Escalate];
+
 /// Set up all button handlers for the biometrics page
-pub fn setup_handlers(page_builder: &Builder, _main_builder: &Builder, window: &ApplicationWindow) {
-    setup_fingerprint(page_builder, window);
-    setup_howdy(page_builder, window);
+pub fn setup_handlers(context: &PageContext) -> Result<(), WidgetError> {
+    let page_builder = &context.builder;
+    let window = &context.window;
+    setup_fingerprint(page_builder, window)?;
+    setup_howdy(page_builder, window)?;
+
+    require_capabilities(
+        page_builder,
+        REQUIRED,
+        &[
+            "btn_fingerprint_setup",
+            "btn_fingerprint_uninstall",
+            "btn_howdy_setup",
+            "btn_howdy_uninstall",
+        ],
+    )?;
+    Ok(())
 }
 
 /// Helper to update button appearance based on installation status and control uninstall button visibility
@@ -25,13 +45,13 @@ fn update_button_state(
     is_installed: bool,
 ) {
     if is_installed {
-        install_button.set_label("Launch App");
+        install_button.set_label(&gettext("Launch App"));
         install_button.add_css_class("suggested-action");
         // Show uninstall when installed; UI defines icon/styling
         uninstall_button.set_visible(true);
-        uninstall_button.set_sensitive(true);
+        uninstall_button.set_sensitive(system_check::environment().has_all(REQUIRED));
     } else {
-        install_button.set_label("Install");
+        install_button.set_label(&gettext("Install"));
         install_button.remove_css_class("suggested-action");
         // Hide uninstall when not installed
         uninstall_button.set_visible(false);
@@ -39,28 +59,38 @@ fn update_button_state(
     }
 }
 
-fn setup_fingerprint(page_builder: &Builder, window: &ApplicationWindow) {
+/// Check for the fingerprint tool on a worker thread, since without a local
+/// package database the check asks the AUR helper, then update the buttons.
+fn refresh_fingerprint_state(setup_button: &gtk4::Button, uninstall_button: &gtk4::Button) {
+    let setup_button = setup_button.clone();
+    let uninstall_button = uninstall_button.clone();
+    glib::spawn_future_local(async move {
+        let installed = gio::spawn_blocking(|| core::is_package_installed("xfprintd-gui"))
+            .await
+            .unwrap_or(false);
+        update_button_state(&setup_button, &uninstall_button, installed);
+    });
+}
+
+fn setup_fingerprint(
+    page_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
     // Both buttons are expected to be defined in the UI; we will simply toggle visibility.
     let btn_fingerprint_setup =
-        extract_widget::<gtk4::Button>(page_builder, "btn_fingerprint_setup");
+        extract_widget::<gtk4::Button>(page_builder, "btn_fingerprint_setup")?;
     let btn_fingerprint_uninstall =
-        extract_widget::<gtk4::Button>(page_builder, "btn_fingerprint_uninstall");
+        extract_widget::<gtk4::Button>(page_builder, "btn_fingerprint_uninstall")?;
 
     // Initial check
-    let is_installed = core::is_package_installed("xfprintd-gui");
-    update_button_state(
-        &btn_fingerprint_setup,
-        &btn_fingerprint_uninstall,
-        is_installed,
-    );
+    refresh_fingerprint_state(&btn_fingerprint_setup, &btn_fingerprint_uninstall);
 
     // Update on window focus (e.g. after installation completes)
     let btn_clone = btn_fingerprint_setup.clone();
     let uninstall_clone = btn_fingerprint_uninstall.clone();
     window.connect_is_active_notify(move |window| {
         if window.is_active() {
-            let is_installed = core::is_package_installed("xfprintd-gui");
-            update_button_state(&btn_clone, &uninstall_clone, is_installed);
+            refresh_fingerprint_state(&btn_clone, &uninstall_clone);
         }
     });
 
@@ -84,8 +114,8 @@ fn setup_fingerprint(page_builder: &Builder, window: &ApplicationWindow) {
                 .then(
                     Command::builder()
                         .aur()
-                        .args(&["-S", "--noconfirm", "--needed", "xfprintd-gui"])
-                        .description("Installing Fingerprint GUI Tool...")
+                        .args(&["-S", "--needed", "xfprintd-gui"])
+                        .description(&gettext("Installing Fingerprint GUI Tool..."))
                         .build(),
                 )
                 .build();
@@ -93,7 +123,7 @@ fn setup_fingerprint(page_builder: &Builder, window: &ApplicationWindow) {
             task_runner::run(
                 window_clone.upcast_ref(),
                 commands,
-                "Install Fingerprint GUI Tool",
+                &gettext("Install Fingerprint GUI Tool"),
             );
         }
     });
@@ -108,8 +138,8 @@ fn setup_fingerprint(page_builder: &Builder, window: &ApplicationWindow) {
             .then(
                 Command::builder()
                     .aur()
-                    .args(&["-R", "--noconfirm", "xfprintd-gui"])
-                    .description("Removing Fingerprint GUI Tool...")
+                    .args(&["-R", "xfprintd-gui"])
+                    .description(&gettext("Removing Fingerprint GUI Tool..."))
                     .build(),
             )
             .build();
@@ -117,16 +147,18 @@ fn setup_fingerprint(page_builder: &Builder, window: &ApplicationWindow) {
         task_runner::run(
             window_uninstall.upcast_ref(),
             commands,
-            "Remove Fingerprint GUI Tool",
+            &gettext("Remove Fingerprint GUI Tool"),
         );
     });
+    Ok(())
 }
 
-fn setup_howdy(page_builder: &Builder, _window: &ApplicationWindow) {
-    let btn_howdy_setup = extract_widget::<gtk4::Button>(page_builder, "btn_howdy_setup");
+fn setup_howdy(page_builder: &Builder, _window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let btn_howdy_setup = extract_widget::<gtk4::Button>(page_builder, "btn_howdy_setup")?;
 
     // Disable Howdy setup as it's not ready yet
     btn_howdy_setup.set_sensitive(false);
+    Ok(())
 }
 
 // fn setup_howdy(page_builder: &Builder, window: &ApplicationWindow) {
@@ -165,7 +197,7 @@ fn setup_howdy(page_builder: &Builder, _window: &ApplicationWindow) {
 //                 .then(
 //                     Command::builder()
 //                         .aur()
-//                         .args(&["-S", "--noconfirm", "--needed", "xero-howdy-qt"])
+//                         .args(&["-S", "--needed", "xero-howdy-qt"])
 //                         .description("Installing Xero Howdy Qt...")
 //                         .build(),
 //                 )
@@ -0,0 +1,270 @@
+//! Btrfs maintenance page handlers.
+//!
+//! Handles:
+//! - Usage breakdown of the root filesystem
+//! - Scrub and filtered balance with live status output
+//! - Periodic scrub timer toggle
+
+use crate::core::actions;
+use crate::core::download::format_bytes;
+use crate::core::fs::{self, BtrfsUsage};
+use crate::core::system_check::Capability;
+use crate::core::systemd;
+use crate::i18n::{self, gettext};
+use crate::ui::navigation::PageContext;
+use crate::ui::task_runner::{self, Command, CommandSequence};
+use crate::ui::utils::{extract_widget, require_capabilities, WidgetError};
+use adw::prelude::*;
+use gtk4::glib;
+use gtk4::{ApplicationWindow, Builder, Button, ListBox, ProgressBar};
+use log::{info, warn};
+use std::rc::Rc;
+
+/// Scrub timer shipped by btrfs-progs, instantiated for `/`.
+const SCRUB_TIMER: &str = "btrfs-scrub@-.timer";
+
+/// Balances block groups under 50% usage, printing status until it
+/// finishes, and exits with the balance's status.
+const BALANCE_SCRIPT: &str = "btrfs balance start -dusage=50 -musage=50 / & \
+    balance=$!; \
+    sleep 1; \
+    while kill -0 $balance 2>/dev/null; do \
+        btrfs balance status / | tail -n +2; sleep 5; \
+    done; \
+    wait $balance";
+
+/// Shared widgets for the page.
+struct BtrfsPage {
+    window: ApplicationWindow,
+    usage_list: ListBox,
+    refresh_button: Button,
+    timer_switch: adw::SwitchRow,
+}
+
+/// Set up all handlers for the btrfs page.
+pub fn setup_handlers(context: &PageContext) -> Result<(), WidgetError> {
+    let page_builder = &context.builder;
+    let window = &context.window;
+    let page = Rc::new(BtrfsPage {
+        window: window.clone(),
+        usage_list: extract_widget(page_builder, "btrfs_usage_list")?,
+        refresh_button: extract_widget(page_builder, "btn_refresh_btrfs")?,
+        timer_switch: extract_widget(page_builder, "btrfs_scrub_timer_switch")?,
+    });
+
+    let page_clone = page.clone();
+    page.refresh_button.connect_clicked(move |_| {
+        info!("Btrfs refresh button clicked");
+        refresh(&page_clone);
+    });
+
+    setup_scrub(page_builder, &page)?;
+    setup_balance(page_builder, &page)?;
+    setup_timer_switch(&page);
+
+    refresh(&page);
+
+    require_capabilities(
+        page_builder,
+        &[Capability::Escalate],
+        &["btn_btrfs_scrub", "btn_btrfs_balance"],
+    )?;
+    Ok(())
+}
+
+fn setup_scrub(builder: &Builder, page: &Rc<BtrfsPage>) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_btrfs_scrub")?;
+    let page = page.clone();
+
+    button.connect_clicked(move |_| {
+        info!("Btrfs scrub button clicked");
+
+        let commands = actions::btrfs_scrub().build();
+
+        let page_clone = page.clone();
+        task_runner::run_with_completion(
+            page.window.upcast_ref(),
+            commands,
+            &gettext("Btrfs Scrub"),
+            move |_| refresh(&page_clone),
+        );
+    });
+    Ok(())
+}
+
+fn setup_balance(builder: &Builder, page: &Rc<BtrfsPage>) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_btrfs_balance")?;
+    let page = page.clone();
+
+    button.connect_clicked(move |_| {
+        info!("Btrfs balance button clicked");
+
+        let commands = CommandSequence::new()
+            .then(
+                Command::builder()
+                    .privileged()
+                    .program("sh")
+                    .args(&["-c", BALANCE_SCRIPT])
+                    .description(&gettext("Balancing partially used block groups..."))
+                    .build(),
+            )
+            .build();
+
+        let page_clone = page.clone();
+        task_runner::run_with_completion(
+            page.window.upcast_ref(),
+            commands,
+            &gettext("Btrfs Balance"),
+            move |_| refresh(&page_clone),
+        );
+    });
+    Ok(())
+}
+
+fn setup_timer_switch(page: &Rc<BtrfsPage>) {
+    let page_clone = page.clone();
+    page.timer_switch.connect_active_notify(move |switch| {
+        // Ignore changes made while syncing to the real unit state
+        if !switch.is_sensitive() {
+            return;
+        }
+
+        let action = if switch.is_active() {
+            "enable"
+        } else {
+            "disable"
+        };
+        info!("Btrfs scrub timer: {}", action);
+
+        // Another action owns the runner; put the switch back, made
+        // insensitive so this handler ignores it
+        if task_runner::busy() {
+            switch.set_sensitive(false);
+            switch.set_active(!switch.is_active());
+            switch.set_sensitive(true);
+            return;
+        }
+
+        let commands = CommandSequence::new()
+            .then(
+                Command::builder()
+                    .privileged()
+                    .program("systemctl")
+                    .args(&[action, "--now", SCRUB_TIMER])
+                    .description(&i18n::fill(
+                        &gettext("Running systemctl {} {}..."),
+                        &[action, SCRUB_TIMER],
+                    ))
+                    .build(),
+            )
+            .build();
+
+        let page_for_refresh = page_clone.clone();
+        task_runner::run_with_completion(
+            page_clone.window.upcast_ref(),
+            commands,
+            &gettext("Btrfs Scrub Timer"),
+            move |_| refresh(&page_for_refresh),
+        );
+    });
+}
+
+/// Re-read usage and timer state in the background.
+fn refresh(page: &Rc<BtrfsPage>) {
+    page.refresh_button.set_sensitive(false);
+    page.timer_switch.set_sensitive(false);
+
+    let (sender, receiver) = std::sync::mpsc::channel::<(Option<BtrfsUsage>, bool)>();
+
+    std::thread::spawn(move || {
+        let usage = fs::btrfs_usage("/")
+            .map_err(|e| warn!("Failed to read btrfs usage: {}", e))
+            .ok();
+        let timer_enabled = systemd::unit_details(&[SCRUB_TIMER])
+            .ok()
+            .and_then(|details| details.into_iter().next())
+            .is_some_and(|details| details.is_enabled());
+        let _ = sender.send((usage, timer_enabled));
+    });
+
+    let page = page.clone();
+    glib::timeout_add_local(
+        std::time::Duration::from_millis(100),
+        move || match receiver.try_recv() {
+            Ok((usage, timer_enabled)) => {
+                populate_usage(&page.usage_list, usage.as_ref());
+                page.timer_switch.set_active(timer_enabled);
+                page.timer_switch.set_sensitive(true);
+                page.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                page.timer_switch.set_sensitive(true);
+                page.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+        },
+    );
+}
+
+/// Show one labeled bar per block group type plus unallocated space.
+fn populate_usage(list: &ListBox, usage: Option<&BtrfsUsage>) {
+    while let Some(child) = list.first_child() {
+        list.remove(&child);
+    }
+
+    let Some(usage) = usage else {
+        list.append(
+            &adw::ActionRow::builder()
+                .title(gettext("Usage unavailable"))
+                .subtitle(gettext("Could not run btrfs filesystem usage"))
+                .build(),
+        );
+        return;
+    };
+
+    let rows = [
+        ("Data", usage.data.used, usage.data.size),
+        ("Metadata", usage.metadata.used, usage.metadata.size),
+        ("System", usage.system.used, usage.system.size),
+        (
+            "Unallocated",
+            usage.unallocated,
+            usage.device_size.max(usage.unallocated),
+        ),
+    ];
+
+    for (title, used, total) in rows {
+        let fraction = if total > 0 {
+            used as f64 / total as f64
+        } else {
+            0.0
+        };
+
+        let subtitle = if title == "Unallocated" {
+            i18n::fill(
+                &gettext("{} free of {}"),
+                &[&format_bytes(used), &format_bytes(total)],
+            )
+        } else {
+            i18n::fill(
+                &gettext("{} used of {} allocated"),
+                &[&format_bytes(used), &format_bytes(total)],
+            )
+        };
+
+        let bar = ProgressBar::builder()
+            .fraction(fraction)
+            .valign(gtk4::Align::Center)
+            .width_request(200)
+            .build();
+
+        let row = adw::ActionRow::builder()
+            .title(title)
+            .subtitle(subtitle)
+            .build();
+        row.add_suffix(&bar);
+        list.append(&row);
+    }
+}
@@ -2,114 +2,241 @@
 //!
 //! Handles:
 //! - Docker installation and setup
-//! - Podman installation (with optional Desktop)
+//! - Podman installation (with optional Desktop) and rootless readiness
+//!   checks with fixes
 //! - VirtualBox installation
 //! - DistroBox installation
 //! - KVM/QEMU virtualization setup
 //! - iOS iPA Sideloader (Plume Impactor from Flathub)
 
 use crate::core;
+use crate::core::podman::{self, Readiness};
+use crate::core::privileged_fs;
+use crate::core::system_check::Capability;
+use crate::i18n::{self, gettext};
+use crate::ui::click_guard::connect_clicked_once_until_done;
+use crate::ui::dialogs::error::show_error;
 use crate::ui::dialogs::selection::{
     show_selection_dialog, SelectionDialogConfig, SelectionOption, SelectionType,
 };
+use crate::ui::dialogs::warning::show_warning_confirmation;
+use crate::ui::markup::Markup;
 use crate::ui::task_runner::{self, Command, CommandSequence};
-use crate::ui::utils::extract_widget;
-use gtk4::prelude::*;
+use crate::ui::utils::{extract_widget, require_capabilities, WidgetError};
+use adw::prelude::*;
+use gtk4::glib;
 use gtk4::{ApplicationWindow, Builder, Button};
 use log::info;
+use std::cell::RefCell;
+use std::rc::Rc;
 
 /// Set up all button handlers for the containers/VMs page.
-pub fn setup_handlers(page_builder: &Builder, _main_builder: &Builder, window: &ApplicationWindow) {
-    setup_docker(page_builder, window);
-    setup_podman(page_builder, window);
-    setup_vbox(page_builder, window);
-    setup_distrobox(page_builder, window);
-    setup_kvm(page_builder, window);
-    setup_ipa_sideloader(page_builder, window);
+pub fn setup_handlers(
+    page_builder: &Builder,
+    _main_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    setup_docker(page_builder, window)?;
+    let podman_panel = setup_podman_panel(page_builder, window)?;
+    setup_podman(page_builder, window, &podman_panel)?;
+    setup_vbox(page_builder, window)?;
+    setup_distrobox(page_builder, window)?;
+    setup_kvm(page_builder, window)?;
+    setup_ipa_sideloader(page_builder, window)?;
+
+    require_capabilities(
+        page_builder,
+        &[
+            Capability::Pacman,
+            Capability::Services,
+            Capability::Escalate,
+        ],
+        &[
+            "btn_docker",
+            "btn_podman",
+            "btn_vbox",
+            "btn_distrobox",
+            "btn_kvm",
+            "btn_podman_fix",
+        ],
+    )?;
+    require_capabilities(
+        page_builder,
+        &[Capability::Flatpak],
+        &["btn_ipa_sideloader"],
+    )?;
+    Ok(())
 }
 
-fn setup_docker(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_docker");
+fn setup_docker(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_docker")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
         info!("Docker button clicked");
 
-        let user = crate::config::env::get().user.clone();
+        if core::is_package_installed("podman-docker") {
+            let window_clone = window.clone();
+            show_warning_confirmation(
+                window.upcast_ref(),
+                &gettext("Conflicting Package Detected"),
+                Markup::trusted(gettext("<b>podman-docker</b> is installed. It provides a <tt>docker</tt> shim for Podman \
+                 and conflicts with the Docker engine.\n\n\
+                 Continuing will remove podman-docker before installing Docker. \
+                 Podman itself is left untouched.")),
+                move || run_docker_setup(&window_clone, true),
+            );
+        } else {
+            run_docker_setup(&window, false);
+        }
+    });
+    Ok(())
+}
 
-        let commands = CommandSequence::new()
-            .then(
-                Command::builder()
-                    .aur()
-                    .args(&[
-                        "-S",
-                        "--noconfirm",
-                        "--needed",
-                        "docker",
-                        "docker-compose",
-                        "docker-buildx",
-                    ])
-                    .description("Installing Docker engine and tools...")
-                    .build(),
-            )
-            .then(
-                Command::builder()
-                    .privileged()
-                    .program("systemctl")
-                    .args(&["enable", "--now", "docker.service"])
-                    .description("Enabling Docker service...")
-                    .build(),
-            )
-            .then(
-                Command::builder()
-                    .privileged()
-                    .program("groupadd")
-                    .args(&["-f", "docker"])
-                    .description("Ensuring docker group exists...")
-                    .build(),
-            )
-            .then(
-                Command::builder()
-                    .privileged()
-                    .program("usermod")
-                    .args(&["-aG", "docker", &user])
-                    .description("Adding your user to docker group...")
-                    .build(),
-            )
-            .build();
+fn run_docker_setup(window: &ApplicationWindow, remove_podman_docker: bool) {
+    let user = crate::config::env::get().user.clone();
+    let needs_relogin = !core::system_check::is_user_in_group(&user, "docker");
 
-        task_runner::run(window.upcast_ref(), commands, "Docker Setup");
+    let mut commands = CommandSequence::new();
+
+    if remove_podman_docker {
+        commands = commands.then(
+            Command::builder()
+                .privileged()
+                .program("pacman")
+                .args(&["-Rdd", "--noconfirm", "podman-docker"])
+                .description(&gettext("Removing conflicting podman-docker shim..."))
+                .build(),
+        );
+    }
+
+    let commands = commands
+        .then(
+            Command::builder()
+                .aur()
+                .args(&[
+                    "-S",
+                    "--needed",
+                    "docker",
+                    "docker-compose",
+                    "docker-buildx",
+                ])
+                .description(&gettext("Installing Docker engine and tools..."))
+                .build(),
+        )
+        .then(
+            Command::builder()
+                .privileged()
+                .program("systemctl")
+                .args(&["enable", "--now", "docker.service"])
+                .description(&gettext("Enabling Docker service..."))
+                .build(),
+        )
+        .then(
+            Command::builder()
+                .privileged()
+                .program("groupadd")
+                .args(&["-f", "docker"])
+                .description(&gettext("Ensuring docker group exists..."))
+                .build(),
+        )
+        .then(
+            Command::builder()
+                .privileged()
+                .program("usermod")
+                .args(&["-aG", "docker", &user])
+                .description(&gettext("Adding your user to docker group..."))
+                .build(),
+        )
+        .then(
+            Command::builder()
+                .privileged()
+                .program("docker")
+                .args(&["info"])
+                .description(&gettext("Verifying Docker daemon is working..."))
+                .build(),
+        )
+        .build();
+
+    let window_clone = window.clone();
+    task_runner::run_with_completion(
+        window.upcast_ref(),
+        commands,
+        &gettext("Docker Setup"),
+        move |success| {
+            if success && needs_relogin {
+                show_relogin_notice(&window_clone, "docker");
+            }
+        },
+    );
+}
+
+/// Explain that new group membership needs a fresh login, offering to log out.
+fn show_relogin_notice(window: &ApplicationWindow, group: &str) {
+    let body = i18n::fill_markup(
+        &gettext(
+            "You were added to the <b>{}</b> group. Group changes only apply to new login sessions, \
+             so commands like <tt>docker ps</tt> will fail with \"permission denied\" until you log out \
+             and back in.\n\n\
+             To use it right away in a single terminal, run <tt>newgrp {}</tt> there.",
+        ),
+        &[group, group],
+    );
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext("Log Out Required"))
+        .body(body)
+        .body_use_markup(true)
+        .build();
+    dialog.add_response("later", &gettext("Later"));
+    dialog.add_response("logout", &gettext("Log Out Now"));
+    dialog.set_response_appearance("logout", adw::ResponseAppearance::Destructive);
+    dialog.set_default_response(Some("later"));
+    dialog.set_close_response("later");
+
+    dialog.connect_response(None, |_, response| {
+        if response == "logout" {
+            crate::ui::session::log_out();
+        }
     });
+
+    dialog.present(Some(window));
 }
 
-fn setup_podman(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_podman");
+fn setup_podman(
+    builder: &Builder,
+    window: &ApplicationWindow,
+    panel: &Rc<PodmanPanel>,
+) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_podman")?;
     let window = window.clone();
-    button.connect_clicked(move |_| {
+    let panel = panel.clone();
+    connect_clicked_once_until_done(&button, move |_, token| {
         info!("Podman button clicked");
 
         let config = SelectionDialogConfig::new(
-            "Podman Installation",
-            "Podman will be installed. Optionally include the Podman Desktop GUI.",
+            &gettext("Podman Installation"),
+            gettext("Podman will be installed. Optionally include the Podman Desktop GUI."),
         )
         .selection_type(SelectionType::Single)
         .selection_required(false)
         .add_option(SelectionOption::new(
             "podman_desktop",
-            "Podman Desktop",
-            "Graphical interface for managing containers",
+            &gettext("Podman Desktop"),
+            gettext("Graphical interface for managing containers"),
             core::is_flatpak_installed("io.podman_desktop.PodmanDesktop"),
         ))
-        .confirm_label("Install");
+        .confirm_label(&gettext("Install"));
 
         let window_for_closure = window.clone();
-        show_selection_dialog(window.upcast_ref(), config, move |selected| {
+        let panel = panel.clone();
+        let dialog = show_selection_dialog(window.upcast_ref(), config, move |selected| {
             let mut commands = CommandSequence::new()
                 .then(
                     Command::builder()
                         .aur()
-                        .args(&["-S", "--noconfirm", "--needed", "podman", "podman-docker"])
-                        .description("Installing Podman container engine...")
+                        .args(&["-S", "--needed", "podman", "podman-docker"])
+                        .description(&gettext("Installing Podman container engine..."))
                         .build(),
                 )
                 .then(
@@ -117,7 +244,7 @@ fn setup_podman(builder: &Builder, window: &ApplicationWindow) {
                         .privileged()
                         .program("systemctl")
                         .args(&["enable", "--now", "podman.socket"])
-                        .description("Enabling Podman socket...")
+                        .description(&gettext("Enabling Podman socket..."))
                         .build(),
                 );
 
@@ -132,24 +259,252 @@ fn setup_podman(builder: &Builder, window: &ApplicationWindow) {
                             "flathub",
                             "io.podman_desktop.PodmanDesktop",
                         ])
-                        .description("Installing Podman Desktop GUI...")
+                        .description(&gettext("Installing Podman Desktop GUI..."))
                         .build(),
                 );
             }
 
             if !commands.is_empty() {
-                task_runner::run(
+                // Installing podman alone does not make it work rootless
+                let panel = panel.clone();
+                task_runner::run_with_completion(
                     window_for_closure.upcast_ref(),
                     commands.build(),
-                    "Podman Setup",
+                    &gettext("Podman Setup"),
+                    move |_| {
+                        panel.refresh_button.grab_focus();
+                        refresh_podman(&panel);
+                    },
                 );
             }
         });
+        token.release_on_close(&dialog);
     });
+    Ok(())
 }
 
-fn setup_vbox(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_vbox");
+/// Widgets of the rootless Podman checklist.
+struct PodmanPanel {
+    window: ApplicationWindow,
+    group: adw::PreferencesGroup,
+    refresh_button: Button,
+    fix_button: Button,
+    /// Check rows, rebuilt on every refresh.
+    rows: RefCell<Vec<adw::ActionRow>>,
+    /// Outcome of the last check, for the fix
+    readiness: RefCell<Option<Readiness>>,
+}
+
+fn setup_podman_panel(
+    builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<Rc<PodmanPanel>, WidgetError> {
+    let panel = Rc::new(PodmanPanel {
+        window: window.clone(),
+        group: extract_widget(builder, "podman_group")?,
+        refresh_button: extract_widget(builder, "btn_podman_refresh")?,
+        fix_button: extract_widget(builder, "btn_podman_fix")?,
+        rows: RefCell::new(Vec::new()),
+        readiness: RefCell::new(None),
+    });
+
+    let panel_clone = panel.clone();
+    panel.refresh_button.connect_clicked(move |_| {
+        info!("Podman refresh button clicked");
+        refresh_podman(&panel_clone);
+    });
+
+    let panel_clone = panel.clone();
+    panel.fix_button.connect_clicked(move |_| {
+        info!("Podman fix button clicked");
+        fix_podman(&panel_clone);
+    });
+
+    refresh_podman(&panel);
+    Ok(panel)
+}
+
+/// Check rootless Podman in the background and rebuild the checklist. The
+/// panel stays hidden while podman is not installed.
+fn refresh_podman(panel: &Rc<PodmanPanel>) {
+    panel.refresh_button.set_sensitive(false);
+
+    let user = crate::config::env::get().user.clone();
+    let uid = unsafe { libc::getuid() };
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let readiness = core::is_package_installed("podman").then(|| podman::check(&user, uid));
+        let _ = sender.send(readiness);
+    });
+
+    let panel = panel.clone();
+    glib::timeout_add_local(
+        std::time::Duration::from_millis(100),
+        move || match receiver.try_recv() {
+            Ok(Some(readiness)) => {
+                apply_podman_readiness(&panel, &readiness);
+                panel.readiness.replace(Some(readiness));
+                panel.group.set_visible(true);
+                panel.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+            Ok(None) => {
+                panel.group.set_visible(false);
+                panel.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                panel.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+        },
+    );
+}
+
+fn apply_podman_readiness(panel: &PodmanPanel, readiness: &Readiness) {
+    for row in panel.rows.borrow_mut().drain(..) {
+        panel.group.remove(&row);
+    }
+
+    let ranges = |ok: bool, path: &str| {
+        if ok {
+            i18n::fill(&gettext("Listed in {}"), &[path])
+        } else {
+            i18n::fill(&gettext("No range for your user in {}"), &[path])
+        }
+    };
+    let helper = |ok: bool| {
+        if ok {
+            gettext("Allowed to map IDs")
+        } else {
+            gettext("Neither setuid root nor holding the capability to map IDs")
+        }
+    };
+    let info = match &readiness.info {
+        Ok(()) => gettext("Podman runs without root"),
+        Err(e) => e.clone(),
+    };
+
+    let checks = [
+        (
+            gettext("User ID range"),
+            readiness.subuid,
+            ranges(readiness.subuid, podman::SUBUID_PATH),
+        ),
+        (
+            gettext("Group ID range"),
+            readiness.subgid,
+            ranges(readiness.subgid, podman::SUBGID_PATH),
+        ),
+        (
+            "newuidmap".to_string(),
+            readiness.newuidmap,
+            helper(readiness.newuidmap),
+        ),
+        (
+            "newgidmap".to_string(),
+            readiness.newgidmap,
+            helper(readiness.newgidmap),
+        ),
+        (
+            gettext("Rootless podman info"),
+            readiness.info.is_ok(),
+            info,
+        ),
+    ];
+
+    for (title, ok, subtitle) in checks {
+        let row = adw::ActionRow::builder()
+            .title(title)
+            .subtitle(subtitle)
+            .use_markup(false)
+            .build();
+        let icon = if ok {
+            "circle-check-symbolic"
+        } else {
+            "triangle-exclamation-symbolic"
+        };
+        row.add_prefix(&gtk4::Image::from_icon_name(icon));
+        panel.group.add(&row);
+        panel.rows.borrow_mut().push(row);
+    }
+
+    panel.fix_button.set_visible(!readiness.is_ready());
+}
+
+/// Add the missing ID ranges, restore the ID map helpers and let Podman
+/// pick up the ranges.
+fn fix_podman(panel: &Rc<PodmanPanel>) {
+    if task_runner::busy() {
+        return;
+    }
+    let Some(readiness) = panel.readiness.borrow().clone() else {
+        return;
+    };
+
+    let user = crate::config::env::get().user.clone();
+    let uid = unsafe { libc::getuid() };
+    let mut commands = CommandSequence::new();
+
+    let ranges = [
+        (
+            readiness.subuid,
+            podman::SUBUID_PATH,
+            gettext("Adding a user ID range..."),
+        ),
+        (
+            readiness.subgid,
+            podman::SUBGID_PATH,
+            gettext("Adding a group ID range..."),
+        ),
+    ];
+    for (ok, path, description) in ranges {
+        if !ok {
+            let user = user.clone();
+            commands = commands.then(
+                privileged_fs::transform(path, move |contents| {
+                    podman::add_subid_range(contents, &user, uid)
+                })
+                .command(&description),
+            );
+        }
+    }
+
+    // The shadow package installs the helpers with their permissions
+    if !readiness.newuidmap || !readiness.newgidmap {
+        commands = commands.then(
+            Command::builder()
+                .privileged()
+                .program("pacman")
+                .args(&["-S", "--noconfirm", "shadow"])
+                .description(&gettext("Restoring newuidmap and newgidmap..."))
+                .build(),
+        );
+    }
+
+    let commands = commands
+        .then(
+            Command::builder()
+                .normal()
+                .program("podman")
+                .args(&["system", "migrate"])
+                .description(&gettext("Applying the ID ranges to Podman..."))
+                .build(),
+        )
+        .build();
+
+    let panel_clone = panel.clone();
+    task_runner::run_with_completion(
+        panel.window.upcast_ref(),
+        commands,
+        &gettext("Rootless Podman Fix"),
+        move |_| refresh_podman(&panel_clone),
+    );
+}
+
+fn setup_vbox(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_vbox")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
@@ -159,18 +514,19 @@ fn setup_vbox(builder: &Builder, window: &ApplicationWindow) {
             .then(
                 Command::builder()
                     .aur()
-                    .args(&["-S", "--noconfirm", "--needed", "virtualbox-meta"])
-                    .description("Installing VirtualBox...")
+                    .args(&["-S", "--needed", "virtualbox-meta"])
+                    .description(&gettext("Installing VirtualBox..."))
                     .build(),
             )
             .build();
 
-        task_runner::run(window.upcast_ref(), commands, "VirtualBox Setup");
+        task_runner::run(window.upcast_ref(), commands, &gettext("VirtualBox Setup"));
     });
+    Ok(())
 }
 
-fn setup_distrobox(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_distrobox");
+fn setup_distrobox(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_distrobox")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
@@ -180,31 +536,66 @@ fn setup_distrobox(builder: &Builder, window: &ApplicationWindow) {
             .then(
                 Command::builder()
                     .aur()
-                    .args(&["-S", "--noconfirm", "--needed", "distrobox"])
-                    .description("Installing DistroBox...")
+                    .args(&["-S", "--needed", "distrobox"])
+                    .description(&gettext("Installing DistroBox..."))
                     .build(),
             )
             .then(
                 Command::builder()
                     .normal()
                     .program("flatpak")
-                    .args(&["install", "-y", "io.github.dvlv.boxbuddyrs"])
-                    .description("Installing BoxBuddy GUI...")
+                    .args(&["install", "-y", "flathub", "io.github.dvlv.boxbuddyrs"])
+                    .description(&gettext("Installing BoxBuddy GUI..."))
                     .build(),
             )
             .build();
 
-        task_runner::run(window.upcast_ref(), commands, "DistroBox Setup");
+        task_runner::run(window.upcast_ref(), commands, &gettext("DistroBox Setup"));
     });
+    Ok(())
 }
 
-fn setup_kvm(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_kvm");
+fn setup_kvm(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_kvm")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
         info!("KVM button clicked");
 
+        let Some(cpu) = core::hardware::detect_cpu() else {
+            show_error(
+                &window,
+                &gettext(
+                    "Could not read CPU information from /proc/cpuinfo, so virtualization support \
+                 cannot be verified.",
+                ),
+            );
+            return;
+        };
+
+        let Some(kvm_module) = cpu
+            .virtualization_supported()
+            .then(|| cpu.kvm_module())
+            .flatten()
+        else {
+            let technology = match &cpu.vendor {
+                core::hardware::CpuVendor::Amd => "AMD-V (SVM)",
+                _ => "Intel VT-x",
+            };
+            show_error(
+                &window,
+                &i18n::fill(
+                    &gettext(
+                        "Hardware virtualization is not available on this CPU.\n\n\
+                         Enable {} in your UEFI/BIOS settings, then reboot and try again.",
+                    ),
+                    &[technology],
+                ),
+            );
+            return;
+        };
+
+        let user = crate::config::env::get().user.clone();
         let mut commands = CommandSequence::new();
 
         // Remove conflicting packages if installed
@@ -212,8 +603,8 @@ fn setup_kvm(builder: &Builder, window: &ApplicationWindow) {
             commands = commands.then(
                 Command::builder()
                     .aur()
-                    .args(&["-Rdd", "--noconfirm", "iptables"])
-                    .description("Removing conflicting iptables...")
+                    .args(&["-Rdd", "iptables"])
+                    .description(&gettext("Removing conflicting iptables..."))
                     .build(),
             );
         }
@@ -222,8 +613,8 @@ fn setup_kvm(builder: &Builder, window: &ApplicationWindow) {
             commands = commands.then(
                 Command::builder()
                     .aur()
-                    .args(&["-Rdd", "--noconfirm", "gnu-netcat"])
-                    .description("Removing conflicting gnu-netcat...")
+                    .args(&["-Rdd", "gnu-netcat"])
+                    .description(&gettext("Removing conflicting gnu-netcat..."))
                     .build(),
             );
         }
@@ -231,26 +622,31 @@ fn setup_kvm(builder: &Builder, window: &ApplicationWindow) {
         commands = commands.then(
             Command::builder()
                 .aur()
-                .args(&[
-                    "-S",
-                    "--noconfirm",
-                    "--needed",
-                    "virt-manager-meta",
-                    "openbsd-netcat",
-                ])
-                .description("Installing virtualization packages...")
+                .args(&["-S", "--needed", "virt-manager-meta", "openbsd-netcat"])
+                .description(&gettext("Installing virtualization packages..."))
                 .build(),
         );
 
+        let nested_conf = format!(
+            "echo 'options {} nested=1' > /etc/modprobe.d/{}.conf",
+            kvm_module.replace('-', "_"),
+            kvm_module
+        );
         commands = commands.then(
             Command::builder()
                 .privileged()
                 .program("sh")
-                .args(&[
-                    "-c",
-                    "echo 'options kvm-intel nested=1' > /etc/modprobe.d/kvm-intel.conf",
-                ])
-                .description("Enabling nested virtualization...")
+                .args(&["-c", &nested_conf])
+                .description(&gettext("Enabling nested virtualization..."))
+                .build(),
+        );
+
+        commands = commands.then(
+            Command::builder()
+                .privileged()
+                .program("usermod")
+                .args(&["-aG", "libvirt", &user])
+                .description(&gettext("Adding your user to libvirt group..."))
                 .build(),
         );
 
@@ -258,17 +654,33 @@ fn setup_kvm(builder: &Builder, window: &ApplicationWindow) {
             Command::builder()
                 .privileged()
                 .program("systemctl")
-                .args(&["restart", "libvirtd.service"])
-                .description("Restarting libvirtd service...")
+                .args(&["enable", "--now", "libvirtd.service"])
+                .description(&gettext("Enabling libvirtd service..."))
                 .build(),
         );
 
-        task_runner::run(window.upcast_ref(), commands.build(), "KVM / QEMU Setup");
+        // virt-host-validate exits non-zero on any failed check; its report is
+        // shown in the output log without failing the whole setup.
+        commands = commands.then(
+            Command::builder()
+                .normal()
+                .program("sh")
+                .args(&["-c", "virt-host-validate qemu || true"])
+                .description(&gettext("Validating virtualization host..."))
+                .build(),
+        );
+
+        task_runner::run(
+            window.upcast_ref(),
+            commands.build(),
+            &gettext("KVM / QEMU Setup"),
+        );
     });
+    Ok(())
 }
 
-fn setup_ipa_sideloader(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_ipa_sideloader");
+fn setup_ipa_sideloader(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_ipa_sideloader")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
@@ -280,11 +692,16 @@ fn setup_ipa_sideloader(builder: &Builder, window: &ApplicationWindow) {
                     .normal()
                     .program("flatpak")
                     .args(&["install", "-y", "flathub", "dev.khcrysalis.PlumeImpactor"])
-                    .description("Installing Plume Impactor from Flathub...")
+                    .description(&gettext("Installing Plume Impactor from Flathub..."))
                     .build(),
             )
             .build();
 
-        task_runner::run(window.upcast_ref(), commands, "iOS iPA Sideloader Setup");
+        task_runner::run(
+            window.upcast_ref(),
+            commands,
+            &gettext("iOS iPA Sideloader Setup"),
+        );
     });
+    Ok(())
 }
@@ -1,32 +1,115 @@
 //! Customization page button handlers.
 //!
 //! Handles:
-//! - ZSH All-in-One setup
+//! - ZSH All-in-One setup and revert to Bash
 //! - Save Desktop tool
 //! - GRUB theme installation
 //! - Plymouth Manager
 //! - Update Layan Theme
 //! - Config/Rice reset
+//! - Wallpaper browser
+//! - Font installer
 
+use crate::core::fonts::{self, FontPackage};
+use crate::core::system_check::Capability;
+use crate::core::{self, deploy, distro, dotfiles, system_check};
+use crate::i18n::{self, gettext, gettext_noop};
+use crate::ui::click_guard::connect_clicked_once_until_done;
+use crate::ui::dialogs::error::show_error;
+use crate::ui::dialogs::selection::{
+    show_selection_dialog, SelectionDialogConfig, SelectionOption, SelectionType,
+};
 use crate::ui::dialogs::terminal;
+use crate::ui::dialogs::wallpapers;
+use crate::ui::dialogs::warning::show_warning_confirmation;
+use crate::ui::markup::{self, Markup};
 use crate::ui::task_runner::{self, Command, CommandSequence};
-use crate::ui::utils::extract_widget;
+use crate::ui::utils::{extract_widget, require_capabilities, WidgetError};
+use gtk4::glib;
 use gtk4::prelude::*;
 use gtk4::{ApplicationWindow, Builder, Button};
-use log::info;
+use log::{info, warn};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
 /// Set up all button handlers for the customization page.
-pub fn setup_handlers(page_builder: &Builder, _main_builder: &Builder, window: &ApplicationWindow) {
-    setup_zsh_aio(page_builder, window);
-    setup_save_desktop(page_builder, window);
-    setup_grub_theme(page_builder, window);
-    setup_plymouth_manager(page_builder, window);
-    setup_layan_patch(page_builder, window);
-    setup_config_reset(page_builder, window);
+pub fn setup_handlers(
+    page_builder: &Builder,
+    _main_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    setup_zsh_aio(page_builder, window)?;
+    setup_zsh_revert(page_builder, window)?;
+    setup_save_desktop(page_builder, window)?;
+    setup_grub_theme(page_builder, window)?;
+    setup_plymouth_manager(page_builder, window)?;
+    setup_layan_patch(page_builder, window)?;
+    setup_config_reset(page_builder, window)?;
+    setup_wallpapers(page_builder, window)?;
+    setup_fonts(page_builder, window)?;
+
+    require_capabilities(
+        page_builder,
+        &[Capability::Pacman, Capability::Escalate],
+        &["btn_zsh_aio", "btn_plymouth_manager", "btn_fonts"],
+    )?;
+    require_capabilities(
+        page_builder,
+        &[Capability::Escalate],
+        &[
+            "btn_zsh_revert",
+            "btn_grub_theme",
+            "btn_grub_restore",
+            "btn_layan_patch",
+        ],
+    )?;
+    require_capabilities(page_builder, &[Capability::Flatpak], &["btn_save_desktop"])?;
+    Ok(())
+}
+
+/// Oh My Zsh plugins as (directory name, label, repository).
+const ZSH_PLUGINS: &[(&str, &str, &str)] = &[
+    (
+        "zsh-completions",
+        "completions",
+        "https://github.com/zsh-users/zsh-completions",
+    ),
+    (
+        "zsh-autosuggestions",
+        "autosuggestions",
+        "https://github.com/zsh-users/zsh-autosuggestions",
+    ),
+    (
+        "zsh-syntax-highlighting",
+        "syntax highlighting",
+        "https://github.com/zsh-users/zsh-syntax-highlighting.git",
+    ),
+];
+
+/// Konsole profile shipped by XeroLinux, relative to the host data directory.
+const KONSOLE_PROFILE: &str = "konsole/XeroLinux.profile";
+
+/// Path of [`KONSOLE_PROFILE`] on the host.
+fn konsole_profile() -> String {
+    core::paths::get()
+        .host_data
+        .join(KONSOLE_PROFILE)
+        .to_string_lossy()
+        .into_owned()
 }
 
-fn setup_zsh_aio(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_zsh_aio");
+/// Switch the Konsole profile at `$PROFILE` to ZSH, if it exists.
+const KONSOLE_TO_ZSH: &str = r#"if [ -f "$PROFILE" ]; then
+    sed -i 's|Command=/bin/bash|Command=/bin/zsh|g' "$PROFILE"
+fi"#;
+
+/// Switch the Konsole profile at `$PROFILE` back to Bash, if it exists.
+const KONSOLE_TO_BASH: &str = r#"if [ -f "$PROFILE" ]; then
+    sed -i 's|Command=/bin/zsh|Command=/bin/bash|g' "$PROFILE"
+fi"#;
+
+fn setup_zsh_aio(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_zsh_aio")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
@@ -35,133 +118,279 @@ fn setup_zsh_aio(builder: &Builder, window: &ApplicationWindow) {
         let env = crate::config::env::get();
         let home = env.home.clone();
         let user = env.user.clone();
+        let shell = system_check::login_shell(&user).unwrap_or_else(|| "unknown".to_string());
 
-        let commands = CommandSequence::new()
-            .then(Command::builder()
-                .aur()
-                .args(&[
-                    "-S",
-                    "--needed",
-                    "--noconfirm",
-                    "zsh",
-                    "grml-zsh-config",
-                    "fastfetch",
-                ])
-                .description("Installing ZSH and dependencies...")
-                .build())
-            .then(Command::builder()
-                .normal()
-                .program("sh")
-                .args(&[
-                    "-c",
-                    "curl -fsSL https://raw.githubusercontent.com/ohmyzsh/ohmyzsh/master/tools/install.sh | sh -s -- --unattended",
-                ])
-                .description("Installing Oh My Zsh framework...")
-                .build())
-            .then(Command::builder()
-                .aur()
-                .args(&[
-                    "-S",
-                    "--noconfirm",
-                    "--needed",
-                    "pacseek",
-                    "ttf-meslo-nerd",
-                    "siji-git",
-                    "otf-unifont",
-                    "bdf-unifont",
-                    "noto-color-emoji-fontconfig",
-                    "xorg-fonts-misc",
-                    "ttf-dejavu",
-                    "ttf-meslo-nerd-font-powerlevel10k",
-                    "noto-fonts-emoji",
-                    "powerline-fonts",
-                    "oh-my-posh-bin",
-                ])
-                .description("Installing fonts and terminal enhancements...")
-                .build())
-            .then(Command::builder()
-                .normal()
-                .program("git")
-                .args(&[
-                    "clone",
-                    "https://github.com/zsh-users/zsh-completions",
-                    &format!("{}/.oh-my-zsh/custom/plugins/zsh-completions", home),
-                ])
-                .description("Installing ZSH completions plugin...")
-                .build())
-            .then(Command::builder()
-                .normal()
-                .program("git")
-                .args(&[
-                    "clone",
-                    "https://github.com/zsh-users/zsh-autosuggestions",
-                    &format!("{}/.oh-my-zsh/custom/plugins/zsh-autosuggestions", home),
-                ])
-                .description("Installing ZSH autosuggestions plugin...")
-                .build())
-            .then(Command::builder()
-                .normal()
-                .program("git")
-                .args(&[
-                    "clone",
-                    "https://github.com/zsh-users/zsh-syntax-highlighting.git",
-                    &format!("{}/.oh-my-zsh/custom/plugins/zsh-syntax-highlighting", home),
-                ])
-                .description("Installing ZSH syntax highlighting plugin...")
-                .build())
-            .then(Command::builder()
-                .normal()
-                .program("sh")
-                .args(&[
-                    "-c",
-                    &format!(
-                        "mv -f {}/.zshrc {}/.zshrc.user 2>/dev/null || true",
-                        home, home
-                    ),
-                ])
-                .description("Backing up existing ZSH configuration...")
-                .build())
-            .then(Command::builder()
-                .normal()
-                .program("wget")
-                .args(&[
-                    "-q",
-                    "-P",
-                    &home,
-                    "https://raw.githubusercontent.com/xerolinux/xero-fixes/main/conf/.zshrc",
-                ])
-                .description("Downloading XeroLinux ZSH configuration...")
-                .build())
-            .then(Command::builder()
-                .normal()
-                .program("sh")
-                .args(&[
-                    "-c",
-                    &format!(
-                        "sed -i 's|Command=/bin/bash|Command=/bin/zsh|g' {}/.local/share/konsole/XeroLinux.profile 2>/dev/null || true",
-                        home
-                    ),
-                ])
-                .description("Updating Konsole profile to use ZSH...")
-                .build())
-            .then(Command::builder()
-                .privileged()
-                .program("chsh")
-                .args(&[&user, "-s", "/bin/zsh"])
-                .description("Setting ZSH as default shell...")
-                .build())
-            .build();
+        let mut changes = Vec::new();
+        if Path::new(&home).join(".zshrc").exists() {
+            changes.push(gettext(
+                "• <tt>~/.zshrc</tt> is replaced by the XeroLinux config, \
+                 the current one is kept as <tt>~/.zshrc.bak.&lt;date&gt;</tt>",
+            ));
+        } else {
+            changes.push(gettext(
+                "• <tt>~/.zshrc</tt> is created from the XeroLinux config",
+            ));
+        }
+        if Path::new(&home).join(".oh-my-zsh").exists() {
+            changes.push(gettext(
+                "• The existing Oh My Zsh install and plugins are kept and updated",
+            ));
+        }
+        changes.push(gettext(
+            "• The XeroLinux Konsole profile is switched to ZSH",
+        ));
+        if shell != "/bin/zsh" {
+            changes.push(i18n::fill_markup(
+                &gettext("• Your login shell changes from <tt>{}</tt> to <tt>/bin/zsh</tt>"),
+                &[&shell],
+            ));
+        }
 
-        task_runner::run(
+        // The changes are already escaped, so fill them in verbatim
+        let message = i18n::fill(
+            &gettext(
+                "This installs ZSH with Oh My Zsh, plugins and fonts.\n\n{}\n\n\
+                 Use <b>Revert to Bash</b> to undo.",
+            ),
+            &[&changes.join("\n")],
+        );
+
+        let window_clone = window.clone();
+        show_warning_confirmation(
             window.upcast_ref(),
-            commands,
-            "ZSH All-in-One Setup",
+            &gettext("Set Up ZSH All-in-One"),
+            Markup::trusted(message),
+            move || {
+                task_runner::run(
+                    window_clone.upcast_ref(),
+                    zsh_aio_commands(&home, &user),
+                    &gettext("ZSH All-in-One Setup"),
+                );
+            },
         );
     });
+    Ok(())
+}
+
+/// Build the ZSH setup sequence; steps for parts already present are
+/// skipped or turned into updates so the setup can be re-run safely.
+fn zsh_aio_commands(home: &str, user: &str) -> CommandSequence {
+    let oh_my_zsh = format!("{}/.oh-my-zsh", home);
+    let zshrc = format!("{}/.zshrc", home);
+
+    let mut commands = CommandSequence::new()
+        .phase(&gettext("Dependencies"))
+        .then(Command::builder()
+            .aur()
+            .args(&[
+                "-S",
+                "--needed",
+                "zsh",
+                "grml-zsh-config",
+                "fastfetch",
+            ])
+            .description(&gettext("Installing ZSH and dependencies..."))
+            .build())
+        .then(Command::builder()
+            .normal()
+            .program("sh")
+            .args(&[
+                "-c",
+                "curl -fsSL https://raw.githubusercontent.com/ohmyzsh/ohmyzsh/master/tools/install.sh | sh -s -- --unattended",
+            ])
+            .description(&gettext("Installing Oh My Zsh framework..."))
+            .run_if(path_missing(&oh_my_zsh))
+            .build())
+        .then(Command::builder()
+            .aur()
+            .args(&[
+                "-S",
+                "--needed",
+                "pacseek",
+                "ttf-meslo-nerd",
+                "siji-git",
+                "otf-unifont",
+                "bdf-unifont",
+                "noto-color-emoji-fontconfig",
+                "xorg-fonts-misc",
+                "ttf-dejavu",
+                "ttf-meslo-nerd-font-powerlevel10k",
+                "noto-fonts-emoji",
+                "powerline-fonts",
+                "oh-my-posh-bin",
+            ])
+            .description(&gettext("Installing fonts and terminal enhancements..."))
+            .build())
+        .phase(&gettext("Plugins"));
+
+    for (name, label, repo) in ZSH_PLUGINS {
+        let dir = format!("{}/custom/plugins/{}", oh_my_zsh, name);
+        commands = commands
+            .then(
+                Command::builder()
+                    .normal()
+                    .program("git")
+                    .args(&["clone", repo, &dir])
+                    .description(&i18n::fill(
+                        &gettext("Installing ZSH {} plugin..."),
+                        &[label],
+                    ))
+                    .run_if(path_missing(&dir))
+                    .build(),
+            )
+            .then(
+                Command::builder()
+                    .normal()
+                    .program("git")
+                    .args(&["-C", &dir, "pull", "--ff-only"])
+                    .description(&i18n::fill(&gettext("Updating ZSH {} plugin..."), &[label]))
+                    .run_if(path_exists(&dir))
+                    .build(),
+            );
+    }
+
+    let user = user.to_string();
+    commands
+        .phase(&gettext("Shell integration"))
+        .then(Command::script(
+            &gettext("Backing up existing ZSH configuration..."),
+            r#"cp -f "$ZSHRC" "$ZSHRC.bak.$(date +%Y%m%d-%H%M%S)""#,
+        )
+        .var("ZSHRC", &zshrc)
+        .run_if(path_exists(&zshrc))
+        .build())
+        .then(Command::script(
+            &gettext("Downloading XeroLinux ZSH configuration..."),
+            r#"wget -q -O "$ZSHRC.new" https://raw.githubusercontent.com/xerolinux/xero-fixes/main/conf/.zshrc
+mv -f "$ZSHRC.new" "$ZSHRC""#,
+        )
+        .var("ZSHRC", &zshrc)
+        .build())
+        .then(Command::script(
+            &gettext("Updating Konsole profile to use ZSH..."),
+            KONSOLE_TO_ZSH,
+        )
+        .var("PROFILE", &konsole_profile())
+        .build())
+        .then(Command::builder()
+            .privileged()
+            .program("chsh")
+            .args(&[&user, "-s", "/bin/zsh"])
+            .description(&gettext("Setting ZSH as default shell..."))
+            .run_if({
+                let user = user.clone();
+                move || system_check::login_shell(&user).as_deref() != Some("/bin/zsh")
+            })
+            .build())
+        .build()
 }
 
-fn setup_save_desktop(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_save_desktop");
+fn setup_zsh_revert(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_zsh_revert")?;
+    let window = window.clone();
+
+    button.connect_clicked(move |_| {
+        info!("Revert to Bash button clicked");
+
+        let env = crate::config::env::get();
+        let home = env.home.clone();
+        let user = env.user.clone();
+        let shell = system_check::login_shell(&user).unwrap_or_else(|| "unknown".to_string());
+        let zshrc = Path::new(&home).join(".zshrc");
+        let backup = dotfiles::latest_backup(&zshrc);
+
+        let mut changes = Vec::new();
+        match &backup {
+            Some(backup) => changes.push(i18n::fill_markup(
+                &gettext("• <tt>~/.zshrc</tt> is restored from <tt>{}</tt>"),
+                &[&backup.file_name().unwrap_or_default().to_string_lossy()],
+            )),
+            None => changes.push(gettext(
+                "• No <tt>~/.zshrc</tt> backup was found, so it is left as is",
+            )),
+        }
+        changes.push(gettext(
+            "• The XeroLinux Konsole profile is switched back to Bash",
+        ));
+        if shell != "/bin/bash" {
+            changes.push(i18n::fill_markup(
+                &gettext("• Your login shell changes from <tt>{}</tt> to <tt>/bin/bash</tt>"),
+                &[&shell],
+            ));
+        }
+
+        let message = i18n::fill(
+            &gettext("{}\n\nOh My Zsh and installed packages are not removed."),
+            &[&changes.join("\n")],
+        );
+
+        let window_clone = window.clone();
+        show_warning_confirmation(
+            window.upcast_ref(),
+            &gettext("Revert to Bash"),
+            Markup::trusted(message),
+            move || {
+                let mut commands = CommandSequence::new();
+
+                if let Some(backup) = backup {
+                    commands = commands.then(
+                        Command::builder()
+                            .normal()
+                            .program("cp")
+                            .args(&["-f", &backup.to_string_lossy(), &zshrc.to_string_lossy()])
+                            .description(&gettext("Restoring previous ZSH configuration..."))
+                            .build(),
+                    );
+                }
+
+                let user_for_check = user.clone();
+                commands = commands
+                    .then(
+                        Command::script(
+                            &gettext("Updating Konsole profile to use Bash..."),
+                            KONSOLE_TO_BASH,
+                        )
+                        .var("PROFILE", &konsole_profile())
+                        .build(),
+                    )
+                    .then(
+                        Command::builder()
+                            .privileged()
+                            .program("chsh")
+                            .args(&[&user, "-s", "/bin/bash"])
+                            .description(&gettext("Setting Bash as default shell..."))
+                            .run_if(move || {
+                                system_check::login_shell(&user_for_check).as_deref()
+                                    != Some("/bin/bash")
+                            })
+                            .build(),
+                    );
+
+                task_runner::run(
+                    window_clone.upcast_ref(),
+                    commands.build(),
+                    &gettext("Revert to Bash"),
+                );
+            },
+        );
+    });
+    Ok(())
+}
+
+/// Run condition that holds while `path` exists.
+fn path_exists(path: &str) -> impl Fn() -> bool + 'static {
+    let path = PathBuf::from(path);
+    move || path.exists()
+}
+
+/// Run condition that holds while `path` does not exist.
+fn path_missing(path: &str) -> impl Fn() -> bool + 'static {
+    let path = PathBuf::from(path);
+    move || !path.exists()
+}
+
+fn setup_save_desktop(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_save_desktop")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
@@ -172,8 +401,13 @@ fn setup_save_desktop(builder: &Builder, window: &ApplicationWindow) {
                 Command::builder()
                     .normal()
                     .program("flatpak")
-                    .args(&["install", "-y", "io.github.vikdevelop.SaveDesktop"])
-                    .description("Installing Save Desktop tool from Flathub...")
+                    .args(&[
+                        "install",
+                        "-y",
+                        "flathub",
+                        "io.github.vikdevelop.SaveDesktop",
+                    ])
+                    .description(&gettext("Installing Save Desktop tool from Flathub..."))
                     .build(),
             )
             .build();
@@ -181,57 +415,194 @@ fn setup_save_desktop(builder: &Builder, window: &ApplicationWindow) {
         task_runner::run(
             window.upcast_ref(),
             commands,
-            "Save Desktop Tool Installation",
+            &gettext("Save Desktop Tool Installation"),
         );
     });
+    Ok(())
 }
 
-fn setup_grub_theme(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_grub_theme");
+/// GRUB theme picker; the backup covers everything it may change.
+const GRUB_THEME: deploy::Manifest = deploy::Manifest {
+    name: "grub-theme",
+    repo: None,
+    files: &[],
+    tracked: &["/etc/default/grub", "/boot/grub/themes"],
+    // Waits for the picker to close, since the workspace it is downloaded to
+    // is removed when the run ends
+    post_commands: &[deploy::StepCommand {
+        privileged: false,
+        program: "bash",
+        args: &[
+            "-c",
+            "curl -fsSL 'https://xerolinux.xyz/script/grubs/xero-grubs.py' -o \"$1/xero-grubs.py\" && python3 \"$1/xero-grubs.py\"",
+            "bash",
+            "${WORKSPACE}",
+        ],
+        description: "Launching the XeroLinux GRUB theme picker...",
+    }],
+    restore_commands: &[deploy::StepCommand {
+        privileged: true,
+        program: "grub-mkconfig",
+        args: &["-o", "/boot/grub/grub.cfg"],
+        description: "Regenerating GRUB configuration...",
+    }],
+};
+
+/// Convert deployment steps into a command sequence.
+fn deploy_commands(steps: Vec<deploy::DeployStep>) -> CommandSequence {
+    steps.iter().fold(CommandSequence::new(), |commands, step| {
+        let builder = if step.privileged {
+            Command::builder().privileged()
+        } else {
+            Command::builder().normal()
+        };
+        let args: Vec<&str> = step.args.iter().map(String::as_str).collect();
+        commands.then(
+            builder
+                .program(&step.program)
+                .args(&args)
+                .description(&step.description)
+                .build(),
+        )
+    })
+}
+
+fn setup_grub_theme(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_grub_theme")?;
+    let restore_button = extract_widget::<Button>(builder, "btn_grub_restore")?;
+    setup_theme_restore(
+        &restore_button,
+        window,
+        GRUB_THEME,
+        gettext_noop("GRUB Theme"),
+    );
     let window = window.clone();
 
     button.connect_clicked(move |_| {
         info!("GRUB Theme button clicked");
 
-        let install_command = "cd /tmp && curl -fsSL 'https://xerolinux.xyz/script/grubs/xero-grubs.py' -o xero-grubs.py && setsid -f python3 /tmp/xero-grubs.py > /dev/null 2>&1 < /dev/null";
+        let home = PathBuf::from(&crate::config::env::get().home);
+        let created = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        if let Err(e) = deploy::snapshot(
+            &deploy::backup_root(),
+            GRUB_THEME.name,
+            &GRUB_THEME.destinations(&home),
+            created,
+        ) {
+            warn!("Failed to back up GRUB theme: {:#}", e);
+            show_error(
+                &window,
+                &i18n::fill(
+                    &gettext("Failed to back up the current GRUB theme: {}"),
+                    &[&format!("{:#}", e)],
+                ),
+            );
+            return;
+        }
+        restore_button.set_sensitive(true);
 
-        terminal::show_terminal_dialog(
+        let staging = Path::new(task_runner::WORKSPACE).join(GRUB_THEME.name);
+        task_runner::run(
             window.upcast_ref(),
-            "XeroLinux GRUB Theme Installation",
-            "bash",
-            &["-c", install_command],
-            true,
+            deploy_commands(deploy::deploy_plan(&GRUB_THEME, &staging, &home)).with_workspace(),
+            &gettext("XeroLinux GRUB Theme Installation"),
         );
     });
+    Ok(())
 }
 
-fn setup_plymouth_manager(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_plymouth_manager");
+/// Wire a "restore previous look" button to the newest backup of `manifest`.
+fn setup_theme_restore(
+    button: &Button,
+    window: &ApplicationWindow,
+    manifest: deploy::Manifest,
+    label: &'static str,
+) {
+    button.set_sensitive(deploy::latest_backup(&deploy::backup_root(), manifest.name).is_some());
     let window = window.clone();
 
-    button.connect_clicked(move |_| {
+    button.connect_clicked(move |button| {
+        info!("Restore {} button clicked", label);
+
+        let Some(backup) = deploy::latest_backup(&deploy::backup_root(), manifest.name) else {
+            button.set_sensitive(false);
+            return;
+        };
+
+        let taken = glib::DateTime::from_unix_local(backup.record.created as i64)
+            .and_then(|time| time.format("%Y-%m-%d %H:%M"))
+            .map(|time| time.to_string())
+            .unwrap_or_else(|_| backup.record.created.to_string());
+        let paths: Vec<String> = backup
+            .record
+            .entries
+            .iter()
+            .map(|entry| {
+                i18n::fill_markup("• <tt>{}</tt>", &[&entry.destination.to_string_lossy()])
+            })
+            .collect();
+        let label = gettext(label);
+        let message = format!(
+            "{}\n\n{}",
+            i18n::fill_markup(
+                &gettext("This puts back the {} saved on {}:"),
+                &[&label, &taken]
+            ),
+            paths.join("\n")
+        );
+
+        let home = PathBuf::from(&crate::config::env::get().home);
+        let window_clone = window.clone();
+        show_warning_confirmation(
+            window.upcast_ref(),
+            &i18n::fill(&gettext("Restore Previous {}"), &[&label]),
+            Markup::trusted(message),
+            move || {
+                task_runner::run(
+                    window_clone.upcast_ref(),
+                    deploy_commands(deploy::restore_plan(&backup, &manifest, &home)),
+                    &i18n::fill(&gettext("Restore {}"), &[&label]),
+                );
+            },
+        );
+    });
+}
+
+fn setup_plymouth_manager(
+    builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_plymouth_manager")?;
+    let window = window.clone();
+
+    connect_clicked_once_until_done(&button, move |_, token| {
         info!("Plymouth Manager button clicked");
 
-        terminal::show_terminal_dialog(
+        let dialog = terminal::show_terminal_dialog(
             window.upcast_ref(),
-            "Plymouth Manager",
+            &gettext("Plymouth Manager"),
             "/usr/local/bin/xpm",
             &[],
             false,
         );
+        token.release_on_close(&dialog);
     });
+    Ok(())
 }
 
-fn setup_layan_patch(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_layan_patch");
+fn setup_layan_patch(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_layan_patch")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
         info!("Update Layan Theme button clicked");
 
-        let home = crate::config::env::get().home.clone();
-
         let commands = CommandSequence::new()
+            .with_workspace()
             .then(
                 Command::builder()
                     .normal()
@@ -241,35 +612,200 @@ fn setup_layan_patch(builder: &Builder, window: &ApplicationWindow) {
                         "--depth",
                         "1",
                         "https://github.com/vinceliuice/Layan-kde.git",
-                        &format!("{}/Layan-kde", home),
+                        "Layan-kde",
                     ])
-                    .description("Downloading Layan KDE theme...")
+                    .description(&gettext("Downloading Layan KDE theme..."))
+                    .cwd_workspace()
                     .build(),
             )
             .then(
-                Command::builder()
-                    .privileged()
-                    .program("sh")
-                    .args(&["-c", &format!("cd {}/Layan-kde && sh install.sh", home)])
-                    .description("Installing Layan KDE theme...")
-                    .build(),
+                Command::script(
+                    &gettext("Installing Layan KDE theme..."),
+                    "cd Layan-kde\nsh install.sh",
+                )
+                .privileged()
+                .cwd_workspace()
+                .build(),
             )
+            .build();
+
+        task_runner::run(
+            window.upcast_ref(),
+            commands,
+            &gettext("Update Layan Theme"),
+        );
+    });
+    Ok(())
+}
+
+fn setup_wallpapers(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_wallpapers")?;
+    let window = window.clone();
+
+    button.connect_clicked(move |_| {
+        info!("Wallpapers button clicked");
+        wallpapers::show_wallpaper_dialog(window.upcast_ref());
+    });
+    Ok(())
+}
+
+fn setup_fonts(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_fonts")?;
+    let window = window.clone();
+
+    connect_clicked_once_until_done(&button, move |_, token| {
+        info!("Fonts button clicked");
+
+        let families = font_families(&window);
+        let mut config = SelectionDialogConfig::new(
+            &gettext("Fonts"),
+            gettext("Choose the fonts to install. Fonts already on the system show a preview."),
+        )
+        .confirm_label(&gettext("Install"));
+        for font in fonts::FONTS {
+            config = config.add_option(
+                SelectionOption::new(
+                    font.package,
+                    font.label,
+                    gettext(font.description),
+                    core::is_package_installed(font.package),
+                )
+                .preview(font_preview(font, &families)),
+            );
+        }
+
+        let window_clone = window.clone();
+        let dialog = show_selection_dialog(window.upcast_ref(), config, move |selected| {
+            if selected.is_empty() {
+                return;
+            }
+
+            let mut args = vec!["-S", "--needed"];
+            args.extend(selected.iter().map(String::as_str));
+            let commands = CommandSequence::new()
+                .then(
+                    Command::builder()
+                        .aur()
+                        .args(&args)
+                        .description(&gettext("Installing fonts..."))
+                        .build(),
+                )
+                .then(
+                    Command::builder()
+                        .normal()
+                        .program("fc-cache")
+                        .args(&["-f"])
+                        .description(&gettext("Refreshing the font cache..."))
+                        .build(),
+                )
+                .build();
+
+            let monospace: Vec<&'static FontPackage> = selected
+                .iter()
+                .filter_map(|package| fonts::find(package))
+                .filter(|font| font.monospace)
+                .collect();
+            let parent = window_clone.clone();
+            task_runner::run_with_completion(
+                window_clone.upcast_ref(),
+                commands,
+                &gettext("Font Installation"),
+                move |success| {
+                    if success && !monospace.is_empty() {
+                        offer_fixed_width_font(&parent, &monospace);
+                    }
+                },
+            );
+        });
+        token.release_on_close(&dialog);
+    });
+    Ok(())
+}
+
+/// Font families the widget can render, as Pango lists them.
+fn font_families(widget: &impl IsA<gtk4::Widget>) -> HashSet<String> {
+    widget
+        .pango_context()
+        .list_families()
+        .iter()
+        .map(|family| family.name().to_string())
+        .collect()
+}
+
+/// Sample of `font` in its own family, or a note when the family is missing
+/// so the sample doesn't show in a fallback font.
+fn font_preview(font: &FontPackage, families: &HashSet<String>) -> Markup {
+    if families.contains(font.family) {
+        Markup::trusted(format!(
+            "<span font_family=\"{}\" size=\"large\">{}</span>",
+            markup::escape(font.family),
+            markup::escape(font.sample)
+        ))
+    } else {
+        Markup::trusted(format!(
+            "<i>{}</i>",
+            markup::escape(&gettext("No preview until the font is installed"))
+        ))
+    }
+}
+
+/// Offer to make one of the just installed `fonts` the fixed-width font of
+/// the desktop, if the desktop has a known setting for it.
+fn offer_fixed_width_font(window: &ApplicationWindow, fonts: &[&'static FontPackage]) {
+    let desktop = distro::session().desktop.clone();
+    if fonts::fixed_width_setting(&desktop, "").is_none() {
+        info!("No fixed-width font setting known for {:?}", desktop);
+        return;
+    }
+
+    let mut config = SelectionDialogConfig::new(
+        &gettext("Fixed-Width Font"),
+        i18n::fill(
+            &gettext(
+                "Use a new font as the fixed-width font of {}? Terminals and editors \
+                 following the system font pick it up.",
+            ),
+            &[&desktop.name()],
+        ),
+    )
+    .selection_type(SelectionType::Single)
+    .confirm_label(&gettext("Set Font"));
+    for (i, font) in fonts.iter().enumerate() {
+        config = config.add_option(
+            SelectionOption::new(font.package, font.label, gettext(font.description), false)
+                .preselected(i == 0),
+        );
+    }
+
+    let window_clone = window.clone();
+    show_selection_dialog(window.upcast_ref(), config, move |selected| {
+        let Some(font) = selected.first().and_then(|package| fonts::find(package)) else {
+            return;
+        };
+        let Some((program, args)) = fonts::fixed_width_setting(&desktop, font.family) else {
+            return;
+        };
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let commands = CommandSequence::new()
             .then(
                 Command::builder()
                     .normal()
-                    .program("rm")
-                    .args(&["-rf", &format!("{}/Layan-kde", home)])
-                    .description("Cleaning up KDE theme files...")
+                    .program(program)
+                    .args(&args)
+                    .description(&gettext("Setting the fixed-width font..."))
                     .build(),
             )
             .build();
-
-        task_runner::run(window.upcast_ref(), commands, "Update Layan Theme");
+        task_runner::run(
+            window_clone.upcast_ref(),
+            commands,
+            &gettext("Fixed-Width Font"),
+        );
     });
 }
 
-fn setup_config_reset(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_config_reset");
+fn setup_config_reset(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_config_reset")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
@@ -278,10 +814,10 @@ fn setup_config_reset(builder: &Builder, window: &ApplicationWindow) {
         let window_clone = window.clone();
         crate::ui::dialogs::warning::show_warning_confirmation(
             window.upcast_ref(),
-            "Config/Rice Reset",
-            "A backup of <span foreground=\"cyan\" weight=\"bold\">~/.config</span> will be created.\n\
+            &gettext("Config/Rice Reset"),
+            Markup::trusted(gettext("A backup of <span foreground=\"cyan\" weight=\"bold\">~/.config</span> will be created.\n\
              Once reset, the system will <span foreground=\"red\" weight=\"bold\">reboot</span>.\n\n\
-             You will be getting updated config as of reset time.",
+             You will be getting updated config as of reset time.")),
             move || {
                 let commands = CommandSequence::new()
                     .then(
@@ -292,7 +828,7 @@ fn setup_config_reset(builder: &Builder, window: &ApplicationWindow) {
                                 "-c",
                                 "cp -Rf ~/.config ~/.config-backup-$(date +%Y.%m.%d-%H.%M.%S)",
                             ])
-                            .description("Backing up configuration...")
+                            .description(&gettext("Backing up configuration..."))
                             .build(),
                     )
                     .then(
@@ -300,14 +836,14 @@ fn setup_config_reset(builder: &Builder, window: &ApplicationWindow) {
                             .normal()
                             .program("bash")
                             .args(&["-c", "cp -Rf /etc/skel/. ~"])
-                            .description("Restoring default configuration...")
+                            .description(&gettext("Restoring default configuration..."))
                             .build(),
                     )
                     .then(
                         Command::builder()
                             .normal()
                             .program("reboot")
-                            .description("Rebooting system...")
+                            .description(&gettext("Rebooting system..."))
                             .build(),
                     )
                     .build();
@@ -315,9 +851,10 @@ fn setup_config_reset(builder: &Builder, window: &ApplicationWindow) {
                 task_runner::run(
                     window_clone.upcast_ref(),
                     commands,
-                    "Config/Rice Reset",
+                    &gettext("Config/Rice Reset"),
                 );
             },
         );
     });
+    Ok(())
 }
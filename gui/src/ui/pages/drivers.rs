@@ -1,39 +1,92 @@
 //! Drivers and hardware tools page button handlers.
 //!
 //! Handles:
-//! - Tailscale VPN
-//! - ASUS ROG laptop tools
+//! - Tailscale VPN installation and status card
+//! - ASUS ROG laptop tools and their profile, GPU mode and charge controls
+//! - NVIDIA driver health checks with fixes
+//! - PRIME offload to the second GPU of hybrid graphics laptops
+//! - DKMS kernel module status with rebuild and remove actions
 //! - OpenRazer drivers
 //! - Cooler Control daemon tools
+//! - Multimedia codecs and VA-API hardware video decoding
 
 use crate::core;
+use crate::core::asus::{self, RogStatus};
+use crate::core::dkms::{self, DkmsModule};
+use crate::core::nvidia::{self, Health};
+use crate::core::privileged_fs;
+use crate::core::system_check::Capability;
+use crate::core::tailscale::{self, Status};
+use crate::i18n::{self, gettext, ngettext};
+use crate::ui::click_guard::connect_clicked_once_until_done;
 use crate::ui::dialogs::selection::{
     show_selection_dialog, SelectionDialogConfig, SelectionOption, SelectionType,
 };
+use crate::ui::dialogs::terminal;
 use crate::ui::dialogs::warning::show_warning_confirmation;
+use crate::ui::markup::Markup;
+use crate::ui::stateful_button;
 use crate::ui::task_runner::{self, Command, CommandSequence};
-use crate::ui::utils::extract_widget;
-use gtk4::prelude::*;
+use crate::ui::utils::{extract_widget, require_capabilities, WidgetError};
+use adw::prelude::*;
+use gtk4::glib;
 use gtk4::{ApplicationWindow, Builder, Button};
-use log::info;
+use log::{info, warn};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
 
 /// Set up all button handlers for the drivers page.
-pub fn setup_handlers(page_builder: &Builder, _main_builder: &Builder, window: &ApplicationWindow) {
-    setup_tailscale(page_builder, window);
-    setup_asus_rog(page_builder, window);
-    setup_openrazer(page_builder, window);
-    setup_cooler_control(page_builder, window);
-    setup_zenergy(page_builder, window);
-    setup_nvidia_legacy(page_builder, window);
-    setup_rocm(page_builder, window);
-    setup_cuda(page_builder, window);
-}
-
-fn setup_tailscale(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_tailscale");
+pub fn setup_handlers(
+    page_builder: &Builder,
+    _main_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    let tailscale_card = setup_tailscale_card(page_builder, window)?;
+    setup_tailscale(page_builder, window, &tailscale_card)?;
+    let asus_card = setup_asus_card(page_builder, window)?;
+    setup_asus_rog(page_builder, window, &asus_card)?;
+    setup_nvidia_health(page_builder, window)?;
+    setup_hybrid_graphics(page_builder, window)?;
+    setup_dkms(page_builder, window)?;
+    setup_openrazer(page_builder, window)?;
+    setup_cooler_control(page_builder, window)?;
+    setup_multimedia(page_builder, window)?;
+    setup_zenergy(page_builder, window)?;
+    setup_nvidia_legacy(page_builder, window)?;
+    setup_rocm(page_builder, window)?;
+    setup_cuda(page_builder, window)?;
+
+    require_capabilities(
+        page_builder,
+        &[Capability::Pacman, Capability::Escalate],
+        &[
+            "btn_nvidia_legacy",
+            "btn_rocm",
+            "btn_cuda",
+            "btn_tailscale",
+            "btn_asus_rog",
+            "btn_openrazer",
+            "btn_zenergy",
+            "btn_cooler_control",
+            "btn_multimedia",
+            "btn_dkms_rebuild_all",
+            "btn_hybrid_setup",
+        ],
+    )?;
+    Ok(())
+}
+
+/// Install Tailscale, or once installed, bring up its status card.
+fn setup_tailscale(
+    builder: &Builder,
+    window: &ApplicationWindow,
+    card: &Rc<TailscaleCard>,
+) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_tailscale")?;
     let window = window.clone();
+    let card = card.clone();
 
-    button.connect_clicked(move |_| {
+    let install = move || {
         info!("Tailscale VPN button clicked");
 
         let commands = CommandSequence::new()
@@ -44,34 +97,56 @@ fn setup_tailscale(builder: &Builder, window: &ApplicationWindow) {
                     "-c",
                     "curl -fsSL https://raw.githubusercontent.com/xerolinux/xero-fixes/main/conf/install.sh | bash",
                 ])
-                .description("Installing Tailscale VPN...")
+                .description(&gettext("Installing Tailscale VPN..."))
                 .build())
             .build();
 
-        task_runner::run(window.upcast_ref(), commands, "Install Tailscale VPN");
-    });
+        task_runner::run(
+            window.upcast_ref(),
+            commands,
+            &gettext("Install Tailscale VPN"),
+        );
+    };
+    let manage = move || {
+        info!("Manage Tailscale button clicked");
+        // Focusing the card scrolls the page to it, before the refresh
+        // makes its button insensitive
+        card.refresh_button.grab_focus();
+        refresh_tailscale(&card);
+    };
+
+    stateful_button::setup(
+        &button,
+        &gettext("Manage Tailscale"),
+        || core::is_package_installed("tailscale"),
+        install,
+        manage,
+    );
+    Ok(())
 }
 
-fn setup_asus_rog(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_asus_rog");
+/// Packages of the ROG tools install.
+const ROG_PACKAGES: &[&str] = &["rog-control-center", "asusctl", "supergfxctl"];
+
+/// Install the ROG tools, or once installed, bring up the ROG card.
+fn setup_asus_rog(
+    builder: &Builder,
+    window: &ApplicationWindow,
+    card: &Rc<AsusCard>,
+) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_asus_rog")?;
     let window = window.clone();
+    let card = card.clone();
 
-    button.connect_clicked(move |_| {
+    let install = move || {
         info!("ASUS ROG Tools button clicked");
 
         let commands = CommandSequence::new()
             .then(
                 Command::builder()
                     .aur()
-                    .args(&[
-                        "-S",
-                        "--noconfirm",
-                        "--needed",
-                        "rog-control-center",
-                        "asusctl",
-                        "supergfxctl",
-                    ])
-                    .description("Installing ASUS ROG control tools...")
+                    .args(&[&["-S", "--needed"], ROG_PACKAGES].concat())
+                    .description(&gettext("Installing ASUS ROG control tools..."))
                     .build(),
             )
             .then(
@@ -79,57 +154,81 @@ fn setup_asus_rog(builder: &Builder, window: &ApplicationWindow) {
                     .privileged()
                     .program("systemctl")
                     .args(&["enable", "--now", "asusd", "supergfxd"])
-                    .description("Enabling ASUS ROG services...")
+                    .description(&gettext("Enabling ASUS ROG services..."))
                     .build(),
             )
             .build();
 
-        task_runner::run(window.upcast_ref(), commands, "Install ASUS ROG Tools");
-    });
+        task_runner::run(
+            window.upcast_ref(),
+            commands,
+            &gettext("Install ASUS ROG Tools"),
+        );
+    };
+    let manage = move || {
+        info!("Manage ASUS ROG button clicked");
+        card.refresh_button.grab_focus();
+        refresh_asus(&card);
+    };
+
+    stateful_button::setup(
+        &button,
+        &gettext("Manage ROG Tools"),
+        || {
+            ROG_PACKAGES
+                .iter()
+                .all(|package| core::is_package_installed(package))
+        },
+        install,
+        manage,
+    );
+    Ok(())
 }
 
-fn setup_openrazer(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_openrazer");
+fn setup_openrazer(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_openrazer")?;
     let window = window.clone();
 
-    button.connect_clicked(move |_| {
+    connect_clicked_once_until_done(&button, move |_, token| {
         info!("OpenRazer Drivers button clicked");
 
         // Show selection dialog for optional frontends
         let window_clone = window.clone();
         let config = SelectionDialogConfig::new(
-            "OpenRazer Drivers & Frontend",
-            "OpenRazer drivers will be installed. Optionally select a frontend application for managing your Razer devices.",
+            &gettext("OpenRazer Drivers & Frontend"),
+            gettext("OpenRazer drivers will be installed. Optionally select a frontend application for managing your Razer devices."),
         )
         .selection_type(SelectionType::Multi)
         .selection_required(false)
         .add_option(SelectionOption::new(
             "polychromatic",
-            "Polychromatic",
-            "Graphical frontend for managing Razer devices (GTK-based)",
+            &gettext("Polychromatic"),
+            gettext("Graphical frontend for managing Razer devices (GTK-based)"),
             core::is_package_installed("polychromatic"),
         ))
         .add_option(SelectionOption::new(
             "razergenie",
-            "RazerGenie",
-            "Graphical frontend for managing Razer devices (Qt-based)",
+            &gettext("RazerGenie"),
+            gettext("Graphical frontend for managing Razer devices (Qt-based)"),
             core::is_package_installed("razergenie"),
         ))
-        .confirm_label("Install");
+        .confirm_label(&gettext("Install"));
 
-        show_selection_dialog(window.upcast_ref(), config, move |selected| {
+        let dialog = show_selection_dialog(window.upcast_ref(), config, move |selected| {
             let commands = build_openrazer_commands(&selected);
             task_runner::run(
                 window_clone.upcast_ref(),
                 commands,
-                "Install OpenRazer Drivers (Reboot Required)",
+                &gettext("Install OpenRazer Drivers (Reboot Required)"),
             );
         });
+        token.release_on_close(&dialog);
     });
+    Ok(())
 }
 
-fn setup_cooler_control(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_cooler_control");
+fn setup_cooler_control(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_cooler_control")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
@@ -141,13 +240,14 @@ fn setup_cooler_control(builder: &Builder, window: &ApplicationWindow) {
                     .aur()
                     .args(&[
                         "-S",
-                        "--noconfirm",
                         "--needed",
                         "coolercontrol",
                         "coolercontrold",
                         "liquidctl",
                     ])
-                    .description("Installing Cooler Control daemon and liquidctl...")
+                    .description(&gettext(
+                        "Installing Cooler Control daemon and liquidctl...",
+                    ))
                     .build(),
             )
             .then(
@@ -155,13 +255,18 @@ fn setup_cooler_control(builder: &Builder, window: &ApplicationWindow) {
                     .privileged()
                     .program("systemctl")
                     .args(&["enable", "--now", "coolercontrold.service"])
-                    .description("Enabling Cooler Control daemon service...")
+                    .description(&gettext("Enabling Cooler Control daemon service..."))
                     .build(),
             )
             .build();
 
-        task_runner::run(window.upcast_ref(), commands, "Install Cooler Control");
+        task_runner::run(
+            window.upcast_ref(),
+            commands,
+            &gettext("Install Cooler Control"),
+        );
     });
+    Ok(())
 }
 
 /// Build commands for OpenRazer installation.
@@ -173,8 +278,8 @@ fn build_openrazer_commands(selected_frontends: &[String]) -> CommandSequence {
     commands = commands.then(
         Command::builder()
             .aur()
-            .args(&["-S", "--noconfirm", "--needed", "openrazer-meta-git"])
-            .description("Installing OpenRazer drivers...")
+            .args(&["-S", "--needed", "openrazer-meta-git"])
+            .description(&gettext("Installing OpenRazer drivers..."))
             .build(),
     );
 
@@ -184,7 +289,7 @@ fn build_openrazer_commands(selected_frontends: &[String]) -> CommandSequence {
             .privileged()
             .program("usermod")
             .args(&["-aG", "plugdev", &user])
-            .description("Adding user to plugdev group...")
+            .description(&gettext("Adding user to plugdev group..."))
             .build(),
     );
 
@@ -193,8 +298,8 @@ fn build_openrazer_commands(selected_frontends: &[String]) -> CommandSequence {
         commands = commands.then(
             Command::builder()
                 .aur()
-                .args(&["-S", "--noconfirm", "--needed", "polychromatic"])
-                .description("Installing Polychromatic frontend...")
+                .args(&["-S", "--needed", "polychromatic"])
+                .description(&gettext("Installing Polychromatic frontend..."))
                 .build(),
         );
     }
@@ -203,8 +308,8 @@ fn build_openrazer_commands(selected_frontends: &[String]) -> CommandSequence {
         commands = commands.then(
             Command::builder()
                 .aur()
-                .args(&["-S", "--noconfirm", "--needed", "razergenie"])
-                .description("Installing RazerGenie frontend...")
+                .args(&["-S", "--needed", "razergenie"])
+                .description(&gettext("Installing RazerGenie frontend..."))
                 .build(),
         );
     }
@@ -212,8 +317,194 @@ fn build_openrazer_commands(selected_frontends: &[String]) -> CommandSequence {
     commands
 }
 
-fn setup_zenergy(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_zenergy");
+/// GStreamer plugins for playback and thumbnails in GTK and Qt apps.
+const GSTREAMER_PACKAGES: &[&str] = &[
+    "gst-libav",
+    "gst-plugins-base",
+    "gst-plugins-good",
+    "gst-plugins-bad",
+    "gst-plugins-ugly",
+    "gst-plugin-va",
+];
+
+/// FFmpeg with the thumbnailer file managers use for videos.
+const FFMPEG_PACKAGES: &[&str] = &["ffmpeg", "ffmpegthumbnailer"];
+
+/// Codec and VA-API state, gathered off the main thread.
+struct MultimediaState {
+    /// VA-API driver packages matching the GPUs
+    drivers: Vec<&'static str>,
+    /// Driver and decode profiles, or why vainfo failed
+    vaapi: Result<core::hardware::VaapiInfo, String>,
+    /// Already installed packages among the options
+    installed: Vec<&'static str>,
+}
+
+fn detect_multimedia() -> MultimediaState {
+    let gpus = core::hardware::detect_gpus()
+        .map_err(|e| warn!("Failed to detect GPUs: {:#}", e))
+        .unwrap_or_default();
+    let cpu = core::hardware::detect_cpu();
+    let drivers = core::hardware::vaapi_packages(&gpus, cpu.as_ref());
+    let vaapi = core::hardware::vaapi_info().map_err(|e| format!("{:#}", e));
+
+    let installed = GSTREAMER_PACKAGES
+        .iter()
+        .chain(FFMPEG_PACKAGES)
+        .chain(&drivers)
+        .copied()
+        .filter(|package| core::is_package_installed(package))
+        .collect();
+
+    MultimediaState {
+        drivers,
+        vaapi,
+        installed,
+    }
+}
+
+fn setup_multimedia(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_multimedia")?;
+    let window = window.clone();
+
+    button.connect_clicked(move |button| {
+        info!("Multimedia button clicked");
+        button.set_sensitive(false);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(detect_multimedia());
+        });
+
+        let window = window.clone();
+        let button = button.clone();
+        glib::timeout_add_local(
+            std::time::Duration::from_millis(100),
+            move || match receiver.try_recv() {
+                Ok(state) => {
+                    button.set_sensitive(true);
+                    show_multimedia_dialog(&window, state);
+                    glib::ControlFlow::Break
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    button.set_sensitive(true);
+                    glib::ControlFlow::Break
+                }
+            },
+        );
+    });
+    Ok(())
+}
+
+fn show_multimedia_dialog(window: &ApplicationWindow, state: MultimediaState) {
+    let status = match &state.vaapi {
+        Ok(vaapi) => i18n::fill(
+            &ngettext(
+                "Hardware video decoding works with {}, for {} format profile.",
+                "Hardware video decoding works with {}, for {} format profiles.",
+                vaapi.decode_profiles.len() as u32,
+            ),
+            &[
+                vaapi.driver.as_deref().unwrap_or("VA-API"),
+                &vaapi.decode_profiles.len().to_string(),
+            ],
+        ),
+        Err(e) => {
+            info!("VA-API is not working: {}", e);
+            gettext("Hardware video decoding is not working, videos are decoded by the CPU.")
+        }
+    };
+
+    let installed = |packages: &[&str]| {
+        packages
+            .iter()
+            .all(|package| state.installed.contains(package))
+    };
+
+    let mut config = SelectionDialogConfig::new(
+        &gettext("Multimedia Codecs"),
+        format!(
+            "{}\n\n{}",
+            status,
+            gettext("Select the codecs and drivers to install.")
+        ),
+    )
+    .add_option(SelectionOption::new(
+        "gstreamer",
+        &gettext("GStreamer plugins"),
+        gettext("Full plugin set for playback and thumbnails in desktop apps"),
+        installed(GSTREAMER_PACKAGES),
+    ))
+    .add_option(SelectionOption::new(
+        "ffmpeg",
+        &gettext("FFmpeg"),
+        gettext("FFmpeg with ffmpegthumbnailer for video thumbnails"),
+        installed(FFMPEG_PACKAGES),
+    ));
+
+    for driver in &state.drivers {
+        let description = match *driver {
+            "intel-media-driver" => gettext("VA-API driver for Intel GPUs from Broadwell on"),
+            "libva-intel-driver" => gettext("VA-API driver for Intel GPUs before Broadwell"),
+            "nvidia-vaapi-driver" => {
+                gettext("VA-API on top of NVDEC for the proprietary NVIDIA driver")
+            }
+            _ => gettext("VA-API driver for AMD GPUs and nouveau"),
+        };
+        config = config.add_option(
+            SelectionOption::new(driver, driver, &description, installed(&[*driver]))
+                .preselected(state.vaapi.is_err()),
+        );
+    }
+
+    let config = config
+        .selection_required(false)
+        .confirm_label(&gettext("Install"));
+
+    let window_clone = window.clone();
+    show_selection_dialog(window.upcast_ref(), config, move |selected| {
+        if task_runner::busy() {
+            return;
+        }
+
+        let mut packages = vec!["-S", "--noconfirm", "--needed", "libva-utils"];
+        for id in &selected {
+            match id.as_str() {
+                "gstreamer" => packages.extend_from_slice(GSTREAMER_PACKAGES),
+                "ffmpeg" => packages.extend_from_slice(FFMPEG_PACKAGES),
+                driver => packages.push(driver),
+            }
+        }
+
+        let commands = CommandSequence::new()
+            .then(
+                Command::builder()
+                    .privileged()
+                    .program("pacman")
+                    .args(&packages)
+                    .description(&gettext("Installing codecs and VA-API drivers..."))
+                    .build(),
+            )
+            .then(
+                Command::builder()
+                    .normal()
+                    .program("vainfo")
+                    .description(&gettext("Checking hardware video decoding..."))
+                    .build(),
+            )
+            .build();
+
+        task_runner::run(
+            window_clone.upcast_ref(),
+            commands,
+            &gettext("Multimedia Codecs Setup"),
+        );
+    });
+}
+
+fn setup_zenergy(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_zenergy")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
@@ -223,18 +514,23 @@ fn setup_zenergy(builder: &Builder, window: &ApplicationWindow) {
             .then(
                 Command::builder()
                     .aur()
-                    .args(&["-S", "--noconfirm", "--needed", "zenergy-dkms-git"])
-                    .description("Installing Zenergy Driver...")
+                    .args(&["-S", "--needed", "zenergy-dkms-git"])
+                    .description(&gettext("Installing Zenergy Driver..."))
                     .build(),
             )
             .build();
 
-        task_runner::run(window.upcast_ref(), commands, "Install Zenergy Driver");
+        task_runner::run(
+            window.upcast_ref(),
+            commands,
+            &gettext("Install Zenergy Driver"),
+        );
     });
+    Ok(())
 }
 
-fn setup_nvidia_legacy(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_nvidia_legacy");
+fn setup_nvidia_legacy(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_nvidia_legacy")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
@@ -243,10 +539,10 @@ fn setup_nvidia_legacy(builder: &Builder, window: &ApplicationWindow) {
         let window_clone = window.clone();
         show_warning_confirmation(
             window.upcast_ref(),
-            "Nvidia Legacy Drivers",
-            "This is only intended for <span foreground=\"red\" weight=\"bold\">GTX900/1000</span> Series Legacy GPUs\n\
+            &gettext("Nvidia Legacy Drivers"),
+            Markup::trusted(gettext("This is only intended for <span foreground=\"red\" weight=\"bold\">GTX900/1000</span> Series Legacy GPUs\n\
             For <span foreground=\"cyan\" weight=\"bold\">RTX/Turing+</span> GPUs download the <span foreground=\"green\" weight=\"bold\">nVidia</span> ISO instead.\n\n\
-            <span foreground=\"red\" weight=\"bold\">No Support/Help</span> will be provided for those Legacy GPUs !",
+            <span foreground=\"red\" weight=\"bold\">No Support/Help</span> will be provided for those Legacy GPUs !")),
             move || {
                 // Use configured path
                 let script_dir = crate::config::paths::scripts();
@@ -262,7 +558,6 @@ fn setup_nvidia_legacy(builder: &Builder, window: &ApplicationWindow) {
                             .aur()
                             .args(&[
                                 "-S",
-                                "--noconfirm",
                                 "--needed",
                                 "lib32-nvidia-580xx-utils",
                                 "lib32-opencl-nvidia-580xx",
@@ -270,7 +565,7 @@ fn setup_nvidia_legacy(builder: &Builder, window: &ApplicationWindow) {
                                 "nvidia-580xx-utils",
                                 "opencl-nvidia-580xx",
                             ])
-                            .description("Installing Nvidia Legacy Drivers...")
+                            .description(&gettext("Installing Nvidia Legacy Drivers..."))
                             .build(),
                     )
                     .then(
@@ -278,7 +573,7 @@ fn setup_nvidia_legacy(builder: &Builder, window: &ApplicationWindow) {
                             .privileged()
                             .program("bash")
                             .args(&[&grub_script])
-                            .description("Configuring GRUB (nvidia-drm.modeset=1)...")
+                            .description(&gettext("Configuring GRUB (nvidia-drm.modeset=1)..."))
                             .build(),
                     )
                     .then(
@@ -286,7 +581,7 @@ fn setup_nvidia_legacy(builder: &Builder, window: &ApplicationWindow) {
                             .privileged()
                             .program("bash")
                             .args(&[&mkinitcpio_script])
-                            .description("Configuring mkinitcpio modules...")
+                            .description(&gettext("Configuring mkinitcpio modules..."))
                             .build(),
                     )
                     .then(
@@ -299,7 +594,7 @@ fn setup_nvidia_legacy(builder: &Builder, window: &ApplicationWindow) {
                                 "nvidia-hibernate.service",
                                 "nvidia-resume.service",
                             ])
-                            .description("Enabling Nvidia power management services...")
+                            .description(&gettext("Enabling Nvidia power management services..."))
                             .build(),
                     )
                     .then(
@@ -307,7 +602,8 @@ fn setup_nvidia_legacy(builder: &Builder, window: &ApplicationWindow) {
                             .privileged()
                             .program("mkinitcpio")
                             .args(&["-P"])
-                            .description("Rebuilding initramfs...")
+                            .description(&gettext("Rebuilding initramfs..."))
+                            .low_priority()
                             .build(),
                     )
                     .build();
@@ -315,15 +611,16 @@ fn setup_nvidia_legacy(builder: &Builder, window: &ApplicationWindow) {
                 task_runner::run(
                     window_clone.upcast_ref(),
                     commands,
-                    "Install Nvidia Legacy Drivers",
+                    &gettext("Install Nvidia Legacy Drivers"),
                 );
             },
         );
     });
+    Ok(())
 }
 
-fn setup_rocm(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_rocm");
+fn setup_rocm(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_rocm")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
@@ -333,66 +630,1189 @@ fn setup_rocm(builder: &Builder, window: &ApplicationWindow) {
             .then(
                 Command::builder()
                     .aur()
-                    .args(&[
-                        "-S",
-                        "--noconfirm",
-                        "--needed",
-                        "rocm-hip-sdk",
-                        "rocm-opencl-sdk",
-                    ])
-                    .description("Installing AMD ROCm SDK...")
+                    .args(&["-S", "--needed", "rocm-hip-sdk", "rocm-opencl-sdk"])
+                    .description(&gettext("Installing AMD ROCm SDK..."))
                     .build(),
             )
             .build();
 
-        task_runner::run(window.upcast_ref(), commands, "Install AMD ROCm");
+        task_runner::run(window.upcast_ref(), commands, &gettext("Install AMD ROCm"));
     });
+    Ok(())
 }
 
-fn setup_cuda(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_cuda");
+fn setup_cuda(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_cuda")?;
     let window = window.clone();
 
-    button.connect_clicked(move |_| {
+    connect_clicked_once_until_done(&button, move |_, token| {
         info!("NVIDIA CUDA button clicked");
 
         // Show selection dialog for CUDA version
         let window_clone = window.clone();
         let config = SelectionDialogConfig::new(
-            "NVIDIA CUDA Toolkit",
-            "Select the CUDA version to install. The latest version is recommended for most users.",
+            &gettext("NVIDIA CUDA Toolkit"),
+            gettext("Select the CUDA version to install. The latest version is recommended for most users."),
         )
         .selection_type(SelectionType::Single)
         .selection_required(true)
         .add_option(SelectionOption::new(
             "cuda",
-            "CUDA (Latest)",
-            "Install the latest CUDA toolkit from official repositories",
+            &gettext("CUDA (Latest)"),
+            gettext("Install the latest CUDA toolkit from official repositories"),
             core::is_package_installed("cuda"),
         ))
         .add_option(SelectionOption::new(
             "cuda-12.9",
-            "CUDA 12.9",
-            "Install CUDA Toolkit version 12.9 specifically",
+            &gettext("CUDA 12.9"),
+            gettext("Install CUDA Toolkit version 12.9 specifically"),
             core::is_package_installed("cuda-12.9"),
         ))
-        .confirm_label("Install");
+        .confirm_label(&gettext("Install"));
 
-        show_selection_dialog(window.upcast_ref(), config, move |selected| {
+        let dialog = show_selection_dialog(window.upcast_ref(), config, move |selected| {
             if let Some(package) = selected.first() {
-                let description = format!("Installing {}...", package);
+                let description = i18n::fill(&gettext("Installing {}..."), &[package]);
                 let commands = CommandSequence::new()
                     .then(
                         Command::builder()
                             .aur()
-                            .args(&["-S", "--noconfirm", "--needed", package])
+                            .args(&["-S", "--needed", package])
                             .description(&description)
                             .build(),
                     )
                     .build();
 
-                task_runner::run(window_clone.upcast_ref(), commands, "Install NVIDIA CUDA");
+                task_runner::run(window_clone.upcast_ref(), commands, &gettext("Install NVIDIA CUDA"));
             }
         });
+        token.release_on_close(&dialog);
+    });
+    Ok(())
+}
+
+/// Widgets and state of the Tailscale status card.
+struct TailscaleCard {
+    window: ApplicationWindow,
+    group: adw::PreferencesGroup,
+    status_row: adw::ActionRow,
+    login_button: Button,
+    refresh_button: Button,
+    connect_switch: adw::SwitchRow,
+    exit_node_row: adw::ComboRow,
+    /// Exit node addresses matching the combo row entries; empty for "None".
+    exit_nodes: RefCell<Vec<String>>,
+    /// Set while widgets are synced to the queried state.
+    syncing: Cell<bool>,
+}
+
+fn setup_tailscale_card(
+    builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<Rc<TailscaleCard>, WidgetError> {
+    let card = Rc::new(TailscaleCard {
+        window: window.clone(),
+        group: extract_widget(builder, "tailscale_group")?,
+        status_row: extract_widget(builder, "tailscale_status_row")?,
+        login_button: extract_widget(builder, "btn_tailscale_login")?,
+        refresh_button: extract_widget(builder, "btn_tailscale_refresh")?,
+        connect_switch: extract_widget(builder, "tailscale_connect_switch")?,
+        exit_node_row: extract_widget(builder, "tailscale_exit_node_row")?,
+        exit_nodes: RefCell::new(Vec::new()),
+        syncing: Cell::new(false),
+    });
+
+    let card_clone = card.clone();
+    card.refresh_button.connect_clicked(move |_| {
+        info!("Tailscale refresh button clicked");
+        refresh_tailscale(&card_clone);
+    });
+
+    let card_clone = card.clone();
+    connect_clicked_once_until_done(&card.login_button, move |_, token| {
+        info!("Tailscale authenticate button clicked");
+        let operator = format!("--operator={}", crate::config::env::get().user);
+        let card_for_exit = card_clone.clone();
+        // Interactive so the login URL printed by tailscale can be clicked
+        let dialog = terminal::show_terminal_dialog_with_completion(
+            card_clone.window.upcast_ref(),
+            &gettext("Tailscale Login"),
+            "pkexec",
+            &["tailscale", "up", &operator],
+            false,
+            move |_| refresh_tailscale(&card_for_exit),
+        );
+        token.release_on_close(&dialog);
+    });
+
+    let card_clone = card.clone();
+    card.connect_switch.connect_active_notify(move |switch| {
+        if card_clone.syncing.get() {
+            return;
+        }
+        let action = if switch.is_active() { "up" } else { "down" };
+        run_tailscale(
+            &card_clone,
+            &[action],
+            &i18n::fill(&gettext("Running tailscale {}..."), &[action]),
+        );
+    });
+
+    let card_clone = card.clone();
+    card.exit_node_row.connect_selected_notify(move |row| {
+        if card_clone.syncing.get() {
+            return;
+        }
+        let node = card_clone
+            .exit_nodes
+            .borrow()
+            .get(row.selected() as usize)
+            .cloned()
+            .unwrap_or_default();
+        let description = if node.is_empty() {
+            gettext("Disabling exit node...")
+        } else {
+            i18n::fill(&gettext("Routing traffic through {}..."), &[&node])
+        };
+        run_tailscale(
+            &card_clone,
+            &["set", &format!("--exit-node={}", node)],
+            &description,
+        );
+    });
+
+    refresh_tailscale(&card);
+    Ok(card)
+}
+
+/// Run a privileged tailscale subcommand and refresh the card afterwards.
+fn run_tailscale(card: &Rc<TailscaleCard>, args: &[&str], description: &str) {
+    info!("Tailscale: {:?}", args);
+
+    // Another action owns the runner; snap the widgets back to real state
+    if task_runner::busy() {
+        refresh_tailscale(card);
+        return;
+    }
+
+    let commands = CommandSequence::new()
+        .then(
+            Command::builder()
+                .privileged()
+                .program("tailscale")
+                .args(args)
+                .description(description)
+                .build(),
+        )
+        .build();
+
+    let card_clone = card.clone();
+    task_runner::run_with_completion(
+        card.window.upcast_ref(),
+        commands,
+        &gettext("Tailscale"),
+        move |_| refresh_tailscale(&card_clone),
+    );
+}
+
+/// Query tailscale status in the background and update the card.
+fn refresh_tailscale(card: &Rc<TailscaleCard>) {
+    card.refresh_button.set_sensitive(false);
+
+    let (sender, receiver) = std::sync::mpsc::channel::<Option<anyhow::Result<Status>>>();
+
+    std::thread::spawn(move || {
+        let status = core::is_package_installed("tailscale").then(tailscale::status);
+        let _ = sender.send(status);
+    });
+
+    let card = card.clone();
+    glib::timeout_add_local(
+        std::time::Duration::from_millis(100),
+        move || match receiver.try_recv() {
+            Ok(status) => {
+                card.group.set_visible(status.is_some());
+                if let Some(status) = status {
+                    apply_tailscale_status(&card, status);
+                }
+                card.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                card.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+        },
+    );
+}
+
+fn apply_tailscale_status(card: &TailscaleCard, status: anyhow::Result<Status>) {
+    card.syncing.set(true);
+
+    match status {
+        Ok(status) => {
+            card.status_row.set_title(
+                &status
+                    .account()
+                    .map_or_else(|| gettext("Not logged in"), str::to_string),
+            );
+            let subtitle = if status.is_running() {
+                let peers = status.peers().len() as u32;
+                i18n::fill(
+                    &ngettext("{} · {} peer", "{} · {} peers", peers),
+                    &[
+                        &status
+                            .own_ip()
+                            .map_or_else(|| gettext("no address"), str::to_string),
+                        &peers.to_string(),
+                    ],
+                )
+            } else {
+                status.backend_state.clone()
+            };
+            card.status_row.set_subtitle(&subtitle);
+            card.login_button.set_visible(status.needs_login());
+            card.connect_switch.set_active(status.is_running());
+            card.connect_switch.set_sensitive(!status.needs_login());
+
+            let options = status.exit_node_options();
+            let mut labels = vec![gettext("None")];
+            let mut nodes = vec![String::new()];
+            for node in &options {
+                labels.push(if node.online {
+                    node.name().to_string()
+                } else {
+                    i18n::fill(&gettext("{} (offline)"), &[node.name()])
+                });
+                nodes.push(node.primary_ip().unwrap_or(node.name()).to_string());
+            }
+            let label_refs: Vec<&str> = labels.iter().map(String::as_str).collect();
+            card.exit_node_row
+                .set_model(Some(&gtk4::StringList::new(&label_refs)));
+
+            let current = status
+                .current_exit_node()
+                .and_then(|current| options.iter().position(|node| node.id == current.id))
+                .map_or(0, |index| index + 1);
+            card.exit_node_row.set_selected(current as u32);
+            card.exit_node_row.set_sensitive(status.is_running());
+            *card.exit_nodes.borrow_mut() = nodes;
+        }
+        Err(e) => {
+            warn!("Failed to query tailscale status: {}", e);
+            card.status_row
+                .set_title(&gettext("Tailscale is not running"));
+            card.status_row.set_subtitle(&gettext(
+                "Start tailscaled from the Services page or authenticate",
+            ));
+            card.login_button.set_visible(true);
+            card.connect_switch.set_sensitive(false);
+            card.exit_node_row.set_sensitive(false);
+        }
+    }
+
+    card.syncing.set(false);
+}
+
+/// Widgets and state of the ASUS ROG card.
+struct AsusCard {
+    window: ApplicationWindow,
+    group: adw::PreferencesGroup,
+    refresh_button: Button,
+    missing_row: adw::ActionRow,
+    profile_row: adw::ComboRow,
+    gpu_mode_row: adw::ComboRow,
+    kbd_row: adw::ComboRow,
+    charge_limit_row: adw::SpinRow,
+    charge_limit_button: Button,
+    /// Last queried state, matching the combo row entries.
+    status: RefCell<RogStatus>,
+    /// Set while widgets are synced to the queried state.
+    syncing: Cell<bool>,
+}
+
+fn setup_asus_card(
+    builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<Rc<AsusCard>, WidgetError> {
+    let card = Rc::new(AsusCard {
+        window: window.clone(),
+        group: extract_widget(builder, "asus_group")?,
+        refresh_button: extract_widget(builder, "btn_asus_refresh")?,
+        missing_row: extract_widget(builder, "asus_missing_row")?,
+        profile_row: extract_widget(builder, "asus_profile_row")?,
+        gpu_mode_row: extract_widget(builder, "asus_gpu_mode_row")?,
+        kbd_row: extract_widget(builder, "asus_kbd_row")?,
+        charge_limit_row: extract_widget(builder, "asus_charge_limit_row")?,
+        charge_limit_button: extract_widget(builder, "btn_asus_charge_limit")?,
+        status: RefCell::new(RogStatus::default()),
+        syncing: Cell::new(false),
+    });
+
+    let levels: Vec<String> = asus::KBD_LEVELS
+        .iter()
+        .map(|(_, label)| gettext(label))
+        .collect();
+    let level_refs: Vec<&str> = levels.iter().map(String::as_str).collect();
+    card.kbd_row
+        .set_model(Some(&gtk4::StringList::new(&level_refs)));
+
+    let card_clone = card.clone();
+    card.refresh_button.connect_clicked(move |_| {
+        info!("ASUS refresh button clicked");
+        refresh_asus(&card_clone);
+    });
+
+    let card_clone = card.clone();
+    card.profile_row.connect_selected_notify(move |row| {
+        if card_clone.syncing.get() {
+            return;
+        }
+        let Some(profile) = card_clone
+            .status
+            .borrow()
+            .profiles
+            .get(row.selected() as usize)
+            .cloned()
+        else {
+            return;
+        };
+        run_asus(
+            &card_clone,
+            "asusctl",
+            &["profile", "-P", &profile],
+            &i18n::fill(&gettext("Switching to the {} profile..."), &[&profile]),
+        );
+    });
+
+    let card_clone = card.clone();
+    card.gpu_mode_row.connect_selected_notify(move |row| {
+        if card_clone.syncing.get() {
+            return;
+        }
+        let status = card_clone.status.borrow().clone();
+        let (Some(current), Some(mode)) = (
+            status.gpu_mode.as_deref(),
+            status.gpu_modes.get(row.selected() as usize),
+        ) else {
+            return;
+        };
+        if current != mode {
+            confirm_gpu_switch(&card_clone, current, mode);
+        }
+    });
+
+    let card_clone = card.clone();
+    card.kbd_row.connect_selected_notify(move |row| {
+        if card_clone.syncing.get() {
+            return;
+        }
+        let Some((level, _)) = asus::KBD_LEVELS.get(row.selected() as usize) else {
+            return;
+        };
+        run_asus(
+            &card_clone,
+            "asusctl",
+            &["-k", level],
+            &gettext("Setting keyboard brightness..."),
+        );
+    });
+
+    let card_clone = card.clone();
+    card.charge_limit_button.connect_clicked(move |_| {
+        let limit = card_clone.charge_limit_row.value() as u8;
+        if let Err(e) = asus::validate_charge_limit(limit) {
+            crate::ui::dialogs::error::show_error(&card_clone.window, &e.to_string());
+            return;
+        }
+        run_asus(
+            &card_clone,
+            "asusctl",
+            &["-c", &limit.to_string()],
+            &i18n::fill(
+                &gettext("Limiting battery charge to {}%..."),
+                &[&limit.to_string()],
+            ),
+        );
     });
+
+    // Nothing to show on other hardware
+    if asus::is_asus_hardware() {
+        refresh_asus(&card);
+    } else {
+        info!("Not an ASUS machine, hiding ROG controls");
+    }
+    Ok(card)
+}
+
+/// Ask before switching the GPU mode, which ends the session.
+fn confirm_gpu_switch(card: &Rc<AsusCard>, current: &str, mode: &str) {
+    let body = if asus::gpu_switch_needs_reboot(current, mode) {
+        gettext("The switch takes effect after a reboot. Save your work first.")
+    } else {
+        gettext(
+            "The switch takes effect once you log out, which closes all applications. \
+             Save your work first.",
+        )
+    };
+    let dialog = adw::AlertDialog::builder()
+        .heading(i18n::fill(&gettext("Switch to {} Mode?"), &[mode]))
+        .body(body)
+        .build();
+    dialog.add_response("cancel", &gettext("Cancel"));
+    dialog.add_response("switch", &gettext("Switch"));
+    dialog.set_response_appearance("switch", adw::ResponseAppearance::Destructive);
+    dialog.set_close_response("cancel");
+    dialog.present(Some(&card.window));
+
+    let card = card.clone();
+    let mode = mode.to_string();
+    dialog.connect_response(None, move |_, response| {
+        if response == "switch" {
+            run_asus(
+                &card,
+                "supergfxctl",
+                &["-m", &mode],
+                &i18n::fill(&gettext("Switching GPU to {} mode..."), &[&mode]),
+            );
+        } else {
+            // Put the combo row back on the current mode
+            refresh_asus(&card);
+        }
+    });
+}
+
+/// Run an asusctl or supergfxctl command and refresh the card afterwards.
+fn run_asus(card: &Rc<AsusCard>, program: &str, args: &[&str], description: &str) {
+    info!("ASUS: {} {:?}", program, args);
+
+    // Another action owns the runner; snap the widgets back to real state
+    if task_runner::busy() {
+        refresh_asus(card);
+        return;
+    }
+
+    let commands = CommandSequence::new()
+        .then(
+            Command::builder()
+                .normal()
+                .program(program)
+                .args(args)
+                .description(description)
+                .build(),
+        )
+        .build();
+
+    let card_clone = card.clone();
+    task_runner::run_with_completion(
+        card.window.upcast_ref(),
+        commands,
+        &gettext("ASUS ROG"),
+        move |_| refresh_asus(&card_clone),
+    );
+}
+
+/// Query the ROG tools in the background and update the card.
+fn refresh_asus(card: &Rc<AsusCard>) {
+    card.refresh_button.set_sensitive(false);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(asus::status());
+    });
+
+    let card = card.clone();
+    glib::timeout_add_local(
+        std::time::Duration::from_millis(100),
+        move || match receiver.try_recv() {
+            Ok(status) => {
+                apply_asus_status(&card, status);
+                card.group.set_visible(true);
+                card.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                card.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+        },
+    );
+}
+
+/// Select `current` in a combo row listing `options`, hiding it without any.
+fn sync_combo(row: &adw::ComboRow, options: &[String], current: Option<&str>) {
+    let refs: Vec<&str> = options.iter().map(String::as_str).collect();
+    row.set_model(Some(&gtk4::StringList::new(&refs)));
+    if let Some(index) = current.and_then(|current| options.iter().position(|o| o == current)) {
+        row.set_selected(index as u32);
+    }
+    row.set_visible(!options.is_empty());
+}
+
+fn apply_asus_status(card: &AsusCard, status: RogStatus) {
+    card.syncing.set(true);
+
+    card.missing_row
+        .set_visible(!status.has_asusctl && !status.has_supergfxctl);
+    sync_combo(
+        &card.profile_row,
+        &status.profiles,
+        status.profile.as_deref(),
+    );
+    sync_combo(
+        &card.gpu_mode_row,
+        &status.gpu_modes,
+        status.gpu_mode.as_deref(),
+    );
+
+    card.kbd_row.set_visible(status.has_asusctl);
+    if let Some(level) = status.kbd_level {
+        card.kbd_row.set_selected(level as u32);
+    }
+    card.charge_limit_row.set_visible(status.has_asusctl);
+    if let Some(limit) = status.charge_limit {
+        card.charge_limit_row.set_value(f64::from(limit));
+    }
+
+    *card.status.borrow_mut() = status;
+    card.syncing.set(false);
+}
+
+/// Widgets of the NVIDIA driver health panel.
+struct NvidiaPanel {
+    window: ApplicationWindow,
+    group: adw::PreferencesGroup,
+    refresh_button: Button,
+    /// Check rows, rebuilt on every refresh.
+    rows: RefCell<Vec<adw::ActionRow>>,
+}
+
+fn setup_nvidia_health(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let panel = Rc::new(NvidiaPanel {
+        window: window.clone(),
+        group: extract_widget(builder, "nvidia_group")?,
+        refresh_button: extract_widget(builder, "btn_nvidia_refresh")?,
+        rows: RefCell::new(Vec::new()),
+    });
+
+    let panel_clone = panel.clone();
+    panel.refresh_button.connect_clicked(move |_| {
+        info!("NVIDIA refresh button clicked");
+        refresh_nvidia(&panel_clone);
+    });
+
+    refresh_nvidia(&panel);
+    Ok(())
+}
+
+/// Whether an NVIDIA GPU is present, checked once in the background.
+fn has_nvidia_gpu() -> bool {
+    core::hardware::detect_gpus()
+        .map_err(|e| warn!("Failed to detect GPUs: {:#}", e))
+        .unwrap_or_default()
+        .iter()
+        .any(|gpu| gpu.name.contains("NVIDIA"))
+}
+
+/// Check the driver in the background and rebuild the panel rows. The
+/// panel stays hidden without an NVIDIA GPU.
+fn refresh_nvidia(panel: &Rc<NvidiaPanel>) {
+    panel.refresh_button.set_sensitive(false);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(has_nvidia_gpu().then(nvidia::check));
+    });
+
+    let panel = panel.clone();
+    glib::timeout_add_local(
+        std::time::Duration::from_millis(100),
+        move || match receiver.try_recv() {
+            Ok(Some(health)) => {
+                apply_nvidia_health(&panel, &health);
+                panel.group.set_visible(true);
+                panel.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+            Ok(None) => {
+                info!("No NVIDIA GPU, hiding driver health");
+                glib::ControlFlow::Break
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                panel.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+        },
+    );
+}
+
+/// Fix offered next to a failed check.
+#[derive(Debug, Clone, Copy)]
+enum NvidiaFix {
+    /// Install the kernel headers and build the DKMS module
+    Rebuild,
+    /// Enable modesetting in the boot options
+    Modeset,
+    /// Reboot into the updated driver
+    Reboot,
+}
+
+fn apply_nvidia_health(panel: &Rc<NvidiaPanel>, health: &Health) {
+    for row in panel.rows.borrow_mut().drain(..) {
+        panel.group.remove(&row);
+    }
+
+    let missing = health.missing_modules();
+    let rebuild = (health.dkms_built() == Some(false)).then_some(NvidiaFix::Rebuild);
+    if missing.is_empty() {
+        add_nvidia_row(panel, health, &gettext("Kernel modules loaded"), "", None);
+    } else {
+        add_nvidia_row(
+            panel,
+            health,
+            &gettext("Kernel modules not loaded"),
+            &missing.join(", "),
+            Some(rebuild.unwrap_or(NvidiaFix::Reboot)),
+        );
+    }
+
+    match health.dkms_built() {
+        Some(true) => add_nvidia_row(
+            panel,
+            health,
+            &gettext("DKMS module built"),
+            &health.kernel,
+            None,
+        ),
+        Some(false) => add_nvidia_row(
+            panel,
+            health,
+            &gettext("DKMS module not built for this kernel"),
+            &health.kernel,
+            Some(NvidiaFix::Rebuild),
+        ),
+        None => {}
+    }
+
+    match health.modeset {
+        Some(true) => add_nvidia_row(panel, health, &gettext("Modesetting enabled"), "", None),
+        Some(false) => add_nvidia_row(
+            panel,
+            health,
+            &gettext("Modesetting disabled"),
+            &gettext("Wayland sessions need nvidia-drm.modeset=1"),
+            Some(NvidiaFix::Modeset),
+        ),
+        // Reported above as a missing module
+        None => {}
+    }
+
+    if let (Some(module), Some(driver)) = (&health.module_version, &health.driver_version) {
+        let versions = i18n::fill(&gettext("Loaded {}, installed {}"), &[module, driver]);
+        if health.version_mismatch() {
+            add_nvidia_row(
+                panel,
+                health,
+                &gettext("Driver updated, reboot pending"),
+                &versions,
+                Some(NvidiaFix::Reboot),
+            );
+        } else {
+            add_nvidia_row(
+                panel,
+                health,
+                &gettext("Driver up to date"),
+                &versions,
+                None,
+            );
+        }
+    }
+}
+
+/// Add a check row, with a fix button if the check failed.
+fn add_nvidia_row(
+    panel: &Rc<NvidiaPanel>,
+    health: &Health,
+    title: &str,
+    subtitle: &str,
+    fix: Option<NvidiaFix>,
+) {
+    let row = adw::ActionRow::builder()
+        .title(title)
+        .subtitle(subtitle)
+        .build();
+    let icon = if fix.is_some() {
+        "triangle-exclamation-symbolic"
+    } else {
+        "circle-check-symbolic"
+    };
+    row.add_prefix(&gtk4::Image::from_icon_name(icon));
+
+    if let Some(fix) = fix {
+        let button = Button::builder()
+            .label(gettext("Fix"))
+            .valign(gtk4::Align::Center)
+            .build();
+        let panel_clone = panel.clone();
+        let health = health.clone();
+        button.connect_clicked(move |_| {
+            info!("NVIDIA fix clicked: {:?}", fix);
+            match fix {
+                NvidiaFix::Rebuild => rebuild_nvidia(&panel_clone, &health),
+                NvidiaFix::Modeset => crate::ui::pages::servicing::open_boot_options(
+                    &panel_clone.window,
+                    &[nvidia::MODESET_PARAM],
+                ),
+                NvidiaFix::Reboot => confirm_nvidia_reboot(&panel_clone),
+            }
+        });
+        row.add_suffix(&button);
+    }
+
+    panel.group.add(&row);
+    panel.rows.borrow_mut().push(row);
+}
+
+/// Install the headers of the running kernel and build the DKMS module
+/// for it.
+fn rebuild_nvidia(panel: &Rc<NvidiaPanel>, health: &Health) {
+    if task_runner::busy() {
+        return;
+    }
+
+    let mut commands = CommandSequence::new();
+    if let Some(headers) = &health.headers {
+        commands = commands.then(
+            Command::builder()
+                .privileged()
+                .program("pacman")
+                .args(&["-S", "--needed", "--noconfirm", headers])
+                .description(&i18n::fill(&gettext("Installing {}..."), &[headers]))
+                .build(),
+        );
+    }
+    let commands = commands
+        .then(
+            Command::builder()
+                .privileged()
+                .program("dkms")
+                .args(&["autoinstall", "-k", &health.kernel])
+                .description(&gettext("Building the NVIDIA module..."))
+                .build(),
+        )
+        .then(core::dkms::verify_command(
+            "nvidia",
+            &gettext("Checking the NVIDIA module..."),
+        ))
+        .build();
+
+    let panel_clone = panel.clone();
+    task_runner::run_with_completion(
+        panel.window.upcast_ref(),
+        commands,
+        &gettext("NVIDIA Driver Repair"),
+        move |_| refresh_nvidia(&panel_clone),
+    );
+}
+
+fn confirm_nvidia_reboot(panel: &Rc<NvidiaPanel>) {
+    let window = panel.window.clone();
+    show_warning_confirmation(
+        panel.window.upcast_ref(),
+        &gettext("Reboot Now?"),
+        gettext("The updated driver is used after a reboot. Save your work first."),
+        move || {
+            if task_runner::busy() {
+                return;
+            }
+            let commands = CommandSequence::new()
+                .then(
+                    Command::builder()
+                        .normal()
+                        .program("systemctl")
+                        .args(&["reboot"])
+                        .description(&gettext("Rebooting system..."))
+                        .build(),
+                )
+                .build();
+            task_runner::run(window.upcast_ref(), commands, &gettext("Reboot"));
+        },
+    );
+}
+
+/// Where the generated `prime-run` wrapper goes, ahead of the one
+/// nvidia-prime installs in /usr/bin.
+const PRIME_RUN_PATH: &str = "/usr/local/bin/prime-run";
+
+/// Print the renderer `prime-run` gives OpenGL, or Vulkan without glxinfo.
+const PRIME_RUN_CHECK: &str = r#"if command -v glxinfo >/dev/null; then
+    "$PRIME_RUN" glxinfo -B | grep -i "renderer string"
+else
+    "$PRIME_RUN" vulkaninfo --summary | grep deviceName
+fi"#;
+
+/// Show the hybrid graphics card once a second GPU is found, with PRIME
+/// offload set up through switcheroo-control and a `prime-run` wrapper.
+fn setup_hybrid_graphics(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let group = extract_widget::<adw::PreferencesGroup>(builder, "hybrid_group")?;
+    let gpu_row = extract_widget::<adw::ActionRow>(builder, "hybrid_gpu_row")?;
+    let setup_button = extract_widget::<Button>(builder, "btn_hybrid_setup")?;
+    let verify_button = extract_widget::<Button>(builder, "btn_hybrid_verify")?;
+    let offload: Rc<RefCell<Option<core::hardware::Offload>>> = Rc::new(RefCell::new(None));
+
+    let window_clone = window.clone();
+    let offload_clone = offload.clone();
+    setup_button.connect_clicked(move |_| {
+        info!("Hybrid graphics setup button clicked");
+        let Some(offload) = offload_clone.borrow().clone() else {
+            return;
+        };
+        let commands = CommandSequence::new()
+            .then(
+                Command::builder()
+                    .aur()
+                    .args(&["-S", "--needed", "switcheroo-control", "mesa-utils"])
+                    .description(&gettext("Installing switcheroo-control..."))
+                    .build(),
+            )
+            .then(
+                Command::builder()
+                    .privileged()
+                    .program("systemctl")
+                    .args(&["enable", "--now", "switcheroo-control"])
+                    .description(&gettext("Enabling switcheroo-control..."))
+                    .build(),
+            )
+            .then(
+                privileged_fs::write_file(
+                    PRIME_RUN_PATH,
+                    &core::hardware::prime_run_script(&offload),
+                )
+                .mode(0o755)
+                .command(&gettext("Writing the prime-run command...")),
+            )
+            .then(prime_run_check())
+            .build();
+        task_runner::run(
+            window_clone.upcast_ref(),
+            commands,
+            &gettext("Set Up PRIME Offload"),
+        );
+    });
+
+    let window_clone = window.clone();
+    verify_button.connect_clicked(move |_| {
+        info!("Hybrid graphics test button clicked");
+        let commands = CommandSequence::new().then(prime_run_check()).build();
+        task_runner::run(
+            window_clone.upcast_ref(),
+            commands,
+            &gettext("Test PRIME Offload"),
+        );
+    });
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let gpus = core::hardware::detect_gpus()
+            .map_err(|e| warn!("Failed to detect GPUs: {:#}", e))
+            .unwrap_or_default();
+        let _ = sender.send(core::hardware::hybrid_offload(&gpus));
+    });
+
+    glib::timeout_add_local(
+        std::time::Duration::from_millis(100),
+        move || match receiver.try_recv() {
+            Ok(Some(found)) => {
+                info!("Hybrid graphics, offload GPU: {}", found.gpu.name);
+                gpu_row.set_subtitle(&found.gpu.name);
+                *offload.borrow_mut() = Some(found);
+                group.set_visible(true);
+                glib::ControlFlow::Break
+            }
+            Ok(None) => {
+                info!("No second GPU, hiding hybrid graphics");
+                glib::ControlFlow::Break
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => glib::ControlFlow::Break,
+        },
+    );
+    Ok(())
+}
+
+/// Step printing the renderer of the offload GPU in the completion message.
+fn prime_run_check() -> Command {
+    Command::script(
+        &gettext("Checking the offload renderer..."),
+        PRIME_RUN_CHECK,
+    )
+    .var("PRIME_RUN", PRIME_RUN_PATH)
+    .summarize()
+    .build()
+}
+
+/// Widgets of the DKMS kernel modules panel.
+struct DkmsPanel {
+    window: ApplicationWindow,
+    group: adw::PreferencesGroup,
+    refresh_button: Button,
+    /// Module rows, rebuilt on every refresh.
+    rows: RefCell<Vec<adw::ActionRow>>,
+}
+
+/// DKMS modules and the kernel they are checked against.
+struct DkmsStatus {
+    kernel: String,
+    modules: Vec<DkmsModule>,
+}
+
+fn setup_dkms(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let panel = Rc::new(DkmsPanel {
+        window: window.clone(),
+        group: extract_widget(builder, "dkms_group")?,
+        refresh_button: extract_widget(builder, "btn_dkms_refresh")?,
+        rows: RefCell::new(Vec::new()),
+    });
+    let rebuild_all_button = extract_widget::<Button>(builder, "btn_dkms_rebuild_all")?;
+
+    let panel_clone = panel.clone();
+    panel.refresh_button.connect_clicked(move |_| {
+        info!("DKMS refresh button clicked");
+        refresh_dkms(&panel_clone);
+    });
+
+    let panel_clone = panel.clone();
+    rebuild_all_button.connect_clicked(move |_| {
+        info!("DKMS rebuild all button clicked");
+        rebuild_dkms(&panel_clone, None);
+    });
+
+    refresh_dkms(&panel);
+    Ok(())
+}
+
+/// Read `dkms status` in the background and rebuild the panel rows. The
+/// panel stays hidden without dkms.
+fn refresh_dkms(panel: &Rc<DkmsPanel>) {
+    panel.refresh_button.set_sensitive(false);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let status = dkms::is_available().then(|| DkmsStatus {
+            kernel: dkms::running_kernel(),
+            modules: dkms::modules(&dkms::status()),
+        });
+        let _ = sender.send(status);
+    });
+
+    let panel = panel.clone();
+    glib::timeout_add_local(
+        std::time::Duration::from_millis(100),
+        move || match receiver.try_recv() {
+            Ok(Some(status)) => {
+                apply_dkms_status(&panel, &status);
+                panel.group.set_visible(true);
+                panel.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+            Ok(None) => {
+                info!("dkms not installed, hiding kernel modules");
+                glib::ControlFlow::Break
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                panel.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+        },
+    );
+}
+
+fn apply_dkms_status(panel: &Rc<DkmsPanel>, status: &DkmsStatus) {
+    for row in panel.rows.borrow_mut().drain(..) {
+        panel.group.remove(&row);
+    }
+
+    if status.modules.is_empty() {
+        let row = adw::ActionRow::builder()
+            .title(gettext("No DKMS modules"))
+            .build();
+        panel.group.add(&row);
+        panel.rows.borrow_mut().push(row);
+        return;
+    }
+
+    for module in &status.modules {
+        add_dkms_row(panel, module, &status.kernel);
+    }
+}
+
+/// Add the row of `module`, highlighted when it is missing for `kernel`.
+fn add_dkms_row(panel: &Rc<DkmsPanel>, module: &DkmsModule, kernel: &str) {
+    let installed = module.installed_for(kernel);
+
+    // "6.10.10-arch1-1: installed"
+    let mut lines: Vec<String> = module
+        .entries
+        .iter()
+        .filter_map(|entry| {
+            let build_kernel = entry.kernel.as_deref()?;
+            Some(i18n::fill(
+                &gettext("{}: {}"),
+                &[build_kernel, &entry.state],
+            ))
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(gettext("Not built for any kernel"));
+    }
+    if !installed {
+        lines.insert(
+            0,
+            i18n::fill(&gettext("Missing for the running kernel {}"), &[kernel]),
+        );
+    }
+
+    let row = adw::ActionRow::builder()
+        .title(format!("{} {}", module.module, module.version))
+        .subtitle(lines.join("\n"))
+        .use_markup(false)
+        .build();
+    let icon = if installed {
+        gtk4::Image::from_icon_name("circle-check-symbolic")
+    } else {
+        let icon = gtk4::Image::from_icon_name("triangle-exclamation-symbolic");
+        icon.add_css_class("warning");
+        icon
+    };
+    row.add_prefix(&icon);
+
+    let rebuild_button = Button::builder()
+        .icon_name("hammer-symbolic")
+        .tooltip_text(gettext("Rebuild for the running kernel"))
+        .valign(gtk4::Align::Center)
+        .build();
+    rebuild_button.add_css_class("flat");
+    if !installed {
+        rebuild_button.add_css_class("suggested-action");
+    }
+    let panel_clone = panel.clone();
+    let module_clone = module.clone();
+    rebuild_button.connect_clicked(move |_| {
+        info!("DKMS rebuild clicked: {}", module_clone.spec());
+        rebuild_dkms(&panel_clone, Some(&module_clone));
+    });
+    row.add_suffix(&rebuild_button);
+
+    let remove_button = Button::builder()
+        .icon_name("trash-symbolic")
+        .tooltip_text(gettext("Remove from DKMS"))
+        .valign(gtk4::Align::Center)
+        .build();
+    remove_button.add_css_class("flat");
+    remove_button.add_css_class("destructive-action");
+    let panel_clone = panel.clone();
+    let module_clone = module.clone();
+    remove_button.connect_clicked(move |_| {
+        info!("DKMS remove clicked: {}", module_clone.spec());
+        remove_dkms(&panel_clone, &module_clone);
+    });
+    row.add_suffix(&remove_button);
+
+    panel.group.add(&row);
+    panel.rows.borrow_mut().push(row);
+}
+
+/// Install the headers of the running kernel and build `module` for it,
+/// or every module when `None`.
+fn rebuild_dkms(panel: &Rc<DkmsPanel>, module: Option<&DkmsModule>) {
+    if task_runner::busy() {
+        return;
+    }
+
+    let kernel = dkms::running_kernel();
+    let mut commands = CommandSequence::new();
+    if let Some(headers) = dkms::headers_package(&kernel) {
+        commands = commands.then(
+            Command::builder()
+                .privileged()
+                .program("pacman")
+                .args(&["-S", "--needed", "--noconfirm", &headers])
+                .description(&i18n::fill(&gettext("Installing {}..."), &[&headers]))
+                .build(),
+        );
+    }
+    commands = match module {
+        Some(module) => {
+            let spec = module.spec();
+            commands
+                .then(
+                    Command::builder()
+                        .privileged()
+                        .program("dkms")
+                        .args(&["install", "--force", &spec, "-k", &kernel])
+                        .description(&i18n::fill(&gettext("Building {}..."), &[&spec]))
+                        .build(),
+                )
+                .then(dkms::verify_command(
+                    &module.module,
+                    &i18n::fill(&gettext("Checking that {} is built..."), &[&module.module]),
+                ))
+        }
+        None => commands.then(
+            Command::builder()
+                .privileged()
+                .program("dkms")
+                .args(&["autoinstall", "-k", &kernel])
+                .description(&gettext("Building all kernel modules..."))
+                .build(),
+        ),
+    };
+
+    let panel_clone = panel.clone();
+    task_runner::run_with_completion(
+        panel.window.upcast_ref(),
+        commands.build(),
+        &gettext("Rebuild Kernel Modules"),
+        move |_| refresh_dkms(&panel_clone),
+    );
+}
+
+/// Remove `module` from DKMS for every kernel, after confirmation.
+fn remove_dkms(panel: &Rc<DkmsPanel>, module: &DkmsModule) {
+    let spec = module.spec();
+    let panel_clone = panel.clone();
+    show_warning_confirmation(
+        panel.window.upcast_ref(),
+        &gettext("Remove Kernel Module?"),
+        i18n::fill(
+            &gettext("{} is removed for every kernel. Its package stays installed; reinstall the package to build the module again."),
+            &[&spec],
+        ),
+        move || {
+            if task_runner::busy() {
+                return;
+            }
+            let commands = CommandSequence::new()
+                .then(
+                    Command::builder()
+                        .privileged()
+                        .program("dkms")
+                        .args(&["remove", &spec, "--all"])
+                        .description(&i18n::fill(&gettext("Removing {}..."), &[&spec]))
+                        .build(),
+                )
+                .build();
+            let panel = panel_clone.clone();
+            task_runner::run_with_completion(
+                panel_clone.window.upcast_ref(),
+                commands,
+                &gettext("Remove Kernel Module"),
+                move |_| refresh_dkms(&panel),
+            );
+        },
+    );
 }
@@ -1,119 +1,354 @@
 //! Gaming tools page button handlers.
 //!
 //! Handles:
-//! - Steam AiO installation
+//! - Steam AiO installation (native with multilib and Vulkan, or Flatpak)
 //! - LACT GPU overclocking
 //! - Game launchers (Lutris, Heroic, Bottles)
-//! - Controller tools
+//! - Controller support (Xbox, PlayStation and Nintendo drivers, udev rules)
 //! - Falcond gaming utility
+//! - Performance tools (gamemode, MangoHud)
+//! - Proton-GE installation and removal
 
+use crate::core::download::format_bytes;
+use crate::core::proton::{self, ProtonRelease};
+use crate::core::system_check::Capability;
+use crate::core::{self, dkms, hardware, pacman_conf, privileged_fs};
+use crate::i18n::{self, gettext};
+use crate::ui::click_guard::connect_clicked_once_until_done;
+use crate::ui::dialogs::download::start_download;
+use crate::ui::dialogs::error::show_error;
+use crate::ui::dialogs::selection::{
+    show_selection_dialog, SelectionDialogConfig, SelectionOption, SelectionType,
+};
+use crate::ui::dialogs::warning::show_warning_confirmation;
+use crate::ui::navigation::PageContext;
 use crate::ui::task_runner::{self, Command, CommandSequence};
-use crate::ui::utils::extract_widget;
+use crate::ui::utils::{extract_widget, require_capabilities, WidgetError};
+use gtk4::glib;
 use gtk4::prelude::*;
 use gtk4::{ApplicationWindow, Builder, Button};
-use log::info;
+use log::{error, info, warn};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, TryRecvError};
+use std::time::Duration;
+
+/// Default MangoHud overlay configuration deployed for the user.
+const MANGOHUD_DEFAULT_CONFIG: &str = "\
+# Deployed by XeroLinux Toolkit. Toggle the overlay with Shift_R+F12.
+legacy_layout=false
+gpu_stats
+gpu_temp
+cpu_stats
+cpu_temp
+ram
+vram
+fps
+frametime
+frame_timing
+gamemode
+position=top-left
+toggle_hud=Shift_R+F12
+";
+
+/// Number of most recent Proton-GE releases offered for installation.
+const PROTON_GE_RELEASE_LIMIT: usize = 15;
 
 /// Set up all button handlers for the gaming tools page.
-pub fn setup_handlers(page_builder: &Builder, _main_builder: &Builder, window: &ApplicationWindow) {
-    setup_steam_aio(page_builder, window);
-    setup_lact_oc(page_builder, window);
-    setup_lutris(page_builder, window);
-    setup_heroic(page_builder, window);
-    setup_bottles(page_builder, window);
-    setup_controller(page_builder, window);
-    setup_falcond(page_builder, window);
+pub fn setup_handlers(context: &PageContext) -> Result<(), WidgetError> {
+    let page_builder = &context.builder;
+    let window = &context.window;
+    setup_steam_aio(page_builder, window)?;
+    setup_lact_oc(page_builder, window)?;
+    setup_lutris(page_builder, window)?;
+    setup_heroic(page_builder, window)?;
+    setup_bottles(page_builder, window)?;
+    setup_controller(page_builder, window)?;
+    setup_falcond(page_builder, window)?;
+    setup_performance_tools(page_builder, window)?;
+    setup_proton_ge(page_builder, window)?;
+    setup_proton_ge_manage(page_builder, window)?;
+
+    require_capabilities(
+        page_builder,
+        &[Capability::Pacman, Capability::Escalate],
+        &[
+            "btn_steam_aio",
+            "btn_lact_oc",
+            "btn_controller",
+            "btn_falcond",
+            "btn_performance_tools",
+        ],
+    )?;
+    require_capabilities(
+        page_builder,
+        &[Capability::Flatpak],
+        &["btn_lutris", "btn_heroic", "btn_bottles"],
+    )?;
+    Ok(())
 }
 
-fn setup_steam_aio(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_steam_aio");
+/// Packages of the native Steam install besides the Vulkan drivers.
+const STEAM_PACKAGES: &[&str] = &[
+    "steam",
+    "gamescope",
+    "mangohud",
+    "mangoverlay",
+    "protonplus",
+    "lib32-mangohud",
+    "wine-meta",
+    "wine-nine",
+    "ttf-liberation",
+    "lib32-fontconfig",
+    "wqy-zenhei",
+    "vkd3d",
+    "giflib",
+    "lib32-giflib",
+    "libpng",
+    "lib32-libpng",
+    "libldap",
+    "lib32-libldap",
+    "gnutls",
+    "lib32-gnutls",
+    "mpg123",
+    "lib32-mpg123",
+    "openal",
+    "lib32-openal",
+    "v4l-utils",
+    "lib32-v4l-utils",
+    "libpulse",
+    "lib32-libpulse",
+    "libgpg-error",
+    "lib32-libgpg-error",
+    "alsa-plugins",
+    "lib32-alsa-plugins",
+    "alsa-lib",
+    "lib32-alsa-lib",
+    "libjpeg-turbo",
+    "lib32-libjpeg-turbo",
+    "sqlite",
+    "lib32-sqlite",
+    "libxcomposite",
+    "lib32-libxcomposite",
+    "libxinerama",
+    "lib32-libgcrypt",
+    "libgcrypt",
+    "lib32-libxinerama",
+    "ncurses",
+    "lib32-ncurses",
+    "ocl-icd",
+    "lib32-ocl-icd",
+    "libxslt",
+    "lib32-libxslt",
+    "libva",
+    "lib32-libva",
+    "gtk3",
+    "lib32-gtk3",
+    "gst-plugins-base-libs",
+    "lib32-gst-plugins-base-libs",
+    "vulkan-icd-loader",
+    "lib32-vulkan-icd-loader",
+    "cups",
+    "dosbox",
+    "lib32-opencl-icd-loader",
+    "lib32-vkd3d",
+    "opencl-icd-loader",
+];
+
+/// What the Steam install needs to know about the system.
+struct SteamSetup {
+    multilib: bool,
+    vulkan: Vec<&'static str>,
+}
+
+fn setup_steam_aio(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_steam_aio")?;
     let window = window.clone();
 
-    button.connect_clicked(move |_| {
+    button.connect_clicked(move |button| {
         info!("Steam AiO button clicked");
+        button.set_sensitive(false);
 
-        let commands = CommandSequence::new()
+        let (tx, rx) = mpsc::channel::<SteamSetup>();
+
+        std::thread::spawn(move || {
+            let multilib =
+                pacman_conf::is_repo_enabled(pacman_conf::MULTILIB).unwrap_or_else(|e| {
+                    warn!("Failed to check the multilib repository: {}", e);
+                    false
+                });
+            let vulkan = match hardware::detect_gpus() {
+                Ok(gpus) => hardware::vulkan_packages(&gpus),
+                Err(e) => {
+                    warn!("Failed to detect GPUs: {}", e);
+                    Vec::new()
+                }
+            };
+            let _ = tx.send(SteamSetup { multilib, vulkan });
+        });
+
+        let window = window.clone();
+        let button = button.clone();
+        glib::timeout_add_local(Duration::from_millis(50), move || match rx.try_recv() {
+            Ok(setup) => {
+                button.set_sensitive(true);
+                show_steam_picker(&window, setup);
+                glib::ControlFlow::Break
+            }
+            Err(TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(TryRecvError::Disconnected) => {
+                button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+        });
+    });
+    Ok(())
+}
+
+fn show_steam_picker(window: &ApplicationWindow, setup: SteamSetup) {
+    let drivers = if setup.vulkan.is_empty() {
+        gettext("No GPU detected, Vulkan drivers are left as they are.")
+    } else {
+        i18n::fill(
+            &gettext("Vulkan drivers for your GPU: {}"),
+            &[&setup.vulkan.join(", ")],
+        )
+    };
+    let native_description = if setup.multilib {
+        drivers
+    } else {
+        format!(
+            "{} {}",
+            drivers,
+            gettext("The multilib repository will be enabled.")
+        )
+    };
+
+    let config = SelectionDialogConfig::new(
+        &gettext("Steam All-in-One"),
+        gettext("Choose how to install Steam."),
+    )
+    .selection_type(SelectionType::Single)
+    .add_option(
+        SelectionOption::new(
+            "native",
+            &gettext("Native Steam"),
+            &native_description,
+            false,
+        )
+        .preselected(true),
+    )
+    .add_option(SelectionOption::new(
+        "flatpak",
+        &gettext("Flatpak Steam"),
+        gettext("Sandboxed Steam from Flathub, with its own runtime and drivers"),
+        false,
+    ))
+    .confirm_label(&gettext("Install"));
+
+    let window_clone = window.clone();
+    show_selection_dialog(window.upcast_ref(), config, move |selected| match selected
+        .first()
+        .map(String::as_str)
+    {
+        Some("native") if !setup.multilib => {
+            let window = window_clone.clone();
+            let vulkan = setup.vulkan.clone();
+            show_warning_confirmation(
+                window_clone.upcast_ref(),
+                &gettext("Enable Multilib"),
+                gettext(
+                    "Steam needs 32-bit libraries from the multilib repository, which is \
+                         disabled. It will be enabled in /etc/pacman.conf and the system \
+                         upgraded before Steam is installed.",
+                ),
+                move || {
+                    task_runner::run(
+                        window.upcast_ref(),
+                        native_steam_commands(false, &vulkan),
+                        &gettext("Steam AiO Installation"),
+                    );
+                },
+            );
+        }
+        Some("native") => task_runner::run(
+            window_clone.upcast_ref(),
+            native_steam_commands(true, &setup.vulkan),
+            &gettext("Steam AiO Installation"),
+        ),
+        Some("flatpak") => task_runner::run(
+            window_clone.upcast_ref(),
+            flatpak_steam_commands(),
+            &gettext("Steam Installation"),
+        ),
+        _ => {}
+    });
+}
+
+/// Build the native Steam install, enabling multilib first when needed and
+/// checking Vulkan at the end.
+fn native_steam_commands(multilib: bool, vulkan: &[&str]) -> CommandSequence {
+    let mut commands = CommandSequence::new().long_running();
+
+    if !multilib {
+        commands = commands
+            .phase(&gettext("Repositories"))
+            .then(
+                pacman_conf::enable_repo_edit(pacman_conf::MULTILIB)
+                    .command(&gettext("Enabling the multilib repository...")),
+            )
+            // Installing from a freshly synced repository without upgrading
+            // would leave a partial upgrade
             .then(
                 Command::builder()
-                    .aur()
-                    .args(&[
-                        "-S",
-                        "--noconfirm",
-                        "--needed",
-                        "steam",
-                        "gamescope",
-                        "mangohud",
-                        "mangoverlay",
-                        "protonplus",
-                        "lib32-mangohud",
-                        "wine-meta",
-                        "wine-nine",
-                        "ttf-liberation",
-                        "lib32-fontconfig",
-                        "wqy-zenhei",
-                        "vkd3d",
-                        "giflib",
-                        "lib32-giflib",
-                        "libpng",
-                        "lib32-libpng",
-                        "libldap",
-                        "lib32-libldap",
-                        "gnutls",
-                        "lib32-gnutls",
-                        "mpg123",
-                        "lib32-mpg123",
-                        "openal",
-                        "lib32-openal",
-                        "v4l-utils",
-                        "lib32-v4l-utils",
-                        "libpulse",
-                        "lib32-libpulse",
-                        "libgpg-error",
-                        "lib32-libgpg-error",
-                        "alsa-plugins",
-                        "lib32-alsa-plugins",
-                        "alsa-lib",
-                        "lib32-alsa-lib",
-                        "libjpeg-turbo",
-                        "lib32-libjpeg-turbo",
-                        "sqlite",
-                        "lib32-sqlite",
-                        "libxcomposite",
-                        "lib32-libxcomposite",
-                        "libxinerama",
-                        "lib32-libgcrypt",
-                        "libgcrypt",
-                        "lib32-libxinerama",
-                        "ncurses",
-                        "lib32-ncurses",
-                        "ocl-icd",
-                        "lib32-ocl-icd",
-                        "libxslt",
-                        "lib32-libxslt",
-                        "libva",
-                        "lib32-libva",
-                        "gtk3",
-                        "lib32-gtk3",
-                        "gst-plugins-base-libs",
-                        "lib32-gst-plugins-base-libs",
-                        "vulkan-icd-loader",
-                        "lib32-vulkan-icd-loader",
-                        "cups",
-                        "dosbox",
-                        "lib32-opencl-icd-loader",
-                        "lib32-vkd3d",
-                        "opencl-icd-loader",
-                    ])
-                    .description("Installing Steam and gaming dependencies...")
+                    .privileged()
+                    .program("pacman")
+                    .args(&["-Syu", "--noconfirm"])
+                    .description(&gettext("Syncing repositories and upgrading the system..."))
                     .build(),
-            )
-            .build();
+            );
+    }
 
-        task_runner::run(window.upcast_ref(), commands, "Steam AiO Installation");
-    });
+    let mut packages = vec!["-S", "--needed"];
+    packages.extend_from_slice(STEAM_PACKAGES);
+    packages.extend_from_slice(vulkan);
+    packages.push("vulkan-tools");
+
+    commands
+        .phase(&gettext("Installation"))
+        .then(
+            Command::builder()
+                .aur()
+                .args(&packages)
+                .description(&gettext("Installing Steam and gaming dependencies..."))
+                .build(),
+        )
+        .phase(&gettext("Verification"))
+        .then(
+            Command::builder()
+                .normal()
+                .program("vulkaninfo")
+                .args(&["--summary"])
+                .description(&gettext("Checking that Vulkan works..."))
+                .build(),
+        )
+        .build()
+}
+
+fn flatpak_steam_commands() -> CommandSequence {
+    CommandSequence::new()
+        .then(
+            Command::builder()
+                .normal()
+                .program("flatpak")
+                .args(&["install", "-y", "flathub", "com.valvesoftware.Steam"])
+                .description(&gettext("Installing Steam from Flathub..."))
+                .build(),
+        )
+        .build()
 }
 
-fn setup_lact_oc(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_lact_oc");
+fn setup_lact_oc(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_lact_oc")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
@@ -123,8 +358,8 @@ fn setup_lact_oc(builder: &Builder, window: &ApplicationWindow) {
             .then(
                 Command::builder()
                     .aur()
-                    .args(&["-S", "--noconfirm", "--needed", "lact"])
-                    .description("Installing LACT GPU control utility...")
+                    .args(&["-S", "--needed", "lact"])
+                    .description(&gettext("Installing LACT GPU control utility..."))
                     .build(),
             )
             .then(
@@ -132,17 +367,18 @@ fn setup_lact_oc(builder: &Builder, window: &ApplicationWindow) {
                     .privileged()
                     .program("systemctl")
                     .args(&["enable", "--now", "lactd"])
-                    .description("Enabling LACT background service...")
+                    .description(&gettext("Enabling LACT background service..."))
                     .build(),
             )
             .build();
 
-        task_runner::run(window.upcast_ref(), commands, "LACT GPU Tools");
+        task_runner::run(window.upcast_ref(), commands, &gettext("LACT GPU Tools"));
     });
+    Ok(())
 }
 
-fn setup_lutris(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_lutris");
+fn setup_lutris(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_lutris")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
@@ -156,21 +392,27 @@ fn setup_lutris(builder: &Builder, window: &ApplicationWindow) {
                     .args(&[
                         "install",
                         "-y",
+                        "flathub",
                         "net.lutris.Lutris",
                         "org.freedesktop.Platform.VulkanLayer.gamescope/x86_64/25.08",
                         "org.freedesktop.Platform.VulkanLayer.MangoHud/x86_64/25.08",
                     ])
-                    .description("Installing Lutris and Vulkan layers...")
+                    .description(&gettext("Installing Lutris and Vulkan layers..."))
                     .build(),
             )
             .build();
 
-        task_runner::run(window.upcast_ref(), commands, "Lutris Installation");
+        task_runner::run(
+            window.upcast_ref(),
+            commands,
+            &gettext("Lutris Installation"),
+        );
     });
+    Ok(())
 }
 
-fn setup_heroic(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_heroic");
+fn setup_heroic(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_heroic")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
@@ -184,11 +426,12 @@ fn setup_heroic(builder: &Builder, window: &ApplicationWindow) {
                     .args(&[
                         "install",
                         "-y",
+                        "flathub",
                         "com.heroicgameslauncher.hgl",
                         "org.freedesktop.Platform.VulkanLayer.gamescope/x86_64/25.08",
                         "org.freedesktop.Platform.VulkanLayer.MangoHud/x86_64/25.08",
                     ])
-                    .description("Installing Heroic Games Launcher...")
+                    .description(&gettext("Installing Heroic Games Launcher..."))
                     .build(),
             )
             .build();
@@ -196,13 +439,14 @@ fn setup_heroic(builder: &Builder, window: &ApplicationWindow) {
         task_runner::run(
             window.upcast_ref(),
             commands,
-            "Heroic Launcher Installation",
+            &gettext("Heroic Launcher Installation"),
         );
     });
+    Ok(())
 }
 
-fn setup_bottles(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_bottles");
+fn setup_bottles(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_bottles")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
@@ -216,55 +460,190 @@ fn setup_bottles(builder: &Builder, window: &ApplicationWindow) {
                     .args(&[
                         "install",
                         "-y",
+                        "flathub",
                         "com.usebottles.bottles",
                         "org.freedesktop.Platform.VulkanLayer.gamescope/x86_64/25.08",
                         "org.freedesktop.Platform.VulkanLayer.MangoHud/x86_64/25.08",
                     ])
-                    .description("Installing Bottles and Vulkan layers...")
+                    .description(&gettext("Installing Bottles and Vulkan layers..."))
                     .build(),
             )
             .build();
 
-        task_runner::run(window.upcast_ref(), commands, "Bottles Installation");
+        task_runner::run(
+            window.upcast_ref(),
+            commands,
+            &gettext("Bottles Installation"),
+        );
     });
+    Ok(())
 }
 
-fn setup_controller(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_controller");
+/// udev rules giving the logged-in user hidraw access to DualShock 4 and
+/// DualSense controllers, over USB and Bluetooth.
+const PLAYSTATION_RULES_PATH: &str = "/etc/udev/rules.d/70-playstation-controllers.rules";
+const PLAYSTATION_RULES: &str = r#"# DualShock 4 and DualSense hidraw access for Steam Input and dualsensectl
+KERNEL=="hidraw*", ATTRS{idVendor}=="054c", ATTRS{idProduct}=="05c4|09cc|0ba0|0ce6|0df2", MODE="0660", TAG+="uaccess"
+KERNEL=="hidraw*", KERNELS=="*054C:05C4*|*054C:09CC*|*054C:0CE6*|*054C:0DF2*", MODE="0660", TAG+="uaccess"
+"#;
+
+fn setup_controller(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_controller")?;
     let window = window.clone();
 
-    button.connect_clicked(move |_| {
+    connect_clicked_once_until_done(&button, move |_, token| {
         info!("Controller Tools button clicked");
 
-        let commands = CommandSequence::new()
-            .then(
-                Command::builder()
-                    .aur()
-                    .args(&[
-                        "-S",
-                        "--noconfirm",
-                        "--needed",
-                        "gamepad-tool-bin",
-                        "sc-controller",
-                        "xone-dkms-git",
-                        "dualsensectl-git",
-                        "xone-dongle-firmware",
-                    ])
-                    .description("Installing controller tools and drivers...")
-                    .build(),
-            )
-            .build();
+        let xpadneo_installed = core::is_package_installed("xpadneo-dkms")
+            || core::is_package_installed("xpadneo-dkms-git");
+        let xone_installed = core::is_package_installed("xone-dkms-git");
+        let playstation_installed = Path::new(PLAYSTATION_RULES_PATH).exists();
+        let joycond_installed =
+            core::is_package_installed("joycond") || core::is_package_installed("joycond-git");
+        let steam_input_installed = core::is_package_installed("game-devices-udev");
+        let tools_installed = core::is_package_installed("gamepad-tool-bin")
+            && core::is_package_installed("sc-controller");
 
-        task_runner::run(
-            window.upcast_ref(),
-            commands,
-            "Controller Tools Installation",
-        );
+        let config = SelectionDialogConfig::new(
+            &gettext("Controller Support"),
+            gettext("Select the controllers to set up."),
+        )
+        .add_option(SelectionOption::new(
+            "xbox",
+            &gettext("Xbox Wireless"),
+            gettext("xpadneo driver for Xbox controllers over Bluetooth, built with DKMS"),
+            xpadneo_installed,
+        ))
+        .add_option(SelectionOption::new(
+            "xbox_adapter",
+            &gettext("Xbox Wireless Adapter"),
+            gettext("xone driver and firmware for the USB adapter, built with DKMS"),
+            xone_installed,
+        ))
+        .add_option(SelectionOption::new(
+            "playstation",
+            &gettext("PlayStation"),
+            gettext(
+                "udev rules and dualsensectl for DualShock 4 and DualSense. The kernel's \
+                 hid-playstation driver already handles them, so ds4drv is not needed.",
+            ),
+            playstation_installed,
+        ))
+        .add_option(SelectionOption::new(
+            "nintendo",
+            &gettext("Nintendo"),
+            gettext("joycond, pairing Joy-Cons into one controller"),
+            joycond_installed,
+        ))
+        .add_option(SelectionOption::new(
+            "steam_input",
+            &gettext("Steam Input udev rules"),
+            gettext("Access to generic and third-party controllers for Steam"),
+            steam_input_installed,
+        ))
+        .add_option(SelectionOption::new(
+            "tools",
+            &gettext("Controller tools"),
+            gettext("Gamepad Tool and SC Controller for testing and remapping"),
+            tools_installed,
+        ))
+        .confirm_label(&gettext("Install"));
+
+        let window_clone = window.clone();
+        let dialog = show_selection_dialog(window.upcast_ref(), config, move |selected| {
+            task_runner::run(
+                window_clone.upcast_ref(),
+                controller_commands(&selected),
+                &gettext("Controller Support Setup"),
+            );
+        });
+        token.release_on_close(&dialog);
     });
+    Ok(())
 }
 
-fn setup_falcond(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_falcond");
+/// Build the controller setup for the selected options, checking that the
+/// DKMS drivers were built for the running kernel at the end.
+fn controller_commands(selected: &[String]) -> CommandSequence {
+    let picked = |id: &str| selected.iter().any(|s| s == id);
+    // (option, packages, DKMS module)
+    let drivers: [(&str, &[&str], Option<&str>); 5] = [
+        ("xbox", &["xpadneo-dkms"], Some("hid-xpadneo")),
+        (
+            "xbox_adapter",
+            &["xone-dkms-git", "xone-dongle-firmware"],
+            Some("xone"),
+        ),
+        ("playstation", &["dualsensectl-git"], None),
+        ("nintendo", &["joycond-git"], None),
+        ("steam_input", &["game-devices-udev"], None),
+    ];
+    let drivers = drivers.iter().filter(|(id, _, _)| picked(id));
+
+    let mut packages = vec!["-S", "--needed"];
+    let mut modules = Vec::new();
+    for (_, names, module) in drivers {
+        packages.extend_from_slice(names);
+        modules.extend(*module);
+    }
+    if picked("tools") {
+        packages.extend(["gamepad-tool-bin", "sc-controller"]);
+    }
+
+    // DKMS builds against the headers of the running kernel
+    let headers = (!modules.is_empty())
+        .then(|| dkms::headers_package(&dkms::running_kernel()))
+        .flatten();
+    if let Some(headers) = &headers {
+        packages.push(headers.as_str());
+    }
+
+    let mut commands = CommandSequence::new().then(
+        Command::builder()
+            .aur()
+            .args(&packages)
+            .description(&gettext("Installing controller drivers and tools..."))
+            .build(),
+    );
+
+    if picked("playstation") {
+        commands = commands.then(
+            privileged_fs::write_file(PLAYSTATION_RULES_PATH, PLAYSTATION_RULES)
+                .command(&gettext("Adding PlayStation controller rules...")),
+        );
+    }
+    if picked("nintendo") {
+        commands = commands.then(
+            Command::builder()
+                .privileged()
+                .program("systemctl")
+                .args(&["enable", "--now", "joycond"])
+                .description(&gettext("Enabling joycond..."))
+                .build(),
+        );
+    }
+
+    commands = commands.then(
+        Command::script(
+            &gettext("Reloading udev rules..."),
+            "udevadm control --reload && udevadm trigger",
+        )
+        .privileged()
+        .build(),
+    );
+
+    for module in modules {
+        commands = commands.then(dkms::verify_command(
+            module,
+            &i18n::fill(&gettext("Checking that {} is built..."), &[module]),
+        ));
+    }
+
+    commands.build()
+}
+
+fn setup_falcond(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_falcond")?;
     let window = window.clone();
 
     let env = crate::config::env::get();
@@ -279,13 +658,12 @@ fn setup_falcond(builder: &Builder, window: &ApplicationWindow) {
                     .aur()
                     .args(&[
                         "-S",
-                        "--noconfirm",
                         "--needed",
                         "falcond",
                         "falcond-gui",
                         "falcond-profiles",
                     ])
-                    .description("Installing Falcond Gaming utility...")
+                    .description(&gettext("Installing Falcond Gaming utility..."))
                     .build(),
             )
             .then(
@@ -293,7 +671,7 @@ fn setup_falcond(builder: &Builder, window: &ApplicationWindow) {
                     .privileged()
                     .program("groupadd")
                     .args(&["-f", "falcond"])
-                    .description("Ensuring falcond group exists...")
+                    .description(&gettext("Ensuring falcond group exists..."))
                     .build(),
             )
             .then(
@@ -301,7 +679,7 @@ fn setup_falcond(builder: &Builder, window: &ApplicationWindow) {
                     .privileged()
                     .program("usermod")
                     .args(&["-aG", "falcond", &user])
-                    .description("Adding your user to falcond group...")
+                    .description(&gettext("Adding your user to falcond group..."))
                     .build(),
             )
             .then(
@@ -309,7 +687,7 @@ fn setup_falcond(builder: &Builder, window: &ApplicationWindow) {
                     .privileged()
                     .program("mkdir")
                     .args(&["-p", "/usr/share/falcond/profiles/user"])
-                    .description("Creating necessary user directory...")
+                    .description(&gettext("Creating necessary user directory..."))
                     .build(),
             )
             .then(
@@ -317,7 +695,7 @@ fn setup_falcond(builder: &Builder, window: &ApplicationWindow) {
                     .privileged()
                     .program("chown")
                     .args(&[":falcond", "/usr/share/falcond/profiles/user"])
-                    .description("Adding propper ownership permissions...")
+                    .description(&gettext("Adding propper ownership permissions..."))
                     .build(),
             )
             .then(
@@ -325,7 +703,7 @@ fn setup_falcond(builder: &Builder, window: &ApplicationWindow) {
                     .privileged()
                     .program("chmod")
                     .args(&["2775", "/usr/share/falcond/profiles/user"])
-                    .description("Adding propper executable permissions...")
+                    .description(&gettext("Adding propper executable permissions..."))
                     .build(),
             )
             .then(
@@ -333,11 +711,372 @@ fn setup_falcond(builder: &Builder, window: &ApplicationWindow) {
                     .privileged()
                     .program("systemctl")
                     .args(&["enable", "--now", "falcond"])
-                    .description("Enabling falcond background service...")
+                    .description(&gettext("Enabling falcond background service..."))
                     .build(),
             )
             .build();
 
-        task_runner::run(window.upcast_ref(), commands, "Falcond Installation");
+        task_runner::run(
+            window.upcast_ref(),
+            commands,
+            &gettext("Falcond Installation"),
+        );
+    });
+    Ok(())
+}
+
+fn setup_performance_tools(
+    builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_performance_tools")?;
+    let window = window.clone();
+
+    connect_clicked_once_until_done(&button, move |_, token| {
+        info!("Performance tools button clicked");
+
+        let config = SelectionDialogConfig::new(
+            &gettext("Performance Tools"),
+            gettext("Installs gamemode and MangoHud, adds you to the gamemode group and deploys a default MangoHud config if you have none."),
+        )
+        .selection_required(false)
+        .add_option(SelectionOption::new(
+            "lib32",
+            &gettext("32-bit libraries"),
+            gettext("lib32-gamemode and lib32-mangohud, needed by Steam and most Proton games"),
+            false,
+        ))
+        .confirm_label(&gettext("Install"));
+
+        let window_clone = window.clone();
+        let dialog = show_selection_dialog(window.upcast_ref(), config, move |selected| {
+            let user = crate::config::env::get().user.clone();
+
+            let mut packages = vec!["-S", "--needed", "gamemode", "mangohud"];
+            if selected.iter().any(|id| id == "lib32") {
+                packages.extend(["lib32-gamemode", "lib32-mangohud"]);
+            }
+
+            let mangohud_conf = core::paths::get()
+                .host_config
+                .join("MangoHud/MangoHud.conf");
+            let existing_conf = mangohud_conf.clone();
+
+            let commands = CommandSequence::new()
+                .then(
+                    Command::builder()
+                        .aur()
+                        .args(&packages)
+                        .description(&gettext("Installing gamemode and MangoHud..."))
+                        .build(),
+                )
+                .then(
+                    Command::write_file(
+                        &gettext("Writing the default MangoHud config..."),
+                        &mangohud_conf.to_string_lossy(),
+                    )
+                    .contents_with(|| Ok(MANGOHUD_DEFAULT_CONFIG.to_string()))
+                    .user_owned()
+                    // Leave the user's own config alone
+                    .run_if(move || !existing_conf.exists())
+                    .build(),
+                )
+                .then(
+                    Command::builder()
+                        .privileged()
+                        .program("groupadd")
+                        .args(&["-f", "gamemode"])
+                        .description(&gettext("Ensuring gamemode group exists..."))
+                        .build(),
+                )
+                .then(
+                    Command::builder()
+                        .privileged()
+                        .program("usermod")
+                        .args(&["-aG", "gamemode", &user])
+                        .description(&gettext("Adding your user to gamemode group..."))
+                        .build(),
+                )
+                .then(
+                    Command::builder()
+                        .normal()
+                        .program("gamemoded")
+                        .args(&["-t"])
+                        .description(&gettext("Verifying gamemode daemon responds..."))
+                        .build(),
+                )
+                .build();
+
+            task_runner::run(
+                window_clone.upcast_ref(),
+                commands,
+                &gettext("Performance Tools Installation"),
+            );
+        });
+        token.release_on_close(&dialog);
+    });
+    Ok(())
+}
+
+fn setup_proton_ge(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_proton_ge")?;
+    let window = window.clone();
+
+    button.connect_clicked(move |button| {
+        info!("Proton-GE button clicked");
+        button.set_sensitive(false);
+
+        let (tx, rx) = mpsc::channel::<Result<Vec<ProtonRelease>, String>>();
+
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            let result = runtime.block_on(proton::fetch_releases());
+            let _ = tx.send(result.map_err(|e| e.to_string()));
+        });
+
+        let window = window.clone();
+        let button = button.clone();
+        glib::timeout_add_local(Duration::from_millis(50), move || match rx.try_recv() {
+            Ok(result) => {
+                button.set_sensitive(true);
+                match result {
+                    Ok(releases) => show_proton_ge_picker(&window, releases),
+                    Err(e) => {
+                        error!("Failed to fetch Proton-GE releases: {}", e);
+                        show_error(
+                            &window,
+                            &i18n::fill(&gettext("Failed to fetch Proton-GE releases:\n{}"), &[&e]),
+                        );
+                    }
+                }
+                glib::ControlFlow::Break
+            }
+            Err(TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(TryRecvError::Disconnected) => {
+                button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+        });
+    });
+    Ok(())
+}
+
+fn show_proton_ge_picker(window: &ApplicationWindow, releases: Vec<ProtonRelease>) {
+    let compat_dir = proton_compat_dir();
+    let installed = proton::list_installed(&compat_dir);
+
+    let mut config = SelectionDialogConfig::new(
+        &gettext("Proton-GE"),
+        i18n::fill(
+            &gettext(
+                "Select a Proton-GE version to install into {}. Restart Steam afterwards to use it.",
+            ),
+            &[&compat_dir.to_string_lossy()],
+        ),
+    )
+    .selection_type(SelectionType::Single)
+    .confirm_label(&gettext("Install"));
+
+    let releases: Vec<ProtonRelease> = releases.into_iter().take(PROTON_GE_RELEASE_LIMIT).collect();
+
+    for release in &releases {
+        let is_installed = installed.contains(&release.tag);
+        let description = if is_installed {
+            i18n::fill(&gettext("{} · Installed"), &[&format_bytes(release.size)])
+        } else {
+            format_bytes(release.size)
+        };
+        config = config.add_option(SelectionOption::new(
+            &release.tag,
+            &release.tag,
+            &description,
+            is_installed,
+        ));
+    }
+
+    let window_clone = window.clone();
+    show_selection_dialog(window.upcast_ref(), config, move |selected| {
+        let Some(release) = selected
+            .first()
+            .and_then(|tag| releases.iter().find(|r| &r.tag == tag))
+        else {
+            return;
+        };
+        download_proton_ge(&window_clone, release.clone(), compat_dir.clone());
+    });
+}
+
+fn download_proton_ge(window: &ApplicationWindow, release: ProtonRelease, compat_dir: PathBuf) {
+    info!("Downloading Proton-GE {}", release.tag);
+
+    let tarball = std::env::temp_dir().join(&release.tarball_name);
+    let tarball_path = tarball.to_string_lossy().to_string();
+
+    let window_clone = window.clone();
+    start_download(
+        window.upcast_ref(),
+        release.tarball_name.clone(),
+        release.tarball_url.clone(),
+        tarball_path,
+        Some(Box::new(move || {
+            verify_and_install_proton_ge(&window_clone, release, tarball, compat_dir);
+        })),
+    );
+}
+
+fn verify_and_install_proton_ge(
+    window: &ApplicationWindow,
+    release: ProtonRelease,
+    tarball: PathBuf,
+    compat_dir: PathBuf,
+) {
+    let (tx, rx) = mpsc::channel::<Result<(), String>>();
+
+    let verify_release = release.clone();
+    let verify_tarball = tarball.clone();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = runtime
+            .block_on(proton::fetch_checksum(&verify_release))
+            .and_then(|expected| proton::verify_sha512(&verify_tarball, &expected));
+        let _ = tx.send(result.map_err(|e| e.to_string()));
+    });
+
+    let window = window.clone();
+    glib::timeout_add_local(Duration::from_millis(50), move || match rx.try_recv() {
+        Ok(Ok(())) => {
+            info!("Checksum verified for {}", release.tarball_name);
+            run_proton_ge_install(&window, &release, &tarball, &compat_dir);
+            glib::ControlFlow::Break
+        }
+        Ok(Err(e)) => {
+            error!("Proton-GE verification failed: {}", e);
+            let _ = std::fs::remove_file(&tarball);
+            show_error(
+                &window,
+                &i18n::fill(
+                    &gettext("Proton-GE verification failed:\n{}"),
+                    &[&e.to_string()],
+                ),
+            );
+            glib::ControlFlow::Break
+        }
+        Err(TryRecvError::Empty) => glib::ControlFlow::Continue,
+        Err(TryRecvError::Disconnected) => glib::ControlFlow::Break,
+    });
+}
+
+fn run_proton_ge_install(
+    window: &ApplicationWindow,
+    release: &ProtonRelease,
+    tarball: &Path,
+    compat_dir: &Path,
+) {
+    let tarball = tarball.to_string_lossy().to_string();
+    let compat_dir = compat_dir.to_string_lossy().to_string();
+
+    let commands = CommandSequence::new()
+        .then(
+            Command::builder()
+                .normal()
+                .program("mkdir")
+                .args(&["-p", &compat_dir])
+                .description(&gettext("Creating compatibility tools directory..."))
+                .build(),
+        )
+        .then(
+            Command::builder()
+                .normal()
+                .program("tar")
+                .args(&["-xf", &tarball, "-C", &compat_dir])
+                .description(&i18n::fill(&gettext("Extracting {}..."), &[&release.tag]))
+                .build(),
+        )
+        .then(
+            Command::builder()
+                .normal()
+                .program("rm")
+                .args(&["-f", &tarball])
+                .description(&gettext("Removing downloaded archive..."))
+                .build(),
+        )
+        .build();
+
+    task_runner::run(
+        window.upcast_ref(),
+        commands,
+        &gettext("Proton-GE Installation"),
+    );
+}
+
+fn setup_proton_ge_manage(
+    builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_proton_ge_manage")?;
+    let window = window.clone();
+
+    connect_clicked_once_until_done(&button, move |_, token| {
+        info!("Manage Proton-GE button clicked");
+
+        let compat_dir = proton_compat_dir();
+        let installed = proton::list_installed(&compat_dir);
+
+        if installed.is_empty() {
+            show_error(
+                &window,
+                &i18n::fill(
+                    &gettext("No Proton-GE versions found in {}."),
+                    &[&compat_dir.to_string_lossy()],
+                ),
+            );
+            return;
+        }
+
+        let mut config = SelectionDialogConfig::new(
+            &gettext("Manage Proton-GE"),
+            gettext("Select the Proton-GE versions to remove."),
+        )
+        .confirm_label(&gettext("Remove"));
+
+        for name in &installed {
+            config = config.add_option(SelectionOption::new(
+                name,
+                name,
+                compat_dir.join(name).to_string_lossy().as_ref(),
+                false,
+            ));
+        }
+
+        let window_clone = window.clone();
+        let dialog = show_selection_dialog(window.upcast_ref(), config, move |selected| {
+            let mut commands = CommandSequence::new();
+            for name in &selected {
+                let path = compat_dir.join(name).to_string_lossy().to_string();
+                commands = commands.then(
+                    Command::builder()
+                        .normal()
+                        .program("rm")
+                        .args(&["-rf", &path])
+                        .description(&i18n::fill(&gettext("Removing {}..."), &[name]))
+                        .build(),
+                );
+            }
+
+            if !commands.is_empty() {
+                task_runner::run(
+                    window_clone.upcast_ref(),
+                    commands.build(),
+                    &gettext("Remove Proton-GE"),
+                );
+            }
+        });
+        token.release_on_close(&dialog);
     });
+    Ok(())
+}
+
+/// Compatibility tools directory for the current user's Steam install.
+fn proton_compat_dir() -> PathBuf {
+    proton::compat_tools_dir(&core::paths::get().home)
 }
@@ -4,20 +4,34 @@
 //! - Linux kernel installation and removal
 //! - Kernel headers management
 //! - Kernel listing and status
+//! - Repository details of the selected kernel
 
+use crate::core;
+use crate::i18n::{self, gettext, ngettext};
 use crate::ui::dialogs::warning::show_warning_confirmation;
+use crate::ui::markup::Markup;
 use crate::ui::task_runner::{self, Command, CommandSequence};
-use crate::ui::utils::extract_widget;
+use crate::ui::utils::{expect_widget, extract_widget, WidgetError};
+use adw::prelude::*;
 use gtk4::glib;
-use gtk4::prelude::*;
-use gtk4::{ApplicationWindow, Box as GtkBox, Builder, Button, Image, Label, ListBox, Orientation};
-use log::{info, warn};
-use std::process::{Command as StdCommand, Stdio};
+use gtk4::{
+    ApplicationWindow, Box as GtkBox, Builder, Button, Image, Label, ListBox, ListBoxRow,
+    Orientation,
+};
+use log::{debug, info, warn};
+use std::cell::RefCell;
+use std::rc::Rc;
 
 /// Set up all button handlers for the kernel manager page.
-pub fn setup_handlers(page_builder: &Builder, _main_builder: &Builder, window: &ApplicationWindow) {
+pub fn setup_handlers(
+    page_builder: &Builder,
+    _main_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
     setup_kernel_lists(page_builder, window);
-    setup_refresh_button(page_builder, window);
+    setup_refresh_button(page_builder, window)?;
+    setup_kernel_details(page_builder)?;
+    Ok(())
 }
 
 /// Initialize and populate kernel lists.
@@ -26,8 +40,8 @@ fn setup_kernel_lists(builder: &Builder, window: &ApplicationWindow) {
 }
 
 /// Set up refresh button to rescan kernels.
-fn setup_refresh_button(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_refresh_kernels");
+fn setup_refresh_button(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_refresh_kernels")?;
     let window = window.clone();
     let builder = builder.clone();
 
@@ -35,6 +49,137 @@ fn setup_refresh_button(builder: &Builder, window: &ApplicationWindow) {
         info!("Refresh kernels button clicked");
         scan_and_populate_kernels(&builder, &window, Some(btn));
     });
+    Ok(())
+}
+
+/// Show the repository details of the kernel selected in the available list.
+fn setup_kernel_details(builder: &Builder) -> Result<(), WidgetError> {
+    let list = extract_widget::<ListBox>(builder, "available_kernels_list")?;
+    let url_row = extract_widget::<adw::ActionRow>(builder, "kernel_url_row")?;
+
+    url_row.connect_activated(|row| {
+        if let Some(url) = row.subtitle().filter(|url| !url.is_empty()) {
+            if let Err(e) = core::package::open_url(&url) {
+                warn!("Failed to open {}: {}", url, e);
+            }
+        }
+    });
+
+    // Queries can finish after the selection moved on; only the current
+    // kernel's details are shown
+    let selected: Rc<RefCell<Option<String>>> = Rc::default();
+    let builder = builder.clone();
+    list.connect_row_selected(move |_, row| {
+        let kernel = row.and_then(row_kernel);
+        selected.replace(kernel.clone());
+        let Some(kernel) = kernel else {
+            expect_widget::<adw::PreferencesGroup>(&builder, "kernel_details_group")
+                .set_visible(false);
+            return;
+        };
+
+        if let Some(details) = core::package::cached_package_details(&kernel) {
+            show_kernel_details(&builder, &kernel, Some(&details));
+            return;
+        }
+        show_kernel_details_loading(&builder, &kernel);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let query = kernel.clone();
+        std::thread::spawn(move || {
+            let _ = sender.send(core::package::package_details(&query));
+        });
+
+        let builder = builder.clone();
+        let selected = selected.clone();
+        glib::timeout_add_local(
+            std::time::Duration::from_millis(100),
+            move || match receiver.try_recv() {
+                Ok(details) => {
+                    if selected.borrow().as_deref() == Some(kernel.as_str()) {
+                        show_kernel_details(&builder, &kernel, details.as_ref());
+                    } else {
+                        debug!("Dropping details of {}, no longer selected", kernel);
+                    }
+                    glib::ControlFlow::Break
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => glib::ControlFlow::Break,
+            },
+        );
+    });
+    Ok(())
+}
+
+/// Kernel name of an available list row; `None` for the placeholder row.
+fn row_kernel(row: &ListBoxRow) -> Option<String> {
+    let label = row
+        .child()
+        .and_downcast::<GtkBox>()?
+        .first_child()
+        .and_downcast::<Label>()?;
+    Some(label.text().to_string())
+}
+
+/// Rows of the details panel, with the value each one shows.
+fn kernel_detail_rows(builder: &Builder) -> [adw::ActionRow; 6] {
+    [
+        "kernel_description_row",
+        "kernel_url_row",
+        "kernel_packager_row",
+        "kernel_build_date_row",
+        "kernel_download_size_row",
+        "kernel_installed_size_row",
+    ]
+    .map(|id| expect_widget::<adw::ActionRow>(builder, id))
+}
+
+fn show_kernel_details_loading(builder: &Builder, kernel: &str) {
+    let group = expect_widget::<adw::PreferencesGroup>(builder, "kernel_details_group");
+    group.set_description(Some(&i18n::fill(
+        &gettext("Loading details of {}…"),
+        &[kernel],
+    )));
+    group.set_visible(true);
+    for row in kernel_detail_rows(builder) {
+        row.set_visible(false);
+    }
+}
+
+/// Fill the details panel, or say none were found when `details` is `None`.
+fn show_kernel_details(
+    builder: &Builder,
+    kernel: &str,
+    details: Option<&core::package::PkgDetails>,
+) {
+    let group = expect_widget::<adw::PreferencesGroup>(builder, "kernel_details_group");
+    group.set_visible(true);
+    let rows = kernel_detail_rows(builder);
+
+    let Some(details) = details else {
+        group.set_description(Some(&i18n::fill(
+            &gettext("No details found for {}"),
+            &[kernel],
+        )));
+        for row in rows {
+            row.set_visible(false);
+        }
+        return;
+    };
+
+    group.set_description(Some(&format!("{} {}", details.name, details.version)));
+    let values = [
+        details.description.as_str(),
+        details.url.as_deref().unwrap_or_default(),
+        details.packager.as_str(),
+        details.build_date.as_str(),
+        details.download_size.as_str(),
+        details.installed_size.as_str(),
+    ];
+    for (row, value) in rows.iter().zip(values) {
+        row.set_subtitle(value);
+        row.set_visible(!value.is_empty());
+    }
 }
 
 /// Scan for available and installed kernels and populate lists.
@@ -50,7 +195,7 @@ fn scan_and_populate_kernels(
     let btn_opt = refresh_btn.cloned();
 
     // Disable content while scanning
-    let content_box = extract_widget::<GtkBox>(&builder, "content_box");
+    let content_box = expect_widget::<GtkBox>(&builder, "content_box");
     content_box.set_sensitive(false);
 
     if let Some(btn) = refresh_btn {
@@ -73,7 +218,7 @@ fn scan_and_populate_kernels(
     // Run blocking operations in a separate thread
     std::thread::spawn(move || {
         let available_result = get_available_kernels();
-        let installed_result = get_installed_kernels();
+        let installed_kernels = get_installed_kernels();
 
         let available_kernels = match available_result {
             Ok(kernels) => kernels,
@@ -83,14 +228,6 @@ fn scan_and_populate_kernels(
             }
         };
 
-        let installed_kernels = match installed_result {
-            Ok(kernels) => kernels,
-            Err(e) => {
-                warn!("Failed to get installed kernels: {}", e);
-                Vec::new()
-            }
-        };
-
         info!(
             "Found {} available kernels, {} installed",
             available_kernels.len(),
@@ -111,7 +248,7 @@ fn scan_and_populate_kernels(
                 update_status_labels(&builder, &available_kernels, &installed_kernels);
 
                 // Re-enable content
-                let content_box = extract_widget::<GtkBox>(&builder, "content_box");
+                let content_box = expect_widget::<GtkBox>(&builder, "content_box");
                 content_box.set_sensitive(true);
 
                 // Restore button state
@@ -134,7 +271,7 @@ fn scan_and_populate_kernels(
             Err(std::sync::mpsc::TryRecvError::Disconnected) => {
                 warn!("Kernel scan thread disconnected");
                 // Re-enable content even on failure
-                let content_box = extract_widget::<GtkBox>(&builder, "content_box");
+                let content_box = expect_widget::<GtkBox>(&builder, "content_box");
                 content_box.set_sensitive(true);
                 if let Some(btn) = &btn_opt {
                     btn.set_sensitive(true);
@@ -159,17 +296,28 @@ fn scan_and_populate_kernels(
 /// Adapted from cachyos-kernel-manager logic.
 fn get_available_kernels() -> anyhow::Result<Vec<String>> {
     // Get all packages in one call
-    let output = StdCommand::new("pacman")
-        .args(["-Sl"])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()?;
-
-    if !output.status.success() {
-        return Err(anyhow::anyhow!("pacman -Sl failed"));
+    let output = core::exec::capture(
+        "pacman",
+        &["-Sl"],
+        core::exec::Options {
+            timeout: std::time::Duration::from_secs(30),
+            // Every package of every repository
+            max_bytes: 64 * 1024 * 1024,
+            merge_stderr: false,
+        },
+    )?;
+
+    if !output.success() {
+        return Err(anyhow::anyhow!(
+            "pacman -Sl failed: {}",
+            output.stderr.trim()
+        ));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
+    if output.truncated {
+        warn!("pacman -Sl printed more than expected, some kernels may be missing");
+    }
+    let stdout = output.stdout;
 
     // First pass: collect all available packages
     let mut all_packages = std::collections::HashSet::new();
@@ -221,59 +369,25 @@ fn get_available_kernels() -> anyhow::Result<Vec<String>> {
 
 /// Get list of installed kernel packages.
 /// Only returns kernels that have both the kernel and headers installed.
-fn get_installed_kernels() -> anyhow::Result<Vec<String>> {
-    let output = StdCommand::new("pacman")
-        .args(["-Q"])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()?;
-
-    if !output.status.success() {
-        return Err(anyhow::anyhow!("pacman -Q failed"));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut installed_headers = Vec::new();
-    let mut all_packages = Vec::new();
-
-    // First pass: collect all packages and identify headers
-    for line in stdout.lines() {
-        if line.trim().is_empty() {
-            continue;
-        }
-
-        let pkg_name = line.split_whitespace().next().unwrap_or("");
-        all_packages.push(pkg_name.to_string());
-
-        // Find kernel headers
-        if pkg_name.starts_with("linux")
-            && pkg_name.ends_with("-headers")
-            && pkg_name != "linux-api-headers"
-        {
-            installed_headers.push(pkg_name.to_string());
-        }
-    }
-
-    let mut kernels = Vec::new();
-
-    // Second pass: for each headers package, check if the kernel is also installed
-    for headers_pkg in installed_headers {
-        if let Some(kernel_name) = headers_pkg.strip_suffix("-headers") {
-            // Check if the corresponding kernel package is installed
-            if all_packages.contains(&kernel_name.to_string()) {
-                kernels.push(kernel_name.to_string());
-            }
-        }
-    }
+fn get_installed_kernels() -> Vec<String> {
+    let all_packages = core::package::installed_set();
+
+    // For each headers package, check if the kernel is also installed
+    let mut kernels: Vec<String> = all_packages
+        .iter()
+        .filter(|pkg_name| pkg_name.starts_with("linux") && *pkg_name != "linux-api-headers")
+        .filter_map(|pkg_name| pkg_name.strip_suffix("-headers"))
+        .filter(|kernel_name| all_packages.contains(*kernel_name))
+        .map(str::to_string)
+        .collect();
 
     kernels.sort();
-    kernels.dedup();
-    Ok(kernels)
+    kernels
 }
 
 /// Populate the installed kernels list.
 fn populate_installed_list(builder: &Builder, kernels: &[String], window: &ApplicationWindow) {
-    let list = extract_widget::<ListBox>(builder, "installed_kernels_list");
+    let list = expect_widget::<ListBox>(builder, "installed_kernels_list");
 
     // Clear existing items
     while let Some(row) = list.first_child() {
@@ -311,7 +425,7 @@ fn populate_installed_list(builder: &Builder, kernels: &[String], window: &Appli
     }
 
     if kernels.is_empty() {
-        let label = Label::new(Some("No kernels installed"));
+        let label = Label::new(Some(&gettext("No kernels installed")));
         label.add_css_class("dim-label");
         label.set_margin_start(12);
         label.set_margin_end(12);
@@ -328,7 +442,7 @@ fn populate_available_list(
     installed: &[String],
     window: &ApplicationWindow,
 ) {
-    let list = extract_widget::<ListBox>(builder, "available_kernels_list");
+    let list = expect_widget::<ListBox>(builder, "available_kernels_list");
 
     // Clear existing items
     while let Some(row) = list.first_child() {
@@ -370,7 +484,7 @@ fn populate_available_list(
     }
 
     if added == 0 {
-        let label = Label::new(Some("All available kernels are installed"));
+        let label = Label::new(Some(&gettext("All available kernels are installed")));
         label.add_css_class("dim-label");
         label.set_margin_start(12);
         label.set_margin_end(12);
@@ -382,13 +496,20 @@ fn populate_available_list(
 
 /// Update status labels with kernel counts.
 fn update_status_labels(builder: &Builder, available: &[String], installed: &[String]) {
-    let installed_count = extract_widget::<Label>(builder, "installed_count_label");
-    let available_count = extract_widget::<Label>(builder, "available_count_label");
-
-    installed_count.set_text(&format!("{} installed", installed.len()));
-
-    let not_installed = available.iter().filter(|k| !installed.contains(k)).count();
-    available_count.set_text(&format!("{} available", not_installed));
+    let installed_count = expect_widget::<Label>(builder, "installed_count_label");
+    let available_count = expect_widget::<Label>(builder, "available_count_label");
+
+    let count = installed.len() as u32;
+    installed_count.set_text(&i18n::fill(
+        &ngettext("{} installed", "{} installed", count),
+        &[&count.to_string()],
+    ));
+
+    let not_installed = available.iter().filter(|k| !installed.contains(k)).count() as u32;
+    available_count.set_text(&i18n::fill(
+        &ngettext("{} available", "{} available", not_installed),
+        &[&not_installed.to_string()],
+    ));
 }
 
 /// Install a kernel with its headers.
@@ -400,12 +521,14 @@ fn install_kernel(kernel_name: &str, window: &ApplicationWindow, builder: &Build
 
     show_warning_confirmation(
         window.upcast_ref(),
-        "Confirm Installation",
-        &format!(
-            "Install <b>{}</b> and <b>{}</b>?\n\n\
-            This will download and install the kernel and its headers.",
-            kernel_name, headers
-        ),
+        &gettext("Confirm Installation"),
+        Markup::trusted(i18n::fill_markup(
+            &gettext(
+                "Install <b>{}</b> and <b>{}</b>?\n\n\
+                 This will download and install the kernel and its headers.",
+            ),
+            &[&kernel_name, &headers],
+        )),
         move || {
             info!("Installing {} and {}", kernel_name, headers);
 
@@ -413,14 +536,22 @@ fn install_kernel(kernel_name: &str, window: &ApplicationWindow, builder: &Build
                 .then(
                     Command::builder()
                         .aur()
-                        .args(&["-S", "--noconfirm", "--needed", &kernel_name, &headers])
-                        .description(&format!("Installing {} and {}...", kernel_name, headers))
+                        .args(&["-S", "--needed", &kernel_name, &headers])
+                        .description(&i18n::fill(
+                            &gettext("Installing {} and {}..."),
+                            &[&kernel_name, &headers],
+                        ))
                         .build(),
                 )
+                .long_running()
                 .build();
 
             // Run installation
-            task_runner::run(window_clone.upcast_ref(), commands, "Install Kernel");
+            task_runner::run(
+                window_clone.upcast_ref(),
+                commands,
+                &gettext("Install Kernel"),
+            );
 
             // Schedule refresh after dialog closes
             glib::timeout_add_seconds_local(2, move || {
@@ -444,14 +575,16 @@ fn remove_kernel(kernel_name: &str, window: &ApplicationWindow, builder: &Builde
 
     show_warning_confirmation(
         window.upcast_ref(),
-        "Confirm Removal",
-        &format!(
-            "Remove <b>{}</b> and <b>{}</b>?\n\n\
-            <span foreground=\"red\" weight=\"bold\">Warning:</span> \
-            This will uninstall the kernel and its headers.\n\
-            Make sure you have at least one other kernel installed.",
-            kernel_name, headers
-        ),
+        &gettext("Confirm Removal"),
+        Markup::trusted(i18n::fill_markup(
+            &gettext(
+                "Remove <b>{}</b> and <b>{}</b>?\n\n\
+                 <span foreground=\"red\" weight=\"bold\">Warning:</span> \
+                 This will uninstall the kernel and its headers.\n\
+                 Make sure you have at least one other kernel installed.",
+            ),
+            &[&kernel_name, &headers],
+        )),
         move || {
             info!("Removing {} and {}", kernel_name, headers);
 
@@ -459,14 +592,22 @@ fn remove_kernel(kernel_name: &str, window: &ApplicationWindow, builder: &Builde
                 .then(
                     Command::builder()
                         .aur()
-                        .args(&["-R", "--noconfirm", &kernel_name, &headers])
-                        .description(&format!("Removing {} and {}...", kernel_name, headers))
+                        .args(&["-R", &kernel_name, &headers])
+                        .description(&i18n::fill(
+                            &gettext("Removing {} and {}..."),
+                            &[&kernel_name, &headers],
+                        ))
                         .build(),
                 )
+                .long_running()
                 .build();
 
             // Run removal
-            task_runner::run(window_clone.upcast_ref(), commands, "Remove Kernel");
+            task_runner::run(
+                window_clone.upcast_ref(),
+                commands,
+                &gettext("Remove Kernel"),
+            );
 
             // Schedule refresh after dialog closes
             glib::timeout_add_seconds_local(2, move || {
@@ -7,16 +7,32 @@
 pub mod kernel_manager_tab;
 pub mod scheduler_tab;
 
+use crate::core::system_check::Capability;
+use crate::ui::utils::{require_capabilities, WidgetError};
 use gtk4::{ApplicationWindow, Builder};
 use log::info;
 
+/// What switching and stopping schedulers needs.
+const SCHEDULER_REQUIRED: &[Capability] = &[Capability::Services, Capability::Escalate];
+
 /// Set up all handlers for the kernel & schedulers page with subtabs.
-pub fn setup_handlers(page_builder: &Builder, main_builder: &Builder, window: &ApplicationWindow) {
+pub fn setup_handlers(
+    page_builder: &Builder,
+    main_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
     info!("Setting up Kernel & Schedulers page with subtabs");
 
     // Setup handlers for both subtabs
-    kernel_manager_tab::setup_handlers(page_builder, main_builder, window);
-    scheduler_tab::setup_handlers(page_builder, main_builder, window);
+    kernel_manager_tab::setup_handlers(page_builder, main_builder, window)?;
+    scheduler_tab::setup_handlers(page_builder, main_builder, window)?;
 
     info!("Kernel & Schedulers page handlers initialized");
+
+    require_capabilities(
+        page_builder,
+        SCHEDULER_REQUIRED,
+        &["btn_switch_scheduler", "btn_stop_scheduler"],
+    )?;
+    Ok(())
 }
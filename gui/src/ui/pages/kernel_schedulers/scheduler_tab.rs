@@ -2,10 +2,15 @@
 //!
 //! Manages sched-ext BPF CPU schedulers via scxctl.
 
+use crate::core;
+use crate::core::{dkms, exec};
+use crate::i18n::{self, gettext};
 use crate::ui::dialogs::warning::show_warning_confirmation;
 use crate::ui::task_runner::{self, Command, CommandSequence};
+use crate::ui::toast;
 use crate::ui::utils::{
-    extract_widget, get_combo_row_value, is_service_enabled, path_exists, run_command,
+    builder_from_resource, expect_widget, extract_widget, get_combo_row_value, is_service_enabled,
+    path_exists, WidgetError,
 };
 use adw::prelude::*;
 use gtk4::glib;
@@ -16,6 +21,13 @@ use std::rc::Rc;
 
 const SCHED_EXT_PATH: &str = "/sys/kernel/sched_ext";
 
+/// Limits of scxctl queries, which hang while the scx_loader service is stuck
+const SCXCTL_OPTIONS: exec::Options = exec::Options {
+    timeout: std::time::Duration::from_secs(2),
+    max_bytes: 64 * 1024,
+    merge_stderr: false,
+};
+
 /// Shared state for the scheduler page
 #[derive(Default)]
 struct State {
@@ -25,12 +37,16 @@ struct State {
     selected_scheduler: Option<String>,
 }
 
-pub fn setup_handlers(builder: &Builder, _main_builder: &Builder, window: &ApplicationWindow) {
+pub fn setup_handlers(
+    builder: &Builder,
+    _main_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
     let state = Rc::new(RefCell::new(State::default()));
 
-    init_kernel_support(builder, &state);
-    setup_buttons(builder, window, &state);
-    setup_persistence(builder, window, &state);
+    init_kernel_support(builder, &state)?;
+    setup_buttons(builder, window, &state)?;
+    setup_persistence(builder, window, &state)?;
 
     // Initial scan
     let b = builder.clone();
@@ -44,16 +60,19 @@ pub fn setup_handlers(builder: &Builder, _main_builder: &Builder, window: &Appli
         update_status(&b, &s);
         glib::ControlFlow::Continue
     });
+    Ok(())
 }
 
-fn init_kernel_support(builder: &Builder, state: &Rc<RefCell<State>>) {
-    let version = run_command("uname", &["-r"]).unwrap_or_else(|| "Unknown".to_string());
+fn init_kernel_support(builder: &Builder, state: &Rc<RefCell<State>>) -> Result<(), WidgetError> {
+    let version = Some(dkms::running_kernel())
+        .filter(|version| !version.is_empty())
+        .unwrap_or_else(|| "Unknown".to_string());
     let supported = path_exists(SCHED_EXT_PATH);
 
     state.borrow_mut().kernel_supported = supported;
 
-    let icon = extract_widget::<Image>(builder, "kernel_status_icon");
-    let label = extract_widget::<Label>(builder, "kernel_version_label");
+    let icon = extract_widget::<Image>(builder, "kernel_status_icon")?;
+    let label = extract_widget::<Label>(builder, "kernel_version_label")?;
 
     if supported {
         icon.set_icon_name(Some("circle-check"));
@@ -63,24 +82,29 @@ fn init_kernel_support(builder: &Builder, state: &Rc<RefCell<State>>) {
     } else {
         icon.set_icon_name(Some("circle-xmark"));
         icon.add_css_class("error");
-        label.set_text(&format!("{} (no sched-ext)", version));
+        label.set_text(&i18n::fill(&gettext("{} (no sched-ext)"), &[&version]));
         label.add_css_class("warning");
     }
 
     // Hidden label for compatibility
-    extract_widget::<Label>(builder, "kernel_support_label").set_text(if supported {
+    extract_widget::<Label>(builder, "kernel_support_label")?.set_text(if supported {
         "Supported"
     } else {
         "Not supported"
     });
+    Ok(())
 }
 
-fn setup_buttons(builder: &Builder, window: &ApplicationWindow, state: &Rc<RefCell<State>>) {
+fn setup_buttons(
+    builder: &Builder,
+    window: &ApplicationWindow,
+    state: &Rc<RefCell<State>>,
+) -> Result<(), WidgetError> {
     // Scheduler Selection Row
     let b = builder.clone();
     let w = window.clone();
     let s = Rc::clone(state);
-    extract_widget::<adw::ActionRow>(builder, "scheduler_selection_row").connect_activated(
+    extract_widget::<adw::ActionRow>(builder, "scheduler_selection_row")?.connect_activated(
         move |_| {
             let schedulers = s.borrow().schedulers.clone();
             let current = s.borrow().selected_scheduler.clone();
@@ -89,7 +113,7 @@ fn setup_buttons(builder: &Builder, window: &ApplicationWindow, state: &Rc<RefCe
 
             show_scheduler_selector(&w, schedulers, current, move |selected| {
                 s.borrow_mut().selected_scheduler = Some(selected.clone());
-                extract_widget::<Label>(&b, "selected_scheduler_label")
+                expect_widget::<Label>(&b, "selected_scheduler_label")
                     .set_label(&humanize_name(&selected));
             });
         },
@@ -98,7 +122,7 @@ fn setup_buttons(builder: &Builder, window: &ApplicationWindow, state: &Rc<RefCe
     // Refresh button
     let b = builder.clone();
     let s = Rc::clone(state);
-    extract_widget::<Button>(builder, "btn_refresh_schedulers").connect_clicked(move |btn| {
+    extract_widget::<Button>(builder, "btn_refresh_schedulers")?.connect_clicked(move |btn| {
         refresh_state(&b, &s, Some(btn));
     });
 
@@ -106,13 +130,14 @@ fn setup_buttons(builder: &Builder, window: &ApplicationWindow, state: &Rc<RefCe
     let b = builder.clone();
     let w = window.clone();
     let s = Rc::clone(state);
-    extract_widget::<Button>(builder, "btn_switch_scheduler").connect_clicked(move |_| {
+    extract_widget::<Button>(builder, "btn_switch_scheduler")?.connect_clicked(move |_| {
         let scheduler = s.borrow().selected_scheduler.clone();
-        let mode = get_combo_row_value(&extract_widget::<adw::ComboRow>(&b, "mode_combo"))
+        let mode = get_combo_row_value(&expect_widget::<adw::ComboRow>(&b, "mode_combo"))
             .unwrap_or_else(|| "auto".to_string());
 
         let Some(sched_name) = scheduler else {
             warn!("No valid scheduler selected");
+            toast::toast(&gettext("Select a scheduler first"));
             return;
         };
 
@@ -131,11 +156,13 @@ fn setup_buttons(builder: &Builder, window: &ApplicationWindow, state: &Rc<RefCe
                     .normal()
                     .program("scxctl")
                     .args(&[cmd, "--sched", &sched_name, "--mode", &mode])
-                    .description(&format!(
-                        "{}ing {} ({} mode)...",
-                        if cmd == "switch" { "Switch" } else { "Start" },
-                        sched,
-                        mode
+                    .description(&i18n::fill(
+                        &if cmd == "switch" {
+                            gettext("Switching {} ({} mode)...")
+                        } else {
+                            gettext("Starting {} ({} mode)...")
+                        },
+                        &[&sched, &mode],
                     ))
                     .build(),
             )
@@ -154,12 +181,12 @@ fn setup_buttons(builder: &Builder, window: &ApplicationWindow, state: &Rc<RefCe
 
     // Stop button
     let w = window.clone();
-    extract_widget::<Button>(builder, "btn_stop_scheduler").connect_clicked(move |_| {
+    extract_widget::<Button>(builder, "btn_stop_scheduler")?.connect_clicked(move |_| {
         let wc = w.clone();
         show_warning_confirmation(
             w.upcast_ref(),
-            "Stop Scheduler",
-            "Stop the current scheduler and fall back to EEVDF?",
+            &gettext("Stop Scheduler"),
+            gettext("Stop the current scheduler and fall back to EEVDF?"),
             move || {
                 task_runner::run(
                     wc.upcast_ref(),
@@ -169,19 +196,24 @@ fn setup_buttons(builder: &Builder, window: &ApplicationWindow, state: &Rc<RefCe
                                 .normal()
                                 .program("scxctl")
                                 .args(&["stop"])
-                                .description("Stopping scheduler...")
+                                .description(&gettext("Stopping scheduler..."))
                                 .build(),
                         )
                         .build(),
-                    "Stop Scheduler",
+                    &gettext("Stop Scheduler"),
                 );
             },
         );
     });
+    Ok(())
 }
 
-fn setup_persistence(builder: &Builder, window: &ApplicationWindow, state: &Rc<RefCell<State>>) {
-    let switch = extract_widget::<adw::SwitchRow>(builder, "persist_switch");
+fn setup_persistence(
+    builder: &Builder,
+    window: &ApplicationWindow,
+    state: &Rc<RefCell<State>>,
+) -> Result<(), WidgetError> {
+    let switch = extract_widget::<adw::SwitchRow>(builder, "persist_switch")?;
     switch.set_active(is_service_enabled("scx.service"));
 
     let b = builder.clone();
@@ -190,11 +222,12 @@ fn setup_persistence(builder: &Builder, window: &ApplicationWindow, state: &Rc<R
     switch.connect_active_notify(move |sw| {
         if sw.is_active() {
             let scheduler = s.borrow().selected_scheduler.clone();
-            let mode = get_combo_row_value(&extract_widget::<adw::ComboRow>(&b, "mode_combo"))
+            let mode = get_combo_row_value(&expect_widget::<adw::ComboRow>(&b, "mode_combo"))
                 .unwrap_or_else(|| "auto".to_string());
 
             let Some(sched_name) = scheduler else {
                 warn!("No valid scheduler selected for persistence");
+                toast::toast(&gettext("Select a scheduler first"));
                 sw.set_active(false);
                 return;
             };
@@ -213,10 +246,21 @@ fn setup_persistence(builder: &Builder, window: &ApplicationWindow, state: &Rc<R
                 .replace("@SCHEDULER_NAME@", &sched_name)
                 .replace("@MODE@", &mode);
 
-            if std::fs::write("/tmp/scx.service", &service).is_err() {
+            // Private to the user, unlike /tmp, and visible to the host
+            let staged = match core::paths::runtime_dir() {
+                Ok(dir) => dir.join("scx.service"),
+                Err(e) => {
+                    warn!("Failed to create the runtime directory: {}", e);
+                    sw.set_active(false);
+                    return;
+                }
+            };
+            if let Err(e) = std::fs::write(&staged, &service) {
+                warn!("Failed to stage {}: {}", staged.display(), e);
                 sw.set_active(false);
                 return;
             }
+            let staged = staged.to_string_lossy();
 
             task_runner::run(
                 w.upcast_ref(),
@@ -225,8 +269,8 @@ fn setup_persistence(builder: &Builder, window: &ApplicationWindow, state: &Rc<R
                         Command::builder()
                             .privileged()
                             .program("cp")
-                            .args(&["/tmp/scx.service", "/etc/systemd/system/scx.service"])
-                            .description("Installing service...")
+                            .args(&[&staged, "/etc/systemd/system/scx.service"])
+                            .description(&gettext("Installing service..."))
                             .build(),
                     )
                     .then(
@@ -234,7 +278,7 @@ fn setup_persistence(builder: &Builder, window: &ApplicationWindow, state: &Rc<R
                             .privileged()
                             .program("systemctl")
                             .args(&["daemon-reload"])
-                            .description("Reloading systemd...")
+                            .description(&gettext("Reloading systemd..."))
                             .build(),
                     )
                     .then(
@@ -242,7 +286,7 @@ fn setup_persistence(builder: &Builder, window: &ApplicationWindow, state: &Rc<R
                             .privileged()
                             .program("systemctl")
                             .args(&["enable", "--now", "scx.service"])
-                            .description("Enabling and starting service...")
+                            .description(&gettext("Enabling and starting service..."))
                             .build(),
                     )
                     .then(
@@ -250,7 +294,7 @@ fn setup_persistence(builder: &Builder, window: &ApplicationWindow, state: &Rc<R
                             .privileged()
                             .program("mkdir")
                             .args(&["-p", "/etc/systemd/system/sysinit.target.wants"])
-                            .description("Preparing sysinit target...")
+                            .description(&gettext("Preparing sysinit target..."))
                             .build(),
                     )
                     .then(
@@ -262,11 +306,11 @@ fn setup_persistence(builder: &Builder, window: &ApplicationWindow, state: &Rc<R
                                 "/etc/systemd/system/scx.service",
                                 "/etc/systemd/system/sysinit.target.wants/scx.service",
                             ])
-                            .description("Linking to sysinit...")
+                            .description(&gettext("Linking to sysinit..."))
                             .build(),
                     )
                     .build(),
-                "Enable Persistence",
+                &gettext("Enable Persistence"),
             );
         } else {
             task_runner::run(
@@ -277,7 +321,7 @@ fn setup_persistence(builder: &Builder, window: &ApplicationWindow, state: &Rc<R
                             .privileged()
                             .program("systemctl")
                             .args(&["stop", "scx.service"])
-                            .description("Stopping service...")
+                            .description(&gettext("Stopping service..."))
                             .build(),
                     )
                     .then(
@@ -285,14 +329,15 @@ fn setup_persistence(builder: &Builder, window: &ApplicationWindow, state: &Rc<R
                             .privileged()
                             .program("systemctl")
                             .args(&["disable", "scx.service"])
-                            .description("Disabling service...")
+                            .description(&gettext("Disabling service..."))
                             .build(),
                     )
                     .build(),
-                "Disable Persistence",
+                &gettext("Disable Persistence"),
             );
         }
     });
+    Ok(())
 }
 
 fn refresh_state(builder: &Builder, state: &Rc<RefCell<State>>, refresh_btn: Option<&Button>) {
@@ -301,11 +346,11 @@ fn refresh_state(builder: &Builder, state: &Rc<RefCell<State>>, refresh_btn: Opt
     let btn_opt = refresh_btn.cloned();
 
     // Disable controls while refreshing
-    let row = extract_widget::<adw::ActionRow>(&builder, "scheduler_selection_row");
-    let mode_combo = extract_widget::<adw::ComboRow>(&builder, "mode_combo");
-    let switch_btn = extract_widget::<Button>(&builder, "btn_switch_scheduler");
-    let stop_btn = extract_widget::<Button>(&builder, "btn_stop_scheduler");
-    let persist = extract_widget::<adw::SwitchRow>(&builder, "persist_switch");
+    let row = expect_widget::<adw::ActionRow>(&builder, "scheduler_selection_row");
+    let mode_combo = expect_widget::<adw::ComboRow>(&builder, "mode_combo");
+    let switch_btn = expect_widget::<Button>(&builder, "btn_switch_scheduler");
+    let stop_btn = expect_widget::<Button>(&builder, "btn_stop_scheduler");
+    let persist = expect_widget::<adw::SwitchRow>(&builder, "persist_switch");
 
     row.set_sensitive(false);
     mode_combo.set_sensitive(false);
@@ -368,7 +413,7 @@ fn refresh_state(builder: &Builder, state: &Rc<RefCell<State>>, refresh_btn: Opt
 
                 // Update selected label
                 if let Some(selected) = &state.borrow().selected_scheduler {
-                    extract_widget::<Label>(&builder, "selected_scheduler_label")
+                    expect_widget::<Label>(&builder, "selected_scheduler_label")
                         .set_label(&humanize_name(selected));
                 }
 
@@ -381,8 +426,8 @@ fn refresh_state(builder: &Builder, state: &Rc<RefCell<State>>, refresh_btn: Opt
                 persist.set_sensitive(true);
 
                 let can_switch = kernel_supported && !schedulers.is_empty();
-                switch_btn.set_sensitive(can_switch);
-                stop_btn.set_sensitive(is_active);
+                switch_btn.set_sensitive(can_control() && can_switch);
+                stop_btn.set_sensitive(can_control() && is_active);
 
                 // Update persistence state
                 persist.set_active(is_service_enabled("scx.service"));
@@ -415,8 +460,8 @@ fn refresh_state(builder: &Builder, state: &Rc<RefCell<State>>, refresh_btn: Opt
                 // Re-enable controls on failure
                 row.set_sensitive(true);
                 mode_combo.set_sensitive(true);
-                switch_btn.set_sensitive(true);
-                stop_btn.set_sensitive(true);
+                switch_btn.set_sensitive(can_control());
+                stop_btn.set_sensitive(can_control());
                 persist.set_sensitive(true);
 
                 if let Some(btn) = &btn_opt {
@@ -438,29 +483,51 @@ fn refresh_state(builder: &Builder, state: &Rc<RefCell<State>>, refresh_btn: Opt
 }
 
 fn update_status(builder: &Builder, state: &Rc<RefCell<State>>) {
-    let (is_active, name, mode) = get_status();
-    state.borrow_mut().is_active = is_active;
+    let builder = builder.clone();
+    let state = Rc::clone(state);
+    glib::spawn_future_local(async move {
+        let output = exec::capture_async("scxctl", &["get"], SCXCTL_OPTIONS)
+            .await
+            .ok()
+            .and_then(exec::Captured::stdout_if_success);
+        let (is_active, name, mode) = parse_status(output);
+        state.borrow_mut().is_active = is_active;
+
+        update_status_labels(&builder, is_active, &name, &mode);
+        expect_widget::<Button>(&builder, "btn_stop_scheduler")
+            .set_sensitive(can_control() && is_active);
+    });
+}
 
-    update_status_labels(builder, is_active, &name, &mode);
-    extract_widget::<Button>(builder, "btn_stop_scheduler").set_sensitive(is_active);
+/// Whether this system can switch and stop schedulers at all.
+fn can_control() -> bool {
+    core::system_check::environment().has_all(super::SCHEDULER_REQUIRED)
 }
 
 fn update_status_labels(builder: &Builder, is_active: bool, name: &str, mode: &str) {
-    let active_label = extract_widget::<Label>(builder, "active_scheduler_label");
+    let active_label = expect_widget::<Label>(builder, "active_scheduler_label");
 
     if is_active {
         active_label.set_text(&format!("{} ({})", humanize_name(name), mode));
         active_label.remove_css_class("dim-label");
         active_label.add_css_class("accent");
     } else {
-        active_label.set_text("EEVDF (Default)");
+        active_label.set_text(&gettext("EEVDF (Default)"));
         active_label.remove_css_class("accent");
         active_label.add_css_class("dim-label");
     }
 }
 
+/// Trimmed output of a successful scxctl query.
+fn scxctl(args: &[&str]) -> Option<String> {
+    exec::capture("scxctl", args, SCXCTL_OPTIONS)
+        .inspect_err(|e| warn!("{:#}", e))
+        .ok()
+        .and_then(exec::Captured::stdout_if_success)
+}
+
 fn get_schedulers() -> Vec<String> {
-    run_command("scxctl", &["list"])
+    scxctl(&["list"])
         .and_then(|out| {
             out.find("supported schedulers:")
                 .and_then(|i| out[i + 21..].find('[').map(|j| i + 21 + j))
@@ -480,7 +547,12 @@ fn get_schedulers() -> Vec<String> {
 }
 
 fn get_status() -> (bool, String, String) {
-    run_command("scxctl", &["get"])
+    parse_status(scxctl(&["get"]))
+}
+
+/// Whether a scheduler runs, its name and mode, from `scxctl get` output.
+fn parse_status(output: Option<String>) -> (bool, String, String) {
+    output
         .map(|out| {
             let lower = out.to_lowercase();
             if lower.contains("not running") || out.is_empty() {
@@ -511,11 +583,11 @@ fn show_scheduler_selector(
     on_select: impl Fn(String) + 'static,
 ) {
     // Load UI from resource
-    let builder = Builder::from_resource(crate::config::resources::dialogs::SCHEDULER_SELECTION);
-    let window: adw::Window = extract_widget(&builder, "scheduler_selection_window");
+    let builder = builder_from_resource(crate::config::resources::dialogs::SCHEDULER_SELECTION);
+    let window: adw::Window = expect_widget(&builder, "scheduler_selection_window");
     window.set_transient_for(Some(parent));
 
-    let content: GtkBox = extract_widget(&builder, "schedulers_container");
+    let content: GtkBox = expect_widget(&builder, "schedulers_container");
 
     // Categories
     let categories = vec![
@@ -585,7 +657,7 @@ fn show_scheduler_selector(
 
     if !others.is_empty() {
         let group = adw::PreferencesGroup::new();
-        group.set_title("Other");
+        group.set_title(&gettext("Other"));
         for item in others {
             let row = adw::ActionRow::new();
             row.set_title(&humanize_name(item));
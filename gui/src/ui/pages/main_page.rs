@@ -1,6 +1,7 @@
 //! Main page button handlers.
 //!
 //! Handles:
+//! - Health strip with updates, failed services, orphans and free space
 //! - System update
 //! - Package manager GUI installation
 //! - Download Arch ISO
@@ -10,33 +11,70 @@
 
 use crate::config;
 use crate::core;
+use crate::core::actions;
+use crate::core::download::format_bytes;
+use crate::core::events::{self, Event};
+use crate::core::health::{self, Reading, Stat};
+use crate::core::package::UpdateComponent;
+use crate::core::system_check::Capability;
+use crate::i18n::{self, gettext, ngettext};
+use crate::ui::click_guard::connect_clicked_once_until_done;
+use crate::ui::context;
 use crate::ui::dialogs::download::show_download_dialog;
 use crate::ui::dialogs::selection::{
     show_selection_dialog, SelectionDialogConfig, SelectionOption, SelectionType,
 };
 use crate::ui::dialogs::terminal;
 use crate::ui::dialogs::warning::show_warning_confirmation;
+use crate::ui::markup::{self, Markup};
+use crate::ui::navigation::{self, PageContext};
 use crate::ui::task_runner::{self, Command, CommandSequence};
-use crate::ui::utils::extract_widget;
+use crate::ui::utils::{extract_widget, require_capabilities, WidgetError};
+use gtk4::glib;
 use gtk4::prelude::*;
-use gtk4::{ApplicationWindow, Builder, Button};
-use log::info;
+use gtk4::{ApplicationWindow, Builder, Button, Label, Switch};
+use log::{info, warn};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::time::Duration;
 
 /// Set up all button handlers for the main page.
-pub fn setup_handlers(page_builder: &Builder, _main_builder: &Builder, window: &ApplicationWindow) {
-    setup_update_system(page_builder, window);
-    setup_pkg_manager(page_builder, window);
-    setup_download_arch_iso(page_builder, window);
-    setup_install_nix(page_builder, window);
-    setup_obs_studio_aio(page_builder, window);
-    setup_external_links(page_builder);
+pub fn setup_handlers(context: &PageContext) -> Result<(), WidgetError> {
+    let page_builder = &context.builder;
+    let window = &context.window;
+    setup_update_system(page_builder, window)?;
+    setup_pkg_manager(page_builder, window)?;
+    setup_download_arch_iso(page_builder, window)?;
+    setup_install_nix(page_builder, window)?;
+    setup_obs_studio_aio(page_builder, window)?;
+    setup_external_links(page_builder)?;
+    setup_health_strip(page_builder, window)?;
+
+    require_capabilities(
+        page_builder,
+        &[Capability::Pacman, Capability::Escalate],
+        &["btn_update_system", "health_updates", "health_orphans"],
+    )?;
+    require_capabilities(
+        page_builder,
+        &[Capability::Flatpak],
+        &["btn_pkg_manager", "btn_obs_studio_aio"],
+    )?;
+    require_capabilities(page_builder, &[Capability::Escalate], &["btn_install_nix"])?;
+    require_capabilities(
+        page_builder,
+        &[Capability::Services],
+        &["health_failed_units"],
+    )?;
+    Ok(())
 }
 
 /// Set up OBS-Studio AiO button on the main page.
-fn setup_obs_studio_aio(builder: &Builder, window: &ApplicationWindow) {
-    let btn_obs_studio_aio = extract_widget::<Button>(builder, "btn_obs_studio_aio");
+fn setup_obs_studio_aio(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let btn_obs_studio_aio = extract_widget::<Button>(builder, "btn_obs_studio_aio")?;
     let window = window.clone();
-    btn_obs_studio_aio.connect_clicked(move |_| {
+    connect_clicked_once_until_done(&btn_obs_studio_aio, move |_, token| {
         info!("Main page: OBS-Studio AiO button clicked");
         let window_ref = window.upcast_ref();
 
@@ -65,67 +103,67 @@ fn setup_obs_studio_aio(builder: &Builder, window: &ApplicationWindow) {
             core::is_flatpak_installed("com.obsproject.Studio.Plugin.BackgroundRemoval");
 
         let config = SelectionDialogConfig::new(
-            "OBS-Studio & Plugins Installation",
-            "OBS-Studio will be installed. Optionally select plugins to install.",
+            &gettext("OBS-Studio & Plugins Installation"),
+            gettext("OBS-Studio will be installed. Optionally select plugins to install."),
         )
         .selection_type(SelectionType::Multi)
         .selection_required(false)
         .add_option(SelectionOption::new(
             "wayland_hotkeys",
-            "Wayland Hotkeys Plugin",
-            "Enable hotkey support for OBS on Wayland",
+            &gettext("Wayland Hotkeys Plugin"),
+            gettext("Enable hotkey support for OBS on Wayland"),
             wayland_hotkeys_installed,
         ))
         .add_option(SelectionOption::new(
             "graphics_capture",
-            "Graphics Capture Plugins",
-            "VkCapture, GStreamer, GStreamer VA-API",
+            &gettext("Graphics Capture Plugins"),
+            gettext("VkCapture, GStreamer, GStreamer VA-API"),
             graphics_capture_installed,
         ))
         .add_option(SelectionOption::new(
             "transitions_effects",
-            "Transitions & Effects",
-            "Move Transition, Transition Table, Scale to Sound",
+            &gettext("Transitions & Effects"),
+            gettext("Move Transition, Transition Table, Scale to Sound"),
             transitions_effects_installed,
         ))
         .add_option(SelectionOption::new(
             "streaming_tools",
-            "Streaming & Recording Tools",
-            "WebSocket API, Scene Switcher, DroidCam",
+            &gettext("Streaming & Recording Tools"),
+            gettext("WebSocket API, Scene Switcher, DroidCam"),
             streaming_tools_installed,
         ))
         .add_option(SelectionOption::new(
             "audio_video_tools",
-            "Audio & Video Tools",
-            "Waveform, Vertical Canvas, Background Removal",
+            &gettext("Audio & Video Tools"),
+            gettext("Waveform, Vertical Canvas, Background Removal"),
             audio_video_tools_installed,
         ))
         .add_option(SelectionOption::new(
             "v4l2",
-            "V4L2loopback Virtual Camera",
-            "Enable OBS virtual camera functionality",
+            &gettext("V4L2loopback Virtual Camera"),
+            gettext("Enable OBS virtual camera functionality"),
             v4l2_installed,
         ))
-        .confirm_label("Install");
+        .confirm_label(&gettext("Install"));
 
         let window_for_closure = window.clone();
-        show_selection_dialog(window_ref, config, move |selected_ids| {
+        let dialog = show_selection_dialog(window_ref, config, move |selected_ids| {
             let mut commands = CommandSequence::new();
 
             // Always install OBS-Studio
             commands = commands.then(Command::builder()
                 .normal()
                 .program("flatpak")
-                .args(&["install", "-y", "com.obsproject.Studio"])
-                .description("Installing OBS-Studio...")
+                .args(&["install", "-y", "flathub", "com.obsproject.Studio"])
+                .description(&gettext("Installing OBS-Studio..."))
                 .build());
 
             if selected_ids.iter().any(|s| s == "wayland_hotkeys") {
                 commands = commands.then(Command::builder()
                     .normal()
                     .program("flatpak")
-                    .args(&["install", "-y", "com.obsproject.Studio.Plugin.WaylandHotkeys"])
-                    .description("Installing Wayland Hotkeys plugin...")
+                    .args(&["install", "-y", "flathub", "com.obsproject.Studio.Plugin.WaylandHotkeys"])
+                    .description(&gettext("Installing Wayland Hotkeys plugin..."))
                     .build());
             }
             if selected_ids.iter().any(|s| s == "graphics_capture") {
@@ -135,12 +173,13 @@ fn setup_obs_studio_aio(builder: &Builder, window: &ApplicationWindow) {
                     .args(&[
                         "install",
                         "-y",
+                        "flathub",
                         "com.obsproject.Studio.Plugin.OBSVkCapture",
                         "org.freedesktop.Platform.VulkanLayer.OBSVkCapture/x86_64/25.08",
                         "com.obsproject.Studio.Plugin.Gstreamer",
                         "com.obsproject.Studio.Plugin.GStreamerVaapi",
                     ])
-                    .description("Installing graphics capture plugins...")
+                    .description(&gettext("Installing graphics capture plugins..."))
                     .build());
             }
             if selected_ids.iter().any(|s| s == "transitions_effects") {
@@ -150,11 +189,12 @@ fn setup_obs_studio_aio(builder: &Builder, window: &ApplicationWindow) {
                     .args(&[
                         "install",
                         "-y",
+                        "flathub",
                         "com.obsproject.Studio.Plugin.MoveTransition",
                         "com.obsproject.Studio.Plugin.TransitionTable",
                         "com.obsproject.Studio.Plugin.ScaleToSound",
                     ])
-                    .description("Installing transitions & effects plugins...")
+                    .description(&gettext("Installing transitions & effects plugins..."))
                     .build());
             }
             if selected_ids.iter().any(|s| s == "streaming_tools") {
@@ -164,11 +204,12 @@ fn setup_obs_studio_aio(builder: &Builder, window: &ApplicationWindow) {
                     .args(&[
                         "install",
                         "-y",
+                        "flathub",
                         "com.obsproject.Studio.Plugin.WebSocket",
                         "com.obsproject.Studio.Plugin.SceneSwitcher",
                         "com.obsproject.Studio.Plugin.DroidCam",
                     ])
-                    .description("Installing streaming tools...")
+                    .description(&gettext("Installing streaming tools..."))
                     .build());
             }
             if selected_ids.iter().any(|s| s == "audio_video_tools") {
@@ -178,24 +219,25 @@ fn setup_obs_studio_aio(builder: &Builder, window: &ApplicationWindow) {
                     .args(&[
                         "install",
                         "-y",
+                        "flathub",
                         "com.obsproject.Studio.Plugin.waveform",
                         "com.obsproject.Studio.Plugin.VerticalCanvas",
                         "com.obsproject.Studio.Plugin.BackgroundRemoval",
                     ])
-                    .description("Installing audio/video enhancement plugins...")
+                    .description(&gettext("Installing audio/video enhancement plugins..."))
                     .build());
             }
             if selected_ids.iter().any(|s| s == "v4l2") {
                 commands = commands.then(Command::builder()
                     .aur()
-                    .args(&["-S", "--noconfirm", "--needed", "v4l2loopback-dkms", "v4l2loopback-utils"])
-                    .description("Installing V4L2 loopback modules...")
+                    .args(&["-S", "--needed", "v4l2loopback-dkms", "v4l2loopback-utils"])
+                    .description(&gettext("Installing V4L2 loopback modules..."))
                     .build());
                 commands = commands.then(Command::builder()
                     .privileged()
                     .program("sh")
                     .args(&["-c", "echo 'v4l2loopback' > /etc/modules-load.d/v4l2loopback.conf"])
-                    .description("Enabling V4L2 loopback module at boot...")
+                    .description(&gettext("Enabling V4L2 loopback module at boot..."))
                     .build());
                 commands = commands.then(Command::builder()
                     .privileged()
@@ -204,46 +246,155 @@ fn setup_obs_studio_aio(builder: &Builder, window: &ApplicationWindow) {
                         "-c",
                         "echo 'options v4l2loopback exclusive_caps=1 card_label=\"OBS Virtual Camera\"' > /etc/modprobe.d/v4l2loopback.conf",
                     ])
-                    .description("Configuring virtual camera options...")
+                    .description(&gettext("Configuring virtual camera options..."))
                     .build());
             }
 
-            task_runner::run(window_for_closure.upcast_ref(), commands.build(), "OBS-Studio Setup");
+            task_runner::run(window_for_closure.upcast_ref(), commands.build(), &gettext("OBS-Studio Setup"));
         });
+        token.release_on_close(&dialog);
     });
+    Ok(())
 }
 
 /// Setup system update button.
-fn setup_update_system(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_update_system");
+///
+/// Interactive mode runs the `upd` script in a terminal; otherwise pending
+/// updates are counted per component and the user picks what to update.
+fn setup_update_system(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_update_system")?;
+    let interactive_switch = extract_widget::<Switch>(builder, "switch_update_interactive")?;
     let window = window.clone();
 
-    button.connect_clicked(move |_| {
+    connect_clicked_once_until_done(&button, move |button, token| {
         info!("Update System button clicked");
 
-        // Use terminal dialog for interactive system update
-        terminal::show_terminal_dialog(
-            window.upcast_ref(),
-            "System Update",
-            "/usr/local/bin/upd",
-            &[],
-            false,
-        );
+        if interactive_switch.is_active() {
+            let dialog = terminal::show_terminal_dialog(
+                window.upcast_ref(),
+                &gettext("System Update"),
+                "/usr/local/bin/upd",
+                &[],
+                false,
+            );
+            token.release_on_close(&dialog);
+            return;
+        }
+
+        button.set_sensitive(false);
+        button.set_label(&gettext("Checking for Updates..."));
+
+        let (sender, receiver) = mpsc::channel();
+        for component in UpdateComponent::ALL {
+            let sender = sender.clone();
+            std::thread::spawn(move || {
+                let pending = if component.is_available() {
+                    Some(component.pending_updates())
+                } else {
+                    None
+                };
+                let _ = sender.send((component, pending));
+            });
+        }
+        drop(sender);
+
+        let button = button.clone();
+        let window = window.clone();
+        let mut results = Vec::new();
+        let mut token = Some(token);
+        glib::timeout_add_local(Duration::from_millis(100), move || {
+            loop {
+                match receiver.try_recv() {
+                    Ok(result) => results.push(result),
+                    Err(mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+                    Err(mpsc::TryRecvError::Disconnected) => break,
+                }
+            }
+
+            button.set_label(&gettext("Update System"));
+            button.set_sensitive(true);
+            let dialog = show_update_selection(&window, std::mem::take(&mut results));
+            if let Some(token) = token.take() {
+                token.release_on_close(&dialog);
+            }
+            glib::ControlFlow::Break
+        });
     });
+    Ok(())
+}
+
+/// Let the user pick which components to update, returning the dialog.
+///
+/// `results` holds the pending count per component; unavailable components
+/// have no entry and are not offered.
+fn show_update_selection(
+    window: &ApplicationWindow,
+    results: Vec<(UpdateComponent, Option<Option<usize>>)>,
+) -> gtk4::Window {
+    let mut config = SelectionDialogConfig::new(
+        &gettext("System Update"),
+        gettext("Select which components to update."),
+    )
+    .selection_type(SelectionType::Multi)
+    .selection_required(true)
+    .confirm_label(&gettext("Update"));
+
+    // Keep the dialog order stable regardless of which check finished first
+    for component in UpdateComponent::ALL {
+        let Some(pending) = results
+            .iter()
+            .find(|(c, _)| *c == component)
+            .and_then(|(_, pending)| *pending)
+        else {
+            continue;
+        };
+
+        let description = match pending {
+            Some(0) => gettext("Up to date"),
+            Some(count) => i18n::fill(
+                &ngettext("{} update pending", "{} updates pending", count as u32),
+                &[&count.to_string()],
+            ),
+            None => gettext("Could not check for updates"),
+        };
+        info!("{}: {}", component.label(), description);
+
+        config = config.add_option(
+            SelectionOption::new(component.id(), component.label(), &description, false)
+                .preselected(pending != Some(0)),
+        );
+    }
+
+    let window_for_closure = window.clone();
+    show_selection_dialog(window.upcast_ref(), config, move |selected| {
+        let components: Vec<UpdateComponent> = UpdateComponent::ALL
+            .into_iter()
+            .filter(|component| selected.iter().any(|s| s == component.id()))
+            .collect();
+        let commands = actions::update_commands(&components);
+
+        if !commands.is_empty() {
+            task_runner::run(
+                window_for_closure.upcast_ref(),
+                commands.build(),
+                &gettext(actions::UPDATE_SYSTEM.title),
+            );
+        }
+    })
 }
 
 /// Setup package manager GUI button.
-fn setup_pkg_manager(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_pkg_manager");
+fn setup_pkg_manager(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_pkg_manager")?;
     let window = window.clone();
 
-    button.connect_clicked(move |_| {
+    connect_clicked_once_until_done(&button, move |_, token| {
         info!("PKG Manager GUI button clicked");
 
         // Check which package managers are already installed
         let config = SelectionDialogConfig::new(
-            "Package Manager GUI Applications",
-            "Select which package manager GUIs to install. Multiple selections allowed.",
+            &gettext("Package Manager GUI Applications"),
+            gettext("Select which package manager GUIs to install. Multiple selections allowed."),
         )
         .selection_type(SelectionType::Multi)
         .selection_required(true)
@@ -255,55 +406,57 @@ fn setup_pkg_manager(builder: &Builder, window: &ApplicationWindow) {
         // ))
         .add_option(SelectionOption::new(
             "octopi",
-            "Octopi",
-            "Powerful Pacman GUI with AUR support",
+            &gettext("Octopi"),
+            gettext("Powerful Pacman GUI with AUR support"),
             core::is_package_installed("octopi"),
         ))
         .add_option(SelectionOption::new(
             "pacseek",
-            "PacSeek",
-            "Terminal UI package manager with search",
+            &gettext("PacSeek"),
+            gettext("Terminal UI package manager with search"),
             core::is_package_installed("pacseek"),
         ))
         .add_option(SelectionOption::new(
             "bauh",
-            "Bauh",
-            "Manage Pacman, AUR, Flatpak, Snap packages",
+            &gettext("Bauh"),
+            gettext("Manage Pacman, AUR, Flatpak, Snap packages"),
             core::is_package_installed("bauh"),
         ))
         .add_option(SelectionOption::new(
             "warehouse",
-            "Warehouse",
-            "Flatpak package manager (Flatpak)",
+            &gettext("Warehouse"),
+            gettext("Flatpak package manager (Flatpak)"),
             core::is_flatpak_installed("io.github.flattool.Warehouse"),
         ))
         .add_option(SelectionOption::new(
             "flatseal",
-            "Flatseal",
-            "Flatpak permissions manager (Flatpak)",
+            &gettext("Flatseal"),
+            gettext("Flatpak permissions manager (Flatpak)"),
             core::is_flatpak_installed("com.github.tchx84.Flatseal"),
         ))
         .add_option(SelectionOption::new(
             "bazaar",
-            "Bazaar",
-            "Browse and install Flatpak apps (Flatpak)",
+            &gettext("Bazaar"),
+            gettext("Browse and install Flatpak apps (Flatpak)"),
             core::is_flatpak_installed("io.github.kolunmi.Bazaar"),
         ))
-        .confirm_label("Install");
+        .confirm_label(&gettext("Install"));
 
         let window_for_closure = window.clone();
-        show_selection_dialog(window.upcast_ref(), config, move |selected| {
+        let dialog = show_selection_dialog(window.upcast_ref(), config, move |selected| {
             let commands = build_pkg_manager_commands(&selected);
 
             if !commands.is_empty() {
                 task_runner::run(
                     window_for_closure.upcast_ref(),
                     commands.build(),
-                    "Package Manager GUI Installation",
+                    &gettext("Package Manager GUI Installation"),
                 );
             }
         });
+        token.release_on_close(&dialog);
     });
+    Ok(())
 }
 
 /// Build commands for selected package managers.
@@ -314,7 +467,7 @@ fn build_pkg_manager_commands(selected: &[String]) -> CommandSequence {
     //     commands = commands.then(
     //         Command::builder()
     //             .aur()
-    //             .args(&["-S", "--noconfirm", "--needed", "xpackagemanager"])
+    //             .args(&["-S", "--needed", "xpackagemanager"])
     //             .description("Installing xPackage Manager...")
     //             .build(),
     //     );
@@ -324,8 +477,8 @@ fn build_pkg_manager_commands(selected: &[String]) -> CommandSequence {
         commands = commands.then(
             Command::builder()
                 .aur()
-                .args(&["-S", "--noconfirm", "--needed", "octopi"])
-                .description("Installing Octopi package manager...")
+                .args(&["-S", "--needed", "octopi"])
+                .description(&gettext("Installing Octopi package manager..."))
                 .build(),
         );
     }
@@ -334,8 +487,8 @@ fn build_pkg_manager_commands(selected: &[String]) -> CommandSequence {
         commands = commands.then(
             Command::builder()
                 .aur()
-                .args(&["-S", "--noconfirm", "--needed", "pacseek", "pacfinder"])
-                .description("Installing PacSeek package browser...")
+                .args(&["-S", "--needed", "pacseek", "pacfinder"])
+                .description(&gettext("Installing PacSeek package browser..."))
                 .build(),
         );
     }
@@ -344,8 +497,8 @@ fn build_pkg_manager_commands(selected: &[String]) -> CommandSequence {
         commands = commands.then(
             Command::builder()
                 .aur()
-                .args(&["-S", "--noconfirm", "--needed", "bauh"])
-                .description("Installing Bauh package manager...")
+                .args(&["-S", "--needed", "bauh"])
+                .description(&gettext("Installing Bauh package manager..."))
                 .build(),
         );
     }
@@ -355,8 +508,8 @@ fn build_pkg_manager_commands(selected: &[String]) -> CommandSequence {
             Command::builder()
                 .normal()
                 .program("flatpak")
-                .args(&["install", "-y", "io.github.flattool.Warehouse"])
-                .description("Installing Warehouse from Flathub...")
+                .args(&["install", "-y", "flathub", "io.github.flattool.Warehouse"])
+                .description(&gettext("Installing Warehouse from Flathub..."))
                 .build(),
         );
     }
@@ -366,8 +519,8 @@ fn build_pkg_manager_commands(selected: &[String]) -> CommandSequence {
             Command::builder()
                 .normal()
                 .program("flatpak")
-                .args(&["install", "-y", "com.github.tchx84.Flatseal"])
-                .description("Installing Flatseal from Flathub...")
+                .args(&["install", "-y", "flathub", "com.github.tchx84.Flatseal"])
+                .description(&gettext("Installing Flatseal from Flathub..."))
                 .build(),
         );
     }
@@ -377,8 +530,8 @@ fn build_pkg_manager_commands(selected: &[String]) -> CommandSequence {
             Command::builder()
                 .normal()
                 .program("flatpak")
-                .args(&["install", "-y", "io.github.kolunmi.Bazaar"])
-                .description("Installing Bazaar from Flathub...")
+                .args(&["install", "-y", "flathub", "io.github.kolunmi.Bazaar"])
+                .description(&gettext("Installing Bazaar from Flathub..."))
                 .build(),
         );
     }
@@ -387,8 +540,11 @@ fn build_pkg_manager_commands(selected: &[String]) -> CommandSequence {
 }
 
 /// Setup download Arch ISO button.
-fn setup_download_arch_iso(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_download_arch_iso");
+fn setup_download_arch_iso(
+    builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_download_arch_iso")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
@@ -396,11 +552,12 @@ fn setup_download_arch_iso(builder: &Builder, window: &ApplicationWindow) {
 
         show_download_dialog(window.upcast_ref());
     });
+    Ok(())
 }
 
 /// Setup Nix package manager installation button.
-fn setup_install_nix(builder: &Builder, window: &ApplicationWindow) {
-    let button = extract_widget::<Button>(builder, "btn_install_nix");
+fn setup_install_nix(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let button = extract_widget::<Button>(builder, "btn_install_nix")?;
     let window = window.clone();
 
     button.connect_clicked(move |_| {
@@ -410,36 +567,36 @@ fn setup_install_nix(builder: &Builder, window: &ApplicationWindow) {
         let window_clone = window.clone();
         show_warning_confirmation(
             window.upcast_ref(),
-            "Experimental Feature Warning",
-            "Nix Package Manager is an <span foreground=\"red\" weight=\"bold\">EXPERIMENTAL</span> feature.\n\n\
+            &gettext("Experimental Feature Warning"),
+            Markup::trusted(gettext("Nix Package Manager is an <span foreground=\"red\" weight=\"bold\">EXPERIMENTAL</span> feature.\n\n\
             This is intended for <span foreground=\"red\" weight=\"bold\">EXPERIENCED USERS ONLY</span>.\n\
             <span foreground=\"red\" weight=\"bold\">Do NOT enable</span> unless you know what you are doing.\n\
             <span foreground=\"red\" weight=\"bold\">NO SUPPORT</span> will be provided for Nix-related issues.\n\n\
-            Proceed at your own risk.",
+            Proceed at your own risk.")),
             move || {
                 info!("User confirmed Nix installation after warning");
 
                 // Show selection dialog to choose installation type
                 let window_for_selection = window_clone.clone();
                 let config = SelectionDialogConfig::new(
-                    "Nix Installation Type",
-                    "Choose the installation type for Nix Package Manager. Multi-user is recommended for most users.",
+                    &gettext("Nix Installation Type"),
+                    gettext("Choose the installation type for Nix Package Manager. Multi-user is recommended for most users."),
                 )
                 .selection_type(SelectionType::Single)
                 .selection_required(true)
                 .add_option(SelectionOption::new(
                     "multi-user",
-                    "Multi-user Installation (Recommended)",
-                    "Better build isolation, security, and sharing between users. Requires systemd and sudo.",
+                    &gettext("Multi-user Installation (Recommended)"),
+                    gettext("Better build isolation, security, and sharing between users. Requires systemd and sudo."),
                     false,
                 ))
                 .add_option(SelectionOption::new(
                     "single-user",
-                    "Single-user Installation",
-                    "Simpler installation owned by your user. Easier to uninstall.",
+                    &gettext("Single-user Installation"),
+                    gettext("Simpler installation owned by your user. Easier to uninstall."),
                     false,
                 ))
-                .confirm_label("Continue");
+                .confirm_label(&gettext("Continue"));
 
                 show_selection_dialog(window_clone.upcast_ref(), config, move |selected| {
                     if selected.is_empty() {
@@ -458,7 +615,7 @@ fn setup_install_nix(builder: &Builder, window: &ApplicationWindow) {
 
                     terminal::show_terminal_dialog(
                         window_for_selection.upcast_ref(),
-                        "Install Nix Package Manager",
+                        &gettext("Install Nix Package Manager"),
                         "sh",
                         &["-c", install_command],
                         false,
@@ -467,25 +624,236 @@ fn setup_install_nix(builder: &Builder, window: &ApplicationWindow) {
             },
         );
     });
+    Ok(())
 }
 
 /// Setup external link buttons.
-fn setup_external_links(builder: &Builder) {
-    let btn_youtube = extract_widget::<Button>(builder, "link_youtube");
+fn setup_external_links(builder: &Builder) -> Result<(), WidgetError> {
+    let btn_youtube = extract_widget::<Button>(builder, "link_youtube")?;
     btn_youtube.connect_clicked(|_| {
         info!("YouTube link clicked");
         let _ = core::package::open_url(config::links::YOUTUBE);
     });
 
-    let btn_website = extract_widget::<Button>(builder, "link_website");
+    let btn_website = extract_widget::<Button>(builder, "link_website")?;
     btn_website.connect_clicked(|_| {
         info!("Website link clicked");
         let _ = core::package::open_url(config::links::WEBSITE);
     });
 
-    let btn_donate = extract_widget::<Button>(builder, "link_donate");
+    let btn_donate = extract_widget::<Button>(builder, "link_donate")?;
     btn_donate.connect_clicked(|_| {
         info!("Donate link clicked");
         let _ = core::package::open_url(config::links::DONATE);
     });
+    Ok(())
+}
+
+/// One chip of the health strip.
+struct HealthChip {
+    stat: Stat,
+    button: Button,
+    value: Label,
+    /// Tooltip from the page, shown while the figure is known
+    tooltip: Option<glib::GString>,
+}
+
+impl HealthChip {
+    fn show_loading(&self) {
+        self.value.set_text("…");
+    }
+
+    fn show(&self, reading: &Reading) {
+        let (text, attention, problem) = match reading {
+            Reading::Value(value) => {
+                let text = match self.stat {
+                    Stat::FreeSpace => format_bytes(*value),
+                    _ => value.to_string(),
+                };
+                (text, self.stat.needs_attention(*value), None)
+            }
+            Reading::Failed(e) => {
+                warn!("Health check {:?} failed: {}", self.stat, e);
+                let problem = i18n::fill(&gettext("Could not be checked: {}"), &[e]);
+                ("—".to_string(), false, Some(problem))
+            }
+            Reading::TimedOut => {
+                warn!("Health check {:?} timed out", self.stat);
+                (
+                    "—".to_string(),
+                    false,
+                    Some(gettext("Took too long to check")),
+                )
+            }
+        };
+
+        self.value.set_text(&text);
+        if attention {
+            self.button.add_css_class("warning");
+        } else {
+            self.button.remove_css_class("warning");
+        }
+        // Disabled chips keep the reason they are disabled
+        if self.button.is_sensitive() {
+            self.button
+                .set_tooltip_text(problem.as_deref().or(self.tooltip.as_deref()));
+        }
+    }
+}
+
+/// Fill in the health strip after startup and after every task run, and
+/// make each chip open what deals with its figure.
+fn setup_health_strip(builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let ids = [
+        (Stat::Updates, "health_updates"),
+        (Stat::FailedUnits, "health_failed_units"),
+        (Stat::Orphans, "health_orphans"),
+        (Stat::FreeSpace, "health_free_space"),
+    ];
+    let mut chips = Vec::new();
+    for (stat, id) in ids {
+        let button = extract_widget::<Button>(builder, id)?;
+        let value = extract_widget::<Label>(builder, &format!("{}_value", id))?;
+        chips.push(HealthChip {
+            stat,
+            tooltip: button.tooltip_text(),
+            button,
+            value,
+        });
+    }
+
+    let btn_update_system = extract_widget::<Button>(builder, "btn_update_system")?;
+    for chip in &chips {
+        let window = window.clone();
+        let btn_update_system = btn_update_system.clone();
+        let stat = chip.stat;
+        chip.button.connect_clicked(move |_| {
+            info!("Main page: {:?} health chip clicked", stat);
+            match stat {
+                Stat::Updates => btn_update_system.emit_clicked(),
+                Stat::FailedUnits => open_page("services"),
+                Stat::Orphans => remove_orphans(&window),
+                Stat::FreeSpace => open_page("servicing_system_tweaks"),
+            }
+        });
+    }
+
+    let chips = Rc::new(chips);
+    let refreshing = Rc::new(Cell::new(false));
+    let again = Rc::new(Cell::new(false));
+    let refresh: Rc<dyn Fn()> = Rc::new({
+        let chips = chips.clone();
+        move || refresh_health(chips.clone(), refreshing.clone(), again.clone())
+    });
+
+    events::subscribe({
+        let refresh = refresh.clone();
+        move |event| {
+            if let Event::TaskFinished { .. } = event {
+                refresh();
+            }
+        }
+    })
+    .detach();
+    refresh();
+    Ok(())
+}
+
+/// Probe the health figures in the background and show them as they come.
+/// A refresh asked for while one runs follows it.
+fn refresh_health(chips: Rc<Vec<HealthChip>>, refreshing: Rc<Cell<bool>>, again: Rc<Cell<bool>>) {
+    if refreshing.replace(true) {
+        again.set(true);
+        return;
+    }
+    for chip in chips.iter() {
+        chip.show_loading();
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(health::gather_all());
+    });
+
+    glib::timeout_add_local(Duration::from_millis(200), move || {
+        let readings = match receiver.try_recv() {
+            Ok(readings) => readings,
+            Err(mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+            Err(mpsc::TryRecvError::Disconnected) => Vec::new(),
+        };
+        for chip in chips.iter() {
+            let reading = readings
+                .iter()
+                .find(|(stat, _)| *stat == chip.stat)
+                .map_or(&Reading::TimedOut, |(_, reading)| reading);
+            chip.show(reading);
+        }
+
+        refreshing.set(false);
+        if again.replace(false) {
+            refresh_health(chips.clone(), refreshing.clone(), again.clone());
+        }
+        glib::ControlFlow::Break
+    });
+}
+
+/// Switch to page `page_id` of the main window.
+fn open_page(page_id: &str) {
+    if let Some(ctx) = context::current() {
+        navigation::open_page(&ctx.ui.tabs_container, page_id);
+    }
+}
+
+/// Ask before removing the packages nothing depends on anymore.
+fn remove_orphans(window: &ApplicationWindow) {
+    let orphans = match core::package::orphans() {
+        Ok(orphans) => orphans,
+        Err(e) => {
+            warn!("Failed to list orphan packages: {:#}", e);
+            return;
+        }
+    };
+    if orphans.is_empty() {
+        info!("No orphan packages to remove");
+        return;
+    }
+
+    let list = orphans
+        .iter()
+        .map(|name| format!("• <tt>{}</tt>", markup::escape(name)))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let message = Markup::trusted(format!(
+        "{}\n\n{}",
+        markup::escape(&ngettext(
+            "This package was installed as a dependency and nothing needs it anymore:",
+            "These packages were installed as dependencies and nothing needs them anymore:",
+            orphans.len() as u32,
+        )),
+        list
+    ));
+
+    let parent = window.clone();
+    show_warning_confirmation(
+        window.upcast_ref(),
+        &gettext("Remove Orphan Packages"),
+        message,
+        move || {
+            let mut args = vec!["-Rns", "--noconfirm"];
+            args.extend(orphans.iter().map(String::as_str));
+            let commands = CommandSequence::new().then(
+                Command::builder()
+                    .privileged()
+                    .program("pacman")
+                    .args(&args)
+                    .description(&gettext("Removing orphan packages..."))
+                    .build(),
+            );
+            task_runner::run(
+                parent.upcast_ref(),
+                commands.build(),
+                &gettext("Remove Orphan Packages"),
+            );
+        },
+    );
 }
@@ -10,8 +10,17 @@
 //! - `kernel_schedulers`: Kernel Manager and SCX Scheduler (with subtabs)
 //! - `servicing`: System fixes and maintenance
 //! - `biometrics`: Fingerprint and facial recognition setup
+//! - `services`: Systemd service state and toggles
+//! - `btrfs`: Btrfs usage, scrub and balance (btrfs root only)
+//! - `system_info`: Hardware overview and copyable system report
+//!
+//! Pages are listed in [`crate::ui::navigation::PAGES`] and set up the first
+//! time they are shown. Their `setup_handlers` take a
+//! [`crate::ui::navigation::PageContext`], or the builders and window one by
+//! one for pages not moved over yet.
 
 pub mod biometrics;
+pub mod btrfs;
 pub mod containers_vms;
 pub mod customization;
 pub mod drivers;
@@ -19,4 +28,6 @@ pub mod gamescope;
 pub mod gaming_tools;
 pub mod kernel_schedulers;
 pub mod main_page;
+pub mod services;
 pub mod servicing;
+pub mod system_info;
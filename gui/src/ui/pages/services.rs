@@ -0,0 +1,294 @@
+//! Services page handlers.
+//!
+//! Handles:
+//! - Curated list of services the toolkit sets up
+//! - Search across all installed service units
+//! - Enable/disable and start/stop through the task runner
+
+use crate::core::systemd::{self, UnitDetails};
+use crate::i18n::{self, gettext};
+use crate::ui::task_runner::{self, Command, CommandSequence};
+use crate::ui::utils::{extract_widget, WidgetError};
+use adw::prelude::*;
+use gtk4::glib;
+use gtk4::{
+    ApplicationWindow, Box as GtkBox, Builder, Button, Label, ListBox, SearchEntry, Switch,
+};
+use log::{info, warn};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+/// Services configured by actions elsewhere in the toolkit.
+const CURATED_UNITS: &[&str] = &[
+    "docker.service",
+    "libvirtd.service",
+    "jellyfin.service",
+    "scx.service",
+    "cups.service",
+    "bluetooth.service",
+    "tailscaled.service",
+];
+
+/// Maximum number of search results shown at once.
+const SEARCH_RESULT_LIMIT: usize = 50;
+
+/// Minimum query length before searching all units.
+const SEARCH_MIN_CHARS: usize = 2;
+
+/// Result of a background state query.
+struct ServiceSnapshot {
+    curated: Vec<UnitDetails>,
+    results: Vec<UnitDetails>,
+    all_units: Vec<String>,
+    failed_count: Option<usize>,
+}
+
+/// Shared page state.
+struct ServicesPage {
+    window: ApplicationWindow,
+    content_box: GtkBox,
+    curated_list: ListBox,
+    search_list: ListBox,
+    failed_label: Label,
+    refresh_button: Button,
+    all_units: RefCell<Vec<String>>,
+    query: RefCell<String>,
+    /// Number of the latest query, older results are dropped
+    generation: Cell<u64>,
+}
+
+/// Set up all handlers for the services page.
+pub fn setup_handlers(
+    page_builder: &Builder,
+    _main_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    let page = Rc::new(ServicesPage {
+        window: window.clone(),
+        content_box: extract_widget(page_builder, "services_content_box")?,
+        curated_list: extract_widget(page_builder, "curated_services_list")?,
+        search_list: extract_widget(page_builder, "search_results_list")?,
+        failed_label: extract_widget(page_builder, "failed_units_label")?,
+        refresh_button: extract_widget(page_builder, "btn_refresh_services")?,
+        all_units: RefCell::new(Vec::new()),
+        query: RefCell::new(String::new()),
+        generation: Cell::new(0),
+    });
+
+    let page_clone = page.clone();
+    page.refresh_button.connect_clicked(move |_| {
+        info!("Refresh services button clicked");
+        refresh(&page_clone);
+    });
+
+    let search_entry = extract_widget::<SearchEntry>(page_builder, "service_search_entry")?;
+    let page_clone = page.clone();
+    search_entry.connect_search_changed(move |entry| {
+        *page_clone.query.borrow_mut() = entry.text().trim().to_lowercase();
+        refresh(&page_clone);
+    });
+
+    refresh(&page);
+    Ok(())
+}
+
+/// Re-query unit state in the background and repopulate both lists.
+fn refresh(page: &Rc<ServicesPage>) {
+    page.content_box.set_sensitive(false);
+    page.refresh_button.set_sensitive(false);
+
+    let generation = page.generation.get() + 1;
+    page.generation.set(generation);
+    let query = page.query.borrow().clone();
+    let cached_units = page.all_units.borrow().clone();
+    let (sender, receiver) = std::sync::mpsc::channel::<ServiceSnapshot>();
+
+    std::thread::spawn(move || {
+        let all_units = if cached_units.is_empty() {
+            match systemd::list_service_unit_files() {
+                Ok(files) => files.into_iter().map(|f| f.unit_file).collect(),
+                Err(e) => {
+                    warn!("Failed to list unit files: {}", e);
+                    Vec::new()
+                }
+            }
+        } else {
+            cached_units
+        };
+
+        let curated: Vec<&str> = CURATED_UNITS
+            .iter()
+            .copied()
+            .filter(|unit| all_units.iter().any(|u| u == unit))
+            .collect();
+
+        let matches: Vec<&str> = if query.chars().count() >= SEARCH_MIN_CHARS {
+            all_units
+                .iter()
+                .filter(|unit| unit.to_lowercase().contains(&query))
+                .filter(|unit| !unit.ends_with("@.service"))
+                .take(SEARCH_RESULT_LIMIT)
+                .map(String::as_str)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let details = |units: &[&str]| {
+            systemd::unit_details(units).unwrap_or_else(|e| {
+                warn!("Failed to query unit details: {}", e);
+                Vec::new()
+            })
+        };
+
+        let snapshot = ServiceSnapshot {
+            curated: details(&curated),
+            results: details(&matches),
+            failed_count: systemd::list_failed_units().ok().map(|units| units.len()),
+            all_units,
+        };
+
+        let _ = sender.send(snapshot);
+    });
+
+    let page = page.clone();
+    glib::timeout_add_local(
+        std::time::Duration::from_millis(100),
+        move || match receiver.try_recv() {
+            // A newer query owns the lists and the buttons
+            Ok(_) | Err(std::sync::mpsc::TryRecvError::Disconnected)
+                if page.generation.get() != generation =>
+            {
+                glib::ControlFlow::Break
+            }
+            Ok(snapshot) => {
+                *page.all_units.borrow_mut() = snapshot.all_units;
+                populate_list(&page, &page.curated_list, &snapshot.curated);
+                populate_list(&page, &page.search_list, &snapshot.results);
+                page.failed_label.set_label(
+                    &snapshot
+                        .failed_count
+                        .map_or_else(|| "-".to_string(), |count| count.to_string()),
+                );
+                page.content_box.set_sensitive(true);
+                page.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                warn!("Service query thread disconnected");
+                page.content_box.set_sensitive(true);
+                page.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+        },
+    );
+}
+
+/// Replace the rows of a list with the given units.
+fn populate_list(page: &Rc<ServicesPage>, list: &ListBox, units: &[UnitDetails]) {
+    while let Some(child) = list.first_child() {
+        list.remove(&child);
+    }
+
+    list.set_visible(!units.is_empty());
+
+    for unit in units {
+        list.append(&create_service_row(page, unit));
+    }
+}
+
+/// Build a row with enable and start switches for a unit.
+fn create_service_row(page: &Rc<ServicesPage>, unit: &UnitDetails) -> adw::ActionRow {
+    let subtitle = match &unit.state_change {
+        Some(since) => i18n::fill(
+            &gettext("{} · {} since {}"),
+            &[&unit.description, &unit.active_state, since],
+        ),
+        None => format!("{} · {}", unit.description, unit.active_state),
+    };
+
+    let row = adw::ActionRow::builder()
+        .title(glib::markup_escape_text(&unit.id))
+        .subtitle(glib::markup_escape_text(&subtitle))
+        .build();
+
+    let enabled_switch = Switch::builder()
+        .active(unit.is_enabled())
+        .sensitive(unit.is_toggleable())
+        .valign(gtk4::Align::Center)
+        .tooltip_text(gettext("Start at boot"))
+        .build();
+
+    let active_switch = Switch::builder()
+        .active(unit.is_active())
+        .valign(gtk4::Align::Center)
+        .tooltip_text(gettext("Running"))
+        .build();
+
+    row.add_suffix(&labeled(&enabled_switch, "Enabled"));
+    row.add_suffix(&labeled(&active_switch, "Running"));
+
+    let unit_id = unit.id.clone();
+    let page_clone = page.clone();
+    enabled_switch.connect_state_set(move |_, enable| {
+        let action = if enable { "enable" } else { "disable" };
+        run_systemctl(&page_clone, action, &unit_id);
+        glib::Propagation::Stop
+    });
+
+    let unit_id = unit.id.clone();
+    let page_clone = page.clone();
+    active_switch.connect_state_set(move |_, start| {
+        let action = if start { "start" } else { "stop" };
+        run_systemctl(&page_clone, action, &unit_id);
+        glib::Propagation::Stop
+    });
+
+    row
+}
+
+/// Wrap a switch with a small caption.
+fn labeled(switch: &Switch, caption: &str) -> GtkBox {
+    let container = GtkBox::new(gtk4::Orientation::Horizontal, 6);
+    container.set_margin_start(12);
+    let label = Label::new(Some(caption));
+    label.add_css_class("dim-label");
+    label.add_css_class("caption");
+    container.append(&label);
+    container.append(switch);
+    container
+}
+
+/// Run a privileged systemctl action and refresh state afterwards.
+fn run_systemctl(page: &Rc<ServicesPage>, action: &str, unit: &str) {
+    info!("Services: {} {}", action, unit);
+
+    // Another action owns the runner; snap the switches back to real state
+    if task_runner::busy() {
+        refresh(page);
+        return;
+    }
+
+    let commands = CommandSequence::new()
+        .then(
+            Command::builder()
+                .privileged()
+                .program("systemctl")
+                .args(&[action, unit])
+                .description(&i18n::fill(
+                    &gettext("Running systemctl {} {}..."),
+                    &[action, unit],
+                ))
+                .build(),
+        )
+        .build();
+
+    let page_clone = page.clone();
+    task_runner::run_with_completion(
+        page.window.upcast_ref(),
+        commands,
+        &gettext("Service Management"),
+        move |_| refresh(&page_clone),
+    );
+}
@@ -3,251 +3,2173 @@
 //! Handles:
 //! - Clear Pacman cache
 //! - Unlock Pacman database
-//! - Plasma X11 session installation
+//! - Plasma X11 session installation and default login session
 //! - Pacman local database fix
 //! - WayDroid guide
 //! - Fix GPGME database
 //! - Fix Arch keyring
-//! - Update mirrorlist
+//! - Update mirrorlist, with rate-mirrors or reflector, and restore a backup
 //! - Parallel downloads adjustment
+//! - ZRAM swap configuration
+//! - Firewall quick setup
+//! - Boot options editor
+//! - Initramfs rebuild after an interrupted update
+//! - Locale and timezone settings
+//! - User group membership
+//! - Scheduled maintenance
+//! - Bluetooth and audio quick fixes
 
 use crate::core;
+use crate::core::actions;
+use crate::core::boot::{self, BootConfig, BootSettings, Bootloader};
+use crate::core::distro;
+use crate::core::download::format_bytes;
+use crate::core::firewall::{self, FirewallBackend};
+use crate::core::groups::{self, GroupChanges};
+use crate::core::locale::{self, LocaleEntry};
+use crate::core::maintenance::{self, Cadence};
+use crate::core::memory::{self, SwapDevice, ZramConfig, ZramDevice};
+use crate::core::mirrorlist;
+use crate::core::pacman_conf;
+use crate::core::privileged_fs;
+use crate::core::setup::MIRROR_COUNTRIES;
+use crate::core::system_check::{self, Capability};
+use crate::i18n::{self, gettext};
+use crate::ui::click_guard::connect_clicked_once_until_done;
+use crate::ui::dialogs::diff;
 use crate::ui::dialogs::selection::{
     show_selection_dialog, SelectionDialogConfig, SelectionOption, SelectionType,
 };
 use crate::ui::dialogs::terminal;
+use crate::ui::dialogs::warning::show_typed_confirmation;
+use crate::ui::markup::Markup;
 use crate::ui::task_runner::{self, Command, CommandSequence};
-use crate::ui::utils::extract_widget;
-use gtk4::prelude::*;
-use gtk4::{ApplicationWindow, Builder};
-use log::info;
+use crate::ui::utils::{extract_widget, require_capabilities, WidgetError};
+use adw::prelude::*;
+use gtk4::glib;
+use gtk4::{ApplicationWindow, Builder, Button};
+use log::{info, warn};
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::rc::Rc;
 
 /// Set up all button handlers for the servicing/system tweaks page
-pub fn setup_handlers(page_builder: &Builder, _main_builder: &Builder, window: &ApplicationWindow) {
-    setup_clr_pacman(page_builder, window);
-    setup_unlock_pacman(page_builder, window);
-    setup_plasma_x11(page_builder, window);
-    setup_pacman_db_fix(page_builder, window);
-    setup_waydroid_guide(page_builder);
-    setup_fix_gpgme(page_builder, window);
-    setup_fix_arch_keyring(page_builder, window);
-    setup_update_mirrorlist(page_builder, window);
-    setup_parallel_downloads(page_builder, window);
-}
-
-fn setup_clr_pacman(page_builder: &Builder, window: &ApplicationWindow) {
-    let btn_clr_pacman = extract_widget::<gtk4::Button>(page_builder, "btn_clr_pacman");
-    let window = window.clone();
-    btn_clr_pacman.connect_clicked(move |_| {
+pub fn setup_handlers(
+    page_builder: &Builder,
+    _main_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    setup_clr_pacman(page_builder, window)?;
+    setup_unlock_pacman(page_builder, window)?;
+    setup_plasma_x11(page_builder, window)?;
+    setup_pacman_db_fix(page_builder, window)?;
+    setup_waydroid_guide(page_builder)?;
+    setup_fix_gpgme(page_builder, window)?;
+    setup_fix_arch_keyring(page_builder, window)?;
+    setup_update_mirrorlist(page_builder, window)?;
+    setup_restore_mirrorlist(page_builder, window)?;
+    setup_rebuild_initramfs(page_builder, window)?;
+    setup_parallel_downloads(page_builder, window)?;
+    setup_zram(page_builder, window)?;
+    setup_firewall(page_builder, window)?;
+    setup_boot_options(page_builder, window)?;
+    setup_locale(page_builder, window)?;
+    setup_user_groups(page_builder, window)?;
+    setup_maintenance(page_builder, window)?;
+    setup_bluetooth(page_builder, window)?;
+    setup_audio(page_builder, window)?;
+
+    require_capabilities(
+        page_builder,
+        &[Capability::Pacman, Capability::Escalate],
+        &[
+            "btn_clr_pacman",
+            "btn_unlock_pacman",
+            "btn_plasma_x11",
+            "btn_pacman_db_fix",
+            "btn_fix_gpgme",
+            "btn_fix_arch_keyring",
+            "btn_update_mirrorlist",
+            "btn_restore_mirrorlist",
+            "btn_parallel_downloads",
+        ],
+    )?;
+    require_capabilities(
+        page_builder,
+        &[
+            Capability::Pacman,
+            Capability::Services,
+            Capability::Escalate,
+        ],
+        &["btn_zram", "btn_firewall"],
+    )?;
+    require_capabilities(
+        page_builder,
+        &[Capability::Escalate],
+        &[
+            "btn_boot_options",
+            "btn_rebuild_initramfs",
+            "btn_locale",
+            "btn_user_groups",
+        ],
+    )?;
+    require_capabilities(
+        page_builder,
+        &[Capability::Services, Capability::Escalate],
+        &["btn_maintenance"],
+    )?;
+    require_capabilities(
+        page_builder,
+        &[
+            Capability::Pacman,
+            Capability::Services,
+            Capability::Escalate,
+        ],
+        &["btn_bluetooth_setup", "btn_bluetooth_restart"],
+    )?;
+    require_capabilities(
+        page_builder,
+        &[Capability::Pacman, Capability::Escalate],
+        &["btn_audio_setup"],
+    )?;
+    require_capabilities(
+        page_builder,
+        &[Capability::Services],
+        &["btn_audio_restart"],
+    )?;
+    Ok(())
+}
+
+fn setup_clr_pacman(page_builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let btn_clr_pacman = extract_widget::<gtk4::Button>(page_builder, "btn_clr_pacman")?;
+    let window = window.clone();
+    connect_clicked_once_until_done(&btn_clr_pacman, move |_, token| {
         info!("Servicing: Clear Pacman Cache button clicked");
         // Use terminal dialog for interactive pacman cache clearing
-        terminal::show_terminal_dialog(
+        let dialog = terminal::show_terminal_dialog(
             window.upcast_ref(),
-            "Clear Pacman Cache",
+            &gettext("Clear Pacman Cache"),
             "pkexec",
             &["pacman", "-Scc"],
             false,
         );
+        token.release_on_close(&dialog);
     });
+    Ok(())
+}
+
+/// Run a registered action in the task runner.
+fn run_action(window: &ApplicationWindow, action: &actions::Action) {
+    task_runner::run(
+        window.upcast_ref(),
+        action.commands(),
+        &gettext(action.title),
+    );
 }
 
-fn setup_unlock_pacman(page_builder: &Builder, window: &ApplicationWindow) {
-    let btn_unlock_pacman = extract_widget::<gtk4::Button>(page_builder, "btn_unlock_pacman");
+fn setup_unlock_pacman(
+    page_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    let btn_unlock_pacman = extract_widget::<gtk4::Button>(page_builder, "btn_unlock_pacman")?;
     let window = window.clone();
     btn_unlock_pacman.connect_clicked(move |_| {
         info!("Servicing: Unlock Pacman DB button clicked");
-        let commands = CommandSequence::new()
-            .then(
-                Command::builder()
-                    .privileged()
-                    .program("rm")
-                    .args(&["-f", "/var/lib/pacman/db.lck"])
-                    .description("Removing Pacman lock file...")
-                    .build(),
-            )
-            .build();
-        task_runner::run(window.upcast_ref(), commands, "Unlock Pacman Database");
+        run_action(&window, &actions::UNLOCK_PACMAN);
     });
+    Ok(())
 }
 
-fn setup_plasma_x11(page_builder: &Builder, window: &ApplicationWindow) {
-    let btn_plasma_x11 = extract_widget::<gtk4::Button>(page_builder, "btn_plasma_x11");
+fn setup_plasma_x11(page_builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let btn_plasma_x11 = extract_widget::<gtk4::Button>(page_builder, "btn_plasma_x11")?;
     let window = window.clone();
     btn_plasma_x11.connect_clicked(move |_| {
         info!("Servicing: Plasma X11 Session button clicked");
-        let commands = CommandSequence::new()
-            .then(
-                Command::builder()
-                    .aur()
-                    .args(&["-S", "--noconfirm", "kwin-x11", "plasma-x11-session"])
-                    .description("Installing KDE Plasma X11 session components...")
-                    .build(),
-            )
+        if system_check::has_plasma() {
+            install_plasma_x11(&window);
+            return;
+        }
+
+        let desktop = &distro::session().desktop;
+        warn!("Plasma X11 session asked for on {:?}", desktop);
+        let dialog = adw::AlertDialog::builder()
+            .heading(gettext("KDE Plasma Not Found"))
+            .body(i18n::fill(
+                &gettext(
+                    "The X11 session is part of KDE Plasma, but this session runs {} and \
+                     Plasma is not installed. Installing it only adds the X11 components.",
+                ),
+                &[&desktop.name()],
+            ))
             .build();
-        task_runner::run(window.upcast_ref(), commands, "Install KDE X11 Session");
+        dialog.add_response("cancel", &gettext("Cancel"));
+        dialog.add_response("install", &gettext("Install Anyway"));
+        dialog.set_response_appearance("install", adw::ResponseAppearance::Destructive);
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_close_response("cancel");
+
+        let window = window.clone();
+        glib::spawn_future_local(async move {
+            if dialog.choose_future(Some(&window)).await == "install" {
+                install_plasma_x11(&window);
+            }
+        });
     });
+    Ok(())
 }
 
-fn setup_pacman_db_fix(page_builder: &Builder, window: &ApplicationWindow) {
-    let btn_pacman_db_fix = extract_widget::<gtk4::Button>(page_builder, "btn_pacman_db_fix");
+/// Install the Plasma X11 session, then offer to make it the default. An
+/// installed session goes straight to the offer.
+fn install_plasma_x11(window: &ApplicationWindow) {
+    if core::is_package_installed("plasma-x11-session") {
+        offer_default_session(window);
+        return;
+    }
+
+    let window_for_offer = window.clone();
+    task_runner::run_with_completion(
+        window.upcast_ref(),
+        actions::PLASMA_X11_SESSION.commands(),
+        &gettext(actions::PLASMA_X11_SESSION.title),
+        move |success| {
+            if success {
+                offer_default_session(&window_for_offer);
+            }
+        },
+    );
+}
+
+/// Ask whether SDDM should preselect the X11 session, or no longer do so
+/// once it does.
+fn offer_default_session(window: &ApplicationWindow) {
+    let configured = Path::new(actions::SDDM_DEFAULT_SESSION).exists();
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext("Log In to X11 by Default?"))
+        .body(if configured {
+            gettext(
+                "SDDM preselects the Plasma X11 session. Reset it to preselect the \
+                 session you used last instead.",
+            )
+        } else {
+            gettext(
+                "SDDM preselects the session you used last. It can preselect the Plasma \
+                 X11 session instead; you can still pick another one at login.",
+            )
+        })
+        .build();
+    dialog.add_response("cancel", &gettext("Not Now"));
+    if configured {
+        dialog.add_response("revert", &gettext("Reset Default"));
+        dialog.set_response_appearance("revert", adw::ResponseAppearance::Destructive);
+    } else {
+        dialog.add_response("default", &gettext("Make Default"));
+        dialog.set_response_appearance("default", adw::ResponseAppearance::Suggested);
+    }
+    dialog.set_close_response("cancel");
+
+    let window = window.clone();
+    glib::spawn_future_local(async move {
+        let commands = match dialog.choose_future(Some(&window)).await.as_str() {
+            "default" => actions::default_x11_session_commands(),
+            "revert" => actions::revert_default_session_commands(),
+            _ => return,
+        };
+        task_runner::run(
+            window.upcast_ref(),
+            commands.build(),
+            &gettext("Default Login Session"),
+        );
+    });
+}
+
+fn setup_pacman_db_fix(
+    page_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    let btn_pacman_db_fix = extract_widget::<gtk4::Button>(page_builder, "btn_pacman_db_fix")?;
     let window = window.clone();
     btn_pacman_db_fix.connect_clicked(move |_| {
         info!("Servicing: Pacman DB Fix button clicked");
-        let commands = CommandSequence::new()
-            .then(
-                Command::builder()
-                    .privileged()
-                    .program("sh")
-                    .args(&[
-                        "-c",
-                        "find /var/lib/pacman/local/ -type f -name 'desc' -exec sed -i '/^%INSTALLED_DB%$/,+2d' {} \\;",
-                    ])
-                    .description("Fixing Pacman local database...")
-                    .build(),
-            )
-            .build();
-        task_runner::run(window.upcast_ref(), commands, "Pacman DB Fix");
+        run_action(&window, &actions::FIX_PACMAN_DB);
     });
+    Ok(())
 }
 
-fn setup_waydroid_guide(page_builder: &Builder) {
-    let btn_waydroid_guide = extract_widget::<gtk4::Button>(page_builder, "btn_waydroid_guide");
+fn setup_waydroid_guide(page_builder: &Builder) -> Result<(), WidgetError> {
+    let btn_waydroid_guide = extract_widget::<gtk4::Button>(page_builder, "btn_waydroid_guide")?;
     btn_waydroid_guide.connect_clicked(move |_| {
         info!("Servicing: WayDroid Guide button clicked - opening guide");
         let _ = std::process::Command::new("xdg-open")
             .arg("https://xerolinux.xyz/posts/waydroid-guide/")
             .spawn();
     });
+    Ok(())
 }
 
-fn setup_fix_gpgme(page_builder: &Builder, window: &ApplicationWindow) {
-    let btn_fix_gpgme = extract_widget::<gtk4::Button>(page_builder, "btn_fix_gpgme");
+fn setup_fix_gpgme(page_builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let btn_fix_gpgme = extract_widget::<gtk4::Button>(page_builder, "btn_fix_gpgme")?;
     let window = window.clone();
-    btn_fix_gpgme.connect_clicked(move |_| {
+    connect_clicked_once_until_done(&btn_fix_gpgme, move |_, token| {
         info!("Servicing: Fix GPGME Database button clicked");
         // Use terminal dialog for interactive GPGME fix
-        terminal::show_terminal_dialog(
+        let dialog = terminal::show_terminal_dialog(
             window.upcast_ref(),
-            "Fix GPGME Database",
+            &gettext("Fix GPGME Database"),
             "pkexec",
             &["sh", "-c", "rm -rf /var/lib/pacman/sync && pacman -Syy"],
             false,
         );
+        token.release_on_close(&dialog);
     });
+    Ok(())
 }
 
-fn setup_fix_arch_keyring(page_builder: &Builder, window: &ApplicationWindow) {
-    let btn_fix_arch_keyring = extract_widget::<gtk4::Button>(page_builder, "btn_fix_arch_keyring");
+fn setup_fix_arch_keyring(
+    page_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    let btn_fix_arch_keyring =
+        extract_widget::<gtk4::Button>(page_builder, "btn_fix_arch_keyring")?;
     let window = window.clone();
     btn_fix_arch_keyring.connect_clicked(move |_| {
         info!("Servicing: Fix Arch Keyring button clicked");
-        let commands = CommandSequence::new()
-            .then(Command::builder()
+        run_action(&window, &actions::FIX_ARCH_KEYRING);
+    });
+    Ok(())
+}
+
+fn setup_update_mirrorlist(
+    page_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    let btn_update_mirrorlist =
+        extract_widget::<gtk4::Button>(page_builder, "btn_update_mirrorlist")?;
+    let window = window.clone();
+    connect_clicked_once_until_done(&btn_update_mirrorlist, move |_, token| {
+        info!("Servicing: Update Mirrorlist button clicked");
+        let backend_row = mirror_backend_row();
+        let region_row = mirror_region_row();
+        let protocol_row = mirror_protocol_row();
+        let region_list = gtk4::ListBox::new();
+        region_list.add_css_class("boxed-list");
+        region_list.set_selection_mode(gtk4::SelectionMode::None);
+        region_list.append(&backend_row);
+        region_list.append(&region_row);
+        region_list.append(&protocol_row);
+
+        let config = SelectionDialogConfig::new(
+            &gettext("Update Mirrorlist"),
+            gettext(
+                "Select which mirrorlists to update. The ranking tool will be installed if needed \
+                 and the current mirrorlist is backed up first.",
+            ),
+        )
+        .selection_type(SelectionType::Single)
+        .selection_required(false)
+        .extra_widget(&region_list)
+        .add_option(SelectionOption::new(
+            "chaotic",
+            &gettext("Chaotic-AUR Mirrorlist"),
+            gettext("Also update Chaotic-AUR mirrorlist (optional)"),
+            false,
+        ))
+        .confirm_label(&gettext("Update"));
+
+        let window_for_closure = window.clone();
+        let dialog = show_selection_dialog(window.upcast_ref(), config, move |selected_ids| {
+            // Index 0 ranks mirrors globally
+            let region = (region_row.selected() as usize)
+                .checked_sub(1)
+                .and_then(|index| MIRROR_COUNTRIES.get(index))
+                .map(|(code, _)| code.to_string());
+            crate::config::user::shared()
+                .update(|config| config.downloads.mirror_region = region.clone());
+
+            let backend = mirrorlist::Backend::ALL
+                .get(backend_row.selected() as usize)
+                .copied()
+                .unwrap_or_default();
+            let protocol = if protocol_row.is_active() {
+                mirrorlist::Protocol::Any
+            } else {
+                mirrorlist::Protocol::Https
+            };
+            let include_chaotic = selected_ids.iter().any(|id| id == "chaotic");
+            let window_for_summary = window_for_closure.clone();
+            task_runner::run_with_completion(
+                window_for_closure.upcast_ref(),
+                actions::mirrorlist_commands(include_chaotic, region.as_deref(), backend, protocol),
+                &gettext(actions::UPDATE_MIRRORLIST.title),
+                move |success| {
+                    if success {
+                        show_top_mirrors(&window_for_summary);
+                    }
+                },
+            );
+        });
+        token.release_on_close(&dialog);
+    });
+    Ok(())
+}
+
+/// Row picking the tool ranking the mirrors, in [`mirrorlist::Backend::ALL`]
+/// order.
+fn mirror_backend_row() -> adw::ComboRow {
+    let backends = gtk4::StringList::new(&[]);
+    for backend in mirrorlist::Backend::ALL {
+        backends.append(backend.package());
+    }
+    adw::ComboRow::builder()
+        .title(gettext("Ranking Tool"))
+        .subtitle(gettext("reflector ranks the Arch mirrors only"))
+        .model(&backends)
+        .build()
+}
+
+/// Switch letting plain HTTP mirrors in.
+fn mirror_protocol_row() -> adw::SwitchRow {
+    adw::SwitchRow::builder()
+        .title(gettext("Allow HTTP Mirrors"))
+        .subtitle(gettext(
+            "More mirrors to choose from; packages are still signature checked",
+        ))
+        .build()
+}
+
+fn setup_restore_mirrorlist(
+    page_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    let btn_restore_mirrorlist =
+        extract_widget::<gtk4::Button>(page_builder, "btn_restore_mirrorlist")?;
+    let window = window.clone();
+    connect_clicked_once_until_done(&btn_restore_mirrorlist, move |_, token| {
+        info!("Servicing: Restore Mirrorlist button clicked");
+        let backups = mirrorlist::backups(Path::new(mirrorlist::ARCH_MIRRORLIST));
+        if backups.is_empty() {
+            let dialog = adw::AlertDialog::builder()
+                .heading(gettext("No Mirrorlist Backups"))
+                .body(gettext(
+                    "Updating the mirrorlist saves a copy of the previous one to restore here.",
+                ))
+                .build();
+            dialog.add_response("close", &gettext("Close"));
+            dialog.present(Some(&window));
+            return;
+        }
+
+        let mut config = SelectionDialogConfig::new(
+            &gettext("Restore Mirrorlist"),
+            gettext("Replace the current mirrorlist with a copy saved before an update."),
+        )
+        .selection_type(SelectionType::Single)
+        .confirm_label(&gettext("Restore"));
+        for backup in &backups {
+            let servers = mirrorlist::ranked_mirrors(
+                &std::fs::read_to_string(&backup.path).unwrap_or_default(),
+                1,
+            );
+            let description = match servers.first() {
+                Some(server) => i18n::fill(&gettext("First mirror: {}"), &[server]),
+                None => gettext("No servers"),
+            };
+            config = config.add_option(SelectionOption::new(
+                &backup.stamp,
+                &backup.date(),
+                description,
+                false,
+            ));
+        }
+
+        let window_for_closure = window.clone();
+        let dialog = show_selection_dialog(window.upcast_ref(), config, move |selected_ids| {
+            let Some(backup) = selected_ids
+                .first()
+                .and_then(|id| backups.iter().find(|backup| &backup.stamp == id))
+            else {
+                return;
+            };
+            let window_for_summary = window_for_closure.clone();
+            task_runner::run_with_completion(
+                window_for_closure.upcast_ref(),
+                actions::restore_mirrorlist_commands(&backup.path),
+                &gettext("Restore Mirrorlist"),
+                move |success| {
+                    if success {
+                        show_top_mirrors(&window_for_summary);
+                    }
+                },
+            );
+        });
+        token.release_on_close(&dialog);
+    });
+    Ok(())
+}
+
+/// Row picking the country mirrors are ranked around, set to the saved one.
+fn mirror_region_row() -> adw::ComboRow {
+    let regions = gtk4::StringList::new(&[&gettext("Auto")]);
+    for (_, name) in MIRROR_COUNTRIES {
+        regions.append(&gettext(name));
+    }
+
+    let saved = crate::config::user::shared()
+        .get()
+        .downloads
+        .mirror_region
+        .clone();
+    let selected = saved
+        .and_then(|code| {
+            MIRROR_COUNTRIES
+                .iter()
+                .position(|(country, _)| *country == code)
+        })
+        .map_or(0, |index| index + 1);
+
+    adw::ComboRow::builder()
+        .title(gettext("Mirror Region"))
+        .subtitle(gettext(
+            "Rank mirrors around this country, or worldwide with Auto",
+        ))
+        .model(&regions)
+        .selected(selected as u32)
+        .build()
+}
+
+/// Show the mirrors pacman now tries first.
+fn show_top_mirrors(window: &ApplicationWindow) {
+    let mirrors = mirrorlist::top_mirrors();
+    if mirrors.is_empty() {
+        warn!("Updated mirrorlist has no servers to show");
+        return;
+    }
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext("Mirrorlist Updated"))
+        .body(i18n::fill(
+            &gettext("Pacman now tries these mirrors first:\n\n{}"),
+            &[&mirrors.join("\n")],
+        ))
+        .build();
+    dialog.add_response("close", &gettext("Close"));
+    dialog.present(Some(window));
+}
+
+fn setup_parallel_downloads(
+    page_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    let btn_parallel_downloads =
+        extract_widget::<gtk4::Button>(page_builder, "btn_parallel_downloads")?;
+    let window = window.clone();
+    btn_parallel_downloads.connect_clicked(move |_| {
+        info!("Servicing: Change Parallel Downloads button clicked");
+        let current = pacman_conf::read_parallel_downloads().unwrap_or_else(|e| {
+            warn!("{:#}", e);
+            None
+        });
+        show_parallel_downloads_dialog(&window, current);
+    });
+    Ok(())
+}
+
+/// Offer the number of packages pacman downloads at once.
+fn show_parallel_downloads_dialog(window: &ApplicationWindow, current: Option<u32>) {
+    let count_row =
+        adw::SpinRow::with_range(1.0, f64::from(pacman_conf::MAX_PARALLEL_DOWNLOADS), 1.0);
+    count_row.set_title(&gettext("Packages at Once"));
+    count_row.set_value(f64::from(
+        current
+            .unwrap_or(1)
+            .min(pacman_conf::MAX_PARALLEL_DOWNLOADS),
+    ));
+    let group = adw::PreferencesGroup::new();
+    group.add(&count_row);
+
+    let body = match current {
+        Some(count) => i18n::fill(
+            &gettext("pacman downloads up to {} packages at once."),
+            &[&count.to_string()],
+        ),
+        None => gettext("pacman downloads one package at a time."),
+    };
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext("Parallel Downloads"))
+        .body(body)
+        .extra_child(&group)
+        .build();
+    dialog.add_response("cancel", &gettext("Cancel"));
+    dialog.add_response("apply", &gettext("Apply"));
+    dialog.set_response_appearance("apply", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("apply"));
+    dialog.set_close_response("cancel");
+
+    let window_clone = window.clone();
+    dialog.connect_response(Some("apply"), move |_, _| {
+        set_parallel_downloads(&window_clone, count_row.value() as u32);
+    });
+
+    dialog.present(Some(window));
+}
+
+fn set_parallel_downloads(window: &ApplicationWindow, count: u32) {
+    info!("Setting parallel downloads to {}", count);
+    let edit = match pacman_conf::parallel_downloads_edit(count) {
+        Ok(edit) => edit,
+        Err(e) => {
+            warn!("{:#}", e);
+            crate::ui::dialogs::error::show_error(window, &format!("{:#}", e));
+            return;
+        }
+    };
+    let commands = CommandSequence::new()
+        .then(edit.command(&i18n::fill(
+            &gettext("Setting parallel downloads to {}..."),
+            &[&count.to_string()],
+        )))
+        .build();
+    task_runner::run(
+        window.upcast_ref(),
+        commands,
+        &gettext("Change Parallel Downloads"),
+    );
+}
+
+fn setup_zram(page_builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let btn_zram = extract_widget::<gtk4::Button>(page_builder, "btn_zram")?;
+    let window = window.clone();
+    btn_zram.connect_clicked(move |_| {
+        info!("Servicing: ZRAM Swap button clicked");
+        let swaps = memory::active_swaps().unwrap_or_else(|e| {
+            warn!("{}", e);
+            Vec::new()
+        });
+        show_zram_dialog(&window, &swaps, &memory::zram_devices());
+    });
+    Ok(())
+}
+
+/// Describe current swap and offer zram size and algorithm choices.
+fn show_zram_dialog(window: &ApplicationWindow, swaps: &[SwapDevice], zram: &[ZramDevice]) {
+    let mut body = if swaps.is_empty() {
+        gettext("No swap is currently active.")
+    } else {
+        let lines: Vec<String> = swaps
+            .iter()
+            .map(|swap| {
+                let algorithm = zram
+                    .iter()
+                    .find(|device| device.name == swap.name)
+                    .map(|device| format!(", {}", device.algorithm))
+                    .unwrap_or_default();
+                format!(
+                    "• {} ({}, {}{})",
+                    swap.name,
+                    swap.kind,
+                    format_bytes(swap.size * 1024),
+                    algorithm
+                )
+            })
+            .collect();
+        format!("{}\n{}", gettext("Active swap:"), lines.join("\n"))
+    };
+    body.push_str("\n\n");
+    body.push_str(&gettext(
+        "zram-generator creates a compressed swap device in RAM, \
+         which is much faster than swapping to disk.",
+    ));
+    if swaps.iter().any(|swap| !swap.is_zram()) {
+        body.push(' ');
+        body.push_str(&gettext(
+            "Existing disk swap stays active and is only used once zram is full.",
+        ));
+    }
+
+    let size_labels: Vec<String> = memory::ZRAM_SIZE_PERCENTS
+        .iter()
+        .map(|percent| i18n::fill(&gettext("{}% of RAM"), &[&percent.to_string()]))
+        .collect();
+    let size_labels: Vec<&str> = size_labels.iter().map(String::as_str).collect();
+    let size_row = adw::ComboRow::builder()
+        .title(gettext("Size"))
+        .model(&gtk4::StringList::new(&size_labels))
+        .selected(1)
+        .build();
+    let algorithm_row = adw::ComboRow::builder()
+        .title(gettext("Compression"))
+        .model(&gtk4::StringList::new(memory::ZRAM_ALGORITHMS))
+        .build();
+
+    let group = adw::PreferencesGroup::new();
+    group.add(&size_row);
+    group.add(&algorithm_row);
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext("ZRAM Swap"))
+        .body(body)
+        .extra_child(&group)
+        .build();
+    dialog.add_response("cancel", &gettext("Cancel"));
+    if memory::zram_configured() {
+        dialog.add_response("disable", &gettext("Disable"));
+        dialog.set_response_appearance("disable", adw::ResponseAppearance::Destructive);
+    }
+    dialog.add_response("enable", &gettext("Enable"));
+    dialog.set_response_appearance("enable", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("enable"));
+    dialog.set_close_response("cancel");
+
+    let window_clone = window.clone();
+    dialog.connect_response(None, move |_, response| match response {
+        "enable" => {
+            let config = ZramConfig {
+                size_percent: memory::ZRAM_SIZE_PERCENTS[size_row.selected() as usize],
+                algorithm: memory::ZRAM_ALGORITHMS[algorithm_row.selected() as usize].to_string(),
+            };
+            enable_zram(&window_clone, &config);
+        }
+        "disable" => disable_zram(&window_clone),
+        _ => {}
+    });
+
+    dialog.present(Some(window));
+}
+
+fn enable_zram(window: &ApplicationWindow, config: &ZramConfig) {
+    info!("Enabling zram: {:?}", config);
+    let commands = CommandSequence::new()
+        .then(
+            Command::builder()
+                .aur()
+                .args(&["-S", "--needed", "zram-generator"])
+                .description(&gettext("Installing zram-generator..."))
+                .build(),
+        )
+        .then(
+            privileged_fs::write_file(memory::ZRAM_CONFIG_PATH, &config.render())
+                .command(&gettext("Writing zram-generator configuration...")),
+        )
+        .then(
+            Command::builder()
                 .privileged()
-                .program("rm")
-                .args(&["-rf", "/etc/pacman.d/gnupg"])
-                .description("Removing existing GnuPG keyring...")
-                .build())
-            .then(Command::builder()
+                .program("systemctl")
+                .args(&["daemon-reload"])
+                .description(&gettext("Reloading systemd units..."))
+                .build(),
+        )
+        .then(
+            Command::builder()
                 .privileged()
-                .program("pacman-key")
-                .args(&["--init"])
-                .description("Initializing new keyring...")
-                .build())
-            .then(Command::builder()
+                .program("systemctl")
+                .args(&["restart", memory::ZRAM_UNIT])
+                .description(&gettext("Starting zram device..."))
+                .build(),
+        )
+        .then(
+            Command::builder()
+                .normal()
+                .program("swapon")
+                .args(&["--show"])
+                .description(&gettext("Verifying active swap..."))
+                .build(),
+        )
+        .build();
+    task_runner::run(window.upcast_ref(), commands, &gettext("Enable ZRAM Swap"));
+}
+
+fn disable_zram(window: &ApplicationWindow) {
+    info!("Disabling zram");
+    let commands = CommandSequence::new()
+        .then(
+            Command::builder()
                 .privileged()
-                .program("pacman-key")
-                .args(&["--populate"])
-                .description("Populating keyring...")
-                .build())
-            .then(Command::builder()
+                .program("systemctl")
+                .args(&["stop", memory::ZRAM_UNIT])
+                .description(&gettext("Stopping zram device..."))
+                .build(),
+        )
+        .then(
+            Command::builder()
                 .privileged()
-                .program("sh")
-                .args(&["-c", "echo 'keyserver hkp://keyserver.ubuntu.com:80' >> /etc/pacman.d/gnupg/gpg.conf"])
-                .description("Setting keyserver...")
-                .build())
-            .then(Command::builder()
+                .program("rm")
+                .args(&["-f", memory::ZRAM_CONFIG_PATH])
+                .description(&gettext("Removing zram-generator configuration..."))
+                .build(),
+        )
+        .then(
+            Command::builder()
                 .privileged()
-                .program("pacman")
-                .args(&["-Syy", "--noconfirm", "archlinux-keyring"])
-                .description("Reinstalling Arch Linux keyring...")
-                .build())
-            .build();
-        task_runner::run(window.upcast_ref(), commands, "Fix GnuPG Keyring");
-    });
+                .program("systemctl")
+                .args(&["daemon-reload"])
+                .description(&gettext("Reloading systemd units..."))
+                .build(),
+        )
+        .then(
+            Command::builder()
+                .normal()
+                .program("swapon")
+                .args(&["--show"])
+                .description(&gettext("Verifying active swap..."))
+                .build(),
+        )
+        .build();
+    task_runner::run(window.upcast_ref(), commands, &gettext("Disable ZRAM Swap"));
 }
 
-fn setup_update_mirrorlist(page_builder: &Builder, window: &ApplicationWindow) {
-    let btn_update_mirrorlist =
-        extract_widget::<gtk4::Button>(page_builder, "btn_update_mirrorlist");
+fn setup_firewall(page_builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let btn_firewall = extract_widget::<gtk4::Button>(page_builder, "btn_firewall")?;
     let window = window.clone();
-    btn_update_mirrorlist.connect_clicked(move |_| {
-        info!("Servicing: Update Mirrorlist button clicked");
-        let window_ref = window.upcast_ref();
+    connect_clicked_once_until_done(&btn_firewall, move |_, token| {
+        info!("Servicing: Firewall Setup button clicked");
 
-                let rate_mirrors_installed = core::is_package_installed("rate-mirrors");
-                let config = SelectionDialogConfig::new(
-                    "Update Mirrorlist",
-                    "Select which mirrorlists to update. rate-mirrors will be installed if needed.",
-                )
-                .selection_type(SelectionType::Single)
+        let backend = firewall::choose_backend(
+            firewall::detect_active(),
+            core::is_package_installed(FirewallBackend::Ufw.package()),
+            core::is_package_installed(FirewallBackend::Firewalld.package()),
+        );
+        let installed = core::is_package_installed(backend.package());
+
+        let description = match backend {
+            FirewallBackend::Ufw => {
+                "Sets up ufw to deny incoming and allow outgoing connections. \
+                 Select any services that should stay reachable from your network."
+            }
+            FirewallBackend::Firewalld => {
+                "firewalld is already installed and will be kept. \
+                 Select any services that should stay reachable from your network."
+            }
+        };
+
+        let config = firewall::ALLOWANCES.iter().fold(
+            SelectionDialogConfig::new(&gettext("Firewall Setup"), description)
                 .selection_required(false)
-                .add_option(SelectionOption::new(
-                    "chaotic",
-                    "Chaotic-AUR Mirrorlist",
-                    "Also update Chaotic-AUR mirrorlist (optional)",
+                .confirm_label(&gettext("Apply")),
+            |config, allowance| {
+                config.add_option(SelectionOption::new(
+                    allowance.id,
+                    allowance.label,
+                    allowance.description,
                     false,
                 ))
-                .confirm_label("Update");
-
-                let window_for_closure = window.clone();
-                show_selection_dialog(window_ref, config, move |selected_ids| {
-                    let mut commands = CommandSequence::new();
-
-                    if !rate_mirrors_installed {
-                        commands = commands.then(Command::builder()
-                             .aur()
-                             .args(&["-S", "--needed", "--noconfirm", "rate-mirrors"])
-                             .description("Installing rate-mirrors utility...")
-                             .build());
-                    }
+            },
+        );
+
+        let window_for_closure = window.clone();
+        let dialog = show_selection_dialog(window.upcast_ref(), config, move |selected_ids| {
+            let allowances: Vec<_> = firewall::ALLOWANCES
+                .iter()
+                .filter(|allowance| selected_ids.iter().any(|id| id == allowance.id))
+                .collect();
+
+            let mut commands = CommandSequence::new();
 
-                    commands = commands.then(Command::builder()
+            if !installed {
+                commands = commands.then(
+                    Command::builder()
+                        .aur()
+                        .args(&["-S", "--needed", backend.package()])
+                        .description(&i18n::fill(
+                            &gettext("Installing {}..."),
+                            &[backend.package()],
+                        ))
+                        .build(),
+                );
+            }
+
+            for step in backend.setup_plan(&allowances) {
+                let args: Vec<&str> = step.args.iter().map(String::as_str).collect();
+                commands = commands.then(
+                    Command::builder()
                         .privileged()
-                        .program("sh")
-                        .args(&["-c", "rate-mirrors --allow-root --protocol https arch | tee /etc/pacman.d/mirrorlist"])
-                        .description("Updating Arch mirrorlist...")
-                        .build());
-
-                    if selected_ids.iter().any(|s| s == "chaotic") {
-                        commands = commands.then(Command::builder()
-                            .privileged()
-                            .program("sh")
-                            .args(&["-c", "rate-mirrors --allow-root --protocol https chaotic-aur | tee /etc/pacman.d/chaotic-mirrorlist"])
-                            .description("Updating Chaotic-AUR mirrorlist...")
-                            .build());
-                    }
+                        .program(step.program)
+                        .args(&args)
+                        .description(&step.description)
+                        .build(),
+                );
+            }
 
-                    if !commands.is_empty() {
-                        task_runner::run(window_for_closure.upcast_ref(), commands.build(), "Update System Mirrorlist");
-                    }
-                });
+            let (program, args) = backend.status_command();
+            commands = commands.then(
+                Command::builder()
+                    .privileged()
+                    .program(program)
+                    .args(args)
+                    .description(&gettext("Verifying firewall configuration..."))
+                    .build(),
+            );
+
+            task_runner::run(
+                window_for_closure.upcast_ref(),
+                commands.build(),
+                &gettext("Firewall Setup"),
+            );
+        });
+        token.release_on_close(&dialog);
     });
+    Ok(())
 }
 
-fn setup_parallel_downloads(page_builder: &Builder, window: &ApplicationWindow) {
-    let btn_parallel_downloads =
-        extract_widget::<gtk4::Button>(page_builder, "btn_parallel_downloads");
+fn setup_boot_options(
+    page_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    let btn_boot_options = extract_widget::<gtk4::Button>(page_builder, "btn_boot_options")?;
     let window = window.clone();
-    btn_parallel_downloads.connect_clicked(move |_| {
-        info!("Servicing: Change Parallel Downloads button clicked");
-        // Use terminal dialog for interactive pmpd tool
-        terminal::show_terminal_dialog(
-            window.upcast_ref(),
-            "Change Parallel Downloads",
-            "pkexec",
-            &["pmpd"],
-            false,
+    btn_boot_options.connect_clicked(move |_| {
+        info!("Servicing: Boot Options button clicked");
+        open_boot_options(&window, &[]);
+    });
+    Ok(())
+}
+
+fn setup_rebuild_initramfs(
+    page_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    let button = extract_widget::<gtk4::Button>(page_builder, "btn_rebuild_initramfs")?;
+    let window = window.clone();
+    button.connect_clicked(move |_| {
+        info!("Servicing: Rebuild Initramfs button clicked");
+        confirm_rebuild_initramfs(&window);
+    });
+    Ok(())
+}
+
+/// Ask for the typed confirmation of the initramfs rebuild, listing what it
+/// touches.
+fn confirm_rebuild_initramfs(window: &ApplicationWindow) {
+    let Some(generator) = boot::detect_initramfs() else {
+        warn!("Neither mkinitcpio nor dracut is configured");
+        crate::ui::dialogs::error::show_error(
+            window,
+            &gettext("Neither mkinitcpio nor dracut is configured on this system."),
+        );
+        return;
+    };
+    let kernels = boot::installed_kernels();
+    if kernels.is_empty() {
+        warn!("No kernels found in /usr/lib/modules");
+        crate::ui::dialogs::error::show_error(
+            window,
+            &gettext("No installed kernels were found in /usr/lib/modules."),
+        );
+        return;
+    }
+    let loader = boot::detect();
+    info!(
+        "Initramfs rebuild with {} for {:?}, bootloader {:?}",
+        generator.name(),
+        kernels,
+        loader
+    );
+
+    let mut message = i18n::fill_markup(
+        &gettext(
+            "<b>This is a recovery tool</b> for systems an interrupted update left \
+             unable to boot. It copies the kernel images back to /boot and rebuilds \
+             the initramfs of every kernel with <b>{}</b>.",
+        ),
+        &[generator.name()],
+    );
+    message.push_str("\n\n");
+    message.push_str(&gettext("Kernels:"));
+    for kernel in &kernels {
+        message.push_str(&i18n::fill_markup(
+            "\n• <b>{}</b> {}",
+            &[&kernel.package, &kernel.version],
+        ));
+    }
+    if let Some(loader) = &loader {
+        message.push_str("\n\n");
+        message.push_str(&i18n::fill_markup(
+            &gettext("The {} boot menu is refreshed afterwards."),
+            &[loader.name()],
+        ));
+    }
+
+    let window_clone = window.clone();
+    show_typed_confirmation(
+        window.upcast_ref(),
+        &gettext("Rebuild Initramfs?"),
+        Markup::trusted(message),
+        "initramfs",
+        move || run_rebuild_initramfs(&window_clone, generator, &kernels, loader.as_ref()),
+    );
+}
+
+fn run_rebuild_initramfs(
+    window: &ApplicationWindow,
+    generator: boot::InitramfsGenerator,
+    kernels: &[boot::Kernel],
+    loader: Option<&Bootloader>,
+) {
+    let mut commands = CommandSequence::new()
+        .long_running()
+        .phase(&gettext("Kernel Images"));
+    for kernel in kernels {
+        // What the kernel's install hook does
+        commands = commands.then(
+            Command::builder()
+                .privileged()
+                .program("install")
+                .args(&[
+                    "-Dm644",
+                    &kernel.image().to_string_lossy(),
+                    &kernel.boot_image().to_string_lossy(),
+                ])
+                .description(&i18n::fill(
+                    &gettext("Copying the {} kernel image..."),
+                    &[&kernel.package],
+                ))
+                .build(),
+        );
+    }
+
+    commands = commands.phase(&gettext("Initramfs"));
+    for (program, args) in boot::rebuild_initramfs(generator, kernels) {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        commands = commands.then(
+            Command::builder()
+                .privileged()
+                .program(program)
+                .args(&args)
+                .description(&i18n::fill(
+                    &gettext("Rebuilding initramfs with {}..."),
+                    &[generator.name()],
+                ))
+                .build(),
+        );
+    }
+
+    if let Some((program, args)) = loader.and_then(boot::regenerate_menu) {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        commands = commands.phase(&gettext("Boot Menu")).then(
+            Command::builder()
+                .privileged()
+                .program(program)
+                .args(&args)
+                .description(&gettext("Regenerating GRUB menu..."))
+                .build(),
+        );
+    }
+
+    task_runner::run(
+        window.upcast_ref(),
+        commands.build(),
+        &gettext("Rebuild Initramfs"),
+    );
+}
+
+/// Open the boot options editor with the `enable` parameters switched on,
+/// leaving it to the user to apply them.
+pub(crate) fn open_boot_options(window: &ApplicationWindow, enable: &[&str]) {
+    let Some(loader) = boot::detect() else {
+        warn!("No supported bootloader found");
+        crate::ui::dialogs::error::show_error(
+            window,
+            &gettext("No GRUB or systemd-boot configuration was found on this system."),
+        );
+        return;
+    };
+    match BootConfig::load(loader) {
+        Ok(config) => show_boot_options_dialog(window, config, enable),
+        Err(e) => {
+            warn!("Failed to read boot configuration: {:#}", e);
+            crate::ui::dialogs::error::show_error(
+                window,
+                &i18n::fill(
+                    &gettext("Could not read the boot configuration: {}"),
+                    &[&format!("{:#}", e)],
+                ),
+            );
+        }
+    }
+}
+
+/// Mark `row` as invalid unless its text is safe to write.
+fn validate_boot_row(row: &adw::EntryRow) -> bool {
+    let valid = boot::validate_text(&row.text()).is_ok();
+    if valid {
+        row.remove_css_class("error");
+    } else {
+        row.add_css_class("error");
+    }
+    valid
+}
+
+/// Offer the menu timeout, default entry and kernel parameters for editing.
+fn show_boot_options_dialog(window: &ApplicationWindow, config: BootConfig, enable: &[&str]) {
+    let current = config.settings();
+    let is_common = |param: &str| boot::COMMON_PARAMS.iter().any(|(p, _)| *p == param);
+
+    let timeout_row = adw::SpinRow::with_range(0.0, f64::from(boot::MAX_TIMEOUT), 1.0);
+    timeout_row.set_title(&gettext("Menu Timeout"));
+    timeout_row.set_subtitle(&gettext("Seconds the boot menu is shown"));
+    timeout_row.set_value(f64::from(current.timeout.min(boot::MAX_TIMEOUT)));
+    let default_row = adw::EntryRow::builder()
+        .title(gettext("Default Entry"))
+        .text(current.default_entry.as_str())
+        .build();
+
+    let menu_group = adw::PreferencesGroup::new();
+    menu_group.add(&timeout_row);
+    menu_group.add(&default_row);
+
+    let params_group = adw::PreferencesGroup::builder()
+        .title(gettext("Kernel Parameters"))
+        .build();
+    let switches: Vec<(&str, adw::SwitchRow)> = boot::COMMON_PARAMS
+        .iter()
+        .map(|(param, label)| {
+            let row = adw::SwitchRow::builder()
+                .title(gettext(label))
+                .subtitle(*param)
+                .active(enable.contains(param) || current.params.iter().any(|p| p == param))
+                .build();
+            params_group.add(&row);
+            (*param, row)
+        })
+        .collect();
+    let other: Vec<&str> = current
+        .params
+        .iter()
+        .map(String::as_str)
+        .filter(|p| !is_common(p))
+        .collect();
+    let other_row = adw::EntryRow::builder()
+        .title(gettext("Other Parameters"))
+        .text(other.join(" "))
+        .build();
+    params_group.add(&other_row);
+
+    let content = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
+    content.append(&menu_group);
+    content.append(&params_group);
+
+    let mut body = i18n::fill(
+        &gettext("Settings of the {} bootloader. Each changed file is backed up first."),
+        &[config.loader.name()],
+    );
+    if config.loader == Bootloader::Grub {
+        body.push(' ');
+        body.push_str(&gettext("The GRUB menu is regenerated afterwards."));
+    }
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext("Boot Options"))
+        .body(body)
+        .extra_child(&content)
+        .build();
+    dialog.add_response("cancel", &gettext("Cancel"));
+    dialog.add_response("apply", &gettext("Apply"));
+    dialog.set_response_appearance("apply", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("apply"));
+    dialog.set_close_response("cancel");
+
+    for row in [&default_row, &other_row] {
+        let dialog = dialog.clone();
+        let default_row = default_row.clone();
+        let other_row = other_row.clone();
+        row.connect_changed(move |_| {
+            let valid = validate_boot_row(&default_row) & validate_boot_row(&other_row);
+            dialog.set_response_enabled("apply", valid);
+        });
+    }
+
+    let window_clone = window.clone();
+    dialog.connect_response(Some("apply"), move |_, _| {
+        let other = boot::split_params(&other_row.text());
+        // Keep the current order so untouched parameters are not rewritten
+        let mut params: Vec<String> = current
+            .params
+            .iter()
+            .filter(|p| {
+                switches
+                    .iter()
+                    .find(|(param, _)| param == p)
+                    .map_or_else(|| other.contains(p), |(_, row)| row.is_active())
+            })
+            .cloned()
+            .collect();
+        for param in &other {
+            if !params.contains(param) {
+                params.push(param.clone());
+            }
+        }
+        for (param, row) in &switches {
+            if row.is_active() {
+                boot::set_param(&mut params, param, true);
+            }
+        }
+
+        let settings = BootSettings {
+            timeout: timeout_row.value() as u32,
+            default_entry: default_row.text().trim().to_string(),
+            params,
+        };
+        match config.changes(&settings) {
+            Ok(changes) if changes.is_empty() => info!("Boot options unchanged"),
+            Ok(changes) => apply_boot_options(&window_clone, &config.loader, &changes),
+            Err(e) => {
+                warn!("Invalid boot options: {:#}", e);
+                crate::ui::dialogs::error::show_error(&window_clone, &format!("{:#}", e));
+            }
+        }
+    });
+
+    dialog.present(Some(window));
+}
+
+fn apply_boot_options(
+    window: &ApplicationWindow,
+    loader: &Bootloader,
+    changes: &[boot::FileChange],
+) {
+    let edits: Vec<_> = changes
+        .iter()
+        .map(|change| privileged_fs::write_file(&change.path, &change.content))
+        .collect();
+    let mut diff = String::new();
+    for edit in &edits {
+        match edit.preview() {
+            Ok(Some(file_diff)) => diff.push_str(&file_diff),
+            Ok(None) => {}
+            Err(e) => {
+                warn!("Failed to preview boot options: {:#}", e);
+                crate::ui::dialogs::error::show_error(window, &format!("{:#}", e));
+                return;
+            }
+        }
+    }
+
+    let window_clone = window.clone();
+    let loader = loader.clone();
+    diff::show_diff_confirmation(
+        window.upcast_ref(),
+        &gettext("Apply Boot Options"),
+        &diff,
+        move || run_boot_options(&window_clone, &loader, &edits),
+    );
+}
+
+fn run_boot_options(
+    window: &ApplicationWindow,
+    loader: &Bootloader,
+    edits: &[privileged_fs::FileEdit],
+) {
+    info!("Applying boot options to {} files", edits.len());
+    let mut commands = CommandSequence::new();
+    for edit in edits {
+        commands = commands.then(edit.command(&i18n::fill(
+            &gettext("Writing {}..."),
+            &[&edit.path.to_string_lossy()],
+        )));
+    }
+    if *loader == Bootloader::Grub {
+        commands = commands.then(
+            Command::builder()
+                .privileged()
+                .program("grub-mkconfig")
+                .args(&["-o", boot::GRUB_CONFIG])
+                .description(&gettext("Regenerating GRUB menu..."))
+                .build(),
+        );
+    }
+    task_runner::run(
+        window.upcast_ref(),
+        commands.build(),
+        &gettext("Apply Boot Options"),
+    );
+}
+
+fn setup_locale(page_builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let btn_locale = extract_widget::<Button>(page_builder, "btn_locale")?;
+    let window = window.clone();
+    btn_locale.connect_clicked(move |_| {
+        info!("Servicing: Locale & Timezone button clicked");
+        let settings = (|| -> anyhow::Result<_> {
+            Ok(LocaleSettings {
+                supported: locale::supported()?,
+                locale_gen: locale::read_locale_gen()?,
+                timezones: locale::timezones()?,
+                lang: locale::current_lang(),
+                timezone: locale::current_timezone(),
+            })
+        })();
+        match settings {
+            Ok(settings) => show_locale_dialog(&window, settings),
+            Err(e) => {
+                warn!("Failed to read locale settings: {:#}", e);
+                crate::ui::dialogs::error::show_error(
+                    &window,
+                    &i18n::fill(
+                        &gettext("Could not read the locale settings: {}"),
+                        &[&format!("{:#}", e)],
+                    ),
+                );
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Current locale and timezone state with the choices on offer.
+struct LocaleSettings {
+    supported: Vec<LocaleEntry>,
+    locale_gen: String,
+    timezones: Vec<String>,
+    lang: Option<String>,
+    timezone: Option<String>,
+}
+
+/// Combo row over `options` with `current` selected and search enabled.
+fn search_combo_row(title: &str, options: &[&str], current: Option<&str>) -> adw::ComboRow {
+    let row = adw::ComboRow::builder()
+        .title(title)
+        .model(&gtk4::StringList::new(options))
+        .enable_search(true)
+        .build();
+    row.set_expression(Some(gtk4::PropertyExpression::new(
+        gtk4::StringObject::static_type(),
+        None::<&gtk4::Expression>,
+        "string",
+    )));
+    if let Some(index) = current.and_then(|current| options.iter().position(|o| *o == current)) {
+        row.set_selected(index as u32);
+    }
+    row
+}
+
+/// Offer the generated locales, the default locale and the timezone.
+fn show_locale_dialog(window: &ApplicationWindow, settings: LocaleSettings) {
+    let generated: BTreeSet<String> = locale::enabled_locales(&settings.locale_gen)
+        .into_iter()
+        .collect();
+    let checked = Rc::new(RefCell::new(generated.clone()));
+
+    let names: Vec<&str> = settings.supported.iter().map(|e| e.name.as_str()).collect();
+    let lang_row = search_combo_row(
+        &gettext("System Language"),
+        &names,
+        settings.lang.as_deref(),
+    );
+    let timezone_names: Vec<&str> = settings.timezones.iter().map(String::as_str).collect();
+    let timezone_row = search_combo_row(
+        &gettext("Timezone"),
+        &timezone_names,
+        settings.timezone.as_deref(),
+    );
+    let settings_group = adw::PreferencesGroup::new();
+    settings_group.add(&lang_row);
+    settings_group.add(&timezone_row);
+
+    let search_entry = gtk4::SearchEntry::builder()
+        .placeholder_text(gettext("Search locales"))
+        .build();
+    let list = gtk4::ListBox::builder()
+        .selection_mode(gtk4::SelectionMode::None)
+        .css_classes(["boxed-list"])
+        .build();
+    for entry in &settings.supported {
+        let check = gtk4::CheckButton::builder()
+            .active(generated.contains(&entry.name))
+            .valign(gtk4::Align::Center)
+            .build();
+        let row = adw::ActionRow::builder()
+            .title(entry.name.as_str())
+            .subtitle(entry.charset.as_str())
+            .activatable_widget(&check)
+            .build();
+        row.add_prefix(&check);
+        let checked = checked.clone();
+        let name = entry.name.clone();
+        check.connect_toggled(move |check| {
+            if check.is_active() {
+                checked.borrow_mut().insert(name.clone());
+            } else {
+                checked.borrow_mut().remove(&name);
+            }
+        });
+        list.append(&row);
+    }
+    let search = search_entry.clone();
+    list.set_filter_func(move |row| {
+        let query = search.text().to_lowercase();
+        row.downcast_ref::<adw::ActionRow>()
+            .is_some_and(|row| row.title().to_lowercase().contains(&query))
+    });
+    let list_clone = list.clone();
+    search_entry.connect_search_changed(move |_| list_clone.invalidate_filter());
+
+    let scrolled = gtk4::ScrolledWindow::builder()
+        .child(&list)
+        .min_content_height(240)
+        .hscrollbar_policy(gtk4::PolicyType::Never)
+        .build();
+
+    let content = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
+    content.append(&settings_group);
+    content.append(
+        &gtk4::Label::builder()
+            .label(gettext("Generated Locales"))
+            .halign(gtk4::Align::Start)
+            .css_classes(["heading"])
+            .build(),
+    );
+    content.append(&search_entry);
+    content.append(&scrolled);
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext("Locale & Timezone"))
+        .body(gettext(
+            "Locales are generated from /etc/locale.gen, which is backed up first. \
+             The system language is always generated.",
+        ))
+        .extra_child(&content)
+        .build();
+    dialog.add_response("cancel", &gettext("Cancel"));
+    dialog.add_response("apply", &gettext("Apply"));
+    dialog.set_response_appearance("apply", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("apply"));
+    dialog.set_close_response("cancel");
+
+    let window_clone = window.clone();
+    dialog.connect_response(Some("apply"), move |_, _| {
+        let lang = settings.supported.get(lang_row.selected() as usize);
+        let timezone = settings.timezones.get(timezone_row.selected() as usize);
+
+        let mut wanted = checked.borrow().clone();
+        if let Some(lang) = lang {
+            wanted.insert(lang.name.clone());
+        }
+        let entries: Vec<LocaleEntry> = settings
+            .supported
+            .iter()
+            .filter(|entry| wanted.contains(&entry.name))
+            .cloned()
+            .collect();
+
+        apply_locale_settings(
+            &window_clone,
+            (wanted != generated).then_some(entries),
+            lang.map(|lang| lang.name.as_str())
+                .filter(|lang| Some(*lang) != settings.lang.as_deref()),
+            timezone
+                .map(String::as_str)
+                .filter(|timezone| Some(*timezone) != settings.timezone.as_deref()),
+        );
+    });
+
+    dialog.present(Some(window));
+}
+
+/// Generate `locales` if given, then set the changed language and timezone.
+fn apply_locale_settings(
+    window: &ApplicationWindow,
+    locales: Option<Vec<LocaleEntry>>,
+    lang: Option<&str>,
+    timezone: Option<&str>,
+) {
+    if locales.is_none() && lang.is_none() && timezone.is_none() {
+        info!("Locale settings unchanged");
+        return;
+    }
+    if task_runner::busy() {
+        return;
+    }
+
+    let mut commands = CommandSequence::new();
+    if let Some(locales) = locales {
+        info!("Generating {} locales", locales.len());
+        commands = commands
+            .then(
+                privileged_fs::transform(locale::LOCALE_GEN, move |current| {
+                    locale::edit_locale_gen(current, &locales)
+                })
+                .command(&gettext("Updating /etc/locale.gen...")),
+            )
+            .then(
+                Command::builder()
+                    .privileged()
+                    .program("locale-gen")
+                    .description(&gettext("Generating locales..."))
+                    .build(),
+            );
+    }
+    if let Some(lang) = lang {
+        commands = commands.then(
+            Command::builder()
+                .privileged()
+                .program("localectl")
+                .args(&["set-locale", &format!("LANG={}", lang)])
+                .description(&i18n::fill(
+                    &gettext("Setting system language to {}..."),
+                    &[lang],
+                ))
+                .build(),
+        );
+    }
+    if let Some(timezone) = timezone {
+        commands = commands.then(
+            Command::builder()
+                .privileged()
+                .program("timedatectl")
+                .args(&["set-timezone", timezone])
+                .description(&i18n::fill(
+                    &gettext("Setting timezone to {}..."),
+                    &[timezone],
+                ))
+                .build(),
+        );
+    }
+    commands = commands.then(
+        Command::builder()
+            .normal()
+            .program("localectl")
+            .args(&["status"])
+            .description(&gettext("Verifying locale settings..."))
+            .build(),
+    );
+
+    task_runner::run(
+        window.upcast_ref(),
+        commands.build(),
+        &gettext("Locale & Timezone"),
+    );
+}
+
+fn setup_user_groups(
+    page_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    let btn_user_groups = extract_widget::<Button>(page_builder, "btn_user_groups")?;
+    let window = window.clone();
+    btn_user_groups.connect_clicked(move |_| {
+        info!("Servicing: User Groups button clicked");
+        let user = crate::config::env::get().user.clone();
+        match groups::user_groups(&user) {
+            Ok(current) => show_user_groups_dialog(&window, user, current),
+            Err(e) => {
+                warn!("Failed to read groups of {}: {:#}", user, e);
+                crate::ui::dialogs::error::show_error(
+                    &window,
+                    &i18n::fill(
+                        &gettext("Could not read your group memberships: {}"),
+                        &[&format!("{:#}", e)],
+                    ),
+                );
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Offer the common groups with the current memberships checked.
+fn show_user_groups_dialog(window: &ApplicationWindow, user: String, current: Vec<String>) {
+    let available = groups::available_groups();
+    let group = adw::PreferencesGroup::new();
+    let checks: Vec<(&str, gtk4::CheckButton)> = available
+        .iter()
+        .map(|(name, description)| {
+            let check = gtk4::CheckButton::builder()
+                .active(current.iter().any(|g| g == name))
+                .valign(gtk4::Align::Center)
+                .build();
+            let row = adw::ActionRow::builder()
+                .title(*name)
+                .subtitle(gettext(description))
+                .activatable_widget(&check)
+                .build();
+            row.add_prefix(&check);
+            group.add(&row);
+            (*name, check)
+        })
+        .collect();
+
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext("User Groups"))
+        .body(i18n::fill(
+            &gettext(
+                "Groups of {}. Missing memberships are a common reason for devices \
+                 and services that do not work. Changes apply after logging out.",
+            ),
+            &[&user],
+        ))
+        .extra_child(&group)
+        .build();
+    dialog.add_response("cancel", &gettext("Cancel"));
+    dialog.add_response("apply", &gettext("Apply"));
+    dialog.set_response_appearance("apply", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("apply"));
+    dialog.set_close_response("cancel");
+
+    let window_clone = window.clone();
+    dialog.connect_response(Some("apply"), move |_, _| {
+        let offered: Vec<&str> = checks.iter().map(|(name, _)| *name).collect();
+        let wanted: Vec<&str> = checks
+            .iter()
+            .filter(|(_, check)| check.is_active())
+            .map(|(name, _)| *name)
+            .collect();
+        let changes = groups::changes(&offered, &current, &wanted);
+        if changes.is_empty() {
+            info!("Group memberships unchanged");
+            return;
+        }
+
+        if changes.remove.iter().any(|g| g == groups::ADMIN_GROUP) {
+            let window = window_clone.clone();
+            let user = user.clone();
+            show_typed_confirmation(
+                window_clone.upcast_ref(),
+                &gettext("Remove Administrator Rights?"),
+                Markup::trusted(i18n::fill_markup(
+                    &gettext(
+                        "Without the <b>{}</b> group, {} can no longer use sudo or \
+                         change the system here. Make sure another account can.",
+                    ),
+                    &[groups::ADMIN_GROUP, &user],
+                )),
+                groups::ADMIN_GROUP,
+                move || apply_group_changes(&window, &user, &changes),
+            );
+        } else {
+            apply_group_changes(&window_clone, &user, &changes);
+        }
+    });
+
+    dialog.present(Some(window));
+}
+
+fn apply_group_changes(window: &ApplicationWindow, user: &str, changes: &GroupChanges) {
+    if task_runner::busy() {
+        return;
+    }
+    info!("Group changes for {}: {:?}", user, changes);
+
+    let mut commands = CommandSequence::new();
+    for group in &changes.add {
+        commands = commands.then(
+            Command::builder()
+                .privileged()
+                .program("gpasswd")
+                .args(&["-a", user, group])
+                .description(&i18n::fill(&gettext("Adding you to {}..."), &[group]))
+                .build(),
+        );
+    }
+    for group in &changes.remove {
+        commands = commands.then(
+            Command::builder()
+                .privileged()
+                .program("gpasswd")
+                .args(&["-d", user, group])
+                .description(&i18n::fill(&gettext("Removing you from {}..."), &[group]))
+                .build(),
+        );
+    }
+
+    let window_clone = window.clone();
+    task_runner::run_with_completion(
+        window.upcast_ref(),
+        commands.build(),
+        &gettext("User Groups"),
+        move |success| {
+            if success {
+                show_group_logout_notice(&window_clone);
+            }
+        },
+    );
+}
+
+/// Group changes only reach new sessions, so offer to log out.
+fn show_group_logout_notice(window: &ApplicationWindow) {
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext("Log Out to Apply"))
+        .body(gettext(
+            "Group changes only apply to new login sessions. \
+             Until you log out and back in, nothing changes.",
+        ))
+        .build();
+    dialog.add_response("later", &gettext("Later"));
+    dialog.add_response("logout", &gettext("Log Out Now"));
+    dialog.set_response_appearance("logout", adw::ResponseAppearance::Destructive);
+    dialog.set_default_response(Some("later"));
+    dialog.set_close_response("later");
+
+    dialog.connect_response(Some("logout"), |_, _| crate::ui::session::log_out());
+    dialog.present(Some(window));
+}
+
+fn setup_maintenance(
+    page_builder: &Builder,
+    window: &ApplicationWindow,
+) -> Result<(), WidgetError> {
+    let btn_maintenance = extract_widget::<Button>(page_builder, "btn_maintenance")?;
+    let window = window.clone();
+    btn_maintenance.connect_clicked(move |_| {
+        info!("Servicing: Scheduled Maintenance button clicked");
+        show_maintenance_dialog(&window);
+    });
+    Ok(())
+}
+
+/// When the timer fires next, for the dialog body.
+fn next_maintenance_run() -> String {
+    match core::systemd::timer(maintenance::TIMER_UNIT) {
+        Ok(Some(core::systemd::Timer {
+            next: Some(next), ..
+        })) => {
+            let next = glib::DateTime::from_unix_local((next / 1_000_000) as i64)
+                .and_then(|time| time.format("%Y-%m-%d %H:%M"))
+                .map(|time| time.to_string())
+                .unwrap_or_else(|_| next.to_string());
+            i18n::fill(&gettext("Next run: {}."), &[&next])
+        }
+        Ok(_) => gettext("The timer is installed but not scheduled."),
+        Err(e) => {
+            warn!("Failed to query {}: {:#}", maintenance::TIMER_UNIT, e);
+            gettext("The timer state could not be read.")
+        }
+    }
+}
+
+/// Offer the maintenance tasks and cadence, preset from the installed
+/// schedule.
+fn show_maintenance_dialog(window: &ApplicationWindow) {
+    let installed = maintenance::installed();
+    let (scheduled, cadence) = match &installed {
+        Some((tasks, cadence)) => (tasks.clone(), *cadence),
+        None => (Vec::new(), Cadence::Weekly),
+    };
+
+    let group = adw::PreferencesGroup::new();
+    let checks: Vec<(&'static maintenance::MaintenanceTask, gtk4::CheckButton)> =
+        maintenance::TASKS
+            .iter()
+            .filter(|task| task.is_available())
+            .map(|task| {
+                let check = gtk4::CheckButton::builder()
+                    .active(scheduled.iter().any(|id| id == task.id))
+                    .valign(gtk4::Align::Center)
+                    .build();
+                let row = adw::ActionRow::builder()
+                    .title(gettext(task.label))
+                    .activatable_widget(&check)
+                    .build();
+                row.add_prefix(&check);
+                group.add(&row);
+                (task, check)
+            })
+            .collect();
+
+    let labels: Vec<String> = Cadence::ALL.iter().map(|c| gettext(c.label())).collect();
+    let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+    let cadence_row = adw::ComboRow::builder()
+        .title(gettext("Run"))
+        .model(&gtk4::StringList::new(&labels))
+        .build();
+    if let Some(index) = Cadence::ALL.iter().position(|c| *c == cadence) {
+        cadence_row.set_selected(index as u32);
+    }
+    group.add(&cadence_row);
+
+    let status = if installed.is_some() {
+        next_maintenance_run()
+    } else {
+        gettext("No maintenance is scheduled.")
+    };
+    let dialog = adw::AlertDialog::builder()
+        .heading(gettext("Scheduled Maintenance"))
+        .body(format!(
+            "{} {}",
+            gettext(
+                "Run the chosen cleanup tasks in the background. \
+                 Runs missed while the computer was off happen at the next boot."
+            ),
+            status
+        ))
+        .extra_child(&group)
+        .build();
+    dialog.add_response("cancel", &gettext("Cancel"));
+    if installed.is_some() {
+        dialog.add_response("disable", &gettext("Disable"));
+        dialog.set_response_appearance("disable", adw::ResponseAppearance::Destructive);
+    }
+    dialog.add_response("apply", &gettext("Apply"));
+    dialog.set_response_appearance("apply", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("apply"));
+    dialog.set_close_response("cancel");
+
+    let window_clone = window.clone();
+    dialog.connect_response(Some("disable"), move |_, _| {
+        if task_runner::busy() {
+            return;
+        }
+        task_runner::run(
+            window_clone.upcast_ref(),
+            maintenance::remove_commands().build(),
+            &gettext("Disable Scheduled Maintenance"),
+        );
+    });
+
+    let window_clone = window.clone();
+    dialog.connect_response(Some("apply"), move |_, _| {
+        let tasks: Vec<&maintenance::MaintenanceTask> = checks
+            .iter()
+            .filter(|(_, check)| check.is_active())
+            .map(|(task, _)| *task)
+            .collect();
+        let cadence = Cadence::ALL
+            .get(cadence_row.selected() as usize)
+            .copied()
+            .unwrap_or(Cadence::Weekly);
+        if tasks.is_empty() {
+            crate::ui::dialogs::error::show_error(
+                &window_clone,
+                &gettext("Choose at least one task, or disable the schedule."),
+            );
+            return;
+        }
+        info!(
+            "Scheduling {:?} maintenance: {:?}",
+            cadence,
+            tasks.iter().map(|task| task.id).collect::<Vec<_>>()
         );
+        schedule_maintenance(&window_clone, &tasks, cadence);
     });
+
+    dialog.present(Some(window));
+}
+
+/// Install the schedule, then reopen the dialog to show the next run.
+fn schedule_maintenance(
+    window: &ApplicationWindow,
+    tasks: &[&maintenance::MaintenanceTask],
+    cadence: Cadence,
+) {
+    if task_runner::busy() {
+        return;
+    }
+    let window_clone = window.clone();
+    task_runner::run_with_completion(
+        window.upcast_ref(),
+        maintenance::install_commands(tasks, cadence).build(),
+        &gettext("Scheduled Maintenance"),
+        move |success| {
+            if success {
+                show_maintenance_dialog(&window_clone);
+            }
+        },
+    );
+}
+
+const BLUETOOTH_PACKAGES: &[&str] = &["bluez", "bluez-utils"];
+const BLUETOOTH_UNIT: &str = "bluetooth.service";
+const AUDIO_PACKAGES: &[&str] = &["pipewire", "pipewire-pulse", "pipewire-alsa", "wireplumber"];
+/// PipeWire user units, restarted in this order.
+const AUDIO_UNITS: &[&str] = &[
+    "pipewire.service",
+    "pipewire-pulse.service",
+    "wireplumber.service",
+];
+
+/// Widgets of a quick-fix card: a status row with setup and restart
+/// buttons.
+struct QuickFixCard {
+    window: ApplicationWindow,
+    refresh_button: Button,
+    status_row: adw::ActionRow,
+    status_icon: gtk4::Image,
+    setup_button: Button,
+    restart_button: Button,
+    /// Queries the state; blocks, so it runs in the background.
+    check: fn() -> QuickFixStatus,
+}
+
+/// State shown on a quick-fix card.
+struct QuickFixStatus {
+    healthy: bool,
+    title: String,
+    subtitle: String,
+    /// Packages are missing or the service is not set up
+    needs_setup: bool,
+    /// Services are installed and can be restarted
+    can_restart: bool,
+}
+
+impl QuickFixCard {
+    fn new(
+        builder: &Builder,
+        window: &ApplicationWindow,
+        key: &str,
+        check: fn() -> QuickFixStatus,
+    ) -> Result<Rc<Self>, WidgetError> {
+        Ok(Rc::new(Self {
+            window: window.clone(),
+            refresh_button: extract_widget(builder, &format!("btn_{}_refresh", key))?,
+            status_row: extract_widget(builder, &format!("{}_status_row", key))?,
+            status_icon: extract_widget(builder, &format!("{}_status_icon", key))?,
+            setup_button: extract_widget(builder, &format!("btn_{}_setup", key))?,
+            restart_button: extract_widget(builder, &format!("btn_{}_restart", key))?,
+            check,
+        }))
+    }
+
+    /// Query the state in the background and update the card.
+    fn refresh(self: &Rc<Self>) {
+        self.refresh_button.set_sensitive(false);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let check = self.check;
+        std::thread::spawn(move || {
+            let _ = sender.send(check());
+        });
+
+        let card = self.clone();
+        glib::timeout_add_local(
+            std::time::Duration::from_millis(100),
+            move || match receiver.try_recv() {
+                Ok(status) => {
+                    card.apply(&status);
+                    card.refresh_button.set_sensitive(true);
+                    glib::ControlFlow::Break
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                    card.refresh_button.set_sensitive(true);
+                    glib::ControlFlow::Break
+                }
+            },
+        );
+    }
+
+    fn apply(&self, status: &QuickFixStatus) {
+        self.status_row.set_title(&status.title);
+        self.status_row.set_subtitle(&status.subtitle);
+        self.status_icon.set_icon_name(Some(if status.healthy {
+            "circle-check-symbolic"
+        } else {
+            "triangle-exclamation-symbolic"
+        }));
+        self.setup_button.set_visible(status.needs_setup);
+        self.restart_button.set_visible(status.can_restart);
+    }
+
+    /// Run `commands` and refresh the card afterwards.
+    fn run(self: &Rc<Self>, commands: CommandSequence, title: &str) {
+        if task_runner::busy() {
+            return;
+        }
+        let card = self.clone();
+        task_runner::run_with_completion(
+            self.window.upcast_ref(),
+            commands.build(),
+            title,
+            move |_| card.refresh(),
+        );
+    }
+}
+
+/// Packages of `packages` that are not installed.
+fn missing_packages(packages: &[&'static str]) -> Vec<&'static str> {
+    packages
+        .iter()
+        .copied()
+        .filter(|package| !core::is_package_installed(package))
+        .collect()
+}
+
+fn bluetooth_status() -> QuickFixStatus {
+    let missing = missing_packages(BLUETOOTH_PACKAGES);
+    if !missing.is_empty() {
+        return QuickFixStatus {
+            healthy: false,
+            title: gettext("Bluetooth packages are not installed"),
+            subtitle: missing.join(", "),
+            needs_setup: true,
+            can_restart: false,
+        };
+    }
+
+    let details = core::systemd::unit_details(&[BLUETOOTH_UNIT])
+        .map_err(|e| warn!("Failed to query {}: {:#}", BLUETOOTH_UNIT, e))
+        .ok()
+        .and_then(|details| details.into_iter().next())
+        .unwrap_or_default();
+    if details.is_active() {
+        QuickFixStatus {
+            healthy: true,
+            title: gettext("Bluetooth is running"),
+            subtitle: String::new(),
+            needs_setup: !details.is_enabled(),
+            can_restart: true,
+        }
+    } else {
+        QuickFixStatus {
+            healthy: false,
+            title: gettext("Bluetooth service is not running"),
+            subtitle: if details.is_enabled() {
+                String::new()
+            } else {
+                gettext("It does not start at boot")
+            },
+            needs_setup: true,
+            can_restart: true,
+        }
+    }
+}
+
+fn audio_status() -> QuickFixStatus {
+    let missing = missing_packages(AUDIO_PACKAGES);
+    let needs_setup = !missing.is_empty();
+    if missing.contains(&"pipewire") {
+        return QuickFixStatus {
+            healthy: false,
+            title: gettext("PipeWire is not installed"),
+            subtitle: missing.join(", "),
+            needs_setup,
+            can_restart: false,
+        };
+    }
+
+    match core::systemd::inactive_user_units(AUDIO_UNITS) {
+        Ok(inactive) if inactive.is_empty() && !needs_setup => QuickFixStatus {
+            healthy: true,
+            title: gettext("Audio services are running"),
+            subtitle: String::new(),
+            needs_setup,
+            can_restart: true,
+        },
+        Ok(inactive) if inactive.is_empty() => QuickFixStatus {
+            healthy: false,
+            title: gettext("Audio packages are missing"),
+            subtitle: missing.join(", "),
+            needs_setup,
+            can_restart: true,
+        },
+        Ok(inactive) => QuickFixStatus {
+            healthy: false,
+            title: gettext("Audio services are not running"),
+            subtitle: inactive.join(", "),
+            needs_setup,
+            can_restart: true,
+        },
+        Err(e) => {
+            warn!("Failed to query audio services: {:#}", e);
+            QuickFixStatus {
+                healthy: false,
+                title: gettext("Could not check the audio services"),
+                subtitle: String::new(),
+                needs_setup,
+                can_restart: true,
+            }
+        }
+    }
+}
+
+fn setup_bluetooth(page_builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let card = QuickFixCard::new(page_builder, window, "bluetooth", bluetooth_status)?;
+
+    let card_clone = card.clone();
+    card.refresh_button
+        .connect_clicked(move |_| card_clone.refresh());
+
+    let card_clone = card.clone();
+    card.setup_button.connect_clicked(move |_| {
+        info!("Servicing: Bluetooth setup button clicked");
+        let mut args = vec!["-S", "--needed", "--noconfirm"];
+        args.extend_from_slice(BLUETOOTH_PACKAGES);
+        let commands = CommandSequence::new()
+            .then(
+                Command::builder()
+                    .privileged()
+                    .program("pacman")
+                    .args(&args)
+                    .description(&gettext("Installing Bluetooth packages..."))
+                    .build(),
+            )
+            .then(
+                Command::builder()
+                    .privileged()
+                    .program("systemctl")
+                    .args(&["enable", "--now", BLUETOOTH_UNIT])
+                    .description(&gettext("Enabling Bluetooth service..."))
+                    .build(),
+            );
+        card_clone.run(commands, &gettext("Bluetooth Setup"));
+    });
+
+    let card_clone = card.clone();
+    card.restart_button.connect_clicked(move |_| {
+        info!("Servicing: Bluetooth restart button clicked");
+        let commands = CommandSequence::new()
+            .then(
+                Command::builder()
+                    .privileged()
+                    .program("rfkill")
+                    .args(&["unblock", "bluetooth"])
+                    .description(&gettext("Unblocking Bluetooth..."))
+                    .build(),
+            )
+            .then(
+                Command::builder()
+                    .privileged()
+                    .program("systemctl")
+                    .args(&["restart", BLUETOOTH_UNIT])
+                    .description(&gettext("Restarting Bluetooth service..."))
+                    .build(),
+            );
+        card_clone.run(commands, &gettext("Restart Bluetooth"));
+    });
+
+    card.refresh();
+    Ok(())
+}
+
+fn setup_audio(page_builder: &Builder, window: &ApplicationWindow) -> Result<(), WidgetError> {
+    let card = QuickFixCard::new(page_builder, window, "audio", audio_status)?;
+
+    let card_clone = card.clone();
+    card.refresh_button
+        .connect_clicked(move |_| card_clone.refresh());
+
+    let card_clone = card.clone();
+    card.setup_button.connect_clicked(move |_| {
+        info!("Servicing: Audio setup button clicked");
+        let mut args = vec!["-S", "--needed", "--noconfirm"];
+        args.extend_from_slice(AUDIO_PACKAGES);
+        let commands = CommandSequence::new().then(
+            Command::builder()
+                .privileged()
+                .program("pacman")
+                .args(&args)
+                .description(&gettext("Installing PipeWire packages..."))
+                .build(),
+        );
+        card_clone.run(commands, &gettext("Audio Setup"));
+    });
+
+    let card_clone = card.clone();
+    card.restart_button.connect_clicked(move |_| {
+        info!("Servicing: Audio restart button clicked");
+        // User units live in the session's manager, so this runs as the user
+        let mut args = vec!["--user", "restart"];
+        args.extend_from_slice(AUDIO_UNITS);
+        let commands = CommandSequence::new().then(
+            Command::builder()
+                .normal()
+                .program("systemctl")
+                .args(&args)
+                .description(&gettext("Restarting audio services..."))
+                .build(),
+        );
+        card_clone.run(commands, &gettext("Restart Audio"));
+    });
+
+    card.refresh();
+    Ok(())
 }
@@ -0,0 +1,134 @@
+//! System information page handlers.
+//!
+//! Handles:
+//! - Probing CPU, GPU, memory, disks, battery and software in the background
+//! - Per-item copy buttons
+//! - Copying the whole report as text
+
+use crate::core::sysinfo::{self, Section, SystemReport};
+use crate::i18n::gettext;
+use crate::ui::navigation::PageContext;
+use crate::ui::utils::{extract_widget, WidgetError};
+use adw::prelude::*;
+use gtk4::glib;
+use gtk4::{Button, Stack};
+use log::info;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Shared widgets for the page.
+struct SystemInfoPage {
+    stack: Stack,
+    sections: gtk4::Box,
+    toasts: adw::ToastOverlay,
+    refresh_button: Button,
+    copy_button: Button,
+    report: RefCell<Option<SystemReport>>,
+}
+
+/// Set up all handlers for the system information page.
+pub fn setup_handlers(context: &PageContext) -> Result<(), WidgetError> {
+    let page_builder = &context.builder;
+    let page = Rc::new(SystemInfoPage {
+        stack: extract_widget(page_builder, "system_info_stack")?,
+        sections: extract_widget(page_builder, "system_info_sections")?,
+        toasts: extract_widget(page_builder, "system_info_toasts")?,
+        refresh_button: extract_widget(page_builder, "btn_refresh_system_info")?,
+        copy_button: extract_widget(page_builder, "btn_copy_system_report")?,
+        report: RefCell::new(None),
+    });
+
+    let page_clone = page.clone();
+    page.refresh_button.connect_clicked(move |_| {
+        info!("System info refresh button clicked");
+        refresh(&page_clone);
+    });
+
+    let page_clone = page.clone();
+    page.copy_button.connect_clicked(move |button| {
+        if let Some(report) = page_clone.report.borrow().as_ref() {
+            info!("Copied system report to clipboard");
+            button.clipboard().set_text(&report.render());
+            page_clone
+                .toasts
+                .add_toast(adw::Toast::new(&gettext("Report copied to clipboard")));
+        }
+    });
+
+    refresh(&page);
+    Ok(())
+}
+
+/// Probe the system in the background and rebuild the sections.
+fn refresh(page: &Rc<SystemInfoPage>) {
+    page.refresh_button.set_sensitive(false);
+    page.copy_button.set_sensitive(false);
+    page.stack.set_visible_child_name("loading");
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(sysinfo::probe());
+    });
+
+    let page = page.clone();
+    glib::timeout_add_local(
+        std::time::Duration::from_millis(100),
+        move || match receiver.try_recv() {
+            Ok(report) => {
+                populate(&page, &report);
+                *page.report.borrow_mut() = Some(report);
+                page.stack.set_visible_child_name("report");
+                page.copy_button.set_sensitive(true);
+                page.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                page.refresh_button.set_sensitive(true);
+                glib::ControlFlow::Break
+            }
+        },
+    );
+}
+
+/// Show one group per report section.
+fn populate(page: &SystemInfoPage, report: &SystemReport) {
+    while let Some(child) = page.sections.first_child() {
+        page.sections.remove(&child);
+    }
+    for section in &report.sections {
+        page.sections.append(&section_group(page, section));
+    }
+}
+
+fn section_group(page: &SystemInfoPage, section: &Section) -> adw::PreferencesGroup {
+    let group = adw::PreferencesGroup::builder()
+        .title(gettext(section.title))
+        .build();
+
+    for item in &section.items {
+        let row = adw::ActionRow::builder()
+            .title(gettext(item.label))
+            .subtitle(item.value.clone().unwrap_or_else(|| gettext("Unavailable")))
+            .subtitle_selectable(true)
+            .css_classes(["property"])
+            .build();
+
+        if let Some(value) = item.value.clone() {
+            let copy_button = Button::builder()
+                .icon_name("copy-symbolic")
+                .tooltip_text(gettext("Copy"))
+                .valign(gtk4::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            let toasts = page.toasts.clone();
+            copy_button.connect_clicked(move |button| {
+                button.clipboard().set_text(&value);
+                toasts.add_toast(adw::Toast::new(&gettext("Copied to clipboard")));
+            });
+            row.add_suffix(&copy_button);
+        }
+        group.add(&row);
+    }
+    group
+}
@@ -1,12 +1,194 @@
 //! Common utilities for seasonal effects.
 
 use adw::prelude::*;
-use gtk4::{ApplicationWindow, DrawingArea, EventControllerMotion, Widget};
-use std::cell::RefCell;
-use std::rc::Rc;
+use gtk4::{glib, gsk, ApplicationWindow, DrawingArea, EventControllerMotion, Widget};
+use log::info;
+use std::cell::{Cell, RefCell};
+use std::path::Path;
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+
+/// Time between frames, about 60 per second.
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+/// Time between frames when drawing is expensive, about 30 per second.
+const REDUCED_FRAME_INTERVAL: Duration = Duration::from_millis(33);
+/// Window area in pixels drawn in full detail with a GPU, 1440p.
+const FULL_DETAIL_AREA: f64 = 2560.0 * 1440.0;
+/// Window area in pixels drawn in full detail on the CPU, 720p.
+const SOFTWARE_FULL_DETAIL_AREA: f64 = 1280.0 * 720.0;
+/// Smallest share of particles left on huge windows.
+const MIN_PARTICLE_SCALE: f64 = 0.25;
+
+/// How the window gets rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Renderer {
+    /// On the GPU
+    Hardware,
+    /// On the CPU, with cairo or a software GL driver like llvmpipe
+    Software,
+}
+
+/// How much drawing an effect may do.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Budget {
+    pub frame_interval: Duration,
+    /// Share of the configured particle count to draw
+    pub particle_scale: f64,
+    /// Whether full-width gradients like fog and glow are drawn
+    pub gradients: bool,
+}
+
+impl Budget {
+    /// Particle count to draw out of the configured `count`.
+    pub fn count(&self, count: usize) -> usize {
+        ((count as f64 * self.particle_scale).round() as usize).max(1)
+    }
+}
+
+/// Budget for a window of `area` pixels on `renderer`, at the configured
+/// effect `intensity`.
+///
+/// Every frame repaints the whole window, so windows above the full
+/// detail area get proportionally fewer particles, and a slower timer
+/// once the area times the intensity exceeds it. Software rendering gets
+/// a smaller full detail area, no more than the default particle count
+/// and no gradients.
+pub fn budget(area: f64, renderer: Renderer, intensity: f64) -> Budget {
+    let full_detail_area = match renderer {
+        Renderer::Hardware => FULL_DETAIL_AREA,
+        Renderer::Software => SOFTWARE_FULL_DETAIL_AREA,
+    };
+    let area_scale = if area > full_detail_area {
+        full_detail_area / area
+    } else {
+        1.0
+    };
+    let intensity_scale = match renderer {
+        Renderer::Software if intensity > 1.0 => 1.0 / intensity,
+        _ => 1.0,
+    };
+    let reduced = renderer == Renderer::Software || area * intensity.max(1.0) > full_detail_area;
+
+    Budget {
+        frame_interval: if reduced {
+            REDUCED_FRAME_INTERVAL
+        } else {
+            FRAME_INTERVAL
+        },
+        particle_scale: (area_scale * intensity_scale).clamp(MIN_PARTICLE_SCALE, 1.0),
+        gradients: renderer == Renderer::Hardware,
+    }
+}
+
+/// How `widget`'s window is rendered; call once it is realized.
+pub fn detect_renderer(widget: &impl IsA<Widget>) -> Renderer {
+    let cairo = widget
+        .native()
+        .and_then(|native| native.renderer())
+        .is_some_and(|renderer| renderer.is::<gsk::CairoRenderer>());
+    // Mesa falls back to llvmpipe when asked to or without a GPU to render on
+    let forced_software = std::env::var("LIBGL_ALWAYS_SOFTWARE").is_ok_and(|value| value != "0")
+        || std::env::var("GALLIUM_DRIVER")
+            .is_ok_and(|driver| matches!(driver.as_str(), "llvmpipe" | "softpipe"));
+    if cairo || forced_software || !has_render_node(Path::new("/dev/dri")) {
+        Renderer::Software
+    } else {
+        Renderer::Hardware
+    }
+}
+
+/// Whether `dri` holds a GPU render node like `renderD128`.
+fn has_render_node(dri: &Path) -> bool {
+    std::fs::read_dir(dri).is_ok_and(|entries| {
+        entries
+            .flatten()
+            .any(|entry| entry.file_name().to_string_lossy().starts_with("renderD"))
+    })
+}
+
+/// Work out an effect's budget on its first frame, log it, and slow the
+/// effect's timer down if needed.
+pub fn plan_budget(
+    name: &str,
+    drawing_area: &DrawingArea,
+    width: f64,
+    height: f64,
+    intensity: f64,
+    timer: &Weak<FrameTimer>,
+) -> Budget {
+    let renderer = detect_renderer(drawing_area);
+    let budget = budget(width * height, renderer, intensity);
+    info!(
+        "Seasonal effect {} at {}x{} on {:?} rendering: {} ms frames, {:.0}% particles, gradients {}",
+        name,
+        width,
+        height,
+        renderer,
+        budget.frame_interval.as_millis(),
+        budget.particle_scale * 100.0,
+        if budget.gradients { "on" } else { "off" }
+    );
+    if let Some(timer) = timer.upgrade() {
+        timer.set_interval(budget.frame_interval);
+    }
+    budget
+}
+
+/// Redraws an effect's drawing area at a fixed rate while running.
+pub struct FrameTimer {
+    drawing_area: Rc<DrawingArea>,
+    interval: Cell<Duration>,
+    source: RefCell<Option<glib::SourceId>>,
+}
+
+impl FrameTimer {
+    /// A running timer for `drawing_area`.
+    pub fn new(drawing_area: Rc<DrawingArea>) -> Rc<Self> {
+        let timer = Rc::new(Self {
+            drawing_area,
+            interval: Cell::new(FRAME_INTERVAL),
+            source: RefCell::new(None),
+        });
+        timer.start();
+        timer
+    }
+
+    /// Start the timer unless it is running.
+    pub fn start(&self) -> bool {
+        let mut source = self.source.borrow_mut();
+        if source.is_some() {
+            return false;
+        }
+        let drawing_area = self.drawing_area.clone();
+        *source = Some(glib::timeout_add_local(self.interval.get(), move || {
+            drawing_area.queue_draw();
+            glib::ControlFlow::Continue
+        }));
+        true
+    }
+
+    /// Stop the timer if it is running.
+    pub fn stop(&self) -> bool {
+        match self.source.borrow_mut().take() {
+            Some(source) => {
+                source.remove();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Change the frame rate, restarting a running timer.
+    pub fn set_interval(&self, interval: Duration) {
+        if self.interval.replace(interval) != interval && self.stop() {
+            self.start();
+        }
+    }
+}
 
 /// Mouse position context for seasonal effects.
 /// Provides mouse coordinates that effects can use.
+#[derive(Clone)]
 pub struct MouseContext {
     position: Rc<RefCell<(f64, f64)>>,
 }
@@ -60,10 +242,17 @@ where
     });
 }
 
+/// Find the window's toolbar view, whether or not it is already wrapped in an overlay.
+pub fn find_toolbar_view(window: &ApplicationWindow) -> Option<adw::ToolbarView> {
+    let content = window.downcast_ref::<adw::ApplicationWindow>()?.content()?;
+    match content.downcast::<gtk4::Overlay>() {
+        Ok(overlay) => overlay.child().and_downcast(),
+        Err(content) => content.downcast().ok(),
+    }
+}
+
 /// Helper function to add a drawing area as an overlay to the window.
 pub fn add_overlay_to_window(window: &ApplicationWindow, drawing_area: &DrawingArea) -> bool {
-    use log::info;
-
     let adw_window = match window.downcast_ref::<adw::ApplicationWindow>() {
         Some(w) => w,
         None => {
@@ -109,3 +298,71 @@ pub fn add_overlay_to_window(window: &ApplicationWindow, drawing_area: &DrawingA
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HD: f64 = 1920.0 * 1080.0;
+    const UHD: f64 = 3840.0 * 2160.0;
+
+    #[test]
+    fn test_budget_full_detail() {
+        let budget = budget(HD, Renderer::Hardware, 1.0);
+        assert_eq!(budget.frame_interval, FRAME_INTERVAL);
+        assert_eq!(budget.particle_scale, 1.0);
+        assert!(budget.gradients);
+        assert_eq!(budget.count(80), 80);
+    }
+
+    #[test]
+    fn test_budget_large_window() {
+        let budget = budget(UHD, Renderer::Hardware, 1.0);
+        assert_eq!(budget.frame_interval, REDUCED_FRAME_INTERVAL);
+        // 4K has 2.25 times the pixels of 1440p
+        assert!((budget.particle_scale - 1.0 / 2.25).abs() < 1e-9);
+        assert!(budget.gradients);
+        assert_eq!(budget.count(80), 36);
+    }
+
+    #[test]
+    fn test_budget_intensity() {
+        // A busier effect slows down sooner, but keeps its particles
+        let budget_hd = budget(HD, Renderer::Hardware, 2.0);
+        assert_eq!(budget_hd.frame_interval, REDUCED_FRAME_INTERVAL);
+        assert_eq!(budget_hd.particle_scale, 1.0);
+        // A calmer one does not speed up past the area limit
+        let budget_uhd = budget(UHD, Renderer::Hardware, 0.5);
+        assert_eq!(budget_uhd.frame_interval, REDUCED_FRAME_INTERVAL);
+    }
+
+    #[test]
+    fn test_budget_software() {
+        let small = budget(1280.0 * 720.0, Renderer::Software, 1.0);
+        assert_eq!(small.frame_interval, REDUCED_FRAME_INTERVAL);
+        assert_eq!(small.particle_scale, 1.0);
+        assert!(!small.gradients);
+
+        // Extra intensity is dropped before the area scaling
+        let busy = budget(1280.0 * 720.0, Renderer::Software, 2.0);
+        assert_eq!(busy.particle_scale, 0.5);
+
+        // Never below the floor, however large
+        let huge = budget(UHD, Renderer::Software, 3.0);
+        assert_eq!(huge.particle_scale, MIN_PARTICLE_SCALE);
+        assert_eq!(huge.count(1), 1);
+    }
+
+    #[test]
+    fn test_has_render_node() {
+        let dir = std::env::temp_dir().join(format!("xero-dri-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("card0"), "").unwrap();
+        assert!(!has_render_node(&dir));
+        std::fs::write(dir.join("renderD128"), "").unwrap();
+        assert!(has_render_node(&dir));
+        assert!(!has_render_node(&dir.join("missing")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
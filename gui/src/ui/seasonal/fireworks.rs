@@ -0,0 +1,396 @@
+//! New Year fireworks effect overlay.
+//!
+//! Rockets launch from the bottom edge at random intervals and burst into
+//! fading sparks that fall under gravity.
+
+use crate::config::seasonal_debug;
+use crate::config::user::SeasonalConfig;
+use crate::ui::seasonal::common::{
+    add_overlay_to_window, plan_budget, setup_resize_handler, FrameTimer, MouseContext,
+    ResizableEffectState,
+};
+use crate::ui::seasonal::{register_effect, SeasonalEffect};
+use gtk4::cairo;
+use gtk4::glib;
+use gtk4::prelude::*;
+use gtk4::{ApplicationWindow, DrawingArea};
+use rand::rngs::StdRng;
+use rand::{RngExt, SeedableRng};
+use std::cell::RefCell;
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+/// Upper bound on live sparks, scaled by the configured intensity.
+const MAX_PARTICLES: usize = 600;
+const SPARKS_PER_BURST: std::ops::RangeInclusive<usize> = 40..=80;
+const GRAVITY: f64 = 140.0;
+/// Seconds between launches.
+const LAUNCH_INTERVAL: std::ops::Range<f64> = 0.6..1.8;
+
+/// Spark colors, one palette per burst.
+const PALETTES: &[&[(f64, f64, f64)]] = &[
+    &[(1.0, 0.84, 0.3), (1.0, 0.65, 0.1), (1.0, 0.95, 0.7)],
+    &[(0.95, 0.2, 0.25), (1.0, 1.0, 1.0), (1.0, 0.55, 0.55)],
+    &[(0.3, 0.6, 1.0), (0.45, 0.95, 1.0), (0.85, 0.9, 1.0)],
+    &[(0.35, 0.95, 0.45), (0.75, 1.0, 0.35), (0.95, 1.0, 0.8)],
+    &[(0.75, 0.35, 1.0), (1.0, 0.45, 0.8), (1.0, 0.8, 0.95)],
+];
+
+/// New Year fireworks effect.
+pub struct FireworksEffect;
+
+impl SeasonalEffect for FireworksEffect {
+    fn is_active(&self) -> bool {
+        // Check environment variable for debugging (overrides date check)
+        if let Some(enabled) = seasonal_debug::check_effect_env(seasonal_debug::ENABLE_FIREWORKS) {
+            return enabled;
+        }
+
+        // Default: local date so midnight matches the user's own New Year
+        if let Ok(dt) = glib::DateTime::now_local() {
+            is_new_year(dt.month(), dt.day_of_month())
+        } else {
+            false
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Fireworks (New Year)"
+    }
+
+    fn id(&self) -> &'static str {
+        "fireworks"
+    }
+
+    fn apply(
+        &self,
+        window: &ApplicationWindow,
+        _mouse_context: Option<&MouseContext>,
+        config: &SeasonalConfig,
+    ) -> Option<Rc<DrawingArea>> {
+        use log::info;
+
+        let drawing_area = Rc::new(DrawingArea::new());
+        drawing_area.set_hexpand(true);
+        drawing_area.set_vexpand(true);
+        drawing_area.set_can_focus(false);
+        drawing_area.set_sensitive(false);
+        drawing_area.set_halign(gtk4::Align::Fill);
+        drawing_area.set_valign(gtk4::Align::Fill);
+        drawing_area.set_visible(crate::ui::seasonal::are_effects_enabled());
+
+        let max_particles = config.scaled_count(MAX_PARTICLES);
+        let state = Rc::new(RefCell::new(None::<FireworksState>));
+        let setup_state = Rc::clone(&state);
+
+        // Redraw timer, slowed down on the first frame if drawing is expensive
+        let timer = FrameTimer::new(drawing_area.clone());
+        let budget_timer = Rc::downgrade(&timer);
+        let intensity = config.intensity();
+
+        drawing_area.set_draw_func(move |da, cr, width, height| {
+            let mut state_ref = setup_state.borrow_mut();
+
+            if state_ref.is_none() {
+                let budget = plan_budget(
+                    "fireworks",
+                    da,
+                    width as f64,
+                    height as f64,
+                    intensity,
+                    &budget_timer,
+                );
+                *state_ref = Some(FireworksState::new(
+                    width as f64,
+                    height as f64,
+                    budget.count(max_particles),
+                ));
+            }
+
+            if let Some(fireworks) = state_ref.as_mut() {
+                let now = std::time::Instant::now();
+                fireworks.update(width as f64, height as f64, now);
+
+                let _ = cr.save();
+                cr.set_operator(cairo::Operator::Clear);
+                let _ = cr.paint();
+                cr.set_operator(cairo::Operator::Over);
+                let _ = cr.restore();
+
+                fireworks.draw(cr);
+            }
+        });
+
+        // Set up resize handler
+        setup_resize_handler(&drawing_area, state);
+
+        if add_overlay_to_window(window, &drawing_area) {
+            // Register effect for lifecycle management (timer start/stop on toggle)
+            register_effect(drawing_area.clone(), timer);
+            info!("Fireworks effect overlay added successfully");
+            Some(drawing_area)
+        } else {
+            // Clean up timer if overlay failed
+            timer.stop();
+            info!("Failed to add fireworks effect overlay");
+            None
+        }
+    }
+}
+
+/// December 31 through January 2, spanning the year boundary.
+fn is_new_year(month: i32, day: i32) -> bool {
+    (month == 12 && day == 31) || (month == 1 && day <= 2)
+}
+
+struct Rocket {
+    x: f64,
+    y: f64,
+    velocity_x: f64,
+    velocity_y: f64,
+    /// Height at which the rocket bursts
+    burst_y: f64,
+    palette: usize,
+}
+
+impl Rocket {
+    fn new(width: f64, height: f64, rng: &mut StdRng) -> Self {
+        let burst_y = rng.random_range(0.15..0.5) * height;
+        // Launch fast enough to reach the burst height before gravity stops it
+        let rise = height - burst_y;
+        let velocity_y = -(2.0 * GRAVITY * rise).sqrt() * rng.random_range(1.05..1.2);
+
+        Self {
+            x: rng.random_range(0.1..0.9) * width,
+            y: height + 5.0,
+            velocity_x: rng.random_range(-30.0..30.0),
+            velocity_y,
+            burst_y,
+            palette: rng.random_range(0..PALETTES.len()),
+        }
+    }
+
+    /// Advance the rocket; returns true once it should burst.
+    fn update(&mut self, dt: f64) -> bool {
+        self.velocity_y += GRAVITY * dt;
+        self.x += self.velocity_x * dt;
+        self.y += self.velocity_y * dt;
+
+        self.y <= self.burst_y || self.velocity_y >= 0.0
+    }
+
+    fn draw(&self, cr: &cairo::Context) {
+        let _ = cr.save();
+
+        // Short trail pointing back along the flight path
+        cr.set_source_rgba(1.0, 0.9, 0.7, 0.35);
+        cr.set_line_width(2.0);
+        cr.move_to(self.x, self.y);
+        cr.line_to(
+            self.x - self.velocity_x * 0.05,
+            self.y - self.velocity_y * 0.05,
+        );
+        let _ = cr.stroke();
+
+        cr.set_source_rgba(1.0, 0.95, 0.85, 0.9);
+        cr.arc(self.x, self.y, 2.0, 0.0, 2.0 * PI);
+        let _ = cr.fill();
+
+        let _ = cr.restore();
+    }
+}
+
+struct Spark {
+    x: f64,
+    y: f64,
+    velocity_x: f64,
+    velocity_y: f64,
+    life: f64,
+    max_life: f64,
+    color: (f64, f64, f64),
+    size: f64,
+}
+
+impl Spark {
+    fn update(&mut self, dt: f64) {
+        // Air drag slows the burst before gravity takes over
+        let drag = (1.0 - 1.5 * dt).max(0.0);
+        self.velocity_x *= drag;
+        self.velocity_y = self.velocity_y * drag + GRAVITY * 0.5 * dt;
+        self.x += self.velocity_x * dt;
+        self.y += self.velocity_y * dt;
+        self.life -= dt;
+    }
+
+    fn draw(&self, cr: &cairo::Context) {
+        let fade = (self.life / self.max_life).clamp(0.0, 1.0);
+        let (r, g, b) = self.color;
+
+        cr.set_source_rgba(r, g, b, fade.powf(1.5) * 0.9);
+        cr.arc(
+            self.x,
+            self.y,
+            self.size * (0.5 + fade * 0.5),
+            0.0,
+            2.0 * PI,
+        );
+        let _ = cr.fill();
+    }
+}
+
+struct FireworksState {
+    rockets: Vec<Rocket>,
+    sparks: Vec<Spark>,
+    max_particles: usize,
+    rng: StdRng,
+    last_time: std::time::Instant,
+    /// Seconds until the next launch
+    next_launch: f64,
+    current_width: f64,
+    current_height: f64,
+}
+
+impl FireworksState {
+    fn new(width: f64, height: f64, max_particles: usize) -> Self {
+        let seed = glib::DateTime::now_utc()
+            .map(|dt| dt.to_unix())
+            .unwrap_or(0) as u64;
+
+        Self {
+            rockets: Vec::new(),
+            sparks: Vec::with_capacity(max_particles),
+            max_particles,
+            rng: StdRng::seed_from_u64(seed),
+            last_time: std::time::Instant::now(),
+            next_launch: 0.5,
+            current_width: width,
+            current_height: height,
+        }
+    }
+
+    fn update(&mut self, width: f64, height: f64, now: std::time::Instant) {
+        self.current_width = width;
+        self.current_height = height;
+
+        let dt = now.duration_since(self.last_time).as_secs_f64().min(0.1);
+        self.last_time = now;
+
+        self.next_launch -= dt;
+        // Hold back launches while the sky is still full of sparks
+        let room = self.max_particles.saturating_sub(self.sparks.len());
+        if self.next_launch <= 0.0 && room >= *SPARKS_PER_BURST.start() {
+            self.rockets.push(Rocket::new(width, height, &mut self.rng));
+            self.next_launch = self.rng.random_range(LAUNCH_INTERVAL);
+        }
+
+        let mut bursts = Vec::new();
+        self.rockets.retain_mut(|rocket| {
+            if rocket.update(dt) {
+                bursts.push((rocket.x, rocket.y, rocket.palette));
+                false
+            } else {
+                true
+            }
+        });
+        for (x, y, palette) in bursts {
+            self.burst(x, y, palette);
+        }
+
+        for spark in &mut self.sparks {
+            spark.update(dt);
+        }
+        self.sparks
+            .retain(|spark| spark.life > 0.0 && spark.y < height + 10.0);
+    }
+
+    fn burst(&mut self, x: f64, y: f64, palette: usize) {
+        let colors = PALETTES[palette];
+        let room = self.max_particles.saturating_sub(self.sparks.len());
+        let count = self.rng.random_range(SPARKS_PER_BURST).min(room);
+
+        for _ in 0..count {
+            let angle = self.rng.random_range(0.0..2.0 * PI);
+            let speed = self.rng.random_range(60.0..220.0);
+            let life = self.rng.random_range(1.0..1.8);
+            self.sparks.push(Spark {
+                x,
+                y,
+                velocity_x: angle.cos() * speed,
+                velocity_y: angle.sin() * speed,
+                life,
+                max_life: life,
+                color: colors[self.rng.random_range(0..colors.len())],
+                size: self.rng.random_range(1.5..2.8),
+            });
+        }
+    }
+
+    fn draw(&self, cr: &cairo::Context) {
+        let _ = cr.save();
+        // Overlapping sparks brighten each other like light does
+        cr.set_operator(cairo::Operator::Add);
+
+        for rocket in &self.rockets {
+            rocket.draw(cr);
+        }
+        for spark in &self.sparks {
+            spark.draw(cr);
+        }
+
+        let _ = cr.restore();
+    }
+}
+
+impl ResizableEffectState for FireworksState {
+    fn handle_resize(&mut self, new_width: f64, new_height: f64) {
+        // Avoid division by zero
+        if self.current_width <= 0.0 || self.current_height <= 0.0 {
+            self.current_width = new_width;
+            self.current_height = new_height;
+            return;
+        }
+
+        // Calculate scale ratios
+        let scale_x = new_width / self.current_width;
+        let scale_y = new_height / self.current_height;
+
+        // Apply proportional scaling to rockets and sparks
+        for rocket in &mut self.rockets {
+            rocket.x *= scale_x;
+            rocket.y *= scale_y;
+            rocket.burst_y *= scale_y;
+        }
+        for spark in &mut self.sparks {
+            spark.x *= scale_x;
+            spark.y *= scale_y;
+        }
+
+        // Update stored dimensions
+        self.current_width = new_width;
+        self.current_height = new_height;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_year_spans_year_boundary() {
+        assert!(is_new_year(12, 31));
+        assert!(is_new_year(1, 1));
+        assert!(is_new_year(1, 2));
+        assert!(!is_new_year(12, 30));
+        assert!(!is_new_year(1, 3));
+        assert!(!is_new_year(6, 1));
+    }
+
+    #[test]
+    fn test_bursts_respect_particle_cap() {
+        let mut state = FireworksState::new(800.0, 600.0, 100);
+        for _ in 0..5 {
+            state.burst(400.0, 200.0, 0);
+        }
+        assert_eq!(state.sparks.len(), 100);
+    }
+}
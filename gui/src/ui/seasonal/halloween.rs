@@ -7,8 +7,10 @@
 //! - Mouse avoidance (bats scatter when the cursor approaches).
 
 use crate::config::seasonal_debug;
+use crate::config::user::SeasonalConfig;
 use crate::ui::seasonal::common::{
-    add_overlay_to_window, setup_resize_handler, MouseContext, ResizableEffectState,
+    add_overlay_to_window, plan_budget, setup_resize_handler, FrameTimer, MouseContext,
+    ResizableEffectState,
 };
 use crate::ui::seasonal::{register_effect, SeasonalEffect};
 use gtk4::cairo;
@@ -48,10 +50,15 @@ impl SeasonalEffect for HalloweenEffect {
         "Bats (Halloween)"
     }
 
+    fn id(&self) -> &'static str {
+        "halloween"
+    }
+
     fn apply(
         &self,
         window: &ApplicationWindow,
         mouse_context: Option<&MouseContext>,
+        config: &SeasonalConfig,
     ) -> Option<Rc<DrawingArea>> {
         use log::info;
 
@@ -70,24 +77,34 @@ impl SeasonalEffect for HalloweenEffect {
             Rc::new(RefCell::new((0.0f64, 0.0f64)))
         };
 
+        let count = config.scaled_count(BAT_COUNT);
         let state = Rc::new(RefCell::new(None::<BatState>));
         let setup_state = Rc::clone(&state);
         let draw_mouse_pos = mouse_pos.clone();
 
-        // Create timer and store its source ID for lifecycle management
-        let timer_source: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
-        let drawing_area_clone = drawing_area.clone();
-        let source_id = glib::timeout_add_local(std::time::Duration::from_millis(16), move || {
-            drawing_area_clone.queue_draw();
-            glib::ControlFlow::Continue
-        });
-        *timer_source.borrow_mut() = Some(source_id);
+        // Redraw timer, slowed down on the first frame if drawing is expensive
+        let timer = FrameTimer::new(drawing_area.clone());
+        let budget_timer = Rc::downgrade(&timer);
+        let intensity = config.intensity();
 
-        drawing_area.set_draw_func(move |_da, cr, width, height| {
+        drawing_area.set_draw_func(move |da, cr, width, height| {
             let mut state_ref = setup_state.borrow_mut();
 
             if state_ref.is_none() {
-                *state_ref = Some(BatState::new(width as f64, height as f64));
+                let budget = plan_budget(
+                    "halloween",
+                    da,
+                    width as f64,
+                    height as f64,
+                    intensity,
+                    &budget_timer,
+                );
+                *state_ref = Some(BatState::new(
+                    width as f64,
+                    height as f64,
+                    budget.count(count),
+                    budget.gradients,
+                ));
             }
 
             if let Some(bat_state) = state_ref.as_mut() {
@@ -103,7 +120,9 @@ impl SeasonalEffect for HalloweenEffect {
                 let _ = cr.restore();
 
                 bat_state.draw_bats(cr);
-                bat_state.draw_fog(cr, width as f64, height as f64);
+                if bat_state.fog {
+                    bat_state.draw_fog(cr, width as f64, height as f64);
+                }
             }
         });
 
@@ -112,14 +131,12 @@ impl SeasonalEffect for HalloweenEffect {
 
         if add_overlay_to_window(window, &drawing_area) {
             // Register effect for lifecycle management (timer start/stop on toggle)
-            register_effect(drawing_area.clone(), timer_source);
+            register_effect(drawing_area.clone(), timer);
             info!("Halloween effect overlay added successfully");
             Some(drawing_area)
         } else {
             // Clean up timer if overlay failed
-            if let Some(source_id) = timer_source.borrow_mut().take() {
-                source_id.remove();
-            }
+            timer.stop();
             info!("Failed to add Halloween effect overlay");
             None
         }
@@ -295,6 +312,8 @@ impl Bat {
 
 struct BatState {
     bats: Vec<Bat>,
+    /// Whether the fog along the bottom edge is drawn
+    fog: bool,
     rng: StdRng,
     last_frame_time: std::time::Instant,
     current_width: f64,
@@ -302,17 +321,18 @@ struct BatState {
 }
 
 impl BatState {
-    fn new(width: f64, height: f64) -> Self {
+    fn new(width: f64, height: f64, count: usize, fog: bool) -> Self {
         let seed = glib::DateTime::now_utc()
             .map(|dt| dt.to_unix())
             .unwrap_or(0) as u64;
 
-        let bats = (0..BAT_COUNT)
+        let bats = (0..count)
             .map(|i| Bat::new(width, height, seed.wrapping_add(i as u64 * 100)))
             .collect();
 
         Self {
             bats,
+            fog,
             rng: StdRng::seed_from_u64(seed),
             last_frame_time: std::time::Instant::now(),
             current_width: width,
@@ -1,34 +1,44 @@
 //! Seasonal overlay effects for the application window.
 //!
 //! This module provides animated overlay effects that appear during specific
-//! times of the year (e.g., snow for December, Halloween effects for October).
+//! times of the year (e.g., snow for December, Halloween effects for October,
+//! fireworks around New Year).
 //!
 //! Effects can be toggled on/off, and the animation timer is stopped when
-//! effects are disabled to save CPU/memory.
+//! effects are disabled to save CPU/memory. Which effects run and how many
+//! particles they draw comes from the `[seasonal]` section of the user config.
+//! Large windows and software rendering scale that down further, see
+//! [`common::budget`].
 
 mod common;
+mod fireworks;
 mod halloween;
 mod snow;
 
-use crate::ui::seasonal::common::MouseContext;
+use crate::config::user;
+use crate::ui::accessibility;
+use crate::ui::seasonal::common::{FrameTimer, MouseContext};
 use gtk4::glib;
 use gtk4::prelude::*;
 use gtk4::{ApplicationWindow, DrawingArea};
 use log::info;
 use std::cell::RefCell;
 use std::rc::Rc;
-use std::sync::atomic::{AtomicBool, Ordering};
 
+pub use fireworks::FireworksEffect;
 pub use halloween::HalloweenEffect;
 pub use snow::SnowEffect;
 
-/// Global state for whether seasonal effects are enabled.
-static EFFECTS_ENABLED: AtomicBool = AtomicBool::new(true);
+thread_local! {
+    /// Window the effects were applied to, kept so they can be restarted.
+    static TARGET: RefCell<Option<(glib::WeakRef<ApplicationWindow>, MouseContext)>> =
+        const { RefCell::new(None) };
+}
 
 /// Entry for a registered effect with its drawing area and timer control.
 struct EffectEntry {
     drawing_area: Rc<DrawingArea>,
-    timer_source: Rc<RefCell<Option<glib::SourceId>>>,
+    timer: Rc<FrameTimer>,
 }
 
 /// Global registry of active effects.
@@ -48,58 +58,85 @@ fn get_effect_registry() -> &'static RefCell<Vec<EffectEntry>> {
 
 /// Check if seasonal effects are currently enabled.
 pub fn are_effects_enabled() -> bool {
-    EFFECTS_ENABLED.load(Ordering::Relaxed)
+    user::shared()
+        .get()
+        .seasonal
+        .is_enabled(accessibility::reduced_motion())
 }
 
-/// Set whether seasonal effects are enabled and update visibility/timers of drawing areas.
+/// Set whether seasonal effects are enabled and save the choice.
 pub fn set_effects_enabled(enabled: bool) {
-    EFFECTS_ENABLED.store(enabled, Ordering::Relaxed);
+    if enabled != are_effects_enabled() {
+        // Only pin the choice once it departs from the motion-based default
+        user::shared().update(|config| config.seasonal.enabled = Some(enabled));
+    }
+    sync_effects();
+}
 
+/// Update visibility/timers of drawing areas to match the config.
+///
+/// Effects that were skipped at startup because they were disabled are
+/// applied now.
+pub fn sync_effects() {
+    let enabled = are_effects_enabled();
     let registry = get_effect_registry();
+    if enabled && registry.borrow().is_empty() {
+        restart_effects();
+        return;
+    }
+
     for entry in registry.borrow().iter() {
         entry.drawing_area.set_visible(enabled);
 
         if enabled {
             // Restart timer if not already running
-            let mut timer_ref = entry.timer_source.borrow_mut();
-            if timer_ref.is_none() {
-                let drawing_area_clone = entry.drawing_area.clone();
-                let source_id =
-                    glib::timeout_add_local(std::time::Duration::from_millis(16), move || {
-                        drawing_area_clone.queue_draw();
-                        glib::ControlFlow::Continue
-                    });
-                *timer_ref = Some(source_id);
+            if entry.timer.start() {
                 info!("Seasonal effect timer restarted");
             }
-        } else {
-            // Stop the timer to save resources
-            let mut timer_ref = entry.timer_source.borrow_mut();
-            if let Some(source_id) = timer_ref.take() {
-                source_id.remove();
-                info!("Seasonal effect timer stopped");
-            }
+        } else if entry.timer.stop() {
+            // Stopped to save resources
+            info!("Seasonal effect timer stopped");
         }
     }
 }
 
+/// All known effects, in drawing order.
+fn all_effects() -> Vec<Box<dyn SeasonalEffect>> {
+    vec![
+        Box::new(SnowEffect),
+        Box::new(HalloweenEffect),
+        Box::new(FireworksEffect),
+    ]
+}
+
 /// Check if any seasonal effect is currently active.
 pub fn has_active_effect() -> bool {
-    let effects: Vec<Box<dyn SeasonalEffect>> =
-        vec![Box::new(SnowEffect), Box::new(HalloweenEffect)];
+    all_effects().iter().any(|e| e.is_active())
+}
 
-    effects.iter().any(|e| e.is_active())
+/// Tear down the running effects and apply them again with the current config.
+pub fn restart_effects() {
+    let registry = get_effect_registry();
+    for entry in registry.borrow_mut().drain(..) {
+        entry.timer.stop();
+        if let Some(overlay) = entry.drawing_area.parent().and_downcast::<gtk4::Overlay>() {
+            overlay.remove_overlay(&*entry.drawing_area);
+        }
+    }
+
+    let window = TARGET.with_borrow(|target| target.as_ref().and_then(|(w, _)| w.upgrade()));
+    if let Some(window) = window {
+        info!("Restarting seasonal effects");
+        apply_seasonal_effects(&window);
+    }
 }
 
-/// Register an effect with its drawing area and timer source for lifecycle management.
-pub fn register_effect(
-    drawing_area: Rc<DrawingArea>,
-    timer_source: Rc<RefCell<Option<glib::SourceId>>>,
-) {
+/// Register an effect with its drawing area and timer for lifecycle management.
+pub fn register_effect(drawing_area: Rc<DrawingArea>, timer: Rc<FrameTimer>) {
     let registry = get_effect_registry();
     registry.borrow_mut().push(EffectEntry {
         drawing_area,
-        timer_source,
+        timer,
     });
 }
 
@@ -111,18 +148,33 @@ pub trait SeasonalEffect {
     /// Get the name of this seasonal effect (for logging).
     fn name(&self) -> &'static str;
 
+    /// Config id used for the per-effect enable flag.
+    fn id(&self) -> &'static str;
+
     /// Apply this effect to the given window.
     /// The mouse_context provides mouse position if the effect needs it.
+    /// The config decides how many particles the effect draws.
     /// Returns the drawing area if the effect was successfully applied.
     fn apply(
         &self,
         window: &ApplicationWindow,
         mouse_context: Option<&MouseContext>,
+        config: &user::SeasonalConfig,
     ) -> Option<Rc<DrawingArea>>;
 }
 
 /// Apply any active seasonal effects to the window.
 pub fn apply_seasonal_effects(window: &ApplicationWindow) {
+    // Mouse tracking is set up once per window, restarts reuse it
+    let mouse_context = TARGET.with_borrow_mut(|target| match target {
+        Some((w, context)) if w.upgrade().as_ref() == Some(window) => context.clone(),
+        _ => {
+            let context = common::setup_mouse_tracking(window);
+            *target = Some((window.downgrade(), context.clone()));
+            context
+        }
+    });
+
     if !are_effects_enabled() {
         info!("Seasonal effects are disabled");
         return;
@@ -130,15 +182,14 @@ pub fn apply_seasonal_effects(window: &ApplicationWindow) {
 
     info!("Checking for active seasonal effects...");
 
-    let mouse_context = common::setup_mouse_tracking(window);
-
-    let effects: Vec<Box<dyn SeasonalEffect>> =
-        vec![Box::new(SnowEffect), Box::new(HalloweenEffect)];
+    let config = user::shared().get().seasonal.clone();
 
-    for effect in effects {
-        if effect.is_active() {
+    for effect in all_effects() {
+        if effect.is_active() && !config.effect_enabled(effect.id()) {
+            info!("Seasonal effect {} is turned off", effect.name());
+        } else if effect.is_active() {
             info!("Active seasonal effect detected: {}", effect.name());
-            if let Some(_drawing_area) = effect.apply(window, Some(&mouse_context)) {
+            if let Some(_drawing_area) = effect.apply(window, Some(&mouse_context), &config) {
                 // Effect registers itself via register_effect()
                 info!("Successfully applied {} effect", effect.name());
             } else {
@@ -1,10 +1,15 @@
 //! Christmas snow effect overlay.
 //!
 //! Adds a high-quality animated snow effect with parallax and soft-glow flakes.
+//! Foreground flakes pile up into a drift along the bottom edge and a thin
+//! layer on the header bar, both of which slowly melt again. The cursor
+//! blows nearby flakes aside.
 
 use crate::config::seasonal_debug;
+use crate::config::user::SeasonalConfig;
 use crate::ui::seasonal::common::{
-    add_overlay_to_window, setup_resize_handler, ResizableEffectState,
+    add_overlay_to_window, find_toolbar_view, plan_budget, setup_resize_handler, FrameTimer,
+    MouseContext, ResizableEffectState,
 };
 use crate::ui::seasonal::{register_effect, SeasonalEffect};
 use gtk4::cairo;
@@ -19,6 +24,30 @@ use std::rc::Rc;
 
 const SNOW_COUNT: usize = 80;
 const WIND_STRENGTH: f64 = 0.5;
+const MOUSE_GUST_RADIUS: f64 = 150.0;
+const MOUSE_GUST_FORCE: f64 = 600.0;
+/// Share of the cursor's horizontal speed passed on to nearby flakes.
+const CURSOR_WIND_TRANSFER: f64 = 1.5;
+/// Cursor speed in px/s below which moving it causes no wind.
+const CURSOR_WIND_THRESHOLD: f64 = 400.0;
+/// How quickly pushed flakes return to their normal drift.
+const PUSH_DAMPING: f64 = 2.5;
+
+/// Width of one drift column in pixels.
+const DRIFT_COLUMN_WIDTH: f64 = 6.0;
+/// Tallest the bottom drift grows at 1x intensity; lower intensities scale it down.
+const DRIFT_MAX_HEIGHT: f64 = 30.0;
+const LEDGE_MAX_HEIGHT: f64 = 5.0;
+/// Snow added by one landed flake, spread over neighbouring columns.
+const FLAKE_DEPOSIT: f64 = 2.5;
+/// Height lost per second; a full drift melts in about five minutes.
+const MELT_RATE: f64 = DRIFT_MAX_HEIGHT / 300.0;
+/// How quickly columns even out with their neighbours.
+const SETTLE_RATE: f64 = 0.5;
+/// Flakes further back than this fall past the drifts.
+const LANDING_DEPTH: f64 = 0.8;
+/// Share of foreground flakes caught by the header bar.
+const LEDGE_CATCH_CHANCE: f64 = 0.08;
 
 /// Christmas snow effect.
 pub struct SnowEffect;
@@ -42,42 +71,74 @@ impl SeasonalEffect for SnowEffect {
         "Snow (Christmas)"
     }
 
+    fn id(&self) -> &'static str {
+        "snow"
+    }
+
     fn apply(
         &self,
         window: &ApplicationWindow,
-        _mouse_context: Option<&crate::ui::seasonal::common::MouseContext>,
+        mouse_context: Option<&MouseContext>,
+        config: &SeasonalConfig,
     ) -> Option<Rc<DrawingArea>> {
         let drawing_area = Rc::new(DrawingArea::new());
         drawing_area.set_hexpand(true);
         drawing_area.set_vexpand(true);
         drawing_area.set_can_focus(false);
         drawing_area.set_sensitive(false);
+        // Clicks must reach the widgets underneath
+        drawing_area.set_can_target(false);
         drawing_area.set_halign(gtk4::Align::Fill);
         drawing_area.set_valign(gtk4::Align::Fill);
         drawing_area.set_visible(crate::ui::seasonal::are_effects_enabled());
 
+        let count = config.scaled_count(SNOW_COUNT);
+        // Low intensities only leave a thin dusting
+        let drift_cap = DRIFT_MAX_HEIGHT * config.intensity().min(1.0);
+        let toolbar = find_toolbar_view(window);
+        let mouse_pos = if let Some(ctx) = mouse_context {
+            ctx.position_internal()
+        } else {
+            Rc::new(RefCell::new((0.0f64, 0.0f64)))
+        };
         let state = Rc::new(RefCell::new(None::<SnowState>));
         let setup_state = Rc::clone(&state);
 
-        // Create timer and store its source ID for lifecycle management
-        let timer_source: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
-        let drawing_area_clone = drawing_area.clone();
-        let source_id = glib::timeout_add_local(std::time::Duration::from_millis(16), move || {
-            drawing_area_clone.queue_draw();
-            glib::ControlFlow::Continue
-        });
-        *timer_source.borrow_mut() = Some(source_id);
+        // Redraw timer, slowed down on the first frame if drawing is expensive
+        let timer = FrameTimer::new(drawing_area.clone());
+        let budget_timer = Rc::downgrade(&timer);
+        let intensity = config.intensity();
 
-        drawing_area.set_draw_func(move |_da, cr, width, height| {
+        drawing_area.set_draw_func(move |da, cr, width, height| {
             let mut state_ref = setup_state.borrow_mut();
 
             if state_ref.is_none() {
-                *state_ref = Some(SnowState::new(width as f64, height as f64));
+                let budget = plan_budget(
+                    "snow",
+                    da,
+                    width as f64,
+                    height as f64,
+                    intensity,
+                    &budget_timer,
+                );
+                *state_ref = Some(SnowState::new(
+                    width as f64,
+                    height as f64,
+                    budget.count(count),
+                    drift_cap,
+                    budget.gradients,
+                ));
             }
 
             if let Some(snow_state) = state_ref.as_mut() {
                 let now = std::time::Instant::now();
-                snow_state.update(width as f64, height as f64, now);
+                // The header bar's bottom edge, when it is visible
+                let ledge_y = toolbar
+                    .as_ref()
+                    .map(|toolbar| f64::from(toolbar.top_bar_height()))
+                    .filter(|ledge_y| *ledge_y > 0.0);
+                let mouse = *mouse_pos.borrow();
+                snow_state.update(width as f64, height as f64, ledge_y, mouse, now);
 
                 let _ = cr.save();
                 cr.set_operator(cairo::Operator::Clear);
@@ -94,13 +155,11 @@ impl SeasonalEffect for SnowEffect {
 
         if add_overlay_to_window(window, &drawing_area) {
             // Register effect for lifecycle management (timer start/stop on toggle)
-            register_effect(drawing_area.clone(), timer_source);
+            register_effect(drawing_area.clone(), timer);
             Some(drawing_area)
         } else {
             // Clean up timer if overlay failed
-            if let Some(source_id) = timer_source.borrow_mut().take() {
-                source_id.remove();
-            }
+            timer.stop();
             None
         }
     }
@@ -115,6 +174,16 @@ struct Snowflake {
     sway_offset: f64,
     sway_speed: f64,
     size: f64,
+    /// Extra velocity from cursor gusts, fading back to zero
+    push_x: f64,
+    push_y: f64,
+}
+
+/// Cursor position and horizontal speed for one frame.
+struct Cursor {
+    x: f64,
+    y: f64,
+    velocity_x: f64,
 }
 
 impl Snowflake {
@@ -128,19 +197,61 @@ impl Snowflake {
             sway_offset: rng.random_range(0.0..2.0 * PI),
             sway_speed: rng.random_range(0.5..2.0),
             size: rng.random_range(2.0..5.0) * z,
+            push_x: 0.0,
+            push_y: 0.0,
         }
     }
 
-    fn update(&mut self, width: f64, height: f64, dt: f64, wind: f64, rng: &mut StdRng) {
-        self.y += self.speed_y * dt;
+    /// Start again just above the top edge.
+    fn respawn(&mut self, width: f64, rng: &mut StdRng) {
+        self.y = rng.random_range(-10.0..0.0);
+        self.x = rng.random_range(0.0..width);
+        self.push_x = 0.0;
+        self.push_y = 0.0;
+    }
+
+    /// Push the flake away from the cursor, and along with it when it moves fast.
+    fn apply_gust(&mut self, cursor: &Cursor, dt: f64) {
+        let dx = self.x - cursor.x;
+        let dy = self.y - cursor.y;
+        let dist_sq = dx * dx + dy * dy;
+
+        if dist_sq > 0.0 && dist_sq < (MOUSE_GUST_RADIUS * MOUSE_GUST_RADIUS) {
+            let dist = dist_sq.sqrt();
+            let falloff = (MOUSE_GUST_RADIUS - dist) / MOUSE_GUST_RADIUS;
+            // Nearer flakes are lighter to push around
+            let force = falloff * MOUSE_GUST_FORCE * self.z * dt;
+            self.push_x += dx / dist * force;
+            self.push_y += dy / dist * force;
+
+            if cursor.velocity_x.abs() > CURSOR_WIND_THRESHOLD {
+                self.push_x += cursor.velocity_x * CURSOR_WIND_TRANSFER * falloff * dt;
+            }
+        }
+
+        let damping = (PUSH_DAMPING * dt).min(1.0);
+        self.push_x -= self.push_x * damping;
+        self.push_y -= self.push_y * damping;
+    }
+
+    fn update(
+        &mut self,
+        width: f64,
+        height: f64,
+        dt: f64,
+        wind: f64,
+        cursor: &Cursor,
+        rng: &mut StdRng,
+    ) {
+        self.apply_gust(cursor, dt);
+        self.y += (self.speed_y + self.push_y) * dt;
         self.sway_offset += self.sway_speed * dt;
         let horizontal_move = (self.sway_offset.sin() * 20.0 * self.z) + (wind * 50.0);
-        self.x += horizontal_move * dt;
+        self.x += (horizontal_move + self.push_x) * dt;
 
         // When wrapping around, respawn at random position in current window dimensions
         if self.y > height + 10.0 {
-            self.y = rng.random_range(-10.0..0.0);
-            self.x = rng.random_range(0.0..width);
+            self.respawn(width, rng);
         }
         if self.x < -20.0 {
             self.x = rng.random_range(width..width + 20.0);
@@ -169,38 +280,158 @@ impl Snowflake {
     }
 }
 
+/// Snow piled up along a horizontal edge, as a row of column heights.
+struct Drift {
+    columns: Vec<f64>,
+    max_height: f64,
+}
+
+impl Drift {
+    fn new(width: f64, max_height: f64) -> Self {
+        Self {
+            columns: vec![0.0; Self::column_count(width)],
+            max_height,
+        }
+    }
+
+    fn column_count(width: f64) -> usize {
+        (width / DRIFT_COLUMN_WIDTH).ceil().max(1.0) as usize
+    }
+
+    fn column_at(&self, x: f64) -> usize {
+        ((x / DRIFT_COLUMN_WIDTH).max(0.0) as usize).min(self.columns.len() - 1)
+    }
+
+    fn height_at(&self, x: f64) -> f64 {
+        self.columns[self.column_at(x)]
+    }
+
+    /// Add a landed flake, spreading it over the neighbouring columns.
+    fn deposit(&mut self, x: f64, amount: f64, rng: &mut StdRng) {
+        let center = self.column_at(x) as isize;
+        for (offset, weight) in [(-2, 0.1), (-1, 0.2), (0, 0.4), (1, 0.2), (2, 0.1)] {
+            let Ok(index) = usize::try_from(center + offset) else {
+                continue;
+            };
+            if let Some(column) = self.columns.get_mut(index) {
+                let jitter = rng.random_range(0.6..1.4);
+                *column = (*column + amount * weight * jitter).min(self.max_height);
+            }
+        }
+    }
+
+    /// Melt and let columns settle towards their neighbours.
+    fn update(&mut self, dt: f64) {
+        let settle = (SETTLE_RATE * dt).min(1.0);
+        let previous = self.columns.clone();
+        let last = previous.len() - 1;
+        for (index, column) in self.columns.iter_mut().enumerate() {
+            let left = previous[index.saturating_sub(1)];
+            let right = previous[(index + 1).min(last)];
+            let settled = *column + ((left + right) / 2.0 - *column) * settle;
+            *column = (settled - MELT_RATE * dt).max(0.0);
+        }
+    }
+
+    /// Resample the columns for a new width, keeping the drift's shape.
+    fn resize(&mut self, new_width: f64) {
+        let count = Self::column_count(new_width);
+        let old = std::mem::take(&mut self.columns);
+        self.columns = (0..count)
+            .map(|index| old[index * old.len() / count])
+            .collect();
+    }
+
+    fn is_empty(&self) -> bool {
+        self.columns.iter().all(|height| *height < 0.5)
+    }
+
+    /// Fill the drift with its base resting on `base_y`.
+    fn draw(&self, cr: &cairo::Context, base_y: f64, width: f64) {
+        if self.is_empty() {
+            return;
+        }
+
+        let _ = cr.save();
+        cr.move_to(0.0, base_y);
+        cr.line_to(0.0, base_y - self.columns[0]);
+        // Curve through column midpoints for a soft outline
+        for pair in self.columns.windows(2).enumerate() {
+            let (index, heights) = pair;
+            let x = (index as f64 + 0.5) * DRIFT_COLUMN_WIDTH;
+            let next_x = x + DRIFT_COLUMN_WIDTH;
+            let mid_x = (x + next_x) / 2.0;
+            let mid_y = base_y - (heights[0] + heights[1]) / 2.0;
+            cr.curve_to(x, base_y - heights[0], x, base_y - heights[0], mid_x, mid_y);
+        }
+        let last = self.columns[self.columns.len() - 1];
+        cr.line_to(width, base_y - last);
+        cr.line_to(width, base_y);
+        cr.close_path();
+
+        let top = base_y - self.max_height;
+        let fill = cairo::LinearGradient::new(0.0, top, 0.0, base_y);
+        fill.add_color_stop_rgba(0.0, 1.0, 1.0, 1.0, 0.95);
+        fill.add_color_stop_rgba(1.0, 0.88, 0.93, 1.0, 0.85);
+        let _ = cr.set_source(&fill);
+        let _ = cr.fill();
+        let _ = cr.restore();
+    }
+}
+
 struct SnowState {
     snowflakes: Vec<Snowflake>,
+    drift: Drift,
+    ledge: Drift,
+    ledge_y: Option<f64>,
+    /// Whether the glow along the bottom edge is drawn
+    glow: bool,
     rng: StdRng,
     last_time: std::time::Instant,
     wind: f64,
     wind_target: f64,
+    /// Cursor position last frame and its smoothed horizontal speed
+    last_mouse: (f64, f64),
+    mouse_velocity_x: f64,
     current_width: f64,
     current_height: f64,
 }
 
 impl SnowState {
-    fn new(width: f64, height: f64) -> Self {
+    fn new(width: f64, height: f64, count: usize, drift_cap: f64, glow: bool) -> Self {
         let seed = glib::DateTime::now_utc()
             .map(|dt| dt.to_unix())
             .unwrap_or(0) as u64;
         let mut rng = StdRng::seed_from_u64(seed);
-        let snowflakes = (0..SNOW_COUNT)
+        let snowflakes = (0..count)
             .map(|_| Snowflake::new(width, height, &mut rng))
             .collect();
 
         Self {
             snowflakes,
+            drift: Drift::new(width, drift_cap),
+            ledge: Drift::new(width, LEDGE_MAX_HEIGHT.min(drift_cap)),
+            ledge_y: None,
+            glow,
             rng,
             last_time: std::time::Instant::now(),
             wind: 0.0,
             wind_target: 0.0,
+            last_mouse: (0.0, 0.0),
+            mouse_velocity_x: 0.0,
             current_width: width,
             current_height: height,
         }
     }
 
-    fn update(&mut self, width: f64, height: f64, now: std::time::Instant) {
+    fn update(
+        &mut self,
+        width: f64,
+        height: f64,
+        ledge_y: Option<f64>,
+        mouse: (f64, f64),
+        now: std::time::Instant,
+    ) {
         // Update stored dimensions during normal loop just in case,
         // though handle_resize does the heavy lifting.
         self.current_width = width;
@@ -214,9 +445,46 @@ impl SnowState {
         }
         self.wind += (self.wind_target - self.wind) * dt;
 
+        // Estimate cursor speed from successive positions
+        if dt > 0.0 {
+            let velocity_x = (mouse.0 - self.last_mouse.0) / dt;
+            self.mouse_velocity_x += (velocity_x - self.mouse_velocity_x) * (10.0 * dt).min(1.0);
+        }
+        self.last_mouse = mouse;
+        let cursor = Cursor {
+            x: mouse.0,
+            y: mouse.1,
+            velocity_x: self.mouse_velocity_x,
+        };
+
+        // A moved header bar leaves its snow behind
+        if ledge_y != self.ledge_y {
+            self.ledge = Drift::new(width, self.ledge.max_height);
+            self.ledge_y = ledge_y;
+        }
+
         for flake in &mut self.snowflakes {
-            flake.update(width, height, dt, self.wind, &mut self.rng);
+            let previous_y = flake.y;
+            flake.update(width, height, dt, self.wind, &cursor, &mut self.rng);
+            if flake.z < LANDING_DEPTH {
+                continue;
+            }
+
+            let deposit = FLAKE_DEPOSIT * flake.size / 5.0;
+            if flake.y >= height - self.drift.height_at(flake.x) {
+                self.drift.deposit(flake.x, deposit, &mut self.rng);
+                flake.respawn(width, &mut self.rng);
+            } else if let Some(ledge_y) = self.ledge_y {
+                let crossed = previous_y < ledge_y && flake.y >= ledge_y;
+                if crossed && self.rng.random::<f64>() < LEDGE_CATCH_CHANCE {
+                    self.ledge.deposit(flake.x, deposit, &mut self.rng);
+                    flake.respawn(width, &mut self.rng);
+                }
+            }
         }
+
+        self.drift.update(dt);
+        self.ledge.update(dt);
     }
 
     fn draw(&self, cr: &cairo::Context, width: f64, height: f64) {
@@ -227,14 +495,21 @@ impl SnowState {
             flake.draw(cr);
         }
 
-        let _ = cr.save();
-        let glow = cairo::LinearGradient::new(0.0, height - 100.0, 0.0, height);
-        glow.add_color_stop_rgba(0.0, 1.0, 1.0, 1.0, 0.0);
-        glow.add_color_stop_rgba(1.0, 1.0, 1.0, 1.0, 0.15);
-        let _ = cr.set_source(&glow);
-        cr.rectangle(0.0, height - 100.0, width, 100.0);
-        let _ = cr.fill();
-        let _ = cr.restore();
+        if self.glow {
+            let _ = cr.save();
+            let glow = cairo::LinearGradient::new(0.0, height - 100.0, 0.0, height);
+            glow.add_color_stop_rgba(0.0, 1.0, 1.0, 1.0, 0.0);
+            glow.add_color_stop_rgba(1.0, 1.0, 1.0, 1.0, 0.15);
+            let _ = cr.set_source(&glow);
+            cr.rectangle(0.0, height - 100.0, width, 100.0);
+            let _ = cr.fill();
+            let _ = cr.restore();
+        }
+
+        self.drift.draw(cr, height, width);
+        if let Some(ledge_y) = self.ledge_y {
+            self.ledge.draw(cr, ledge_y, width);
+        }
     }
 }
 
@@ -256,9 +531,68 @@ impl ResizableEffectState for SnowState {
             flake.x *= scale_x;
             flake.y *= scale_y;
         }
+        // Drifts stay anchored to their edges, only their width changes
+        self.drift.resize(new_width);
+        self.ledge.resize(new_width);
 
         // Update stored dimensions
         self.current_width = new_width;
         self.current_height = new_height;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drift_is_capped_and_melts() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut drift = Drift::new(120.0, 7.5);
+        for _ in 0..500 {
+            drift.deposit(60.0, FLAKE_DEPOSIT, &mut rng);
+        }
+        assert!(drift.height_at(60.0) <= 7.5);
+        assert!(drift.height_at(60.0) > 7.0);
+
+        // Five minutes without new snow melts everything
+        for _ in 0..3000 {
+            drift.update(0.1);
+        }
+        assert!(drift.is_empty());
+    }
+
+    #[test]
+    fn test_cursor_pushes_nearby_flakes_away() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut flake = Snowflake::new(800.0, 600.0, &mut rng);
+        flake.x = 110.0;
+        flake.y = 100.0;
+        let cursor = Cursor {
+            x: 100.0,
+            y: 100.0,
+            velocity_x: 0.0,
+        };
+
+        flake.apply_gust(&cursor, 0.016);
+        assert!(flake.push_x > 0.0);
+
+        // Out of reach, the push fades
+        let push = flake.push_x;
+        flake.x = 400.0;
+        flake.apply_gust(&cursor, 0.016);
+        assert!(flake.push_x < push);
+    }
+
+    #[test]
+    fn test_drift_resize_keeps_shape() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut drift = Drift::new(600.0, DRIFT_MAX_HEIGHT);
+        drift.deposit(0.0, FLAKE_DEPOSIT, &mut rng);
+
+        drift.resize(300.0);
+        assert_eq!(drift.columns.len(), 50);
+        assert!(drift.height_at(0.0) > 0.0);
+        assert_eq!(drift.height_at(299.0), 0.0);
+    }
+}
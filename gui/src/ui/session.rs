@@ -0,0 +1,84 @@
+//! Ending the graphical session, e.g. after group changes.
+
+use gtk4::{gio, glib};
+use log::{info, warn};
+
+/// Session manager logout calls, tried in order: bus name, object path,
+/// interface, method and arguments.
+type LogoutCall = (
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    fn() -> Option<glib::Variant>,
+);
+
+const LOGOUT_CALLS: &[LogoutCall] = &[
+    // KDE Plasma
+    (
+        "org.kde.Shutdown",
+        "/Shutdown",
+        "org.kde.Shutdown",
+        "logout",
+        || None,
+    ),
+    // GNOME and Cinnamon-style session managers; 1 skips the confirmation
+    (
+        "org.gnome.SessionManager",
+        "/org/gnome/SessionManager",
+        "org.gnome.SessionManager",
+        "Logout",
+        || Some((1u32,).into()),
+    ),
+    // Xfce: no prompt, allow saving
+    (
+        "org.xfce.SessionManager",
+        "/org/xfce/SessionManager",
+        "org.xfce.SessionManager",
+        "Logout",
+        || Some((false, true).into()),
+    ),
+];
+
+/// Log out of the current session through the desktop's session manager,
+/// falling back to terminating the logind session.
+pub fn log_out() {
+    match gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE) {
+        Ok(connection) => {
+            for (name, path, interface, method, args) in LOGOUT_CALLS {
+                match connection.call_sync(
+                    Some(name),
+                    path,
+                    interface,
+                    method,
+                    args().as_ref(),
+                    None,
+                    gio::DBusCallFlags::NONE,
+                    5000,
+                    gio::Cancellable::NONE,
+                ) {
+                    Ok(_) => {
+                        info!("Requested logout through {}", name);
+                        return;
+                    }
+                    Err(e) => info!("{} unavailable: {}", name, e),
+                }
+            }
+        }
+        Err(e) => warn!("Failed to connect to the session bus: {}", e),
+    }
+    terminate_session();
+}
+
+/// End the current graphical session through logind.
+fn terminate_session() {
+    let mut command = std::process::Command::new("loginctl");
+    match std::env::var("XDG_SESSION_ID") {
+        Ok(session) => command.args(["terminate-session", &session]),
+        Err(_) => command.args(["terminate-user", &crate::config::env::get().user]),
+    };
+
+    if let Err(e) = command.spawn() {
+        warn!("Failed to request session logout: {}", e);
+    }
+}
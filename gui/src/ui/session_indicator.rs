@@ -0,0 +1,137 @@
+//! Header bar indicator of the administrator session.
+//!
+//! The indicator shows while the authentication daemon runs, and its
+//! popover lists what the daemon ran as root so far. It refreshes on
+//! startup, after every task run and whenever the popover opens.
+
+use crate::core;
+use crate::core::download::format_time_remaining;
+use crate::core::events::{self, Event};
+use crate::i18n::{fill, gettext, ngettext};
+use crate::ui::utils::try_extract_widget;
+use gtk4::prelude::*;
+use gtk4::{glib, Builder, Label, MenuButton, Popover};
+use log::warn;
+use std::sync::mpsc;
+use std::time::Duration;
+use xero_auth::protocol::SessionStats;
+
+/// Widgets of the indicator.
+#[derive(Clone)]
+struct Indicator {
+    button: MenuButton,
+    commands: Label,
+    cpu_time: Label,
+    last_command: Label,
+}
+
+/// Show the indicator while the daemon runs and keep its figures current.
+pub fn init(builder: &Builder) {
+    let (Some(button), Some(popover), Some(commands), Some(cpu_time), Some(last_command)) = (
+        try_extract_widget::<MenuButton>(builder, "session_indicator"),
+        try_extract_widget::<Popover>(builder, "session_popover"),
+        try_extract_widget::<Label>(builder, "session_commands"),
+        try_extract_widget::<Label>(builder, "session_cpu_time"),
+        try_extract_widget::<Label>(builder, "session_last_command"),
+    ) else {
+        warn!("Main window has no session indicator");
+        return;
+    };
+    let indicator = Indicator {
+        button,
+        commands,
+        cpu_time,
+        last_command,
+    };
+
+    let on_show = indicator.clone();
+    popover.connect_show(move |_| refresh(&on_show));
+
+    let on_event = indicator.clone();
+    events::subscribe(move |event| {
+        if let Event::TaskFinished { .. } = event {
+            refresh(&on_event);
+        }
+    })
+    .detach();
+
+    refresh(&indicator);
+}
+
+/// Ask the daemon for its counters in the background and show them.
+fn refresh(indicator: &Indicator) {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let stats = tokio::runtime::Runtime::new()
+            .map_err(anyhow::Error::from)
+            .and_then(|rt| rt.block_on(core::daemon::session_stats()));
+        let _ = sender.send(stats);
+    });
+
+    let indicator = indicator.clone();
+    glib::timeout_add_local(Duration::from_millis(200), move || {
+        let stats = match receiver.try_recv() {
+            Ok(stats) => stats,
+            Err(mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+            Err(mpsc::TryRecvError::Disconnected) => return glib::ControlFlow::Break,
+        };
+        match stats {
+            Ok(Some(stats)) => show(&indicator, &stats),
+            Ok(None) => indicator.button.set_visible(false),
+            Err(e) => {
+                warn!("Failed to read the daemon session counters: {:#}", e);
+                indicator.button.set_visible(false);
+            }
+        }
+        glib::ControlFlow::Break
+    });
+}
+
+fn show(indicator: &Indicator, stats: &SessionStats) {
+    let commands = u32::try_from(stats.commands).unwrap_or(u32::MAX);
+    indicator.commands.set_label(&fill(
+        &ngettext(
+            "{} command run as administrator",
+            "{} commands run as administrator",
+            commands,
+        ),
+        &[&stats.commands.to_string()],
+    ));
+    indicator.cpu_time.set_label(&fill(
+        &gettext("CPU time used: {}"),
+        &[&format_cpu_time(stats.cpu_time_micros)],
+    ));
+    indicator
+        .last_command
+        .set_label(&match &stats.last_command {
+            Some(last) => fill(
+                &gettext("Last command: {} (exit code {})"),
+                &[&last.program, &last.exit_code.to_string()],
+            ),
+            None => gettext("No command run yet"),
+        });
+    indicator.button.set_visible(true);
+}
+
+/// CPU time to show, with tenths of a second below a minute.
+fn format_cpu_time(micros: u64) -> String {
+    let time = Duration::from_micros(micros);
+    if time < Duration::from_secs(60) {
+        format!("{:.1}s", time.as_secs_f64())
+    } else {
+        format_time_remaining(time.as_secs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_cpu_time() {
+        assert_eq!(format_cpu_time(0), "0.0s");
+        assert_eq!(format_cpu_time(1_300_000), "1.3s");
+        assert_eq!(format_cpu_time(125_000_000), "2m 5s");
+        assert_eq!(format_cpu_time(3_661_000_000), "1h 1m 1s");
+    }
+}
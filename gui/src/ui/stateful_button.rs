@@ -0,0 +1,97 @@
+//! Buttons that install something, or manage it once it is installed.
+//!
+//! The installed state is checked off the main thread when the button is
+//! set up and again after every task run, so a finished install turns the
+//! button into a manage button, and a removal turns it back.
+
+use crate::core::events::{self, Event, Subscription};
+use crate::i18n::gettext;
+use gtk4::prelude::*;
+use gtk4::{glib, Button};
+use std::cell::Cell;
+use std::rc::{Rc, Weak};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// A button and the labels of its two states.
+struct StatefulButton {
+    button: Button,
+    /// Label from the page layout, shown until installed
+    install_label: String,
+    manage_label: String,
+    /// Whether the thing is installed; runs off the main thread
+    check: fn() -> bool,
+    installed: Cell<bool>,
+    /// Ends with the button, which owns the state through its click handler
+    _subscription: Subscription,
+}
+
+/// Make `button` call `install` until `check` reports the thing installed,
+/// then show `manage_label` and call `manage` instead.
+pub fn setup<I, M>(button: &Button, manage_label: &str, check: fn() -> bool, install: I, manage: M)
+where
+    I: Fn() + 'static,
+    M: Fn() + 'static,
+{
+    let state = Rc::new_cyclic(|weak_state: &Weak<StatefulButton>| {
+        let weak_state = weak_state.clone();
+        StatefulButton {
+            button: button.clone(),
+            install_label: button.label().map(String::from).unwrap_or_default(),
+            manage_label: manage_label.to_string(),
+            check,
+            installed: Cell::new(false),
+            _subscription: events::subscribe(move |event| {
+                if let (Event::TaskFinished { .. }, Some(state)) = (event, weak_state.upgrade()) {
+                    refresh(&state);
+                }
+            }),
+        }
+    });
+
+    let state_clone = state.clone();
+    button.connect_clicked(move |_| {
+        if state_clone.installed.get() {
+            manage();
+        } else {
+            install();
+        }
+    });
+
+    refresh(&state);
+}
+
+/// Check the installed state in the background and update the button.
+fn refresh(state: &Rc<StatefulButton>) {
+    let (sender, receiver) = mpsc::channel();
+    let check = state.check;
+    std::thread::spawn(move || {
+        let _ = sender.send(check());
+    });
+
+    let state = state.clone();
+    glib::timeout_add_local(Duration::from_millis(100), move || {
+        match receiver.try_recv() {
+            Ok(installed) => {
+                apply(&state, installed);
+                glib::ControlFlow::Break
+            }
+            Err(mpsc::TryRecvError::Empty) => glib::ControlFlow::Continue,
+            Err(mpsc::TryRecvError::Disconnected) => glib::ControlFlow::Break,
+        }
+    });
+}
+
+fn apply(state: &StatefulButton, installed: bool) {
+    state.installed.set(installed);
+    let button = &state.button;
+    if installed {
+        button.set_label(&state.manage_label);
+        button.set_tooltip_text(Some(&gettext("Installed — Manage")));
+        button.remove_css_class("suggested-action");
+    } else {
+        button.set_label(&state.install_label);
+        button.set_tooltip_text(None);
+        button.add_css_class("suggested-action");
+    }
+}
@@ -3,6 +3,13 @@
 //! This module provides the core data structures for representing commands
 //! and their execution results in the task runner system.
 
+use super::script::{Script, SCRIPT_SHELL};
+use super::workspace::PLACEHOLDER as WORKSPACE;
+use crate::config::user::AurConfirmation;
+use serde::Serialize;
+use std::fmt;
+use std::rc::Rc;
+
 /// Type of command to execute.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum CommandType {
@@ -14,8 +21,9 @@ pub enum CommandType {
     Aur,
 }
 
-/// Status of a task in the UI.
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// Status of a task in the UI and in run reports.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TaskStatus {
     /// Task is pending (not started yet)
     Pending,
@@ -27,6 +35,8 @@ pub enum TaskStatus {
     Failed,
     /// Task was canceled by user
     Cancelled,
+    /// Task was skipped because its run condition did not hold
+    Skipped,
 }
 
 /// Result of command execution.
@@ -41,6 +51,27 @@ pub enum CommandResult {
     },
 }
 
+/// Check evaluated right before a command runs; the command is skipped when
+/// it returns `false`.
+///
+/// Evaluated on the main thread after earlier steps finished, so it can
+/// observe their effects (e.g. a directory created by a previous step).
+#[derive(Clone)]
+pub struct RunCondition(Rc<dyn Fn() -> bool>);
+
+impl RunCondition {
+    /// Whether the command should run.
+    pub fn holds(&self) -> bool {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for RunCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RunCondition")
+    }
+}
+
 /// A command step to be executed by the task runner.
 ///
 /// Commands can be of different types (normal, privileged, AUR) and include
@@ -55,6 +86,52 @@ pub struct Command {
     pub args: Vec<String>,
     /// Human-readable description shown in the UI
     pub description: String,
+    /// Optional check deciding whether the command runs
+    pub condition: Option<RunCondition>,
+    /// Run with lowered CPU and I/O priority
+    pub low_priority: bool,
+    /// Let the AUR helper show PKGBUILDs and ask before building, in a
+    /// terminal instead of the task output
+    pub review: bool,
+    /// Script written to a file and run in place of the arguments
+    pub script: Option<Script>,
+    /// File written through the daemon in place of running a program
+    pub write: Option<FileWrite>,
+    /// Show the step's output in the completion message
+    pub summarize: bool,
+    /// Directory the command runs in, the app's own if unset
+    pub working_dir: Option<String>,
+}
+
+/// Contents of a file write, computed when its step runs.
+#[derive(Clone)]
+struct DeferredContents(Rc<dyn Fn() -> Result<String, String>>);
+
+impl fmt::Debug for DeferredContents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DeferredContents")
+    }
+}
+
+/// A file replaced by a step, atomically by the daemon for root-owned ones.
+#[derive(Clone, Debug)]
+pub struct FileWrite {
+    /// Absolute path of the file
+    pub path: String,
+    contents: DeferredContents,
+    /// Mode of the file; the old file's, or 0644 for new files, if unset
+    pub mode: Option<u32>,
+    /// Keep a timestamped copy of the old file
+    pub backup: bool,
+    /// Written by the app itself, for files the user owns
+    pub user_owned: bool,
+}
+
+impl FileWrite {
+    /// The contents to write, computed now.
+    pub(super) fn contents(&self) -> Result<String, String> {
+        (self.contents.0)()
+    }
 }
 
 /// Builder for constructing `Command` objects with a fluent API.
@@ -93,6 +170,10 @@ pub struct CommandBuilder {
     program: Option<String>,
     args: Vec<String>,
     description: Option<String>,
+    condition: Option<RunCondition>,
+    low_priority: bool,
+    review: bool,
+    working_dir: Option<String>,
 }
 
 impl CommandBuilder {
@@ -116,14 +197,46 @@ impl CommandBuilder {
         self
     }
 
+    /// Only run the command if `condition` returns `true` when its turn comes.
+    ///
+    /// Skipped commands count as successful and the sequence continues.
+    pub fn run_if<F>(mut self, condition: F) -> Self
+    where
+        F: Fn() -> bool + 'static,
+    {
+        self.condition = Some(RunCondition(Rc::new(condition)));
+        self
+    }
+
+    /// Run with lowered CPU and I/O priority so the desktop stays responsive.
+    ///
+    /// AUR commands get this by default while the matching preference is on.
+    pub fn low_priority(mut self) -> Self {
+        self.low_priority = true;
+        self
+    }
+
+    /// Run in the workspace of the sequence, which must be built with
+    /// [`CommandSequence::with_workspace`](super::CommandSequence::with_workspace).
+    pub fn cwd_workspace(mut self) -> Self {
+        self.working_dir = Some(WORKSPACE.to_string());
+        self
+    }
+
     /// Build the final `Command` object.
     ///
+    /// AUR commands drop `--noconfirm` from their arguments; whether the
+    /// helper asks is up to the confirmation preference.
+    ///
     /// # Panics
     ///
     /// Panics if required fields (program for normal/privileged, description) are missing.
-    pub fn build(self) -> Command {
+    pub fn build(mut self) -> Command {
         let program = match self.command_type {
-            CommandType::Aur => "aur".to_string(),
+            CommandType::Aur => {
+                self.args.retain(|arg| arg != "--noconfirm");
+                "aur".to_string()
+            }
             _ => self
                 .program
                 .expect("program is required for normal and privileged commands"),
@@ -136,6 +249,13 @@ impl CommandBuilder {
             program,
             args: self.args,
             description,
+            condition: self.condition,
+            low_priority: self.low_priority,
+            review: self.review,
+            script: None,
+            write: None,
+            summarize: false,
+            working_dir: self.working_dir,
         }
     }
 }
@@ -160,6 +280,194 @@ impl Command {
     pub fn builder() -> CommandBuilderType {
         CommandBuilderType
     }
+
+    /// Create a builder for a multi-line bash script step.
+    ///
+    /// The body is run from a private temporary file and is not expanded
+    /// beforehand, so pass values with [`ScriptBuilder::var`] instead of
+    /// formatting them into it. Scripts run with `set -e`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use crate::ui::task_runner::Command;
+    ///
+    /// let cmd = Command::script(
+    ///     "Backing up configuration",
+    ///     r#"cp -f "$CONFIG" "$CONFIG.bak""#,
+    /// )
+    /// .var("CONFIG", "/home/user/.zshrc")
+    /// .build();
+    /// ```
+    pub fn script(description: &str, body: &str) -> ScriptBuilder {
+        ScriptBuilder {
+            command_type: CommandType::Normal,
+            description: description.to_string(),
+            script: Script::new(body),
+            condition: None,
+            summarize: false,
+            working_dir: None,
+        }
+    }
+
+    /// Create a builder for a step replacing the root-owned file at `path`,
+    /// or a file of the user's with [`FileWriteBuilder::user_owned`].
+    ///
+    /// The daemon writes a temporary file next to it and renames it over
+    /// the old one, so readers never see a partial file.
+    pub fn write_file(description: &str, path: &str) -> FileWriteBuilder {
+        FileWriteBuilder {
+            description: description.to_string(),
+            write: FileWrite {
+                path: path.to_string(),
+                contents: DeferredContents(Rc::new(|| Ok(String::new()))),
+                mode: None,
+                backup: false,
+                user_owned: false,
+            },
+            condition: None,
+        }
+    }
+}
+
+/// Builder for script steps, started with [`Command::script`].
+#[derive(Debug)]
+pub struct ScriptBuilder {
+    command_type: CommandType,
+    description: String,
+    script: Script,
+    condition: Option<RunCondition>,
+    summarize: bool,
+    working_dir: Option<String>,
+}
+
+impl ScriptBuilder {
+    /// Run the script with elevated privileges.
+    pub fn privileged(mut self) -> Self {
+        self.command_type = CommandType::Privileged;
+        self
+    }
+
+    /// Define a read-only shell variable for the script.
+    ///
+    /// `name` must be a shell identifier; the value is quoted, so it is
+    /// never split or expanded.
+    pub fn var(mut self, name: &str, value: &str) -> Self {
+        self.script.var(name, value);
+        self
+    }
+
+    /// Only run the script if `condition` returns `true` when its turn comes.
+    pub fn run_if<F>(mut self, condition: F) -> Self
+    where
+        F: Fn() -> bool + 'static,
+    {
+        self.condition = Some(RunCondition(Rc::new(condition)));
+        self
+    }
+
+    /// Show what the script prints in the completion message, like the
+    /// result of a check at the end of a run.
+    pub fn summarize(mut self) -> Self {
+        self.summarize = true;
+        self
+    }
+
+    /// Run the script in the workspace of the sequence, which must be built
+    /// with [`CommandSequence::with_workspace`](super::CommandSequence::with_workspace).
+    pub fn cwd_workspace(mut self) -> Self {
+        self.working_dir = Some(WORKSPACE.to_string());
+        self
+    }
+
+    /// Build the final `Command` object.
+    pub fn build(self) -> Command {
+        Command {
+            command_type: self.command_type,
+            program: SCRIPT_SHELL.to_string(),
+            args: Vec::new(),
+            description: self.description,
+            condition: self.condition,
+            low_priority: false,
+            review: false,
+            script: Some(self.script),
+            write: None,
+            summarize: self.summarize,
+            working_dir: self.working_dir,
+        }
+    }
+}
+
+/// Builder for file write steps, started with [`Command::write_file`].
+#[derive(Debug)]
+pub struct FileWriteBuilder {
+    description: String,
+    write: FileWrite,
+    condition: Option<RunCondition>,
+}
+
+impl FileWriteBuilder {
+    /// Compute the contents right before the file is written, after earlier
+    /// steps finished.
+    ///
+    /// An error fails the step with that message.
+    pub fn contents_with<F>(mut self, contents: F) -> Self
+    where
+        F: Fn() -> Result<String, String> + 'static,
+    {
+        self.write.contents = DeferredContents(Rc::new(contents));
+        self
+    }
+
+    /// Set the mode of the written file.
+    pub fn mode(mut self, mode: u32) -> Self {
+        self.write.mode = Some(mode);
+        self
+    }
+
+    /// Copy the old file to `<path>.bak.<timestamp>` before replacing it.
+    pub fn backup(mut self) -> Self {
+        self.write.backup = true;
+        self
+    }
+
+    /// Write the file as the user instead of through the daemon, for files
+    /// in their home. With [`FileWriteBuilder::backup`], a differing old
+    /// file is kept as `<name>.bak` or `<name>.bak.<n>`.
+    pub fn user_owned(mut self) -> Self {
+        self.write.user_owned = true;
+        self
+    }
+
+    /// Only write the file if `condition` returns `true` when its turn comes.
+    pub fn run_if<F>(mut self, condition: F) -> Self
+    where
+        F: Fn() -> bool + 'static,
+    {
+        self.condition = Some(RunCondition(Rc::new(condition)));
+        self
+    }
+
+    /// Build the final `Command` object.
+    pub fn build(self) -> Command {
+        Command {
+            command_type: if self.write.user_owned {
+                CommandType::Normal
+            } else {
+                CommandType::Privileged
+            },
+            program: String::new(),
+            args: Vec::new(),
+            description: self.description,
+            condition: self.condition,
+            low_priority: false,
+            review: false,
+            script: None,
+            write: Some(self.write),
+            summarize: false,
+            working_dir: None,
+        }
+    }
 }
 
 /// Entry point for the command builder API.
@@ -205,6 +513,10 @@ impl CommandBuilderType {
             program: None,
             args: Vec::new(),
             description: None,
+            condition: None,
+            low_priority: false,
+            review: false,
+            working_dir: None,
         }
     }
 
@@ -215,16 +527,29 @@ impl CommandBuilderType {
             program: None,
             args: Vec::new(),
             description: None,
+            condition: None,
+            low_priority: false,
+            review: false,
+            working_dir: None,
         }
     }
 
     /// Create a builder for an AUR helper command (paru/yay).
+    ///
+    /// The helper runs with `--noconfirm` unless the AUR confirmation
+    /// preference asks for review; when it asks per run, the task runner
+    /// decides before starting.
     pub fn aur(self) -> CommandBuilder {
+        let confirmation = crate::config::user::shared().get().tasks.aur_confirmation;
         CommandBuilder {
             command_type: CommandType::Aur,
             program: None,
             args: Vec::new(),
             description: None,
+            condition: None,
+            low_priority: false,
+            review: confirmation == AurConfirmation::Review,
+            working_dir: None,
         }
     }
 }
@@ -0,0 +1,389 @@
+//! Explanations of common pacman errors in the output of a failed step.
+//!
+//! When a step fails, its output is matched against a table of known
+//! errors. A match explains in plain words what went wrong and may offer a
+//! way out, which runs one of the maintenance actions or the failed step
+//! again rather than commands of its own.
+
+use super::command::Command;
+use super::widgets::TaskRunnerWidgets;
+use super::CommandSequence;
+use crate::core::actions;
+use crate::i18n::{gettext, gettext_noop};
+use adw::prelude::*;
+use gtk4::glib;
+use log::info;
+use std::path::Path;
+use std::rc::Rc;
+
+/// What the user can do about a diagnosed error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Remedy {
+    /// Run the maintenance action with this id
+    Action(&'static str),
+    /// Show the files in the way, then offer to run the step again
+    /// overwriting them
+    ConflictingFiles(Vec<String>),
+}
+
+/// A known error found in the output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnosis {
+    /// Untranslated, like the explanation
+    pub title: &'static str,
+    pub explanation: &'static str,
+    pub remedy: Option<Remedy>,
+}
+
+/// A known error and how to recognize it.
+struct Pattern {
+    /// Any of these in the output is a match
+    needles: &'static [&'static str],
+    title: &'static str,
+    explanation: &'static str,
+    remedy: fn(&str) -> Option<Remedy>,
+}
+
+/// Known errors, the more specific first.
+const PATTERNS: &[Pattern] = &[
+    Pattern {
+        needles: &["(conflicting files)"],
+        title: gettext_noop("Files Are in the Way"),
+        explanation: gettext_noop(
+            "Files a package wants to install already exist and belong to no package, or to \
+             another one. They are often left over from software installed by hand. Check \
+             where they came from before overwriting them.",
+        ),
+        remedy: |output| {
+            let files = conflicting_files(output);
+            (!files.is_empty()).then_some(Remedy::ConflictingFiles(files))
+        },
+    },
+    Pattern {
+        needles: &[
+            "is marginal trust",
+            "is unknown trust",
+            "invalid or corrupted package (PGP signature)",
+            "key could not be looked up remotely",
+        ],
+        title: gettext_noop("Package Signature Not Trusted"),
+        explanation: gettext_noop(
+            "A package signature could not be verified, which almost always means the pacman \
+             keyring is outdated or damaged. Rebuilding the keyring and running the operation \
+             again fixes it.",
+        ),
+        remedy: |_| Some(Remedy::Action(actions::FIX_ARCH_KEYRING.id)),
+    },
+    Pattern {
+        needles: &["unable to lock database"],
+        title: gettext_noop("Package Database Locked"),
+        explanation: gettext_noop(
+            "Another package manager holds the pacman database, or one that crashed left its \
+             lock behind. Only remove the lock when no other package manager is running.",
+        ),
+        remedy: |_| Some(Remedy::Action(actions::UNLOCK_PACMAN.id)),
+    },
+    Pattern {
+        needles: &["not enough free disk space"],
+        title: gettext_noop("Not Enough Disk Space"),
+        explanation: gettext_noop(
+            "The packages do not fit on the disk. Clearing the package cache usually frees \
+             enough space.",
+        ),
+        remedy: |_| Some(Remedy::Action(actions::CLEAR_PACMAN_CACHE.id)),
+    },
+    Pattern {
+        needles: &[
+            "failed to synchronize all databases",
+            "failed retrieving file",
+        ],
+        title: gettext_noop("Download Failed"),
+        explanation: gettext_noop(
+            "Packages or databases could not be downloaded. Check your connection; if it works, \
+             the mirrors may be out of date or unreachable and ranking them again helps.",
+        ),
+        remedy: |_| Some(Remedy::Action(actions::UPDATE_MIRRORLIST.id)),
+    },
+    Pattern {
+        needles: &[
+            "could not satisfy dependencies",
+            "unable to satisfy dependency",
+        ],
+        title: gettext_noop("Dependencies Cannot Be Met"),
+        explanation: gettext_noop(
+            "A package needs a version of another package that is not available. This usually \
+             sorts itself out once the repositories are in sync again; try again later, after a \
+             full system update.",
+        ),
+        remedy: |_| None,
+    },
+];
+
+/// The first known error in `output`, if any.
+pub fn diagnose(output: &str) -> Option<Diagnosis> {
+    PATTERNS
+        .iter()
+        .find(|pattern| pattern.needles.iter().any(|needle| output.contains(needle)))
+        .map(|pattern| Diagnosis {
+            title: pattern.title,
+            explanation: pattern.explanation,
+            remedy: (pattern.remedy)(output),
+        })
+}
+
+/// Paths pacman reported as existing in the filesystem, in order and each
+/// once.
+pub fn conflicting_files(output: &str) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+    for line in output.lines() {
+        // "package: /path exists in filesystem (owned by other)"
+        let Some((before, _)) = line.split_once(" exists in filesystem") else {
+            continue;
+        };
+        let Some((_, path)) = before.split_once(": ") else {
+            continue;
+        };
+        let path = path.trim();
+        if path.starts_with('/') && !files.iter().any(|file| file == path) {
+            files.push(path.to_string());
+        }
+    }
+    files
+}
+
+/// `command` with `--overwrite` for each of `files`, if it runs pacman
+/// directly.
+pub fn overwrite_retry(command: &Command, files: &[String]) -> Option<Command> {
+    let runs_pacman = Path::new(&command.program).file_name()? == "pacman";
+    if !runs_pacman || command.script.is_some() || command.write.is_some() || files.is_empty() {
+        return None;
+    }
+    let mut retry = command.clone();
+    for file in files {
+        retry.args.push("--overwrite".to_string());
+        retry.args.push(file.clone());
+    }
+    Some(retry)
+}
+
+/// Explain the error step `index` failed with, if it is a known one, and
+/// offer its remedy.
+pub(super) fn offer(widgets: &Rc<TaskRunnerWidgets>, command: &Command, index: usize) {
+    let Some(diagnosis) = widgets
+        .step_output_text(index)
+        .and_then(|output| diagnose(&output))
+    else {
+        return;
+    };
+    info!("Step {} failed with: {}", index + 1, diagnosis.title);
+
+    let dialog = adw::AlertDialog::new(
+        Some(&gettext(diagnosis.title)),
+        Some(&gettext(diagnosis.explanation)),
+    );
+    dialog.add_response("close", &gettext("Close"));
+    dialog.set_close_response("close");
+    let remedy_label = match &diagnosis.remedy {
+        Some(Remedy::Action(id)) => actions::find(id).map(|action| gettext(action.title)),
+        Some(Remedy::ConflictingFiles(_)) => Some(gettext("Show Conflicting Files")),
+        None => None,
+    };
+    if let Some(label) = remedy_label {
+        dialog.add_response("remedy", &label);
+        dialog.set_response_appearance("remedy", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("remedy"));
+    }
+
+    let widgets = widgets.clone();
+    let command = command.clone();
+    glib::spawn_future_local(async move {
+        if dialog.choose_future(Some(&widgets.window)).await != "remedy" {
+            return;
+        }
+        match diagnosis.remedy {
+            Some(Remedy::Action(id)) => {
+                if let Some(action) = actions::find(id) {
+                    super::run(&widgets.window, action.commands(), &gettext(action.title));
+                }
+            }
+            Some(Remedy::ConflictingFiles(files)) => {
+                show_conflicting_files(&widgets, &command, &files).await;
+            }
+            None => {}
+        }
+    });
+}
+
+/// List the conflicting `files`, offering to run `command` again
+/// overwriting them.
+async fn show_conflicting_files(widgets: &TaskRunnerWidgets, command: &Command, files: &[String]) {
+    let dialog = adw::AlertDialog::new(
+        Some(&gettext("Conflicting Files")),
+        Some(&gettext(
+            "Check that nothing you need is among these files. Overwriting replaces them with \
+             the versions from the package.",
+        )),
+    );
+    let list = gtk4::Label::new(Some(&files.join("\n")));
+    list.set_xalign(0.0);
+    list.set_selectable(true);
+    list.add_css_class("monospace");
+    let scrolled = gtk4::ScrolledWindow::new();
+    scrolled.set_child(Some(&list));
+    scrolled.set_propagate_natural_height(true);
+    scrolled.set_max_content_height(300);
+    dialog.set_extra_child(Some(&scrolled));
+
+    dialog.add_response("close", &gettext("Close"));
+    dialog.set_close_response("close");
+    let retry = overwrite_retry(command, files);
+    if retry.is_some() {
+        dialog.add_response("overwrite", &gettext("Retry Overwriting These Files"));
+        dialog.set_response_appearance("overwrite", adw::ResponseAppearance::Destructive);
+    }
+
+    if dialog.choose_future(Some(&widgets.window)).await == "overwrite" {
+        if let Some(retry) = retry {
+            let title = retry.description.clone();
+            super::run(
+                &widgets.window,
+                CommandSequence::new().then(retry).build(),
+                &title,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFLICTING_FILES: &str = "\
+:: Proceed with installation? [Y/n]
+(2/2) checking keys in keyring                     [######################] 100%
+(2/2) checking package integrity                   [######################] 100%
+(2/2) loading package files                        [######################] 100%
+(2/2) checking for file conflicts                  [######################] 100%
+error: failed to commit transaction (conflicting files)
+python-pip: /usr/lib/python3.12/site-packages/pip/__init__.py exists in filesystem
+python-pip: /usr/bin/pip exists in filesystem (owned by python-pip-git)
+python-pip: /usr/bin/pip exists in filesystem (owned by python-pip-git)
+Errors occurred, no packages were upgraded.
+";
+
+    const MARGINAL_TRUST: &str = "\
+(1/1) checking keys in keyring                     [######################] 100%
+(1/1) checking package integrity                   [######################] 100%
+error: archlinux-keyring: signature from \"Pierre Schmitz <pierre@archlinux.de>\" is marginal trust
+:: File /var/cache/pacman/pkg/archlinux-keyring-20240313-1-any.pkg.tar.zst is corrupted (invalid or corrupted package (PGP signature)).
+Do you want to delete it? [Y/n]
+error: failed to commit transaction (invalid or corrupted package)
+Errors occurred, no packages were upgraded.
+";
+
+    const LOCKED: &str = "\
+error: failed to init transaction (unable to lock database)
+error: could not lock database: File exists
+  if you're sure a package manager is not already
+  running, you can remove /var/lib/pacman/db.lck
+";
+
+    const DISK_FULL: &str = "\
+(12/12) checking available disk space              [######################] 100%
+error: Partition / too full: 412345 blocks needed, 10240 blocks free
+error: not enough free disk space
+error: failed to commit transaction (not enough free disk space)
+Errors occurred, no packages were upgraded.
+";
+
+    const SYNC_FAILED: &str = "\
+:: Synchronizing package databases...
+error: failed retrieving file 'core.db' from mirror.example.org : Could not resolve host: mirror.example.org
+error: failed to update core (failed to retrieve some files)
+error: failed to synchronize all databases (failed to retrieve some files)
+";
+
+    const DEPENDENCIES: &str = "\
+resolving dependencies...
+warning: cannot resolve \"libfoo.so=3-64\", a dependency of \"bar\"
+:: The following package cannot be upgraded due to unresolvable dependencies:
+      bar
+error: failed to prepare transaction (could not satisfy dependencies)
+:: unable to satisfy dependency 'libfoo.so=3-64' required by bar
+";
+
+    fn remedy(output: &str) -> Option<Remedy> {
+        diagnose(output).unwrap().remedy
+    }
+
+    #[test]
+    fn test_diagnose() {
+        assert_eq!(
+            remedy(CONFLICTING_FILES),
+            Some(Remedy::ConflictingFiles(vec![
+                "/usr/lib/python3.12/site-packages/pip/__init__.py".to_string(),
+                "/usr/bin/pip".to_string(),
+            ]))
+        );
+        assert_eq!(
+            remedy(MARGINAL_TRUST),
+            Some(Remedy::Action("fix-arch-keyring"))
+        );
+        assert_eq!(remedy(LOCKED), Some(Remedy::Action("unlock-pacman")));
+        assert_eq!(
+            remedy(DISK_FULL),
+            Some(Remedy::Action("clear-pacman-cache"))
+        );
+        assert_eq!(
+            remedy(SYNC_FAILED),
+            Some(Remedy::Action("update-mirrorlist"))
+        );
+        assert_eq!(diagnose(DEPENDENCIES).unwrap().remedy, None);
+
+        assert_eq!(diagnose("error: target not found: nosuchpkg\n"), None);
+        assert_eq!(diagnose(""), None);
+    }
+
+    #[test]
+    fn test_remedies_are_actions() {
+        for output in [MARGINAL_TRUST, LOCKED, DISK_FULL, SYNC_FAILED] {
+            let Some(Remedy::Action(id)) = remedy(output) else {
+                panic!("no action for {}", output);
+            };
+            assert!(actions::find(id).is_some(), "{}", id);
+        }
+    }
+
+    #[test]
+    fn test_overwrite_retry() {
+        let files = conflicting_files(CONFLICTING_FILES);
+        let upgrade = Command::builder()
+            .privileged()
+            .program("pacman")
+            .args(&["-Syu", "--noconfirm"])
+            .description("Upgrade")
+            .build();
+        let retry = overwrite_retry(&upgrade, &files).unwrap();
+        assert_eq!(
+            retry.args,
+            [
+                "-Syu",
+                "--noconfirm",
+                "--overwrite",
+                "/usr/lib/python3.12/site-packages/pip/__init__.py",
+                "--overwrite",
+                "/usr/bin/pip",
+            ]
+        );
+        assert_eq!(retry.description, "Upgrade");
+        assert!(overwrite_retry(&upgrade, &[]).is_none());
+
+        // Only pacman takes --overwrite
+        let aur = Command::builder()
+            .aur()
+            .args(&["-S", "foo"])
+            .description("Install")
+            .build();
+        assert!(overwrite_retry(&aur, &files).is_none());
+    }
+}
@@ -2,21 +2,63 @@
 //!
 //! This module handles the actual execution of commands, including:
 //! - Process spawning and management
+//! - Privileged commands through the daemon client library
 //! - Output capture (stdout/stderr)
 //! - Error handling and result processing
 //! - Command resolution (privilege escalation, AUR helpers)
 
 use super::command::{Command, CommandResult, CommandType, TaskStatus};
+use super::script;
+use super::stall;
 use super::widgets::TaskRunnerWidgets;
+use super::workspace;
 use crate::core;
 use crate::core::daemon::get_xero_auth_path;
+use crate::core::dotfiles::{self, DeployOutcome};
+use crate::i18n::{self, gettext};
+use crate::ui::dialogs::terminal;
+use adw::prelude::*;
 use gtk4::gio;
 use gtk4::glib;
 use log::{error, info, warn};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+use xero_auth::limits::{self, ResourceLimits};
 use xero_auth::utils::read_buffer_with_line_processing;
 
+/// Niceness of low-priority commands.
+const LOW_PRIORITY_NICE: i32 = 10;
+
+/// Share of the machine's CPU time low-priority commands run through the
+/// daemon may use, in percent.
+const LOW_PRIORITY_CPU_SHARE: u32 = 75;
+
+/// Lock pacman holds on its database while it runs.
+const PACMAN_DB_LOCK: &str = "/var/lib/pacman/db.lck";
+
+/// Programs of privileged steps that need the pacman database.
+const PACMAN_DB_PROGRAMS: &[&str] = &["pacman", "pacman-key"];
+
+/// Longest wait for the database lock before starting the step anyway.
+const PACMAN_DB_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Interval between checks of the database lock.
+const PACMAN_DB_POLL: Duration = Duration::from_millis(250);
+
+/// Interval between checks of a silent step for a prompt.
+const STALL_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Process groups of the children currently running, so they can be
+/// stopped together with everything they started.
+static CHILD_GROUPS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+fn child_groups() -> std::sync::MutexGuard<'static, Vec<u32>> {
+    CHILD_GROUPS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
 /// Context for a running command execution.
 pub struct RunningContext {
     pub widgets: Rc<TaskRunnerWidgets>,
@@ -66,12 +108,18 @@ impl RunningContext {
         // Clear current process
         self.current_process.borrow_mut().take();
 
+        let exit_code = match &result {
+            CommandResult::Success => Some(0),
+            CommandResult::Failure { exit_code } => *exit_code,
+        };
+        self.widgets.record_exit_code(self.index, exit_code);
+
         // Check if canceled
         if *self.cancelled.borrow() {
             // Mark the current task as canceled
             self.widgets
                 .update_task_status(self.index, TaskStatus::Cancelled);
-            finalize_execution(&self.widgets, false, super::CANCELLED_MESSAGE);
+            finalize_execution(&self.widgets, false, &super::cancelled_message());
             return;
         }
 
@@ -103,17 +151,21 @@ impl RunningContext {
                     .update_task_status(self.index, TaskStatus::Failed);
 
                 // Include exit code in error message if available
-                let exit_msg = exit_code
-                    .map(|code| format!(" (exit code: {})", code))
-                    .unwrap_or_default();
-                let final_message = format!(
-                    "Operation failed at step {} of {}{}",
-                    self.index + 1,
-                    self.commands.len(),
-                    exit_msg
-                );
+                let step = (self.index + 1).to_string();
+                let total = self.commands.len().to_string();
+                let final_message = match exit_code {
+                    Some(code) => i18n::fill(
+                        &gettext("Operation failed at step {} of {} (exit code: {})"),
+                        &[&step, &total, &code.to_string()],
+                    ),
+                    None => i18n::fill(
+                        &gettext("Operation failed at step {} of {}"),
+                        &[&step, &total],
+                    ),
+                };
 
                 finalize_execution(&self.widgets, false, &final_message);
+                super::diagnostics::offer(&self.widgets, &self.commands[self.index], self.index);
             }
         }
     }
@@ -132,23 +184,126 @@ pub fn execute_commands(
         if index < commands.len() {
             widgets.update_task_status(index, TaskStatus::Cancelled);
         }
-        finalize_execution(&widgets, false, super::CANCELLED_MESSAGE);
+        finalize_execution(&widgets, false, &super::cancelled_message());
         return;
     }
 
     if index >= commands.len() {
-        finalize_execution(&widgets, true, super::SUCCESS_MESSAGE);
+        finalize_execution(&widgets, true, &success_summary(&widgets, &commands));
         return;
     }
 
+    widgets.append_phase_header(index);
+
+    // Skip commands whose run condition does not hold at this point
+    let skip = commands[index]
+        .condition
+        .as_ref()
+        .is_some_and(|condition| !condition.holds());
+    if skip {
+        info!("Skipping: {}", commands[index].description);
+        widgets.append_colored(
+            &format!("Skipped: {}\n", commands[index].description),
+            "stdout",
+        );
+        widgets.update_task_status(index, TaskStatus::Skipped);
+        execute_commands(widgets, commands, index + 1, cancelled, current_process);
+        return;
+    }
+
+    // An AUR helper may still hold the database lock after it exited
+    if waits_for_package_db(&commands, index) {
+        let wait = LockWait::new(PACMAN_DB_LOCK, PACMAN_DB_TIMEOUT);
+        if wait.check() == LockState::Held {
+            wait_for_package_db(widgets, commands, index, cancelled, current_process, wait);
+            return;
+        }
+    }
+
+    launch_command(widgets, commands, index, cancelled, current_process);
+}
+
+/// Show that the step waits for the database lock and start it once the
+/// lock is released or the wait timed out.
+fn wait_for_package_db(
+    widgets: Rc<TaskRunnerWidgets>,
+    commands: Rc<Vec<Command>>,
+    index: usize,
+    cancelled: Rc<RefCell<bool>>,
+    current_process: Rc<RefCell<Option<gio::Subprocess>>>,
+    wait: LockWait,
+) {
+    info!("Waiting for {} to be released", PACMAN_DB_LOCK);
+    let status = gettext("Waiting for package database…");
+    widgets.update_task_status(index, TaskStatus::Running);
+    widgets.set_title(&status);
+    widgets.append_colored(&format!("{}\n", status), "stdout");
+
+    glib::timeout_add_local(PACMAN_DB_POLL, move || {
+        if *cancelled.borrow() {
+            widgets.update_task_status(index, TaskStatus::Cancelled);
+            finalize_execution(&widgets, false, &super::cancelled_message());
+            return glib::ControlFlow::Break;
+        }
+        match wait.check() {
+            LockState::Held => return glib::ControlFlow::Continue,
+            LockState::Released => info!("{} released", PACMAN_DB_LOCK),
+            LockState::TimedOut => {
+                warn!("{} still held, starting step anyway", PACMAN_DB_LOCK);
+                widgets.append_colored(
+                    &format!(
+                        "{}\n",
+                        gettext("The package database is still locked, continuing anyway.")
+                    ),
+                    "stderr",
+                );
+            }
+        }
+        launch_command(
+            widgets.clone(),
+            commands.clone(),
+            index,
+            cancelled.clone(),
+            current_process.clone(),
+        );
+        glib::ControlFlow::Break
+    });
+}
+
+/// Start `commands[index]` and continue with the next step once it ends.
+fn launch_command(
+    widgets: Rc<TaskRunnerWidgets>,
+    commands: Rc<Vec<Command>>,
+    index: usize,
+    cancelled: Rc<RefCell<bool>>,
+    current_process: Rc<RefCell<Option<gio::Subprocess>>>,
+) {
     let cmd = &commands[index];
 
     // Mark current task as running
     widgets.update_task_status(index, TaskStatus::Running);
     widgets.set_title(&cmd.description);
 
-    let (program, args) = match resolve_command(cmd) {
-        Ok(result) => result,
+    // Write steps compute their contents now, after earlier steps ran
+    let prepared = match &cmd.write {
+        Some(write) => write.contents().map(|contents| Launch::Write {
+            path: write.path.clone(),
+            contents,
+            mode: write.mode,
+            backup: write.backup,
+            user_owned: write.user_owned,
+        }),
+        None => prepare(cmd).and_then(|(prepared, script)| {
+            resolve_command(&prepared).map(|(program, args)| Launch::Process {
+                prepared: Box::new(prepared),
+                script,
+                program,
+                args,
+            })
+        }),
+    };
+    let launch = match prepared {
+        Ok(launch) => launch,
         Err(err) => {
             error!("Failed to prepare command: {}", err);
             let error_msg = format!("Failed to prepare command: {}\n", err);
@@ -157,17 +312,12 @@ pub fn execute_commands(
             finalize_execution(
                 &widgets,
                 false,
-                &format!("Failed to prepare command: {}", err),
+                &i18n::fill(&gettext("Failed to prepare command: {}"), &[&err]),
             );
             return;
         }
     };
 
-    info!("Executing: {} {:?}", program, args);
-
-    // Use std::process for real-time output streaming
-    use std::process::{Command, Stdio};
-    use std::sync::Arc;
     use std::thread;
 
     // Create context for this command
@@ -182,172 +332,829 @@ pub fn execute_commands(
     // Display command header
     widgets.append_command_header(&cmd.description);
 
-    let mut process = Command::new(&program);
-    process.args(&args);
-
-    // Inject sudo shim to intercept sudo calls in scripts
-    let scripts_dir = crate::config::paths::scripts();
-    if scripts_dir.exists() {
-        if let Ok(path) = std::env::var("PATH") {
-            let new_path = format!("{}:{}", scripts_dir.display(), path);
-            process.env("PATH", new_path);
-        }
-    }
+    // Set up result storage
+    let result_arc = ResultSlot::default();
 
-    process.stdout(Stdio::piped());
-    process.stderr(Stdio::piped());
+    // Set up real-time output streaming using channels
+    let (stdout_tx, stdout_rx) = mpsc::channel();
+    let (stderr_tx, stderr_rx) = mpsc::channel();
 
-    let child = match process.spawn() {
-        Ok(child) => child,
-        Err(err) => {
-            error!("Failed to start command: {}", err);
-            let error_msg = format!("Failed to start operation: {}\n", err);
-            widgets.append_colored(&error_msg, "error");
-            widgets.update_task_status(index, TaskStatus::Failed);
-            finalize_execution(
-                &widgets,
-                false,
-                &format!("Failed to start operation: {}", err),
+    let result_arc_clone = result_arc.clone();
+    let limits = resource_limits(cmd);
+    let (prepared, script, program, args) = match launch {
+        Launch::Write {
+            path,
+            contents,
+            mode,
+            backup,
+            user_owned,
+        } => {
+            info!("Writing {}", path);
+            thread::spawn(move || {
+                let result = match write_contents(&path, &contents, mode, backup, user_owned) {
+                    Ok(backup_path) => {
+                        send_chunk(&stdout_tx, &write_report(&path, backup_path.as_deref()));
+                        CommandResult::Success
+                    }
+                    Err(e) => {
+                        error!("Failed to write {}: {:#}", path, e);
+                        send_chunk(&stderr_tx, &format!("{:#}\n", e));
+                        CommandResult::Failure { exit_code: None }
+                    }
+                };
+                *result_arc_clone.lock().unwrap() = Some(result);
+            });
+            forward_output(
+                widgets, context, stdout_rx, stderr_rx, result_arc, None, None,
             );
             return;
         }
+        Launch::Process {
+            prepared,
+            script,
+            program,
+            args,
+        } => (prepared, script, program, args),
     };
+    info!("Executing: {} {:?}", program, args);
+    // Privileged programs as the daemon resolves them
+    let (shown_program, shown_args) =
+        preview_command(&prepared).unwrap_or_else(|_| (program.clone(), args.clone()));
+    widgets.record_command(index, &shown_program, &shown_args);
 
-    // Store child process for cancellation
-    use std::sync::Mutex;
-    let child_arc = Arc::new(Mutex::new(Some(child)));
-    *current_process.borrow_mut() = None; // Clear gio subprocess reference
-
-    // Set up result storage
-    let result_arc: Arc<Mutex<Option<CommandResult>>> = Arc::new(Mutex::new(None));
-
-    // Set up real-time output streaming using channels
-    use std::sync::mpsc;
-    let (stdout_tx, stdout_rx) = mpsc::channel();
-    let (stderr_tx, stderr_rx) = mpsc::channel();
+    if prepared.review {
+        // The helper asks before building, so it runs in a terminal and the
+        // step ends with it
+        widgets.append_colored(
+            &format!(
+                "{}\n",
+                gettext(
+                    "Continuing in a terminal window, where the AUR helper asks before building..."
+                )
+            ),
+            "stdout",
+        );
+        let (program, args) = in_working_dir(prepared.working_dir.as_deref(), program, args);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        terminal::show_terminal_dialog_with_exit_code(
+            &widgets.window,
+            &cmd.description,
+            &program,
+            &args,
+            true,
+            move |exit_code| {
+                context.set_exit_result(match exit_code {
+                    Some(0) => CommandResult::Success,
+                    exit_code => CommandResult::Failure { exit_code },
+                });
+            },
+        );
+        return;
+    }
+    let working_dir = prepared.working_dir.clone();
 
-    // Spawn thread to read stdout
-    let stdout_handle = child_arc
-        .lock()
-        .unwrap()
-        .as_mut()
-        .and_then(|c| c.stdout.take())
-        .map(|stdout| {
-            thread::spawn(move || {
-                read_buffer_with_line_processing(
-                    stdout,
-                    |text| match stdout_tx.send(text) {
-                        Ok(()) => true,
-                        Err(e) => {
-                            warn!("Failed to send stdout chunk to channel: {}", e);
-                            false
-                        }
-                    },
-                    |e| {
-                        warn!("Error reading stdout: {}", e);
-                    },
-                );
+    let mut confirmations = None;
+    // Process group of a step running as the user, watched for prompts
+    let mut group = None;
+    if cmd.command_type == CommandType::Privileged {
+        // Talk to the daemon directly, falling back to the xero-auth binary
+        let daemon_program = prepared.program;
+        let daemon_args = prepared.args;
+        let (confirm_tx, confirm_rx) = mpsc::channel();
+        confirmations = Some(confirm_rx);
+        thread::spawn(move || {
+            // Removed once the step finished, however it ended
+            let _script = script;
+            let result = run_on_daemon(
+                &daemon_program,
+                &daemon_args,
+                working_dir.as_deref(),
+                limits,
+                |text| send_chunk(&stdout_tx, text),
+                |text| send_chunk(&stderr_tx, text),
+                move |reason| request_confirmation(&confirm_tx, reason),
+            )
+            .map(|outcome| match outcome {
+                Ok(0) => CommandResult::Success,
+                Ok(code) => CommandResult::Failure {
+                    exit_code: Some(code),
+                },
+                Err(e) => {
+                    error!("Daemon failed to run command: {:#}", e);
+                    send_chunk(&stderr_tx, &format!("{:#}\n", e));
+                    CommandResult::Failure { exit_code: None }
+                }
             })
+            // The binary passes the limits on to the daemon itself
+            .unwrap_or_else(|| {
+                // The binary gets the working directory as an argument
+                match spawn_process(&program, &args, None, ResourceLimits::default()) {
+                    Ok(child) => wait_for_process(child, stdout_tx, stderr_tx),
+                    Err(err) => {
+                        error!("Failed to start command: {}", err);
+                        send_chunk(&stderr_tx, &format!("Failed to start operation: {}\n", err));
+                        CommandResult::Failure { exit_code: None }
+                    }
+                }
+            });
+            *result_arc_clone.lock().unwrap() = Some(result);
         });
-
-    // Spawn thread to read stderr
-    let stderr_handle = child_arc
-        .lock()
-        .unwrap()
-        .as_mut()
-        .and_then(|c| c.stderr.take())
-        .map(|stderr| {
-            thread::spawn(move || {
-                read_buffer_with_line_processing(
-                    stderr,
-                    |text| match stderr_tx.send(text) {
-                        Ok(()) => true,
-                        Err(e) => {
-                            warn!("Failed to send stderr chunk to channel: {}", e);
-                            false
-                        }
-                    },
-                    |e| {
-                        warn!("Error reading stderr: {}", e);
-                    },
+    } else {
+        let child = match spawn_process(&program, &args, working_dir.as_deref(), limits) {
+            Ok(child) => child,
+            Err(err) => {
+                error!("Failed to start command: {}", err);
+                let error_msg = format!("Failed to start operation: {}\n", err);
+                widgets.append_colored(&error_msg, "error");
+                widgets.update_task_status(index, TaskStatus::Failed);
+                finalize_execution(
+                    &widgets,
+                    false,
+                    &i18n::fill(
+                        &gettext("Failed to start operation: {}"),
+                        &[&err.to_string()],
+                    ),
                 );
-            })
+                return;
+            }
+        };
+
+        group = Some(child.id());
+
+        // Wait for process to complete in a separate thread
+        thread::spawn(move || {
+            let _script = script;
+            let result = wait_for_process(child, stdout_tx, stderr_tx);
+            *result_arc_clone.lock().unwrap() = Some(result);
         });
+    }
+    *current_process.borrow_mut() = None; // Clear gio subprocess reference
+
+    forward_output(
+        widgets,
+        context,
+        stdout_rx,
+        stderr_rx,
+        result_arc,
+        confirmations,
+        group,
+    );
+}
+
+/// A command the daemon wants confirmed, and where the answer goes.
+struct ConfirmRequest {
+    reason: String,
+    answer: mpsc::Sender<bool>,
+}
+
+/// Have the main loop ask whether to run a command the daemon wants
+/// confirmed for `reason`, blocking until the user answers.
+fn request_confirmation(requests: &mpsc::Sender<ConfirmRequest>, reason: &str) -> bool {
+    let (answer, answered) = mpsc::channel();
+    let request = ConfirmRequest {
+        reason: reason.to_string(),
+        answer,
+    };
+    requests.send(request).is_ok() && answered.recv().unwrap_or(false)
+}
+
+/// Ask whether to run the command of the current step. Declining cancels
+/// the run, so the sequence stops at this step.
+fn ask_confirmation(
+    widgets: &TaskRunnerWidgets,
+    context: &Rc<RunningContext>,
+    request: ConfirmRequest,
+) {
+    let dialog = adw::AlertDialog::new(
+        Some(&gettext("Run This Command?")),
+        Some(&i18n::fill(
+            &gettext("This command needs your confirmation: {}."),
+            &[&request.reason],
+        )),
+    );
+    if let Some(line) = widgets.command_line(context.index) {
+        let command_line = gtk4::Label::new(Some(&line));
+        command_line.set_xalign(0.0);
+        command_line.set_wrap(true);
+        command_line.set_wrap_mode(gtk4::pango::WrapMode::WordChar);
+        command_line.set_selectable(true);
+        command_line.add_css_class("monospace");
+        dialog.set_extra_child(Some(&command_line));
+    }
+    dialog.add_response("cancel", &gettext("Cancel Operation"));
+    dialog.add_response("run", &gettext("Run"));
+    dialog.set_response_appearance("run", adw::ResponseAppearance::Destructive);
+    dialog.set_default_response(Some("cancel"));
+    dialog.set_close_response("cancel");
+
+    let window = widgets.window.clone();
+    let context = context.clone();
+    glib::spawn_future_local(async move {
+        let approved = dialog.choose_future(Some(&window)).await == "run";
+        if !approved {
+            info!("Command not confirmed: {}", request.reason);
+            *context.cancelled.borrow_mut() = true;
+        }
+        let _ = request.answer.send(approved);
+    });
+}
+
+/// What a step does once prepared.
+enum Launch {
+    /// Replace a file, through the daemon unless it is the user's.
+    Write {
+        path: String,
+        contents: String,
+        mode: Option<u32>,
+        backup: bool,
+        user_owned: bool,
+    },
+    /// Run a program, from a script file for script steps.
+    Process {
+        prepared: Box<Command>,
+        script: Option<script::ScriptFile>,
+        program: String,
+        args: Vec<String>,
+    },
+}
 
-    // Process output in main thread
-    let widgets_stdout = widgets.clone();
-    let widgets_stderr = widgets.clone();
-    let result_arc_for_output = result_arc.clone();
+/// Forward a step's output and result from the main loop, asking the user
+/// about the commands the daemon wants confirmed.
+///
+/// A step running in process `group` that stays silent while waiting for
+/// input gets a dialog offering to stop it or run it again in a terminal.
+fn forward_output(
+    widgets: Rc<TaskRunnerWidgets>,
+    context: Rc<RunningContext>,
+    stdout_rx: mpsc::Receiver<String>,
+    stderr_rx: mpsc::Receiver<String>,
+    result_arc: ResultSlot,
+    confirmations: Option<mpsc::Receiver<ConfirmRequest>>,
+    group: Option<u32>,
+) {
+    let driver = CommandDriver::new(stdout_rx, stderr_rx, result_arc);
+    let mut last_output = Instant::now();
+    let mut last_check = Instant::now();
+    let mut asked = false;
+    let retry = Rc::new(Cell::new(false));
     glib::timeout_add_local(std::time::Duration::from_millis(50), move || {
-        // Process stdout
-        while let Ok(text) = stdout_rx.try_recv() {
-            let cleaned_text = strip_ansi_escapes::strip_str(&text);
-            // Text already includes newline from buffer processing
-            widgets_stdout.append_colored(&cleaned_text, "stdout");
+        if let Some(request) = confirmations.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            ask_confirmation(&widgets, &context, request);
         }
-        // Process stderr
-        while let Ok(text) = stderr_rx.try_recv() {
-            let cleaned_text = strip_ansi_escapes::strip_str(&text);
+        let result = driver.poll(|text, tag| {
+            last_output = Instant::now();
             // Text already includes newline from buffer processing
-            widgets_stderr.append_colored(&cleaned_text, "stderr");
-        }
-        // Stop if result is ready
-        if result_arc_for_output.lock().unwrap().is_some() {
-            glib::ControlFlow::Break
-        } else {
-            glib::ControlFlow::Continue
+            widgets.append_colored(&strip_ansi_escapes::strip_str(text), tag);
+        });
+        match result {
+            // Stopped to be run again, its own result doesn't count
+            Some(_) if retry.get() => {
+                retry_in_terminal(&widgets, &context);
+                glib::ControlFlow::Break
+            }
+            Some(result) => {
+                context.set_exit_result(result);
+                glib::ControlFlow::Break
+            }
+            None => {
+                let check = group.filter(|_| {
+                    !asked
+                        && !*context.cancelled.borrow()
+                        && last_output.elapsed() >= stall::SILENCE
+                        && last_check.elapsed() >= STALL_CHECK_INTERVAL
+                });
+                if let Some(group) = check {
+                    last_check = Instant::now();
+                    if stall::waiting_for_input(&stall::read_group(group)) {
+                        asked = true;
+                        ask_about_stall(&widgets, &context, group, retry.clone());
+                    }
+                }
+                glib::ControlFlow::Continue
+            }
         }
     });
+}
 
-    // Wait for process to complete in a separate thread
-    let result_arc_clone = result_arc.clone();
+/// Stop process group `group`, stopped processes included.
+fn stop_group(group: u32) {
+    let group = -(group as libc::pid_t);
+    // Stopped processes only handle the signal once continued
+    unsafe {
+        libc::kill(group, libc::SIGTERM);
+        libc::kill(group, libc::SIGCONT);
+    }
+}
+
+/// Ask what to do about a step that seems to wait for an answer. Stopping
+/// it fails the step, unless `retry` is set to run it again in a terminal.
+fn ask_about_stall(
+    widgets: &TaskRunnerWidgets,
+    context: &Rc<RunningContext>,
+    group: u32,
+    retry: Rc<Cell<bool>>,
+) {
+    let description = &context.commands[context.index].description;
+    warn!("Step seems to wait for input: {}", description);
+    let dialog = adw::AlertDialog::new(
+        Some(&gettext("Step Waiting for Input?")),
+        Some(&i18n::fill(
+            &gettext(
+                "\"{}\" has printed nothing for a while and seems to wait for an answer, \
+                 which cannot be given here. Run it again in a terminal to answer it.",
+            ),
+            &[description],
+        )),
+    );
+    dialog.add_response("wait", &gettext("Keep Waiting"));
+    dialog.add_response("abort", &gettext("Stop Step"));
+    dialog.add_response("terminal", &gettext("Retry in Terminal"));
+    dialog.set_response_appearance("abort", adw::ResponseAppearance::Destructive);
+    dialog.set_response_appearance("terminal", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("terminal"));
+    dialog.set_close_response("wait");
 
-    thread::spawn(move || {
-        // Wait for output threads to finish
-        if let Some(handle) = stdout_handle {
-            if let Err(e) = handle.join() {
-                warn!("Error joining stdout reader thread: {:?}", e);
+    let window = widgets.window.clone();
+    glib::spawn_future_local(async move {
+        match dialog.choose_future(Some(&window)).await.as_str() {
+            "terminal" => {
+                info!("Retrying waiting step in a terminal");
+                retry.set(true);
+                stop_group(group);
+            }
+            "abort" => {
+                info!("Stopping waiting step");
+                stop_group(group);
             }
+            _ => {}
+        }
+    });
+}
+
+/// Run the current step again in a terminal, where it can be answered,
+/// and end the step with its exit code.
+fn retry_in_terminal(widgets: &TaskRunnerWidgets, context: &Rc<RunningContext>) {
+    let cmd = &context.commands[context.index];
+    let launch = prepare(cmd).and_then(|(prepared, script)| {
+        resolve_command(&prepared).map(|(program, args)| {
+            let (program, args) = in_working_dir(prepared.working_dir.as_deref(), program, args);
+            (script, program, args)
+        })
+    });
+    let (script, program, args) = match launch {
+        Ok(launch) => launch,
+        Err(err) => {
+            error!("Failed to prepare command: {}", err);
+            widgets.append_colored(&format!("Failed to prepare command: {}\n", err), "error");
+            context.set_exit_result(CommandResult::Failure { exit_code: None });
+            return;
         }
-        if let Some(handle) = stderr_handle {
-            if let Err(e) = handle.join() {
-                warn!("Error joining stderr reader thread: {:?}", e);
+    };
+
+    widgets.append_colored(
+        &format!(
+            "\n{}\n",
+            gettext("Running the step again in a terminal window...")
+        ),
+        "stdout",
+    );
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let context = context.clone();
+    terminal::show_terminal_dialog_with_exit_code(
+        &widgets.window,
+        &cmd.description,
+        &program,
+        &args,
+        true,
+        move |exit_code| {
+            // Removed once the step finished in the terminal
+            drop(script);
+            context.set_exit_result(match exit_code {
+                Some(0) => CommandResult::Success,
+                exit_code => CommandResult::Failure { exit_code },
+            });
+        },
+    );
+}
+
+/// Whether `commands[index]` needs the pacman database after an AUR helper
+/// ran earlier in the sequence.
+///
+/// Helpers may leave a pacman child behind that holds the lock for a moment
+/// after they exit, and pacman run through the daemon then fails or hangs.
+pub(super) fn waits_for_package_db(commands: &[Command], index: usize) -> bool {
+    let cmd = &commands[index];
+    let program = Path::new(&cmd.program)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    cmd.command_type == CommandType::Privileged
+        && PACMAN_DB_PROGRAMS.contains(&program)
+        && commands[..index]
+            .iter()
+            .any(|earlier| earlier.command_type == CommandType::Aur)
+}
+
+/// State of a lock file being waited for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum LockState {
+    Held,
+    Released,
+    TimedOut,
+}
+
+/// Wait for a lock file to disappear, up to a deadline.
+pub(super) struct LockWait {
+    path: PathBuf,
+    deadline: Instant,
+}
+
+impl LockWait {
+    pub(super) fn new(path: impl Into<PathBuf>, timeout: Duration) -> Self {
+        Self {
+            path: path.into(),
+            deadline: Instant::now() + timeout,
+        }
+    }
+
+    /// Check the lock once.
+    pub(super) fn check(&self) -> LockState {
+        if !self.path.exists() {
+            LockState::Released
+        } else if Instant::now() >= self.deadline {
+            LockState::TimedOut
+        } else {
+            LockState::Held
+        }
+    }
+
+    /// Block until the lock is released or the deadline passed.
+    pub(super) fn wait(&self, poll: Duration) -> LockState {
+        loop {
+            match self.check() {
+                LockState::Held => std::thread::sleep(poll),
+                state => return state,
             }
         }
+    }
+}
 
-        // Wait for process
-        let mut child_guard = child_arc.lock().unwrap();
-        if let Some(mut child) = child_guard.take() {
-            let result = match child.wait() {
-                Ok(status) => {
-                    if status.success() {
-                        CommandResult::Success
-                    } else {
-                        CommandResult::Failure {
-                            exit_code: status.code(),
-                        }
+/// Block until the pacman database is unlocked, for the command line,
+/// reporting the wait through `progress`.
+pub(super) fn wait_for_package_db_blocking(progress: impl Fn(&str)) {
+    let wait = LockWait::new(PACMAN_DB_LOCK, PACMAN_DB_TIMEOUT);
+    if wait.check() != LockState::Held {
+        return;
+    }
+    progress("Waiting for package database…");
+    if wait.wait(PACMAN_DB_POLL) == LockState::TimedOut {
+        warn!("{} still held, starting step anyway", PACMAN_DB_LOCK);
+        progress("The package database is still locked, continuing anyway.");
+    }
+}
+
+/// Where a command's worker thread stores its result.
+type ResultSlot = Arc<Mutex<Option<CommandResult>>>;
+
+/// Output channels and result slot of one running command, polled from the
+/// main loop.
+struct CommandDriver {
+    stdout: mpsc::Receiver<String>,
+    stderr: mpsc::Receiver<String>,
+    result: ResultSlot,
+}
+
+impl CommandDriver {
+    fn new(
+        stdout: mpsc::Receiver<String>,
+        stderr: mpsc::Receiver<String>,
+        result: ResultSlot,
+    ) -> Self {
+        Self {
+            stdout,
+            stderr,
+            result,
+        }
+    }
+
+    /// Pass pending output to `sink` with its tag and return the result once
+    /// the command has finished.
+    ///
+    /// The result is taken before draining: producers send all output before
+    /// storing the result, so once it is seen no further output can arrive
+    /// and the final lines are always forwarded before it.
+    fn poll<F>(&self, mut sink: F) -> Option<CommandResult>
+    where
+        F: FnMut(&str, &str),
+    {
+        let result = self.result.lock().unwrap_or_else(|e| e.into_inner()).take();
+        while let Ok(text) = self.stdout.try_recv() {
+            sink(&text, "stdout");
+        }
+        while let Ok(text) = self.stderr.try_recv() {
+            sink(&text, "stderr");
+        }
+        result
+    }
+}
+
+fn send_chunk(tx: &mpsc::Sender<String>, text: &str) {
+    if let Err(e) = tx.send(text.to_string()) {
+        warn!("Failed to send output chunk to channel: {}", e);
+    }
+}
+
+/// Spawn `program` in `working_dir` without stdin, with piped output and
+/// the sudo shim on PATH.
+pub(super) fn spawn_process(
+    program: &str,
+    args: &[String],
+    working_dir: Option<&str>,
+    limits: ResourceLimits,
+) -> std::io::Result<std::process::Child> {
+    use std::os::unix::process::CommandExt;
+    use std::process::Stdio;
+
+    let mut process = std::process::Command::new(program);
+    process.args(args);
+    if let Some(dir) = working_dir {
+        process.current_dir(dir);
+    }
+    inject_sudo_shim(&mut process);
+    apply_local_priority(&mut process, limits);
+    // Prompts read end of file and fail instead of waiting for an answer
+    process.stdin(Stdio::null());
+    process.stdout(Stdio::piped());
+    process.stderr(Stdio::piped());
+    // Own group, so stopping the task also reaches the child's children
+    process.process_group(0);
+
+    let child = process.spawn()?;
+    child_groups().push(child.id());
+    Ok(child)
+}
+
+/// Stream a child's output into the channels until it exits.
+fn wait_for_process(
+    mut child: std::process::Child,
+    stdout_tx: mpsc::Sender<String>,
+    stderr_tx: mpsc::Sender<String>,
+) -> CommandResult {
+    use std::thread;
+
+    // Spawn thread to read stdout
+    let stdout_handle = child.stdout.take().map(|stdout| {
+        thread::spawn(move || {
+            read_buffer_with_line_processing(
+                stdout,
+                |text| match stdout_tx.send(text) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        warn!("Failed to send stdout chunk to channel: {}", e);
+                        false
                     }
+                },
+                |e| {
+                    warn!("Error reading stdout: {}", e);
+                },
+            );
+        })
+    });
+
+    // Spawn thread to read stderr
+    let stderr_handle = child.stderr.take().map(|stderr| {
+        thread::spawn(move || {
+            read_buffer_with_line_processing(
+                stderr,
+                |text| match stderr_tx.send(text) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        warn!("Failed to send stderr chunk to channel: {}", e);
+                        false
+                    }
+                },
+                |e| {
+                    warn!("Error reading stderr: {}", e);
+                },
+            );
+        })
+    });
+
+    // Wait for output threads to finish
+    if let Some(handle) = stdout_handle {
+        if let Err(e) = handle.join() {
+            warn!("Error joining stdout reader thread: {:?}", e);
+        }
+    }
+    if let Some(handle) = stderr_handle {
+        if let Err(e) = handle.join() {
+            warn!("Error joining stderr reader thread: {:?}", e);
+        }
+    }
+
+    let status = child.wait();
+    child_groups().retain(|pid| *pid != child.id());
+
+    match status {
+        Ok(status) => {
+            if status.success() {
+                CommandResult::Success
+            } else {
+                CommandResult::Failure {
+                    exit_code: status.code(),
                 }
-                Err(e) => {
-                    error!("Error waiting for process: {}", e);
-                    CommandResult::Failure { exit_code: None }
-                }
-            };
-            *result_arc_clone.lock().unwrap() = Some(result);
+            }
         }
-    });
+        Err(e) => {
+            error!("Error waiting for process: {}", e);
+            CommandResult::Failure { exit_code: None }
+        }
+    }
+}
 
-    // Check for result in main thread
-    let context_clone = context.clone();
-    glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
-        let mut result_guard = result_arc.lock().unwrap();
-        if let Some(result) = result_guard.take() {
-            context_clone.set_exit_result(result);
-            glib::ControlFlow::Break
-        } else {
-            glib::ControlFlow::Continue
+/// Run a privileged command through the daemon client library.
+///
+/// Blocks until the command finishes and returns its exit code. Returns
+/// `None` when no connection to the daemon could be made, so the caller can
+/// fall back to the xero-auth binary.
+pub(super) fn run_on_daemon<F, G, C>(
+    program: &str,
+    args: &[String],
+    working_dir: Option<&str>,
+    limits: ResourceLimits,
+    on_output: F,
+    on_error: G,
+    confirm: C,
+) -> Option<anyhow::Result<i32>>
+where
+    F: Fn(&str),
+    G: Fn(&str),
+    C: Fn(&str) -> bool + Send + Sync + 'static,
+{
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            warn!("Failed to create runtime for daemon client: {}", e);
+            return None;
         }
-    });
+    };
+
+    runtime.block_on(async {
+        let mut client = match xero_auth::Client::new().await {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("Daemon client unavailable, using xero-auth binary: {:#}", e);
+                return None;
+            }
+        };
+        client.set_limits(limits);
+        client.set_confirm(confirm);
+        Some(
+            client
+                .execute(
+                    program,
+                    args,
+                    daemon_env(),
+                    working_dir,
+                    on_output,
+                    on_error,
+                )
+                .await,
+        )
+    })
+}
+
+/// Replace `path` with `contents` through the daemon and return the path
+/// of the backup, if one was made.
+pub(super) fn write_on_daemon(
+    path: &str,
+    contents: &[u8],
+    mode: Option<u32>,
+    backup: bool,
+) -> anyhow::Result<Option<String>> {
+    use anyhow::Context;
+
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("Failed to create runtime for daemon client")?
+        .block_on(async {
+            let mut client = xero_auth::Client::new().await?;
+            client.write_file(path, contents, mode, backup).await
+        })
+        .with_context(|| format!("Failed to write {}", path))
+}
+
+/// Replace `path` with `contents`, as the user or through the daemon, and
+/// return the path of the backup, if one was made.
+pub(super) fn write_contents(
+    path: &str,
+    contents: &str,
+    mode: Option<u32>,
+    backup: bool,
+    user_owned: bool,
+) -> anyhow::Result<Option<String>> {
+    use anyhow::Context;
+    use std::os::unix::fs::PermissionsExt;
+
+    if !user_owned {
+        return write_on_daemon(path, contents.as_bytes(), mode, backup);
+    }
+    let backup_path = if backup {
+        match dotfiles::deploy(Path::new(path), contents) {
+            Ok(DeployOutcome::Replaced { backup }) => Some(backup.display().to_string()),
+            Ok(DeployOutcome::Created | DeployOutcome::Unchanged) => None,
+            Err(e) => return Err(e).with_context(|| format!("Failed to write {}", path)),
+        }
+    } else {
+        if let Some(parent) = Path::new(path).parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path))?;
+        None
+    };
+    if let Some(mode) = mode {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+            .with_context(|| format!("Failed to set the mode of {}", path))?;
+    }
+    Ok(backup_path)
+}
+
+/// Output line reporting a finished write.
+pub(super) fn write_report(path: &str, backup_path: Option<&str>) -> String {
+    match backup_path {
+        Some(backup_path) => format!("Wrote {} (backup: {})\n", path, backup_path),
+        None => format!("Wrote {}\n", path),
+    }
+}
+
+/// Resource limits for `command`.
+///
+/// Low-priority commands, and AUR commands while the preference is on, get
+/// a high niceness, idle I/O and at most three quarters of the CPU time.
+/// The CPU quota needs the daemon's systemd scope, so commands running as
+/// the user (like AUR builds) only get niceness and I/O class.
+pub(super) fn resource_limits(command: &Command) -> ResourceLimits {
+    let low_priority = command.low_priority
+        || (command.command_type == CommandType::Aur
+            && crate::config::user::shared().get().tasks.low_priority_aur);
+    if !low_priority {
+        return ResourceLimits::default();
+    }
+
+    let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get() as u32);
+    ResourceLimits {
+        nice: Some(LOW_PRIORITY_NICE),
+        ionice_class: Some(limits::IOPRIO_CLASS_IDLE),
+        cpu_quota_percent: Some(cores * LOW_PRIORITY_CPU_SHARE),
+    }
+}
+
+/// Apply niceness and I/O class to a process started as the user.
+pub(super) fn apply_local_priority(process: &mut std::process::Command, limits: ResourceLimits) {
+    use std::os::unix::process::CommandExt;
+
+    let (nice, ionice_class) = (limits.nice, limits.ionice_class);
+    if nice.is_some() || ionice_class.is_some() {
+        unsafe {
+            process.pre_exec(move || limits::apply_priority(nice, ionice_class));
+        }
+    }
+}
+
+/// Scripts directory prepended to PATH, so the sudo shim intercepts sudo
+/// calls in scripts.
+fn shim_path() -> Option<String> {
+    let scripts_dir = crate::config::paths::scripts();
+    if !scripts_dir.exists() {
+        return None;
+    }
+    let path = std::env::var("PATH").ok()?;
+    Some(format!("{}:{}", scripts_dir.display(), path))
+}
+
+/// Environment for commands run by the daemon, as the xero-auth binary
+/// would pass it: the current environment with the sudo shim on PATH.
+fn daemon_env() -> Vec<String> {
+    let mut env: Vec<String> = std::env::vars()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+    if let Some(path) = shim_path() {
+        env.push(format!("PATH={}", path));
+    }
+    env
+}
+
+/// Prepend the scripts directory to PATH so the sudo shim intercepts
+/// sudo calls in scripts.
+pub(super) fn inject_sudo_shim(process: &mut std::process::Command) {
+    if let Some(path) = shim_path() {
+        process.env("PATH", path);
+    }
 }
 
 /// Resolve command to executable program and arguments,
@@ -361,18 +1168,9 @@ pub fn execute_commands(
 /// # Errors
 ///
 /// Returns an error if the AUR helper is required but not available.
-fn resolve_command(command: &Command) -> Result<(String, Vec<String>), String> {
+pub(super) fn resolve_command(command: &Command) -> Result<(String, Vec<String>), String> {
     // Prepare PATH with scripts directory for sudo shim
-    let scripts_dir = crate::config::paths::scripts();
-    let shim_path_env = if scripts_dir.exists() {
-        if let Ok(path) = std::env::var("PATH") {
-            Some(format!("PATH={}:{}", scripts_dir.display(), path))
-        } else {
-            None
-        }
-    } else {
-        None
-    };
+    let shim_path_env = shim_path().map(|path| format!("PATH={}", path));
 
     match command.command_type {
         CommandType::Normal => Ok((command.program.clone(), command.args.clone())),
@@ -385,6 +1183,10 @@ fn resolve_command(command: &Command) -> Result<(String, Vec<String>), String> {
                 args.push("--env".to_string());
                 args.push(env.clone());
             }
+            args.extend(resource_limits(command).to_cli_args());
+            if let Some(dir) = &command.working_dir {
+                args.push(format!("--cwd={}", dir));
+            }
 
             args.push(command.program.clone());
             args.extend(command.args.clone());
@@ -393,29 +1195,122 @@ fn resolve_command(command: &Command) -> Result<(String, Vec<String>), String> {
         CommandType::Aur => {
             let helper = core::aur_helper()
                 .ok_or_else(|| "AUR helper not available (paru or yay required)".to_string())?;
-            let mut args = Vec::with_capacity(command.args.len() + 2);
+            let mut args = Vec::with_capacity(command.args.len() + 3);
             args.push("--sudo".to_string());
             args.push(get_xero_auth_path().to_string_lossy().to_string());
+            if !command.review {
+                args.push("--noconfirm".to_string());
+            }
             args.extend(command.args.clone());
             Ok((helper.to_string(), args))
         }
     }
 }
 
+/// `command` ready to run: workspace references resolved for the running
+/// sequence, and script steps written to a file. Keep the returned file
+/// alive until the step finishes.
+pub(super) fn prepare(command: &Command) -> Result<(Command, Option<script::ScriptFile>), String> {
+    let command = workspace::substitute(command, workspace::active_path().as_deref())?;
+    script::prepare(&command)
+}
+
+/// `program` and `args` started from `working_dir` through `env`, for
+/// terminals, which start them in the app's directory.
+fn in_working_dir(
+    working_dir: Option<&str>,
+    program: String,
+    args: Vec<String>,
+) -> (String, Vec<String>) {
+    let Some(dir) = working_dir else {
+        return (program, args);
+    };
+    let mut wrapped = vec!["-C".to_string(), dir.to_string(), program];
+    wrapped.extend(args);
+    ("env".to_string(), wrapped)
+}
+
+/// Program and arguments `command` runs, as shown before authenticating.
+///
+/// Privileged programs appear the way the daemon resolves them; other
+/// commands as [`resolve_command`] starts them.
+pub(super) fn preview_command(command: &Command) -> Result<(String, Vec<String>), String> {
+    match command.command_type {
+        CommandType::Privileged => {
+            let program =
+                xero_auth::shared::resolve_program(&command.program, xero_auth::shared::SAFE_PATH)
+                    .map(|path| path.to_string_lossy().to_string())
+                    .unwrap_or_else(|_| command.program.clone());
+            Ok((program, command.args.clone()))
+        }
+        _ => resolve_command(command),
+    }
+}
+
+/// Terminate the process groups of all running children and every command
+/// the daemon runs.
+pub(super) fn terminate_children() {
+    for pid in child_groups().iter() {
+        info!("Terminating process group {}", pid);
+        if unsafe { libc::kill(-(*pid as libc::pid_t), libc::SIGTERM) } != 0 {
+            warn!(
+                "Failed to terminate process group {}: {}",
+                pid,
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    match rt.block_on(core::daemon::cancel_commands()) {
+        Ok(count) => info!("Daemon cancelled {} command(s)", count),
+        Err(e) => error!("Failed to cancel daemon commands: {}", e),
+    }
+}
+
 /// Stop the daemon if needed.
-fn stop_daemon_if_needed() {
+pub(super) fn stop_daemon_if_needed() {
     let rt = tokio::runtime::Runtime::new().unwrap();
     if let Err(e) = rt.block_on(core::daemon::stop_daemon()) {
         error!("Failed to stop daemon: {}", e);
     }
 }
 
+/// Success message, followed by the output of the steps that summarize.
+fn success_summary(widgets: &TaskRunnerWidgets, commands: &[Command]) -> String {
+    let mut message = super::success_message();
+    for (index, _) in commands.iter().enumerate().filter(|(_, cmd)| cmd.summarize) {
+        let Some(output) = widgets.step_output_text(index) else {
+            continue;
+        };
+        for line in summary_lines(&output) {
+            message.push('\n');
+            message.push_str(line);
+        }
+    }
+    message
+}
+
+/// Lines of step output, without the header and exit code the task output
+/// frames it with.
+fn summary_lines(output: &str) -> impl Iterator<Item = &str> {
+    let framing = |line: &str| {
+        line.is_empty()
+            || line.starts_with("[Exit code:")
+            || (line.starts_with("=== ") && line.ends_with(" ==="))
+    };
+    output
+        .lines()
+        .map(str::trim)
+        .filter(move |line| !framing(line))
+}
+
 /// Finalize dialog with success or failure message.
 pub fn finalize_execution(widgets: &TaskRunnerWidgets, success: bool, message: &str) {
     use std::sync::atomic::Ordering;
 
-    // Stop daemon before finalizing
-    stop_daemon_if_needed();
+    // Stop daemon before finalizing, unless the session should stay open
+    super::release_daemon();
 
     // Print final message to terminal
     if success {
@@ -426,6 +1321,183 @@ pub fn finalize_execution(widgets: &TaskRunnerWidgets, success: bool, message: &
         widgets.append_colored(&error_msg, "error");
     }
 
+    // A failed step's workspace may be kept to look into
+    if let Some(kept) = workspace::close(widgets.has_failed_step()) {
+        widgets.append_colored(
+            &format!(
+                "{}\n",
+                i18n::fill(
+                    &gettext("The working folder of the failed step was kept at {}"),
+                    &[&kept.display().to_string()],
+                )
+            ),
+            "stderr",
+        );
+    }
+
     super::ACTION_RUNNING.store(false, Ordering::SeqCst);
+    super::power::release_inhibitor();
     widgets.show_completion(success, message);
+    widgets.emit_finished(success);
+    super::after_completion(&widgets.window, success, message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn driver() -> (
+        CommandDriver,
+        mpsc::Sender<String>,
+        mpsc::Sender<String>,
+        ResultSlot,
+    ) {
+        let (stdout_tx, stdout_rx) = mpsc::channel();
+        let (stderr_tx, stderr_rx) = mpsc::channel();
+        let result = ResultSlot::default();
+        let driver = CommandDriver::new(stdout_rx, stderr_rx, result.clone());
+        (driver, stdout_tx, stderr_tx, result)
+    }
+
+    fn poll(driver: &CommandDriver) -> (Vec<String>, Option<CommandResult>) {
+        let mut output = Vec::new();
+        let result = driver.poll(|text, tag| output.push(format!("{}:{}", tag, text)));
+        (output, result)
+    }
+
+    #[test]
+    fn test_output_is_forwarded_while_running() {
+        let (driver, stdout_tx, stderr_tx, _result) = driver();
+        stdout_tx.send("one".to_string()).unwrap();
+        stderr_tx.send("warning".to_string()).unwrap();
+
+        let (output, result) = poll(&driver);
+        assert_eq!(output, ["stdout:one", "stderr:warning"]);
+        assert!(result.is_none());
+        assert!(poll(&driver).0.is_empty());
+    }
+
+    #[test]
+    fn test_final_burst_arrives_before_result() {
+        let (driver, stdout_tx, stderr_tx, result) = driver();
+
+        // A command that prints a burst right before exiting
+        let producer = std::thread::spawn(move || {
+            for i in 0..1000 {
+                stdout_tx.send(format!("line {}", i)).unwrap();
+            }
+            stderr_tx.send("done".to_string()).unwrap();
+            *result.lock().unwrap() = Some(CommandResult::Success);
+        });
+
+        let mut output = Vec::new();
+        let result = loop {
+            let (mut chunk, result) = poll(&driver);
+            output.append(&mut chunk);
+            if let Some(result) = result {
+                break result;
+            }
+        };
+        producer.join().unwrap();
+
+        assert!(matches!(result, CommandResult::Success));
+        assert_eq!(output.len(), 1001);
+        assert_eq!(output[999], "stdout:line 999");
+        assert_eq!(output[1000], "stderr:done");
+    }
+
+    fn step(command_type: CommandType, program: &str) -> Command {
+        Command {
+            command_type,
+            program: program.to_string(),
+            args: Vec::new(),
+            description: String::new(),
+            condition: None,
+            low_priority: false,
+            review: false,
+            script: None,
+            write: None,
+            summarize: false,
+            working_dir: None,
+        }
+    }
+
+    #[test]
+    fn test_summary_lines() {
+        let output = "\n=== Checking the renderer ===\n\
+                      OpenGL renderer string: NVIDIA GeForce RTX 4070/PCIe/SSE2\n\
+                      \n[Exit code: 0]\n";
+        assert_eq!(
+            summary_lines(output).collect::<Vec<_>>(),
+            ["OpenGL renderer string: NVIDIA GeForce RTX 4070/PCIe/SSE2"]
+        );
+        assert_eq!(summary_lines("").count(), 0);
+    }
+
+    #[test]
+    fn test_working_dir() {
+        let args = vec!["clone".to_string(), "theme".to_string()];
+        assert_eq!(
+            in_working_dir(None, "git".to_string(), args.clone()),
+            ("git".to_string(), args.clone())
+        );
+        assert_eq!(
+            in_working_dir(Some("/work/1"), "git".to_string(), args),
+            (
+                "env".to_string(),
+                vec!["-C", "/work/1", "git", "clone", "theme"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            )
+        );
+
+        // The xero-auth binary passes it on to the daemon
+        let mut install = step(CommandType::Privileged, "sh");
+        install.working_dir = Some("/work/1".to_string());
+        let (_, args) = resolve_command(&install).unwrap();
+        let cwd = args.iter().position(|arg| arg == "--cwd=/work/1").unwrap();
+        assert!(cwd < args.iter().position(|arg| arg == "sh").unwrap());
+    }
+
+    #[test]
+    fn test_waits_for_package_db_after_aur() {
+        let commands = [
+            step(CommandType::Privileged, "pacman"),
+            step(CommandType::Aur, "paru"),
+            step(CommandType::Normal, "pacman"),
+            step(CommandType::Privileged, "systemctl"),
+            step(CommandType::Privileged, "/usr/bin/pacman-key"),
+            step(CommandType::Privileged, "pacman"),
+        ];
+        let waits: Vec<bool> = (0..commands.len())
+            .map(|index| waits_for_package_db(&commands, index))
+            .collect();
+        assert_eq!(waits, [false, false, false, false, true, true]);
+    }
+
+    #[test]
+    fn test_lock_wait() {
+        let lock = std::env::temp_dir().join(format!("xero-db-{}.lck", std::process::id()));
+        std::fs::write(&lock, "").unwrap();
+
+        let wait = LockWait::new(&lock, Duration::from_secs(10));
+        assert_eq!(wait.check(), LockState::Held);
+        assert_eq!(
+            LockWait::new(&lock, Duration::ZERO).wait(Duration::from_millis(1)),
+            LockState::TimedOut
+        );
+
+        // Released while waiting
+        let remover = {
+            let lock = lock.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(50));
+                std::fs::remove_file(lock).unwrap();
+            })
+        };
+        assert_eq!(wait.wait(Duration::from_millis(5)), LockState::Released);
+        remover.join().unwrap();
+        assert_eq!(wait.check(), LockState::Released);
+    }
 }
@@ -0,0 +1,229 @@
+//! Headless command execution for the command line.
+//!
+//! Runs a command sequence without any windows, printing one progress line
+//! per step and the name of each phase to stdout while command output
+//! passes straight through. When stdout is kept for a JSON report, both go
+//! to stderr instead.
+
+use super::command::{Command, CommandType, TaskStatus};
+use super::executor::{
+    apply_local_priority, inject_sudo_shim, prepare, preview_command, resolve_command,
+    resource_limits, run_on_daemon, stop_daemon_if_needed, wait_for_package_db_blocking,
+    waits_for_package_db, write_contents, write_report,
+};
+use super::report::RunReport;
+use super::workspace;
+use super::{needs_daemon, phase_ranges, success_message, CommandSequence};
+use crate::core::logs;
+use log::{error, info};
+use std::io::IsTerminal;
+use std::process::Stdio;
+
+/// Where progress and command output go.
+#[derive(Clone, Copy)]
+struct Output {
+    /// Keep stdout free for the JSON report
+    json: bool,
+}
+
+impl Output {
+    fn line(self, text: &str) {
+        self.print(&format!("{}\n", text));
+    }
+
+    fn print(self, text: &str) {
+        if self.json {
+            eprint!("{}", text);
+        } else {
+            print!("{}", text);
+        }
+    }
+}
+
+/// Run `commands` in the foreground and return the report of the run.
+///
+/// Stops at the first failing step; [`RunReport::exit_code`] gives its exit
+/// code, or 1 when the step could not be started or was killed by a
+/// signal. With `json`, stdout is left to the caller.
+pub fn run_headless(commands: CommandSequence, title: &str, json: bool) -> RunReport {
+    let output = Output { json };
+    let phases = phase_ranges(&commands.phases, commands.commands.len());
+    let uses_workspace = commands.workspace;
+    let commands = commands.commands;
+    let mut report = RunReport::new(title, &commands, logs::log_path());
+    output.line(&format!("==> {}", title));
+
+    let uses_daemon = needs_daemon(&commands);
+    if uses_daemon {
+        if let Err(e) = crate::core::daemon::start_daemon() {
+            error!("Failed to start daemon: {}", e);
+            eprintln!("Failed to start authentication daemon: {}", e);
+            report.finish(false);
+            return report;
+        }
+        info!("Daemon ready for privileged commands");
+    }
+
+    if uses_workspace {
+        if let Err(e) = workspace::open() {
+            error!("Failed to create the workspace: {}", e);
+            eprintln!("Failed to create the working folder of the task: {}", e);
+            if uses_daemon {
+                stop_daemon_if_needed();
+            }
+            report.finish(false);
+            return report;
+        }
+    }
+
+    let total = commands.len();
+    let mut success = true;
+
+    for (index, cmd) in commands.iter().enumerate() {
+        let step = format!("[{}/{}]", index + 1, total);
+        if let Some((name, _)) = phases.iter().find(|(_, steps)| steps.start == index) {
+            output.line(&format!(":: {}", name));
+        }
+
+        if cmd
+            .condition
+            .as_ref()
+            .is_some_and(|condition| !condition.holds())
+        {
+            output.line(&format!("{} Skipped: {}", step, cmd.description));
+            report.set_status(index, TaskStatus::Skipped);
+            continue;
+        }
+
+        output.line(&format!("{} {}", step, cmd.description));
+        report.set_status(index, TaskStatus::Running);
+        if waits_for_package_db(&commands, index) {
+            wait_for_package_db_blocking(|message| output.line(message));
+        }
+
+        match run_step(cmd, index, &mut report, output) {
+            Ok(0) => {
+                report.set_exit_code(index, Some(0));
+                report.set_status(index, TaskStatus::Success);
+            }
+            Ok(code) => {
+                eprintln!(
+                    "Operation failed at step {} of {} (exit code: {})",
+                    index + 1,
+                    total,
+                    code
+                );
+                report.set_exit_code(index, Some(code));
+                report.set_status(index, TaskStatus::Failed);
+                success = false;
+                break;
+            }
+            Err(err) => {
+                eprintln!("{} {}", step, err);
+                report.set_status(index, TaskStatus::Failed);
+                success = false;
+                break;
+            }
+        }
+    }
+
+    if uses_daemon {
+        stop_daemon_if_needed();
+    }
+
+    let failed = report
+        .steps
+        .iter()
+        .any(|step| step.status == TaskStatus::Failed);
+    if let Some(kept) = workspace::close(failed) {
+        eprintln!(
+            "The working folder of the failed step was kept at {}",
+            kept.display()
+        );
+    }
+
+    if success {
+        output.line(&success_message());
+    }
+    report.finish(success);
+    report
+}
+
+/// Run one step, through the daemon when it is privileged, and return its
+/// exit code, recording what it runs in `report`.
+fn run_step(
+    cmd: &Command,
+    index: usize,
+    report: &mut RunReport,
+    output: Output,
+) -> Result<i32, String> {
+    if let Some(write) = &cmd.write {
+        let contents = write
+            .contents()
+            .map_err(|err| format!("Failed to prepare command: {}", err))?;
+        let backup_path = write_contents(
+            &write.path,
+            &contents,
+            write.mode,
+            write.backup,
+            write.user_owned,
+        )
+        .map_err(|e| format!("{:#}", e))?;
+        output.print(&write_report(&write.path, backup_path.as_deref()));
+        return Ok(0);
+    }
+    // Kept until the step finished, however it ended
+    let (mut cmd, _script) =
+        prepare(cmd).map_err(|err| format!("Failed to prepare command: {}", err))?;
+    // Without a terminal, nobody could answer the AUR helper's questions
+    if cmd.review && !std::io::stdin().is_terminal() {
+        cmd.review = false;
+    }
+    if let Ok((program, args)) = preview_command(&cmd) {
+        report.set_command(index, &program, &args);
+    }
+    match cmd.command_type {
+        CommandType::Privileged => run_on_daemon(
+            &cmd.program,
+            &cmd.args,
+            cmd.working_dir.as_deref(),
+            resource_limits(&cmd),
+            |text| output.print(text),
+            |text| eprint!("{}", text),
+            xero_auth::confirm_on_terminal,
+        )
+        .map(|outcome| outcome.map_err(|e| format!("{:#}", e))),
+        _ => None,
+    }
+    .unwrap_or_else(|| run_process(&cmd, output))
+}
+
+/// Run `cmd` as a child process with inherited output and return its exit
+/// code, or 1 when it was killed by a signal.
+fn run_process(cmd: &Command, output: Output) -> Result<i32, String> {
+    let (program, args) =
+        resolve_command(cmd).map_err(|err| format!("Failed to prepare command: {}", err))?;
+
+    info!("Executing: {} {:?}", program, args);
+    let mut process = std::process::Command::new(&program);
+    process.args(&args);
+    if let Some(dir) = &cmd.working_dir {
+        process.current_dir(dir);
+    }
+    if output.json {
+        process.stdout(Stdio::from(std::io::stderr()));
+    }
+    inject_sudo_shim(&mut process);
+    if cmd.command_type != CommandType::Privileged {
+        apply_local_priority(&mut process, resource_limits(cmd));
+    }
+
+    let status = process
+        .status()
+        .map_err(|err| format!("Failed to start operation: {}", err))?;
+    Ok(if status.success() {
+        0
+    } else {
+        status.code().filter(|code| *code != 0).unwrap_or(1)
+    })
+}
@@ -4,8 +4,27 @@
 //! - Step-by-step execution status with visual progress tracking
 //! - Output capture (stdout/stderr) for better error reporting
 //! - Cancellation support (waits for current command to finish)
-//! - Automatic privilege escalation via pkexec
-//! - AUR helper integration (paru/yay)
+//! - Closing during a run asks to stop it and terminates its process groups
+//! - Automatic privilege escalation via pkexec, after listing the privileged steps
+//! - AUR helper integration (paru/yay), run in a terminal when the AUR
+//!   confirmation preference asks for PKGBUILD review
+//! - Conditional steps via `run_if`, skipped when the check fails
+//! - Privileged pacman steps wait for the database lock after AUR helper steps
+//! - Steps grouped into named phases, collapsed once every step succeeded
+//! - Headless execution with plain-text progress for the command line
+//! - A [`RunReport`] of each run, printed as JSON by the command line
+//! - Authorization kept for the configured idle timeout between tasks
+//! - Start and end of each run sent as [`crate::core::events`]
+//! - Step rows show their command line and copy it, copy their output or run
+//!   the step again from a context menu
+//! - Common pacman errors of a failed step explained, with a button running
+//!   the maintenance action that fixes them
+//! - Steps run without stdin; one that still waits for an answer is offered
+//!   to be stopped or run again in a terminal
+//! - Output colors follow the dark, light and high-contrast styles and the
+//!   accent color
+//! - Steps that clone and build can share a scratch workspace, removed when
+//!   the run ends
 //!
 //! ## Usage
 //!
@@ -30,7 +49,7 @@
 //! let commands = CommandSequence::new()
 //!     .then(Command::builder()
 //!         .aur()
-//!         .args(&["-S", "--noconfirm", "package"])
+//!         .args(&["-S", "package"])
 //!         .description("Installing package")
 //!         .build())
 //!     .then(Command::builder()
@@ -52,22 +71,43 @@
 //! 4. Show completion status with appropriate success/failure messages
 
 mod command;
+mod diagnostics;
 mod executor;
+mod headless;
+mod palette;
+mod power;
+mod report;
+mod script;
+mod stall;
+mod summary;
 mod widgets;
-
-use crate::ui::utils::extract_widget;
+mod workspace;
+
+use crate::config;
+use crate::config::user::AurConfirmation;
+use crate::core::events::{self, Event};
+use crate::core::logs;
+use crate::i18n::{self, gettext};
+use crate::ui::dialogs::error::show_error;
+use crate::ui::toast;
+use crate::ui::utils::{builder_from_resource, extract_widget, WidgetError};
+use adw::prelude::*;
+use gtk4::gio;
 use gtk4::glib;
-use gtk4::prelude::*;
-use gtk4::{Button, Label, Separator, ToggleButton, Window};
+use gtk4::{Button, Label, ToggleButton, Window};
 use log::{error, info, warn};
 use std::cell::RefCell;
+use std::ops::Range;
 use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 // Re-export public API
-pub use command::{Command, TaskStatus};
+pub use command::{Command, CommandType};
+pub use headless::run_headless;
+pub use report::RunReport;
+pub use workspace::PLACEHOLDER as WORKSPACE;
 
-use widgets::{TaskItem, TaskRunnerWidgets};
+use widgets::{CompletionCallback, TaskList, TaskRunnerWidgets};
 
 /// Helper for building sequences of commands with a fluent API.
 ///
@@ -91,6 +131,20 @@ use widgets::{TaskItem, TaskRunnerWidgets};
 #[derive(Debug, Default)]
 pub struct CommandSequence {
     pub(super) commands: Vec<Command>,
+    /// Named groups of the commands, in order
+    phases: Vec<Phase>,
+    /// Checked against the battery before running
+    pub(super) long_running: bool,
+    /// Give the run a scratch directory for its steps
+    pub(super) workspace: bool,
+}
+
+/// Named group of consecutive steps, running up to the next phase.
+#[derive(Clone, Debug)]
+struct Phase {
+    name: String,
+    /// Index of the first command of the phase
+    start: usize,
 }
 
 impl CommandSequence {
@@ -98,6 +152,9 @@ impl CommandSequence {
     pub fn new() -> Self {
         Self {
             commands: Vec::new(),
+            phases: Vec::new(),
+            long_running: false,
+            workspace: false,
         }
     }
 
@@ -109,8 +166,63 @@ impl CommandSequence {
         self
     }
 
+    /// Start a phase grouping the commands added after it, like
+    /// "Dependencies" or "Shell integration".
+    ///
+    /// The task dialog shows the name as a header above the steps and
+    /// collapses the phase once all of them succeeded.
+    pub fn phase(mut self, name: &str) -> Self {
+        self.phases.push(Phase {
+            name: name.to_string(),
+            start: self.commands.len(),
+        });
+        self
+    }
+
+    /// Append all commands of another sequence.
+    pub fn extend(mut self, other: CommandSequence) -> Self {
+        let offset = self.commands.len();
+        self.phases
+            .extend(other.phases.into_iter().map(|phase| Phase {
+                start: phase.start + offset,
+                ..phase
+            }));
+        self.commands.extend(other.commands);
+        self.long_running |= other.long_running;
+        self.workspace |= other.workspace;
+        self
+    }
+
+    /// Mark the sequence as one that should not be interrupted by an empty
+    /// battery, like kernel installs and system updates.
+    ///
+    /// Running it on a low battery asks for confirmation first.
+    pub fn long_running(mut self) -> Self {
+        self.long_running = true;
+        self
+    }
+
+    /// Give the run a fresh directory in the cache for steps that clone and
+    /// build, so they neither leave files in the home directory nor collide
+    /// with what an earlier run left.
+    ///
+    /// Steps refer to it as `${WORKSPACE}` in arguments and script
+    /// variables, or run in it with `cwd_workspace()`. It is removed once
+    /// the run ends, however it ends.
+    pub fn with_workspace(mut self) -> Self {
+        self.workspace = true;
+        self
+    }
+
     /// Build the final command sequence.
+    ///
+    /// Sequences installing flatpaks start by adding the Flathub remote when
+    /// it is missing.
     pub fn build(self) -> Self {
+        if needs_flathub_remote(&self.commands) && !crate::core::package::is_flathub_enabled() {
+            info!("Flathub remote is missing, adding it first");
+            return crate::core::package::ensure_flathub_sequence().extend(self);
+        }
         self
     }
 
@@ -118,25 +230,222 @@ impl CommandSequence {
     pub fn is_empty(&self) -> bool {
         self.commands.is_empty()
     }
+
+    /// The commands of the sequence, in order.
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+}
+
+/// Name and command indices of each phase that has commands, in order.
+fn phase_ranges(phases: &[Phase], len: usize) -> Vec<(&str, Range<usize>)> {
+    phases
+        .iter()
+        .enumerate()
+        .map(|(i, phase)| {
+            let end = phases.get(i + 1).map_or(len, |next| next.start);
+            (phase.name.as_str(), phase.start..end.min(len))
+        })
+        .filter(|(_, steps)| !steps.is_empty())
+        .collect()
+}
+
+/// Whether `commands` install flatpaks without adding the Flathub remote
+/// first.
+fn needs_flathub_remote(commands: &[Command]) -> bool {
+    let flatpak = |cmd: &Command, subcommand: &str| {
+        cmd.program == "flatpak" && cmd.args.iter().any(|arg| arg == subcommand)
+    };
+    let installs = commands.iter().any(|cmd| flatpak(cmd, "install"));
+    installs && !commands.iter().any(|cmd| flatpak(cmd, "remote-add"))
+}
+
+/// Check if any command needs the daemon (privileged or AUR commands).
+fn needs_daemon(commands: &[Command]) -> bool {
+    commands.iter().any(|cmd| {
+        matches!(
+            cmd.command_type,
+            command::CommandType::Privileged | command::CommandType::Aur
+        )
+    })
 }
 
 /// Message displayed when waiting for current command to finish after cancellation.
-pub(super) const CANCEL_WAITING_MESSAGE: &str = "Waiting for current command to finish...";
+pub(super) fn cancel_waiting_message() -> String {
+    gettext("Waiting for current command to finish...")
+}
 
 /// Message displayed when operation is canceled.
-pub(super) const CANCELLED_MESSAGE: &str = "Operation cancelled by user";
+pub(super) fn cancelled_message() -> String {
+    gettext("Operation cancelled by user")
+}
 
 /// Message displayed when all operations complete successfully.
-pub(super) const SUCCESS_MESSAGE: &str = "All operations completed successfully!";
+pub(super) fn success_message() -> String {
+    gettext("All operations completed successfully!")
+}
 
 /// Global flag to track if an action is currently running.
 static ACTION_RUNNING: AtomicBool = AtomicBool::new(false);
 
+thread_local! {
+    /// Dialog of the most recent action, raised when the app is launched again.
+    static ACTIVE_DIALOG: RefCell<glib::WeakRef<Window>> = RefCell::new(glib::WeakRef::new());
+
+    /// Cancellation flag of the running task, set when it is stopped.
+    static ACTIVE_CANCELLED: RefCell<Option<Rc<RefCell<bool>>>> = const { RefCell::new(None) };
+
+    /// Scheduled stop of the authentication daemon after the idle timeout.
+    static PENDING_DAEMON_STOP: RefCell<Option<glib::SourceId>> = const { RefCell::new(None) };
+}
+
+fn cancel_daemon_stop() {
+    if let Some(source) = PENDING_DAEMON_STOP.with(|pending| pending.borrow_mut().take()) {
+        source.remove();
+    }
+}
+
+/// Stop the authentication daemon once the configured idle timeout passes,
+/// or right away if no timeout is set.
+fn release_daemon() {
+    cancel_daemon_stop();
+
+    let minutes = config::user::shared().get().auth.idle_timeout_minutes;
+    if minutes == 0 {
+        executor::stop_daemon_if_needed();
+        return;
+    }
+
+    info!("Keeping authentication session for {} minute(s)", minutes);
+    let source = glib::timeout_add_seconds_local_once(minutes * 60, || {
+        PENDING_DAEMON_STOP.with(|pending| pending.borrow_mut().take());
+        info!("Authentication session idle timeout reached");
+        executor::stop_daemon_if_needed();
+    });
+    PENDING_DAEMON_STOP.with(|pending| pending.replace(Some(source)));
+}
+
+/// End the authentication session now instead of waiting for the idle timeout.
+///
+/// Returns `false` and does nothing while an action is running, since it
+/// still needs the daemon.
+pub fn end_auth_session() -> bool {
+    if is_running() {
+        warn!("Action running - keeping authentication session");
+        return false;
+    }
+
+    cancel_daemon_stop();
+    executor::stop_daemon_if_needed();
+    info!("Authentication session ended");
+    true
+}
+
+/// Apply the task preferences once a run has finished.
+fn after_completion(window: &Window, success: bool, message: &str) {
+    let tasks = config::user::shared().get().tasks.clone();
+
+    if tasks.notifications && !window.is_active() {
+        if let Some(app) = gio::Application::default() {
+            let title = window.title().unwrap_or_default();
+            let notification = gio::Notification::new(&title);
+            notification.set_body(Some(message));
+            app.send_notification(Some("task-finished"), &notification);
+        }
+    }
+
+    if success && tasks.auto_close {
+        window.close();
+    }
+}
+
+/// Present the task dialog if one is still open.
+///
+/// Returns `true` if a dialog was presented.
+pub fn present_dialog() -> bool {
+    let dialog = ACTIVE_DIALOG
+        .with(|dialog| dialog.borrow().upgrade())
+        .filter(|dialog| dialog.is_visible());
+
+    match dialog {
+        Some(dialog) => {
+            dialog.present();
+            true
+        }
+        None => false,
+    }
+}
+
 /// Check if an action is currently running.
 pub fn is_running() -> bool {
     ACTION_RUNNING.load(Ordering::SeqCst)
 }
 
+/// Whether an action is running, telling the user with a toast that offers
+/// to show it. For handlers about to start another one.
+pub fn busy() -> bool {
+    if !is_running() {
+        return false;
+    }
+    toast::toast_with_action(
+        &gettext("Another operation is still running"),
+        &gettext("Show"),
+        || {
+            present_dialog();
+        },
+    );
+    true
+}
+
+/// Stop the running action right away, terminating the processes it started
+/// here and through the daemon.
+pub fn stop_running() {
+    if !is_running() {
+        return;
+    }
+
+    warn!("Stopping running action");
+    if let Some(cancelled) = ACTIVE_CANCELLED.with(|active| active.borrow().clone()) {
+        *cancelled.borrow_mut() = true;
+    }
+    executor::terminate_children();
+    ACTION_RUNNING.store(false, Ordering::SeqCst);
+    power::release_inhibitor();
+}
+
+/// Ask whether to stop the running action, then call `on_stopped`.
+///
+/// Calls `on_stopped` right away when nothing is running.
+pub fn confirm_stop<F>(parent: &impl IsA<gtk4::Widget>, on_stopped: F)
+where
+    F: FnOnce() + 'static,
+{
+    if !is_running() {
+        on_stopped();
+        return;
+    }
+
+    let dialog = adw::AlertDialog::new(
+        Some(&gettext("An operation is still running")),
+        Some(&gettext(
+            "Stop it? Programs it started are terminated, which may leave the system partly changed.",
+        )),
+    );
+    dialog.add_response("cancel", &gettext("Keep Running"));
+    dialog.add_response("stop", &gettext("Stop and Close"));
+    dialog.set_response_appearance("stop", adw::ResponseAppearance::Destructive);
+    dialog.set_default_response(Some("cancel"));
+    dialog.set_close_response("cancel");
+
+    let parent = parent.clone().upcast::<gtk4::Widget>();
+    glib::spawn_future_local(async move {
+        if dialog.choose_future(Some(&parent)).await == "stop" {
+            stop_running();
+            on_stopped();
+        }
+    });
+}
+
 /// Run commands with a progress dialog.
 ///
 /// Displays a modal dialog showing command execution progress with:
@@ -167,66 +476,187 @@ pub fn is_running() -> bool {
 /// run(&window, commands, "System Setup");
 /// ```
 pub fn run(parent: &Window, commands: CommandSequence, title: &str) {
+    run_inner(parent, commands, title, None);
+}
+
+/// Run commands with a progress dialog and invoke `on_complete` when done.
+///
+/// The callback receives `true` only if every command succeeded. It is not
+/// called when the run is rejected because another action is in progress.
+pub fn run_with_completion<F>(
+    parent: &Window,
+    commands: CommandSequence,
+    title: &str,
+    on_complete: F,
+) where
+    F: FnOnce(bool) + 'static,
+{
+    let on_complete = move |report: &RunReport| on_complete(report.success);
+    run_inner(parent, commands, title, Some(Box::new(on_complete)));
+}
+
+fn run_inner(
+    parent: &Window,
+    commands: CommandSequence,
+    title: &str,
+    on_complete: Option<CompletionCallback>,
+) {
     if commands.is_empty() {
         error!("No commands provided");
         return;
     }
 
-    if is_running() {
+    if busy() {
         warn!("Action already running - ignoring request");
         return;
     }
 
     ACTION_RUNNING.store(true, Ordering::SeqCst);
+    power::inhibit(parent, title);
+
+    let parent_clone = parent.clone();
+    let title = title.to_string();
+    power::confirm_power(parent, commands.long_running, move |proceed| {
+        if proceed {
+            confirm_aur_review(&parent_clone, commands, &title, on_complete);
+        } else {
+            info!("Low battery warning cancelled");
+            ACTION_RUNNING.store(false, Ordering::SeqCst);
+            power::release_inhibitor();
+        }
+    });
+}
 
-    let builder = gtk4::Builder::from_resource(crate::config::resources::dialogs::TASK_LIST);
-
-    let window: Window = extract_widget(&builder, "task_window");
-    let title_label: Label = extract_widget(&builder, "task_title");
-    let task_list_container: gtk4::Box = extract_widget(&builder, "task_list_container");
-    let scrolled_window: gtk4::ScrolledWindow = extract_widget(&builder, "task_scrolled_window");
-    let cancel_button: Button = extract_widget(&builder, "cancel_button");
-    let close_button: Button = extract_widget(&builder, "close_button");
-    let sidebar_toggle: ToggleButton = extract_widget(&builder, "sidebar_toggle_button");
-    let sidebar_revealer: gtk4::Revealer = extract_widget(&builder, "sidebar_revealer");
-    let output_text_view: gtk4::TextView = extract_widget(&builder, "output_text_view");
-    let output_text_buffer = output_text_view.buffer();
+/// Ask whether AUR packages are reviewed when the preference leaves it to
+/// each run, then go on to authentication.
+fn confirm_aur_review(
+    parent: &Window,
+    mut commands: CommandSequence,
+    title: &str,
+    on_complete: Option<CompletionCallback>,
+) {
+    let asks = config::user::shared().get().tasks.aur_confirmation == AurConfirmation::Ask;
+    if !asks
+        || !commands
+            .commands
+            .iter()
+            .any(|command| command.command_type == CommandType::Aur)
+    {
+        confirm_auth(parent, commands, title, on_complete);
+        return;
+    }
 
-    window.set_transient_for(Some(parent));
-    window.set_title(Some(title));
+    let dialog = adw::AlertDialog::new(
+        Some(&gettext("Review AUR Packages?")),
+        Some(&gettext(
+            "AUR packages are built from scripts anyone can upload. To review them, the AUR \
+             helper runs in a terminal, shows each PKGBUILD and what changed, and asks before \
+             building.",
+        )),
+    );
+    dialog.add_response("cancel", &gettext("Cancel"));
+    dialog.add_response("silent", &gettext("Install Without Review"));
+    dialog.add_response("review", &gettext("Review"));
+    dialog.set_response_appearance("review", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("review"));
+    dialog.set_close_response("cancel");
+
+    let parent = parent.clone();
+    let title = title.to_string();
+    glib::spawn_future_local(async move {
+        let response = dialog.choose_future(Some(&parent)).await;
+        if response == "cancel" {
+            info!("AUR review question cancelled");
+            ACTION_RUNNING.store(false, Ordering::SeqCst);
+            power::release_inhibitor();
+            return;
+        }
+        let review = response == "review";
+        for command in &mut commands.commands {
+            if command.command_type == CommandType::Aur {
+                command.review = review;
+            }
+        }
+        confirm_auth(&parent, commands, &title, on_complete);
+    });
+}
 
-    let commands_vec = commands.commands;
+/// Explain the password prompt unless the session is still authorized, then
+/// start the run.
+fn confirm_auth(
+    parent: &Window,
+    commands: CommandSequence,
+    title: &str,
+    on_complete: Option<CompletionCallback>,
+) {
+    if needs_daemon(&commands.commands)
+        && config::user::shared().get().auth.show_summary
+        && !xero_auth::is_daemon_running()
+    {
+        let steps = summary::privileged_steps(&commands.commands);
+        let parent_clone = parent.clone();
+        let title = title.to_string();
+        summary::show_auth_summary(parent, &steps, move |confirmed| {
+            if confirmed {
+                start_run(&parent_clone, commands, &title, on_complete);
+            } else {
+                info!("Authentication summary cancelled");
+                ACTION_RUNNING.store(false, Ordering::SeqCst);
+                power::release_inhibitor();
+            }
+        });
+        return;
+    }
 
-    // Create task items for each command
-    let mut task_items = Vec::new();
-    for (i, cmd) in commands_vec.iter().enumerate() {
-        let task_item = TaskItem::new(&cmd.description);
-        task_item.set_status(TaskStatus::Pending);
-        task_list_container.append(&task_item.container);
-
-        if i < commands_vec.len() - 1 {
-            let sep = Separator::new(gtk4::Orientation::Horizontal);
-            task_list_container.append(&sep);
+    start_run(parent, commands, title, on_complete);
+}
+
+/// Open the task dialog and start executing `commands`.
+fn start_run(
+    parent: &Window,
+    commands: CommandSequence,
+    title: &str,
+    on_complete: Option<CompletionCallback>,
+) {
+    let CommandSequence {
+        commands: commands_vec,
+        phases,
+        workspace,
+        ..
+    } = commands;
+
+    let report = RunReport::new(title, &commands_vec, logs::log_path());
+    let widgets = match load_task_dialog(&commands_vec, &phases, report.clone()) {
+        Ok(widgets) => Rc::new(widgets),
+        Err(e) => {
+            error!("Failed to build the task dialog: {}", e);
+            ACTION_RUNNING.store(false, Ordering::SeqCst);
+            power::release_inhibitor();
+            show_error(
+                parent,
+                &i18n::fill(
+                    &gettext("The task window could not be opened, nothing was run.\n\n{}"),
+                    &[&e.to_string()],
+                ),
+            );
+            if let Some(on_complete) = on_complete {
+                let mut report = report;
+                report.finish(false);
+                on_complete(&report);
+            }
+            return;
         }
-        task_items.push(task_item);
-    }
+    };
+    let window = widgets.window.clone();
+    let cancel_button = widgets.cancel_button.clone();
+    let close_button = widgets.close_button.clone();
 
-    // Initialize output buffer
-    output_text_buffer.set_text("Command outputs will appear here as tasks execute...\n\n");
+    window.set_transient_for(Some(parent));
+    window.set_title(Some(title));
 
-    let widgets = Rc::new(TaskRunnerWidgets::new(
-        window.clone(),
-        title_label,
-        task_list_container,
-        scrolled_window,
-        cancel_button.clone(),
-        close_button.clone(),
-        task_items,
-        sidebar_toggle,
-        sidebar_revealer,
-        output_text_view,
-        output_text_buffer,
-    ));
+    if let Some(on_complete) = on_complete {
+        widgets.set_on_complete(on_complete);
+    }
 
     // Setup sidebar toggle binding and initialize collapsed
     widgets.setup_sidebar_toggle();
@@ -235,6 +665,7 @@ pub fn run(parent: &Window, commands: CommandSequence, title: &str) {
     let cancelled = Rc::new(RefCell::new(false));
     let current_process = Rc::new(RefCell::new(None::<gtk4::gio::Subprocess>));
     let commands = Rc::new(commands_vec);
+    widgets.setup_step_menus(&commands);
 
     // Cancel button handler
     let widgets_clone = widgets.clone();
@@ -242,7 +673,7 @@ pub fn run(parent: &Window, commands: CommandSequence, title: &str) {
     cancel_button.connect_clicked(move |_| {
         *cancelled_clone.borrow_mut() = true;
         widgets_clone.disable_cancel();
-        widgets_clone.set_title(CANCEL_WAITING_MESSAGE);
+        widgets_clone.set_title(&cancel_waiting_message());
     });
 
     // Close button handler
@@ -251,37 +682,233 @@ pub fn run(parent: &Window, commands: CommandSequence, title: &str) {
         widgets_clone.window.close();
     });
 
-    // Window close handler
+    // Window close handler, asks first while a command is running
+    let widgets_clone = widgets.clone();
     let cancelled_clone = cancelled.clone();
-    window.connect_close_request(move |_| {
+    window.connect_close_request(move |window| {
+        if is_running() {
+            let window_clone = window.clone();
+            confirm_stop(window, move || window_clone.close());
+            return glib::Propagation::Stop;
+        }
         ACTION_RUNNING.store(false, Ordering::SeqCst);
         *cancelled_clone.borrow_mut() = true;
+        power::release_inhibitor();
+        // Closed while offering to retry authentication
+        widgets_clone.abandon();
         glib::Propagation::Proceed
     });
 
+    // The inhibitor must not outlive the dialog, however it goes away, but
+    // an old dialog must not release the one of a newer run
+    window.connect_destroy(|_| {
+        if !is_running() {
+            power::release_inhibitor();
+        }
+    });
+
+    ACTIVE_DIALOG.with(|dialog| dialog.replace(window.downgrade()));
+    ACTIVE_CANCELLED.with(|active| active.replace(Some(cancelled.clone())));
     window.present();
+    events::emit(Event::TaskStarted {
+        title: title.to_string(),
+    });
 
-    // Check if we need the daemon (any privileged or AUR commands)
-    let needs_daemon = commands.iter().any(|cmd| {
-        matches!(
-            cmd.command_type,
-            command::CommandType::Privileged | command::CommandType::Aur
-        )
+    // Retrying authentication runs the sequence from its first step
+    let widgets_clone = widgets.clone();
+    let commands_clone = commands.clone();
+    let cancelled_clone = cancelled.clone();
+    let process_clone = current_process.clone();
+    let title = title.to_string();
+    widgets.retry_button.connect_clicked(move |_| {
+        if busy() {
+            return;
+        }
+        info!("Retrying authentication");
+        ACTION_RUNNING.store(true, Ordering::SeqCst);
+        power::inhibit(&widgets_clone.window, &title);
+        *cancelled_clone.borrow_mut() = false;
+        widgets_clone.hide_retry();
+        widgets_clone.restart_report();
+        start_commands(
+            widgets_clone.clone(),
+            commands_clone.clone(),
+            cancelled_clone.clone(),
+            process_clone.clone(),
+            workspace,
+        );
     });
 
-    // Start daemon if needed
-    if needs_daemon {
+    start_commands(widgets, commands, cancelled, current_process, workspace);
+}
+
+/// Start the daemon if `commands` need it, create the run's workspace if
+/// it has one, then execute them.
+fn start_commands(
+    widgets: Rc<TaskRunnerWidgets>,
+    commands: Rc<Vec<Command>>,
+    cancelled: Rc<RefCell<bool>>,
+    current_process: Rc<RefCell<Option<gio::Subprocess>>>,
+    workspace: bool,
+) {
+    if needs_daemon(&commands) {
+        cancel_daemon_stop();
         if let Err(e) = crate::core::daemon::start_daemon() {
             error!("Failed to start daemon: {}", e);
-            let error_msg = format!("Failed to start authentication daemon: {}\n", e);
-            widgets.append_colored(&error_msg, "error");
-            widgets.set_title(&format!("Failed to start authentication daemon: {}", e));
-            widgets.show_completion(false, "Failed to start authentication daemon");
+            let error_msg = i18n::fill(
+                &gettext("Failed to start authentication daemon: {}"),
+                &[&e.to_string()],
+            );
+            widgets.append_colored(&format!("{}\n", error_msg), "error");
+            ACTION_RUNNING.store(false, Ordering::SeqCst);
+            power::release_inhibitor();
+            // A dismissed prompt is worth another try, other reasons end the run
+            if e.retryable() {
+                widgets.show_retry(&gettext(
+                    "Authentication was cancelled — click Retry to authenticate again.",
+                ));
+            } else {
+                widgets.show_completion(false, &e.to_string());
+                widgets.emit_finished(false);
+            }
             return;
         }
         info!("Daemon ready for privileged commands");
     }
 
-    // Start executing commands
+    // Removed again by finalize_execution, however the run ends
+    if workspace {
+        if let Err(e) = workspace::open() {
+            error!("Failed to create the workspace: {}", e);
+            executor::finalize_execution(
+                &widgets,
+                false,
+                &i18n::fill(
+                    &gettext("Failed to create the working folder of the task: {}"),
+                    &[&e.to_string()],
+                ),
+            );
+            return;
+        }
+    }
+
     executor::execute_commands(widgets, commands, 0, cancelled, current_process);
 }
+
+/// Build the task dialog with a pending row for each of `commands`, grouped
+/// under the headers of `phases`, filling in `report` as they run.
+fn load_task_dialog(
+    commands: &[Command],
+    phases: &[Phase],
+    report: RunReport,
+) -> Result<TaskRunnerWidgets, WidgetError> {
+    let builder = builder_from_resource(crate::config::resources::dialogs::TASK_LIST);
+
+    let window: Window = extract_widget(&builder, "task_window")?;
+    let title_label: Label = extract_widget(&builder, "task_title")?;
+    let task_list_container: gtk4::Box = extract_widget(&builder, "task_list_container")?;
+    let scrolled_window: gtk4::ScrolledWindow = extract_widget(&builder, "task_scrolled_window")?;
+    let cancel_button: Button = extract_widget(&builder, "cancel_button")?;
+    let close_button: Button = extract_widget(&builder, "close_button")?;
+    let retry_button: Button = extract_widget(&builder, "retry_button")?;
+    let sidebar_toggle: ToggleButton = extract_widget(&builder, "sidebar_toggle_button")?;
+    let sidebar_revealer: gtk4::Revealer = extract_widget(&builder, "sidebar_revealer")?;
+    let output_text_view: gtk4::TextView = extract_widget(&builder, "output_text_view")?;
+    let output_text_buffer = output_text_view.buffer();
+
+    // Create task items for each command
+    let task_list = TaskList::new(
+        &task_list_container,
+        commands,
+        &phase_ranges(phases, commands.len()),
+    );
+
+    // Initialize output buffer
+    output_text_buffer.set_text(&format!(
+        "{}\n\n",
+        gettext("Command outputs will appear here as tasks execute...")
+    ));
+
+    Ok(TaskRunnerWidgets::new(
+        window,
+        title_label,
+        task_list_container,
+        scrolled_window,
+        cancel_button,
+        close_button,
+        retry_button,
+        task_list,
+        sidebar_toggle,
+        sidebar_revealer,
+        output_text_view,
+        output_text_buffer,
+        report,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flatpak(args: &[&str]) -> Command {
+        Command::builder()
+            .normal()
+            .program("flatpak")
+            .args(args)
+            .description("flatpak")
+            .build()
+    }
+
+    #[test]
+    fn test_needs_flathub_remote() {
+        let install = flatpak(&["install", "-y", "flathub", "com.obsproject.Studio"]);
+        let systemctl = Command::builder()
+            .privileged()
+            .program("systemctl")
+            .args(&["install"])
+            .description("systemctl")
+            .build();
+
+        assert!(needs_flathub_remote(&[systemctl.clone(), install.clone()]));
+        assert!(!needs_flathub_remote(&[systemctl]));
+        assert!(!needs_flathub_remote(&[flatpak(&[
+            "uninstall",
+            "-y",
+            "app"
+        ])]));
+        // Already added by the sequence
+        assert!(!needs_flathub_remote(&[
+            flatpak(&["remote-add", "--if-not-exists", "flathub", "repo"]),
+            install,
+        ]));
+    }
+
+    #[test]
+    fn test_phase_ranges() {
+        let step = || flatpak(&["update"]);
+        let sequence = CommandSequence::new()
+            .then(step())
+            .phase("Dependencies")
+            .then(step())
+            .then(step())
+            .phase("Empty")
+            .phase("Installation")
+            .then(step());
+
+        assert_eq!(
+            phase_ranges(&sequence.phases, sequence.commands.len()),
+            vec![("Dependencies", 1..3), ("Installation", 3..4)]
+        );
+
+        // Phases of an appended sequence keep their steps
+        let sequence = CommandSequence::new().then(step()).extend(
+            CommandSequence::new()
+                .phase("Shell integration")
+                .then(step()),
+        );
+        assert_eq!(
+            phase_ranges(&sequence.phases, sequence.commands.len()),
+            vec![("Shell integration", 1..2)]
+        );
+    }
+}
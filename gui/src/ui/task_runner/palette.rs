@@ -0,0 +1,195 @@
+//! Colors of the command output for the current style.
+//!
+//! Every color keeps at least the WCAG AA contrast of 4.5:1 against the
+//! background of the output view, and 7:1 with high contrast.
+
+/// An sRGB color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    /// CSS notation, as text tags take it.
+    pub fn css(self) -> String {
+        format!("rgb({}, {}, {})", self.0, self.1, self.2)
+    }
+
+    /// Relative luminance, as defined by WCAG.
+    fn luminance(self) -> f64 {
+        let channel = |value: u8| {
+            let value = value as f64 / 255.0;
+            if value <= 0.04045 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * channel(self.0) + 0.7152 * channel(self.1) + 0.0722 * channel(self.2)
+    }
+}
+
+/// Contrast ratio of two colors, from 1 for equal ones to 21.
+pub fn contrast_ratio(a: Rgb, b: Rgb) -> f64 {
+    let (a, b) = (a.luminance(), b.luminance());
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// The style the output is shown in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    pub dark: bool,
+    pub high_contrast: bool,
+    /// Accent color of the system, used for headers when readable
+    pub accent: Option<Rgb>,
+}
+
+impl Style {
+    /// Contrast every color keeps against the background.
+    pub fn min_contrast(self) -> f64 {
+        if self.high_contrast {
+            7.0
+        } else {
+            4.5
+        }
+    }
+}
+
+/// A color for each kind of output, named after its text tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    /// Background of the output view the colors are chosen for
+    pub background: Rgb,
+    pub header: Rgb,
+    pub timestamp: Rgb,
+    pub stdout: Rgb,
+    pub stderr: Rgb,
+    pub error: Rgb,
+}
+
+impl Palette {
+    /// Text tags and their colors.
+    pub fn tags(&self) -> [(&'static str, Rgb); 5] {
+        [
+            ("header", self.header),
+            ("timestamp", self.timestamp),
+            ("stdout", self.stdout),
+            ("stderr", self.stderr),
+            ("error", self.error),
+        ]
+    }
+}
+
+/// Colors for `style`, from the Adwaita palette.
+pub fn palette(style: Style) -> Palette {
+    let mut palette = match (style.dark, style.high_contrast) {
+        (false, false) => Palette {
+            background: Rgb(0xff, 0xff, 0xff),
+            header: Rgb(0x1a, 0x5f, 0xb4),
+            timestamp: Rgb(0x6a, 0x6a, 0x6a),
+            stdout: Rgb(0x1b, 0x7a, 0x44),
+            stderr: Rgb(0xb3, 0x47, 0x00),
+            error: Rgb(0xc0, 0x1c, 0x28),
+        },
+        (true, false) => Palette {
+            background: Rgb(0x1e, 0x1e, 0x1e),
+            header: Rgb(0x78, 0xae, 0xed),
+            timestamp: Rgb(0x9a, 0x99, 0x96),
+            stdout: Rgb(0x57, 0xe3, 0x89),
+            stderr: Rgb(0xff, 0xa3, 0x48),
+            error: Rgb(0xff, 0x7b, 0x63),
+        },
+        (false, true) => Palette {
+            background: Rgb(0xff, 0xff, 0xff),
+            header: Rgb(0x0d, 0x3b, 0x75),
+            timestamp: Rgb(0x3d, 0x38, 0x46),
+            stdout: Rgb(0x0b, 0x5c, 0x2e),
+            stderr: Rgb(0x8a, 0x32, 0x00),
+            error: Rgb(0xa5, 0x1d, 0x2d),
+        },
+        (true, true) => Palette {
+            background: Rgb(0x1e, 0x1e, 0x1e),
+            header: Rgb(0x99, 0xc1, 0xf1),
+            timestamp: Rgb(0xde, 0xdd, 0xda),
+            stdout: Rgb(0x8f, 0xf0, 0xa4),
+            stderr: Rgb(0xff, 0xbe, 0x6f),
+            error: Rgb(0xff, 0xaa, 0xa4),
+        },
+    };
+
+    // Light accents like yellow are hard to read on a light background
+    if let Some(accent) = style.accent {
+        if contrast_ratio(accent, palette.background) >= style.min_contrast() {
+            palette.header = accent;
+        }
+    }
+    palette
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contrast_ratio() {
+        let black = Rgb(0, 0, 0);
+        let white = Rgb(0xff, 0xff, 0xff);
+        assert!((contrast_ratio(black, white) - 21.0).abs() < 0.01);
+        assert!((contrast_ratio(white, black) - 21.0).abs() < 0.01);
+        assert!((contrast_ratio(white, white) - 1.0).abs() < 0.01);
+        // The old stdout green on a light background
+        assert!(contrast_ratio(Rgb(46, 204, 113), white) < 3.0);
+    }
+
+    #[test]
+    fn test_palettes_are_readable() {
+        for dark in [false, true] {
+            for high_contrast in [false, true] {
+                let style = Style {
+                    dark,
+                    high_contrast,
+                    accent: None,
+                };
+                let palette = palette(style);
+                for (tag, color) in palette.tags() {
+                    let ratio = contrast_ratio(color, palette.background);
+                    assert!(
+                        ratio >= style.min_contrast(),
+                        "{} on {:?} has contrast {:.2}",
+                        tag,
+                        style,
+                        ratio
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_accent_header() {
+        let purple = Rgb(0x81, 0x3d, 0x9c);
+        let light = Style {
+            accent: Some(purple),
+            ..Style::default()
+        };
+        assert_eq!(palette(light).header, purple);
+
+        // Unreadable on dark, so the default header stays
+        let dark = Style {
+            dark: true,
+            ..light
+        };
+        assert_eq!(
+            palette(dark).header,
+            palette(Style {
+                dark: true,
+                ..Style::default()
+            })
+            .header
+        );
+
+        let yellow = Style {
+            accent: Some(Rgb(0xc8, 0x88, 0x00)),
+            ..Style::default()
+        };
+        assert_eq!(palette(yellow).header, palette(Style::default()).header);
+    }
+}
@@ -0,0 +1,102 @@
+//! Battery check before long operations, and logout and suspend
+//! inhibition while a task runs.
+
+use crate::config;
+use crate::core::hardware;
+use crate::i18n::{self, gettext};
+use adw::prelude::*;
+use gtk4::{gio, glib, Window};
+use log::{info, warn};
+use std::cell::Cell;
+
+thread_local! {
+    /// Cookie of the inhibitor held by the running task.
+    static INHIBIT_COOKIE: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+/// Keep the session from logging out and the system from suspending until
+/// [`release_inhibitor`] is called.
+///
+/// `reason` is shown by the desktop when something tries anyway, so the
+/// task title tells the user what is still running.
+pub(super) fn inhibit(window: &Window, reason: &str) {
+    // Never hold two, whatever happened to the previous run
+    release_inhibitor();
+
+    let Some(app) = gio::Application::default().and_downcast::<gtk4::Application>() else {
+        warn!("No application to inhibit logout and suspend with");
+        return;
+    };
+    let cookie = app.inhibit(
+        Some(window),
+        gtk4::ApplicationInhibitFlags::LOGOUT | gtk4::ApplicationInhibitFlags::SUSPEND,
+        Some(reason),
+    );
+    if cookie == 0 {
+        warn!("Could not inhibit logout and suspend for {:?}", reason);
+        return;
+    }
+    info!("Inhibiting logout and suspend for {:?}", reason);
+    INHIBIT_COOKIE.with(|current| current.set(Some(cookie)));
+}
+
+/// Release the inhibitor, if one is held.
+pub(super) fn release_inhibitor() {
+    let Some(cookie) = INHIBIT_COOKIE.with(Cell::take) else {
+        return;
+    };
+    if let Some(app) = gio::Application::default().and_downcast::<gtk4::Application>() {
+        app.uninhibit(cookie);
+        info!("Released logout and suspend inhibitor");
+    }
+}
+
+/// Ask before a long operation runs on a low battery, then call `on_done`
+/// with whether to go ahead.
+///
+/// Calls `on_done(true)` right away when the sequence is not long running,
+/// the warning is turned off or the battery is fine.
+pub(super) fn confirm_power<F>(parent: &Window, long_running: bool, on_done: F)
+where
+    F: FnOnce(bool) + 'static,
+{
+    let threshold = config::user::shared().get().tasks.battery_threshold;
+    let status = if long_running && threshold > 0 {
+        hardware::power_status()
+    } else {
+        None
+    };
+    let Some(percent) = status
+        .filter(|status| status.is_low(threshold))
+        .and_then(|status| status.battery_percent)
+    else {
+        on_done(true);
+        return;
+    };
+
+    warn!("Battery at {}% before a long operation", percent);
+    let dialog = adw::AlertDialog::new(
+        Some(&gettext("Battery Low")),
+        Some(&i18n::fill(
+            &gettext(
+                "Your battery is at {}%. This operation takes a while, and the system may not \
+                 start if the computer turns off midway. Plug in your charger before continuing.",
+            ),
+            &[&percent.to_string()],
+        )),
+    );
+    dialog.add_response("cancel", &gettext("Cancel"));
+    dialog.add_response("proceed", &gettext("Proceed Anyway"));
+    dialog.set_response_appearance("proceed", adw::ResponseAppearance::Destructive);
+    dialog.set_default_response(Some("cancel"));
+    dialog.set_close_response("cancel");
+
+    let parent = parent.clone();
+    glib::spawn_future_local(async move {
+        let proceed = dialog.choose_future(Some(&parent)).await == "proceed";
+        if proceed {
+            info!("Running on low battery at the user's request");
+        }
+        on_done(proceed);
+    });
+}
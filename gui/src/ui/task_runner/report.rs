@@ -0,0 +1,335 @@
+//! Machine-readable results of a task run.
+//!
+//! The executor fills a report as the steps run, the completion callback
+//! receives it and the command line prints it with `--json`. Times are Unix
+//! seconds and step durations milliseconds, so readers need no date parsing.
+
+use super::command::{Command, TaskStatus};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// What happened in a run, step by step.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    pub title: String,
+    /// Whether every step succeeded or was skipped
+    pub success: bool,
+    pub started: u64,
+    /// `None` while the run is going on
+    pub ended: Option<u64>,
+    pub steps: Vec<StepReport>,
+    /// Log file steps are recorded in once they start
+    #[serde(skip)]
+    log_path: PathBuf,
+}
+
+/// One step of a run.
+#[derive(Debug, Clone, Serialize)]
+pub struct StepReport {
+    pub description: String,
+    /// Program and arguments as run, `None` for file writes and steps that
+    /// never started
+    pub command: Option<Vec<String>>,
+    pub status: TaskStatus,
+    pub exit_code: Option<i32>,
+    pub duration_ms: Option<u64>,
+    /// Log file the step was recorded in, `None` if it never started
+    pub log_path: Option<PathBuf>,
+    #[serde(skip)]
+    started: Option<Instant>,
+}
+
+impl StepReport {
+    /// The command as a shell command line, quoting arguments where needed.
+    pub fn command_line(&self) -> Option<String> {
+        let command = self.command.as_ref()?;
+        Some(
+            command
+                .iter()
+                .map(|arg| quote(arg))
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+
+    fn pending(description: String) -> Self {
+        Self {
+            description,
+            command: None,
+            status: TaskStatus::Pending,
+            exit_code: None,
+            duration_ms: None,
+            log_path: None,
+            started: None,
+        }
+    }
+}
+
+/// Quote `arg` for the shell.
+fn quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-/:=,+@".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+impl RunReport {
+    /// A report with every one of `commands` pending, started now.
+    pub fn new(title: &str, commands: &[Command], log_path: PathBuf) -> Self {
+        Self {
+            title: title.to_string(),
+            success: false,
+            started: unix_now(),
+            ended: None,
+            steps: commands
+                .iter()
+                .map(|command| StepReport::pending(command.description.clone()))
+                .collect(),
+            log_path,
+        }
+    }
+
+    /// Set every step back to pending and start the run again now.
+    pub fn restart(&mut self) {
+        for step in &mut self.steps {
+            *step = StepReport::pending(std::mem::take(&mut step.description));
+        }
+        self.success = false;
+        self.started = unix_now();
+        self.ended = None;
+    }
+
+    /// Record the new status of step `index`, timing it from `Running` to
+    /// the status it ends with.
+    pub fn set_status(&mut self, index: usize, status: TaskStatus) {
+        let Some(step) = self.steps.get_mut(index) else {
+            return;
+        };
+        match status {
+            TaskStatus::Running => {
+                // Waiting for the package database already counts
+                if step.started.is_none() {
+                    step.started = Some(Instant::now());
+                    step.log_path = Some(self.log_path.clone());
+                }
+            }
+            TaskStatus::Pending => {}
+            _ => {
+                step.duration_ms = step
+                    .started
+                    .map(|started| started.elapsed().as_millis() as u64);
+            }
+        }
+        step.status = status;
+    }
+
+    /// Record the program and arguments step `index` runs.
+    pub fn set_command(&mut self, index: usize, program: &str, args: &[String]) {
+        if let Some(step) = self.steps.get_mut(index) {
+            let mut command = vec![program.to_string()];
+            command.extend(args.iter().cloned());
+            step.command = Some(command);
+        }
+    }
+
+    pub fn set_exit_code(&mut self, index: usize, exit_code: Option<i32>) {
+        if let Some(step) = self.steps.get_mut(index) {
+            step.exit_code = exit_code;
+        }
+    }
+
+    /// End the run. A step still running was interrupted and counts as
+    /// cancelled.
+    pub fn finish(&mut self, success: bool) {
+        for index in 0..self.steps.len() {
+            if self.steps[index].status == TaskStatus::Running {
+                self.set_status(index, TaskStatus::Cancelled);
+            }
+        }
+        self.success = success;
+        self.ended = Some(unix_now());
+    }
+
+    /// Process exit code for the run: 0 on success, else the exit code of
+    /// the failed step, or 1 when it has none.
+    pub fn exit_code(&self) -> i32 {
+        if self.success {
+            return 0;
+        }
+        self.steps
+            .iter()
+            .find(|step| step.status == TaskStatus::Failed)
+            .and_then(|step| step.exit_code)
+            .filter(|code| *code != 0)
+            .unwrap_or(1)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn step(description: &str) -> Command {
+        Command::builder()
+            .normal()
+            .program("true")
+            .description(description)
+            .build()
+    }
+
+    fn sample() -> RunReport {
+        RunReport::new(
+            "Update",
+            &[step("Sync"), step("Clean"), step("Upgrade"), step("Reboot")],
+            PathBuf::from("/tmp/app.log"),
+        )
+    }
+
+    #[test]
+    fn test_assembly() {
+        let mut report = sample();
+        report.set_status(0, TaskStatus::Running);
+        report.set_command(0, "pacman", &["-Sy".to_string()]);
+        report.set_status(0, TaskStatus::Success);
+        report.set_exit_code(0, Some(0));
+        report.set_status(1, TaskStatus::Skipped);
+        report.set_status(2, TaskStatus::Running);
+        report.set_command(2, "pacman", &["-Su".to_string()]);
+        report.set_status(2, TaskStatus::Failed);
+        report.set_exit_code(2, Some(8));
+        report.finish(false);
+
+        let steps = &report.steps;
+        assert_eq!(
+            steps[0].command.as_deref(),
+            Some(&["pacman".to_string(), "-Sy".to_string()][..])
+        );
+        assert!(steps[0].duration_ms.is_some());
+        assert_eq!(steps[0].log_path, Some(PathBuf::from("/tmp/app.log")));
+        // Skipped and never started steps have no command, time or log
+        for index in [1, 3] {
+            assert_eq!(steps[index].command, None);
+            assert_eq!(steps[index].duration_ms, None);
+            assert_eq!(steps[index].log_path, None);
+        }
+        assert_eq!(steps[1].status, TaskStatus::Skipped);
+        assert_eq!(steps[3].status, TaskStatus::Pending);
+        assert!(!report.success);
+        assert!(report.ended.is_some_and(|ended| ended >= report.started));
+        assert_eq!(report.exit_code(), 8);
+    }
+
+    #[test]
+    fn test_interrupted_step_is_cancelled() {
+        let mut report = sample();
+        report.set_status(0, TaskStatus::Running);
+        report.finish(false);
+        assert_eq!(report.steps[0].status, TaskStatus::Cancelled);
+        assert!(report.steps[0].duration_ms.is_some());
+        // Cancelled without a failing step
+        assert_eq!(report.exit_code(), 1);
+
+        // Statuses of steps out of range are ignored
+        report.set_status(9, TaskStatus::Running);
+        report.set_exit_code(9, Some(1));
+        assert_eq!(report.steps.len(), 4);
+
+        // Retried from the first step
+        report.restart();
+        assert_eq!(report.steps[0].status, TaskStatus::Pending);
+        assert_eq!(report.steps[0].description, "Sync");
+        assert_eq!(report.steps[0].duration_ms, None);
+        assert_eq!(report.ended, None);
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let mut report = sample();
+        report.set_status(0, TaskStatus::Failed);
+        report.set_exit_code(0, None);
+        report.finish(false);
+        // Killed by a signal
+        assert_eq!(report.exit_code(), 1);
+
+        let mut report = sample();
+        report.finish(true);
+        assert_eq!(report.exit_code(), 0);
+    }
+
+    #[test]
+    fn test_command_line() {
+        let mut report = sample();
+        report.set_command(
+            0,
+            "sh",
+            &[
+                "-c".to_string(),
+                "echo 'hi' > /tmp/out".to_string(),
+                String::new(),
+            ],
+        );
+        assert_eq!(
+            report.steps[0].command_line().as_deref(),
+            Some("sh -c 'echo '\\''hi'\\'' > /tmp/out' ''")
+        );
+        assert_eq!(report.steps[1].command_line(), None);
+    }
+
+    #[test]
+    fn test_json() {
+        let mut report = sample();
+        report.set_status(0, TaskStatus::Running);
+        report.set_command(0, "pacman", &["-Sy".to_string()]);
+        report.set_status(0, TaskStatus::Success);
+        report.set_exit_code(0, Some(0));
+        report.set_status(1, TaskStatus::Cancelled);
+        report.finish(false);
+
+        let value: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(value["title"], "Update");
+        assert_eq!(value["success"], false);
+        assert_eq!(value["started"], report.started);
+        assert_eq!(value["ended"], report.ended.unwrap());
+        assert_eq!(value["steps"].as_array().unwrap().len(), 4);
+
+        let first = &value["steps"][0];
+        assert_eq!(first["description"], "Sync");
+        assert_eq!(first["command"], json!(["pacman", "-Sy"]));
+        assert_eq!(first["status"], "success");
+        assert_eq!(first["exit_code"], 0);
+        assert!(first["duration_ms"].is_u64());
+        assert_eq!(first["log_path"], "/tmp/app.log");
+
+        assert_eq!(
+            value["steps"][1],
+            json!({
+                "description": "Clean",
+                "command": null,
+                "status": "cancelled",
+                "exit_code": null,
+                "duration_ms": null,
+                "log_path": null
+            })
+        );
+        assert_eq!(value["steps"][3]["status"], "pending");
+        // Internal state stays out
+        assert!(value.get("log_path").is_none());
+    }
+}
@@ -0,0 +1,267 @@
+//! Multi-line script steps.
+//!
+//! A script step's body is written verbatim to a private temporary file and
+//! run with bash, like a quoted here-doc: nothing in the body is expanded
+//! before bash reads it. Values such as the home directory are passed as
+//! variables defined at the top of the file instead of being formatted into
+//! the body.
+
+use super::command::Command;
+use log::warn;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Shell that runs script files.
+pub(super) const SCRIPT_SHELL: &str = "bash";
+
+/// Body and variables of a script step.
+#[derive(Clone, Debug)]
+pub struct Script {
+    body: String,
+    vars: Vec<(String, String)>,
+}
+
+impl Script {
+    pub(super) fn new(body: &str) -> Self {
+        Self {
+            body: body.to_string(),
+            vars: Vec::new(),
+        }
+    }
+
+    pub(super) fn var(&mut self, name: &str, value: &str) {
+        self.vars.push((name.to_string(), value.to_string()));
+    }
+
+    /// Whether a variable value contains `pattern`.
+    pub(super) fn mentions(&self, pattern: &str) -> bool {
+        self.vars.iter().any(|(_, value)| value.contains(pattern))
+    }
+
+    /// Replace `pattern` with `with` in every variable value.
+    pub(super) fn replace_in_vars(&mut self, pattern: &str, with: &str) {
+        for (_, value) in &mut self.vars {
+            *value = value.replace(pattern, with);
+        }
+    }
+
+    /// The file contents: `set -e`, one read-only definition per variable,
+    /// then the body.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable name is not a shell identifier or a
+    /// value contains a NUL byte.
+    pub(super) fn render(&self) -> Result<String, String> {
+        let mut script = String::from("set -e\n");
+        for (name, value) in &self.vars {
+            if !is_identifier(name) {
+                return Err(format!("Invalid script variable name: {:?}", name));
+            }
+            if value.contains('\0') {
+                return Err(format!("Script variable {} contains a NUL byte", name));
+            }
+            script.push_str(&format!(
+                "readonly {}='{}'\n",
+                name,
+                value.replace('\'', "'\\''")
+            ));
+        }
+        script.push_str(&self.body);
+        if !script.ends_with('\n') {
+            script.push('\n');
+        }
+        Ok(script)
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// A script written to disk, removed again on drop.
+#[derive(Debug)]
+pub(super) struct ScriptFile {
+    path: PathBuf,
+}
+
+impl ScriptFile {
+    /// Write `contents` to a new file only the user can access.
+    pub(super) fn create(contents: &str) -> std::io::Result<Self> {
+        Self::create_in(&script_dir(), contents)
+    }
+
+    fn create_in(dir: &Path, contents: &str) -> std::io::Result<Self> {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = dir.join(format!(
+            "xero-toolkit-script-{}-{}.sh",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::SeqCst)
+        ));
+        // Refuses to reuse an existing file or follow a planted symlink
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o700)
+            .open(&path)?;
+        let script = Self { path };
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        Ok(script)
+    }
+
+    pub(super) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ScriptFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove script {}: {}", self.path.display(), e);
+            }
+        }
+    }
+}
+
+/// The app's private runtime directory, or the system temporary directory.
+fn script_dir() -> PathBuf {
+    match crate::core::paths::runtime_dir() {
+        Ok(dir) => dir.to_path_buf(),
+        Err(e) => {
+            warn!(
+                "Runtime directory unavailable, using the temporary one: {}",
+                e
+            );
+            std::env::temp_dir()
+        }
+    }
+}
+
+/// `command` ready to run: script steps get their file written and passed
+/// to the shell. Keep the returned file alive until the step finishes.
+pub(super) fn prepare(command: &Command) -> Result<(Command, Option<ScriptFile>), String> {
+    let Some(script) = &command.script else {
+        return Ok((command.clone(), None));
+    };
+    let file = ScriptFile::create(&script.render()?)
+        .map_err(|e| format!("Failed to write script: {}", e))?;
+    let mut prepared = command.clone();
+    prepared.args = vec![file.path().to_string_lossy().to_string()];
+    Ok((prepared, Some(file)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn test_dir() -> PathBuf {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "xero-toolkit-script-test-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::SeqCst)
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_render_quotes_variables() {
+        let mut script = Script::new("echo \"$HOME_DIR\"");
+        script.var("HOME_DIR", "/home/it's me");
+        assert_eq!(
+            script.render().unwrap(),
+            "set -e\nreadonly HOME_DIR='/home/it'\\''s me'\necho \"$HOME_DIR\"\n"
+        );
+
+        let mut script = Script::new("true");
+        script.var("1ST", "value");
+        assert!(script.render().is_err());
+        let mut script = Script::new("true");
+        script.var("NAME; rm -rf", "value");
+        assert!(script.render().is_err());
+        let mut script = Script::new("true");
+        script.var("NAME", "a\0b");
+        assert!(script.render().is_err());
+    }
+
+    #[test]
+    fn test_file_is_private_and_removed_on_drop() {
+        let dir = test_dir();
+        let file = ScriptFile::create_in(&dir, "echo hi\n").unwrap();
+        let path = file.path().to_path_buf();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "echo hi\n");
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        drop(file);
+        assert!(!path.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_prepare_runs_the_file() {
+        let cmd = Command::script("Testing", "exit 3")
+            .var("NAME", "value")
+            .build();
+        let (prepared, file) = prepare(&cmd).unwrap();
+        let file = file.unwrap();
+        assert_eq!(prepared.program, SCRIPT_SHELL);
+        assert_eq!(
+            prepared.args,
+            vec![file.path().to_string_lossy().to_string()]
+        );
+        assert!(std::fs::read_to_string(file.path())
+            .unwrap()
+            .ends_with("readonly NAME='value'\nexit 3\n"));
+
+        let path = file.path().to_path_buf();
+        drop(file);
+        assert!(!path.exists());
+
+        // Plain commands pass through unchanged
+        let cmd = Command::builder()
+            .normal()
+            .program("true")
+            .description("Testing")
+            .build();
+        let (prepared, file) = prepare(&cmd).unwrap();
+        assert!(file.is_none());
+        assert!(prepared.args.is_empty());
+    }
+
+    #[test]
+    fn test_file_is_removed_after_failing_run() {
+        let dir = test_dir();
+        let out = dir.join("out");
+        let mut script =
+            Script::new("echo \"$MESSAGE\" > \"$OUT\"\nfalse\necho unreachable >> \"$OUT\"");
+        script.var("MESSAGE", "$(not expanded)");
+        script.var("OUT", &out.to_string_lossy());
+
+        let file = ScriptFile::create_in(&dir, &script.render().unwrap()).unwrap();
+        let path = file.path().to_path_buf();
+        let status = std::process::Command::new(SCRIPT_SHELL)
+            .arg(&path)
+            .status()
+            .unwrap();
+        drop(file);
+
+        // `set -e` stops at the first failing line
+        assert_eq!(status.code(), Some(1));
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "$(not expanded)\n");
+        assert!(!path.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
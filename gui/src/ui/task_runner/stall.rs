@@ -0,0 +1,250 @@
+//! Detection of steps stuck on a prompt nobody can answer.
+//!
+//! Steps run with stdin on /dev/null, so most prompts read end of file and
+//! fail right away. A program that reads the terminal itself, or a stdin
+//! handed down by a wrapper, still blocks, and the step then hangs without
+//! output. Once a step has been silent for [`SILENCE`], the processes of its
+//! group are looked up in /proc to tell such a prompt from a slow step.
+//!
+//! Privileged steps run in the daemon, as root, where /proc hides what they
+//! wait for, so only steps running as the user are checked.
+
+use std::path::Path;
+use std::time::Duration;
+
+/// How long a step stays silent before its processes are checked.
+pub const SILENCE: Duration = Duration::from_secs(20);
+
+/// Number of the `read` system call.
+#[cfg(target_arch = "x86_64")]
+const READ_SYSCALL: i64 = 0;
+#[cfg(target_arch = "aarch64")]
+const READ_SYSCALL: i64 = 63;
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const READ_SYSCALL: i64 = -1;
+
+/// What a process is doing, as /proc shows it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Process {
+    pub pid: u32,
+    /// State letter, like `S` for sleeping or `T` for stopped
+    pub state: char,
+    /// Kernel function it sleeps in, like `n_tty_read`
+    pub wchan: String,
+    /// System call it is blocked in and its first argument
+    pub syscall: Option<(i64, u64)>,
+    /// Target of its stdin, like `pipe:[1234]`, `None` if closed
+    pub stdin: Option<String>,
+    /// Targets of all its file descriptors
+    pub fds: Vec<String>,
+}
+
+/// State letter and process group from the contents of /proc/<pid>/stat.
+fn parse_stat(content: &str) -> Option<(char, u32)> {
+    // The command name may hold spaces and parentheses itself
+    let (_, rest) = content.rsplit_once(')')?;
+    let mut fields = rest.split_whitespace();
+    let state = fields.next()?.chars().next()?;
+    let _parent = fields.next()?;
+    let group = fields.next()?.parse().ok()?;
+    Some((state, group))
+}
+
+/// System call number and first argument from /proc/<pid>/syscall, `None`
+/// while the process runs.
+fn parse_syscall(content: &str) -> Option<(i64, u64)> {
+    let mut fields = content.split_whitespace();
+    let number = fields.next()?.parse().ok()?;
+    let argument = u64::from_str_radix(fields.next()?.strip_prefix("0x")?, 16).ok()?;
+    Some((number, argument))
+}
+
+/// Read process `pid`, if it belongs to process group `group`.
+fn read_process(pid: u32, group: u32) -> Option<Process> {
+    let dir = Path::new("/proc").join(pid.to_string());
+    let (state, process_group) = parse_stat(&std::fs::read_to_string(dir.join("stat")).ok()?)?;
+    if process_group != group {
+        return None;
+    }
+
+    let fds: Vec<(u32, String)> = std::fs::read_dir(dir.join("fd"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let fd = entry.file_name().to_str()?.parse().ok()?;
+            let target = std::fs::read_link(entry.path()).ok()?;
+            Some((fd, target.to_string_lossy().into_owned()))
+        })
+        .collect();
+
+    Some(Process {
+        pid,
+        state,
+        wchan: std::fs::read_to_string(dir.join("wchan")).unwrap_or_default(),
+        syscall: std::fs::read_to_string(dir.join("syscall"))
+            .ok()
+            .and_then(|content| parse_syscall(&content)),
+        stdin: fds
+            .iter()
+            .find(|(fd, _)| *fd == 0)
+            .map(|(_, target)| target.clone()),
+        fds: fds.into_iter().map(|(_, target)| target).collect(),
+    })
+}
+
+/// The processes of process group `group`.
+pub fn read_group(group: u32) -> Vec<Process> {
+    std::fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+        .filter_map(|pid| read_process(pid, group))
+        .collect()
+}
+
+/// Whether a process among `processes`, the whole group of a step, waits
+/// for input.
+///
+/// That is one stopped for reading the terminal from the background, one
+/// reading a terminal, or one reading a stdin no other process of the group
+/// writes to. Readers at the end of a pipeline inside the step wait for
+/// their writers and don't count.
+pub fn waiting_for_input(processes: &[Process]) -> bool {
+    processes.iter().any(|process| match process.state {
+        'T' => true,
+        'S' if process.wchan == "n_tty_read" => true,
+        'S' if process.syscall == Some((READ_SYSCALL, 0)) => {
+            let Some(stdin) = &process.stdin else {
+                return false;
+            };
+            if !stdin.starts_with("pipe:") {
+                return stdin != "/dev/null";
+            }
+            !processes
+                .iter()
+                .filter(|other| other.pid != process.pid)
+                .any(|other| other.fds.contains(stdin))
+        }
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::CommandExt;
+    use std::process::{Child, Stdio};
+    use std::time::Instant;
+
+    #[test]
+    fn test_parse_stat() {
+        assert_eq!(
+            parse_stat("4242 (paru) S 4200 4242 4242 0 -1 4194560 ..."),
+            Some(('S', 4242))
+        );
+        assert_eq!(parse_stat("77 (a (b) c) T 1 70 70 0 -1"), Some(('T', 70)));
+        assert_eq!(parse_stat("garbage"), None);
+    }
+
+    #[test]
+    fn test_parse_syscall() {
+        assert_eq!(
+            parse_syscall("0 0x0 0x7fc21d19a000 0x20000 0x0 0x0 0x0 0x7ffe 0x7fc2\n"),
+            Some((0, 0))
+        );
+        assert_eq!(parse_syscall("230 0x3 0x0"), Some((230, 3)));
+        assert_eq!(parse_syscall("running\n"), None);
+    }
+
+    fn process(pid: u32, state: char, wchan: &str, syscall: Option<(i64, u64)>) -> Process {
+        Process {
+            pid,
+            state,
+            wchan: wchan.to_string(),
+            syscall,
+            stdin: Some("/dev/null".to_string()),
+            fds: vec!["/dev/null".to_string(), "pipe:[10]".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_waiting_for_input() {
+        let read = Some((READ_SYSCALL, 0));
+        let busy = process(1, 'S', "do_epoll_wait", Some((232, 4)));
+        assert!(!waiting_for_input(std::slice::from_ref(&busy)));
+        assert!(!waiting_for_input(&[]));
+
+        // Stopped for reading the terminal from the background group
+        assert!(waiting_for_input(&[
+            busy.clone(),
+            process(2, 'T', "", None)
+        ]));
+        assert!(waiting_for_input(&[process(2, 'S', "n_tty_read", None)]));
+
+        // Reading a terminal passed as stdin
+        let mut prompt = process(2, 'S', "wait_woken", read);
+        prompt.stdin = Some("/dev/pts/3".to_string());
+        assert!(waiting_for_input(&[busy.clone(), prompt]));
+
+        // Reading a pipe nobody in the group writes to
+        let mut reader = process(2, 'S', "anon_pipe_read", read);
+        reader.stdin = Some("pipe:[20]".to_string());
+        reader.fds = vec!["pipe:[20]".to_string()];
+        assert!(waiting_for_input(&[busy.clone(), reader.clone()]));
+        // The end of a pipeline, like `rate-mirrors | tee`
+        let mut writer = busy.clone();
+        writer.fds[1] = "pipe:[20]".to_string();
+        assert!(!waiting_for_input(&[writer, reader]));
+    }
+
+    /// Spawn `script` in a new process group, with `stdin`.
+    fn spawn(script: &str, stdin: Stdio) -> Child {
+        std::process::Command::new("sh")
+            .args(["-c", script])
+            .stdin(stdin)
+            .stdout(Stdio::null())
+            .process_group(0)
+            .spawn()
+            .unwrap()
+    }
+
+    /// Whether the group of `child` is seen waiting for input within a few
+    /// seconds.
+    fn seen_waiting(child: &Child) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(3);
+        while Instant::now() < deadline {
+            if waiting_for_input(&read_group(child.id())) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        false
+    }
+
+    fn stop(mut child: Child) {
+        unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+        let _ = child.wait();
+    }
+
+    #[test]
+    fn test_blocking_child() {
+        // A prompt on a stdin nothing writes to, held open by the test
+        let child = spawn("read answer", Stdio::piped());
+        assert!(seen_waiting(&child));
+        stop(child);
+
+        // Without stdin the prompt fails right away
+        let mut child = spawn("read answer", Stdio::null());
+        assert!(!child.wait().unwrap().success());
+
+        // Busy, and waiting inside its own pipeline
+        let child = spawn("sleep 30", Stdio::null());
+        assert!(!seen_waiting(&child));
+        stop(child);
+        let child = spawn("sleep 30 | cat", Stdio::null());
+        assert!(!seen_waiting(&child));
+        stop(child);
+    }
+}
@@ -0,0 +1,135 @@
+//! Summary of the privileged steps shown before the password prompt.
+
+use super::command::{Command, CommandType};
+use super::executor::preview_command;
+use crate::i18n::gettext;
+use adw::prelude::*;
+use gtk4::{glib, Label, Orientation, Window};
+use log::info;
+
+/// Longest height of the step list before it scrolls.
+const MAX_LIST_HEIGHT: i32 = 320;
+
+/// A step that runs with elevated privileges.
+pub(super) struct PrivilegedStep {
+    description: String,
+    command_line: String,
+}
+
+/// The privileged and AUR steps of `commands` with their command lines.
+pub(super) fn privileged_steps(commands: &[Command]) -> Vec<PrivilegedStep> {
+    commands
+        .iter()
+        .filter(|cmd| matches!(cmd.command_type, CommandType::Privileged | CommandType::Aur))
+        .map(|cmd| PrivilegedStep {
+            description: cmd.description.clone(),
+            command_line: match (&cmd.write, &cmd.script, preview_command(cmd)) {
+                (Some(write), _, _) => format!("write {}", write.path),
+                // Scripts show what bash will read
+                (None, Some(script), _) => script.render().unwrap_or_else(|err| err),
+                (None, None, Ok((program, args))) => shell_join(&program, &args),
+                (None, None, Err(err)) => err,
+            },
+        })
+        .collect()
+}
+
+/// Join a command line, quoting arguments the shell would split or expand.
+fn shell_join(program: &str, args: &[String]) -> String {
+    std::iter::once(program)
+        .chain(args.iter().map(String::as_str))
+        .map(|arg| {
+            let plain = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+            if plain {
+                arg.to_string()
+            } else {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn step_list(steps: &[PrivilegedStep]) -> gtk4::ScrolledWindow {
+    let list = gtk4::Box::new(Orientation::Vertical, 12);
+    for step in steps {
+        let row = gtk4::Box::new(Orientation::Vertical, 4);
+
+        let description = Label::new(Some(&step.description));
+        description.set_xalign(0.0);
+        description.set_wrap(true);
+        description.add_css_class("heading");
+        row.append(&description);
+
+        let command_line = Label::new(Some(&step.command_line));
+        command_line.set_xalign(0.0);
+        command_line.set_wrap(true);
+        command_line.set_wrap_mode(gtk4::pango::WrapMode::WordChar);
+        command_line.set_selectable(true);
+        command_line.add_css_class("monospace");
+        command_line.add_css_class("dim-label");
+        row.append(&command_line);
+
+        list.append(&row);
+    }
+
+    let scrolled = gtk4::ScrolledWindow::new();
+    scrolled.set_hscrollbar_policy(gtk4::PolicyType::Never);
+    scrolled.set_propagate_natural_height(true);
+    scrolled.set_max_content_height(MAX_LIST_HEIGHT);
+    scrolled.set_child(Some(&list));
+    scrolled
+}
+
+/// List the privileged steps and ask before authenticating.
+///
+/// `on_response` receives `true` when the user chose to authenticate.
+pub(super) fn show_auth_summary<F>(parent: &Window, steps: &[PrivilegedStep], on_response: F)
+where
+    F: FnOnce(bool) + 'static,
+{
+    info!(
+        "Showing authentication summary for {} privileged steps",
+        steps.len()
+    );
+
+    let dialog = adw::AlertDialog::new(
+        Some(&gettext("Authentication Required")),
+        Some(&gettext(
+            "These steps run with administrator rights. Check them before entering your password.",
+        )),
+    );
+    dialog.set_extra_child(Some(&step_list(steps)));
+    dialog.add_response("cancel", &gettext("Cancel"));
+    dialog.add_response("authenticate", &gettext("Authenticate & Run"));
+    dialog.set_response_appearance("authenticate", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("authenticate"));
+    dialog.set_close_response("cancel");
+
+    let parent = parent.clone();
+    glib::spawn_future_local(async move {
+        let response = dialog.choose_future(Some(&parent)).await;
+        on_response(response == "authenticate");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_join() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            shell_join("/usr/bin/pacman", &args(&["-S", "--needed", "paru"])),
+            "/usr/bin/pacman -S --needed paru"
+        );
+        assert_eq!(
+            shell_join("/usr/bin/sh", &args(&["-c", "echo 'hi' && true", ""])),
+            "/usr/bin/sh -c 'echo '\\''hi'\\'' && true' ''"
+        );
+    }
+}
@@ -3,28 +3,52 @@
 //! This module provides the UI components for displaying command execution progress,
 //! including task items, status icons, and scroll management.
 
-use super::command::TaskStatus;
+use super::command::{Command, TaskStatus};
+use super::palette;
+use super::report::RunReport;
+use super::CommandSequence;
+use crate::core::events::{self, Event};
+use crate::i18n::{fill, gettext, ngettext};
+use crate::ui::accessibility;
 use adw::prelude::*;
+use gtk4::accessible::{Property, Relation, State};
+use gtk4::{gdk, gio};
 use gtk4::{
-    Box as GtkBox, Button, Image, Label, Revealer, ScrolledWindow, TextBuffer, TextView,
-    ToggleButton, Window,
+    AccessibleAnnouncementPriority, Box as GtkBox, Button, GestureClick, Image, Label, PopoverMenu,
+    Revealer, ScrolledWindow, Separator, TextBuffer, TextTagTable, TextView, ToggleButton, Window,
 };
+use std::cell::{Cell, RefCell};
+use std::ops::Range;
+use std::rc::Rc;
+use std::time::Instant;
+
+/// Callback invoked once with the report of a finished run.
+pub type CompletionCallback = Box<dyn FnOnce(&RunReport)>;
 
 /// Container for all task runner dialog widgets.
 pub struct TaskRunnerWidgets {
     pub window: Window,
     pub title_label: Label,
-    #[allow(dead_code)]
-    // Stored for potential future use, currently only used during initialization
     pub task_list_container: GtkBox,
     pub scrolled_window: ScrolledWindow,
     pub cancel_button: Button,
     pub close_button: Button,
-    pub task_items: Vec<TaskItem>,
+    /// Shown when authentication failed in a way worth another attempt
+    pub retry_button: Button,
+    pub task_list: TaskList,
     pub sidebar_toggle: ToggleButton,
     pub sidebar_revealer: Revealer,
     pub output_text_view: TextView,
     pub output_text_buffer: TextBuffer,
+    on_complete: RefCell<Option<CompletionCallback>>,
+    /// Steps of the run so far, passed to the completion callback
+    report: RefCell<RunReport>,
+    /// Where each step's output lies in the output buffer
+    step_output: RefCell<StepOutput>,
+    /// When the dialog opened, for the duration of the run
+    started: Instant,
+    /// Whether the end of the run was announced
+    finished: Cell<bool>,
 }
 
 impl TaskRunnerWidgets {
@@ -37,11 +61,13 @@ impl TaskRunnerWidgets {
         scrolled_window: ScrolledWindow,
         cancel_button: Button,
         close_button: Button,
-        task_items: Vec<TaskItem>,
+        retry_button: Button,
+        task_list: TaskList,
         sidebar_toggle: ToggleButton,
         sidebar_revealer: Revealer,
         output_text_view: TextView,
         output_text_buffer: TextBuffer,
+        report: RunReport,
     ) -> Self {
         let widgets = Self {
             window,
@@ -50,11 +76,17 @@ impl TaskRunnerWidgets {
             scrolled_window,
             cancel_button,
             close_button,
-            task_items,
+            retry_button,
+            task_list,
             sidebar_toggle,
             sidebar_revealer,
             output_text_view,
             output_text_buffer,
+            on_complete: RefCell::new(None),
+            report: RefCell::new(report),
+            step_output: RefCell::new(StepOutput::default()),
+            started: Instant::now(),
+            finished: Cell::new(false),
         };
 
         // Set up color tags for output
@@ -63,38 +95,41 @@ impl TaskRunnerWidgets {
         widgets
     }
 
-    /// Set up color tags for styled output.
+    /// Set up color tags for styled output, following the style of the
+    /// system while the dialog is open.
     fn setup_color_tags(&self) {
         use gtk4::TextTag;
 
         let tag_table = self.output_text_buffer.tag_table();
-
-        // Header tag (blue)
-        let header_tag = TextTag::new(Some("header"));
-        header_tag.set_property("foreground", "rgb(100, 149, 237)");
-        header_tag.set_property("weight", 700); // Bold
-        tag_table.add(&header_tag);
-
-        // Timestamp tag (gray)
-        let timestamp_tag = TextTag::new(Some("timestamp"));
-        timestamp_tag.set_property("foreground", "rgb(128, 128, 128)");
-        tag_table.add(&timestamp_tag);
-
-        // Stdout tag (green)
-        let stdout_tag = TextTag::new(Some("stdout"));
-        stdout_tag.set_property("foreground", "rgb(46, 204, 113)");
-        tag_table.add(&stdout_tag);
-
-        // Stderr tag (orange/red)
-        let stderr_tag = TextTag::new(Some("stderr"));
-        stderr_tag.set_property("foreground", "rgb(255, 140, 0)");
-        tag_table.add(&stderr_tag);
-
-        // Error tag (red)
-        let error_tag = TextTag::new(Some("error"));
-        error_tag.set_property("foreground", "rgb(231, 76, 60)");
-        error_tag.set_property("weight", 700);
-        tag_table.add(&error_tag);
+        for (name, _) in palette::palette(palette::Style::default()).tags() {
+            let tag = TextTag::new(Some(name));
+            if matches!(name, "header" | "error") {
+                tag.set_weight(700); // Bold
+            }
+            tag_table.add(&tag);
+        }
+        apply_palette(&tag_table);
+
+        // Re-applied when the style changes in the middle of a run
+        let reapply = |tag_table: &TextTagTable| {
+            let tag_table = tag_table.downgrade();
+            move |_: &adw::StyleManager| {
+                if let Some(tag_table) = tag_table.upgrade() {
+                    apply_palette(&tag_table);
+                }
+            }
+        };
+        let style_manager = adw::StyleManager::default();
+        let handlers = RefCell::new(vec![
+            style_manager.connect_dark_notify(reapply(&tag_table)),
+            style_manager.connect_high_contrast_notify(reapply(&tag_table)),
+            style_manager.connect_accent_color_notify(reapply(&tag_table)),
+        ]);
+        self.window.connect_destroy(move |_| {
+            for id in handlers.take() {
+                adw::StyleManager::default().disconnect(id);
+            }
+        });
     }
 
     /// Bind the sidebar toggle button to the revealer.
@@ -113,11 +148,11 @@ impl TaskRunnerWidgets {
             .connect_reveal_child_notify(move |revealer| {
                 let is_revealed = revealer.reveals_child();
                 let tooltip = if is_revealed {
-                    "Hide command output"
+                    gettext("Hide command output")
                 } else {
-                    "Show command output"
+                    gettext("Show command output")
                 };
-                toggle.set_tooltip_text(Some(tooltip));
+                toggle.set_tooltip_text(Some(&tooltip));
 
                 // Only allow targeting when revealed so it doesn't block header bar when hidden
                 revealer_clone.set_can_target(is_revealed);
@@ -125,11 +160,234 @@ impl TaskRunnerWidgets {
     }
 }
 
+/// Where the output of each step lies in the output buffer, in characters
+/// as the buffer counts them.
+///
+/// Output is only ever appended, so a range stays valid once recorded. A
+/// step started again, like on retry, is recorded anew.
+#[derive(Debug, Default)]
+struct StepOutput {
+    ranges: Vec<Option<Range<i32>>>,
+    /// Step whose output is still being appended
+    running: Option<usize>,
+}
+
+impl StepOutput {
+    /// Start the output of step `index` at `offset`, unless it already runs.
+    fn start(&mut self, index: usize, offset: i32) {
+        if self.running == Some(index) {
+            return;
+        }
+        if self.ranges.len() <= index {
+            self.ranges.resize(index + 1, None);
+        }
+        self.ranges[index] = Some(offset..offset);
+        self.running = Some(index);
+    }
+
+    /// End the output of step `index` at `offset`.
+    fn end(&mut self, index: usize, offset: i32) {
+        if self.running != Some(index) {
+            return;
+        }
+        self.running = None;
+        if let Some(Some(range)) = self.ranges.get_mut(index) {
+            range.end = offset;
+        }
+    }
+
+    /// Output of step `index`, up to `buffer_end` while it still runs.
+    fn range(&self, index: usize, buffer_end: i32) -> Option<Range<i32>> {
+        let range = self.ranges.get(index)?.clone()?;
+        if self.running == Some(index) {
+            return Some(range.start..buffer_end);
+        }
+        Some(range)
+    }
+
+    fn clear(&mut self) {
+        self.ranges.clear();
+        self.running = None;
+    }
+}
+
+/// Rows of the task list, with the phase each command belongs to.
+pub struct TaskList {
+    items: Vec<TaskItem>,
+    phases: Vec<PhaseGroup>,
+    /// Index into `phases` for each command, if it is part of one
+    item_phases: Vec<Option<usize>>,
+}
+
+impl TaskList {
+    /// Append a pending row for each of `commands` to `container`, grouped
+    /// under a header for each of `phases`.
+    pub fn new(container: &GtkBox, commands: &[Command], phases: &[(&str, Range<usize>)]) -> Self {
+        let mut items = Vec::new();
+        let mut groups: Vec<PhaseGroup> = Vec::new();
+        let mut item_phases = Vec::new();
+
+        for (index, cmd) in commands.iter().enumerate() {
+            let starts_phase = match phases.iter().find(|(_, steps)| steps.start == index) {
+                Some((name, steps)) => {
+                    if index > 0 {
+                        container.append(&Separator::new(gtk4::Orientation::Horizontal));
+                    }
+                    let group = PhaseGroup::new(name, steps.clone());
+                    container.append(&group.header);
+                    container.append(&group.revealer);
+                    groups.push(group);
+                    true
+                }
+                None => false,
+            };
+
+            let phase = groups
+                .len()
+                .checked_sub(1)
+                .filter(|&last| groups[last].steps.contains(&index));
+            let parent = phase.map_or(container, |last| &groups[last].steps_box);
+            if index > 0 && !starts_phase {
+                parent.append(&Separator::new(gtk4::Orientation::Horizontal));
+            }
+
+            let task_item = TaskItem::new(&cmd.description);
+            task_item.set_status(TaskStatus::Pending);
+            parent.append(&task_item.container);
+            items.push(task_item);
+            item_phases.push(phase);
+        }
+
+        Self {
+            items,
+            phases: groups,
+            item_phases,
+        }
+    }
+
+    /// The phase `index` belongs to.
+    fn phase_of(&self, index: usize) -> Option<&PhaseGroup> {
+        self.item_phases
+            .get(index)
+            .copied()
+            .flatten()
+            .map(|phase| &self.phases[phase])
+    }
+
+    /// The visible row of `index`, the phase header once its phase collapsed.
+    fn row(&self, index: usize) -> Option<&GtkBox> {
+        let item = self.items.get(index)?;
+        match self.phase_of(index) {
+            Some(phase) if !phase.revealer.reveals_child() => Some(&phase.header),
+            _ => Some(&item.container),
+        }
+    }
+
+    /// Collapse the phase of `index` if every one of its steps succeeded.
+    fn collapse_finished_phase(&self, index: usize) {
+        let Some(phase) = self.phase_of(index) else {
+            return;
+        };
+        let finished = self.items[phase.steps.clone()]
+            .iter()
+            .all(|item| matches!(item.status(), TaskStatus::Success | TaskStatus::Skipped));
+        if finished {
+            phase.collapse();
+        }
+    }
+}
+
+/// Header row and steps of a phase, collapsed into the header once all
+/// steps succeeded.
+struct PhaseGroup {
+    name: String,
+    steps: Range<usize>,
+    header: GtkBox,
+    summary_label: Label,
+    status_icon: Image,
+    expand_button: ToggleButton,
+    revealer: Revealer,
+    steps_box: GtkBox,
+}
+
+impl PhaseGroup {
+    fn new(name: &str, steps: Range<usize>) -> Self {
+        let header = GtkBox::new(gtk4::Orientation::Horizontal, 12);
+        header.set_margin_start(12);
+        header.set_margin_end(12);
+
+        let label = Label::new(Some(name));
+        label.set_xalign(0.0);
+        label.add_css_class("heading");
+        header.update_relation(&[Relation::LabelledBy(&[label.upcast_ref()])]);
+
+        let summary_label = Label::new(None);
+        summary_label.set_xalign(0.0);
+        summary_label.set_hexpand(true);
+        summary_label.add_css_class("dim-label");
+        summary_label.set_visible(false);
+
+        let status_icon = Image::from_icon_name("circle-check");
+        status_icon.set_pixel_size(24);
+        status_icon.update_property(&[Property::Label(&status_name(&TaskStatus::Success))]);
+        status_icon.set_visible(false);
+
+        // Only offered once the phase collapsed
+        let expand_button = ToggleButton::new();
+        expand_button.set_icon_name("pan-down-symbolic");
+        expand_button.set_tooltip_text(Some(&gettext("Show steps")));
+        expand_button.add_css_class("flat");
+        expand_button.set_valign(gtk4::Align::Center);
+        expand_button.set_active(true);
+        expand_button.set_visible(false);
+
+        header.append(&label);
+        header.append(&summary_label);
+        header.append(&status_icon);
+        header.append(&expand_button);
+
+        let steps_box = GtkBox::new(gtk4::Orientation::Vertical, 12);
+        let revealer = Revealer::new();
+        revealer.set_child(Some(&steps_box));
+        revealer.set_reveal_child(true);
+        expand_button
+            .bind_property("active", &revealer, "reveal-child")
+            .bidirectional()
+            .build();
+
+        Self {
+            name: name.to_string(),
+            steps,
+            header,
+            summary_label,
+            status_icon,
+            expand_button,
+            revealer,
+            steps_box,
+        }
+    }
+
+    /// Hide the steps behind a summary that can be expanded again.
+    fn collapse(&self) {
+        let count = self.steps.len() as u32;
+        self.summary_label.set_text(&fill(
+            &ngettext("{} step completed", "{} steps completed", count),
+            &[&count.to_string()],
+        ));
+        self.summary_label.set_visible(true);
+        self.status_icon.set_visible(true);
+        self.expand_button.set_visible(true);
+        self.expand_button.set_active(false);
+    }
+}
+
 /// A single task item in the task list.
 pub struct TaskItem {
     pub container: GtkBox,
     pub status_icon: Image,
     pub spinner_icon: Image,
+    description: String,
+    status: RefCell<TaskStatus>,
 }
 
 impl TaskItem {
@@ -145,13 +403,19 @@ impl TaskItem {
         label.set_xalign(0.0);
         label.set_hexpand(true);
         label.set_wrap(true);
+        container.update_relation(&[Relation::LabelledBy(&[label.upcast_ref()])]);
 
-        // Spinner icon for running state
+        // Spinner icon for running state, a static icon when motion is reduced
         let spinner_icon = Image::new();
-        spinner_icon.set_icon_name(Some("circle-noth-symbolic"));
         spinner_icon.set_pixel_size(24);
         spinner_icon.set_visible(false);
-        spinner_icon.add_css_class("spinning");
+        if accessibility::reduced_motion() {
+            spinner_icon.set_icon_name(Some("circle-play-symbolic"));
+        } else {
+            spinner_icon.set_icon_name(Some("circle-noth-symbolic"));
+            spinner_icon.add_css_class("spinning");
+        }
+        spinner_icon.update_property(&[Property::Label(&status_name(&TaskStatus::Running))]);
 
         // Status icon for success/failure
         let status_icon = Image::new();
@@ -166,11 +430,24 @@ impl TaskItem {
             container,
             status_icon,
             spinner_icon,
+            description: description.to_string(),
+            status: RefCell::new(TaskStatus::Pending),
         }
     }
 
+    /// The current status of this task item.
+    pub fn status(&self) -> TaskStatus {
+        self.status.borrow().clone()
+    }
+
     /// Update the status of this task item.
     pub fn set_status(&self, status: TaskStatus) {
+        self.status.replace(status.clone());
+        self.container
+            .update_state(&[State::Busy(status == TaskStatus::Running)]);
+        self.status_icon
+            .update_property(&[Property::Label(&status_name(&status))]);
+
         match status {
             TaskStatus::Pending => {
                 self.spinner_icon.set_visible(false);
@@ -195,30 +472,55 @@ impl TaskItem {
                 self.status_icon.set_icon_name(Some("circle-stop"));
                 self.status_icon.set_visible(true);
             }
+            TaskStatus::Skipped => {
+                self.spinner_icon.set_visible(false);
+                self.status_icon.set_icon_name(Some("arrow-right-symbolic"));
+                self.status_icon
+                    .set_tooltip_text(Some(&status_name(&TaskStatus::Skipped)));
+                self.status_icon.add_css_class("dim-label");
+                self.status_icon.set_visible(true);
+            }
         }
     }
 }
 
+/// Translated status name read out by screen readers.
+fn status_name(status: &TaskStatus) -> String {
+    match status {
+        TaskStatus::Pending => gettext("Pending"),
+        TaskStatus::Running => gettext("Running"),
+        TaskStatus::Success => gettext("Completed"),
+        TaskStatus::Failed => gettext("Failed"),
+        TaskStatus::Cancelled => gettext("Cancelled"),
+        TaskStatus::Skipped => gettext("Skipped"),
+    }
+}
+
 impl TaskRunnerWidgets {
     /// Scroll to a specific task in the list (only if outside visible area).
     fn scroll_to_task(&self, index: usize) {
-        if self.task_items.get(index).is_none() {
+        let Some(row) = self.task_list.row(index) else {
             return;
-        }
+        };
 
         let vadjustment = self.scrolled_window.vadjustment();
         let viewport_top = vadjustment.value();
         let viewport_bottom = viewport_top + vadjustment.page_size();
 
-        let total_tasks = self.task_items.len() as f64;
-        if total_tasks == 0.0 {
-            return;
-        }
-
-        let content_height = vadjustment.upper();
-        let estimated_task_height = content_height / total_tasks;
-        let task_top = (index as f64) * estimated_task_height;
-        let task_bottom = task_top + estimated_task_height;
+        // Rows are placed by phase headers and collapsed phases, so use
+        // where the row actually is once it has been laid out
+        let (task_top, task_bottom) = match row.compute_bounds(&self.task_list_container) {
+            Some(bounds) => (
+                f64::from(bounds.y()),
+                f64::from(bounds.y() + bounds.height()),
+            ),
+            None => {
+                let total_tasks = self.task_list.items.len() as f64;
+                let estimated_task_height = vadjustment.upper() / total_tasks;
+                let task_top = (index as f64) * estimated_task_height;
+                (task_top, task_top + estimated_task_height)
+            }
+        };
 
         if task_bottom > viewport_bottom {
             let target = (task_bottom - vadjustment.page_size())
@@ -231,13 +533,35 @@ impl TaskRunnerWidgets {
     }
 
     /// Update the status of a specific task.
+    ///
+    /// A phase collapses into its header once all of its steps succeeded.
     pub fn update_task_status(&self, index: usize, status: TaskStatus) {
-        if let Some(task_item) = self.task_items.get(index) {
+        self.report.borrow_mut().set_status(index, status.clone());
+        let offset = self.output_text_buffer.end_iter().offset();
+        match status {
+            TaskStatus::Running => self.step_output.borrow_mut().start(index, offset),
+            TaskStatus::Pending => {}
+            _ => self.step_output.borrow_mut().end(index, offset),
+        }
+        if let Some(task_item) = self.task_list.items.get(index) {
+            // "Failed: Install packages"
+            let message = fill(
+                &gettext("{}: {}"),
+                &[&status_name(&status), &task_item.description],
+            );
             task_item.set_status(status);
+            self.task_list.collapse_finished_phase(index);
             self.scroll_to_task(index);
+            self.announce(&message);
         }
     }
 
+    /// Read a message out to screen readers without moving focus.
+    fn announce(&self, message: &str) {
+        self.window
+            .announce(message, AccessibleAnnouncementPriority::Medium);
+    }
+
     /// Set the dialog title.
     pub fn set_title(&self, title: &str) {
         self.title_label.set_text(title);
@@ -255,7 +579,203 @@ impl TaskRunnerWidgets {
         self.close_button.set_sensitive(true);
     }
 
+    /// Show that authentication failed with `message` and offer to retry.
+    ///
+    /// Unlike [`Self::show_completion`], the completion callback stays
+    /// pending for the retried run.
+    pub fn show_retry(&self, message: &str) {
+        self.set_title(message);
+        self.close_button.remove_css_class("suggested-action");
+        self.title_label.remove_css_class("success");
+        self.title_label.add_css_class("error");
+        self.enable_close();
+        self.retry_button.set_visible(true);
+        self.announce(message);
+    }
+
+    /// Go back to the running state when the retry button is clicked.
+    pub fn hide_retry(&self) {
+        self.retry_button.set_visible(false);
+        self.close_button.set_visible(false);
+        self.close_button.set_sensitive(false);
+        self.cancel_button.set_visible(true);
+        self.cancel_button.set_sensitive(true);
+        self.title_label.remove_css_class("error");
+        self.set_title(&gettext("Running operations…"));
+    }
+
+    /// Fire the completion callback as failed if the run never completed,
+    /// like when the dialog is closed instead of retried.
+    pub fn abandon(&self) {
+        self.emit_finished(false);
+        self.complete(false);
+    }
+
+    /// End the report and fire the completion callback, if still pending.
+    fn complete(&self, success: bool) {
+        let Some(callback) = self.on_complete.borrow_mut().take() else {
+            return;
+        };
+        self.report.borrow_mut().finish(success);
+        // Cloned so the callback may look at the dialog again
+        let report = self.report.borrow().clone();
+        callback(&report);
+    }
+
+    /// Record the program and arguments step `index` runs and show them in
+    /// the tooltip of its row.
+    pub fn record_command(&self, index: usize, program: &str, args: &[String]) {
+        self.report.borrow_mut().set_command(index, program, args);
+        if let Some(item) = self.task_list.items.get(index) {
+            item.container
+                .set_tooltip_text(self.command_line(index).as_deref());
+        }
+    }
+
+    /// Command line step `index` ran, `None` for file writes and steps that
+    /// never started.
+    pub fn command_line(&self, index: usize) -> Option<String> {
+        self.report.borrow().steps.get(index)?.command_line()
+    }
+
+    /// Output of step `index` so far, `None` if it printed nothing.
+    pub fn step_output_text(&self, index: usize) -> Option<String> {
+        let buffer = &self.output_text_buffer;
+        let range = self
+            .step_output
+            .borrow()
+            .range(index, buffer.end_iter().offset())
+            .filter(|range| !range.is_empty())?;
+        let text = buffer.text(
+            &buffer.iter_at_offset(range.start),
+            &buffer.iter_at_offset(range.end),
+            false,
+        );
+        Some(text.trim_start_matches('\n').to_string())
+    }
+
+    /// Give each row a context menu to copy the command or output of its
+    /// step, and to run the step again once the run finished.
+    pub fn setup_step_menus(self: &Rc<Self>, commands: &Rc<Vec<Command>>) {
+        for (index, item) in self.task_list.items.iter().enumerate() {
+            let copy_command = gio::SimpleAction::new("copy-command", None);
+            let widgets = Rc::downgrade(self);
+            copy_command.connect_activate(move |_, _| {
+                let Some(widgets) = widgets.upgrade() else {
+                    return;
+                };
+                if let Some(line) = widgets.command_line(index) {
+                    widgets.window.clipboard().set_text(&line);
+                }
+            });
+
+            let copy_output = gio::SimpleAction::new("copy-output", None);
+            let widgets = Rc::downgrade(self);
+            copy_output.connect_activate(move |_, _| {
+                let Some(widgets) = widgets.upgrade() else {
+                    return;
+                };
+                if let Some(text) = widgets.step_output_text(index) {
+                    widgets.window.clipboard().set_text(&text);
+                }
+            });
+
+            // Runs in a dialog of its own, over this one
+            let rerun = gio::SimpleAction::new("rerun", None);
+            let widgets = Rc::downgrade(self);
+            let commands = commands.clone();
+            rerun.connect_activate(move |_, _| {
+                let Some(widgets) = widgets.upgrade() else {
+                    return;
+                };
+                let command = commands[index].clone();
+                let title = command.description.clone();
+                let mut sequence = CommandSequence::new();
+                if super::workspace::uses_workspace(&command) {
+                    sequence = sequence.with_workspace();
+                }
+                super::run(&widgets.window, sequence.then(command).build(), &title);
+            });
+
+            let actions = gio::SimpleActionGroup::new();
+            actions.add_action(&copy_command);
+            actions.add_action(&copy_output);
+            actions.add_action(&rerun);
+            item.container.insert_action_group("step", Some(&actions));
+
+            let menu = gio::Menu::new();
+            menu.append(Some(&gettext("Copy Command")), Some("step.copy-command"));
+            menu.append(
+                Some(&gettext("Copy Output of This Step")),
+                Some("step.copy-output"),
+            );
+            menu.append(Some(&gettext("Re-run Just This Step")), Some("step.rerun"));
+            let popover = PopoverMenu::from_model(Some(&menu));
+            popover.set_parent(&item.container);
+            popover.set_has_arrow(false);
+            popover.set_halign(gtk4::Align::Start);
+            // Not a child of the row, so it has to be removed by hand
+            let popover_clone = popover.clone();
+            item.container
+                .connect_destroy(move |_| popover_clone.unparent());
+
+            let click = GestureClick::new();
+            click.set_button(gdk::BUTTON_SECONDARY);
+            let widgets = Rc::downgrade(self);
+            click.connect_pressed(move |_, _, x, y| {
+                let Some(widgets) = widgets.upgrade() else {
+                    return;
+                };
+                copy_command.set_enabled(widgets.command_line(index).is_some());
+                copy_output.set_enabled(widgets.step_output_text(index).is_some());
+                rerun.set_enabled(!super::is_running());
+                popover.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+                popover.popup();
+            });
+            item.container.add_controller(click);
+        }
+    }
+
+    /// Record the exit code of step `index`, `None` if it has none.
+    pub fn record_exit_code(&self, index: usize, exit_code: Option<i32>) {
+        self.report.borrow_mut().set_exit_code(index, exit_code);
+    }
+
+    /// Whether a step of the run failed, as opposed to being cancelled.
+    pub fn has_failed_step(&self) -> bool {
+        self.report
+            .borrow()
+            .steps
+            .iter()
+            .any(|step| step.status == TaskStatus::Failed)
+    }
+
+    /// Start the report over, when the run is retried from its first step.
+    pub fn restart_report(&self) {
+        self.report.borrow_mut().restart();
+        self.step_output.borrow_mut().clear();
+    }
+
+    /// Announce the end of the run, once.
+    pub fn emit_finished(&self, success: bool) {
+        if self.finished.replace(true) {
+            return;
+        }
+        events::emit(Event::TaskFinished {
+            title: self.window.title().unwrap_or_default().to_string(),
+            success,
+            duration: self.started.elapsed(),
+        });
+    }
+
+    /// Register a callback invoked once when the run completes.
+    pub fn set_on_complete(&self, callback: CompletionCallback) {
+        *self.on_complete.borrow_mut() = Some(callback);
+    }
+
     /// Show completion state with a final message.
+    ///
+    /// Fires the completion callback, if one was registered.
     pub fn show_completion(&self, success: bool, message: &str) {
         self.set_title(message);
 
@@ -270,6 +790,8 @@ impl TaskRunnerWidgets {
         }
 
         self.enable_close();
+        self.announce(message);
+        self.complete(success);
     }
 
     /// Append text with a specific color tag.
@@ -298,6 +820,18 @@ impl TaskRunnerWidgets {
         self.append_colored(&header, "header");
     }
 
+    /// Append the header of the phase starting with `index`, if any, so the
+    /// output reads in sections.
+    pub fn append_phase_header(&self, index: usize) {
+        let Some(phase) = self.task_list.phase_of(index) else {
+            return;
+        };
+        if phase.steps.start == index {
+            let header = format!("\n##### {} #####\n", phase.name);
+            self.append_colored(&header, "header");
+        }
+    }
+
     /// Scroll output view to bottom.
     fn scroll_to_bottom(&self) {
         let mut end = self.output_text_buffer.end_iter();
@@ -312,3 +846,70 @@ impl TaskRunnerWidgets {
         self.sidebar_revealer.set_reveal_child(false);
     }
 }
+
+/// Style of the system, as the output colors follow it.
+fn current_style() -> palette::Style {
+    let style_manager = adw::StyleManager::default();
+    let dark = style_manager.is_dark();
+    let accent = style_manager.accent_color().to_standalone_rgba(dark);
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    palette::Style {
+        dark,
+        high_contrast: style_manager.is_high_contrast(),
+        accent: Some(palette::Rgb(
+            channel(accent.red()),
+            channel(accent.green()),
+            channel(accent.blue()),
+        )),
+    }
+}
+
+/// Color the output tags in `tag_table` for the current style.
+fn apply_palette(tag_table: &TextTagTable) {
+    let style = current_style();
+    for (name, color) in palette::palette(style).tags() {
+        if let Some(tag) = tag_table.lookup(name) {
+            tag.set_foreground(Some(&color.css()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_output() {
+        let mut output = StepOutput::default();
+        assert_eq!(output.range(0, 100), None);
+
+        output.start(0, 10);
+        // Still running, up to the end of the buffer
+        assert_eq!(output.range(0, 25), Some(10..25));
+        // Waiting for the package database and then launching both start it
+        output.start(0, 25);
+        output.end(0, 40);
+        assert_eq!(output.range(0, 100), Some(10..40));
+
+        // Skipped steps never start
+        output.end(1, 45);
+        assert_eq!(output.range(1, 100), None);
+
+        output.start(2, 50);
+        output.end(2, 70);
+        assert_eq!(output.range(2, 100), Some(50..70));
+        // Ending another step leaves it alone
+        output.start(3, 70);
+        output.end(2, 80);
+        assert_eq!(output.range(3, 90), Some(70..90));
+
+        // A step started again keeps only its latest output
+        output.end(3, 90);
+        output.start(2, 90);
+        output.end(2, 95);
+        assert_eq!(output.range(2, 100), Some(90..95));
+
+        output.clear();
+        assert_eq!(output.range(0, 100), None);
+    }
+}
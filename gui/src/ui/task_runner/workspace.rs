@@ -0,0 +1,328 @@
+//! Scratch directory of a run, for steps that clone and build.
+//!
+//! A sequence built with [`CommandSequence::with_workspace`] gets a fresh
+//! directory under `~/.cache/xero-toolkit/work/<run-id>` when its commands
+//! start. Steps reach it through [`PLACEHOLDER`] in their arguments and
+//! script variables, or run in it with `cwd_workspace()`; scripts also get
+//! it as `$WORKSPACE`. The directory is removed when the run finishes,
+//! fails or is cancelled, and when the [`Workspace`] is dropped otherwise,
+//! like on a panic. A run whose step failed keeps it when the matching
+//! preference is on, so what the step left behind can be looked at.
+//!
+//! [`CommandSequence::with_workspace`]: super::CommandSequence::with_workspace
+
+use super::command::Command;
+use log::{info, warn};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Replaced by the workspace path in arguments, working directories and
+/// script variables.
+pub const PLACEHOLDER: &str = "${WORKSPACE}";
+
+/// Name of the variable scripts find the workspace path in.
+const SCRIPT_VAR: &str = "WORKSPACE";
+
+thread_local! {
+    /// Workspace of the running sequence.
+    static ACTIVE: RefCell<Option<Workspace>> = const { RefCell::new(None) };
+}
+
+/// A run's scratch directory, removed on drop unless kept.
+#[derive(Debug)]
+pub struct Workspace {
+    path: PathBuf,
+    keep: bool,
+}
+
+impl Workspace {
+    /// Create a new, private directory for a run under `root`.
+    pub fn create_in(root: &Path) -> std::io::Result<Self> {
+        use std::os::unix::fs::DirBuilderExt;
+
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        std::fs::create_dir_all(root)?;
+        let started = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        loop {
+            let path = root.join(format!(
+                "{}-{}-{}",
+                started,
+                std::process::id(),
+                NEXT.fetch_add(1, Ordering::SeqCst)
+            ));
+            // Refuses to reuse a directory left by another run
+            match std::fs::DirBuilder::new().mode(0o700).create(&path) {
+                Ok(()) => return Ok(Self { path, keep: false }),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// End the workspace, keeping the directory when `keep` is set.
+    ///
+    /// Returns the path of a kept directory.
+    pub fn finish(mut self, keep: bool) -> Option<PathBuf> {
+        self.keep = keep;
+        keep.then(|| self.path.clone())
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        if self.keep {
+            return;
+        }
+        match std::fs::remove_dir_all(&self.path) {
+            Ok(()) => info!("Removed workspace {}", self.path.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            // Files a privileged step created are left behind
+            Err(e) => warn!("Failed to remove workspace {}: {}", self.path.display(), e),
+        }
+    }
+}
+
+/// Folder workspaces are created in.
+fn root() -> PathBuf {
+    crate::core::paths::get().cache.join("work")
+}
+
+/// Create the workspace of the run that starts now.
+pub(super) fn open() -> std::io::Result<PathBuf> {
+    let workspace = Workspace::create_in(&root())?;
+    let path = workspace.path().to_path_buf();
+    info!("Created workspace {}", path.display());
+    // A workspace still open from an earlier run goes away here
+    ACTIVE.with(|active| active.replace(Some(workspace)));
+    Ok(path)
+}
+
+/// Path of the running sequence's workspace, if it has one.
+pub(super) fn active_path() -> Option<PathBuf> {
+    ACTIVE.with(|active| {
+        active
+            .borrow()
+            .as_ref()
+            .map(|workspace| workspace.path().to_path_buf())
+    })
+}
+
+/// Remove the workspace of the finished run, or keep it when a step failed
+/// and the preference asks to. Returns the path of a kept workspace.
+pub(super) fn close(step_failed: bool) -> Option<PathBuf> {
+    let workspace = ACTIVE.with(|active| active.borrow_mut().take())?;
+    let keep = keeps(
+        step_failed,
+        crate::config::user::shared()
+            .get()
+            .tasks
+            .keep_failed_workspace,
+    );
+    let kept = workspace.finish(keep);
+    if let Some(path) = &kept {
+        info!("Keeping workspace of the failed run at {}", path.display());
+    }
+    kept
+}
+
+/// Whether a finished run keeps its workspace. Cancelled runs never do.
+fn keeps(step_failed: bool, keep_failed: bool) -> bool {
+    step_failed && keep_failed
+}
+
+/// Whether `command` refers to the workspace.
+pub(super) fn uses_workspace(command: &Command) -> bool {
+    let mentions = |value: &str| value.contains(PLACEHOLDER);
+    command.args.iter().any(|arg| mentions(arg))
+        || command.working_dir.as_deref().is_some_and(mentions)
+        || command
+            .script
+            .as_ref()
+            .is_some_and(|script| script.mentions(PLACEHOLDER))
+}
+
+/// `command` with [`PLACEHOLDER`] replaced by `workspace`, and scripts
+/// given `$WORKSPACE`.
+///
+/// # Errors
+///
+/// Returns an error if `command` refers to the workspace but the run has
+/// none.
+pub(super) fn substitute(command: &Command, workspace: Option<&Path>) -> Result<Command, String> {
+    let Some(workspace) = workspace else {
+        if uses_workspace(command) {
+            return Err(format!(
+                "\"{}\" needs a workspace, but its sequence has none",
+                command.description
+            ));
+        }
+        return Ok(command.clone());
+    };
+
+    let path = workspace.to_string_lossy();
+    let mut command = command.clone();
+    for arg in &mut command.args {
+        *arg = arg.replace(PLACEHOLDER, &path);
+    }
+    if let Some(dir) = &mut command.working_dir {
+        *dir = dir.replace(PLACEHOLDER, &path);
+    }
+    if let Some(script) = &mut command.script {
+        script.replace_in_vars(PLACEHOLDER, &path);
+        script.var(SCRIPT_VAR, &path);
+    }
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_root() -> PathBuf {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        std::env::temp_dir().join(format!(
+            "xero-toolkit-workspace-test-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::SeqCst)
+        ))
+    }
+
+    /// A workspace with a clone-like tree in it.
+    fn populated(root: &Path) -> Workspace {
+        let workspace = Workspace::create_in(root).unwrap();
+        let clone = workspace.path().join("Layan-kde/.git");
+        std::fs::create_dir_all(&clone).unwrap();
+        std::fs::write(clone.join("HEAD"), "ref: refs/heads/master\n").unwrap();
+        workspace
+    }
+
+    #[test]
+    fn test_workspaces_are_unique_and_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = test_root();
+        let first = Workspace::create_in(&root).unwrap();
+        let second = Workspace::create_in(&root).unwrap();
+        assert_ne!(first.path(), second.path());
+        assert_eq!(first.path().parent(), Some(root.as_path()));
+        let mode = std::fs::metadata(first.path())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        drop(first);
+        drop(second);
+        std::fs::remove_dir(&root).unwrap();
+    }
+
+    #[test]
+    fn test_removed_when_finished() {
+        let root = test_root();
+        let workspace = populated(&root);
+        let path = workspace.path().to_path_buf();
+        assert_eq!(workspace.finish(false), None);
+        assert!(!path.exists());
+        std::fs::remove_dir(&root).unwrap();
+    }
+
+    #[test]
+    fn test_kept_for_failed_step() {
+        let root = test_root();
+        let workspace = populated(&root);
+        let path = workspace.path().to_path_buf();
+        assert_eq!(workspace.finish(keeps(true, true)), Some(path.clone()));
+        assert!(path.join("Layan-kde/.git/HEAD").exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_removed_on_cancel() {
+        // Stopped by the user, no step failed: removed even when failed
+        // workspaces are kept
+        assert!(!keeps(false, true));
+        assert!(!keeps(true, false));
+        assert!(!keeps(false, false));
+
+        let root = test_root();
+        let workspace = populated(&root);
+        let path = workspace.path().to_path_buf();
+        assert_eq!(workspace.finish(keeps(false, true)), None);
+        assert!(!path.exists());
+        std::fs::remove_dir(&root).unwrap();
+    }
+
+    #[test]
+    fn test_removed_on_panic() {
+        let root = test_root();
+        let workspace = populated(&root);
+        let path = workspace.path().to_path_buf();
+
+        let result = std::panic::catch_unwind(move || {
+            let _workspace = workspace;
+            panic!("step handler panicked");
+        });
+        assert!(result.is_err());
+        assert!(!path.exists());
+        std::fs::remove_dir(&root).unwrap();
+    }
+
+    #[test]
+    fn test_substitute() {
+        let clone = Command::builder()
+            .normal()
+            .program("git")
+            .args(&[
+                "clone",
+                "https://example.invalid/theme.git",
+                "${WORKSPACE}/theme",
+            ])
+            .description("Cloning")
+            .cwd_workspace()
+            .build();
+        assert!(uses_workspace(&clone));
+
+        let workspace = Path::new("/home/user/.cache/xero-toolkit/work/1-2-3");
+        let resolved = substitute(&clone, Some(workspace)).unwrap();
+        assert_eq!(
+            resolved.args[2],
+            "/home/user/.cache/xero-toolkit/work/1-2-3/theme"
+        );
+        assert_eq!(resolved.working_dir.as_deref(), workspace.to_str());
+        assert!(!uses_workspace(&resolved));
+
+        assert!(substitute(&clone, None)
+            .unwrap_err()
+            .contains("needs a workspace"));
+
+        // Commands not using it run anywhere
+        let plain = Command::builder()
+            .normal()
+            .program("true")
+            .description("Nothing")
+            .build();
+        assert!(!uses_workspace(&plain));
+        assert!(substitute(&plain, None).unwrap().working_dir.is_none());
+    }
+
+    #[test]
+    fn test_substitute_script() {
+        let script = Command::script("Installing", "cd \"$THEME\"\nsh install.sh")
+            .var("THEME", "${WORKSPACE}/theme")
+            .build();
+        assert!(uses_workspace(&script));
+
+        let resolved = substitute(&script, Some(Path::new("/work/1"))).unwrap();
+        let rendered = resolved.script.unwrap().render().unwrap();
+        assert!(rendered.contains("readonly THEME='/work/1/theme'\n"));
+        assert!(rendered.contains("readonly WORKSPACE='/work/1'\n"));
+    }
+}
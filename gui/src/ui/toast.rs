@@ -0,0 +1,94 @@
+//! Transient messages over the main window.
+//!
+//! For things that need no answer, like a missing selection or an action
+//! that is already running, pages show a toast instead of a modal dialog.
+//! The overlay wraps the main window content; `ui::app` installs it in the
+//! application context, where the functions here find it.
+
+use crate::ui::context;
+use log::{info, warn};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// How a toast competes with the ones already shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// Shown after the current toast
+    Normal,
+    /// Replaces the current toast right away
+    High,
+}
+
+/// Button label of a toast and what clicking it does.
+pub type ToastAction<'a> = (&'a str, Box<dyn Fn()>);
+
+/// Shows toasts on the main window's overlay.
+#[derive(Clone)]
+pub struct ToastService {
+    overlay: adw::ToastOverlay,
+    /// Text and toast of the last one shown, so repeats replace it
+    last: Rc<RefCell<Option<(String, adw::Toast)>>>,
+}
+
+impl ToastService {
+    pub fn new(overlay: adw::ToastOverlay) -> Self {
+        Self {
+            overlay,
+            last: Rc::default(),
+        }
+    }
+
+    /// Show `text`, with a button calling `action` if given.
+    pub fn show(&self, text: &str, priority: Priority, action: Option<ToastAction>) {
+        info!("Toast: {}", text);
+
+        // Clicking the same button again must not queue copies
+        if let Some((last_text, last_toast)) = self.last.borrow_mut().take() {
+            if last_text == text {
+                last_toast.dismiss();
+            }
+        }
+
+        let toast = adw::Toast::builder()
+            .title(text)
+            .use_markup(false)
+            .priority(match priority {
+                Priority::Normal => adw::ToastPriority::Normal,
+                Priority::High => adw::ToastPriority::High,
+            })
+            .build();
+        if let Some((label, callback)) = action {
+            toast.set_button_label(Some(label));
+            toast.connect_button_clicked(move |_| callback());
+        }
+
+        self.last.replace(Some((text.to_string(), toast.clone())));
+        self.overlay.add_toast(toast);
+    }
+}
+
+/// Run `f` with the main window's toast service, if the window is built.
+fn with_service(text: &str, f: impl FnOnce(&ToastService)) {
+    match context::current() {
+        Some(ctx) => f(&ctx.ui.toasts),
+        None => warn!("No main window for toast: {}", text),
+    }
+}
+
+/// Show `text` on the main window.
+pub fn toast(text: &str) {
+    with_service(text, |service| service.show(text, Priority::Normal, None));
+}
+
+/// Show `text` on the main window with a button labelled `label`.
+///
+/// The button answers what the user just did, so the toast replaces the
+/// current one right away instead of waiting behind it.
+pub fn toast_with_action<F>(text: &str, label: &str, callback: F)
+where
+    F: Fn() + 'static,
+{
+    with_service(text, |service| {
+        service.show(text, Priority::High, Some((label, Box::new(callback))))
+    });
+}
@@ -0,0 +1,152 @@
+//! Banner announcing a newer version of the toolkit.
+//!
+//! The online check runs in a background thread at most once a day; in
+//! between, the version found by the last check is compared against the
+//! installed one so the banner keeps showing until the user acts on it.
+
+use crate::config::user;
+use crate::core::actions;
+use crate::core::package::UpdateComponent;
+use crate::core::update_check;
+use crate::i18n::{self, gettext};
+use crate::ui::task_runner;
+use crate::ui::utils::try_extract_widget;
+use adw::prelude::*;
+use gtk4::glib;
+use gtk4::{ApplicationWindow, Builder};
+use log::{info, warn};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Check for a toolkit update in the background and reveal the banner if one exists.
+pub fn check_for_updates(builder: &Builder, window: &ApplicationWindow) {
+    let Some(banner) = try_extract_widget::<adw::Banner>(builder, "update_banner") else {
+        warn!("Main window has no update banner - skipping update check");
+        return;
+    };
+
+    let config = user::shared();
+    if !config.get().updates.check {
+        info!("Toolkit update check disabled");
+        return;
+    }
+
+    let now = update_check::now();
+    let due = update_check::is_due(config.get().updates.last_check, now);
+    if due {
+        // Stamped up front so a failing server is not asked again until tomorrow
+        config.update(|config| config.updates.last_check = Some(now));
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let latest = if due {
+            update_check::fetch_latest_version()
+        } else {
+            None
+        };
+        let _ = sender.send((latest, update_check::installed_version()));
+    });
+
+    let window = window.clone();
+    glib::timeout_add_local(Duration::from_millis(200), move || {
+        let (latest, installed) = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return glib::ControlFlow::Continue,
+            Err(mpsc::TryRecvError::Disconnected) => return glib::ControlFlow::Break,
+        };
+
+        let config = user::shared();
+        if let Some(latest) = latest {
+            config.update(|config| config.updates.latest_version = Some(latest));
+        }
+
+        let updates = config.get().updates.clone();
+        let Some(latest) = updates.latest_version else {
+            return glib::ControlFlow::Break;
+        };
+        if !update_check::is_newer(&latest, &installed) {
+            return glib::ControlFlow::Break;
+        }
+        if updates.skipped_version.as_deref() == Some(latest.as_str()) {
+            info!("Toolkit {} is available but was skipped", latest);
+            return glib::ControlFlow::Break;
+        }
+
+        info!("Toolkit update available: {} -> {}", installed, latest);
+        show_banner(&banner, &window, &latest, &installed);
+        glib::ControlFlow::Break
+    });
+}
+
+fn show_banner(banner: &adw::Banner, window: &ApplicationWindow, version: &str, installed: &str) {
+    banner.set_title(&i18n::fill_markup(
+        &gettext("Xero Toolkit {} is available"),
+        &[version],
+    ));
+
+    let window = window.clone();
+    let version = version.to_string();
+    let installed = installed.to_string();
+    banner.connect_button_clicked(move |banner| {
+        show_update_dialog(banner, &window, &version, &installed);
+    });
+    banner.set_revealed(true);
+}
+
+fn show_update_dialog(
+    banner: &adw::Banner,
+    window: &ApplicationWindow,
+    version: &str,
+    installed: &str,
+) {
+    let dialog = adw::AlertDialog::new(
+        Some(&gettext("Toolkit Update Available")),
+        Some(&i18n::fill(
+            &gettext(
+                "Version {0} of Xero Toolkit is available, you are running {1}. \
+                 Updating installs it together with all other pending system updates.",
+            ),
+            &[version, installed],
+        )),
+    );
+    dialog.add_response("skip", &gettext("Skip This Version"));
+    dialog.add_response("later", &gettext("Later"));
+    dialog.add_response("update", &gettext("Update Now"));
+    dialog.set_response_appearance("update", adw::ResponseAppearance::Suggested);
+    dialog.set_default_response(Some("update"));
+    dialog.set_close_response("later");
+
+    let banner = banner.clone();
+    let window_clone = window.clone();
+    let version = version.to_string();
+    dialog.connect_response(None, move |_, response| match response {
+        "update" => {
+            // Partial upgrades are unsupported on Arch, so update everything
+            let components: Vec<UpdateComponent> = [UpdateComponent::Repo, UpdateComponent::Aur]
+                .into_iter()
+                .filter(UpdateComponent::is_available)
+                .collect();
+            let banner = banner.clone();
+            task_runner::run_with_completion(
+                window_clone.upcast_ref(),
+                actions::update_commands(&components).build(),
+                &gettext("Updating Xero Toolkit"),
+                move |success| {
+                    if success {
+                        banner.set_revealed(false);
+                    }
+                },
+            );
+        }
+        "skip" => {
+            info!("Skipping toolkit version {}", version);
+            let version = version.clone();
+            user::shared().update(|config| config.updates.skipped_version = Some(version));
+            banner.set_revealed(false);
+        }
+        _ => banner.set_revealed(false),
+    });
+
+    dialog.present(Some(window));
+}
@@ -1,16 +1,142 @@
 //! UI utility functions for widget extraction and common operations.
 
+use crate::core::exec;
+use crate::core::system_check::{self, Capability};
 use adw::prelude::ComboRowExt;
 use gtk4::glib;
 use gtk4::prelude::*;
 use gtk4::{Builder, StringList};
-use std::process::Command;
+use log::{error, info, warn};
 
-/// Helper to extract widgets from builder with consistent error handling.
-pub fn extract_widget<T: IsA<glib::Object>>(builder: &Builder, name: &str) -> T {
+/// Key under which [`builder_from_resource`] records the resource path.
+const RESOURCE_KEY: &str = "xero-toolkit-resource";
+
+/// Why a widget could not be taken from a builder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WidgetError {
+    /// Id of the widget in the UI file
+    pub id: String,
+    /// Resource path the builder was loaded from, if known
+    pub resource: Option<String>,
+    /// Type name of the object found under the id, if any
+    pub found: Option<String>,
+    /// Type name the caller asked for
+    pub expected: String,
+}
+
+impl std::fmt::Display for WidgetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let source = self.resource.as_deref().unwrap_or("builder UI");
+        match &self.found {
+            None => write!(f, "Widget '{}' not found in {}", self.id, source),
+            Some(found) => write!(
+                f,
+                "Widget '{}' in {} is a {}, not a {}",
+                self.id, source, found, self.expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WidgetError {}
+
+/// Load a builder from a resource, remembering the path for error messages.
+pub fn builder_from_resource(resource: &str) -> Builder {
+    let builder = Builder::from_resource(resource);
+    // SAFETY: the key is only ever used with a String value
+    unsafe { builder.set_data(RESOURCE_KEY, resource.to_string()) };
     builder
-        .object(name)
-        .unwrap_or_else(|| panic!("Failed to get widget with id '{}'", name))
+}
+
+fn builder_resource(builder: &Builder) -> Option<String> {
+    // SAFETY: set only by builder_from_resource, always as a String
+    unsafe {
+        builder
+            .data::<String>(RESOURCE_KEY)
+            .map(|path| path.as_ref().clone())
+    }
+}
+
+/// Get the widget with id `name` from `builder`.
+///
+/// # Errors
+///
+/// Returns an error naming the id and the UI resource if there is no object
+/// with that id or it is not a `T`.
+pub fn extract_widget<T: IsA<glib::Object>>(
+    builder: &Builder,
+    name: &str,
+) -> Result<T, WidgetError> {
+    let error = |found: Option<String>| WidgetError {
+        id: name.to_string(),
+        resource: builder_resource(builder),
+        found,
+        expected: T::static_type().name().to_string(),
+    };
+    let object = builder
+        .object::<glib::Object>(name)
+        .ok_or_else(|| error(None))?;
+    object
+        .downcast::<T>()
+        .map_err(|object| error(Some(object.type_().name().to_string())))
+}
+
+/// Get an optional widget, or `None` if the UI file does not have it.
+///
+/// An object with the id but of another type is still logged as an error.
+pub fn try_extract_widget<T: IsA<glib::Object>>(builder: &Builder, name: &str) -> Option<T> {
+    match extract_widget(builder, name) {
+        Ok(widget) => Some(widget),
+        Err(e) => {
+            if e.found.is_some() {
+                error!("{}", e);
+            }
+            None
+        }
+    }
+}
+
+/// Get a widget the application cannot work without.
+///
+/// # Panics
+///
+/// Panics with the [`WidgetError`] message if the widget is missing.
+pub fn expect_widget<T: IsA<glib::Object>>(builder: &Builder, name: &str) -> T {
+    extract_widget(builder, name).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Disable the widgets `ids` with a tooltip saying why, unless the system
+/// has every capability in `required`.
+///
+/// Actions the system cannot run are disabled up front instead of failing
+/// halfway through a task. Pages call this after setting up their handlers,
+/// so it overrides the sensitivity those gave the widgets; code that enables
+/// one of them again later checks [`Environment::has_all`] itself.
+///
+/// [`Environment::has_all`]: crate::core::system_check::Environment::has_all
+///
+/// # Errors
+///
+/// Returns an error if one of the widgets is missing.
+pub fn require_capabilities(
+    builder: &Builder,
+    required: &[Capability],
+    ids: &[&str],
+) -> Result<(), WidgetError> {
+    let environment = system_check::environment();
+    let Some(reason) = required
+        .iter()
+        .find_map(|capability| environment.missing_reason(*capability))
+    else {
+        return Ok(());
+    };
+    info!("Disabling {:?}: {}", ids, reason);
+    for id in ids {
+        let widget = extract_widget::<gtk4::Widget>(builder, id)?;
+        widget.set_sensitive(false);
+        widget.set_tooltip_text(Some(&reason));
+    }
+    Ok(())
 }
 
 /// Get the selected string value from an AdwComboRow.
@@ -21,14 +147,12 @@ pub fn get_combo_row_value(combo: &adw::ComboRow) -> Option<String> {
     string_list.string(selected).map(|s| s.to_string())
 }
 
-/// Run a command and return stdout as a trimmed string.
+/// Run a command and return stdout as a trimmed string if it succeeded.
 pub fn run_command(program: &str, args: &[&str]) -> Option<String> {
-    Command::new(program)
-        .args(args)
-        .output()
+    exec::capture(program, args, exec::Options::default())
+        .inspect_err(|e| warn!("{:#}", e))
         .ok()
-        .filter(|o| o.status.success())
-        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .and_then(exec::Captured::stdout_if_success)
 }
 
 /// Check if a systemd service is enabled.
@@ -42,3 +166,51 @@ pub fn is_service_enabled(service: &str) -> bool {
 pub fn path_exists(path: &str) -> bool {
     std::path::Path::new(path).exists()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UI: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<interface>
+  <object class="GtkLabel" id="label_title">
+    <property name="label">Title</property>
+  </object>
+</interface>"#;
+
+    #[test]
+    fn test_widget_error_message() {
+        let missing = WidgetError {
+            id: "btn_start".to_string(),
+            resource: Some(
+                "/xyz/xerolinux/xero-toolkit/ui/dialogs/task_list_dialog.ui".to_string(),
+            ),
+            found: None,
+            expected: "GtkButton".to_string(),
+        };
+        assert_eq!(
+            missing.to_string(),
+            "Widget 'btn_start' not found in /xyz/xerolinux/xero-toolkit/ui/dialogs/task_list_dialog.ui"
+        );
+
+        // Building widgets needs a display
+        if std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+            return;
+        }
+        gtk4::init().unwrap();
+        let builder = Builder::from_string(UI);
+
+        assert!(extract_widget::<gtk4::Label>(&builder, "label_title").is_ok());
+        let e = extract_widget::<gtk4::Button>(&builder, "btn_missing").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Widget 'btn_missing' not found in builder UI"
+        );
+        let e = extract_widget::<gtk4::Button>(&builder, "label_title").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Widget 'label_title' in builder UI is a GtkLabel, not a GtkButton"
+        );
+        assert!(try_extract_widget::<gtk4::Button>(&builder, "btn_missing").is_none());
+    }
+}
@@ -0,0 +1,56 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+# update-translations.sh — extract translatable strings and merge them into po files
+# Usage: tools/update-translations.sh (can be run from any directory)
+#
+# Regenerates po/xero-toolkit.pot from the Rust sources and UI files, then
+# merges it into every language listed in po/LINGUAS.
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" >/dev/null 2>&1 && pwd)"
+REPO_ROOT="$(cd "$SCRIPT_DIR/.." >/dev/null 2>&1 && pwd)"
+cd "$REPO_ROOT"
+
+DOMAIN="xero-toolkit"
+POT="po/${DOMAIN}.pot"
+
+for cmd in xgettext msgmerge; do
+  if ! command -v "$cmd" >/dev/null 2>&1; then
+    echo "$cmd not found; install gettext" >&2
+    exit 1
+  fi
+done
+
+# ---------------------------------------------------------------------------
+# Extraction
+# ---------------------------------------------------------------------------
+
+rust_files="$(mktemp)"
+ui_files="$(mktemp)"
+trap 'rm -f "$rust_files" "$ui_files"' EXIT
+
+find gui/src -name '*.rs' | sort > "$rust_files"
+find gui/resources/ui -name '*.ui' | sort > "$ui_files"
+
+xgettext --from-code=UTF-8 --package-name="$DOMAIN" --add-comments \
+  --language=Rust \
+  --keyword=gettext --keyword=ngettext:1,2 --keyword=gettext_noop \
+  --files-from="$rust_files" \
+  --output="$POT"
+
+xgettext --from-code=UTF-8 --package-name="$DOMAIN" --join-existing \
+  --language=Glade \
+  --files-from="$ui_files" \
+  --output="$POT"
+
+echo "Updated $POT"
+
+# ---------------------------------------------------------------------------
+# Merge
+# ---------------------------------------------------------------------------
+
+while read -r lang; do
+  [[ -z "$lang" || "$lang" == \#* ]] && continue
+  msgmerge --quiet --update --backup=none "po/${lang}.po" "$POT"
+  echo "Merged po/${lang}.po"
+done < po/LINGUAS
@@ -3,8 +3,8 @@
 //! Command-line client for testing the authentication daemon.
 
 use clap::Parser;
-use xero_auth::shared::is_daemon_running;
-use xero_auth::Client;
+use xero_auth::shared::is_daemon_running_async;
+use xero_auth::{confirm_on_terminal, Client, ResourceLimits};
 
 #[derive(Parser, Debug)]
 #[command(name = "xero-auth")]
@@ -14,6 +14,22 @@ struct Args {
     #[arg(short, long)]
     env: Vec<String>,
 
+    /// Niceness to run the program with, from -20 to 19
+    #[arg(long, allow_hyphen_values = true)]
+    nice: Option<i32>,
+
+    /// I/O scheduling class: 1 realtime, 2 best-effort, 3 idle
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=3))]
+    ionice_class: Option<u8>,
+
+    /// CPU time in percent of one CPU (needs systemd-run)
+    #[arg(long)]
+    cpu_quota: Option<u32>,
+
+    /// Directory to run the program in
+    #[arg(long)]
+    cwd: Option<String>,
+
     /// The program to execute
     program: String,
 
@@ -24,7 +40,7 @@ struct Args {
 
 #[tokio::main]
 async fn main() {
-    if !is_daemon_running() {
+    if !is_daemon_running_async().await {
         eprintln!("Error: xero-auth daemon is not running");
         std::process::exit(1);
     }
@@ -49,12 +65,19 @@ async fn main() {
         }
     };
 
+    client.set_limits(ResourceLimits {
+        nice: args.nice,
+        ionice_class: args.ionice_class,
+        cpu_quota_percent: args.cpu_quota,
+    });
+    client.set_confirm(confirm_on_terminal);
+
     let exit_code = match client
         .execute(
             &args.program,
             &args.args,
             args.env,
-            None,
+            args.cwd.as_deref(),
             |line| print!("{}", line),
             |line| eprint!("{}", line),
         )
@@ -3,8 +3,8 @@
 //! Runs as root and executes commands on behalf of authenticated clients.
 
 use clap::Parser;
-use simple_logger::SimpleLogger;
-use xero_auth::run_daemon;
+use std::path::PathBuf;
+use xero_auth::{logging, run_daemon, systemd};
 
 /// Xero Authentication Daemon
 ///
@@ -28,24 +28,67 @@ struct Args {
     #[arg(short = 'p', long)]
     parent_pid: Option<u32>,
 
+    /// Run as a socket-activated systemd service
+    ///
+    /// Listens on the socket systemd passes instead of creating one, leaving
+    /// its owner and permissions to the socket unit. --uid is ignored.
+    #[arg(long)]
+    systemd: bool,
+
+    /// Print example systemd units for socket activation and exit
+    #[arg(long)]
+    print_units: bool,
+
     /// Enable debug logging
     #[arg(short, long)]
     debug: bool,
 }
 
-#[tokio::main]
-async fn main() {
+fn main() {
     let args = Args::parse();
 
+    if args.print_units {
+        let daemon_path =
+            std::env::current_exe().unwrap_or_else(|_| PathBuf::from("/usr/bin/xero-authd"));
+        print!("{}", systemd::units(&daemon_path));
+        return;
+    }
+
     let log_level = if args.debug {
         log::LevelFilter::Debug
     } else {
         log::LevelFilter::Info
     };
 
-    SimpleLogger::new().with_level(log_level).init().unwrap();
+    logging::init(
+        logging::level_from_env(log_level),
+        logging::log_path("daemon.log"),
+    )
+    .unwrap();
+
+    // Clears the LISTEN_* variables, which is only sound while no other
+    // thread runs, so before the runtime starts its workers
+    let listener = match systemd::take_listener() {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Daemon error: {:#}", e);
+            std::process::exit(1);
+        }
+    };
 
-    if let Err(e) = run_daemon(args.uid, args.parent_pid).await {
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start the async runtime: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = runtime.block_on(run_daemon(
+        args.uid,
+        args.parent_pid,
+        args.systemd,
+        listener,
+    )) {
         eprintln!("Daemon error: {}", e);
         std::process::exit(1);
     }
@@ -1,28 +1,129 @@
 //! Client implementation for communicating with the xero-auth daemon.
 
-use crate::protocol::{ClientMessage, DaemonMessage};
+use crate::limits::ResourceLimits;
+use crate::protocol::{ClientMessage, DaemonMessage, SessionStats};
 use crate::protocol_io::{read_message, write_message};
-use crate::shared::get_socket_path;
+use crate::shared::client_socket_path;
 use anyhow::{Context, Result};
+use std::path::Path;
+use std::sync::Mutex;
 use tokio::net::UnixStream;
 
+/// Asks the user to confirm a command for the given reason.
+type ConfirmHandler = Box<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Ask on the terminal whether to run a command the daemon wants
+/// confirmed for `reason`. Denied when stdin is not a terminal, as nobody
+/// could answer.
+pub fn confirm_on_terminal(reason: &str) -> bool {
+    use std::io::{BufRead, IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() {
+        eprintln!("Denied, no terminal to confirm on: {}", reason);
+        return false;
+    }
+    eprint!("{}. Run it anyway? [y/N] ", reason);
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer).is_ok()
+        && matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
 /// Client for communicating with the xero-auth daemon.
 pub struct Client {
     stream: UnixStream,
+    limits: ResourceLimits,
+    confirm: Option<ConfirmHandler>,
+}
+
+/// Output and exit code of a command run through [`Client::collect_output`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandOutput {
+    /// Output chunks in the order the daemon sent them.
+    pub lines: Vec<String>,
+    pub exit_code: i32,
 }
 
 impl Client {
-    /// Connect to the daemon.
+    /// Connect to the daemon of the current user, or to the one systemd
+    /// runs if it answers.
     pub async fn new() -> Result<Self> {
-        let socket_path = get_socket_path(None)?;
+        Self::connect(&client_socket_path().await?).await
+    }
 
+    /// Connect to a daemon listening on `socket_path`.
+    pub async fn connect(socket_path: &Path) -> Result<Self> {
         use tokio::time::{timeout, Duration};
-        let stream = timeout(Duration::from_secs(5), UnixStream::connect(&socket_path))
+        let stream = timeout(Duration::from_secs(5), UnixStream::connect(socket_path))
             .await
             .context("Connection timeout")?
             .context("Failed to connect to daemon")?;
 
-        Ok(Self { stream })
+        Ok(Self {
+            stream,
+            limits: ResourceLimits::default(),
+            confirm: None,
+        })
+    }
+
+    /// Request `limits` for every command executed from now on.
+    pub fn set_limits(&mut self, limits: ResourceLimits) {
+        self.limits = limits;
+    }
+
+    /// Ask `confirm` whenever the daemon wants a command confirmed, see
+    /// [`crate::policy`]. It gets the daemon's reason and blocks until the
+    /// user answers. Without a handler, such commands are denied.
+    pub fn set_confirm(&mut self, confirm: impl Fn(&str) -> bool + Send + Sync + 'static) {
+        self.confirm = Some(Box::new(confirm));
+    }
+
+    /// Check that the daemon answers.
+    pub async fn ping(&mut self) -> Result<()> {
+        let (mut reader, mut writer) = self.stream.split();
+        write_message(&mut writer, &ClientMessage::Ping).await?;
+
+        match read_message::<_, DaemonMessage>(&mut reader).await? {
+            Some(DaemonMessage::Pong) => Ok(()),
+            Some(msg) => anyhow::bail!("Unexpected response to ping: {:?}", msg),
+            None => anyhow::bail!("Connection closed before pong"),
+        }
+    }
+
+    /// Counters of the commands the daemon ran so far.
+    pub async fn status(&mut self) -> Result<SessionStats> {
+        let (mut reader, mut writer) = self.stream.split();
+        write_message(&mut writer, &ClientMessage::Status).await?;
+
+        match read_message::<_, DaemonMessage>(&mut reader).await? {
+            Some(DaemonMessage::Status(stats)) => Ok(stats),
+            Some(msg) => anyhow::bail!("Unexpected response to status: {:?}", msg),
+            None => anyhow::bail!("Connection closed before status"),
+        }
+    }
+
+    /// Execute a command and collect everything it prints.
+    ///
+    /// Output and error chunks end up in the same list, as they share the PTY.
+    pub async fn collect_output(
+        &mut self,
+        program: &str,
+        args: &[String],
+    ) -> Result<CommandOutput> {
+        let lines = Mutex::new(Vec::new());
+        let push = |text: &str| {
+            if let Ok(mut lines) = lines.lock() {
+                lines.push(text.to_string());
+            }
+        };
+        let exit_code = self
+            .execute(program, args, Vec::new(), None, push, push)
+            .await?;
+
+        Ok(CommandOutput {
+            lines: lines.into_inner().unwrap_or_default(),
+            exit_code,
+        })
     }
 
     /// Execute a command on the daemon.
@@ -60,6 +161,9 @@ impl Client {
             args: args.to_vec(),
             env,
             working_dir: working_dir.map(|s| s.to_string()),
+            nice: self.limits.nice,
+            ionice_class: self.limits.ionice_class,
+            cpu_quota_percent: self.limits.cpu_quota_percent,
         };
         write_message(&mut writer, &message).await?;
 
@@ -78,6 +182,17 @@ impl Client {
                 DaemonMessage::Error(text) => {
                     on_error(&text);
                 }
+                DaemonMessage::ConfirmationRequired { request_id, reason } => {
+                    let approved = self
+                        .confirm
+                        .as_ref()
+                        .is_some_and(|confirm| confirm(&reason));
+                    let answer = ClientMessage::Confirm {
+                        request_id,
+                        approved,
+                    };
+                    write_message(&mut writer, &answer).await?;
+                }
                 DaemonMessage::Completed { exit_code: code } => {
                     exit_code = Some(code);
                     break;
@@ -92,6 +207,65 @@ impl Client {
         Ok(exit_code.unwrap_or(-1))
     }
 
+    /// Replace `path` with `contents` as root, optionally keeping a backup
+    /// of the old file.
+    ///
+    /// Returns the path of the backup, if one was made.
+    pub async fn write_file(
+        &mut self,
+        path: &str,
+        contents: &[u8],
+        mode: Option<u32>,
+        backup: bool,
+    ) -> Result<Option<String>> {
+        let (mut reader, mut writer) = self.stream.split();
+        let message = ClientMessage::WriteFile {
+            path: path.to_string(),
+            contents: contents.to_vec(),
+            mode,
+            backup,
+        };
+        write_message(&mut writer, &message).await?;
+
+        match read_message::<_, DaemonMessage>(&mut reader).await? {
+            Some(DaemonMessage::FileWritten { backup_path, .. }) => Ok(backup_path),
+            Some(DaemonMessage::ErrorMessage(msg)) => anyhow::bail!("Daemon error: {}", msg),
+            Some(msg) => anyhow::bail!("Unexpected response to write: {:?}", msg),
+            None => anyhow::bail!("Connection closed before write acknowledgment"),
+        }
+    }
+
+    /// Read `path` as root; `None` if it does not exist.
+    pub async fn read_file(&mut self, path: &str) -> Result<Option<Vec<u8>>> {
+        let (mut reader, mut writer) = self.stream.split();
+        let message = ClientMessage::ReadFile {
+            path: path.to_string(),
+        };
+        write_message(&mut writer, &message).await?;
+
+        match read_message::<_, DaemonMessage>(&mut reader).await? {
+            Some(DaemonMessage::FileContents(contents)) => Ok(contents),
+            Some(DaemonMessage::ErrorMessage(msg)) => anyhow::bail!("Daemon error: {}", msg),
+            Some(msg) => anyhow::bail!("Unexpected response to read: {:?}", msg),
+            None => anyhow::bail!("Connection closed before file contents"),
+        }
+    }
+
+    /// Terminate every command running on the daemon.
+    ///
+    /// Commands are executed one per connection, so this has to be sent on
+    /// a connection of its own. Returns how many commands were signalled.
+    pub async fn cancel(&mut self) -> Result<u32> {
+        let (mut reader, mut writer) = self.stream.split();
+        write_message(&mut writer, &ClientMessage::Cancel).await?;
+
+        match read_message::<_, DaemonMessage>(&mut reader).await? {
+            Some(DaemonMessage::Cancelled { count }) => Ok(count),
+            Some(msg) => anyhow::bail!("Unexpected response to cancel: {:?}", msg),
+            None => anyhow::bail!("Connection closed before cancel acknowledgment"),
+        }
+    }
+
     /// Send a shutdown request to the daemon.
     pub async fn shutdown(&mut self) -> Result<()> {
         let (mut reader, mut writer) = self.stream.split();
@@ -1,20 +1,66 @@
 //! Daemon implementation that runs as root and executes commands.
 
+use crate::files;
+use crate::inhibit;
+use crate::limits::{self, ResourceLimits};
+use crate::policy::Policy;
 use crate::protocol::{ClientMessage, DaemonMessage};
 use crate::protocol_io::{read_message, write_message};
-use crate::shared::{get_socket_path, is_process_running};
+use crate::shared::{
+    exec_as_command, get_socket_path, is_process_running, resolve_trusted_program,
+    SYSTEMD_SOCKET_PATH,
+};
+use crate::stats::Stats;
 use crate::utils::read_buffer_with_line_processing;
 use anyhow::{Context, Result};
 use log::{error, info, warn};
 use pty::fork::Fork;
+use std::collections::HashSet;
 use std::ffi::CString;
 use std::os::unix::fs::PermissionsExt;
 use std::os::unix::process::CommandExt;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use tokio::net::unix::{ReadHalf, WriteHalf};
 use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::Mutex;
 
+/// ID of the next confirmation asked for.
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Process IDs of the commands currently running, each leading its own
+/// session and process group.
+type RunningChildren = Arc<std::sync::Mutex<HashSet<libc::pid_t>>>;
+
+/// A validated Execute request.
+struct Execution {
+    /// Program name as requested, used as argv[0]
+    program: String,
+    /// Resolved path of the program
+    path: PathBuf,
+    args: Vec<String>,
+    env: Vec<String>,
+    working_dir: Option<String>,
+    limits: ResourceLimits,
+}
+
+/// Settings for [`serve`].
+#[derive(Debug)]
+pub struct DaemonConfig {
+    /// Path of the Unix socket to listen on.
+    pub socket_path: PathBuf,
+    /// User ID of the original user; the socket is made accessible to its group.
+    pub effective_uid: Option<u32>,
+    /// Process to monitor; the daemon shuts down once it is gone.
+    pub parent_pid: Option<u32>,
+    /// Socket already listening at `socket_path`, as passed by systemd.
+    /// Whoever created it owns the file and its permissions.
+    pub listener: Option<std::os::unix::net::UnixListener>,
+    /// Commands that need the client's confirmation.
+    pub policy: Policy,
+}
+
 /// Run the authentication daemon.
 ///
 /// # Arguments
@@ -23,27 +69,73 @@ use tokio::sync::Mutex;
 ///   If provided, the socket will be created in that user's runtime directory.
 /// * `parent_pid` - Optional parent process ID to monitor. If provided, the daemon will
 ///   shut down if the parent process is no longer running.
-pub async fn run_daemon(effective_uid: Option<u32>, parent_pid: Option<u32>) -> Result<()> {
+/// * `systemd` - Whether systemd started the daemon. It must have passed a socket, and
+///   `effective_uid` is ignored as the socket unit sets who may connect.
+/// * `listener` - Socket passed by systemd, from [`crate::systemd::take_listener`]. It is used
+///   instead of binding one, with or without `systemd`.
+pub async fn run_daemon(
+    effective_uid: Option<u32>,
+    parent_pid: Option<u32>,
+    systemd: bool,
+    listener: Option<std::os::unix::net::UnixListener>,
+) -> Result<()> {
     let uid = unsafe { libc::getuid() };
     if uid != 0 {
         anyhow::bail!("Daemon must run as root");
     }
 
-    let socket_path = get_socket_path(effective_uid)?;
+    let socket_path = match &listener {
+        Some(listener) => listener
+            .local_addr()
+            .ok()
+            .and_then(|addr| addr.as_pathname().map(Path::to_path_buf))
+            .unwrap_or_else(|| PathBuf::from(SYSTEMD_SOCKET_PATH)),
+        None if systemd => anyhow::bail!("systemd did not pass a socket (LISTEN_FDS is not set)"),
+        None => get_socket_path(effective_uid)?,
+    };
 
-    if socket_path.exists() {
-        std::fs::remove_file(&socket_path).context("Failed to remove old socket")?;
-    }
+    serve(DaemonConfig {
+        socket_path,
+        effective_uid: if systemd { None } else { effective_uid },
+        parent_pid,
+        listener,
+        policy: Policy::default(),
+    })
+    .await
+}
 
-    if let Some(parent) = socket_path.parent() {
-        std::fs::create_dir_all(parent).context("Failed to create socket directory")?;
-    }
+/// Listen on `config.socket_path` until shut down.
+///
+/// Unlike [`run_daemon`] this does not require root; commands run with the
+/// privileges of the calling process, which lets tests run the daemon
+/// in-process.
+pub async fn serve(config: DaemonConfig) -> Result<()> {
+    let DaemonConfig {
+        socket_path,
+        effective_uid,
+        parent_pid,
+        listener,
+        policy,
+    } = config;
+    let policy = Arc::new(policy);
 
     info!("Starting xero-authd daemon");
     info!("Socket path: {:?}", socket_path);
 
-    let listener = UnixListener::bind(&socket_path).context("Failed to bind Unix socket")?;
-    set_socket_permissions(&socket_path, effective_uid)?;
+    // The lock is held until the daemon stops; without one the socket
+    // belongs to systemd
+    let (listener, lock) = match listener {
+        Some(listener) => {
+            info!("Using the socket passed by systemd");
+            let listener =
+                UnixListener::from_std(listener).context("Failed to use the passed socket")?;
+            (listener, None)
+        }
+        None => {
+            let (listener, lock) = bind_socket(&socket_path, effective_uid)?;
+            (listener, Some(lock))
+        }
+    };
 
     info!("Daemon listening on {:?}", socket_path);
     if let Some(pid) = parent_pid {
@@ -51,6 +143,8 @@ pub async fn run_daemon(effective_uid: Option<u32>, parent_pid: Option<u32>) ->
     }
 
     let shutdown = Arc::new(AtomicBool::new(false));
+    let children = RunningChildren::default();
+    let stats = Stats::default();
 
     if let Some(pid) = parent_pid {
         spawn_parent_monitor(shutdown.clone(), pid);
@@ -73,8 +167,11 @@ pub async fn run_daemon(effective_uid: Option<u32>, parent_pid: Option<u32>) ->
                         info!("New client connection");
                         let shutdown_clone = shutdown.clone();
                         let parent_pid_clone = parent_pid;
+                        let children_clone = children.clone();
+                        let policy_clone = policy.clone();
+                        let stats_clone = stats.clone();
                         tokio::spawn(async move {
-                            if let Err(e) = handle_client(stream, shutdown_clone, parent_pid_clone).await {
+                            if let Err(e) = handle_client(stream, shutdown_clone, parent_pid_clone, children_clone, policy_clone, stats_clone).await {
                                 error!("Error handling client: {}", e);
                             }
                         });
@@ -101,13 +198,62 @@ pub async fn run_daemon(effective_uid: Option<u32>, parent_pid: Option<u32>) ->
         }
     }
 
-    if socket_path.exists() {
+    if lock.is_some() && socket_path.exists() {
         let _ = std::fs::remove_file(&socket_path);
     }
 
     Ok(())
 }
 
+/// Bind a new socket at `socket_path`, replacing a stale one, and lock it
+/// so only one daemon serves each user.
+fn bind_socket(
+    socket_path: &Path,
+    effective_uid: Option<u32>,
+) -> Result<(UnixListener, std::fs::File)> {
+    if let Some(parent) = socket_path.parent() {
+        std::fs::create_dir_all(parent).context("Failed to create socket directory")?;
+    }
+
+    let lock = lock_socket(socket_path)?;
+
+    if socket_path.exists() {
+        if std::os::unix::net::UnixStream::connect(socket_path).is_ok() {
+            anyhow::bail!("Address in use: a daemon is listening on {:?}", socket_path);
+        }
+        warn!("Removing stale socket {:?}", socket_path);
+        std::fs::remove_file(socket_path).context("Failed to remove stale socket")?;
+    }
+
+    let listener = UnixListener::bind(socket_path).context("Failed to bind Unix socket")?;
+    set_socket_permissions(socket_path, effective_uid)?;
+    Ok((listener, lock))
+}
+
+/// Take an exclusive lock on the lock file next to `socket_path`.
+///
+/// The lock is released when the returned file is dropped or the daemon dies.
+fn lock_socket(socket_path: &Path) -> Result<std::fs::File> {
+    use std::os::unix::io::AsRawFd;
+
+    let lock_path = socket_path.with_extension("lock");
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open lock file {:?}", lock_path))?;
+
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+        let error = std::io::Error::last_os_error();
+        if error.kind() == std::io::ErrorKind::WouldBlock {
+            anyhow::bail!("Another daemon is already running for this user");
+        }
+        return Err(error).with_context(|| format!("Failed to lock {:?}", lock_path));
+    }
+    Ok(file)
+}
+
 fn set_socket_permissions(socket_path: &std::path::Path, effective_uid: Option<u32>) -> Result<()> {
     if let Some(uid) = effective_uid {
         let socket_path_cstr = CString::new(socket_path.to_string_lossy().as_ref())
@@ -185,6 +331,9 @@ async fn handle_client(
     mut stream: UnixStream,
     shutdown: Arc<AtomicBool>,
     parent_pid: Option<u32>,
+    children: RunningChildren,
+    policy: Arc<Policy>,
+    stats: Stats,
 ) -> Result<()> {
     let (mut reader, writer) = stream.split();
     let writer_arc = Arc::new(Mutex::new(writer));
@@ -194,6 +343,12 @@ async fn handle_client(
             break;
         }
 
+        let message = match read_message(&mut reader).await? {
+            Some(msg) => msg,
+            None => break, // EOF
+        };
+
+        // Checked after reading so a request sent after the parent died is refused
         if let Some(pid) = parent_pid {
             if !is_process_running(pid) {
                 warn!(
@@ -211,16 +366,15 @@ async fn handle_client(
             }
         }
 
-        let message = match read_message(&mut reader).await? {
-            Some(msg) => msg,
-            None => break, // EOF
-        };
-
         match message {
             ClientMessage::Ping => {
                 let mut w = writer_arc.lock().await;
                 write_message(&mut *w, &DaemonMessage::Pong).await?;
             }
+            ClientMessage::Status => {
+                let mut w = writer_arc.lock().await;
+                write_message(&mut *w, &DaemonMessage::Status(stats.snapshot())).await?;
+            }
             ClientMessage::Shutdown => {
                 info!("Received shutdown request from client");
                 let mut w = writer_arc.lock().await;
@@ -228,13 +382,112 @@ async fn handle_client(
                 shutdown.store(true, Ordering::SeqCst);
                 break;
             }
+            ClientMessage::Cancel => {
+                let count = cancel_children(&children);
+                info!("Cancelled {} running command(s)", count);
+                let mut w = writer_arc.lock().await;
+                write_message(&mut *w, &DaemonMessage::Cancelled { count }).await?;
+            }
+            ClientMessage::WriteFile {
+                path,
+                contents,
+                mode,
+                backup,
+            } => {
+                info!("Writing {} ({} bytes)", path, contents.len());
+                let result = tokio::task::spawn_blocking(move || {
+                    files::write_file(&path, &contents, mode, backup).map(|backup| (path, backup))
+                })
+                .await
+                .context("File writer task failed")?;
+                let response = match result {
+                    Ok((path, backup)) => DaemonMessage::FileWritten {
+                        path,
+                        backup_path: backup.map(|backup| backup.to_string_lossy().into_owned()),
+                    },
+                    Err(e) => {
+                        warn!("Refusing to write file: {:#}", e);
+                        DaemonMessage::ErrorMessage(format!("{:#}", e))
+                    }
+                };
+                let mut w = writer_arc.lock().await;
+                write_message(&mut *w, &response).await?;
+            }
+            ClientMessage::ReadFile { path } => {
+                info!("Reading {}", path);
+                let result = tokio::task::spawn_blocking(move || files::read_file(&path))
+                    .await
+                    .context("File reader task failed")?;
+                let response = match result {
+                    Ok(contents) => DaemonMessage::FileContents(contents),
+                    Err(e) => {
+                        warn!("Refusing to read file: {:#}", e);
+                        DaemonMessage::ErrorMessage(format!("{:#}", e))
+                    }
+                };
+                let mut w = writer_arc.lock().await;
+                write_message(&mut *w, &response).await?;
+            }
             ClientMessage::Execute {
                 program,
                 args,
                 env,
                 working_dir,
-            } => {
-                execute_command(&writer_arc, program, args, env, working_dir).await?;
+                nice,
+                ionice_class,
+                cpu_quota_percent,
+            } => match resolve_trusted_program(&program) {
+                Ok(path) => {
+                    if let Some(reason) =
+                        policy.confirmation_reason(&path, &args, working_dir.as_deref())
+                    {
+                        let answer =
+                            confirm(&mut reader, &writer_arc, &reason, policy.confirm_timeout)
+                                .await?;
+                        if answer != Confirmation::Approved {
+                            warn!("Not running {}: {} was not confirmed", program, reason);
+                            let message = match answer {
+                                Confirmation::Denied => format!("Denied: {}", reason),
+                                _ => format!("Not confirmed in time: {}", reason),
+                            };
+                            let mut w = writer_arc.lock().await;
+                            // The client may be gone already
+                            let _ =
+                                write_message(&mut *w, &DaemonMessage::ErrorMessage(message)).await;
+                            if answer == Confirmation::Unanswered {
+                                break;
+                            }
+                            continue;
+                        }
+                    }
+                    let execution = Execution {
+                        program,
+                        path,
+                        args,
+                        env,
+                        working_dir,
+                        limits: ResourceLimits {
+                            nice,
+                            ionice_class,
+                            cpu_quota_percent,
+                        },
+                    };
+                    execute_command(&writer_arc, &children, &stats, execution).await?;
+                }
+                Err(e) => {
+                    warn!("Refusing to execute {}: {}", program, e);
+                    let mut w = writer_arc.lock().await;
+                    write_message(&mut *w, &DaemonMessage::ErrorMessage(e.to_string())).await?;
+                }
+            },
+            ClientMessage::Confirm { request_id, .. } => {
+                warn!("Ignoring confirmation {} nobody asked for", request_id);
+                let mut w = writer_arc.lock().await;
+                write_message(
+                    &mut *w,
+                    &DaemonMessage::ErrorMessage("No confirmation was asked for".to_string()),
+                )
+                .await?;
             }
         }
     }
@@ -242,14 +495,161 @@ async fn handle_client(
     Ok(())
 }
 
+/// The client's answer to a confirmation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Confirmation {
+    Approved,
+    Denied,
+    /// No answer within the timeout, or the client went away
+    Unanswered,
+}
+
+/// Ask the client to confirm a command for `reason` and wait up to
+/// `timeout` for its answer.
+///
+/// Any other message in place of the answer denies the command.
+async fn confirm(
+    reader: &mut ReadHalf<'_>,
+    writer: &Arc<Mutex<WriteHalf<'_>>>,
+    reason: &str,
+    timeout: std::time::Duration,
+) -> Result<Confirmation> {
+    let request_id = NEXT_REQUEST_ID.fetch_add(1, Ordering::SeqCst);
+    info!("Asking for confirmation {}: {}", request_id, reason);
+    {
+        let mut w = writer.lock().await;
+        write_message(
+            &mut *w,
+            &DaemonMessage::ConfirmationRequired {
+                request_id,
+                reason: reason.to_string(),
+            },
+        )
+        .await?;
+    }
+
+    let Ok(answer) = tokio::time::timeout(timeout, read_message(reader)).await else {
+        warn!("Confirmation {} timed out", request_id);
+        return Ok(Confirmation::Unanswered);
+    };
+    Ok(match answer? {
+        Some(ClientMessage::Confirm {
+            request_id: answered,
+            approved,
+        }) if answered == request_id => {
+            info!(
+                "Confirmation {} {}",
+                request_id,
+                if approved { "approved" } else { "denied" }
+            );
+            if approved {
+                Confirmation::Approved
+            } else {
+                Confirmation::Denied
+            }
+        }
+        Some(message) => {
+            warn!(
+                "Expected an answer to confirmation {}, got {:?}",
+                request_id, message
+            );
+            Confirmation::Denied
+        }
+        None => Confirmation::Unanswered,
+    })
+}
+
+fn lock_children(children: &RunningChildren) -> std::sync::MutexGuard<'_, HashSet<libc::pid_t>> {
+    children.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Send SIGTERM to the process group of every running command.
+///
+/// Returns how many commands were signalled.
+fn cancel_children(children: &RunningChildren) -> u32 {
+    let children = lock_children(children);
+    let mut count = 0;
+    for &pid in children.iter() {
+        // Each command leads its own session, so its group id is its pid
+        if unsafe { libc::kill(-pid, libc::SIGTERM) } == 0 {
+            count += 1;
+        } else {
+            warn!(
+                "Failed to signal process group {}: {}",
+                pid,
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+    count
+}
+
+/// Command line that runs `wrapper` with `args`.
+fn wrap(wrapper: &Path, args: Vec<String>) -> Vec<String> {
+    std::iter::once(wrapper.to_string_lossy().to_string())
+        .chain(args)
+        .collect()
+}
+
 async fn execute_command(
     writer: &Arc<Mutex<tokio::net::unix::WriteHalf<'_>>>,
-    program: String,
-    args: Vec<String>,
-    env: Vec<String>,
-    working_dir: Option<String>,
+    children: &RunningChildren,
+    stats: &Stats,
+    execution: Execution,
 ) -> Result<()> {
-    info!("Executing: {} {:?}", program, args);
+    let Execution {
+        program,
+        path,
+        args,
+        env,
+        working_dir,
+        limits,
+    } = execution;
+    info!("Executing: {} ({}) {:?}", program, path.display(), args);
+
+    // Resolved before forking so a missing systemd-run can be reported
+    let mut systemd_run = None;
+    if let Some(quota) = limits.cpu_quota_percent {
+        match limits::systemd_run() {
+            Some(found) => systemd_run = Some((found, quota)),
+            None => {
+                let message = "systemd-run is not available, ignoring the CPU quota\n";
+                warn!("{}", message.trim_end());
+                let mut w = writer.lock().await;
+                write_message(&mut *w, &DaemonMessage::Error(message.to_string())).await?;
+            }
+        }
+    }
+    if !limits.is_empty() {
+        info!("Applying resource limits: {:?}", limits);
+    }
+    let systemd_inhibit = inhibit::systemd_inhibit();
+    if systemd_inhibit.is_none() {
+        warn!(
+            "systemd-inhibit is not available, {} may be interrupted by suspend",
+            program
+        );
+    }
+
+    // Innermost, since wrappers would pass the resolved path as argv[0]
+    let mut wrapped = None;
+    if systemd_run.is_some() || systemd_inhibit.is_some() {
+        let mut command = exec_as_command(&program, &path, &args).unwrap_or_else(|e| {
+            warn!("Running {} without its name as argv[0]: {}", program, e);
+            std::iter::once(path.to_string_lossy().to_string())
+                .chain(args.iter().cloned())
+                .collect()
+        });
+        if let Some((systemd_run, quota)) = systemd_run {
+            command = wrap(&systemd_run, limits::scope_args(quota, &command));
+        }
+        // Outermost, so the lock also covers the scope
+        if let Some(systemd_inhibit) = systemd_inhibit {
+            let why = format!("Running {}", program);
+            command = wrap(&systemd_inhibit, inhibit::inhibit_args(&why, &command));
+        }
+        wrapped = Some(command);
+    }
 
     let fork = Fork::from_ptmx().map_err(|e| anyhow::anyhow!("Failed to create PTY: {}", e))?;
 
@@ -262,8 +662,27 @@ async fn execute_command(
                 }
             }
 
-            let mut cmd = std::process::Command::new(&program);
-            cmd.args(&args);
+            let mut cmd = match &wrapped {
+                Some(command) => {
+                    let mut cmd = std::process::Command::new(&command[0]);
+                    cmd.args(&command[1..]);
+                    cmd
+                }
+                None => {
+                    // Keep the requested name as argv[0] for multi-call binaries
+                    let mut cmd = std::process::Command::new(&path);
+                    cmd.arg0(&program);
+                    cmd.args(&args);
+                    cmd
+                }
+            };
+
+            let (nice, ionice_class) = (limits.nice, limits.ionice_class);
+            if nice.is_some() || ionice_class.is_some() {
+                unsafe {
+                    cmd.pre_exec(move || limits::apply_priority(nice, ionice_class));
+                }
+            }
 
             // Apply environment variables
             for env_str in env {
@@ -278,7 +697,15 @@ async fn execute_command(
             std::process::exit(1);
         }
         Fork::Parent(pid, master) => {
-            let exit_code = read_pty_output(writer.clone(), master, pid).await?;
+            lock_children(children).insert(pid);
+            let finished = read_pty_output(writer.clone(), master, pid).await;
+            lock_children(children).remove(&pid);
+            let (exit_code, cpu_time) = finished?;
+            info!(
+                "{} exited with {} after {:?} of CPU time",
+                program, exit_code, cpu_time
+            );
+            stats.record(&program, exit_code, cpu_time);
             let mut w = writer.lock().await;
             write_message(&mut *w, &DaemonMessage::Completed { exit_code }).await?;
         }
@@ -287,11 +714,13 @@ async fn execute_command(
     Ok(())
 }
 
+/// Forward the output of child `pid` until it exits, returning its exit
+/// code and the CPU time it and its waited-for children used.
 async fn read_pty_output(
     writer: Arc<Mutex<tokio::net::unix::WriteHalf<'_>>>,
     master: pty::prelude::Master,
     pid: libc::pid_t,
-) -> Result<i32> {
+) -> Result<(i32, std::time::Duration)> {
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Result<String, std::io::Error>>();
 
     let read_handle = tokio::task::spawn_blocking(move || {
@@ -327,30 +756,42 @@ async fn read_pty_output(
         }
     };
 
-    tokio::select! {
-        _ = read_handle => {},
-        _ = output_task => {},
+    // Forward everything read before the PTY closed, then reap the reader
+    output_task.await;
+    if let Err(e) = read_handle.await {
+        warn!("PTY reader task failed: {}", e);
     }
 
-    let exit_code = tokio::task::spawn_blocking(move || {
+    let finished = tokio::task::spawn_blocking(move || {
         let mut status: libc::c_int = 0;
-        let result = unsafe { libc::waitpid(pid, &mut status, 0) };
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        let result = unsafe { libc::wait4(pid, &mut status, 0, &mut usage) };
 
         if result == pid {
-            if libc::WIFEXITED(status) {
+            let exit_code = if libc::WIFEXITED(status) {
                 libc::WEXITSTATUS(status) as i32
             } else if libc::WIFSIGNALED(status) {
                 128 + libc::WTERMSIG(status) as i32
             } else {
                 -1
-            }
+            };
+            (exit_code, cpu_time(&usage))
         } else {
             warn!("Failed to wait for child process {}", pid);
-            -1
+            (-1, std::time::Duration::ZERO)
         }
     })
     .await
-    .unwrap_or(-1);
+    .unwrap_or((-1, std::time::Duration::ZERO));
 
-    Ok(exit_code)
+    Ok(finished)
+}
+
+/// User and system CPU time in `usage`.
+fn cpu_time(usage: &libc::rusage) -> std::time::Duration {
+    let duration = |time: libc::timeval| {
+        std::time::Duration::from_secs(time.tv_sec.max(0) as u64)
+            + std::time::Duration::from_micros(time.tv_usec.max(0) as u64)
+    };
+    duration(usage.ru_utime) + duration(usage.ru_stime)
 }
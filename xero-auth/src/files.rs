@@ -0,0 +1,338 @@
+//! Reading and writing files on behalf of the client.
+//!
+//! Writes go to a temporary file in the target's directory that is renamed
+//! over the target, so readers never see a partial file. The old file can
+//! be kept as a timestamped backup next to it. Paths are checked here, not
+//! by the client: they must be absolute, must not be symlinks and must not
+//! resolve to one of the [`BLOCKED_PATHS`].
+
+use anyhow::{bail, Context, Result};
+use std::io::Write;
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Files that are never read or written, and trees that are not regular
+/// filesystems.
+pub const BLOCKED_PATHS: &[&str] = &[
+    "/etc/shadow",
+    "/etc/shadow-",
+    "/etc/gshadow",
+    "/etc/gshadow-",
+    "/etc/sudoers",
+    "/etc/sudoers.d",
+    "/dev",
+    "/proc",
+    "/sys",
+    "/run",
+];
+
+/// Largest file returned by [`read_file`], well below the message limit.
+pub const MAX_READ_LEN: u64 = 8 * 1024 * 1024;
+
+/// Mode of files created without an explicit one.
+const DEFAULT_MODE: u32 = 0o644;
+
+/// Check `path` and resolve its directory.
+///
+/// Returns the path with symlinks in its directory resolved, so the
+/// blocked list cannot be bypassed through a link to it.
+pub fn validate_path(path: &str) -> Result<PathBuf> {
+    let path = Path::new(path);
+    if !path.is_absolute() {
+        bail!("{:?} is not an absolute path", path);
+    }
+    if path
+        .components()
+        .any(|component| matches!(component, Component::ParentDir))
+    {
+        bail!("{:?} contains relative components", path);
+    }
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        bail!("{:?} does not name a file", path);
+    };
+
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("Directory of {:?} is not accessible", path))?;
+    let resolved = dir.join(name);
+    if is_blocked(&resolved) {
+        bail!("{:?} is blocked", resolved);
+    }
+    if resolved
+        .symlink_metadata()
+        .is_ok_and(|metadata| metadata.file_type().is_symlink())
+    {
+        bail!("{:?} is a symlink", resolved);
+    }
+    Ok(resolved)
+}
+
+/// Whether `path` is or lies below one of the [`BLOCKED_PATHS`].
+pub fn is_blocked(path: &Path) -> bool {
+    BLOCKED_PATHS
+        .iter()
+        .any(|blocked| path.starts_with(blocked))
+}
+
+/// Contents of `path`, `None` if it does not exist.
+pub fn read_file(path: &str) -> Result<Option<Vec<u8>>> {
+    let path = validate_path(path)?;
+    let metadata = match std::fs::metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", path)),
+    };
+    if !metadata.is_file() {
+        bail!("{:?} is not a regular file", path);
+    }
+    if metadata.len() > MAX_READ_LEN {
+        bail!("{:?} is larger than {} bytes", path, MAX_READ_LEN);
+    }
+    std::fs::read(&path)
+        .map(Some)
+        .with_context(|| format!("Failed to read {:?}", path))
+}
+
+/// Replace `path` with `contents` in one rename.
+///
+/// A replaced file keeps its owner and, unless `mode` is given, its mode;
+/// new files get `mode` or 0644. With `backup`, an existing file is copied
+/// to `<path>.bak.<YYYYmmdd-HHMMSS>` first. Returns the backup's path.
+pub fn write_file(
+    path: &str,
+    contents: &[u8],
+    mode: Option<u32>,
+    backup: bool,
+) -> Result<Option<PathBuf>> {
+    let path = validate_path(path)?;
+    let existing = match std::fs::metadata(&path) {
+        Ok(metadata) if metadata.is_file() => Some(metadata),
+        Ok(_) => bail!("{:?} is not a regular file", path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e).with_context(|| format!("Failed to inspect {:?}", path)),
+    };
+
+    let backup_path = match &existing {
+        Some(metadata) if backup => Some(write_backup(&path, metadata)?),
+        _ => None,
+    };
+
+    let mode = mode
+        .or(existing.as_ref().map(|metadata| metadata.mode()))
+        .unwrap_or(DEFAULT_MODE)
+        & 0o7777;
+    let owner = existing
+        .as_ref()
+        .map(|metadata| (metadata.uid(), metadata.gid()));
+
+    let tmp = temp_path(&path);
+    let result = (|| -> Result<()> {
+        // Refuses to follow a planted symlink or reuse a file
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&tmp)
+            .with_context(|| format!("Failed to create {:?}", tmp))?;
+        file.write_all(contents)
+            .with_context(|| format!("Failed to write {:?}", tmp))?;
+        if let Some((uid, gid)) = owner {
+            std::os::unix::fs::fchown(&file, Some(uid), Some(gid))
+                .with_context(|| format!("Failed to set the owner of {:?}", tmp))?;
+        }
+        file.set_permissions(std::fs::Permissions::from_mode(mode))
+            .with_context(|| format!("Failed to set the mode of {:?}", tmp))?;
+        file.sync_all()
+            .with_context(|| format!("Failed to sync {:?}", tmp))?;
+        std::fs::rename(&tmp, &path).with_context(|| format!("Failed to replace {:?}", path))
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result.map(|()| backup_path)
+}
+
+/// Copy `path` to a new timestamped backup with the same owner and mode.
+fn write_backup(path: &Path, metadata: &std::fs::Metadata) -> Result<PathBuf> {
+    let stamp = timestamp();
+    let mut backup = PathBuf::from(format!("{}.bak.{}", path.display(), stamp));
+    // Two writes within a second get their own backups
+    let mut attempt = 1;
+    while backup.symlink_metadata().is_ok() {
+        backup = PathBuf::from(format!("{}.bak.{}-{}", path.display(), stamp, attempt));
+        attempt += 1;
+    }
+
+    let mut source =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&backup)
+        .with_context(|| format!("Failed to create {:?}", backup))?;
+    std::io::copy(&mut source, &mut file)
+        .with_context(|| format!("Failed to back up {:?}", path))?;
+    std::os::unix::fs::fchown(&file, Some(metadata.uid()), Some(metadata.gid()))
+        .with_context(|| format!("Failed to set the owner of {:?}", backup))?;
+    file.set_permissions(std::fs::Permissions::from_mode(metadata.mode() & 0o7777))
+        .with_context(|| format!("Failed to set the mode of {:?}", backup))?;
+    Ok(backup)
+}
+
+/// A new temporary name next to `path`.
+fn temp_path(path: &Path) -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(
+        ".{}.xero-{}-{}",
+        name,
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::SeqCst)
+    ))
+}
+
+/// Local time as `YYYYmmdd-HHMMSS`, the format of the toolkit's backups.
+fn timestamp() -> String {
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&now, &mut tm) }.is_null() {
+        return now.to_string();
+    }
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("xero-auth-files-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    fn path_str(path: &Path) -> &str {
+        path.to_str().unwrap()
+    }
+
+    #[test]
+    fn test_validate_path() {
+        assert!(validate_path("etc/pacman.conf").is_err());
+        assert!(validate_path("/etc/../etc/pacman.conf").is_err());
+        assert!(validate_path("/").is_err());
+        assert!(validate_path("/nonexistent-dir/file").is_err());
+        assert_eq!(
+            validate_path("/etc/pacman.conf").unwrap(),
+            Path::new("/etc/pacman.conf")
+        );
+    }
+
+    #[test]
+    fn test_blocked_paths() {
+        assert!(validate_path("/etc/shadow").is_err());
+        assert!(validate_path("/etc/gshadow-").is_err());
+        assert!(validate_path("/etc/sudoers.d/10-installer").is_err());
+        assert!(validate_path("/proc/sys/kernel/hostname").is_err());
+        assert!(is_blocked(Path::new("/sys/kernel")));
+        // Only whole components count
+        assert!(!is_blocked(Path::new("/etc/shadowsocks.json")));
+        assert!(!is_blocked(Path::new("/devices")));
+    }
+
+    #[test]
+    fn test_symlinks_are_refused() {
+        let dir = test_dir("symlinks");
+        // A link to a blocked directory resolves to the blocked path
+        std::os::unix::fs::symlink("/etc", dir.join("etc-link")).unwrap();
+        let error = validate_path(path_str(&dir.join("etc-link/shadow"))).unwrap_err();
+        assert!(error.to_string().contains("blocked"), "{error:#}");
+        // While other files behind it resolve to their real path
+        assert_eq!(
+            validate_path(path_str(&dir.join("etc-link/pacman.conf"))).unwrap(),
+            Path::new("/etc/pacman.conf")
+        );
+
+        std::os::unix::fs::symlink("/etc/pacman.conf", dir.join("file-link")).unwrap();
+        let error = validate_path(path_str(&dir.join("file-link"))).unwrap_err();
+        assert!(error.to_string().contains("symlink"), "{error:#}");
+        assert!(write_file(path_str(&dir.join("file-link")), b"x", None, false).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_file_creates_and_replaces() {
+        let dir = test_dir("write");
+        let path = dir.join("app.conf");
+
+        assert_eq!(
+            write_file(path_str(&path), b"first\n", None, true).unwrap(),
+            None
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), b"first\n");
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, DEFAULT_MODE);
+
+        // The mode of a replaced file is kept
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        let backup = write_file(path_str(&path), b"second\n", None, true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second\n");
+        assert_eq!(std::fs::read(&backup).unwrap(), b"first\n");
+        assert!(backup
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("app.conf.bak."));
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // A second backup within the same second gets its own name
+        let second = write_file(path_str(&path), b"third\n", Some(0o755), true)
+            .unwrap()
+            .unwrap();
+        assert_ne!(second, backup);
+        assert_eq!(std::fs::read(&second).unwrap(), b"second\n");
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+
+        // No temporary files are left behind
+        let leftovers = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with('.'))
+            .count();
+        assert_eq!(leftovers, 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_read_file() {
+        let dir = test_dir("read");
+        let path = dir.join("data");
+        assert_eq!(read_file(path_str(&path)).unwrap(), None);
+        std::fs::write(&path, b"\x00\xffbinary").unwrap();
+        assert_eq!(
+            read_file(path_str(&path)).unwrap().as_deref(),
+            Some(&b"\x00\xffbinary"[..])
+        );
+        assert!(read_file(path_str(&dir)).is_err());
+        assert!(read_file("/etc/shadow").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,62 @@
+//! Suspend inhibition for commands spawned by the daemon.
+//!
+//! Each command runs under `systemd-inhibit`, which holds a logind
+//! inhibitor lock for exactly as long as the command runs. The lock belongs
+//! to the command rather than to the GUI or the daemon, so neither crashing
+//! lets the machine suspend in the middle of a package transaction.
+
+use crate::shared::resolve_trusted_program;
+use std::path::{Path, PathBuf};
+
+/// Operations blocked while a command runs.
+pub const INHIBIT_WHAT: &str = "sleep:handle-lid-switch";
+
+/// Application name shown by the desktop next to the lock.
+pub const INHIBIT_WHO: &str = "Xero Toolkit";
+
+/// Path of systemd-inhibit, if logind manages this system.
+pub fn systemd_inhibit() -> Option<PathBuf> {
+    if !Path::new("/run/systemd/system").is_dir() {
+        return None;
+    }
+    resolve_trusted_program("systemd-inhibit").ok()
+}
+
+/// Arguments for systemd-inhibit that execute `command` while blocking
+/// suspend, giving `why` as the reason.
+pub fn inhibit_args(why: &str, command: &[String]) -> Vec<String> {
+    let mut inhibit = vec![
+        format!("--what={}", INHIBIT_WHAT),
+        format!("--who={}", INHIBIT_WHO),
+        format!("--why={}", why),
+        "--mode=block".to_string(),
+        "--".to_string(),
+    ];
+    inhibit.extend_from_slice(command);
+    inhibit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inhibit_args() {
+        let args = inhibit_args(
+            "Running pacman",
+            &["/usr/bin/pacman".to_string(), "-Syu".to_string()],
+        );
+        assert_eq!(
+            args,
+            [
+                "--what=sleep:handle-lid-switch",
+                "--who=Xero Toolkit",
+                "--why=Running pacman",
+                "--mode=block",
+                "--",
+                "/usr/bin/pacman",
+                "-Syu"
+            ]
+        );
+    }
+}
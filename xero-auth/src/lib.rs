@@ -5,11 +5,24 @@
 
 pub mod client;
 pub mod daemon;
+pub mod files;
+pub mod inhibit;
+pub mod limits;
+pub mod logging;
+pub mod policy;
 pub mod protocol;
 pub mod protocol_io;
 pub mod shared;
+pub mod stats;
+pub mod systemd;
 pub mod utils;
 
-pub use client::Client;
-pub use daemon::run_daemon;
-pub use shared::{get_socket_path, is_daemon_running, wait_for_socket};
+pub use client::{confirm_on_terminal, Client, CommandOutput};
+pub use daemon::{run_daemon, serve, DaemonConfig};
+pub use limits::ResourceLimits;
+pub use policy::Policy;
+pub use shared::{
+    get_socket_path, is_daemon_running, is_daemon_running_async, is_systemd_socket_active,
+    ping_socket, ping_socket_async, wait_for_socket, wait_for_socket_async, wait_for_socket_at,
+    wait_for_socket_at_async, WaitError,
+};
@@ -0,0 +1,147 @@
+//! CPU and I/O limits for commands spawned by the daemon.
+//!
+//! Niceness and the I/O scheduling class are set on the command's own
+//! process right before it executes. A CPU quota needs a cgroup so it also
+//! covers the command's children; the daemon gets one by running the
+//! command inside a transient systemd scope. Without systemd-run the quota
+//! is skipped with a warning while niceness and I/O class still apply.
+
+use crate::shared::resolve_trusted_program;
+use std::path::{Path, PathBuf};
+
+/// `IOPRIO_WHO_PROCESS` from `linux/ioprio.h`.
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+/// `IOPRIO_CLASS_SHIFT` from `linux/ioprio.h`.
+const IOPRIO_CLASS_SHIFT: u32 = 13;
+/// Priority within the realtime and best-effort classes, the kernel default.
+const IOPRIO_DEFAULT_LEVEL: u32 = 4;
+/// I/O scheduling class that only gets disk time nobody else wants.
+pub const IOPRIO_CLASS_IDLE: u8 = 3;
+
+/// Limits requested for a command, all optional.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Niceness, from -20 (highest priority) to 19 (lowest).
+    pub nice: Option<i32>,
+    /// I/O scheduling class as in `ionice(1)`: 1 realtime, 2 best-effort, 3 idle.
+    pub ionice_class: Option<u8>,
+    /// CPU time in percent of one CPU, so 200 allows two full cores.
+    pub cpu_quota_percent: Option<u32>,
+}
+
+impl ResourceLimits {
+    /// Whether no limit is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Arguments for the xero-auth binary that request these limits.
+    pub fn to_cli_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(nice) = self.nice {
+            args.push(format!("--nice={}", nice));
+        }
+        if let Some(class) = self.ionice_class {
+            args.push(format!("--ionice-class={}", class));
+        }
+        if let Some(quota) = self.cpu_quota_percent {
+            args.push(format!("--cpu-quota={}", quota));
+        }
+        args
+    }
+}
+
+/// The `ioprio` value for `class`, or `None` for an unknown class.
+pub fn ioprio_value(class: u8) -> Option<libc::c_int> {
+    let level = match class {
+        1 | 2 => IOPRIO_DEFAULT_LEVEL,
+        IOPRIO_CLASS_IDLE => 0,
+        _ => return None,
+    };
+    Some(((u32::from(class) << IOPRIO_CLASS_SHIFT) | level) as libc::c_int)
+}
+
+/// Apply niceness and I/O class to the calling process.
+///
+/// Meant for `pre_exec`, so it only makes async-signal-safe calls.
+pub fn apply_priority(nice: Option<i32>, ionice_class: Option<u8>) -> std::io::Result<()> {
+    if let Some(nice) = nice {
+        let nice = nice.clamp(-20, 19);
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    if let Some(ioprio) = ionice_class.and_then(ioprio_value) {
+        let result = unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) };
+        if result != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Path of systemd-run, if systemd manages this system.
+pub fn systemd_run() -> Option<PathBuf> {
+    if !Path::new("/run/systemd/system").is_dir() {
+        return None;
+    }
+    resolve_trusted_program("systemd-run").ok()
+}
+
+/// Arguments for systemd-run that execute `command` in a transient scope
+/// limited to `quota_percent` of one CPU.
+pub fn scope_args(quota_percent: u32, command: &[String]) -> Vec<String> {
+    let mut scope = vec![
+        "--scope".to_string(),
+        "--quiet".to_string(),
+        "--collect".to_string(),
+        "-p".to_string(),
+        format!("CPUQuota={}%", quota_percent),
+        "--".to_string(),
+    ];
+    scope.extend_from_slice(command);
+    scope
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ioprio_value() {
+        assert_eq!(ioprio_value(3), Some(3 << 13));
+        assert_eq!(ioprio_value(2), Some((2 << 13) | 4));
+        assert_eq!(ioprio_value(0), None);
+        assert_eq!(ioprio_value(4), None);
+    }
+
+    #[test]
+    fn test_scope_args() {
+        let args = scope_args(150, &["/usr/bin/make".to_string(), "-j8".to_string()]);
+        assert_eq!(
+            args,
+            [
+                "--scope",
+                "--quiet",
+                "--collect",
+                "-p",
+                "CPUQuota=150%",
+                "--",
+                "/usr/bin/make",
+                "-j8"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cli_args() {
+        assert!(ResourceLimits::default().to_cli_args().is_empty());
+        let limits = ResourceLimits {
+            nice: Some(10),
+            ionice_class: Some(IOPRIO_CLASS_IDLE),
+            cpu_quota_percent: None,
+        };
+        assert!(!limits.is_empty());
+        assert_eq!(limits.to_cli_args(), ["--nice=10", "--ionice-class=3"]);
+    }
+}
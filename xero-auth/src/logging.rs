@@ -0,0 +1,175 @@
+//! Logging shared by the toolkit and the daemon.
+//!
+//! Records go to stdout and to a size-rotated file in the user's cache
+//! directory, so logs survive launches from the desktop menu.
+
+use anyhow::{Context, Result};
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Environment variable overriding the configured log level.
+pub const LEVEL_ENV: &str = "XERO_TOOLKIT_LOG";
+
+/// Size at which the log file is rotated.
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Number of files kept, including the current one.
+const KEEP_FILES: usize = 3;
+
+/// Path of a log file in `~/.cache/xero-toolkit`.
+pub fn log_path(file_name: &str) -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("xero-toolkit").join(file_name))
+}
+
+/// Level from [`LEVEL_ENV`] if set and valid, otherwise `default`.
+pub fn level_from_env(default: LevelFilter) -> LevelFilter {
+    std::env::var(LEVEL_ENV)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Install the logger, writing to stdout and, when `file` is given, to
+/// that file with rotation.
+///
+/// A file that cannot be opened is reported on stderr and skipped, so
+/// logging to stdout keeps working.
+pub fn init(level: LevelFilter, file: Option<PathBuf>) -> Result<()> {
+    let file = file.and_then(|path| match RotatingFile::open(path) {
+        Ok(file) => Some(Mutex::new(file)),
+        Err(e) => {
+            eprintln!("Failed to open log file: {e:#}");
+            None
+        }
+    });
+
+    log::set_boxed_logger(Box::new(Logger { file })).context("Logger already initialized")?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+struct Logger {
+    file: Option<Mutex<RotatingFile>>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "{} {:<5} [{}] {}\n",
+            timestamp(SystemTime::now()),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+
+        print!("{line}");
+
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                file.write_line(&line);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stdout().flush();
+    }
+}
+
+/// Log file that moves to `.1`, `.2`, ... once it grows past the size limit.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+        Ok(Self { path, file, size })
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.size > 0 && self.size + line.len() as u64 > MAX_FILE_SIZE {
+            if let Err(e) = self.rotate() {
+                eprintln!("Failed to rotate log file: {e:#}");
+            }
+        }
+
+        if self.file.write_all(line.as_bytes()).is_ok() {
+            self.size += line.len() as u64;
+        }
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        for index in (1..KEEP_FILES).rev() {
+            let from = if index == 1 {
+                self.path.clone()
+            } else {
+                rotated_path(&self.path, index - 1)
+            };
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, index))?;
+            }
+        }
+
+        *self = Self::open(self.path.clone())?;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{index}"));
+    PathBuf::from(name)
+}
+
+/// Format a time as UTC `YYYY-MM-DDTHH:MM:SSZ`.
+fn timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
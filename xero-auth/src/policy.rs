@@ -0,0 +1,140 @@
+//! Commands that need the user's confirmation before they run.
+//!
+//! The daemon runs whatever a connected client asks for. A few commands are
+//! destructive enough that a bug or a wrong path in the client must not run
+//! them unasked, so the daemon holds them back until the client confirms,
+//! see [`DaemonMessage::ConfirmationRequired`].
+//!
+//! [`DaemonMessage::ConfirmationRequired`]: crate::protocol::DaemonMessage::ConfirmationRequired
+
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+/// Directories whose recursive removal, or that of anything below them,
+/// needs confirmation.
+pub const PROTECTED_DIRS: &[&str] = &["/boot", "/etc", "/usr", "/var/lib/pacman"];
+
+/// How long the daemon waits for a confirmation before denying.
+pub const CONFIRM_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// What the daemon asks the client to confirm.
+#[derive(Debug, Clone)]
+pub struct Policy {
+    pub protected_dirs: Vec<PathBuf>,
+    /// Time after which an unanswered confirmation counts as denied.
+    pub confirm_timeout: Duration,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            protected_dirs: PROTECTED_DIRS.iter().map(PathBuf::from).collect(),
+            confirm_timeout: CONFIRM_TIMEOUT,
+        }
+    }
+}
+
+impl Policy {
+    /// Why running `program` with `args` in `working_dir` needs
+    /// confirmation, if it does.
+    ///
+    /// Paths are compared as written, without following symlinks.
+    pub fn confirmation_reason(
+        &self,
+        program: &Path,
+        args: &[String],
+        working_dir: Option<&str>,
+    ) -> Option<String> {
+        if program.file_name()? != "rm" {
+            return None;
+        }
+
+        let mut recursive = false;
+        let mut operands = Vec::new();
+        let mut options_done = false;
+        for arg in args {
+            if options_done || arg == "-" || !arg.starts_with('-') {
+                operands.push(arg);
+            } else if arg == "--" {
+                options_done = true;
+            } else if let Some(long) = arg.strip_prefix("--") {
+                recursive |= long == "recursive";
+            } else {
+                recursive |= arg.contains(['r', 'R']);
+            }
+        }
+        if !recursive {
+            return None;
+        }
+
+        let base = Path::new(working_dir.unwrap_or("/"));
+        let removed = operands
+            .into_iter()
+            .find(|operand| self.is_protected(&normalize(&base.join(operand))))?;
+        Some(format!("Recursively removes {}", removed))
+    }
+
+    /// Whether removing `path` removes a protected directory or something
+    /// inside one.
+    fn is_protected(&self, path: &Path) -> bool {
+        self.protected_dirs
+            .iter()
+            .any(|dir| path.starts_with(dir) || dir.starts_with(path))
+    }
+}
+
+/// `path` with `.` and `..` resolved as written.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::Normal(name) => normalized.push(name),
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reason(program: &str, args: &[&str], working_dir: Option<&str>) -> Option<String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        Policy::default().confirmation_reason(Path::new(program), &args, working_dir)
+    }
+
+    #[test]
+    fn test_recursive_removal() {
+        assert_eq!(
+            reason("/usr/bin/rm", &["-rf", "/etc/pacman.d"], None).as_deref(),
+            Some("Recursively removes /etc/pacman.d")
+        );
+        assert!(reason("rm", &["-R", "/boot"], None).is_some());
+        assert!(reason("rm", &["--recursive", "--force", "/usr/share/x"], None).is_some());
+        // Holds protected directories
+        assert!(reason("rm", &["-rf", "/"], None).is_some());
+        assert!(reason("rm", &["-rf", "/var/lib"], None).is_some());
+        // Relative, and spelled around the check
+        assert!(reason("rm", &["-r", "pacman.d"], Some("/etc")).is_some());
+        assert!(reason("rm", &["-r", "/tmp/../etc/x"], None).is_some());
+        assert!(reason("rm", &["-r", "--", "-x", "/etc"], None).is_some());
+    }
+
+    #[test]
+    fn test_other_commands() {
+        // Not recursive
+        assert_eq!(
+            reason("rm", &["-f", "/etc/pacman.d/mirrorlist"], None),
+            None
+        );
+        assert_eq!(reason("rm", &["-rf", "/tmp/build"], None), None);
+        assert_eq!(reason("rm", &["-rf", "/var/lib/pacmanx"], None), None);
+        // An operand after --, not an option
+        assert_eq!(reason("rm", &["--", "-r", "/etc/x"], None), None);
+        assert_eq!(reason("cp", &["-r", "/etc", "/tmp"], None), None);
+    }
+}
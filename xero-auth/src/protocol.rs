@@ -11,9 +11,34 @@ pub enum ClientMessage {
         args: Vec<String>,
         env: Vec<String>,
         working_dir: Option<String>,
+        /// Niceness to run with, from -20 to 19.
+        nice: Option<i32>,
+        /// I/O scheduling class: 1 realtime, 2 best-effort, 3 idle.
+        ionice_class: Option<u8>,
+        /// CPU time in percent of one CPU, enforced through a transient
+        /// systemd scope. Skipped with a warning when systemd-run is not
+        /// available; niceness and I/O class still apply.
+        cpu_quota_percent: Option<u32>,
     },
+    /// Replace a file in one rename, see [`crate::files::write_file`].
+    WriteFile {
+        path: String,
+        contents: Vec<u8>,
+        /// Mode of the file; a replaced file keeps its own when unset.
+        mode: Option<u32>,
+        /// Keep the old file as a timestamped backup.
+        backup: bool,
+    },
+    /// Read a file, see [`crate::files::read_file`].
+    ReadFile { path: String },
+    /// Answer to [`DaemonMessage::ConfirmationRequired`].
+    Confirm { request_id: u64, approved: bool },
     /// Ping to check if daemon is alive.
     Ping,
+    /// Ask for the counters of the commands run so far.
+    Status,
+    /// Terminate every command running on the daemon, for any client.
+    Cancel,
     /// Shutdown the daemon.
     Shutdown,
 }
@@ -25,12 +50,49 @@ pub enum DaemonMessage {
     Output(String),
     /// Command error output (stderr line).
     Error(String),
+    /// The command just requested needs the user's confirmation, see
+    /// [`crate::policy`]. It runs once the client answers with
+    /// [`ClientMessage::Confirm`]; a denial or no answer within the timeout
+    /// is reported as [`DaemonMessage::ErrorMessage`].
+    ConfirmationRequired { request_id: u64, reason: String },
     /// Command completed with exit code.
     Completed { exit_code: i32 },
     /// Error occurred.
     ErrorMessage(String),
+    /// File written, with the path of the backup if one was made.
+    FileWritten {
+        path: String,
+        backup_path: Option<String>,
+    },
+    /// File read; `None` if it does not exist.
+    FileContents(Option<Vec<u8>>),
     /// Pong response to ping.
     Pong,
+    /// Answer to [`ClientMessage::Status`].
+    Status(SessionStats),
+    /// Cancel handled; `count` commands were signalled.
+    Cancelled { count: u32 },
     /// Shutdown acknowledged.
     ShutdownAck,
 }
+
+/// Counters of the commands a daemon ran since it started, over all
+/// connections.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Archive, Serialize, Deserialize)]
+pub struct SessionStats {
+    /// Commands that ran to their end, whatever their exit code.
+    pub commands: u64,
+    /// User and system CPU time of those commands and the children they
+    /// waited for, in microseconds. Commands confined to a systemd scope
+    /// run outside the daemon and are not included.
+    pub cpu_time_micros: u64,
+    /// The command that finished last.
+    pub last_command: Option<LastCommand>,
+}
+
+/// A finished command, see [`SessionStats::last_command`].
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+pub struct LastCommand {
+    pub program: String,
+    pub exit_code: i32,
+}
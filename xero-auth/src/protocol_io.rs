@@ -1,12 +1,52 @@
 //! I/O utilities for protocol message serialization/deserialization.
 
 use anyhow::{Context, Result};
-use rkyv::api::high;
+use rkyv::api::high::{self, HighValidator};
+use rkyv::bytecheck::CheckBytes;
 use rkyv::rancor::Error;
 use rkyv::ser::allocator::ArenaHandle;
 use rkyv::util::AlignedVec;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
+/// Largest message accepted, so a corrupt length prefix cannot exhaust memory.
+pub const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+
+/// Serialize a message with its length prefix.
+///
+/// The format is: [8-byte length (u64, little-endian)][message bytes]
+fn encode<M>(message: &M) -> Result<Vec<u8>>
+where
+    for<'a> M: rkyv::Serialize<high::HighSerializer<AlignedVec, ArenaHandle<'a>, Error>>,
+{
+    let bytes = high::to_bytes(message).context("Failed to serialize message")?;
+    let mut frame = Vec::with_capacity(8 + bytes.len());
+    frame.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+    frame.extend_from_slice(&bytes);
+    Ok(frame)
+}
+
+/// Check a length prefix and allocate an aligned buffer for the message.
+fn message_buffer(len_bytes: [u8; 8]) -> Result<AlignedVec<16>> {
+    let len = u64::from_le_bytes(len_bytes) as usize;
+    if len > MAX_MESSAGE_LEN {
+        anyhow::bail!("Message of {} bytes exceeds the limit", len);
+    }
+
+    // Archived data must be aligned for validation
+    let mut buffer = AlignedVec::<16>::with_capacity(len);
+    buffer.resize(len, 0);
+    Ok(buffer)
+}
+
+fn decode<M>(buffer: &[u8]) -> Result<M>
+where
+    M: rkyv::Archive,
+    M::Archived: for<'a> CheckBytes<HighValidator<'a, Error>>
+        + rkyv::Deserialize<M, high::HighDeserializer<Error>>,
+{
+    high::from_bytes::<M, Error>(buffer).context("Failed to deserialize message")
+}
+
 /// Write a rkyv-serialized message to a writer.
 ///
 /// The format is: [8-byte length (u64, little-endian)][message bytes]
@@ -15,23 +55,20 @@ where
     W: AsyncWriteExt + Unpin,
     for<'a> M: rkyv::Serialize<high::HighSerializer<AlignedVec, ArenaHandle<'a>, Error>>,
 {
-    let bytes = high::to_bytes(message).context("Failed to serialize message")?;
-    let len = bytes.len() as u64;
-    let len_bytes = len.to_le_bytes();
-    writer.write_all(&len_bytes).await?;
-    writer.write_all(&bytes).await?;
+    writer.write_all(&encode(message)?).await?;
     Ok(())
 }
 
 /// Read an rkyv-serialized message from a reader.
 ///
-/// Returns `None` on EOF, `Some(message)` on success.
-/// Uses unchecked deserialization since we control the data source.
+/// Returns `None` on EOF, `Some(message)` on success. Oversized or
+/// malformed messages are rejected with an error.
 pub async fn read_message<R, M>(reader: &mut R) -> Result<Option<M>>
 where
     R: AsyncReadExt + Unpin,
     M: rkyv::Archive,
-    M::Archived: rkyv::Deserialize<M, high::HighDeserializer<Error>>,
+    M::Archived: for<'a> CheckBytes<HighValidator<'a, Error>>
+        + rkyv::Deserialize<M, high::HighDeserializer<Error>>,
 {
     // Read length prefix (u64, little-endian)
     let mut len_bytes = [0u8; 8];
@@ -42,14 +79,40 @@ where
         }
         Err(e) => return Err(e.into()),
     }
-    let len = u64::from_le_bytes(len_bytes) as usize;
 
-    // Read the message bytes
-    let mut buffer = vec![0u8; len];
-    reader.read_exact(&mut buffer).await?;
+    let mut buffer = message_buffer(len_bytes)?;
+    reader.read_exact(&mut buffer[..]).await?;
+    decode(&buffer[..]).map(Some)
+}
+
+/// Blocking variant of [`write_message`], for callers outside a runtime.
+pub fn write_message_blocking<W, M>(writer: &mut W, message: &M) -> Result<()>
+where
+    W: std::io::Write,
+    for<'a> M: rkyv::Serialize<high::HighSerializer<AlignedVec, ArenaHandle<'a>, Error>>,
+{
+    writer.write_all(&encode(message)?)?;
+    Ok(())
+}
+
+/// Blocking variant of [`read_message`], for callers outside a runtime.
+pub fn read_message_blocking<R, M>(reader: &mut R) -> Result<Option<M>>
+where
+    R: std::io::Read,
+    M: rkyv::Archive,
+    M::Archived: for<'a> CheckBytes<HighValidator<'a, Error>>
+        + rkyv::Deserialize<M, high::HighDeserializer<Error>>,
+{
+    let mut len_bytes = [0u8; 8];
+    match reader.read_exact(&mut len_bytes) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    }
 
-    // Deserialize using unchecked high-level API (we control the data source)
-    let message: M = unsafe { high::from_bytes_unchecked(&buffer[..]) }
-        .context("Failed to deserialize message")?;
-    Ok(Some(message))
+    let mut buffer = message_buffer(len_bytes)?;
+    reader.read_exact(&mut buffer[..])?;
+    decode(&buffer[..]).map(Some)
 }
@@ -1,9 +1,20 @@
 //! Shared utilities for client and daemon.
 
 use anyhow::Result;
-use std::path::PathBuf;
+use std::fmt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
 use std::time::{Duration, Instant};
 
+/// Directories searched for programs given without a path.
+///
+/// Fixed so the daemon never depends on the PATH it was started with.
+pub const SAFE_PATH: &str = "/usr/bin:/usr/sbin:/usr/local/bin";
+
+/// Socket of a daemon started by systemd socket activation.
+pub const SYSTEMD_SOCKET_PATH: &str = "/run/xero-authd.sock";
+
 /// Get the socket path for the daemon.
 ///
 /// # Arguments
@@ -32,44 +43,574 @@ pub fn is_process_running(pid: u32) -> bool {
     }
 }
 
-/// Check if the daemon is running by checking if the socket exists.
-pub fn is_daemon_running() -> bool {
-    get_socket_path(None)
-        .map(|path| path.exists())
-        .unwrap_or(false)
+/// How long [`is_daemon_running`] waits for the daemon to answer.
+const PING_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// The systemd socket, if it exists and the current user may connect to it.
+fn systemd_socket() -> Option<&'static Path> {
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::FileTypeExt;
+
+    let path = Path::new(SYSTEMD_SOCKET_PATH);
+    if !std::fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        return None;
+    }
+    // Connecting needs write permission on the socket
+    let path_cstr = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    (unsafe { libc::access(path_cstr.as_ptr(), libc::W_OK) } == 0).then_some(path)
 }
 
-/// Wait for the daemon socket to become available.
+/// Whether a daemon started by systemd answers for the current user.
 ///
-/// Polls the socket path at regular intervals until it appears or the timeout is reached.
-/// This is used when starting the daemon to wait for it to be ready to accept connections.
+/// Connecting starts the daemon if it is not running yet.
+pub fn is_systemd_socket_active() -> bool {
+    systemd_socket().is_some_and(|path| ping_socket(path, PING_TIMEOUT))
+}
+
+/// Socket to connect to: the systemd socket if a daemon answers there,
+/// else the socket in the current user's runtime directory.
+pub async fn client_socket_path() -> Result<PathBuf> {
+    if let Some(path) = systemd_socket() {
+        if ping_socket_async(path, PING_TIMEOUT).await {
+            return Ok(path.to_path_buf());
+        }
+    }
+    get_socket_path(None)
+}
+
+/// Check if the daemon is running by pinging it on its socket, the systemd
+/// socket first.
 ///
-/// # Arguments
+/// A socket file left behind by a daemon that died refuses connections, so
+/// it counts as not running.
+pub fn is_daemon_running() -> bool {
+    is_systemd_socket_active()
+        || get_socket_path(None)
+            .map(|path| ping_socket(&path, PING_TIMEOUT))
+            .unwrap_or(false)
+}
+
+/// Whether a daemon answers a ping on `socket_path` within `timeout`.
 ///
-/// * `timeout` - Maximum time to wait for the socket to appear
-/// * `poll_interval` - How often to check for the socket (default: 50ms)
+/// Blocking, so callers do not need an async runtime.
+pub fn ping_socket(socket_path: &Path, timeout: Duration) -> bool {
+    use crate::protocol::{ClientMessage, DaemonMessage};
+    use crate::protocol_io::{read_message_blocking, write_message_blocking};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = match UnixStream::connect(socket_path) {
+        Ok(stream) => stream,
+        Err(_) => return false,
+    };
+    if stream.set_read_timeout(Some(timeout)).is_err()
+        || stream.set_write_timeout(Some(timeout)).is_err()
+    {
+        return false;
+    }
+
+    write_message_blocking(&mut stream, &ClientMessage::Ping).is_ok()
+        && matches!(
+            read_message_blocking::<_, DaemonMessage>(&mut stream),
+            Ok(Some(DaemonMessage::Pong))
+        )
+}
+
+/// Whether a daemon answers a ping on `socket_path` within `timeout`,
+/// without blocking the async runtime.
+pub async fn ping_socket_async(socket_path: &Path, timeout: Duration) -> bool {
+    use crate::protocol::{ClientMessage, DaemonMessage};
+    use crate::protocol_io::{read_message, write_message};
+    use tokio::net::UnixStream;
+
+    let ping = async {
+        let mut stream = UnixStream::connect(socket_path).await.ok()?;
+        write_message(&mut stream, &ClientMessage::Ping)
+            .await
+            .ok()?;
+        read_message::<_, DaemonMessage>(&mut stream).await.ok()?
+    };
+    matches!(
+        tokio::time::timeout(timeout, ping).await,
+        Ok(Some(DaemonMessage::Pong))
+    )
+}
+
+/// Async version of [`is_daemon_running`].
+pub async fn is_daemon_running_async() -> bool {
+    if let Some(path) = systemd_socket() {
+        if ping_socket_async(path, PING_TIMEOUT).await {
+            return true;
+        }
+    }
+    match get_socket_path(None) {
+        Ok(path) => ping_socket_async(&path, PING_TIMEOUT).await,
+        Err(_) => false,
+    }
+}
+
+/// How often [`wait_for_socket`] looks at the socket.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Reason waiting for the daemon socket failed.
+#[derive(Debug)]
+pub enum WaitError {
+    /// Nothing listened on the socket before the deadline.
+    Timeout(PathBuf),
+    /// The socket or the directory holding it cannot be accessed.
+    PermissionDenied(PathBuf),
+    /// The daemon, or pkexec starting it, exited before listening.
+    DaemonExited(ExitStatus),
+    /// Looking at the socket failed for another reason.
+    Io(std::io::Error),
+}
+
+impl fmt::Display for WaitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout(path) => {
+                write!(f, "Nothing listened on {} in time", path.display())
+            }
+            Self::PermissionDenied(path) => {
+                write!(f, "Permission denied accessing {}", path.display())
+            }
+            Self::DaemonExited(status) => {
+                write!(f, "Daemon exited before it was ready ({})", status)
+            }
+            Self::Io(e) => write!(f, "Failed to check the daemon socket: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for WaitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// What one look at the socket found.
+#[derive(Debug, PartialEq, Eq)]
+enum SocketState {
+    /// A daemon accepts connections.
+    Ready,
+    /// No socket yet, or one nobody listens on.
+    NotReady,
+    /// The socket exists, but its permissions do not let us connect yet.
+    Denied,
+}
+
+/// Look at `socket_path` once.
 ///
-/// # Returns
+/// A directory that cannot be searched fails right away, while a socket
+/// refusing us may still get its permissions set by the daemon.
+fn probe_socket(socket_path: &Path) -> Result<SocketState, WaitError> {
+    use std::io::ErrorKind;
+    use std::os::unix::net::UnixStream;
+
+    match std::fs::symlink_metadata(socket_path) {
+        Ok(_) => {}
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(SocketState::NotReady),
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+            let dir = socket_path.parent().unwrap_or(socket_path);
+            return Err(WaitError::PermissionDenied(dir.to_path_buf()));
+        }
+        Err(e) => return Err(WaitError::Io(e)),
+    }
+
+    match UnixStream::connect(socket_path) {
+        Ok(_) => Ok(SocketState::Ready),
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => Ok(SocketState::Denied),
+        // Not listening yet, or left behind by a daemon that died
+        Err(_) => Ok(SocketState::NotReady),
+    }
+}
+
+/// The error for a wait that reached its deadline after last finding `state`.
+fn deadline_error(socket_path: &Path, state: SocketState) -> WaitError {
+    match state {
+        SocketState::Denied => WaitError::PermissionDenied(socket_path.to_path_buf()),
+        _ => WaitError::Timeout(socket_path.to_path_buf()),
+    }
+}
+
+/// Wait until the daemon accepts connections on its socket.
 ///
-/// * `Ok(())` if the socket appeared within the timeout
-/// * `Err` if the timeout was reached or an error occurred
-pub fn wait_for_socket(timeout: Duration, poll_interval: Duration) -> Result<()> {
-    let socket_path = get_socket_path(None)?;
-    let start = Instant::now();
+/// Gives up at `deadline`, or as soon as `child`, the process starting the
+/// daemon, exits. Blocks the calling thread; see [`wait_for_socket_async`].
+pub fn wait_for_socket(
+    deadline: Instant,
+    child: Option<&mut std::process::Child>,
+) -> Result<(), WaitError> {
+    let socket_path = get_socket_path(None).map_err(|e| WaitError::Io(std::io::Error::other(e)))?;
+    wait_for_socket_at(&socket_path, deadline, child)
+}
 
+/// Wait for a socket at an explicit path, see [`wait_for_socket`].
+pub fn wait_for_socket_at(
+    socket_path: &Path,
+    deadline: Instant,
+    mut child: Option<&mut std::process::Child>,
+) -> Result<(), WaitError> {
     loop {
-        if socket_path.exists() {
+        let state = probe_socket(socket_path)?;
+        if state == SocketState::Ready {
             return Ok(());
         }
 
-        if start.elapsed() >= timeout {
-            anyhow::bail!(
-                "Socket did not appear within {:?} at {:?}",
-                timeout,
-                socket_path
+        // Includes a child that already exited but was not reaped yet
+        if let Some(child) = child.as_mut() {
+            if let Ok(Some(status)) = child.try_wait() {
+                return Err(WaitError::DaemonExited(status));
+            }
+        }
+
+        if Instant::now() >= deadline {
+            return Err(deadline_error(socket_path, state));
+        }
+        std::thread::sleep(WAIT_POLL_INTERVAL);
+    }
+}
+
+/// Async version of [`wait_for_socket`], sleeping on the tokio timer
+/// between looks.
+pub async fn wait_for_socket_async(
+    deadline: Instant,
+    child: Option<&mut tokio::process::Child>,
+) -> Result<(), WaitError> {
+    let socket_path = get_socket_path(None).map_err(|e| WaitError::Io(std::io::Error::other(e)))?;
+    wait_for_socket_at_async(&socket_path, deadline, child).await
+}
+
+/// Async version of [`wait_for_socket_at`].
+pub async fn wait_for_socket_at_async(
+    socket_path: &Path,
+    deadline: Instant,
+    mut child: Option<&mut tokio::process::Child>,
+) -> Result<(), WaitError> {
+    loop {
+        let state = probe_socket(socket_path)?;
+        if state == SocketState::Ready {
+            return Ok(());
+        }
+
+        if let Some(child) = child.as_mut() {
+            if let Ok(Some(status)) = child.try_wait() {
+                return Err(WaitError::DaemonExited(status));
+            }
+        }
+
+        if Instant::now() >= deadline {
+            return Err(deadline_error(socket_path, state));
+        }
+        tokio::time::sleep(WAIT_POLL_INTERVAL).await;
+    }
+}
+
+/// Reason a program was refused by [`resolve_trusted_program`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgramError {
+    /// A path that is neither absolute nor a bare program name.
+    RelativePath(String),
+    /// Not found at the given path or in [`SAFE_PATH`].
+    NotFound(String),
+    /// The resolved file is not a regular file.
+    NotAFile(PathBuf),
+    /// The resolved file is owned by someone else than the expected user.
+    WrongOwner { path: PathBuf, uid: u32 },
+    /// The resolved file is writable by its group or by everyone.
+    Writable { path: PathBuf, mode: u32 },
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RelativePath(program) => {
+                write!(f, "Refusing relative program path '{}'", program)
+            }
+            Self::NotFound(program) => {
+                write!(f, "Program '{}' not found in {}", program, SAFE_PATH)
+            }
+            Self::NotAFile(path) => write!(f, "{} is not a regular file", path.display()),
+            Self::WrongOwner { path, uid } => write!(
+                f,
+                "Refusing {}: owned by uid {} instead of root",
+                path.display(),
+                uid
+            ),
+            Self::Writable { path, mode } => write!(
+                f,
+                "Refusing {}: writable by group or others (mode {:o})",
+                path.display(),
+                mode & 0o7777
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProgramError {}
+
+/// Resolve `program` to an absolute, canonical path.
+///
+/// Absolute paths are taken as they are, bare names are looked up in the
+/// colon-separated `search_path`, anything else is refused.
+pub fn resolve_program(program: &str, search_path: &str) -> Result<PathBuf, ProgramError> {
+    let path = Path::new(program);
+    let candidate = if path.is_absolute() {
+        path.to_path_buf()
+    } else if program.is_empty() || program.contains('/') {
+        return Err(ProgramError::RelativePath(program.to_string()));
+    } else {
+        search_path
+            .split(':')
+            .filter(|dir| Path::new(dir).is_absolute())
+            .map(|dir| Path::new(dir).join(program))
+            .find(|candidate| candidate.is_file())
+            .ok_or_else(|| ProgramError::NotFound(program.to_string()))?
+    };
+
+    candidate
+        .canonicalize()
+        .map_err(|_| ProgramError::NotFound(program.to_string()))
+}
+
+/// Check that `path` is a regular file owned by `owner_uid` that neither its
+/// group nor others can write to.
+pub fn check_program_file(path: &Path, owner_uid: u32) -> Result<(), ProgramError> {
+    let metadata =
+        std::fs::metadata(path).map_err(|_| ProgramError::NotFound(path.display().to_string()))?;
+    if !metadata.is_file() {
+        return Err(ProgramError::NotAFile(path.to_path_buf()));
+    }
+    if metadata.uid() != owner_uid {
+        return Err(ProgramError::WrongOwner {
+            path: path.to_path_buf(),
+            uid: metadata.uid(),
+        });
+    }
+    if metadata.mode() & 0o022 != 0 {
+        return Err(ProgramError::Writable {
+            path: path.to_path_buf(),
+            mode: metadata.mode(),
+        });
+    }
+    Ok(())
+}
+
+/// Resolve a program sent by a client against [`SAFE_PATH`] and check that
+/// only root can have changed it.
+pub fn resolve_trusted_program(program: &str) -> Result<PathBuf, ProgramError> {
+    let path = resolve_program(program, SAFE_PATH)?;
+    check_program_file(&path, 0)?;
+    Ok(path)
+}
+
+/// Shell whose `exec -a` sets the `argv[0]` of a wrapped program.
+const ARGV0_SHELL: &str = "bash";
+
+/// Command line that executes `path` with `name` as its `argv[0]`.
+///
+/// Wrappers like systemd-inhibit execute the resolved path they are given,
+/// which makes it `argv[0]`. Multi-call binaries, like busybox or bash started
+/// as `sh`, behave by that name, so a shell puts the requested one back.
+pub fn exec_as_command(
+    name: &str,
+    path: &Path,
+    args: &[String],
+) -> Result<Vec<String>, ProgramError> {
+    let shell = resolve_trusted_program(ARGV0_SHELL)?;
+    Ok(argv0_command(&shell, name, path, args))
+}
+
+fn argv0_command(shell: &Path, name: &str, path: &Path, args: &[String]) -> Vec<String> {
+    let mut command = vec![
+        shell.to_string_lossy().to_string(),
+        "-c".to_string(),
+        "exec -a \"$0\" -- \"$@\"".to_string(),
+        name.to_string(),
+        path.to_string_lossy().to_string(),
+    ];
+    command.extend_from_slice(args);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// A scratch directory, removed again on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "xero-auth-shared-{}-{}",
+                name,
+                std::process::id()
+            ));
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn file(&self, name: &str, mode: u32) -> PathBuf {
+            let path = self.0.join(name);
+            std::fs::write(&path, "#!/bin/sh\n").unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn current_uid() -> u32 {
+        unsafe { libc::getuid() }
+    }
+
+    #[test]
+    fn test_wait_for_socket_created_mid_wait() {
+        let dir = TempDir::new("wait");
+        let socket_path = dir.0.join("daemon.sock");
+
+        let path = socket_path.clone();
+        let listener = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            std::os::unix::net::UnixListener::bind(path).unwrap()
+        });
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        wait_for_socket_at(&socket_path, deadline, None).unwrap();
+        drop(listener.join().unwrap());
+
+        // Left behind, nobody listens any more
+        let deadline = Instant::now() + Duration::from_millis(200);
+        assert!(matches!(
+            wait_for_socket_at(&socket_path, deadline, None),
+            Err(WaitError::Timeout(path)) if path == socket_path
+        ));
+    }
+
+    #[test]
+    fn test_wait_for_socket_child_exited() {
+        let dir = TempDir::new("wait-exit");
+        let mut child = std::process::Command::new("false").spawn().unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let result = wait_for_socket_at(&dir.0.join("daemon.sock"), deadline, Some(&mut child));
+        assert!(matches!(result, Err(WaitError::DaemonExited(status)) if !status.success()));
+    }
+
+    #[test]
+    fn test_wait_for_socket_permission_denied() {
+        // Root is never denied, so there is nothing to check
+        if unsafe { libc::geteuid() } == 0 {
+            return;
+        }
+
+        let dir = TempDir::new("wait-denied");
+        let locked = dir.0.join("locked");
+        std::fs::create_dir(&locked).unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let result = wait_for_socket_at(&locked.join("daemon.sock"), deadline, None);
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o700)).unwrap();
+        assert!(matches!(result, Err(WaitError::PermissionDenied(path)) if path == locked));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_socket_async() {
+        let dir = TempDir::new("wait-async");
+        let socket_path = dir.0.join("daemon.sock");
+
+        let path = socket_path.clone();
+        let listener = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            tokio::net::UnixListener::bind(path).unwrap()
+        });
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        wait_for_socket_at_async(&socket_path, deadline, None)
+            .await
+            .unwrap();
+        drop(listener.await.unwrap());
+    }
+
+    #[test]
+    fn test_resolve_program() {
+        let dir = TempDir::new("resolve");
+        let tool = dir.file("tool", 0o755);
+        let search_path = format!("relative/bin:/nonexistent:{}", dir.0.display());
+
+        let expected = tool.canonicalize().unwrap();
+        assert_eq!(resolve_program("tool", &search_path), Ok(expected.clone()));
+        assert_eq!(
+            resolve_program(tool.to_str().unwrap(), "/nonexistent"),
+            Ok(expected)
+        );
+
+        assert_eq!(
+            resolve_program("missing", &search_path),
+            Err(ProgramError::NotFound("missing".to_string()))
+        );
+        for relative in ["./tool", "bin/tool", ""] {
+            assert_eq!(
+                resolve_program(relative, &search_path),
+                Err(ProgramError::RelativePath(relative.to_string()))
             );
         }
+        // Relative entries in the search path are ignored
+        assert!(resolve_program("tool", "relative/bin").is_err());
+    }
+
+    #[test]
+    fn test_check_program_file() {
+        let dir = TempDir::new("check");
+        let uid = current_uid();
+
+        let safe = dir.file("safe", 0o755);
+        assert_eq!(check_program_file(&safe, uid), Ok(()));
+
+        for mode in [0o775, 0o757] {
+            let writable = dir.file(&format!("writable-{:o}", mode), mode);
+            assert!(matches!(
+                check_program_file(&writable, uid),
+                Err(ProgramError::Writable { .. })
+            ));
+        }
+
+        assert_eq!(
+            check_program_file(&safe, uid + 1),
+            Err(ProgramError::WrongOwner {
+                path: safe.clone(),
+                uid,
+            })
+        );
+        assert_eq!(
+            check_program_file(&dir.0, uid),
+            Err(ProgramError::NotAFile(dir.0.clone()))
+        );
+    }
+
+    #[test]
+    fn test_argv0_survives_wrapping() {
+        let shell = resolve_program(ARGV0_SHELL, SAFE_PATH).unwrap();
+        let cat = resolve_program("cat", SAFE_PATH).unwrap();
+        let command = argv0_command(&shell, "busybox", &cat, &["/proc/self/cmdline".to_string()]);
+        assert_eq!(command[3], "busybox");
 
-        std::thread::sleep(poll_interval);
+        // Like systemd-inhibit, env executes the path it is given
+        let output = std::process::Command::new("env")
+            .arg("--")
+            .args(&command)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"busybox\0/proc/self/cmdline\0");
     }
 }
@@ -0,0 +1,62 @@
+//! Counters of the commands the daemon ran, shared by all connections.
+
+use crate::protocol::{LastCommand, SessionStats};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Counters of a daemon; clones share them.
+#[derive(Debug, Clone, Default)]
+pub struct Stats(Arc<Mutex<SessionStats>>);
+
+impl Stats {
+    fn lock(&self) -> MutexGuard<'_, SessionStats> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Count a command that finished with `exit_code` after using
+    /// `cpu_time`.
+    pub fn record(&self, program: &str, exit_code: i32, cpu_time: Duration) {
+        let mut stats = self.lock();
+        stats.commands += 1;
+        let micros = u64::try_from(cpu_time.as_micros()).unwrap_or(u64::MAX);
+        stats.cpu_time_micros = stats.cpu_time_micros.saturating_add(micros);
+        stats.last_command = Some(LastCommand {
+            program: program.to_string(),
+            exit_code,
+        });
+    }
+
+    /// The counters as they are now.
+    pub fn snapshot(&self) -> SessionStats {
+        self.lock().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let stats = Stats::default();
+        assert_eq!(stats.snapshot(), SessionStats::default());
+
+        let shared = stats.clone();
+        shared.record("pacman", 0, Duration::from_millis(1500));
+        stats.record("sh", 2, Duration::from_micros(250));
+        assert_eq!(
+            shared.snapshot(),
+            SessionStats {
+                commands: 2,
+                cpu_time_micros: 1_500_250,
+                last_command: Some(LastCommand {
+                    program: "sh".to_string(),
+                    exit_code: 2,
+                }),
+            }
+        );
+
+        stats.record("yes", 0, Duration::MAX);
+        assert_eq!(stats.snapshot().cpu_time_micros, u64::MAX);
+    }
+}
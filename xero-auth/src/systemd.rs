@@ -0,0 +1,122 @@
+//! systemd socket activation.
+//!
+//! Instead of the GUI starting the daemon through pkexec, a system can run
+//! it as a socket-activated service. systemd then creates the socket at
+//! [`SYSTEMD_SOCKET_PATH`], sets its owner and mode from the socket unit and
+//! hands it to the daemon as file descriptor 3, following the
+//! `sd_listen_fds` protocol.
+
+use crate::shared::SYSTEMD_SOCKET_PATH;
+use anyhow::{Context, Result};
+use log::warn;
+use std::os::unix::io::{FromRawFd, RawFd};
+use std::os::unix::net::UnixListener;
+use std::path::Path;
+
+/// First file descriptor systemd passes.
+const LISTEN_FDS_START: RawFd = 3;
+
+/// Number of sockets passed to process `pid`, from the values of
+/// `LISTEN_PID` and `LISTEN_FDS`.
+pub fn listen_fds(listen_pid: Option<&str>, listen_fds: Option<&str>, pid: u32) -> usize {
+    // Variables meant for another process and inherited by accident
+    if listen_pid.and_then(|listen_pid| listen_pid.trim().parse::<u32>().ok()) != Some(pid) {
+        return 0;
+    }
+    listen_fds
+        .and_then(|count| count.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Take the listening socket systemd passed, if it passed one.
+///
+/// Clears the `LISTEN_*` variables, so commands run by the daemon do not
+/// see them. Changing the environment is only sound while no other thread
+/// runs, so this is called before the async runtime starts.
+pub fn take_listener() -> Result<Option<UnixListener>> {
+    let count = listen_fds(
+        std::env::var("LISTEN_PID").ok().as_deref(),
+        std::env::var("LISTEN_FDS").ok().as_deref(),
+        std::process::id(),
+    );
+    for name in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+        std::env::remove_var(name);
+    }
+
+    if count == 0 {
+        return Ok(None);
+    }
+    if count > 1 {
+        warn!("systemd passed {} sockets, using the first", count);
+    }
+
+    // systemd leaves the descriptor inheritable
+    if unsafe { libc::fcntl(LISTEN_FDS_START, libc::F_SETFD, libc::FD_CLOEXEC) } != 0 {
+        return Err(std::io::Error::last_os_error())
+            .context("Failed to use the socket passed by systemd");
+    }
+    let listener = unsafe { UnixListener::from_raw_fd(LISTEN_FDS_START) };
+    listener
+        .local_addr()
+        .context("The socket passed by systemd is not a Unix socket")?;
+    listener
+        .set_nonblocking(true)
+        .context("Failed to use the socket passed by systemd")?;
+    Ok(Some(listener))
+}
+
+/// Example socket and service units running the daemon at `daemon_path`.
+pub fn units(daemon_path: &Path) -> String {
+    format!(
+        "\
+# /etc/systemd/system/xero-authd.socket
+#
+# Members of SocketGroup run commands as root without being asked for a
+# password. Only enable this for users who are trusted with that.
+[Unit]
+Description=Xero authentication daemon socket
+
+[Socket]
+ListenStream={socket}
+SocketUser=root
+SocketGroup=wheel
+SocketMode=0660
+
+[Install]
+WantedBy=sockets.target
+
+# /etc/systemd/system/xero-authd.service
+[Unit]
+Description=Xero authentication daemon
+Requires=xero-authd.socket
+
+[Service]
+ExecStart={daemon} --systemd
+",
+        socket = SYSTEMD_SOCKET_PATH,
+        daemon = daemon_path.display(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listen_fds() {
+        assert_eq!(listen_fds(Some("42"), Some("1"), 42), 1);
+        assert_eq!(listen_fds(Some("42"), Some("2"), 42), 2);
+        // Meant for another process
+        assert_eq!(listen_fds(Some("41"), Some("1"), 42), 0);
+        assert_eq!(listen_fds(None, Some("1"), 42), 0);
+        assert_eq!(listen_fds(Some("42"), None, 42), 0);
+        assert_eq!(listen_fds(Some("42"), Some("many"), 42), 0);
+    }
+
+    #[test]
+    fn test_units() {
+        let units = units(Path::new("/usr/bin/xero-authd"));
+        assert!(units.contains("ListenStream=/run/xero-authd.sock\n"));
+        assert!(units.contains("ExecStart=/usr/bin/xero-authd --systemd\n"));
+    }
+}
@@ -0,0 +1,91 @@
+//! In-process daemon for protocol tests.
+
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use xero_auth::{ping_socket, serve, DaemonConfig, Policy};
+
+/// File name of the socket inside a test directory.
+pub const SOCKET_NAME: &str = "xero-authd.sock";
+
+/// A fresh directory for a daemon socket.
+pub fn socket_dir() -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "xero-auth-test-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::SeqCst)
+    ));
+    std::fs::create_dir_all(&dir).expect("create socket directory");
+    dir
+}
+
+/// Daemon settings for a socket at `socket_path`.
+pub fn config(socket_path: &Path, parent_pid: Option<u32>) -> DaemonConfig {
+    DaemonConfig {
+        socket_path: socket_path.to_path_buf(),
+        effective_uid: None,
+        parent_pid,
+        listener: None,
+        policy: Policy::default(),
+    }
+}
+
+/// A daemon serving on a private socket, removed again on drop.
+pub struct TestDaemon {
+    pub socket_path: PathBuf,
+    handle: JoinHandle<anyhow::Result<()>>,
+    dir: PathBuf,
+}
+
+impl TestDaemon {
+    /// Start a daemon, optionally monitoring `parent_pid`.
+    pub fn spawn(parent_pid: Option<u32>) -> Self {
+        Self::spawn_in(socket_dir(), parent_pid)
+    }
+
+    /// Start a daemon with its socket in an existing directory.
+    pub fn spawn_in(dir: PathBuf, parent_pid: Option<u32>) -> Self {
+        let socket_path = dir.join(SOCKET_NAME);
+        Self::spawn_with(dir, config(&socket_path, parent_pid))
+    }
+
+    /// Start a daemon with `config`, its socket inside `dir`.
+    pub fn spawn_with(dir: PathBuf, config: DaemonConfig) -> Self {
+        let socket_path = config.socket_path.clone();
+        let handle = tokio::spawn(serve(config));
+        // Waits for an answer, as a stale socket file may exist beforehand
+        let start = Instant::now();
+        while !ping_socket(&socket_path, Duration::from_millis(100)) {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "daemon did not answer on {:?}",
+                socket_path
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        Self {
+            socket_path,
+            handle,
+            dir,
+        }
+    }
+
+    /// Wait for the daemon to stop on its own.
+    pub async fn stopped(&mut self, timeout: Duration) -> anyhow::Result<()> {
+        tokio::time::timeout(timeout, &mut self.handle)
+            .await
+            .context("Daemon did not stop")?
+            .context("Daemon task panicked")?
+    }
+}
+
+impl Drop for TestDaemon {
+    fn drop(&mut self) {
+        self.handle.abort();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
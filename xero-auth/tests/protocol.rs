@@ -0,0 +1,418 @@
+//! Client/daemon protocol tests against an in-process daemon.
+
+mod common;
+
+use common::{socket_dir, TestDaemon, SOCKET_NAME};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::UnixStream;
+use xero_auth::protocol::{ClientMessage, DaemonMessage, LastCommand, SessionStats};
+use xero_auth::protocol_io::{read_message, write_message, MAX_MESSAGE_LEN};
+use xero_auth::{ping_socket, serve, Client, Policy, ResourceLimits};
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_output_arrives_in_order() {
+    let daemon = TestDaemon::spawn(None);
+    let mut client = Client::connect(&daemon.socket_path).await.unwrap();
+
+    let output = client
+        .collect_output("sh", &args(&["-c", "echo one; echo two; echo three"]))
+        .await
+        .unwrap();
+
+    assert_eq!(output.exit_code, 0);
+    assert_eq!(output.lines, args(&["one\n", "two\n", "three\n"]));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_exit_codes_are_forwarded() {
+    let daemon = TestDaemon::spawn(None);
+    let mut client = Client::connect(&daemon.socket_path).await.unwrap();
+
+    let output = client
+        .collect_output("sh", &args(&["-c", "exit 3"]))
+        .await
+        .unwrap();
+    assert_eq!(output.exit_code, 3);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_untrusted_programs_are_refused() {
+    let daemon = TestDaemon::spawn(None);
+    let mut client = Client::connect(&daemon.socket_path).await.unwrap();
+
+    let error = client
+        .collect_output("/nonexistent/program", &[])
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("not found"), "{error:#}");
+
+    let error = client.collect_output("./program", &[]).await.unwrap_err();
+    assert!(error.to_string().contains("relative"), "{error:#}");
+
+    // The connection stays usable after a refusal
+    let output = client.collect_output("true", &[]).await.unwrap();
+    assert_eq!(output.exit_code, 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_niceness_is_applied() {
+    let daemon = TestDaemon::spawn(None);
+    let mut client = Client::connect(&daemon.socket_path).await.unwrap();
+
+    client.set_limits(ResourceLimits {
+        nice: Some(15),
+        ..ResourceLimits::default()
+    });
+    let output = client.collect_output("nice", &[]).await.unwrap();
+    assert_eq!(output.lines.concat().trim(), "15");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_malformed_messages_drop_only_that_client() {
+    let daemon = TestDaemon::spawn(None);
+
+    let mut stream = UnixStream::connect(&daemon.socket_path).await.unwrap();
+    let garbage = [0xffu8; 24];
+    stream
+        .write_all(&(garbage.len() as u64).to_le_bytes())
+        .await
+        .unwrap();
+    stream.write_all(&garbage).await.unwrap();
+    let reply = read_message::<_, DaemonMessage>(&mut stream).await.unwrap();
+    assert!(reply.is_none(), "daemon answered garbage with {:?}", reply);
+
+    let mut stream = UnixStream::connect(&daemon.socket_path).await.unwrap();
+    stream
+        .write_all(&(MAX_MESSAGE_LEN as u64 + 1).to_le_bytes())
+        .await
+        .unwrap();
+    let reply = read_message::<_, DaemonMessage>(&mut stream).await.unwrap();
+    assert!(reply.is_none(), "daemon answered oversized message");
+
+    let mut client = Client::connect(&daemon.socket_path).await.unwrap();
+    client.ping().await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_shutdown_is_acknowledged() {
+    let mut daemon = TestDaemon::spawn(None);
+    let mut client = Client::connect(&daemon.socket_path).await.unwrap();
+
+    client.ping().await.unwrap();
+    client.shutdown().await.unwrap();
+
+    daemon.stopped(Duration::from_secs(5)).await.unwrap();
+    assert!(!daemon.socket_path.exists());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_passed_socket_is_used() {
+    let dir = socket_dir();
+    let socket_path = dir.join(SOCKET_NAME);
+
+    // As systemd creates it before starting the daemon
+    let listener = std::os::unix::net::UnixListener::bind(&socket_path).unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let mut config = common::config(&socket_path, None);
+    config.listener = Some(listener);
+    let daemon = tokio::spawn(serve(config));
+
+    let mut client = Client::connect(&socket_path).await.unwrap();
+    client.ping().await.unwrap();
+    client.shutdown().await.unwrap();
+    tokio::time::timeout(Duration::from_secs(5), daemon)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+
+    // The socket belongs to systemd and stays, and no lock file is made
+    assert!(socket_path.exists());
+    assert!(!socket_path.with_extension("lock").exists());
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+fn spawn_parent() -> std::process::Child {
+    std::process::Command::new("sleep")
+        .arg("30")
+        .spawn()
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_daemon_stops_with_its_parent() {
+    let mut parent = spawn_parent();
+    let mut daemon = TestDaemon::spawn(Some(parent.id()));
+
+    parent.kill().unwrap();
+    parent.wait().unwrap();
+
+    // The monitor polls every two seconds
+    daemon.stopped(Duration::from_secs(5)).await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_commands_are_refused_without_parent() {
+    let mut parent = spawn_parent();
+    let mut daemon = TestDaemon::spawn(Some(parent.id()));
+
+    let mut client = Client::connect(&daemon.socket_path).await.unwrap();
+    client.ping().await.unwrap();
+
+    parent.kill().unwrap();
+    parent.wait().unwrap();
+
+    let error = client.collect_output("true", &[]).await.unwrap_err();
+    assert!(error.to_string().contains("Parent process"), "{error:#}");
+
+    // Refusing a command also shuts the daemon down
+    daemon.stopped(Duration::from_secs(1)).await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_stale_socket_is_replaced() {
+    let dir = socket_dir();
+    let socket_path = dir.join(SOCKET_NAME);
+
+    // A socket file nobody listens on, as left by a daemon that died
+    drop(std::os::unix::net::UnixListener::bind(&socket_path).unwrap());
+    assert!(socket_path.exists());
+    assert!(!ping_socket(&socket_path, Duration::from_millis(200)));
+
+    let daemon = TestDaemon::spawn_in(dir, None);
+    assert!(ping_socket(&daemon.socket_path, Duration::from_secs(1)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_only_one_daemon_per_socket() {
+    let daemon = TestDaemon::spawn(None);
+
+    let error = serve(common::config(&daemon.socket_path, None))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("already running"), "{error:#}");
+
+    // The first daemon keeps serving
+    let mut client = Client::connect(&daemon.socket_path).await.unwrap();
+    client.ping().await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_cancel_terminates_running_commands() {
+    let daemon = TestDaemon::spawn(None);
+    let mut runner = Client::connect(&daemon.socket_path).await.unwrap();
+
+    let socket_path = daemon.socket_path.clone();
+    let cancel = tokio::spawn(async move {
+        // Give the command time to start
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let mut client = Client::connect(&socket_path).await.unwrap();
+        client.cancel().await.unwrap()
+    });
+
+    let output = tokio::time::timeout(
+        Duration::from_secs(5),
+        runner.collect_output("sh", &args(&["-c", "sleep 30; echo done"])),
+    )
+    .await
+    .expect("command was not cancelled")
+    .unwrap();
+
+    assert_eq!(cancel.await.unwrap(), 1);
+    // Killed by SIGTERM
+    assert_eq!(output.exit_code, 128 + 15);
+    assert!(output.lines.is_empty());
+
+    // Nothing left to cancel
+    let mut client = Client::connect(&daemon.socket_path).await.unwrap();
+    assert_eq!(client.cancel().await.unwrap(), 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_status_counts_commands_across_clients() {
+    let daemon = TestDaemon::spawn(None);
+    let mut client = Client::connect(&daemon.socket_path).await.unwrap();
+    assert_eq!(client.status().await.unwrap(), SessionStats::default());
+
+    let mut cpu_time = 0;
+    for (count, code) in [(1, 0), (2, 4), (3, 1)] {
+        let mut runner = Client::connect(&daemon.socket_path).await.unwrap();
+        let script = format!("i=0; while [ $i -lt 2000 ]; do i=$((i+1)); done; exit {code}");
+        let output = runner
+            .collect_output("sh", &args(&["-c", &script]))
+            .await
+            .unwrap();
+        assert_eq!(output.exit_code, code);
+
+        let stats = client.status().await.unwrap();
+        assert_eq!(stats.commands, count);
+        assert!(stats.cpu_time_micros >= cpu_time);
+        cpu_time = stats.cpu_time_micros;
+        assert_eq!(
+            stats.last_command,
+            Some(LastCommand {
+                program: "sh".to_string(),
+                exit_code: code,
+            })
+        );
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_files_are_written_and_read() {
+    let daemon = TestDaemon::spawn(None);
+    let mut client = Client::connect(&daemon.socket_path).await.unwrap();
+
+    let dir = socket_dir().canonicalize().unwrap();
+    let path = dir.join("app.conf");
+    let path = path.to_str().unwrap();
+
+    assert_eq!(client.read_file(path).await.unwrap(), None);
+    let contents = b"quoting 'survives' \"as-is\"\n$HOME\n";
+    assert_eq!(
+        client.write_file(path, contents, None, true).await.unwrap(),
+        None
+    );
+    assert_eq!(
+        client.read_file(path).await.unwrap().as_deref(),
+        Some(&contents[..])
+    );
+
+    let backup = client
+        .write_file(path, b"second\n", None, true)
+        .await
+        .unwrap()
+        .expect("backup of the replaced file");
+    assert_eq!(std::fs::read(&backup).unwrap(), contents);
+    assert_eq!(std::fs::read(path).unwrap(), b"second\n");
+
+    // Refused paths leave the connection usable
+    let error = client.read_file("/etc/shadow").await.unwrap_err();
+    assert!(error.to_string().contains("blocked"), "{error:#}");
+    let error = client
+        .write_file("relative.conf", b"", None, false)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("absolute"), "{error:#}");
+    client.ping().await.unwrap();
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// A daemon asking to confirm removals in its `protected` directory, which
+/// holds a `victim` directory.
+fn confirming_daemon(confirm_timeout: Duration) -> (TestDaemon, PathBuf) {
+    let dir = socket_dir();
+    let protected = dir.join("protected");
+    std::fs::create_dir_all(protected.join("victim")).unwrap();
+    let mut config = common::config(&dir.join(SOCKET_NAME), None);
+    config.policy = Policy {
+        protected_dirs: vec![protected.clone()],
+        confirm_timeout,
+    };
+    (
+        TestDaemon::spawn_with(dir, config),
+        protected.join("victim"),
+    )
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_confirmed_command_runs() {
+    let (daemon, victim) = confirming_daemon(Duration::from_secs(5));
+    let mut client = Client::connect(&daemon.socket_path).await.unwrap();
+    let reasons = Arc::new(Mutex::new(Vec::new()));
+    let asked = reasons.clone();
+    client.set_confirm(move |reason| {
+        asked.lock().unwrap().push(reason.to_string());
+        true
+    });
+
+    let output = client
+        .collect_output("rm", &args(&["-r", victim.to_str().unwrap()]))
+        .await
+        .unwrap();
+    assert_eq!(output.exit_code, 0);
+    assert!(!victim.exists());
+    assert_eq!(
+        *reasons.lock().unwrap(),
+        [format!("Recursively removes {}", victim.display())]
+    );
+
+    // Nothing to confirm
+    let output = client.collect_output("true", &[]).await.unwrap();
+    assert_eq!(output.exit_code, 0);
+    assert_eq!(reasons.lock().unwrap().len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_denied_command_does_not_run() {
+    let (daemon, victim) = confirming_daemon(Duration::from_secs(5));
+    let mut client = Client::connect(&daemon.socket_path).await.unwrap();
+    client.set_confirm(|_| false);
+
+    let error = client
+        .collect_output("rm", &args(&["-r", victim.to_str().unwrap()]))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("Denied"), "{error:#}");
+    assert!(victim.exists());
+
+    // The connection stays usable
+    client.ping().await.unwrap();
+
+    // Denied without anyone to ask
+    let mut client = Client::connect(&daemon.socket_path).await.unwrap();
+    assert!(client
+        .collect_output("rm", &args(&["-r", victim.to_str().unwrap()]))
+        .await
+        .is_err());
+    assert!(victim.exists());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_unanswered_confirmation_denies() {
+    let (daemon, victim) = confirming_daemon(Duration::from_millis(300));
+    let mut stream = UnixStream::connect(&daemon.socket_path).await.unwrap();
+    let execute = ClientMessage::Execute {
+        program: "rm".to_string(),
+        args: args(&["-r", victim.to_str().unwrap()]),
+        env: Vec::new(),
+        working_dir: None,
+        nice: None,
+        ionice_class: None,
+        cpu_quota_percent: None,
+    };
+    write_message(&mut stream, &execute).await.unwrap();
+
+    let asked = read_message::<_, DaemonMessage>(&mut stream).await.unwrap();
+    assert!(
+        matches!(asked, Some(DaemonMessage::ConfirmationRequired { .. })),
+        "{asked:?}"
+    );
+
+    // Never answered
+    let reply = tokio::time::timeout(
+        Duration::from_secs(5),
+        read_message::<_, DaemonMessage>(&mut stream),
+    )
+    .await
+    .expect("confirmation did not time out")
+    .unwrap();
+    match reply {
+        Some(DaemonMessage::ErrorMessage(message)) => {
+            assert!(message.contains("in time"), "{message}")
+        }
+        reply => panic!("unexpected reply {reply:?}"),
+    }
+    // The connection is closed
+    let reply = read_message::<_, DaemonMessage>(&mut stream).await.unwrap();
+    assert!(reply.is_none(), "{reply:?}");
+    assert!(victim.exists());
+}